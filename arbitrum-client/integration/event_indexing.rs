@@ -56,7 +56,7 @@ async fn test_find_merkle_path(test_args: IntegrationTestArgs) -> Result<()> {
     let final_root =
         client.find_merkle_authentication_path(state.index2_commitment).await?.compute_root();
 
-    assert_eq_result!(final_root, expected_root)
+    assert_eq_result!(final_root.inner(), expected_root)
 }
 integration_test_async!(test_find_merkle_path);
 
@@ -20,7 +20,9 @@ use std::str::FromStr;
 use ::constants::Scalar;
 use arbitrum_client::{
     client::{ArbitrumClient, ArbitrumClientConfig},
-    constants::Chain,
+    constants::{
+        Chain, DEFAULT_EVENT_BLOCK_WINDOW, DEFAULT_GAS_LIMIT_CEILING, DEFAULT_RPC_TIMEOUT_MS,
+    },
 };
 use circuit_types::SizedWalletShare;
 use clap::Parser;
@@ -118,6 +120,10 @@ impl From<CliArgs> for IntegrationTestArgs {
             darkpool_addr,
             arb_priv_key,
             rpc_url: test_args.rpc_url,
+            fallback_rpc_urls: vec![],
+            rpc_timeout_ms: DEFAULT_RPC_TIMEOUT_MS,
+            event_block_window: DEFAULT_EVENT_BLOCK_WINDOW,
+            gas_limit_ceiling: DEFAULT_GAS_LIMIT_CEILING,
         }))
         .unwrap();
 
@@ -13,6 +13,9 @@ abigen!(
         function isNullifierSpent(uint256 memory nullifier) external view returns (bool)
         function getRoot() external view returns (uint256)
         function rootInHistory(uint256 memory root) external view returns (bool)
+        function rootHistoryLength() external view returns (uint256)
+        function merkleHeight() external view returns (uint8)
+        function getFeeRecipient() external view returns (address)
 
         function newWallet(bytes memory proof, bytes memory valid_wallet_create_statement_bytes) external
         function updateWallet(bytes memory proof, bytes memory valid_wallet_update_statement_bytes, bytes memory wallet_commitment_signature, bytes memory transfer_aux_data) external
@@ -37,6 +40,9 @@ abigen!(
         function isNullifierSpent(uint256 memory nullifier) external view returns (bool)
         function getRoot() external view returns (uint256)
         function rootInHistory(uint256 memory root) external view returns (bool)
+        function rootHistoryLength() external view returns (uint256)
+        function merkleHeight() external view returns (uint8)
+        function getFeeRecipient() external view returns (address)
 
         function newWallet(bytes memory proof, bytes memory valid_wallet_create_statement_bytes) external
         function updateWallet(bytes memory proof, bytes memory valid_wallet_update_statement_bytes, bytes memory wallet_commitment_signature, bytes memory transfer_aux_data) external
@@ -53,6 +59,14 @@ abigen!(
     ]"#
 );
 
+/// The subset of the ERC-20 interface used by the relayer
+abigen!(
+    Erc20Contract,
+    r#"[
+        function approve(address spender, uint256 amount) external returns (bool)
+    ]"#
+);
+
 sol! {
     function newWallet(bytes memory proof, bytes memory valid_wallet_create_statement_bytes) external;
     function updateWallet(bytes memory proof, bytes memory valid_wallet_update_statement_bytes, bytes memory wallet_commitment_signature, bytes memory transfer_aux_data) external;
@@ -61,3 +75,10 @@ sol! {
     function settleOfflineFee(bytes memory proof, bytes memory valid_offline_fee_settlement_statement) external;
     function redeemFee(bytes memory proof, bytes memory valid_fee_redemption_statement, bytes memory recipient_wallet_commitment_signature) external;
 }
+
+// Custom errors that the darkpool contract may revert with
+sol! {
+    error NullifierAlreadySpent();
+    error InvalidProof();
+    error MerkleRootNotInHistory();
+}
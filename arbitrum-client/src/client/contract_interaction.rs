@@ -12,9 +12,13 @@ use common::types::{
     },
     transfer_auth::TransferAuth,
 };
-use constants::Scalar;
 use contracts_common::types::MatchPayload;
-use renegade_crypto::fields::{scalar_to_u256, u256_to_scalar};
+use ethers::{
+    abi::Detokenize,
+    contract::ContractCall,
+    types::{Address, TransactionReceipt, TxHash, U256},
+};
+use renegade_crypto::fields::scalar_to_u256;
 use tracing::{info, instrument};
 
 use crate::{
@@ -26,11 +30,14 @@ use crate::{
         to_contract_valid_relayer_fee_settlement_statement,
         to_contract_valid_wallet_create_statement, to_contract_valid_wallet_update_statement,
     },
-    errors::ArbitrumClientError,
-    helpers::{send_tx, serialize_calldata},
+    errors::{ArbitrumClientError, ConversionError},
+    helpers::{
+        build_erc20_approval, check_fee_recipient, check_merkle_height,
+        estimate_gas_within_ceiling, send_tx, serialize_calldata, u256_to_canonical_scalar,
+    },
 };
 
-use super::ArbitrumClient;
+use super::{ArbitrumClient, SignerHttpProvider};
 
 impl ArbitrumClient {
     // -----------
@@ -39,13 +46,15 @@ impl ArbitrumClient {
 
     /// Get the current Merkle root in the contract
     #[instrument(skip_all, err)]
-    pub async fn get_merkle_root(&self) -> Result<Scalar, ArbitrumClientError> {
-        self.darkpool_contract
+    pub async fn get_merkle_root(&self) -> Result<MerkleRoot, ArbitrumClientError> {
+        let root = self
+            .darkpool_contract
             .get_root()
             .call()
             .await
-            .map_err(|e| ArbitrumClientError::ContractInteraction(e.to_string()))
-            .map(|r| u256_to_scalar(&r))
+            .map_err(|e| ArbitrumClientError::ContractInteraction(e.to_string()))?;
+
+        u256_to_canonical_scalar(&root).map(MerkleRoot::new)
     }
 
     /// Check whether the given Merkle root is a valid historical root
@@ -55,12 +64,62 @@ impl ArbitrumClient {
         root: MerkleRoot,
     ) -> Result<bool, ArbitrumClientError> {
         self.darkpool_contract
-            .root_in_history(scalar_to_u256(&root))
+            .root_in_history(scalar_to_u256(&root.inner()))
+            .call()
+            .await
+            .map_err(|e| ArbitrumClientError::ContractInteraction(e.to_string()))
+    }
+
+    /// Get the number of historical Merkle roots the contract accepts
+    #[instrument(skip_all, err)]
+    pub async fn get_root_history_length(&self) -> Result<usize, ArbitrumClientError> {
+        let len = self
+            .darkpool_contract
+            .root_history_length()
+            .call()
+            .await
+            .map_err(|e| ArbitrumClientError::ContractInteraction(e.to_string()))?;
+
+        usize::try_from(len).map_err(|_| ConversionError::InvalidUint.into())
+    }
+
+    /// Get the height of the contract's Merkle tree
+    #[instrument(skip_all, err)]
+    pub async fn get_merkle_height(&self) -> Result<usize, ArbitrumClientError> {
+        self.darkpool_contract
+            .merkle_height()
+            .call()
+            .await
+            .map_err(|e| ArbitrumClientError::ContractInteraction(e.to_string()))
+            .map(|height| height as usize)
+    }
+
+    /// Check that the contract's Merkle tree height matches the height the
+    /// relayer was compiled with
+    pub async fn validate_merkle_height(&self) -> Result<(), ArbitrumClientError> {
+        let height = self.get_merkle_height().await?;
+        check_merkle_height(height)
+    }
+
+    /// Get the address to which the darkpool routes protocol fees
+    #[instrument(skip_all, err)]
+    pub async fn get_fee_recipient(&self) -> Result<Address, ArbitrumClientError> {
+        self.darkpool_contract
+            .get_fee_recipient()
             .call()
             .await
             .map_err(|e| ArbitrumClientError::ContractInteraction(e.to_string()))
     }
 
+    /// Check that the contract routes protocol fees to the expected address
+    pub async fn validate_fee_recipient(
+        &self,
+        expected: Address,
+    ) -> Result<(), ArbitrumClientError> {
+        let recipient = self.get_fee_recipient().await?;
+        check_fee_recipient(recipient, expected)
+    }
+
     /// Check whether the given nullifier is used
     #[instrument(skip_all, err, fields(nullifier = %nullifier))]
     pub async fn check_nullifier_used(
@@ -68,7 +127,7 @@ impl ArbitrumClient {
         nullifier: Nullifier,
     ) -> Result<bool, ArbitrumClientError> {
         self.darkpool_contract
-            .is_nullifier_spent(scalar_to_u256(&nullifier))
+            .is_nullifier_spent(scalar_to_u256(&nullifier.inner()))
             .call()
             .await
             .map_err(|e| ArbitrumClientError::ContractInteraction(e.to_string()))
@@ -98,11 +157,12 @@ impl ArbitrumClient {
         let contract_statement = to_contract_valid_wallet_create_statement(statement);
         let valid_wallet_create_statement_calldata = serialize_calldata(&contract_statement)?;
 
-        let receipt = send_tx(
-            self.darkpool_contract
-                .new_wallet(proof_calldata, valid_wallet_create_statement_calldata),
-        )
-        .await?;
+        let receipt = self
+            .send_tx_within_gas_ceiling(
+                self.darkpool_contract
+                    .new_wallet(proof_calldata, valid_wallet_create_statement_calldata),
+            )
+            .await?;
 
         let tx_hash = format!("{:#x}", receipt.transaction_hash);
         tracing::Span::current().record("tx_hash", &tx_hash);
@@ -137,13 +197,14 @@ impl ArbitrumClient {
             transfer_auth.map(to_contract_transfer_aux_data).transpose()?.unwrap_or_default();
         let transfer_aux_data_calldata = serialize_calldata(&contract_transfer_aux_data)?;
 
-        let receipt = send_tx(self.darkpool_contract.update_wallet(
-            proof_calldata,
-            valid_wallet_update_statement_calldata,
-            wallet_commitment_signature.into(),
-            transfer_aux_data_calldata,
-        ))
-        .await?;
+        let receipt = self
+            .send_tx_within_gas_ceiling(self.darkpool_contract.update_wallet(
+                proof_calldata,
+                valid_wallet_update_statement_calldata,
+                wallet_commitment_signature.into(),
+                transfer_aux_data_calldata,
+            ))
+            .await?;
 
         let tx_hash = format!("{:#x}", receipt.transaction_hash);
         tracing::Span::current().record("tx_hash", &tx_hash);
@@ -155,7 +216,8 @@ impl ArbitrumClient {
     /// Call the `process_match_settle` contract method with the given
     /// match payloads and `VALID MATCH SETTLE` statement
     ///
-    /// Awaits until the transaction is confirmed on-chain
+    /// Awaits until the transaction is confirmed on-chain, returning the
+    /// transaction's receipt
     #[instrument(skip_all, err, fields(
         tx_hash,
         party0_blinder = %match_bundle.match_proof.statement.party0_modified_shares.blinder,
@@ -166,7 +228,7 @@ impl ArbitrumClient {
         party0_validity_proofs: &OrderValidityProofBundle,
         party1_validity_proofs: &OrderValidityProofBundle,
         match_bundle: &MatchBundle,
-    ) -> Result<(), ArbitrumClientError> {
+    ) -> Result<TransactionReceipt, ArbitrumClientError> {
         // Destructure proof bundles
 
         let GenericMatchSettleBundle {
@@ -231,20 +293,24 @@ impl ArbitrumClient {
 
         // Call `process_match_settle` on darkpool contract
 
-        let receipt = send_tx(self.darkpool_contract.process_match_settle(
-            party_0_match_payload_calldata,
-            party_1_match_payload_calldata,
-            valid_match_settle_statement_calldata,
-            match_proofs_calldata,
-            match_link_proofs_calldata,
-        ))
-        .await?;
+        let receipt = self
+            .send_tx_within_gas_ceiling(self.darkpool_contract.process_match_settle(
+                party_0_match_payload_calldata,
+                party_1_match_payload_calldata,
+                valid_match_settle_statement_calldata,
+                match_proofs_calldata,
+                match_link_proofs_calldata,
+            ))
+            .await?;
 
         let tx_hash = format!("{:#x}", receipt.transaction_hash);
         tracing::Span::current().record("tx_hash", &tx_hash);
         info!("`process_match_settle` tx hash: {}", tx_hash);
 
-        Ok(())
+        if receipt.block_number.is_none() {
+            return Err(ArbitrumClientError::TxDropped);
+        }
+        Ok(receipt)
     }
 
     /// Call the `settle_online_relayer_fee` contract method with the given
@@ -270,12 +336,13 @@ impl ArbitrumClient {
         let valid_relayer_fee_settlement_statement_calldata =
             serialize_calldata(&contract_statement)?;
 
-        let receipt = send_tx(self.darkpool_contract.settle_online_relayer_fee(
-            proof_calldata,
-            valid_relayer_fee_settlement_statement_calldata,
-            relayer_wallet_commitment_signature.into(),
-        ))
-        .await?;
+        let receipt = self
+            .send_tx_within_gas_ceiling(self.darkpool_contract.settle_online_relayer_fee(
+                proof_calldata,
+                valid_relayer_fee_settlement_statement_calldata,
+                relayer_wallet_commitment_signature.into(),
+            ))
+            .await?;
 
         let tx_hash = format!("{:#x}", receipt.transaction_hash);
         tracing::Span::current().record("tx_hash", &tx_hash);
@@ -305,8 +372,8 @@ impl ArbitrumClient {
         let valid_offline_fee_settlement_statement_calldata =
             serialize_calldata(&contract_statement)?;
 
-        let receipt =
-            send_tx(self.darkpool_contract.settle_offline_fee(
+        let receipt = self
+            .send_tx_within_gas_ceiling(self.darkpool_contract.settle_offline_fee(
                 proof_calldata,
                 valid_offline_fee_settlement_statement_calldata,
             ))
@@ -340,12 +407,13 @@ impl ArbitrumClient {
         let contract_statement = to_contract_valid_fee_redemption_statement(statement)?;
         let valid_fee_redemption_statement_calldata = serialize_calldata(&contract_statement)?;
 
-        let receipt = send_tx(self.darkpool_contract.redeem_fee(
-            proof_calldata,
-            valid_fee_redemption_statement_calldata,
-            recipient_wallet_commitment_signature.into(),
-        ))
-        .await?;
+        let receipt = self
+            .send_tx_within_gas_ceiling(self.darkpool_contract.redeem_fee(
+                proof_calldata,
+                valid_fee_redemption_statement_calldata,
+                recipient_wallet_commitment_signature.into(),
+            ))
+            .await?;
 
         let tx_hash = format!("{:#x}", receipt.transaction_hash);
         tracing::Span::current().record("tx_hash", &tx_hash);
@@ -353,4 +421,83 @@ impl ArbitrumClient {
 
         Ok(())
     }
+
+    /// Approve `spender` to transfer `amount` of the given ERC-20 token from
+    /// the relayer's account
+    ///
+    /// Relayer-managed flows that deposit from the relayer's own account may
+    /// call this before submitting the deposit. Awaits until the transaction is
+    /// confirmed on-chain, returning its hash
+    #[instrument(skip_all, err, fields(
+        tx_hash,
+        token = %token,
+        spender = %spender,
+        amount = %amount
+    ))]
+    pub async fn approve_erc20(
+        &self,
+        token: Address,
+        spender: Address,
+        amount: U256,
+    ) -> Result<TxHash, ArbitrumClientError> {
+        let receipt = self
+            .send_tx_within_gas_ceiling(build_erc20_approval(token, spender, amount, self.client()))
+            .await?;
+
+        let tx_hash = format!("{:#x}", receipt.transaction_hash);
+        tracing::Span::current().record("tx_hash", &tx_hash);
+        info!("`approve` tx hash: {}", tx_hash);
+
+        Ok(receipt.transaction_hash)
+    }
+
+    // -----------
+    // | HELPERS |
+    // -----------
+
+    /// Estimate the gas for a transaction and submit it only if the estimate
+    /// is within the configured gas ceiling
+    async fn send_tx_within_gas_ceiling<D: Detokenize>(
+        &self,
+        tx: ContractCall<SignerHttpProvider, D>,
+    ) -> Result<TransactionReceipt, ArbitrumClientError> {
+        let gas = estimate_gas_within_ceiling(&tx, self.gas_limit_ceiling).await?;
+        send_tx(tx.gas(gas)).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use ethers::{
+        abi::{encode, Token},
+        providers::Provider,
+        types::{Address, Bytes, U256},
+    };
+
+    use crate::abi::DarkpoolContract;
+
+    /// Tests decoding the fee recipient from a mocked `eth_call` response
+    #[tokio::test]
+    async fn test_get_fee_recipient() {
+        let (provider, mock) = Provider::mocked();
+        let fee_recipient = Address::random();
+        mock.push::<Bytes, _>(Bytes::from(encode(&[Token::Address(fee_recipient)]))).unwrap();
+
+        let contract = DarkpoolContract::new(Address::random(), Arc::new(provider));
+        let res = contract.get_fee_recipient().call().await.unwrap();
+        assert_eq!(res, fee_recipient);
+    }
+
+    /// Tests decoding the Merkle tree height from a mocked `eth_call` response
+    #[tokio::test]
+    async fn test_get_merkle_height() {
+        let (provider, mock) = Provider::mocked();
+        mock.push::<Bytes, _>(Bytes::from(encode(&[Token::Uint(U256::from(16u8))]))).unwrap();
+
+        let contract = DarkpoolContract::new(Address::random(), Arc::new(provider));
+        let res = contract.merkle_height().call().await.unwrap();
+        assert_eq!(res, 16);
+    }
 }
@@ -1,6 +1,8 @@
 //! Defines `ArbitrumClient` helpers that allow for indexing events
 //! emitted by the darkpool contract
 
+use std::{fmt::Display, future::Future, time::Duration};
+
 use alloy_sol_types::SolCall;
 use circuit_types::SizedWalletShare;
 use common::types::merkle::MerkleAuthenticationPath;
@@ -12,20 +14,25 @@ use ethers::{
 };
 use num_bigint::BigUint;
 use num_traits::ToPrimitive;
-use renegade_crypto::fields::{scalar_to_u256, u256_to_scalar};
-use tracing::{error, instrument};
+use renegade_crypto::fields::scalar_to_u256;
+use tracing::{debug, error, instrument, warn};
+use util::err_str;
 
 use crate::{
     abi::{
         newWalletCall, processMatchSettleCall, redeemFeeCall, settleOfflineFeeCall,
         settleOnlineRelayerFeeCall, updateWalletCall, NodeChangedFilter, WalletUpdatedFilter,
     },
-    constants::{DEFAULT_AUTHENTICATION_PATH, SELECTOR_LEN},
+    constants::{
+        DEFAULT_AUTHENTICATION_PATH, EVENT_QUERY_INITIAL_RETRY_DELAY_MS,
+        EVENT_QUERY_MAX_RETRY_DELAY_MS, MAX_EVENT_QUERY_ATTEMPTS, SELECTOR_LEN,
+    },
     errors::ArbitrumClientError,
     helpers::{
         parse_shares_from_new_wallet, parse_shares_from_process_match_settle,
         parse_shares_from_redeem_fee, parse_shares_from_settle_offline_fee,
         parse_shares_from_settle_online_relayer_fee, parse_shares_from_update_wallet,
+        u256_to_canonical_scalar,
     },
 };
 
@@ -45,14 +52,17 @@ impl ArbitrumClient {
         public_blinder_share: Scalar,
     ) -> Result<Option<TxHash>, ArbitrumClientError> {
         let events = self
-            .darkpool_contract
-            .event::<WalletUpdatedFilter>()
-            .address(self.darkpool_contract.address().into())
-            .topic1(scalar_to_u256(&public_blinder_share))
-            .from_block(self.deploy_block)
-            .query_with_meta()
-            .await
-            .map_err(|e| ArbitrumClientError::EventQuerying(e.to_string()))?;
+            .query_in_windows(|from, to| async move {
+                self.darkpool_contract
+                    .event::<WalletUpdatedFilter>()
+                    .address(self.darkpool_contract.address().into())
+                    .topic1(scalar_to_u256(&public_blinder_share))
+                    .from_block(from)
+                    .to_block(to)
+                    .query_with_meta()
+                    .await
+            })
+            .await?;
 
         let tx_hash = events.last().map(|(_, meta)| meta.transaction_hash);
 
@@ -86,20 +96,23 @@ impl ArbitrumClient {
             let height = H256::from_slice((coords.height as u8).encode().as_slice());
             let index = H256::from_slice(coords.index.to_u128().unwrap().encode().as_slice());
             let events = self
-                .darkpool_contract
-                .event::<NodeChangedFilter>()
-                .address(self.darkpool_contract.address().into())
-                .topic1(height)
-                .topic2(index)
-                .from_block(self.deploy_block)
-                .query()
-                .await
-                .map_err(|e| ArbitrumClientError::EventQuerying(e.to_string()))?;
+                .query_in_windows(|from, to| async move {
+                    self.darkpool_contract
+                        .event::<NodeChangedFilter>()
+                        .address(self.darkpool_contract.address().into())
+                        .topic1(height)
+                        .topic2(index)
+                        .from_block(from)
+                        .to_block(to)
+                        .query()
+                        .await
+                })
+                .await?;
 
             let value = events.last().map(|event| event.new_value);
 
             if let Some(value) = value {
-                path[MERKLE_HEIGHT - coords.height] = u256_to_scalar(&value);
+                path[MERKLE_HEIGHT - coords.height] = u256_to_canonical_scalar(&value)?;
             }
         }
 
@@ -113,14 +126,17 @@ impl ArbitrumClient {
         commitment: Scalar,
     ) -> Result<u128, ArbitrumClientError> {
         let events = self
-            .darkpool_contract
-            .event::<NodeChangedFilter>()
-            .address(self.darkpool_contract.address().into())
-            .topic3(scalar_to_u256(&commitment))
-            .from_block(self.deploy_block)
-            .query()
-            .await
-            .map_err(|e| ArbitrumClientError::EventQuerying(e.to_string()))?;
+            .query_in_windows(|from, to| async move {
+                self.darkpool_contract
+                    .event::<NodeChangedFilter>()
+                    .address(self.darkpool_contract.address().into())
+                    .topic3(scalar_to_u256(&commitment))
+                    .from_block(from)
+                    .to_block(to)
+                    .query()
+                    .await
+            })
+            .await?;
 
         events.last().map(|event| event.index).ok_or(ArbitrumClientError::CommitmentNotFound)
     }
@@ -167,4 +183,227 @@ impl ArbitrumClient {
             },
         }
     }
+
+    // -----------
+    // | Helpers |
+    // -----------
+
+    /// Run an event query over the blocks from the darkpool deployment to the
+    /// current head, split into windows of at most `event_block_window` blocks
+    async fn query_in_windows<D, E, F, Fut>(&self, query: F) -> Result<Vec<D>, ArbitrumClientError>
+    where
+        F: FnMut(u64, u64) -> Fut,
+        Fut: Future<Output = Result<Vec<D>, E>>,
+        E: Display,
+    {
+        let from_block = self.deploy_block.as_number().map(|n| n.as_u64()).unwrap_or_default();
+        let to_block = self
+            .client()
+            .get_block_number()
+            .await
+            .map_err(err_str!(ArbitrumClientError::Rpc))?
+            .as_u64();
+
+        scan_block_range(from_block, to_block, self.event_block_window, query).await
+    }
+}
+
+/// Scan the inclusive block range `[from_block, to_block]` in windows of at
+/// most `window` blocks, concatenating the results of `query` over each window
+///
+/// The window must be positive, the client rejects a zero window on
+/// construction
+///
+/// A failed window is retried up to `MAX_EVENT_QUERY_ATTEMPTS` times before the
+/// scan is aborted. Results from completed windows are kept, so a retry resumes
+/// the scan from the failed window rather than from the start of the range.
+/// Retries back off exponentially so that a disconnected provider may
+/// reconnect, the client's transport failing over to a fallback endpoint in
+/// the meantime
+async fn scan_block_range<D, E, F, Fut>(
+    from_block: u64,
+    to_block: u64,
+    window: u64,
+    mut query: F,
+) -> Result<Vec<D>, ArbitrumClientError>
+where
+    F: FnMut(u64, u64) -> Fut,
+    Fut: Future<Output = Result<Vec<D>, E>>,
+    E: Display,
+{
+    let mut results = Vec::new();
+    let mut start = from_block;
+    let mut attempts = 0;
+    let initial_backoff = Duration::from_millis(EVENT_QUERY_INITIAL_RETRY_DELAY_MS);
+    let max_backoff = Duration::from_millis(EVENT_QUERY_MAX_RETRY_DELAY_MS);
+    let mut backoff = initial_backoff;
+
+    while start <= to_block {
+        let end = start.saturating_add(window - 1).min(to_block);
+        match query(start, end).await {
+            Ok(events) => {
+                debug!("scanned blocks {start}-{end} of {from_block}-{to_block}");
+                results.extend(events);
+                attempts = 0;
+                backoff = initial_backoff;
+
+                match end.checked_add(1) {
+                    Some(next) => start = next,
+                    None => break,
+                }
+            },
+            Err(e) => {
+                attempts += 1;
+                if attempts >= MAX_EVENT_QUERY_ATTEMPTS {
+                    return Err(ArbitrumClientError::EventQuerying(e.to_string()));
+                }
+
+                warn!("error querying events in blocks {start}-{end}, resuming from {start}: {e}");
+                tokio::time::sleep(backoff).await;
+                backoff = Duration::min(backoff * 2, max_backoff);
+            },
+        }
+    }
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::Mutex, time::Duration};
+
+    use tokio::time::Instant;
+
+    use crate::constants::{
+        EVENT_QUERY_INITIAL_RETRY_DELAY_MS, EVENT_QUERY_MAX_RETRY_DELAY_MS,
+        MAX_EVENT_QUERY_ATTEMPTS,
+    };
+
+    use super::scan_block_range;
+
+    /// The maximum block span enforced by the mock provider
+    const MAX_SPAN: u64 = 100;
+
+    /// A mock `eth_getLogs` that rejects ranges wider than `MAX_SPAN` and
+    /// otherwise returns one "event" per block in the range
+    async fn mock_get_logs(from: u64, to: u64) -> Result<Vec<u64>, String> {
+        if to - from + 1 > MAX_SPAN {
+            return Err(format!("block range too large: {from}-{to}"));
+        }
+
+        Ok((from..=to).collect())
+    }
+
+    /// Tests that a range wider than the provider's max span is fully covered
+    #[tokio::test]
+    async fn test_scan_covers_range() {
+        let (from, to) = (17, 1_033);
+        let events = scan_block_range(from, to, MAX_SPAN, mock_get_logs).await.unwrap();
+        assert_eq!(events, (from..=to).collect::<Vec<_>>());
+
+        // A single block range
+        let events = scan_block_range(5, 5, MAX_SPAN, mock_get_logs).await.unwrap();
+        assert_eq!(events, vec![5]);
+    }
+
+    /// Tests that a window exceeding the provider's max span fails the scan
+    #[tokio::test]
+    async fn test_scan_window_too_large() {
+        let res = scan_block_range(0, 1_000, MAX_SPAN + 1, mock_get_logs).await;
+        assert!(res.is_err());
+    }
+
+    /// Tests that a transiently failing window is retried without duplicating
+    /// or dropping events, and without re-querying completed windows
+    #[tokio::test]
+    async fn test_scan_resumes_after_failure() {
+        let failed = Mutex::new(false);
+        let queried = Mutex::new(Vec::new());
+        let (from, to) = (0, 450);
+        let events = scan_block_range(from, to, MAX_SPAN, |start, end| {
+            queried.lock().unwrap().push(start);
+
+            // Fail the third window once
+            let fail =
+                start == 2 * MAX_SPAN && !std::mem::replace(&mut *failed.lock().unwrap(), true);
+            async move {
+                if fail {
+                    return Err("connection reset".to_string());
+                }
+
+                mock_get_logs(start, end).await
+            }
+        })
+        .await
+        .unwrap();
+
+        assert!(*failed.lock().unwrap());
+        assert_eq!(events, (from..=to).collect::<Vec<_>>());
+
+        // Only the failed window is queried twice
+        let windows = queried.into_inner().unwrap();
+        let expected = vec![0, MAX_SPAN, 2 * MAX_SPAN, 2 * MAX_SPAN, 3 * MAX_SPAN, 4 * MAX_SPAN];
+        assert_eq!(windows, expected);
+    }
+
+    /// Tests that a window failing until its last attempt is retried from the
+    /// checkpointed block, backing off up to the maximum delay, and that the
+    /// scan aborts once the window's attempts are exhausted
+    #[tokio::test(start_paused = true)]
+    async fn test_scan_retries_from_checkpoint() {
+        let (from, to) = (0, 450);
+        let failing_window = 2 * MAX_SPAN;
+
+        // Fail the third window on all but its last attempt
+        let failures = Mutex::new(0);
+        let queried = Mutex::new(Vec::new());
+        let scan_start = Instant::now();
+        let events = scan_block_range(from, to, MAX_SPAN, |start, end| {
+            queried.lock().unwrap().push(start);
+            let mut failures = failures.lock().unwrap();
+            let fail = start == failing_window && *failures < MAX_EVENT_QUERY_ATTEMPTS - 1;
+            if fail {
+                *failures += 1;
+            }
+
+            async move {
+                if fail {
+                    return Err("connection reset".to_string());
+                }
+
+                mock_get_logs(start, end).await
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(events, (from..=to).collect::<Vec<_>>());
+
+        // Only the failed window is re-queried, each time from its first block
+        let windows = queried.into_inner().unwrap();
+        let retries = vec![failing_window; MAX_EVENT_QUERY_ATTEMPTS];
+        let expected = [vec![0, MAX_SPAN], retries, vec![3 * MAX_SPAN, 4 * MAX_SPAN]].concat();
+        assert_eq!(windows, expected);
+
+        // The retries back off exponentially up to the maximum delay
+        let max_delay = Duration::from_millis(EVENT_QUERY_MAX_RETRY_DELAY_MS);
+        let expected_delay: Duration = (0..MAX_EVENT_QUERY_ATTEMPTS as u32 - 1)
+            .map(|i| Duration::from_millis(EVENT_QUERY_INITIAL_RETRY_DELAY_MS << i).min(max_delay))
+            .sum();
+        assert_eq!(scan_start.elapsed(), expected_delay);
+
+        // The attempts outlast the backoff's growth, so the last retries are capped
+        let last_retry = EVENT_QUERY_INITIAL_RETRY_DELAY_MS << (MAX_EVENT_QUERY_ATTEMPTS - 2);
+        assert!(Duration::from_millis(last_retry) > max_delay);
+
+        // A window failing on every attempt aborts the scan
+        let res = scan_block_range(from, to, MAX_SPAN, |start, end| async move {
+            if start == failing_window {
+                return Err("connection reset".to_string());
+            }
+
+            mock_get_logs(start, end).await
+        })
+        .await;
+        assert!(res.is_err());
+    }
 }
@@ -0,0 +1,283 @@
+//! An HTTP JSON-RPC transport that fails over between a prioritized list of
+//! RPC endpoints
+//!
+//! Requests are sent to the highest priority endpoint that is currently
+//! healthy. When an endpoint fails at the connection level it is placed in a
+//! cooldown, during which lower priority endpoints are preferred. Requests that
+//! exceed the configured timeout are treated as connection failures
+
+use std::{
+    fmt::Debug,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use ethers::providers::{Http, HttpClientError, JsonRpcClient};
+use reqwest::{Client, Url};
+use serde::{de::DeserializeOwned, Serialize};
+use tracing::warn;
+
+use crate::errors::ArbitrumClientConfigError;
+
+/// The error message emitted when no RPC endpoints are configured
+const ERR_NO_ENDPOINTS: &str = "no RPC endpoints configured";
+
+/// A single RPC endpoint along with its health
+#[derive(Debug)]
+struct Endpoint {
+    /// The URL of the endpoint, kept for logging
+    url: String,
+    /// The HTTP transport used to reach the endpoint
+    transport: Http,
+    /// The time until which the endpoint is considered unhealthy, if it has
+    /// recently failed
+    unhealthy_until: Mutex<Option<Instant>>,
+}
+
+impl Endpoint {
+    /// Whether the endpoint is outside of a failure cooldown
+    fn is_healthy(&self) -> bool {
+        match *self.unhealthy_until.lock().unwrap() {
+            Some(until) => Instant::now() >= until,
+            None => true,
+        }
+    }
+
+    /// Mark the endpoint as failed, placing it in a cooldown
+    fn mark_failed(&self, cooldown: Duration) {
+        *self.unhealthy_until.lock().unwrap() = Some(Instant::now() + cooldown);
+    }
+
+    /// Mark the endpoint as healthy, clearing any cooldown
+    fn mark_healthy(&self) {
+        *self.unhealthy_until.lock().unwrap() = None;
+    }
+}
+
+/// An HTTP transport that rotates through a list of endpoints on connection
+/// failure, preferring the primary endpoint when it is healthy
+#[derive(Debug)]
+pub struct FallbackHttp {
+    /// The endpoints in priority order, the first being the primary
+    endpoints: Vec<Endpoint>,
+    /// The duration for which a failed endpoint is deprioritized
+    cooldown: Duration,
+}
+
+impl FallbackHttp {
+    /// Constructor
+    pub fn new(
+        urls: &[String],
+        cooldown: Duration,
+        timeout: Duration,
+    ) -> Result<Self, ArbitrumClientConfigError> {
+        if urls.is_empty() {
+            return Err(ArbitrumClientConfigError::RpcClientInitialization(
+                ERR_NO_ENDPOINTS.to_string(),
+            ));
+        }
+
+        let client = Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| ArbitrumClientConfigError::RpcClientInitialization(e.to_string()))?;
+
+        let mut endpoints = Vec::with_capacity(urls.len());
+        for url in urls.iter() {
+            let parsed_url = Url::parse(url)
+                .map_err(|e| ArbitrumClientConfigError::RpcClientInitialization(e.to_string()))?;
+            let transport = Http::new_with_client(parsed_url, client.clone());
+            endpoints.push(Endpoint {
+                url: url.clone(),
+                transport,
+                unhealthy_until: Mutex::new(None),
+            });
+        }
+
+        Ok(Self { endpoints, cooldown })
+    }
+
+    /// The order in which to attempt endpoints for a request
+    ///
+    /// Healthy endpoints are tried first in priority order, followed by those
+    /// in cooldown so that a request is never refused outright
+    fn endpoint_order(&self) -> Vec<&Endpoint> {
+        let (healthy, unhealthy): (Vec<_>, Vec<_>) =
+            self.endpoints.iter().partition(|endpoint| endpoint.is_healthy());
+        healthy.into_iter().chain(unhealthy).collect()
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for FallbackHttp {
+    type Error = HttpClientError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let mut last_err = None;
+        for endpoint in self.endpoint_order() {
+            match endpoint.transport.request(method, &params).await {
+                // Only connection-level failures trigger a failover, JSON-RPC errors are
+                // returned to the caller as they would recur on any endpoint
+                Err(HttpClientError::ReqwestError(e)) => {
+                    warn!("RPC endpoint {} failed, failing over: {e}", endpoint.url);
+                    endpoint.mark_failed(self.cooldown);
+                    last_err = Some(HttpClientError::ReqwestError(e));
+                },
+                res => {
+                    if res.is_ok() {
+                        endpoint.mark_healthy();
+                    }
+
+                    return res;
+                },
+            }
+        }
+
+        // The constructor guarantees at least one endpoint, so an error was recorded
+        Err(last_err.unwrap())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use ethers::{
+        providers::{Middleware, Provider},
+        types::U256,
+    };
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::FallbackHttp;
+
+    /// The chain ID returned by the mock endpoint
+    const MOCK_CHAIN_ID: u64 = 42;
+    /// The request timeout used in tests
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Get the URL of a local port that refuses connections
+    async fn dead_endpoint() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        format!("http://{addr}")
+    }
+
+    /// Spawn a mock endpoint that accepts connections but never responds
+    async fn hung_endpoint() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let mut streams = Vec::new();
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                streams.push(stream);
+            }
+        });
+
+        format!("http://{addr}")
+    }
+
+    /// Spawn a mock JSON-RPC endpoint that answers every request with the mock
+    /// chain ID
+    async fn mock_endpoint() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+
+                // Read the request headers and body
+                let mut buf = Vec::new();
+                let mut chunk = [0u8; 1024];
+                loop {
+                    let n = stream.read(&mut chunk).await.unwrap();
+                    if n == 0 {
+                        break;
+                    }
+                    buf.extend_from_slice(&chunk[..n]);
+
+                    let req = String::from_utf8_lossy(&buf).to_lowercase();
+                    if let Some(header_end) = req.find("\r\n\r\n") {
+                        let content_len = req
+                            .lines()
+                            .find_map(|l| l.strip_prefix("content-length:"))
+                            .map(|l| l.trim().parse::<usize>().unwrap())
+                            .unwrap_or_default();
+                        if buf.len() >= header_end + 4 + content_len {
+                            break;
+                        }
+                    }
+                }
+
+                let body = format!(r#"{{"jsonrpc":"2.0","id":1,"result":"{MOCK_CHAIN_ID:#x}"}}"#);
+                let resp = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: \
+                     {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                stream.write_all(resp.as_bytes()).await.unwrap();
+            }
+        });
+
+        format!("http://{addr}")
+    }
+
+    /// Tests that requests fail over to the second endpoint when the primary
+    /// is unreachable
+    #[tokio::test]
+    async fn test_failover() {
+        let urls = vec![dead_endpoint().await, mock_endpoint().await];
+        let transport = FallbackHttp::new(&urls, Duration::from_secs(60), TIMEOUT).unwrap();
+        let provider = Provider::new(transport);
+
+        let chain_id = provider.get_chainid().await.unwrap();
+        assert_eq!(chain_id, U256::from(MOCK_CHAIN_ID));
+
+        // The primary should now be in cooldown and the fallback preferred
+        let transport = provider.as_ref();
+        assert!(!transport.endpoints[0].is_healthy());
+        assert!(transport.endpoints[1].is_healthy());
+        assert_eq!(transport.endpoint_order()[0].url, urls[1]);
+
+        // Subsequent requests continue to succeed
+        let chain_id = provider.get_chainid().await.unwrap();
+        assert_eq!(chain_id, U256::from(MOCK_CHAIN_ID));
+    }
+
+    /// Tests that a request to an unresponsive endpoint errors at the
+    /// configured timeout
+    #[tokio::test]
+    async fn test_request_timeout() {
+        let timeout = Duration::from_millis(200);
+        let urls = vec![hung_endpoint().await];
+        let transport = FallbackHttp::new(&urls, Duration::from_secs(60), timeout).unwrap();
+        let provider = Provider::new(transport);
+
+        let start = Instant::now();
+        assert!(provider.get_chainid().await.is_err());
+        let elapsed = start.elapsed();
+        assert!(elapsed >= timeout);
+        assert!(elapsed < timeout * 10);
+
+        // The timed out endpoint is placed in a cooldown
+        assert!(!provider.as_ref().endpoints[0].is_healthy());
+    }
+
+    /// Tests that an empty endpoint list is rejected
+    #[test]
+    fn test_no_endpoints() {
+        assert!(FallbackHttp::new(&[], Duration::from_secs(1), TIMEOUT).is_err());
+    }
+}
@@ -1,27 +1,30 @@
 //! The definition of the Arbitrum client, which holds the configuration
 //! details, along with a lower-level handle for the darkpool smart contract
 
-use std::{str::FromStr, sync::Arc};
+use std::{str::FromStr, sync::Arc, time::Duration};
 
 use alloy_primitives::ChainId;
 use constants::{DEVNET_DEPLOY_BLOCK, TESTNET_DEPLOY_BLOCK};
 use ethers::{
     core::k256::ecdsa::SigningKey,
     middleware::SignerMiddleware,
-    providers::{Http, Middleware, Provider},
+    providers::{Middleware, Provider},
     signers::{LocalWallet, Signer, Wallet},
-    types::{Address, BlockNumber},
+    types::{Address, BlockNumber, TransactionReceipt, TxHash},
 };
 use util::err_str;
 
 use crate::{
     abi::DarkpoolContract,
-    constants::Chain,
+    constants::{Chain, RPC_ENDPOINT_COOLDOWN_MS},
     errors::{ArbitrumClientConfigError, ArbitrumClientError},
 };
 
 mod contract_interaction;
 mod event_indexing;
+mod fallback_provider;
+
+pub use fallback_provider::FallbackHttp;
 
 /// A configuration struct for the Arbitrum client, consists of relevant
 /// contract addresses, and endpoint for setting up an RPC client, and a private
@@ -36,30 +39,44 @@ pub struct ArbitrumClientConfig {
     pub chain: Chain,
     /// HTTP-addressable RPC endpoint for the client to connect to
     pub rpc_url: String,
+    /// Additional RPC endpoints to fail over to, in priority order, when the
+    /// primary endpoint is unreachable
+    pub fallback_rpc_urls: Vec<String>,
+    /// The timeout in milliseconds on a single RPC request
+    pub rpc_timeout_ms: u64,
+    /// The maximum number of blocks to span in a single event query, must be
+    /// positive
+    pub event_block_window: u64,
+    /// The maximum gas a transaction may be estimated to use before the
+    /// client refuses to submit it
+    pub gas_limit_ceiling: u64,
     /// The private key of the account to use for signing transactions
     pub arb_priv_key: LocalWallet,
 }
 
 /// A type alias for the RPC client, which is an ethers middleware stack that
 /// includes a signer derived from a raw private key, and a provider that
-/// connects to the RPC endpoint over HTTP.
-pub type SignerHttpProvider = SignerMiddleware<Provider<Http>, Wallet<SigningKey>>;
+/// connects to the RPC endpoints over HTTP.
+pub type SignerHttpProvider = SignerMiddleware<Provider<FallbackHttp>, Wallet<SigningKey>>;
 
 impl ArbitrumClientConfig {
     /// Gets the block number at which the darkpool was deployed
-    fn get_deploy_block(&self) -> BlockNumber {
+    fn get_deploy_block(&self) -> Result<BlockNumber, ArbitrumClientConfigError> {
         match self.chain {
-            Chain::Mainnet => unimplemented!(),
-            Chain::Testnet => BlockNumber::Number(TESTNET_DEPLOY_BLOCK.into()),
-            Chain::Devnet => BlockNumber::Number(DEVNET_DEPLOY_BLOCK.into()),
+            Chain::Mainnet => Err(ArbitrumClientConfigError::UnsupportedChain(self.chain)),
+            Chain::Testnet => Ok(BlockNumber::Number(TESTNET_DEPLOY_BLOCK.into())),
+            Chain::Devnet => Ok(BlockNumber::Number(DEVNET_DEPLOY_BLOCK.into())),
         }
     }
 
     /// Constructs an RPC client capable of signing transactions from the
     /// configuration
     async fn get_rpc_client(&self) -> Result<Arc<SignerHttpProvider>, ArbitrumClientConfigError> {
-        let provider = Provider::<Http>::try_from(&self.rpc_url)
-            .map_err(|e| ArbitrumClientConfigError::RpcClientInitialization(e.to_string()))?;
+        let urls: Vec<String> =
+            [self.rpc_url.clone()].into_iter().chain(self.fallback_rpc_urls.clone()).collect();
+        let cooldown = Duration::from_millis(RPC_ENDPOINT_COOLDOWN_MS);
+        let timeout = Duration::from_millis(self.rpc_timeout_ms);
+        let provider = Provider::new(FallbackHttp::new(&urls, cooldown, timeout)?);
 
         let chain_id = provider
             .get_chainid()
@@ -102,15 +119,26 @@ pub struct ArbitrumClient {
     pub darkpool_contract: DarkpoolContract<SignerHttpProvider>,
     /// The block number at which the darkpool was deployed
     deploy_block: BlockNumber,
+    /// The maximum number of blocks to span in a single event query
+    event_block_window: u64,
+    /// The maximum gas a transaction may be estimated to use before the
+    /// client refuses to submit it
+    gas_limit_ceiling: u64,
 }
 
 impl ArbitrumClient {
     /// Constructs a new Arbitrum client from the given configuration
     pub async fn new(config: ArbitrumClientConfig) -> Result<Self, ArbitrumClientError> {
+        if config.event_block_window == 0 {
+            return Err(ArbitrumClientConfigError::ZeroEventBlockWindow.into());
+        }
+
+        let deploy_block = config.get_deploy_block()?;
         let darkpool_contract = config.construct_contract_instance().await?;
-        let deploy_block = config.get_deploy_block();
+        let event_block_window = config.event_block_window;
+        let gas_limit_ceiling = config.gas_limit_ceiling;
 
-        Ok(Self { darkpool_contract, deploy_block })
+        Ok(Self { darkpool_contract, deploy_block, event_block_window, gas_limit_ceiling })
     }
 
     /// Get a reference to the underlying RPC client
@@ -141,6 +169,18 @@ impl ArbitrumClient {
             .map_err(|e| ArbitrumClientError::Rpc(e.to_string()))
     }
 
+    /// Get the receipt of a transaction, `None` if the transaction is not
+    /// included in the canonical chain
+    pub async fn get_transaction_receipt(
+        &self,
+        tx_hash: TxHash,
+    ) -> Result<Option<TransactionReceipt>, ArbitrumClientError> {
+        self.client()
+            .get_transaction_receipt(tx_hash)
+            .await
+            .map_err(|e| ArbitrumClientError::TxQuerying(e.to_string()))
+    }
+
     /// Resets the deploy block to the current block number.
     ///
     /// Used in integration tests to ensure that we are only querying for events
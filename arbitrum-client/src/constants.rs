@@ -6,10 +6,14 @@ use ark_ff::{BigInt, Fp};
 use constants::{Scalar, MERKLE_HEIGHT};
 use lazy_static::lazy_static;
 use renegade_crypto::hash::compute_poseidon_hash;
-use serde::{Deserialize, Serialize};
+use serde::{de::Error as DeserializeError, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::errors::ChainParseError;
 
 /// The chain environment
-#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+///
+/// Serialized as its lowercase name, the same form accepted by `FromStr`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Chain {
     /// Mainnet chain
     Mainnet,
@@ -30,21 +34,63 @@ impl Display for Chain {
 }
 
 impl FromStr for Chain {
-    type Err = String;
+    type Err = ChainParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
+        match s.trim().to_lowercase().as_str() {
             "mainnet" => Ok(Chain::Mainnet),
             "testnet" => Ok(Chain::Testnet),
             "devnet" => Ok(Chain::Devnet),
-            _ => Err(format!("Invalid chain: {s}")),
+            "" => Err(ChainParseError::Empty),
+            _ => Err(ChainParseError::Unknown(s.to_string())),
         }
     }
 }
 
+impl Serialize for Chain {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Chain {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Chain::from_str(&s).map_err(D::Error::custom)
+    }
+}
+
 /// The number of bytes in a Solidity function selector
 pub const SELECTOR_LEN: usize = 4;
 
+/// The duration for which an RPC endpoint is deprioritized after a connection
+/// failure
+pub const RPC_ENDPOINT_COOLDOWN_MS: u64 = 30_000; // 30 seconds
+/// The default timeout on a single RPC request, after which the request fails
+/// and may be retried against another endpoint
+pub const DEFAULT_RPC_TIMEOUT_MS: u64 = 10_000; // 10 seconds
+
+/// The default maximum number of blocks spanned by a single `eth_getLogs`
+/// query when indexing events
+pub const DEFAULT_EVENT_BLOCK_WINDOW: u64 = 10_000;
+/// The number of times a single window of an event scan is attempted before
+/// the scan is aborted
+///
+/// With the backoff below, a window is retried for roughly 16 seconds, the
+/// later retries at the maximum delay
+pub const MAX_EVENT_QUERY_ATTEMPTS: usize = 8;
+/// The delay before a failed window of an event scan is first retried, giving
+/// the RPC transport time to fail over or reconnect
+///
+/// The delay doubles on each subsequent failure of the same window
+pub const EVENT_QUERY_INITIAL_RETRY_DELAY_MS: u64 = 250;
+/// The maximum delay between retries of a failed window of an event scan
+pub const EVENT_QUERY_MAX_RETRY_DELAY_MS: u64 = 4_000;
+
+/// The default ceiling on the estimated gas of a transaction, above which the
+/// transaction is not submitted
+pub const DEFAULT_GAS_LIMIT_CEILING: u64 = 100_000_000;
+
 // The following are used for cases in which runtime type-based event filtering
 // is not possible. In these cases, we must construct filters manually using ABI
 // signatures
@@ -92,3 +138,47 @@ lazy_static! {
         values.try_into().unwrap()
     };
 }
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use crate::errors::ChainParseError;
+
+    use super::Chain;
+
+    /// Tests parsing the supported chains, case-insensitively
+    #[test]
+    fn test_parse_valid_chains() {
+        assert_eq!(Chain::from_str("mainnet"), Ok(Chain::Mainnet));
+        assert_eq!(Chain::from_str("Testnet"), Ok(Chain::Testnet));
+        assert_eq!(Chain::from_str(" DEVNET "), Ok(Chain::Devnet));
+
+        // Parsing round trips through the display representation
+        for chain in [Chain::Mainnet, Chain::Testnet, Chain::Devnet] {
+            assert_eq!(Chain::from_str(&chain.to_string()), Ok(chain));
+        }
+    }
+
+    /// Tests that invalid chain strings return a descriptive error
+    #[test]
+    fn test_parse_invalid_chains() {
+        assert_eq!(Chain::from_str(""), Err(ChainParseError::Empty));
+
+        let err = Chain::from_str("foo").unwrap_err();
+        assert_eq!(err, ChainParseError::Unknown("foo".to_string()));
+        assert!(err.to_string().contains("foo"));
+        assert!(err.to_string().contains("testnet"));
+    }
+
+    /// Tests serializing and deserializing a chain as its name
+    #[test]
+    fn test_chain_serde() {
+        let ser = serde_json::to_string(&Chain::Devnet).unwrap();
+        assert_eq!(ser, "\"devnet\"");
+        assert_eq!(serde_json::from_str::<Chain>(&ser).unwrap(), Chain::Devnet);
+
+        let err = serde_json::from_str::<Chain>("\"foo\"").unwrap_err();
+        assert!(err.to_string().contains("foo"));
+    }
+}
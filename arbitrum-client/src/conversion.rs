@@ -149,10 +149,10 @@ pub fn to_contract_valid_wallet_update_statement(
     let old_pk_root = to_contract_public_signing_key(&statement.old_pk_root)?;
 
     Ok(ContractValidWalletUpdateStatement {
-        old_shares_nullifier: statement.old_shares_nullifier.inner(),
+        old_shares_nullifier: statement.old_shares_nullifier.inner().inner(),
         new_private_shares_commitment: statement.new_private_shares_commitment.inner(),
         new_public_shares,
-        merkle_root: statement.merkle_root.inner(),
+        merkle_root: statement.merkle_root.inner().inner(),
         external_transfer,
         old_pk_root,
     })
@@ -189,9 +189,9 @@ pub fn to_contract_valid_reblind_statement(
     statement: &ValidReblindStatement,
 ) -> ContractValidReblindStatement {
     ContractValidReblindStatement {
-        original_shares_nullifier: statement.original_shares_nullifier.inner(),
+        original_shares_nullifier: statement.original_shares_nullifier.inner().inner(),
         reblinded_private_shares_commitment: statement.reblinded_private_share_commitment.inner(),
-        merkle_root: statement.merkle_root.inner(),
+        merkle_root: statement.merkle_root.inner().inner(),
     }
 }
 
@@ -270,10 +270,10 @@ pub fn to_contract_valid_relayer_fee_settlement_statement(
     statement: &SizedValidRelayerFeeSettlementStatement,
 ) -> Result<ContractValidRelayerFeeSettlementStatement, ConversionError> {
     Ok(ContractValidRelayerFeeSettlementStatement {
-        sender_root: statement.sender_root.inner(),
-        recipient_root: statement.recipient_root.inner(),
-        sender_nullifier: statement.sender_nullifier.inner(),
-        recipient_nullifier: statement.recipient_nullifier.inner(),
+        sender_root: statement.sender_root.inner().inner(),
+        recipient_root: statement.recipient_root.inner().inner(),
+        sender_nullifier: statement.sender_nullifier.inner().inner(),
+        recipient_nullifier: statement.recipient_nullifier.inner().inner(),
         sender_wallet_commitment: statement.sender_wallet_commitment.inner(),
         recipient_wallet_commitment: statement.recipient_wallet_commitment.inner(),
         sender_updated_public_shares: statement
@@ -325,8 +325,8 @@ pub fn to_contract_valid_offline_fee_settlement_statement(
     statement: &SizedValidOfflineFeeSettlementStatement,
 ) -> ContractValidOfflineFeeSettlementStatement {
     ContractValidOfflineFeeSettlementStatement {
-        merkle_root: statement.merkle_root.inner(),
-        nullifier: statement.nullifier.inner(),
+        merkle_root: statement.merkle_root.inner().inner(),
+        nullifier: statement.nullifier.inner().inner(),
         updated_wallet_commitment: statement.updated_wallet_commitment.inner(),
         updated_wallet_public_shares: statement
             .updated_wallet_public_shares
@@ -347,10 +347,10 @@ pub fn to_contract_valid_fee_redemption_statement(
     statement: &SizedValidFeeRedemptionStatement,
 ) -> Result<ContractValidFeeRedemptionStatement, ConversionError> {
     Ok(ContractValidFeeRedemptionStatement {
-        wallet_root: statement.wallet_root.inner(),
-        note_root: statement.note_root.inner(),
-        nullifier: statement.wallet_nullifier.inner(),
-        note_nullifier: statement.note_nullifier.inner(),
+        wallet_root: statement.wallet_root.inner().inner(),
+        note_root: statement.note_root.inner().inner(),
+        nullifier: statement.wallet_nullifier.inner().inner(),
+        note_nullifier: statement.note_nullifier.inner().inner(),
         new_wallet_commitment: statement.new_wallet_commitment.inner(),
         new_wallet_public_shares: statement
             .new_wallet_public_shares
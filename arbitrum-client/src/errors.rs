@@ -2,6 +2,21 @@
 
 use std::{error::Error, fmt::Display};
 
+use alloy_sol_types::SolError;
+use ethers::{
+    contract::ContractError,
+    providers::Middleware,
+    types::{Address, U256},
+};
+
+use crate::{
+    abi::{InvalidProof, MerkleRootNotInHistory, NullifierAlreadySpent},
+    constants::{Chain, SELECTOR_LEN},
+};
+
+/// The chain names accepted when parsing a chain, used in error messages
+const VALID_CHAINS: &str = "mainnet, testnet, devnet";
+
 /// The error type returned by the Arbitrum client interface
 #[derive(Clone, Debug)]
 pub enum ArbitrumClientError {
@@ -32,6 +47,78 @@ pub enum ArbitrumClientError {
     /// Error thrown when a target public blinder share was not found
     /// in a given transaction
     BlinderNotFound,
+    /// The contract reverted because a nullifier in the transaction was
+    /// already spent
+    NullifierAlreadySpent,
+    /// The contract reverted because a proof in the transaction failed to
+    /// verify
+    InvalidProof,
+    /// The contract reverted because a Merkle root referenced by the
+    /// transaction is not in the contract's root history
+    MerkleRootNotInHistory,
+    /// The estimated gas of a transaction exceeds the configured ceiling, the
+    /// transaction was not submitted
+    GasLimitExceeded {
+        /// The gas estimated for the transaction
+        estimate: U256,
+        /// The configured gas ceiling
+        ceiling: u64,
+    },
+    /// The contract's Merkle tree height differs from the height the relayer
+    /// was compiled with, openings produced by the relayer would be invalid
+    MerkleHeightMismatch {
+        /// The height of the contract's Merkle tree
+        contract: usize,
+        /// The height the relayer was compiled with
+        compiled: usize,
+    },
+    /// The contract routes protocol fees to a different address than the
+    /// relayer was configured to expect
+    FeeRecipientMismatch {
+        /// The fee recipient set in the contract
+        contract: Address,
+        /// The fee recipient the relayer was configured with
+        expected: Address,
+    },
+    /// A value read from the contract is not a canonical scalar field element
+    NonCanonicalScalar(U256),
+}
+
+impl ArbitrumClientError {
+    /// Decode the revert data of a contract call into a typed error
+    ///
+    /// Returns `None` if the revert data does not begin with the selector of a
+    /// known darkpool custom error
+    pub fn from_revert_data(data: &[u8]) -> Option<Self> {
+        if data.len() < SELECTOR_LEN {
+            return None;
+        }
+
+        let selector: [u8; SELECTOR_LEN] = data[..SELECTOR_LEN].try_into().unwrap();
+        match selector {
+            NullifierAlreadySpent::SELECTOR => Some(Self::NullifierAlreadySpent),
+            InvalidProof::SELECTOR => Some(Self::InvalidProof),
+            MerkleRootNotInHistory::SELECTOR => Some(Self::MerkleRootNotInHistory),
+            _ => None,
+        }
+    }
+
+    /// Convert an error returned by a contract call, decoding the revert
+    /// reason if the darkpool reverted with a known custom error
+    pub fn from_contract_error<M: Middleware>(e: ContractError<M>) -> Self {
+        e.as_revert()
+            .and_then(|data| Self::from_revert_data(data.as_ref()))
+            .unwrap_or_else(|| Self::ContractInteraction(e.to_string()))
+    }
+
+    /// Whether the error is a contract revert that will recur deterministically
+    /// if the same transaction is resubmitted
+    pub fn is_permanent_revert(&self) -> bool {
+        matches!(
+            self,
+            Self::NullifierAlreadySpent | Self::InvalidProof | Self::MerkleRootNotInHistory
+        )
+    }
 }
 
 impl Display for ArbitrumClientError {
@@ -48,6 +135,10 @@ pub enum ArbitrumClientConfigError {
     RpcClientInitialization(String),
     /// Error thrown when a contract address can't be parsed
     AddressParsing(String),
+    /// Error thrown when the configured chain has no darkpool deployment
+    UnsupportedChain(Chain),
+    /// Error thrown when the event block window spans no blocks
+    ZeroEventBlockWindow,
 }
 
 impl Display for ArbitrumClientConfigError {
@@ -57,6 +148,25 @@ impl Display for ArbitrumClientConfigError {
 }
 impl Error for ArbitrumClientConfigError {}
 
+/// The error type returned when parsing a chain from a string
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChainParseError {
+    /// The chain string is empty
+    Empty,
+    /// The chain string does not name a known chain
+    Unknown(String),
+}
+
+impl Display for ChainParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "empty chain, expected one of: {VALID_CHAINS}"),
+            Self::Unknown(s) => write!(f, "unknown chain \"{s}\", expected one of: {VALID_CHAINS}"),
+        }
+    }
+}
+impl Error for ChainParseError {}
+
 impl From<ArbitrumClientConfigError> for ArbitrumClientError {
     fn from(e: ArbitrumClientConfigError) -> Self {
         Self::Config(e)
@@ -85,3 +195,38 @@ impl From<ConversionError> for ArbitrumClientError {
         Self::Conversion(e)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use alloy_sol_types::SolError;
+
+    use crate::abi::{InvalidProof, MerkleRootNotInHistory, NullifierAlreadySpent};
+
+    use super::ArbitrumClientError;
+
+    /// Tests decoding the revert payloads of the known custom errors
+    #[test]
+    fn test_decode_known_reverts() {
+        let payload = NullifierAlreadySpent {}.encode();
+        let err = ArbitrumClientError::from_revert_data(&payload);
+        assert!(matches!(err, Some(ArbitrumClientError::NullifierAlreadySpent)));
+
+        let payload = InvalidProof {}.encode();
+        let err = ArbitrumClientError::from_revert_data(&payload);
+        assert!(matches!(err, Some(ArbitrumClientError::InvalidProof)));
+
+        let payload = MerkleRootNotInHistory {}.encode();
+        let err = ArbitrumClientError::from_revert_data(&payload);
+        assert!(matches!(err, Some(ArbitrumClientError::MerkleRootNotInHistory)));
+    }
+
+    /// Tests that unknown or malformed revert payloads are not decoded
+    #[test]
+    fn test_decode_unknown_revert() {
+        let err = ArbitrumClientError::from_revert_data(&[0xde, 0xad, 0xbe, 0xef, 0x01]);
+        assert!(err.is_none());
+
+        let err = ArbitrumClientError::from_revert_data(&[0xde, 0xad]);
+        assert!(err.is_none());
+    }
+}
@@ -2,7 +2,7 @@
 
 use alloy_sol_types::SolCall;
 use circuit_types::{traits::BaseType, SizedWalletShare};
-use constants::Scalar;
+use constants::{Scalar, MERKLE_HEIGHT};
 use contracts_common::types::{
     ValidFeeRedemptionStatement as ContractValidFeeRedemptionStatement,
     ValidMatchSettleStatement as ContractValidMatchSettleStatement,
@@ -11,17 +11,21 @@ use contracts_common::types::{
     ValidWalletCreateStatement as ContractValidWalletCreateStatement,
     ValidWalletUpdateStatement as ContractValidWalletUpdateStatement,
 };
+use std::sync::Arc;
+
 use ethers::{
     abi::Detokenize,
     contract::ContractCall,
-    types::{Bytes, TransactionReceipt},
+    providers::Middleware,
+    types::{Address, Bytes, TransactionReceipt, U256},
 };
+use renegade_crypto::fields::u256_to_scalar_checked;
 use serde::{Deserialize, Serialize};
 
 use crate::{
     abi::{
         newWalletCall, processMatchSettleCall, redeemFeeCall, settleOfflineFeeCall,
-        settleOnlineRelayerFeeCall, updateWalletCall,
+        settleOnlineRelayerFeeCall, updateWalletCall, Erc20Contract,
     },
     client::SignerHttpProvider,
     errors::ArbitrumClientError,
@@ -47,12 +51,78 @@ pub async fn send_tx(
 ) -> Result<TransactionReceipt, ArbitrumClientError> {
     tx.send()
         .await
-        .map_err(|e| ArbitrumClientError::ContractInteraction(e.to_string()))?
+        .map_err(ArbitrumClientError::from_contract_error)?
         .await
         .map_err(|e| ArbitrumClientError::ContractInteraction(e.to_string()))?
         .ok_or(ArbitrumClientError::TxDropped)
 }
 
+/// Estimates the gas used by a transaction, rejecting it if the estimate
+/// exceeds the given ceiling
+///
+/// A transaction that would revert fails estimation, so its revert is
+/// surfaced here before any gas is paid
+pub async fn estimate_gas_within_ceiling<D: Detokenize>(
+    tx: &ContractCall<SignerHttpProvider, D>,
+    ceiling: u64,
+) -> Result<U256, ArbitrumClientError> {
+    let estimate = tx.estimate_gas().await.map_err(ArbitrumClientError::from_contract_error)?;
+    check_gas_estimate(estimate, ceiling)?;
+
+    Ok(estimate)
+}
+
+/// Builds a call approving `spender` to transfer `amount` of the given ERC-20
+/// token from the client's account
+pub fn build_erc20_approval<M: Middleware>(
+    token: Address,
+    spender: Address,
+    amount: U256,
+    client: Arc<M>,
+) -> ContractCall<M, bool> {
+    Erc20Contract::new(token, client).approve(spender, amount)
+}
+
+/// Checks a gas estimate against the configured ceiling
+pub fn check_gas_estimate(estimate: U256, ceiling: u64) -> Result<(), ArbitrumClientError> {
+    if estimate > U256::from(ceiling) {
+        return Err(ArbitrumClientError::GasLimitExceeded { estimate, ceiling });
+    }
+
+    Ok(())
+}
+
+/// Checks the contract's Merkle tree height against the compiled
+/// `MERKLE_HEIGHT`
+pub fn check_merkle_height(contract_height: usize) -> Result<(), ArbitrumClientError> {
+    if contract_height != MERKLE_HEIGHT {
+        return Err(ArbitrumClientError::MerkleHeightMismatch {
+            contract: contract_height,
+            compiled: MERKLE_HEIGHT,
+        });
+    }
+
+    Ok(())
+}
+
+/// Checks the contract's protocol fee recipient against the expected address
+pub fn check_fee_recipient(
+    contract: Address,
+    expected: Address,
+) -> Result<(), ArbitrumClientError> {
+    if contract != expected {
+        return Err(ArbitrumClientError::FeeRecipientMismatch { contract, expected });
+    }
+
+    Ok(())
+}
+
+/// Converts a value read from the contract to a scalar, rejecting values
+/// that would be reduced modulo the field order
+pub fn u256_to_canonical_scalar(value: &U256) -> Result<Scalar, ArbitrumClientError> {
+    u256_to_scalar_checked(value).ok_or(ArbitrumClientError::NonCanonicalScalar(*value))
+}
+
 /// Parses wallet shares from the calldata of a `newWallet` call
 pub fn parse_shares_from_new_wallet(
     calldata: &[u8],
@@ -183,3 +253,94 @@ pub fn parse_shares_from_redeem_fee(
 
     Ok(SizedWalletShare::from_scalars(&mut shares))
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use constants::MERKLE_HEIGHT;
+    use ethers::{
+        providers::Provider,
+        types::{Address, U256},
+    };
+
+    use crate::errors::ArbitrumClientError;
+
+    use super::{
+        build_erc20_approval, check_fee_recipient, check_gas_estimate, check_merkle_height,
+    };
+
+    /// The gas ceiling used in the tests
+    const CEILING: u64 = 1_000_000;
+
+    /// Tests that estimates at or below the ceiling are accepted
+    #[test]
+    fn test_gas_estimate_within_ceiling() {
+        assert!(check_gas_estimate(U256::from(CEILING / 2), CEILING).is_ok());
+        assert!(check_gas_estimate(U256::from(CEILING), CEILING).is_ok());
+    }
+
+    /// Tests that an estimate above the ceiling is rejected with a typed error
+    #[test]
+    fn test_gas_estimate_above_ceiling() {
+        let estimate = U256::from(CEILING + 1);
+        let err = check_gas_estimate(estimate, CEILING).unwrap_err();
+        assert!(matches!(
+            err,
+            ArbitrumClientError::GasLimitExceeded { estimate: e, ceiling: CEILING } if e == estimate
+        ));
+    }
+
+    /// Tests that an approval is encoded as a call to the ERC-20 `approve`
+    /// method
+    #[test]
+    fn test_erc20_approval_encoding() {
+        let (provider, _mock) = Provider::mocked();
+        let token = Address::random();
+        let spender = Address::random();
+        let amount = U256::from(1_000_000u64);
+
+        let call = build_erc20_approval(token, spender, amount, Arc::new(provider));
+        assert_eq!(call.tx.to_addr(), Some(&token));
+
+        // The selector of `approve(address,uint256)`, followed by the
+        // left-padded spender and the big-endian amount
+        let mut expected = vec![0x09, 0x5e, 0xa7, 0xb3];
+        expected.extend_from_slice(&[0u8; 12]);
+        expected.extend_from_slice(spender.as_bytes());
+        let mut amount_bytes = [0u8; 32];
+        amount.to_big_endian(&mut amount_bytes);
+        expected.extend_from_slice(&amount_bytes);
+
+        assert_eq!(call.calldata().unwrap().to_vec(), expected);
+    }
+
+    /// Tests that a contract tree height differing from the compiled height is
+    /// rejected
+    #[test]
+    fn test_merkle_height_mismatch() {
+        assert!(check_merkle_height(MERKLE_HEIGHT).is_ok());
+
+        let err = check_merkle_height(MERKLE_HEIGHT - 1).unwrap_err();
+        assert!(matches!(
+            err,
+            ArbitrumClientError::MerkleHeightMismatch { contract, compiled: MERKLE_HEIGHT }
+                if contract == MERKLE_HEIGHT - 1
+        ));
+    }
+
+    /// Tests that a contract fee recipient differing from the expected
+    /// address is rejected
+    #[test]
+    fn test_fee_recipient_mismatch() {
+        let (recipient, other) = (Address::random(), Address::random());
+        assert!(check_fee_recipient(recipient, recipient).is_ok());
+
+        let err = check_fee_recipient(other, recipient).unwrap_err();
+        assert!(matches!(
+            err,
+            ArbitrumClientError::FeeRecipientMismatch { contract, expected }
+                if contract == other && expected == recipient
+        ));
+    }
+}
@@ -17,3 +17,6 @@ pub mod constants;
 pub mod conversion;
 pub mod errors;
 pub mod helpers;
+#[cfg(feature = "test-helpers")]
+pub mod mock;
+pub mod traits;
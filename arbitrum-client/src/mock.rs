@@ -0,0 +1,264 @@
+//! A configurable mock of the darkpool client, for testing consumers of the
+//! `DarkpoolClient` trait without a live RPC
+
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use circuit_types::{merkle::MerkleRoot, wallet::Nullifier};
+use common::types::{
+    merkle::MerkleAuthenticationPath,
+    proof_bundles::{MatchBundle, OrderValidityProofBundle, SizedValidWalletUpdateBundle},
+    transfer_auth::TransferAuth,
+};
+use constants::Scalar;
+use ethers::types::{TransactionReceipt, TxHash};
+
+use crate::{
+    errors::ArbitrumClientError,
+    traits::{DarkpoolClient, MatchSettlement},
+};
+
+/// The state backing a mock darkpool client
+#[derive(Default)]
+struct MockDarkpoolState {
+    /// The nullifiers spent in the mock contract
+    spent_nullifiers: Vec<Nullifier>,
+    /// The Merkle roots rotated out of the mock contract's root history
+    expired_roots: Vec<MerkleRoot>,
+    /// The Merkle authentication paths indexed by the mock contract, keyed by
+    /// wallet commitment
+    merkle_paths: Vec<(Scalar, MerkleAuthenticationPath)>,
+    /// The block number at which submitted matches are included
+    match_block: u64,
+    /// The current block number of the mock chain, advanced by one each time
+    /// it is queried
+    current_block: u64,
+    /// The receipts of the settlement transactions included in the mock chain
+    receipts: Vec<TransactionReceipt>,
+    /// The number of settlement transactions submitted, used to derive
+    /// distinct transaction hashes
+    n_txs: u64,
+    /// The gas used by submitted matches
+    match_gas_used: u64,
+    /// Whether to omit the gas used from settlement receipts, as some nodes do
+    /// for pending or pruned transactions
+    omit_gas_used: bool,
+    /// An error to return from the next contract interaction, if set
+    next_error: Option<ArbitrumClientError>,
+    /// The number of wallet updates submitted to the mock
+    wallet_updates: usize,
+    /// The number of matches submitted to the mock
+    match_settlements: usize,
+    /// The number of batch settlement transactions submitted to the mock
+    batch_settlements: usize,
+    /// Whether the mock settles matches individually, as the darkpool
+    /// contract does, rather than in batches
+    batching_unsupported: bool,
+}
+
+/// A mock darkpool client returning deterministic, configurable responses
+///
+/// Clones share the same underlying state, so a test may keep a handle to
+/// configure and inspect the mock after handing it to the code under test
+#[derive(Clone, Default)]
+pub struct MockDarkpoolClient {
+    /// The shared mock state
+    state: Arc<Mutex<MockDarkpoolState>>,
+}
+
+impl MockDarkpoolClient {
+    /// Constructor
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // -----------------
+    // | Configuration |
+    // -----------------
+
+    /// Mark a nullifier as spent
+    pub fn spend_nullifier(&self, nullifier: Nullifier) {
+        self.state.lock().unwrap().spent_nullifiers.push(nullifier);
+    }
+
+    /// Rotate a Merkle root out of the contract's root history, all other
+    /// roots are considered valid
+    pub fn expire_root(&self, root: MerkleRoot) {
+        self.state.lock().unwrap().expired_roots.push(root);
+    }
+
+    /// Set the Merkle authentication path returned for a commitment
+    pub fn set_merkle_path(&self, path: MerkleAuthenticationPath) {
+        let mut state = self.state.lock().unwrap();
+        state.merkle_paths.retain(|(comm, _)| comm != &path.value);
+        state.merkle_paths.push((path.value, path));
+    }
+
+    /// Set the block number at which submitted matches are included
+    pub fn set_match_block(&self, block: u64) {
+        self.state.lock().unwrap().match_block = block;
+    }
+
+    /// Set the current block number of the mock chain
+    pub fn set_current_block(&self, block: u64) {
+        self.state.lock().unwrap().current_block = block;
+    }
+
+    /// Drop the receipts of all submitted transactions from the mock chain, as
+    /// a reorg would
+    pub fn reorg(&self) {
+        self.state.lock().unwrap().receipts.clear();
+    }
+
+    /// Set the gas used by submitted matches
+    pub fn set_match_gas_used(&self, gas_used: u64) {
+        self.state.lock().unwrap().match_gas_used = gas_used;
+    }
+
+    /// Omit the gas used from the receipts of submitted matches
+    pub fn omit_gas_used(&self) {
+        self.state.lock().unwrap().omit_gas_used = true;
+    }
+
+    /// Report that the mock does not support batch settlement
+    pub fn disable_batch_settlement(&self) {
+        self.state.lock().unwrap().batching_unsupported = true;
+    }
+
+    /// Fail the next contract interaction with the given error
+    pub fn fail_next(&self, err: ArbitrumClientError) {
+        self.state.lock().unwrap().next_error = Some(err);
+    }
+
+    // -----------
+    // | Getters |
+    // -----------
+
+    /// The number of wallet updates submitted to the mock
+    pub fn n_wallet_updates(&self) -> usize {
+        self.state.lock().unwrap().wallet_updates
+    }
+
+    /// The number of matches submitted to the mock
+    pub fn n_match_settlements(&self) -> usize {
+        self.state.lock().unwrap().match_settlements
+    }
+
+    /// The number of batch settlement transactions submitted to the mock
+    pub fn n_batch_settlements(&self) -> usize {
+        self.state.lock().unwrap().batch_settlements
+    }
+
+    /// Include a settlement transaction in the mock chain, returning its
+    /// receipt
+    fn include_settlement(state: &mut MockDarkpoolState) -> TransactionReceipt {
+        state.n_txs += 1;
+        let receipt = TransactionReceipt {
+            transaction_hash: TxHash::from_low_u64_be(state.n_txs),
+            block_number: Some(state.match_block.into()),
+            gas_used: (!state.omit_gas_used).then(|| state.match_gas_used.into()),
+            ..Default::default()
+        };
+
+        state.receipts.push(receipt.clone());
+        receipt
+    }
+
+    /// Take the configured error for the next interaction, if one is set
+    fn take_error(&self) -> Result<(), ArbitrumClientError> {
+        match self.state.lock().unwrap().next_error.take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl DarkpoolClient for MockDarkpoolClient {
+    async fn check_nullifier_used(
+        &self,
+        nullifier: Nullifier,
+    ) -> Result<bool, ArbitrumClientError> {
+        self.take_error()?;
+        Ok(self.state.lock().unwrap().spent_nullifiers.contains(&nullifier))
+    }
+
+    async fn check_merkle_root_valid(&self, root: MerkleRoot) -> Result<bool, ArbitrumClientError> {
+        self.take_error()?;
+        Ok(!self.state.lock().unwrap().expired_roots.contains(&root))
+    }
+
+    async fn find_merkle_authentication_path(
+        &self,
+        commitment: Scalar,
+    ) -> Result<MerkleAuthenticationPath, ArbitrumClientError> {
+        self.take_error()?;
+        let state = self.state.lock().unwrap();
+        state
+            .merkle_paths
+            .iter()
+            .find_map(|(comm, path)| (comm == &commitment).then(|| path.clone()))
+            .ok_or(ArbitrumClientError::CommitmentNotFound)
+    }
+
+    async fn current_block(&self) -> Result<u64, ArbitrumClientError> {
+        self.take_error()?;
+        let mut state = self.state.lock().unwrap();
+        let block = state.current_block;
+        state.current_block += 1;
+        Ok(block)
+    }
+
+    async fn get_transaction_receipt(
+        &self,
+        tx_hash: TxHash,
+    ) -> Result<Option<TransactionReceipt>, ArbitrumClientError> {
+        self.take_error()?;
+        let state = self.state.lock().unwrap();
+        Ok(state.receipts.iter().find(|r| r.transaction_hash == tx_hash).cloned())
+    }
+
+    async fn update_wallet(
+        &self,
+        _valid_wallet_update: &SizedValidWalletUpdateBundle,
+        _wallet_commitment_signature: Vec<u8>,
+        _transfer_auth: Option<TransferAuth>,
+    ) -> Result<(), ArbitrumClientError> {
+        self.take_error()?;
+        self.state.lock().unwrap().wallet_updates += 1;
+        Ok(())
+    }
+
+    async fn process_match_settle(
+        &self,
+        _party0_validity_proofs: &OrderValidityProofBundle,
+        _party1_validity_proofs: &OrderValidityProofBundle,
+        _match_bundle: &MatchBundle,
+    ) -> Result<TransactionReceipt, ArbitrumClientError> {
+        self.take_error()?;
+        let mut state = self.state.lock().unwrap();
+        state.match_settlements += 1;
+        Ok(Self::include_settlement(&mut state))
+    }
+
+    fn supports_batch_settlement(&self) -> bool {
+        !self.state.lock().unwrap().batching_unsupported
+    }
+
+    async fn batch_process_match_settle(
+        &self,
+        matches: &[MatchSettlement],
+    ) -> Vec<Result<TransactionReceipt, ArbitrumClientError>> {
+        // The mock settles a batch in a single transaction, so every match shares
+        // the outcome of the batch
+        let res = self.take_error();
+        let mut state = self.state.lock().unwrap();
+        state.batch_settlements += 1;
+        if res.is_ok() {
+            state.match_settlements += matches.len();
+        }
+
+        let receipt = res.map(|_| Self::include_settlement(&mut state));
+        vec![receipt; matches.len()]
+    }
+}
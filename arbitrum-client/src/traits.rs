@@ -0,0 +1,169 @@
+//! Defines a trait over the darkpool contract interactions used by the relayer
+//!
+//! Consumers that only need this surface may be written against the trait, so
+//! that they can be tested against a mock in place of a live RPC
+
+use async_trait::async_trait;
+use circuit_types::{merkle::MerkleRoot, wallet::Nullifier};
+use common::types::{
+    merkle::MerkleAuthenticationPath,
+    proof_bundles::{MatchBundle, OrderValidityProofBundle, SizedValidWalletUpdateBundle},
+    transfer_auth::TransferAuth,
+};
+use constants::Scalar;
+use ethers::types::{TransactionReceipt, TxHash};
+use futures::future::join_all;
+
+use crate::{client::ArbitrumClient, errors::ArbitrumClientError};
+
+/// Error message emitted when the RPC returns a block tag in place of a number
+const ERR_BLOCK_TAG: &str = "block number returned as a tag";
+
+/// A match to be settled on-chain, the arguments of `process_match_settle`
+#[derive(Clone, Debug)]
+pub struct MatchSettlement {
+    /// The validity proofs of the first party
+    pub party0_validity_proofs: OrderValidityProofBundle,
+    /// The validity proofs of the second party
+    pub party1_validity_proofs: OrderValidityProofBundle,
+    /// The proof of `VALID MATCH SETTLE` and its links to the parties' proofs
+    pub match_bundle: MatchBundle,
+}
+
+/// The contract-interaction surface of the darkpool
+#[async_trait]
+pub trait DarkpoolClient: Send + Sync {
+    /// Check whether the given nullifier is used
+    async fn check_nullifier_used(&self, nullifier: Nullifier)
+        -> Result<bool, ArbitrumClientError>;
+
+    /// Check whether the given Merkle root is within the contract's root
+    /// history
+    async fn check_merkle_root_valid(&self, root: MerkleRoot) -> Result<bool, ArbitrumClientError>;
+
+    /// Find the Merkle authentication path of the given wallet commitment
+    async fn find_merkle_authentication_path(
+        &self,
+        commitment: Scalar,
+    ) -> Result<MerkleAuthenticationPath, ArbitrumClientError>;
+
+    /// Get the current block number
+    async fn current_block(&self) -> Result<u64, ArbitrumClientError>;
+
+    /// Get the receipt of a transaction, `None` if the transaction is not
+    /// included in the canonical chain
+    async fn get_transaction_receipt(
+        &self,
+        tx_hash: TxHash,
+    ) -> Result<Option<TransactionReceipt>, ArbitrumClientError>;
+
+    /// Submit a wallet update with the given `VALID WALLET UPDATE` bundle
+    async fn update_wallet(
+        &self,
+        valid_wallet_update: &SizedValidWalletUpdateBundle,
+        wallet_commitment_signature: Vec<u8>,
+        transfer_auth: Option<TransferAuth>,
+    ) -> Result<(), ArbitrumClientError>;
+
+    /// Submit a match, returning the receipt of the settlement transaction
+    async fn process_match_settle(
+        &self,
+        party0_validity_proofs: &OrderValidityProofBundle,
+        party1_validity_proofs: &OrderValidityProofBundle,
+        match_bundle: &MatchBundle,
+    ) -> Result<TransactionReceipt, ArbitrumClientError>;
+
+    /// Whether the client settles a batch of matches in a single transaction
+    ///
+    /// The darkpool contract does not expose a batch settlement method, so by
+    /// default matches are not batched
+    fn supports_batch_settlement(&self) -> bool {
+        false
+    }
+
+    /// Submit a batch of matches, returning the receipt of the transaction
+    /// that settled each match
+    ///
+    /// By default each match is submitted individually, with the submissions
+    /// made concurrently
+    async fn batch_process_match_settle(
+        &self,
+        matches: &[MatchSettlement],
+    ) -> Vec<Result<TransactionReceipt, ArbitrumClientError>> {
+        let submissions = matches.iter().map(|settlement| {
+            self.process_match_settle(
+                &settlement.party0_validity_proofs,
+                &settlement.party1_validity_proofs,
+                &settlement.match_bundle,
+            )
+        });
+
+        join_all(submissions).await
+    }
+}
+
+#[async_trait]
+impl DarkpoolClient for ArbitrumClient {
+    async fn check_nullifier_used(
+        &self,
+        nullifier: Nullifier,
+    ) -> Result<bool, ArbitrumClientError> {
+        ArbitrumClient::check_nullifier_used(self, nullifier).await
+    }
+
+    async fn check_merkle_root_valid(&self, root: MerkleRoot) -> Result<bool, ArbitrumClientError> {
+        ArbitrumClient::check_merkle_root_valid(self, root).await
+    }
+
+    async fn find_merkle_authentication_path(
+        &self,
+        commitment: Scalar,
+    ) -> Result<MerkleAuthenticationPath, ArbitrumClientError> {
+        ArbitrumClient::find_merkle_authentication_path(self, commitment).await
+    }
+
+    async fn current_block(&self) -> Result<u64, ArbitrumClientError> {
+        let block = ArbitrumClient::block_number(self).await?;
+        block
+            .as_number()
+            .map(|n| n.as_u64())
+            .ok_or_else(|| ArbitrumClientError::Rpc(ERR_BLOCK_TAG.to_string()))
+    }
+
+    async fn get_transaction_receipt(
+        &self,
+        tx_hash: TxHash,
+    ) -> Result<Option<TransactionReceipt>, ArbitrumClientError> {
+        ArbitrumClient::get_transaction_receipt(self, tx_hash).await
+    }
+
+    async fn update_wallet(
+        &self,
+        valid_wallet_update: &SizedValidWalletUpdateBundle,
+        wallet_commitment_signature: Vec<u8>,
+        transfer_auth: Option<TransferAuth>,
+    ) -> Result<(), ArbitrumClientError> {
+        ArbitrumClient::update_wallet(
+            self,
+            valid_wallet_update,
+            wallet_commitment_signature,
+            transfer_auth,
+        )
+        .await
+    }
+
+    async fn process_match_settle(
+        &self,
+        party0_validity_proofs: &OrderValidityProofBundle,
+        party1_validity_proofs: &OrderValidityProofBundle,
+        match_bundle: &MatchBundle,
+    ) -> Result<TransactionReceipt, ArbitrumClientError> {
+        ArbitrumClient::process_match_settle(
+            self,
+            party0_validity_proofs,
+            party1_validity_proofs,
+            match_bundle,
+        )
+        .await
+    }
+}
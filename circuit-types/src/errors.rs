@@ -7,6 +7,7 @@ use std::{
 
 use mpc_plonk::errors::PlonkError;
 use mpc_relation::errors::CircuitError;
+use serde::Serialize;
 
 /// Represents an error during the course of an MPC circuit execution
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -56,6 +57,11 @@ impl Error for ProverError {}
 pub enum VerifierError {
     /// An error in plonk verification
     Plonk(PlonkError),
+    /// An error verifying a link between two proofs
+    Link(String),
+    /// A sub-proof of a proof bundle is invalid, holds the name of the
+    /// sub-proof and the error verifying it
+    InvalidSubProof(String, Box<VerifierError>),
 }
 
 impl Display for VerifierError {
@@ -65,6 +71,29 @@ impl Display for VerifierError {
 }
 impl Error for VerifierError {}
 
+/// Represents an error validating a match result against the orders and
+/// balances it settles, each variant holds the index of the offending party
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub enum MatchValidationError {
+    /// The match exceeds the party's send balance
+    ExceedsBalance(usize),
+    /// The match exceeds the party's order amount
+    ExceedsOrder(usize),
+    /// The match's mints differ from those of the party's order or balance
+    MintMismatch(usize),
+    /// The match's direction differs from the side of the party's order
+    SideMismatch(usize),
+    /// The match exchanges no base token
+    ZeroAmount,
+}
+
+impl Display for MatchValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{:?}", self)
+    }
+}
+impl Error for MatchValidationError {}
+
 /// Represents an error in converting to/from this package's types
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct TypeConversionError(pub(crate) String /* reason */);
@@ -217,6 +217,7 @@ pub mod native_helpers {
     use itertools::Itertools;
     use jf_primitives::elgamal::EncKey;
     use rand::thread_rng;
+    use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
     use renegade_crypto::hash::{compute_poseidon_hash, evaluate_hash_chain};
 
     use crate::{
@@ -294,7 +295,21 @@ pub mod native_helpers {
         share_commitment: WalletShareStateCommitment,
         wallet_blinder: Scalar,
     ) -> Nullifier {
-        compute_poseidon_hash(&[share_commitment, wallet_blinder])
+        Nullifier::new(compute_poseidon_hash(&[share_commitment, wallet_blinder]))
+    }
+
+    /// Compute the nullifiers of many sets of wallet shares, given the share
+    /// commitment and blinder of each
+    ///
+    /// The hashes are computed in parallel on the rayon thread pool, the
+    /// nullifiers are returned in the order of their inputs
+    pub fn compute_wallet_share_nullifiers_batch(
+        shares: &[(WalletShareStateCommitment, Scalar)],
+    ) -> Vec<Nullifier> {
+        shares
+            .par_iter()
+            .map(|(commitment, blinder)| compute_wallet_share_nullifier(*commitment, *blinder))
+            .collect()
     }
 
     /// Reblind a wallet given its secret shares
@@ -408,8 +423,8 @@ pub mod native_helpers {
     }
 
     /// Compute the nullifier for a note
-    pub fn note_nullifier(note_comm: Scalar, note_blinder: Scalar) -> Scalar {
-        compute_poseidon_hash(&[note_comm, note_blinder])
+    pub fn note_nullifier(note_comm: Scalar, note_blinder: Scalar) -> Nullifier {
+        Nullifier::new(compute_poseidon_hash(&[note_comm, note_blinder]))
     }
 
     // -------------------------
@@ -431,4 +446,30 @@ pub mod native_helpers {
 
         (cipher.into(), randomness)
     }
+
+    #[cfg(test)]
+    mod test {
+        use constants::Scalar;
+        use itertools::Itertools;
+        use rand::thread_rng;
+
+        use super::{compute_wallet_share_nullifier, compute_wallet_share_nullifiers_batch};
+
+        /// Tests that the batched nullifiers match those computed individually
+        #[test]
+        fn test_nullifier_batch() {
+            const N_SHARES: usize = 100;
+
+            let mut rng = thread_rng();
+            let shares = (0..N_SHARES)
+                .map(|_| (Scalar::random(&mut rng), Scalar::random(&mut rng)))
+                .collect_vec();
+
+            let expected = shares
+                .iter()
+                .map(|(commitment, blinder)| compute_wallet_share_nullifier(*commitment, *blinder))
+                .collect_vec();
+            assert_eq!(compute_wallet_share_nullifiers_batch(&shares), expected);
+        }
+    }
 }
@@ -8,7 +8,9 @@ use num_bigint::BigUint;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    order::OrderSide,
+    balance::Balance,
+    errors::MatchValidationError,
+    order::{Order, OrderSide},
     traits::{
         BaseType, CircuitBaseType, CircuitVarType, MpcBaseType, MpcType, MultiproverCircuitBaseType,
     },
@@ -64,6 +66,59 @@ impl MatchResult {
             OrderSide::Sell => (self.quote_mint.clone(), self.quote_amount),
         }
     }
+
+    /// Validate the match against the orders it settles and the balances each
+    /// party sends from
+    ///
+    /// A malformed match would otherwise only be caught when its settlement
+    /// proof fails
+    pub fn validate(
+        &self,
+        order0: &Order,
+        order1: &Order,
+        balance0: &Balance,
+        balance1: &Balance,
+    ) -> Result<(), MatchValidationError> {
+        if self.base_amount == 0 {
+            return Err(MatchValidationError::ZeroAmount);
+        }
+
+        let side0 = OrderSide::from(self.direction);
+        self.validate_party(0 /* party */, order0, balance0, side0)?;
+        self.validate_party(1 /* party */, order1, balance1, side0.opposite())
+    }
+
+    /// Validate the match against a single party's order and send balance
+    fn validate_party(
+        &self,
+        party: usize,
+        order: &Order,
+        balance: &Balance,
+        side: OrderSide,
+    ) -> Result<(), MatchValidationError> {
+        if order.base_mint != self.base_mint || order.quote_mint != self.quote_mint {
+            return Err(MatchValidationError::MintMismatch(party));
+        }
+
+        if order.side != side {
+            return Err(MatchValidationError::SideMismatch(party));
+        }
+
+        if self.base_amount > order.amount {
+            return Err(MatchValidationError::ExceedsOrder(party));
+        }
+
+        let (send_mint, send_amount) = self.send_mint_amount(side);
+        if balance.mint != send_mint {
+            return Err(MatchValidationError::MintMismatch(party));
+        }
+
+        if send_amount > balance.amount {
+            return Err(MatchValidationError::ExceedsBalance(party));
+        }
+
+        Ok(())
+    }
 }
 
 /// The fee takes from a match
@@ -104,3 +159,106 @@ pub struct OrderSettlementIndices {
     /// The index of the order that is to be matched
     pub order: usize,
 }
+
+#[cfg(test)]
+mod test {
+    use num_bigint::BigUint;
+
+    use crate::{
+        balance::Balance,
+        errors::MatchValidationError,
+        order::{Order, OrderSide},
+    };
+
+    use super::MatchResult;
+
+    /// The base mint used in tests
+    const BASE_MINT: u64 = 1;
+    /// The quote mint used in tests
+    const QUOTE_MINT: u64 = 2;
+
+    /// Build an order on the test pair
+    fn order(side: OrderSide, amount: u128) -> Order {
+        Order {
+            quote_mint: BigUint::from(QUOTE_MINT),
+            base_mint: BigUint::from(BASE_MINT),
+            side,
+            amount,
+            ..Default::default()
+        }
+    }
+
+    /// Build a balance of the given mint
+    fn balance(mint: u64, amount: u128) -> Balance {
+        Balance { mint: BigUint::from(mint), amount, ..Default::default() }
+    }
+
+    /// Build a match in which party 0 buys `base_amount` of the base for
+    /// `quote_amount` of the quote
+    fn match_result(base_amount: u128, quote_amount: u128) -> MatchResult {
+        MatchResult {
+            quote_mint: BigUint::from(QUOTE_MINT),
+            base_mint: BigUint::from(BASE_MINT),
+            quote_amount,
+            base_amount,
+            direction: false,
+            min_amount_order_index: false,
+        }
+    }
+
+    /// Validate a match against a buy order and a sell order of 10 base, each
+    /// with a balance of 100 in the mint they send
+    fn validate(match_res: &MatchResult) -> Result<(), MatchValidationError> {
+        let buy = order(OrderSide::Buy, 10);
+        let sell = order(OrderSide::Sell, 10);
+        let quote_balance = balance(QUOTE_MINT, 100);
+        let base_balance = balance(BASE_MINT, 100);
+
+        match_res.validate(&buy, &sell, &quote_balance, &base_balance)
+    }
+
+    /// Tests that a well formed match validates
+    #[test]
+    fn test_valid_match() {
+        assert_eq!(validate(&match_result(10, 50)), Ok(()));
+    }
+
+    /// Tests that malformed matches are rejected
+    #[test]
+    fn test_malformed_matches() {
+        // No base exchanged
+        assert_eq!(validate(&match_result(0, 0)), Err(MatchValidationError::ZeroAmount));
+
+        // Base amount exceeds both orders
+        assert_eq!(validate(&match_result(11, 50)), Err(MatchValidationError::ExceedsOrder(0)));
+
+        // Quote amount exceeds the buyer's balance
+        assert_eq!(validate(&match_result(10, 101)), Err(MatchValidationError::ExceedsBalance(0)));
+
+        // Direction reversed from the orders
+        let mut match_res = match_result(10, 50);
+        match_res.direction = true;
+        assert_eq!(validate(&match_res), Err(MatchValidationError::SideMismatch(0)));
+
+        // Mints differ from the orders
+        let mut match_res = match_result(10, 50);
+        match_res.base_mint = BigUint::from(QUOTE_MINT + 1);
+        assert_eq!(validate(&match_res), Err(MatchValidationError::MintMismatch(0)));
+    }
+
+    /// Tests that the counterparty's order and balance are checked
+    #[test]
+    fn test_counterparty_validated() {
+        let buy = order(OrderSide::Buy, 10);
+        let quote_balance = balance(QUOTE_MINT, 100);
+        let match_res = match_result(10, 50);
+
+        let small_sell = order(OrderSide::Sell, 5);
+        let res = match_res.validate(&buy, &small_sell, &quote_balance, &balance(BASE_MINT, 100));
+        assert_eq!(res, Err(MatchValidationError::ExceedsOrder(1)));
+
+        let sell = order(OrderSide::Sell, 10);
+        let res = match_res.validate(&buy, &sell, &quote_balance, &balance(BASE_MINT, 5));
+        assert_eq!(res, Err(MatchValidationError::ExceedsBalance(1)));
+    }
+}
@@ -2,6 +2,8 @@
 #![allow(clippy::missing_docs_in_private_items)]
 #![allow(missing_docs)]
 
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
 use circuit_macros::circuit_type;
 use constants::{Scalar, ScalarField};
 use mpc_relation::{traits::Circuit, Variable};
@@ -12,8 +14,76 @@ use crate::{
     traits::{BaseType, CircuitBaseType, CircuitVarType},
 };
 
-/// A type alias for readability
-pub type MerkleRoot = Scalar;
+/// The root of the global Merkle tree
+///
+/// Wrapped in a newtype so that roots may not be mistaken for nullifiers or
+/// other scalars; conversions to and from `Scalar` must be explicit
+///
+/// A root may not be passed where a nullifier is expected:
+/// ```compile_fail
+/// use circuit_types::{merkle::MerkleRoot, wallet::Nullifier};
+/// use constants::Scalar;
+///
+/// fn spend(_nullifier: Nullifier) {}
+/// spend(MerkleRoot::new(Scalar::zero()));
+/// ```
+///
+/// Nor used as a scalar without an explicit conversion:
+/// ```compile_fail
+/// use circuit_types::merkle::MerkleRoot;
+/// use constants::Scalar;
+///
+/// let _scalar: Scalar = MerkleRoot::new(Scalar::zero());
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct MerkleRoot(Scalar);
+
+impl MerkleRoot {
+    /// Constructor
+    pub fn new(root: Scalar) -> Self {
+        Self(root)
+    }
+
+    /// Get the underlying scalar
+    pub fn inner(&self) -> Scalar {
+        self.0
+    }
+}
+
+impl From<Scalar> for MerkleRoot {
+    fn from(root: Scalar) -> Self {
+        Self(root)
+    }
+}
+
+impl From<MerkleRoot> for Scalar {
+    fn from(root: MerkleRoot) -> Self {
+        root.0
+    }
+}
+
+impl Display for MerkleRoot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl BaseType for MerkleRoot {
+    const NUM_SCALARS: usize = 1;
+
+    fn to_scalars(&self) -> Vec<Scalar> {
+        vec![self.0]
+    }
+
+    fn from_scalars<I: Iterator<Item = Scalar>>(i: &mut I) -> Self {
+        Self(i.next().unwrap())
+    }
+}
+
+impl CircuitBaseType for MerkleRoot {
+    type VarType = Variable;
+}
 
 /// A fully specified merkle opening from hashed leaf to root
 #[circuit_type(serde, singleprover_circuit)]
@@ -36,3 +106,42 @@ impl<const HEIGHT: usize> Default for MerkleOpening<HEIGHT> {
         Self { elems: [Scalar::zero(); HEIGHT], indices: [false; HEIGHT] }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use constants::Scalar;
+    use rand::thread_rng;
+
+    use crate::{traits::BaseType, wallet::Nullifier};
+
+    use super::MerkleRoot;
+
+    /// Tests that roots and nullifiers convert explicitly to and from the
+    /// same scalar
+    ///
+    /// That the types may not be used interchangeably is checked by the
+    /// `compile_fail` examples on `MerkleRoot`
+    #[test]
+    fn test_root_nullifier_conversions() {
+        let scalar = Scalar::random(&mut thread_rng());
+        let root = MerkleRoot::new(scalar);
+        let nullifier = Nullifier::new(scalar);
+        assert_eq!(Scalar::from(root), Scalar::from(nullifier));
+        assert_eq!(root.to_scalars(), nullifier.to_scalars());
+    }
+
+    /// Tests that the wrappers serialize identically to the underlying scalar
+    #[test]
+    fn test_serde_transparent() {
+        let scalar = Scalar::random(&mut thread_rng());
+        let expected = serde_json::to_string(&scalar).unwrap();
+
+        let root_ser = serde_json::to_string(&MerkleRoot::new(scalar)).unwrap();
+        let nullifier_ser = serde_json::to_string(&Nullifier::new(scalar)).unwrap();
+        assert_eq!(root_ser, expected);
+        assert_eq!(nullifier_ser, expected);
+
+        let root: MerkleRoot = serde_json::from_str(&root_ser).unwrap();
+        assert_eq!(root.inner(), scalar);
+    }
+}
@@ -14,6 +14,7 @@ use serde::{Deserialize, Serialize};
 use crate::{
     elgamal::EncryptionKey,
     traits::{BaseType, CircuitBaseType, CircuitVarType},
+    wallet::Nullifier,
     Amount,
 };
 
@@ -54,9 +55,9 @@ impl Note {
     }
 
     /// Compute the nullifier for the note
-    pub fn nullifier(&self) -> Scalar {
+    pub fn nullifier(&self) -> Nullifier {
         let comm = self.commitment();
-        compute_poseidon_hash(&[comm, self.blinder])
+        Nullifier::new(compute_poseidon_hash(&[comm, self.blinder]))
     }
 
     /// Get the elements of the note that are encrypted when the note is created
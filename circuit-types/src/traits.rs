@@ -50,7 +50,7 @@ use std::{
 
 use crate::{
     errors::{MpcError, ProverError, VerifierError},
-    srs::SYSTEM_SRS,
+    srs::{MAX_SRS_DEGREE, SYSTEM_SRS},
     AuthenticatedBool, CollaborativePlonkProof, Fabric, MpcPlonkCircuit, MpcProofLinkingHint,
     PlonkCircuit, PlonkProof, ProofLinkingHint,
 };
@@ -762,7 +762,41 @@ pub fn setup_preprocessed_keys<C: SingleProverCircuit>(
         return (pk, vk);
     }
 
-    // Create a dummy circuit of correct topology to generate the keys
+    // Check that the system SRS is large enough for the circuit
+    let cs = build_dummy_circuit::<C>();
+    let srs_degree = srs_degree_for_circuit(&cs);
+    assert!(
+        srs_degree <= MAX_SRS_DEGREE,
+        "{name} requires an SRS of degree {srs_degree}, the system SRS supports {MAX_SRS_DEGREE}"
+    );
+
+    // Generate the keys and cache them
+    let (pk, vk) = PlonkKzgSnark::<SystemCurve>::preprocess(&SYSTEM_SRS, &cs).unwrap();
+    let pair = (Arc::new(pk), Arc::new(vk));
+
+    CIRCUIT_KEY_CACHE.write().unwrap().insert(C::name(), pair.clone());
+    pair
+}
+
+/// Compute the SRS degree required to preprocess a circuit
+///
+/// This is determined by the size of the circuit's constraint system, and so
+/// by the circuit's size parameters
+pub fn required_srs_degree<C: SingleProverCircuit>() -> usize {
+    srs_degree_for_circuit(&build_dummy_circuit::<C>())
+}
+
+/// Get the SRS degree required by a finalized constraint system
+///
+/// The constraint system is evaluated over a power of two sized domain, and
+/// the SRS must cover two additional powers for blinding
+fn srs_degree_for_circuit(cs: &PlonkCircuit) -> usize {
+    cs.num_gates().next_power_of_two() + 2
+}
+
+/// Create a dummy circuit of correct topology, e.g. to generate keys for the
+/// circuit
+fn build_dummy_circuit<C: SingleProverCircuit>() -> PlonkCircuit {
     // We use zero'd scalars here to give valid boolean types as well as scalar
     // types
     let mut scalars = iter::repeat(Scalar::zero());
@@ -782,12 +816,7 @@ pub fn setup_preprocessed_keys<C: SingleProverCircuit>(
     C::apply_constraints(witness_var, statement_var, &mut cs).unwrap();
     cs.finalize_for_arithmetization().unwrap();
 
-    // Generate the keys and cache them
-    let (pk, vk) = PlonkKzgSnark::<SystemCurve>::preprocess(&SYSTEM_SRS, &cs).unwrap();
-    let pair = (Arc::new(pk), Arc::new(vk));
-
-    CIRCUIT_KEY_CACHE.write().unwrap().insert(C::name(), pair.clone());
-    pair
+    cs
 }
 
 /// Defines the abstraction of a Circuit
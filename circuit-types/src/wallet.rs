@@ -2,7 +2,10 @@
 //! the wallet
 #![allow(missing_docs, clippy::missing_docs_in_private_items)]
 
-use std::ops::Add;
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    ops::Add,
+};
 
 use circuit_macros::circuit_type;
 use constants::{AuthenticatedScalar, Scalar, ScalarField};
@@ -30,8 +33,68 @@ use super::{
 pub type WalletShareStateCommitment = Scalar;
 /// Commitment type alias for readability
 pub type NoteCommitment = Scalar;
-/// Nullifier type alias for readability
-pub type Nullifier = Scalar;
+/// The nullifier of a wallet's shares or of a note
+///
+/// Wrapped in a newtype so that nullifiers may not be mistaken for Merkle
+/// roots or other scalars; conversions to and from `Scalar` must be explicit
+///
+/// A nullifier may not be passed where a Merkle root is expected:
+/// ```compile_fail
+/// use circuit_types::{merkle::MerkleRoot, wallet::Nullifier};
+/// use constants::Scalar;
+///
+/// fn check_root(_root: MerkleRoot) {}
+/// check_root(Nullifier::new(Scalar::zero()));
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Nullifier(Scalar);
+
+impl Nullifier {
+    /// Constructor
+    pub fn new(nullifier: Scalar) -> Self {
+        Self(nullifier)
+    }
+
+    /// Get the underlying scalar
+    pub fn inner(&self) -> Scalar {
+        self.0
+    }
+}
+
+impl From<Scalar> for Nullifier {
+    fn from(nullifier: Scalar) -> Self {
+        Self(nullifier)
+    }
+}
+
+impl From<Nullifier> for Scalar {
+    fn from(nullifier: Nullifier) -> Self {
+        nullifier.0
+    }
+}
+
+impl Display for Nullifier {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl BaseType for Nullifier {
+    const NUM_SCALARS: usize = 1;
+
+    fn to_scalars(&self) -> Vec<Scalar> {
+        vec![self.0]
+    }
+
+    fn from_scalars<I: Iterator<Item = Scalar>>(i: &mut I) -> Self {
+        Self(i.next().unwrap())
+    }
+}
+
+impl CircuitBaseType for Nullifier {
+    type VarType = Variable;
+}
 
 // --------------------
 // | Wallet Base Type |
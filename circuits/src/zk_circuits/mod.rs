@@ -11,6 +11,7 @@ pub mod valid_reblind;
 pub mod valid_relayer_fee_settlement;
 pub mod valid_wallet_create;
 pub mod valid_wallet_update;
+pub mod validity_bundle;
 
 use circuit_types::{
     traits::{
@@ -40,7 +41,19 @@ pub fn check_constraint_satisfaction<C: SingleProverCircuit>(
     witness: &C::Witness,
     statement: &C::Statement,
 ) -> bool {
-    // Apply the constraints
+    let cs = build_constrained_circuit::<C>(witness, statement);
+
+    // Check for satisfaction
+    let statement_scalars = statement.to_scalars().iter().map(Scalar::inner).collect_vec();
+    cs.check_circuit_satisfiability(&statement_scalars).is_ok()
+}
+
+/// Allocate a witness and statement in a new constraint system and apply a
+/// circuit's constraints to them
+fn build_constrained_circuit<C: SingleProverCircuit>(
+    witness: &C::Witness,
+    statement: &C::Statement,
+) -> PlonkCircuit {
     let mut cs = PlonkCircuit::new_turbo_plonk();
     let circuit_layout = C::get_circuit_layout().unwrap();
     for (id, layout) in circuit_layout.group_layouts.into_iter() {
@@ -51,10 +64,7 @@ pub fn check_constraint_satisfaction<C: SingleProverCircuit>(
     let statement_var = statement.create_public_var(&mut cs);
 
     C::apply_constraints(witness_var, statement_var, &mut cs).unwrap();
-
-    // Check for satisfaction
-    let statement_scalars = statement.to_scalars().iter().map(Scalar::inner).collect_vec();
-    cs.check_circuit_satisfiability(&statement_scalars).is_ok()
+    cs
 }
 
 /// Check whether a witness and statement satisfy wire assignments for a
@@ -91,13 +101,16 @@ pub mod test_helpers {
         keychain::{NonNativeScalar, PublicKeyChain, PublicSigningKey, NUM_KEYS},
         merkle::MerkleOpening,
         order::{Order, OrderSide},
+        traits::{BaseType, SingleProverCircuit},
         wallet::{Wallet, WalletShare},
+        PlonkCircuit,
     };
-    use constants::Scalar;
+    use constants::{Scalar, ScalarField};
     use itertools::Itertools;
     use lazy_static::lazy_static;
+    use mpc_relation::{errors::CircuitError, traits::Circuit};
     use num_bigint::BigUint;
-    use rand::thread_rng;
+    use rand::{rngs::StdRng, thread_rng, CryptoRng, RngCore, SeedableRng};
     use renegade_crypto::hash::compute_poseidon_hash;
 
     use circuit_types::native_helpers::create_wallet_shares_with_randomness;
@@ -174,27 +187,143 @@ pub mod test_helpers {
     pub type SizedWallet = Wallet<MAX_BALANCES, MAX_ORDERS>;
     pub type SizedWalletShare = WalletShare<MAX_BALANCES, MAX_ORDERS>;
 
+    // ------------------------
+    // | Constraint Debugging |
+    // ------------------------
+
+    /// The first constraint of a circuit left unsatisfied by an assignment
+    #[derive(Clone, Debug)]
+    pub struct UnsatisfiedConstraint {
+        /// The index of the unsatisfied gate in the constraint system
+        ///
+        /// `None` if the assignment failed a check other than a gate check,
+        /// e.g. a public input length mismatch
+        pub gate_index: Option<usize>,
+        /// The constraint system's description of the failure
+        pub details: String,
+    }
+
+    impl From<CircuitError> for UnsatisfiedConstraint {
+        fn from(err: CircuitError) -> Self {
+            match err {
+                CircuitError::GateCheckFailure(index, details) => {
+                    UnsatisfiedConstraint { gate_index: Some(index), details }
+                },
+                err => UnsatisfiedConstraint { gate_index: None, details: err.to_string() },
+            }
+        }
+    }
+
+    /// Find the first constraint of a circuit that a witness and statement
+    /// leave unsatisfied, if any
+    pub fn find_unsatisfied_constraint<C: SingleProverCircuit>(
+        witness: &C::Witness,
+        statement: &C::Statement,
+    ) -> Option<UnsatisfiedConstraint> {
+        let cs = super::build_constrained_circuit::<C>(witness, statement);
+        let statement_scalars = statement.to_scalars().iter().map(Scalar::inner).collect_vec();
+        first_unsatisfied_constraint(&cs, &statement_scalars)
+    }
+
+    /// Find the first constraint of a constraint system left unsatisfied by
+    /// its assignment and the given public inputs, if any
+    pub fn first_unsatisfied_constraint(
+        cs: &PlonkCircuit,
+        public_inputs: &[ScalarField],
+    ) -> Option<UnsatisfiedConstraint> {
+        cs.check_circuit_satisfiability(public_inputs).err().map(UnsatisfiedConstraint::from)
+    }
+
+    /// Assert that a witness and statement satisfy a circuit's constraints,
+    /// reporting the first unsatisfied constraint on failure
+    pub fn assert_constraints_satisfied<C: SingleProverCircuit>(
+        witness: &C::Witness,
+        statement: &C::Statement,
+    ) {
+        if let Some(failure) = find_unsatisfied_constraint::<C>(witness, statement) {
+            panic!("constraints not satisfied: {failure:?}");
+        }
+    }
+
     // -----------
     // | Helpers |
     // -----------
 
+    /// The seed of a randomized test case
+    ///
+    /// The seed is printed only if the test panics while it is held, so that a
+    /// failing run may be replayed by substituting the seed
+    pub struct TestSeed(pub u64);
+
+    impl TestSeed {
+        /// An rng seeded from the test seed
+        ///
+        /// Seeded helpers should be passed sub-seeds drawn from this rng rather
+        /// than the test seed itself, so that their randomness is independent
+        /// of the test's own
+        pub fn rng(&self) -> StdRng {
+            StdRng::seed_from_u64(self.0)
+        }
+    }
+
+    impl Drop for TestSeed {
+        fn drop(&mut self) {
+            if std::thread::panicking() {
+                eprintln!("test seed: {}", self.0);
+            }
+        }
+    }
+
+    /// Sample a seed for a randomized test case
+    pub fn random_test_seed() -> TestSeed {
+        TestSeed(thread_rng().next_u64())
+    }
+
     /// Construct secret shares of a wallet for testing
     pub fn create_wallet_shares<const MAX_BALANCES: usize, const MAX_ORDERS: usize>(
         wallet: &Wallet<MAX_BALANCES, MAX_ORDERS>,
     ) -> (WalletShare<MAX_BALANCES, MAX_ORDERS>, WalletShare<MAX_BALANCES, MAX_ORDERS>)
+    where
+        [(); MAX_BALANCES + MAX_ORDERS]: Sized,
+    {
+        create_wallet_shares_with_rng(wallet, &mut thread_rng())
+    }
+
+    /// Construct secret shares of a wallet deterministically from a seed
+    ///
+    /// The same wallet and seed always yield the same shares
+    pub fn create_wallet_shares_seeded<const MAX_BALANCES: usize, const MAX_ORDERS: usize>(
+        wallet: &Wallet<MAX_BALANCES, MAX_ORDERS>,
+        seed: u64,
+    ) -> (WalletShare<MAX_BALANCES, MAX_ORDERS>, WalletShare<MAX_BALANCES, MAX_ORDERS>)
+    where
+        [(); MAX_BALANCES + MAX_ORDERS]: Sized,
+    {
+        create_wallet_shares_with_rng(wallet, &mut StdRng::seed_from_u64(seed))
+    }
+
+    /// Construct secret shares of a wallet, sampling the shares from the
+    /// given rng
+    pub fn create_wallet_shares_with_rng<
+        R: RngCore + CryptoRng,
+        const MAX_BALANCES: usize,
+        const MAX_ORDERS: usize,
+    >(
+        wallet: &Wallet<MAX_BALANCES, MAX_ORDERS>,
+        rng: &mut R,
+    ) -> (WalletShare<MAX_BALANCES, MAX_ORDERS>, WalletShare<MAX_BALANCES, MAX_ORDERS>)
     where
         [(); MAX_BALANCES + MAX_ORDERS]: Sized,
     {
         // Sample a random secret share for the blinder
-        let mut rng = thread_rng();
-        let blinder_share = Scalar::random(&mut rng);
+        let blinder_share = Scalar::random(rng);
 
         let blinder = wallet.blinder;
         create_wallet_shares_with_randomness(
             wallet,
             blinder,
             blinder_share,
-            from_fn(|| Some(Scalar::random(&mut rng))),
+            from_fn(|| Some(Scalar::random(rng))),
         )
     }
 
@@ -351,6 +480,30 @@ pub mod test_helpers {
         assert_eq!(openings[2].indices.to_vec(), expected_third_indices);
     }
 
+    /// Tests that the first unsatisfied gate of a constraint system is
+    /// reported
+    #[test]
+    fn test_first_unsatisfied_constraint() {
+        let mut cs = PlonkCircuit::new_turbo_plonk();
+        let one = cs.create_variable(Scalar::one().inner()).unwrap();
+        let two = cs.create_variable(Scalar::from(2u8).inner()).unwrap();
+
+        // Break a constraint between two satisfied constraints
+        cs.enforce_equal(one, one).unwrap();
+        let broken_gate = cs.num_gates();
+        cs.enforce_equal(one, two).unwrap();
+        cs.enforce_equal(two, two).unwrap();
+
+        let failure = first_unsatisfied_constraint(&cs, &[]).unwrap();
+        assert_eq!(failure.gate_index, Some(broken_gate));
+
+        // A satisfied constraint system reports no failure
+        let mut cs = PlonkCircuit::new_turbo_plonk();
+        let one = cs.create_variable(Scalar::one().inner()).unwrap();
+        cs.enforce_equal(one, one).unwrap();
+        assert!(first_unsatisfied_constraint(&cs, &[]).is_none());
+    }
+
     /// Verify that the wallet shares helper correctly splits and recombines
     #[test]
     fn test_split_wallet_into_shares() {
@@ -366,6 +519,24 @@ pub mod test_helpers {
         assert_eq!(wallet, recovered_wallet);
     }
 
+    /// Verify that shares generated from the same seed are identical, and
+    /// those from distinct seeds differ
+    #[test]
+    fn test_seeded_wallet_shares() {
+        let wallet = INITIAL_WALLET.clone();
+        let (private1, public1) = create_wallet_shares_seeded(&wallet, 42 /* seed */);
+        let (private2, public2) = create_wallet_shares_seeded(&wallet, 42 /* seed */);
+        assert_eq!(private1, private2);
+        assert_eq!(public1, public2);
+
+        let (private3, _) = create_wallet_shares_seeded(&wallet, 43 /* seed */);
+        assert_ne!(private1, private3);
+
+        // The seeded shares are a valid sharing of the wallet
+        let recovered_wallet = private1 + public1.unblind_shares(wallet.blinder);
+        assert_eq!(wallet, recovered_wallet);
+    }
+
     /// Verify that reblinding a wallet creates valid secret shares of the
     /// underlying wallet
     #[test]
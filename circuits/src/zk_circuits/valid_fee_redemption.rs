@@ -365,8 +365,8 @@ pub mod test_helpers {
 
         // Create the witness and statement
         let statement = ValidFeeRedemptionStatement {
-            wallet_root: root,
-            note_root: root,
+            wallet_root: root.into(),
+            note_root: root.into(),
             wallet_nullifier,
             note_nullifier,
             new_wallet_commitment,
@@ -507,7 +507,7 @@ mod test {
         let (root, opening) = create_multi_opening(&[note_comm]);
         let nullifier = note_nullifier(note_comm, note.blinder);
 
-        (root, opening[0].clone(), nullifier)
+        (root.into(), opening[0].clone(), nullifier)
     }
 
     // -----------------------
@@ -634,7 +634,7 @@ mod test {
         let (mut statement, witness) = create_witness_and_statement(&wallet, &note);
 
         // Modify the nullifier
-        statement.note_nullifier = Scalar::random(&mut rng);
+        statement.note_nullifier = Scalar::random(&mut rng).into();
 
         assert!(!check_constraints_satisfied(&witness, &statement));
     }
@@ -784,7 +784,7 @@ mod test {
         let nullifier = compute_wallet_share_nullifier(comm, wallet.blinder);
 
         witness.wallet_opening = opening[0].clone();
-        statement.wallet_root = root;
+        statement.wallet_root = root.into();
         statement.wallet_nullifier = nullifier;
 
         assert!(!check_constraints_satisfied(&witness, &statement));
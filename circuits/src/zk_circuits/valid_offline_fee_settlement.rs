@@ -470,7 +470,7 @@ pub mod test_helpers {
         };
 
         let statement = ValidOfflineFeeSettlementStatement {
-            merkle_root: root,
+            merkle_root: root.into(),
             nullifier,
             updated_wallet_commitment: new_wallet_commitment,
             updated_wallet_public_shares,
@@ -288,8 +288,10 @@ pub mod test_helpers {
         wallet::Wallet,
     };
 
+    use rand::{thread_rng, RngCore};
+
     use crate::zk_circuits::test_helpers::{
-        create_multi_opening, create_wallet_shares, MAX_BALANCES, MAX_ORDERS, PRIVATE_KEYS,
+        create_multi_opening, create_wallet_shares_seeded, MAX_BALANCES, MAX_ORDERS, PRIVATE_KEYS,
     };
 
     use super::{ValidReblindStatement, ValidReblindWitness};
@@ -309,11 +311,28 @@ pub mod test_helpers {
     >(
         wallet: &Wallet<MAX_BALANCES, MAX_ORDERS>,
     ) -> (ValidReblindWitness<MAX_BALANCES, MAX_ORDERS, MERKLE_HEIGHT>, ValidReblindStatement)
+    where
+        [(); MAX_BALANCES + MAX_ORDERS]: Sized,
+    {
+        construct_witness_statement_seeded(wallet, thread_rng().next_u64())
+    }
+
+    /// Construct a witness and statement for `VALID REBLIND` from a given
+    /// wallet, deriving the wallet's shares from the given seed
+    pub fn construct_witness_statement_seeded<
+        const MAX_BALANCES: usize,
+        const MAX_ORDERS: usize,
+        const MERKLE_HEIGHT: usize,
+    >(
+        wallet: &Wallet<MAX_BALANCES, MAX_ORDERS>,
+        seed: u64,
+    ) -> (ValidReblindWitness<MAX_BALANCES, MAX_ORDERS, MERKLE_HEIGHT>, ValidReblindStatement)
     where
         [(); MAX_BALANCES + MAX_ORDERS]: Sized,
     {
         // Build shares of the original wallet, then reblind it
-        let (old_wallet_private_shares, old_wallet_public_shares) = create_wallet_shares(wallet);
+        let (old_wallet_private_shares, old_wallet_public_shares) =
+            create_wallet_shares_seeded(wallet, seed);
         let (reblinded_private_shares, reblinded_public_shares) =
             reblind_wallet(&old_wallet_private_shares, wallet);
 
@@ -345,7 +364,7 @@ pub mod test_helpers {
         let statement = ValidReblindStatement {
             original_shares_nullifier,
             reblinded_private_share_commitment: new_private_commitment,
-            merkle_root,
+            merkle_root: merkle_root.into(),
         };
 
         (witness, statement)
@@ -360,12 +379,17 @@ mod test {
         traits::{BaseType, SecretShareType},
     };
     use constants::{Scalar, MERKLE_HEIGHT};
-    use rand::{thread_rng, Rng};
+    use rand::{Rng, RngCore};
 
     use crate::zk_circuits::{
         check_constraint_satisfaction,
-        test_helpers::{SizedWallet, SizedWalletShare, INITIAL_WALLET, MAX_BALANCES, MAX_ORDERS},
-        valid_reblind::test_helpers::construct_witness_statement,
+        test_helpers::{
+            assert_constraints_satisfied, find_unsatisfied_constraint, random_test_seed,
+            SizedWallet, SizedWalletShare, INITIAL_WALLET, MAX_BALANCES, MAX_ORDERS,
+        },
+        valid_reblind::test_helpers::{
+            construct_witness_statement, construct_witness_statement_seeded,
+        },
     };
 
     use super::ValidReblind;
@@ -409,7 +433,7 @@ mod test {
         let wallet = INITIAL_WALLET.clone();
         let (witness, statement) = construct_witness_statement(&wallet);
 
-        assert!(check_constraint_satisfaction::<SizedReblind>(&witness, &statement))
+        assert_constraints_satisfied::<SizedReblind>(&witness, &statement);
     }
 
     /// Tests an invalid reblinding, i.e. a secret share that was sampled
@@ -417,12 +441,14 @@ mod test {
     #[test]
     fn test_invalid_reblind__invalid_secret_share() {
         // Construct the witness and statement
+        let seed = random_test_seed();
+        let mut rng = seed.rng();
         let wallet = INITIAL_WALLET.clone();
-        let (mut witness, mut statement) = construct_witness_statement(&wallet);
+        let (mut witness, mut statement) =
+            construct_witness_statement_seeded(&wallet, rng.next_u64());
 
         // Choose a random index in the wallet and alter the secret shares such that it
         // remains a valid blinding, but an incorrectly sampled one
-        let mut rng = thread_rng();
         let mut private_shares_serialized: Vec<Scalar> =
             witness.reblinded_wallet_private_shares.to_scalars();
         let mut public_shares_serialized: Vec<Scalar> =
@@ -458,14 +484,16 @@ mod test {
     #[test]
     fn test_invalid_reblind__invalid_wallet_blinder() {
         // Construct the witness and statement
+        let seed = random_test_seed();
+        let mut rng = seed.rng();
         let wallet = INITIAL_WALLET.clone();
-        let (mut witness, mut statement) = construct_witness_statement(&wallet);
+        let (mut witness, mut statement) =
+            construct_witness_statement_seeded(&wallet, rng.next_u64());
 
         // Reblind the wallet with a new, incorrect blinder
         let recovered_blinder = witness.reblinded_wallet_private_shares.blinder
             + witness.reblinded_wallet_public_shares.blinder;
 
-        let mut rng = thread_rng();
         let new_blinder = Scalar::random(&mut rng);
         let new_blinder_private_share = Scalar::random(&mut rng);
 
@@ -496,11 +524,13 @@ mod test {
     #[test]
     fn test_invalid_reblind__wallet_private_value_modified() {
         // Construct the witness and statement
+        let seed = random_test_seed();
+        let mut rng = seed.rng();
         let wallet = INITIAL_WALLET.clone();
-        let (mut witness, mut statement) = construct_witness_statement(&wallet);
+        let (mut witness, mut statement) =
+            construct_witness_statement_seeded(&wallet, rng.next_u64());
 
         // Prover attempt to change a private share
-        let mut rng = thread_rng();
         let mut private_shares = witness.reblinded_wallet_private_shares.to_scalars();
         let random_index = rng.gen_range(0..private_shares.len());
         private_shares[random_index] += Scalar::one();
@@ -518,11 +548,13 @@ mod test {
     #[test]
     fn test_invalid_reblind__wallet_public_value_modified() {
         // Construct the witness and statement
+        let seed = random_test_seed();
+        let mut rng = seed.rng();
         let wallet = INITIAL_WALLET.clone();
-        let (mut witness, mut statement) = construct_witness_statement(&wallet);
+        let (mut witness, mut statement) =
+            construct_witness_statement_seeded(&wallet, rng.next_u64());
 
         // Prover attempts to change a public share
-        let mut rng = thread_rng();
         let mut public_shares = witness.reblinded_wallet_public_shares.to_scalars();
         let random_index = rng.gen_range(0..public_shares.len());
         public_shares[random_index] += Scalar::one();
@@ -560,17 +592,18 @@ mod test {
     #[test]
     fn test_invalid_merkle_opening() {
         // Construct the witness and statement
+        let seed = random_test_seed();
+        let mut rng = seed.rng();
         let wallet = INITIAL_WALLET.clone();
-        let (original_witness, original_statement) = construct_witness_statement(&wallet);
-
-        let mut rng = thread_rng();
+        let (original_witness, original_statement) =
+            construct_witness_statement_seeded(&wallet, rng.next_u64());
 
         // Invalid opening
         let mut witness = original_witness.clone();
         let statement = original_statement.clone();
 
         let random_index = rng.gen_range(0..witness.original_share_opening.elems.len());
-        witness.original_share_opening.elems[random_index] = Scalar::random(&mut thread_rng());
+        witness.original_share_opening.elems[random_index] = Scalar::random(&mut rng);
 
         assert!(!check_constraint_satisfaction::<SizedReblind>(&witness, &statement));
 
@@ -578,7 +611,7 @@ mod test {
         let witness = original_witness;
         let mut statement = original_statement;
 
-        statement.merkle_root = Scalar::random(&mut thread_rng());
+        statement.merkle_root = Scalar::random(&mut rng).into();
 
         assert!(!check_constraint_satisfaction::<SizedReblind>(&witness, &statement));
     }
@@ -587,15 +620,16 @@ mod test {
     #[test]
     fn test_invalid_nullifier() {
         // Construct the witness and statement
+        let seed = random_test_seed();
+        let mut rng = seed.rng();
         let wallet = INITIAL_WALLET.clone();
-        let (original_witness, original_statement) = construct_witness_statement(&wallet);
-
-        let mut rng = thread_rng();
+        let (original_witness, original_statement) =
+            construct_witness_statement_seeded(&wallet, rng.next_u64());
 
         // Invalid nullifier
         let witness = original_witness;
         let mut statement = original_statement;
-        statement.original_shares_nullifier = Scalar::random(&mut rng);
+        statement.original_shares_nullifier = Scalar::random(&mut rng).into();
 
         assert!(!check_constraint_satisfaction::<SizedReblind>(&witness, &statement));
     }
@@ -605,12 +639,17 @@ mod test {
     #[test]
     fn test_invalid_commitment() {
         // Construct the witness and statement
+        let seed = random_test_seed();
+        let mut rng = seed.rng();
         let wallet = INITIAL_WALLET.clone();
-        let (witness, mut statement) = construct_witness_statement(&wallet);
+        let (witness, mut statement) = construct_witness_statement_seeded(&wallet, rng.next_u64());
 
-        let mut rng = thread_rng();
         statement.reblinded_private_share_commitment = Scalar::random(&mut rng);
 
         assert!(!check_constraint_satisfaction::<SizedReblind>(&witness, &statement));
+
+        // The commitment is checked by a gate constraint
+        let failure = find_unsatisfied_constraint::<SizedReblind>(&witness, &statement).unwrap();
+        assert!(failure.gate_index.is_some());
     }
 }
@@ -529,8 +529,8 @@ pub mod test_helpers {
         };
 
         let statement = ValidRelayerFeeSettlementStatement {
-            sender_root: root,
-            recipient_root: root,
+            sender_root: root.into(),
+            recipient_root: root.into(),
             sender_wallet_commitment: compute_wallet_private_share_commitment(
                 &sender_updated_private_shares,
             ),
@@ -666,7 +666,7 @@ mod test {
         let (root, openings) = create_multi_opening(&[new_comm]);
         let nullifier = compute_wallet_share_nullifier(new_comm, recipient_wallet.blinder);
 
-        statement.recipient_root = root;
+        statement.recipient_root = root.into();
         statement.recipient_nullifier = nullifier;
         witness.recipient_opening = openings[0].clone();
 
@@ -945,7 +945,7 @@ mod test {
             witness.recipient_public_shares.blinder + witness.recipient_private_shares.blinder;
         let new_nullifier = compute_wallet_share_nullifier(new_comm, blinder);
 
-        statement.recipient_root = new_root;
+        statement.recipient_root = new_root.into();
         statement.recipient_nullifier = new_nullifier;
         witness.recipient_opening = opening[0].clone();
 
@@ -1033,7 +1033,7 @@ mod test {
         let blinder =
             witness.recipient_public_shares.blinder + witness.recipient_private_shares.blinder;
         let new_nullifier = compute_wallet_share_nullifier(new_comm, blinder);
-        statement.recipient_root = new_root;
+        statement.recipient_root = new_root.into();
         statement.recipient_nullifier = new_nullifier;
         witness.recipient_opening = opening[0].clone();
 
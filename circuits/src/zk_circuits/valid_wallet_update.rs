@@ -526,9 +526,10 @@ pub mod test_helpers {
         transfers::ExternalTransfer,
         wallet::Wallet,
     };
+    use rand::{rngs::StdRng, thread_rng, RngCore, SeedableRng};
 
     use crate::zk_circuits::test_helpers::{
-        create_multi_opening, create_wallet_shares, MAX_BALANCES, MAX_ORDERS,
+        create_multi_opening, create_wallet_shares_with_rng, MAX_BALANCES, MAX_ORDERS,
     };
 
     use super::{ValidWalletUpdateStatement, ValidWalletUpdateWitness};
@@ -559,14 +560,44 @@ pub mod test_helpers {
         ValidWalletUpdateWitness<MAX_BALANCES, MAX_ORDERS, MERKLE_HEIGHT>,
         ValidWalletUpdateStatement<MAX_BALANCES, MAX_ORDERS>,
     )
+    where
+        [(); MAX_BALANCES + MAX_ORDERS]: Sized,
+    {
+        let seed = thread_rng().next_u64();
+        construct_witness_statement_seeded(
+            old_wallet,
+            new_wallet,
+            transfer_index,
+            external_transfer,
+            seed,
+        )
+    }
+
+    /// Construct a witness and statement, deriving the shares of both wallets
+    /// from the given seed
+    pub fn construct_witness_statement_seeded<
+        const MAX_BALANCES: usize,
+        const MAX_ORDERS: usize,
+        const MERKLE_HEIGHT: usize,
+    >(
+        old_wallet: &Wallet<MAX_BALANCES, MAX_ORDERS>,
+        new_wallet: &Wallet<MAX_BALANCES, MAX_ORDERS>,
+        transfer_index: usize,
+        external_transfer: ExternalTransfer,
+        seed: u64,
+    ) -> (
+        ValidWalletUpdateWitness<MAX_BALANCES, MAX_ORDERS, MERKLE_HEIGHT>,
+        ValidWalletUpdateStatement<MAX_BALANCES, MAX_ORDERS>,
+    )
     where
         [(); MAX_BALANCES + MAX_ORDERS]: Sized,
     {
         // Construct secret shares of the wallets
+        let mut rng = StdRng::seed_from_u64(seed);
         let (old_wallet_private_shares, old_wallet_public_shares) =
-            create_wallet_shares(old_wallet);
+            create_wallet_shares_with_rng(old_wallet, &mut rng);
         let (new_wallet_private_shares, new_wallet_public_shares) =
-            create_wallet_shares(new_wallet);
+            create_wallet_shares_with_rng(new_wallet, &mut rng);
 
         // Create dummy openings for the old shares
         let old_shares_commitment =
@@ -595,7 +626,7 @@ pub mod test_helpers {
             old_pk_root: old_wallet.keys.pk_root.clone(),
             new_private_shares_commitment,
             new_public_shares: new_wallet_public_shares,
-            merkle_root,
+            merkle_root: merkle_root.into(),
             external_transfer,
         };
 
@@ -613,24 +644,30 @@ mod test {
         fixed_point::FixedPoint,
         native_helpers::compute_wallet_private_share_commitment,
         order::Order,
-        traits::CircuitBaseType,
+        srs::MAX_SRS_DEGREE,
+        traits::{required_srs_degree, CircuitBaseType},
         transfers::{ExternalTransfer, ExternalTransferDirection},
         AMOUNT_BITS, PRICE_BITS,
     };
     use constants::Scalar;
     use mpc_relation::{traits::Circuit, PlonkCircuit};
     use num_bigint::BigUint;
-    use rand::{thread_rng, Rng, RngCore};
+    use rand::{Rng, RngCore};
     use renegade_crypto::fields::scalar_to_u128;
 
     use crate::zk_circuits::{
         check_constraint_satisfaction,
-        test_helpers::{SizedWallet, INITIAL_WALLET, MAX_BALANCES, MAX_ORDERS},
+        test_helpers::{
+            assert_constraints_satisfied, random_test_seed, SizedWallet, INITIAL_WALLET,
+            MAX_BALANCES, MAX_ORDERS,
+        },
     };
 
     use super::{
-        test_helpers::{construct_witness_statement, MERKLE_HEIGHT},
-        ValidWalletUpdate,
+        test_helpers::{
+            construct_witness_statement, construct_witness_statement_seeded, MERKLE_HEIGHT,
+        },
+        SizedValidWalletUpdate, ValidWalletUpdate,
     };
 
     /// A marker constant for no transfer on the index
@@ -651,6 +688,42 @@ mod test {
         )
     }
 
+    /// Returns true if the circuit constraints are satisfied on the given
+    /// parameters, with the wallets' shares derived from the given seed
+    fn constraints_satisfied_on_wallets_seeded(
+        old_wallet: &SizedWallet,
+        new_wallet: &SizedWallet,
+        transfer_index: usize,
+        transfer: ExternalTransfer,
+        seed: u64,
+    ) -> bool {
+        let (witness, statement) = construct_witness_statement_seeded(
+            old_wallet,
+            new_wallet,
+            transfer_index,
+            transfer,
+            seed,
+        );
+        check_constraint_satisfaction::<ValidWalletUpdate<MAX_BALANCES, MAX_ORDERS, MERKLE_HEIGHT>>(
+            &witness, &statement,
+        )
+    }
+
+    /// Asserts that the circuit constraints are satisfied on the given
+    /// parameters, reporting the first unsatisfied constraint otherwise
+    fn assert_satisfied_on_wallets(
+        old_wallet: &SizedWallet,
+        new_wallet: &SizedWallet,
+        transfer_index: usize,
+        transfer: ExternalTransfer,
+    ) {
+        let (witness, statement) =
+            construct_witness_statement(old_wallet, new_wallet, transfer_index, transfer);
+        assert_constraints_satisfied::<ValidWalletUpdate<MAX_BALANCES, MAX_ORDERS, MERKLE_HEIGHT>>(
+            &witness, &statement,
+        );
+    }
+
     /// Get the scalar representation of the maximum allowable amount
     fn max_amount_scalar() -> Scalar {
         Scalar::from(2u8).pow(AMOUNT_BITS as u64) - Scalar::one()
@@ -662,6 +735,22 @@ mod test {
         FixedPoint { repr }
     }
 
+    // ------------
+    // | SRS Size |
+    // ------------
+
+    /// Tests that a larger wallet size requires a larger SRS, and that the
+    /// system sized circuit fits in the system SRS
+    #[test]
+    fn test_required_srs_degree() {
+        let small =
+            required_srs_degree::<ValidWalletUpdate<MAX_BALANCES, MAX_ORDERS, MERKLE_HEIGHT>>();
+        let large = required_srs_degree::<SizedValidWalletUpdate>();
+
+        assert!(large > small);
+        assert!(large <= MAX_SRS_DEGREE);
+    }
+
     // ----------
     // | Orders |
     // ----------
@@ -675,12 +764,12 @@ mod test {
         // Remove an order from the initial wallet
         old_wallet.orders[0] = Order::default();
 
-        assert!(constraints_satisfied_on_wallets(
+        assert_satisfied_on_wallets(
             &old_wallet,
             &new_wallet,
             NO_TRANSFER,
-            ExternalTransfer::default()
-        ));
+            ExternalTransfer::default(),
+        );
     }
 
     /// Tests order cancellation
@@ -690,12 +779,12 @@ mod test {
         let mut new_wallet = INITIAL_WALLET.clone();
         new_wallet.orders[0] = Order::default();
 
-        assert!(constraints_satisfied_on_wallets(
+        assert_satisfied_on_wallets(
             &old_wallet,
             &new_wallet,
             NO_TRANSFER,
-            ExternalTransfer::default()
-        ))
+            ExternalTransfer::default(),
+        );
     }
 
     /// Tests multiple valid order updates at once
@@ -708,12 +797,12 @@ mod test {
         old_wallet.orders[0] = Order::default();
         new_wallet.orders[1] = Order::default();
 
-        assert!(constraints_satisfied_on_wallets(
+        assert_satisfied_on_wallets(
             &old_wallet,
             &new_wallet,
             NO_TRANSFER,
-            ExternalTransfer::default()
-        ))
+            ExternalTransfer::default(),
+        );
     }
 
     /// Tests the case in which an order is placed with an amount that is too
@@ -809,7 +898,8 @@ mod test {
     /// price
     #[test]
     fn test_invalid_worst_case_price() {
-        let mut rng = thread_rng();
+        let seed = random_test_seed();
+        let mut rng = seed.rng();
         let old_wallet = INITIAL_WALLET.clone();
         let mut new_wallet = INITIAL_WALLET.clone();
 
@@ -820,11 +910,12 @@ mod test {
 
         // Construct a statement and witness then modify the worst case price of the
         // first order to be too large
-        let (witness, statement) = construct_witness_statement(
+        let (witness, statement) = construct_witness_statement_seeded(
             &old_wallet,
             &new_wallet,
             NO_TRANSFER,
             ExternalTransfer::default(),
+            rng.next_u64(),
         );
 
         let res = check_constraint_satisfaction::<
@@ -1005,7 +1096,7 @@ mod test {
             account_addr: BigUint::from(0u8),
         };
 
-        assert!(constraints_satisfied_on_wallets(&old_wallet, &new_wallet, idx, transfer));
+        assert_satisfied_on_wallets(&old_wallet, &new_wallet, idx, transfer);
     }
 
     /// Tests a valid external transfer that withdraws a partial balance
@@ -1029,7 +1120,7 @@ mod test {
             account_addr: BigUint::from(0u8),
         };
 
-        assert!(constraints_satisfied_on_wallets(&old_wallet, &new_wallet, idx, transfer));
+        assert_satisfied_on_wallets(&old_wallet, &new_wallet, idx, transfer);
     }
 
     /// Tests an invalid withdrawal in which a balance is incorrectly updated
@@ -1090,7 +1181,8 @@ mod test {
         let new_wallet = INITIAL_WALLET.clone();
 
         // Withdraw a random balance
-        let mut rng = thread_rng();
+        let seed = random_test_seed();
+        let mut rng = seed.rng();
         let withdrawn_mint = BigUint::from(rng.next_u32());
         let withdrawn_amount = 1u128;
 
@@ -1103,7 +1195,13 @@ mod test {
             account_addr: BigUint::from(0u8),
         };
 
-        assert!(!constraints_satisfied_on_wallets(&old_wallet, &new_wallet, idx, transfer));
+        assert!(!constraints_satisfied_on_wallets_seeded(
+            &old_wallet,
+            &new_wallet,
+            idx,
+            transfer,
+            rng.next_u64()
+        ));
     }
 
     /// Tests an invalid withdrawal in which the prover adds an unrelated
@@ -1137,7 +1235,8 @@ mod test {
     #[test]
     fn test_invalid_withdrawal__non_zero_protocol_fee() {
         // Setup a wallet with outstanding fees
-        let mut rng = thread_rng();
+        let seed = random_test_seed();
+        let mut rng = seed.rng();
         let fee_idx = rng.gen_range(0..MAX_BALANCES);
 
         let mut old_wallet = INITIAL_WALLET.clone();
@@ -1155,7 +1254,13 @@ mod test {
             account_addr: BigUint::from(0u8),
         };
 
-        assert!(!constraints_satisfied_on_wallets(&old_wallet, &new_wallet, idx, transfer));
+        assert!(!constraints_satisfied_on_wallets_seeded(
+            &old_wallet,
+            &new_wallet,
+            idx,
+            transfer,
+            rng.next_u64()
+        ));
     }
 
     /// Try withdrawing from a balance with non-zero relayer fee -- this is
@@ -1163,7 +1268,8 @@ mod test {
     #[test]
     fn test_invalid_withdrawal__non_zero_relayer_fee() {
         // Setup a wallet with outstanding fees
-        let mut rng = thread_rng();
+        let seed = random_test_seed();
+        let mut rng = seed.rng();
         let fee_idx = rng.gen_range(0..MAX_BALANCES);
 
         let mut old_wallet = INITIAL_WALLET.clone();
@@ -1181,7 +1287,13 @@ mod test {
             account_addr: BigUint::from(0u8),
         };
 
-        assert!(!constraints_satisfied_on_wallets(&old_wallet, &new_wallet, idx, transfer));
+        assert!(!constraints_satisfied_on_wallets_seeded(
+            &old_wallet,
+            &new_wallet,
+            idx,
+            transfer,
+            rng.next_u64()
+        ));
     }
 
     // --- Deposits --- //
@@ -1207,7 +1319,7 @@ mod test {
             account_addr: BigUint::from(0u8),
         };
 
-        assert!(constraints_satisfied_on_wallets(&old_wallet, &new_wallet, idx, transfer));
+        assert_satisfied_on_wallets(&old_wallet, &new_wallet, idx, transfer);
     }
 
     /// Tests a valid deposit into the wallet that adds to an existing balance
@@ -1230,7 +1342,7 @@ mod test {
             account_addr: BigUint::from(0u8),
         };
 
-        assert!(constraints_satisfied_on_wallets(&old_wallet, &new_wallet, idx, transfer));
+        assert_satisfied_on_wallets(&old_wallet, &new_wallet, idx, transfer);
     }
 
     /// Tests an invalid deposit in which the balance is updated incorrectly
@@ -1303,7 +1415,7 @@ mod test {
             account_addr: BigUint::from(0u8),
         };
 
-        assert!(constraints_satisfied_on_wallets(&old_wallet, &new_wallet, idx, transfer));
+        assert_satisfied_on_wallets(&old_wallet, &new_wallet, idx, transfer);
     }
 
     // --- Mint Updates --- //
@@ -1312,7 +1424,8 @@ mod test {
     /// in a withdrawal
     #[test]
     fn test_replace_zero_balance() {
-        let mut rng = thread_rng();
+        let seed = random_test_seed();
+        let mut rng = seed.rng();
 
         // Setup the old wallet with a balance of zero amount and fees
         let mut old_wallet = INITIAL_WALLET.clone();
@@ -1332,7 +1445,16 @@ mod test {
             account_addr: BigUint::from(0u8),
         };
 
-        assert!(constraints_satisfied_on_wallets(&old_wallet, &new_wallet, idx, transfer));
+        let (witness, statement) = construct_witness_statement_seeded(
+            &old_wallet,
+            &new_wallet,
+            idx,
+            transfer,
+            rng.next_u64(),
+        );
+        assert_constraints_satisfied::<ValidWalletUpdate<MAX_BALANCES, MAX_ORDERS, MERKLE_HEIGHT>>(
+            &witness, &statement,
+        );
     }
 
     /// Tests the case in which a zero'd balance is replaced by a zero mint
@@ -1347,12 +1469,12 @@ mod test {
         // Replace it with an entirely zero'd balance
         new_wallet.balances[0] = Balance::default();
 
-        assert!(constraints_satisfied_on_wallets(
+        assert_satisfied_on_wallets(
             &old_wallet,
             &new_wallet,
             NO_TRANSFER,
-            ExternalTransfer::default()
-        ));
+            ExternalTransfer::default(),
+        );
     }
 
     // -------------------------------
@@ -1402,18 +1524,20 @@ mod test {
     /// This is invalid for now, we may allow the _user_ to do so in the future
     #[test]
     fn test_malicious_prover__change_cluster() {
-        let mut rng = thread_rng();
+        let seed = random_test_seed();
+        let mut rng = seed.rng();
         let old_wallet = INITIAL_WALLET.clone();
         let mut new_wallet = INITIAL_WALLET.clone();
 
         let (_, new_key) = DecryptionKey::random_pair(&mut rng);
         new_wallet.managing_cluster = new_key;
 
-        assert!(!constraints_satisfied_on_wallets(
+        assert!(!constraints_satisfied_on_wallets_seeded(
             &old_wallet,
             &new_wallet,
             NO_TRANSFER,
-            ExternalTransfer::default()
+            ExternalTransfer::default(),
+            rng.next_u64()
         ));
     }
 }
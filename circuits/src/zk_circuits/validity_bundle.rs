@@ -0,0 +1,240 @@
+//! Verification of the proofs that together attest to an order's validity
+//!
+//! An order's validity is proven by a proof of `VALID REBLIND`, a proof of
+//! `VALID COMMITMENTS`, and a link between the two. These helpers verify each
+//! sub-proof independently so that an auditor may identify which of the proofs
+//! in a disputed bundle fails
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use circuit_types::{errors::VerifierError, PlonkLinkProof, PlonkProof};
+use constants::{MAX_BALANCES, MAX_ORDERS, MERKLE_HEIGHT};
+
+use crate::verify_singleprover_proof;
+
+use super::{
+    proof_linking::validate_commitments_reblind_link,
+    valid_commitments::{ValidCommitments, ValidCommitmentsStatement},
+    valid_reblind::{ValidReblind, ValidReblindStatement},
+};
+
+/// The sub-proofs of an order validity bundle
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValidityProof {
+    /// The proof of `VALID REBLIND` for the order's wallet
+    ValidReblind,
+    /// The proof of `VALID COMMITMENTS` for the order
+    ValidCommitments,
+    /// The link between the `VALID REBLIND` and `VALID COMMITMENTS` proofs
+    ReblindCommitmentsLink,
+}
+
+impl Display for ValidityProof {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let name = match self {
+            ValidityProof::ValidReblind => "VALID REBLIND",
+            ValidityProof::ValidCommitments => "VALID COMMITMENTS",
+            ValidityProof::ReblindCommitmentsLink => "VALID REBLIND <-> VALID COMMITMENTS link",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+/// Verify an order validity bundle using the system wide sizing constants,
+/// returning the result of each sub-proof
+pub fn verify_sized_validity_proofs(
+    reblind_statement: &ValidReblindStatement,
+    reblind_proof: &PlonkProof,
+    commitments_statement: &ValidCommitmentsStatement,
+    commitments_proof: &PlonkProof,
+    link_proof: &PlonkLinkProof,
+) -> Vec<(ValidityProof, Result<(), VerifierError>)> {
+    verify_validity_proofs::<MAX_BALANCES, MAX_ORDERS, MERKLE_HEIGHT>(
+        reblind_statement,
+        reblind_proof,
+        commitments_statement,
+        commitments_proof,
+        link_proof,
+    )
+}
+
+/// Verify each of the proofs in an order validity bundle, returning the result
+/// of each sub-proof
+pub fn verify_validity_proofs<
+    const MAX_BALANCES: usize,
+    const MAX_ORDERS: usize,
+    const MERKLE_HEIGHT: usize,
+>(
+    reblind_statement: &ValidReblindStatement,
+    reblind_proof: &PlonkProof,
+    commitments_statement: &ValidCommitmentsStatement,
+    commitments_proof: &PlonkProof,
+    link_proof: &PlonkLinkProof,
+) -> Vec<(ValidityProof, Result<(), VerifierError>)>
+where
+    [(); MAX_BALANCES + MAX_ORDERS]: Sized,
+{
+    let reblind_res = verify_singleprover_proof::<
+        ValidReblind<MAX_BALANCES, MAX_ORDERS, MERKLE_HEIGHT>,
+    >(reblind_statement.clone(), reblind_proof);
+    let commitments_res = verify_singleprover_proof::<ValidCommitments<MAX_BALANCES, MAX_ORDERS>>(
+        *commitments_statement,
+        commitments_proof,
+    );
+
+    // The link is verified against the proofs alone, so a failure here indicates
+    // that the two proofs were not generated over the same wallet shares
+    let link_res = validate_commitments_reblind_link::<MAX_BALANCES, MAX_ORDERS, MERKLE_HEIGHT>(
+        link_proof,
+        reblind_proof,
+        commitments_proof,
+    )
+    .map_err(|e| VerifierError::Link(e.to_string()));
+
+    vec![
+        (ValidityProof::ValidReblind, reblind_res),
+        (ValidityProof::ValidCommitments, commitments_res),
+        (ValidityProof::ReblindCommitmentsLink, link_res),
+    ]
+}
+
+/// Verify an order validity bundle, failing with the first invalid sub-proof
+pub fn verify_bundle<
+    const MAX_BALANCES: usize,
+    const MAX_ORDERS: usize,
+    const MERKLE_HEIGHT: usize,
+>(
+    reblind_statement: &ValidReblindStatement,
+    reblind_proof: &PlonkProof,
+    commitments_statement: &ValidCommitmentsStatement,
+    commitments_proof: &PlonkProof,
+    link_proof: &PlonkLinkProof,
+) -> Result<(), VerifierError>
+where
+    [(); MAX_BALANCES + MAX_ORDERS]: Sized,
+{
+    verify_validity_proofs::<MAX_BALANCES, MAX_ORDERS, MERKLE_HEIGHT>(
+        reblind_statement,
+        reblind_proof,
+        commitments_statement,
+        commitments_proof,
+        link_proof,
+    )
+    .into_iter()
+    .try_for_each(|(proof, res)| {
+        res.map_err(|e| VerifierError::InvalidSubProof(proof.to_string(), Box::new(e)))
+    })
+}
+
+#[cfg(all(test, feature = "large_tests"))]
+mod test {
+    use circuit_types::{errors::VerifierError, PlonkLinkProof, PlonkProof};
+    use constants::Scalar;
+
+    use crate::{
+        singleprover_prove_with_hint,
+        zk_circuits::{
+            proof_linking::link_commitments_reblind,
+            test_helpers::{INITIAL_WALLET, MAX_BALANCES, MAX_ORDERS},
+            valid_commitments::{
+                test_helpers::create_witness_and_statement_with_shares as commitments_witness_statement,
+                ValidCommitments, ValidCommitmentsStatement,
+            },
+            valid_reblind::{
+                test_helpers::construct_witness_statement as reblind_witness_statement,
+                ValidReblind, ValidReblindStatement,
+            },
+        },
+    };
+
+    use super::{verify_bundle, ValidityProof};
+
+    /// The Merkle height used for testing
+    const MERKLE_HEIGHT: usize = 3;
+    /// Valid reblind with testing sizing
+    type SizedValidReblind = ValidReblind<MAX_BALANCES, MAX_ORDERS, MERKLE_HEIGHT>;
+    /// Valid commitments with testing sizing
+    type SizedValidCommitments = ValidCommitments<MAX_BALANCES, MAX_ORDERS>;
+
+    /// The statements and proofs of a validity bundle
+    struct TestBundle {
+        /// The statement of `VALID REBLIND`
+        reblind_statement: ValidReblindStatement,
+        /// The proof of `VALID REBLIND`
+        reblind_proof: PlonkProof,
+        /// The statement of `VALID COMMITMENTS`
+        commitments_statement: ValidCommitmentsStatement,
+        /// The proof of `VALID COMMITMENTS`
+        commitments_proof: PlonkProof,
+        /// The link between the two proofs
+        link_proof: PlonkLinkProof,
+    }
+
+    impl TestBundle {
+        /// Verify the bundle
+        fn verify(&self) -> Result<(), VerifierError> {
+            verify_bundle::<MAX_BALANCES, MAX_ORDERS, MERKLE_HEIGHT>(
+                &self.reblind_statement,
+                &self.reblind_proof,
+                &self.commitments_statement,
+                &self.commitments_proof,
+                &self.link_proof,
+            )
+        }
+    }
+
+    /// Prove a valid bundle over the initial wallet
+    fn prove_bundle() -> TestBundle {
+        let mut wallet = INITIAL_WALLET.clone();
+        let (reblind_witness, reblind_statement) = reblind_witness_statement(&wallet);
+
+        let private_share = reblind_witness.reblinded_wallet_private_shares.clone();
+        let public_share = reblind_witness.reblinded_wallet_public_shares.clone();
+        wallet.blinder = public_share.blinder + private_share.blinder;
+        let (comm_witness, commitments_statement) =
+            commitments_witness_statement(&wallet, &public_share, &private_share);
+
+        let (reblind_proof, reblind_hint) = singleprover_prove_with_hint::<SizedValidReblind>(
+            reblind_witness,
+            reblind_statement.clone(),
+        )
+        .unwrap();
+        let (commitments_proof, comm_hint) = singleprover_prove_with_hint::<SizedValidCommitments>(
+            comm_witness,
+            commitments_statement,
+        )
+        .unwrap();
+        let link_proof = link_commitments_reblind::<MAX_BALANCES, MAX_ORDERS, MERKLE_HEIGHT>(
+            &reblind_hint,
+            &comm_hint,
+        )
+        .unwrap();
+
+        TestBundle {
+            reblind_statement,
+            reblind_proof,
+            commitments_statement,
+            commitments_proof,
+            link_proof,
+        }
+    }
+
+    /// Tests that a valid bundle verifies and that a tampered bundle fails with
+    /// the tampered sub-proof identified
+    #[test]
+    fn test_verify_bundle() {
+        let mut bundle = prove_bundle();
+        bundle.verify().unwrap();
+
+        // Tamper with the statement of VALID REBLIND
+        let tampered_root = bundle.reblind_statement.merkle_root.inner() + Scalar::one();
+        bundle.reblind_statement.merkle_root = tampered_root.into();
+        match bundle.verify() {
+            Err(VerifierError::InvalidSubProof(proof, _)) => {
+                assert_eq!(proof, ValidityProof::ValidReblind.to_string())
+            },
+            res => panic!("expected an invalid VALID REBLIND proof, got {res:?}"),
+        }
+    }
+}
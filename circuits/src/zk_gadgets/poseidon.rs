@@ -6,7 +6,8 @@ use constants::ScalarField;
 use itertools::Itertools;
 use mpc_relation::{constants::GATE_WIDTH, errors::CircuitError, traits::Circuit, Variable};
 use renegade_crypto::hash::{
-    CAPACITY, FULL_ROUND_CONSTANTS, PARTIAL_ROUND_CONSTANTS, RATE, R_F, R_P, WIDTH as SPONGE_WIDTH,
+    CsprngDomain, CAPACITY, FULL_ROUND_CONSTANTS, PARTIAL_ROUND_CONSTANTS, RATE, R_F, R_P,
+    WIDTH as SPONGE_WIDTH,
 };
 
 // -----------------
@@ -19,17 +20,35 @@ impl PoseidonCSPRNGGadget {
     /// Samples values from a chained Poseidon hash CSPRNG, seeded with the
     /// given input
     pub fn sample<C: Circuit<ScalarField>>(
+        seed: Variable,
+        num_vals: usize,
+        cs: &mut C,
+    ) -> Result<Vec<Variable>, CircuitError> {
+        Self::sample_in_domain(seed, num_vals, CsprngDomain::Legacy, cs)
+    }
+
+    /// Samples values from a chained Poseidon hash CSPRNG with the given
+    /// derivation, seeded with the given input
+    pub fn sample_in_domain<C: Circuit<ScalarField>>(
         mut seed: Variable,
         num_vals: usize,
+        domain: CsprngDomain,
         cs: &mut C,
     ) -> Result<Vec<Variable>, CircuitError> {
         let mut values = Vec::with_capacity(num_vals);
+        let tag = match domain {
+            CsprngDomain::Legacy => None,
+            CsprngDomain::Tagged(tag) => Some(cs.mul_constant(cs.one(), &ScalarField::from(tag))?),
+        };
 
         // Chained hash of the seed value
         let mut hasher = PoseidonHashGadget::new(cs.zero() /* zero_var */);
         for _ in 0..num_vals {
-            // Absorb the seed and then squeeze the next element
-            hasher.absorb(seed, cs)?;
+            // Absorb the domain tag if any and the seed, then squeeze the next element
+            match tag {
+                Some(tag) => hasher.batch_absorb(&[tag, seed], cs)?,
+                None => hasher.absorb(seed, cs)?,
+            }
             seed = hasher.squeeze(cs)?;
 
             values.push(seed);
@@ -321,9 +340,12 @@ mod test {
     use itertools::Itertools;
     use mpc_relation::traits::Circuit;
     use rand::thread_rng;
-    use renegade_crypto::hash::{compute_poseidon_hash, Poseidon2Sponge};
+    use renegade_crypto::hash::{
+        compute_poseidon_hash, evaluate_hash_chain, CsprngDomain, Poseidon2Sponge, PoseidonCSPRNG,
+        BLINDER_CSPRNG_DOMAIN, SHARE_CSPRNG_DOMAIN,
+    };
 
-    use crate::zk_gadgets::poseidon::PoseidonHashGadget;
+    use crate::zk_gadgets::poseidon::{PoseidonCSPRNGGadget, PoseidonHashGadget};
 
     /// Tests absorbing a series of elements into the hasher and comparing to
     /// the hasher in `renegade-crypto`
@@ -381,4 +403,44 @@ mod test {
         // Check that the constraints are satisfied
         assert!(cs.check_circuit_satisfiability(&[]).is_ok());
     }
+
+    /// Tests that the CSPRNG gadget agrees with the native CSPRNG under each
+    /// derivation, that the legacy derivation is unchanged, and that the
+    /// derivations give distinct streams from the same seed
+    #[test]
+    fn test_csprng_domains() {
+        const N: usize = 5;
+        let mut rng = thread_rng();
+        let seed = Scalar::random(&mut rng);
+
+        // The legacy stream hashes only the seed of each sample
+        let legacy = CsprngDomain::Legacy;
+        let legacy_stream = PoseidonCSPRNG::new(seed).take(N).collect_vec();
+        assert_eq!(legacy_stream[0], compute_poseidon_hash(&[seed]));
+        assert_eq!(legacy_stream, evaluate_hash_chain(seed, N));
+
+        let blinder = CsprngDomain::Tagged(BLINDER_CSPRNG_DOMAIN);
+        let share = CsprngDomain::Tagged(SHARE_CSPRNG_DOMAIN);
+        let blinder_stream = PoseidonCSPRNG::new_in_domain(seed, blinder).take(N).collect_vec();
+        let share_stream = PoseidonCSPRNG::new_in_domain(seed, share).take(N).collect_vec();
+        assert_ne!(blinder_stream, share_stream);
+        assert_ne!(blinder_stream, legacy_stream);
+
+        // Sample each stream in-circuit and constrain it to the native stream
+        let mut cs = PlonkCircuit::new_turbo_plonk();
+        let seed_var = seed.create_witness(&mut cs);
+        for (domain, expected) in
+            [(legacy, legacy_stream), (blinder, blinder_stream), (share, share_stream)]
+        {
+            let samples =
+                PoseidonCSPRNGGadget::sample_in_domain(seed_var, N, domain, &mut cs).unwrap();
+            for (sample, expected_value) in samples.into_iter().zip(expected.into_iter()) {
+                let expected_var = expected_value.create_witness(&mut cs);
+                cs.enforce_equal(sample, expected_var).unwrap();
+            }
+        }
+
+        // Check that the constraints are satisfied
+        assert!(cs.check_circuit_satisfiability(&[]).is_ok());
+    }
 }
@@ -413,7 +413,7 @@ mod test {
         cs.enforce_equal(nullifier, expected_var).unwrap();
 
         // Verify that all constraints are satisfied
-        assert!(cs.check_circuit_satisfiability(&[expected.inner()]).is_ok())
+        assert!(cs.check_circuit_satisfiability(&[Scalar::from(expected).inner()]).is_ok())
     }
 
     /// Tests the amount gadget
@@ -175,6 +175,11 @@ impl<K: Eq, V: Clone> KeyedList<K, V> {
         self.elems.retain(|(k, v)| f(k, v));
     }
 
+    /// Shortens the map to its first `len` elements, dropping the rest
+    pub fn truncate(&mut self, len: usize) {
+        self.elems.truncate(len)
+    }
+
     /// Clears the map, removing all key-value pairs.
     pub fn clear(&mut self) {
         self.elems.clear()
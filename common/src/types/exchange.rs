@@ -10,6 +10,8 @@ use serde::{Deserialize, Serialize};
 use super::{token::Token, Price};
 
 /// List of all supported exchanges
+///
+/// Excludes the `Override` pseudo-exchange, which has no feed to connect to
 pub static ALL_EXCHANGES: &[Exchange] =
     &[Exchange::Binance, Exchange::Coinbase, Exchange::Kraken, Exchange::Okx, Exchange::UniswapV3];
 
@@ -22,6 +24,9 @@ pub enum Exchange {
     Kraken,
     Okx,
     UniswapV3,
+    /// A pseudo-exchange reporting a price configured by the operator, used for
+    /// pairs without a reliable exchange feed
+    Override,
 }
 
 impl Display for Exchange {
@@ -32,6 +37,7 @@ impl Display for Exchange {
             Exchange::Kraken => String::from("kraken"),
             Exchange::Okx => String::from("okx"),
             Exchange::UniswapV3 => String::from("uniswapv3"),
+            Exchange::Override => String::from("override"),
         };
         write!(f, "{}", fmt_str)
     }
@@ -47,6 +53,7 @@ impl FromStr for Exchange {
             "kraken" => Ok(Exchange::Kraken),
             "okx" => Ok(Exchange::Okx),
             "uniswapv3" | "uniswap" => Ok(Exchange::UniswapV3),
+            "override" => Ok(Exchange::Override),
             _ => Err(format!("Unknown exchange: {s}")),
         }
     }
@@ -70,6 +77,10 @@ pub struct PriceReport {
     pub local_timestamp: u64,
     /// The time that this update was generated by the exchange, if available.
     pub reported_timestamp: Option<u128>,
+    /// Whether the price is an exponential moving average of the median
+    /// rather than the raw median
+    #[serde(default)]
+    pub smoothed: bool,
 }
 
 /// The state of the PriceReporter. The Nominal state means that enough
@@ -326,4 +326,52 @@ mod types_test {
 
         assert_eq!(peer_info, deserialized)
     }
+
+    /// Build a peer's info signed with the given cluster keypair
+    fn signed_peer_info(cluster_keypair: &DalekKeypair) -> PeerInfo {
+        let cluster_id = ClusterId::new(&cluster_keypair.public);
+        PeerInfo::new_with_cluster_secret_key(
+            WrappedPeerId::random(),
+            cluster_id,
+            Multiaddr::empty(),
+            cluster_keypair,
+        )
+    }
+
+    /// Tests that a peer signed with its cluster's key verifies
+    #[test]
+    fn test_cluster_auth_sig_valid() {
+        let keypair = DalekKeypair::generate(&mut OsRng {});
+        let peer_info = signed_peer_info(&keypair);
+
+        assert!(peer_info.verify_cluster_auth_sig().is_ok());
+    }
+
+    /// Tests that a forged signature is rejected, both when signed by a key
+    /// outside the cluster and when the signature is moved to another peer
+    #[test]
+    fn test_cluster_auth_sig_invalid() {
+        let keypair = DalekKeypair::generate(&mut OsRng {});
+        let forger_keypair = DalekKeypair::generate(&mut OsRng {});
+
+        // A signature from a key other than the cluster's
+        let mut forged = signed_peer_info(&forger_keypair);
+        forged.cluster_id = ClusterId::new(&keypair.public);
+        assert!(forged.verify_cluster_auth_sig().is_err());
+
+        // A valid signature over a different peer ID
+        let mut replayed = signed_peer_info(&keypair);
+        replayed.peer_id = WrappedPeerId::random();
+        assert!(replayed.verify_cluster_auth_sig().is_err());
+    }
+
+    /// Tests that a peer without a signature is rejected
+    #[test]
+    fn test_cluster_auth_sig_missing() {
+        let keypair = DalekKeypair::generate(&mut OsRng {});
+        let mut peer_info = signed_peer_info(&keypair);
+        peer_info.cluster_auth_signature = Vec::new();
+
+        assert!(peer_info.verify_cluster_auth_sig().is_err());
+    }
 }
@@ -1,12 +1,16 @@
 //! Groups type definitions for handshake state objects used throughout the node
 
-use circuit_types::fixed_point::FixedPoint;
-use constants::Scalar;
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use circuit_types::{fixed_point::FixedPoint, wallet::Nullifier, Amount};
 use crossbeam::channel::Sender;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use super::wallet::OrderIdentifier;
+use super::{tasks::TaskIdentifier, token::Token, wallet::OrderIdentifier};
 
 /// The role in an MPC network setup; either Dialer or Listener depending on
 /// which node initiates the connection
@@ -31,6 +35,92 @@ impl ConnectionRole {
     }
 }
 
+/// The policy by which the internal matching engine selects the execution
+/// price of a match
+///
+/// The taker is the order the engine is run on, and the maker is the resting
+/// order it is matched against
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PriceSelection {
+    /// Execute at the midpoint price reported for the pair
+    #[default]
+    Midpoint,
+    /// Execute at the taker's limit price, the best price for the maker
+    MakerFavorable,
+    /// Execute at the maker's limit price, the best price for the taker
+    TakerFavorable,
+}
+
+impl Display for PriceSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fmt_str = match self {
+            PriceSelection::Midpoint => "midpoint",
+            PriceSelection::MakerFavorable => "maker-favorable",
+            PriceSelection::TakerFavorable => "taker-favorable",
+        };
+        write!(f, "{fmt_str}")
+    }
+}
+
+impl FromStr for PriceSelection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "midpoint" => Ok(PriceSelection::Midpoint),
+            "maker-favorable" => Ok(PriceSelection::MakerFavorable),
+            "taker-favorable" => Ok(PriceSelection::TakerFavorable),
+            _ => Err(format!("Unknown price selection policy: {s}")),
+        }
+    }
+}
+
+/// A receipt of a match settled by the local relayer
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatchReceipt {
+    /// The identifier of the settlement task
+    pub task_id: TaskIdentifier,
+    /// The identifier of the local order in the match
+    pub local_order_id: OrderIdentifier,
+    /// The identifier of the counterparty's order in the match
+    pub peer_order_id: OrderIdentifier,
+    /// The amount of the base token exchanged in the match
+    pub base_amount: Amount,
+    /// The amount of the quote token exchanged in the match
+    pub quote_amount: Amount,
+    /// The block in which the match was settled, `None` if the counterparty
+    /// submitted the match
+    pub settlement_block: Option<u64>,
+}
+
+/// The cumulative volume matched on a token pair
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PairMatchVolume {
+    /// The base token of the pair
+    pub base: Token,
+    /// The quote token of the pair
+    pub quote: Token,
+    /// The total amount of the base token exchanged in matches on the pair
+    pub base_volume: Amount,
+    /// The total amount of the quote token exchanged in matches on the pair
+    pub quote_volume: Amount,
+    /// The number of matches settled on the pair
+    pub num_matches: u64,
+}
+
+/// Counters of the match attempts made on a locally managed order
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderMatchStats {
+    /// The number of matches proposed on the order, by a peer or by the local
+    /// relayer
+    pub proposed: u64,
+    /// The number of match attempts on the order that failed
+    pub failed: u64,
+    /// The cumulative base amount filled on the order by completed matches
+    pub filled_amount: Amount,
+}
+
 /// The state of a given handshake execution
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct HandshakeState {
@@ -45,9 +135,9 @@ pub struct HandshakeState {
     /// The identifier of the order that the local peer has proposed for match
     pub local_order_id: OrderIdentifier,
     /// The public secret share nullifier of remote peer's order
-    pub peer_share_nullifier: Scalar,
+    pub peer_share_nullifier: Nullifier,
     /// The public secret share nullifier of the local peer's order
-    pub local_share_nullifier: Scalar,
+    pub local_share_nullifier: Nullifier,
     /// The agreed upon price of the asset the local party intends to match on
     pub execution_price: FixedPoint,
     /// The current state information of the
@@ -86,8 +176,8 @@ impl HandshakeState {
         role: ConnectionRole,
         peer_order_id: OrderIdentifier,
         local_order_id: OrderIdentifier,
-        peer_share_nullifier: Scalar,
-        local_share_nullifier: Scalar,
+        peer_share_nullifier: Nullifier,
+        local_share_nullifier: Nullifier,
         execution_price: FixedPoint,
     ) -> Self {
         Self {
@@ -150,8 +240,8 @@ pub mod mocks {
             role: ConnectionRole::Dialer,
             peer_order_id: Uuid::new_v4(),
             local_order_id: Uuid::new_v4(),
-            peer_share_nullifier: Scalar::random(&mut rng),
-            local_share_nullifier: Scalar::random(&mut rng),
+            peer_share_nullifier: Scalar::random(&mut rng).into(),
+            local_share_nullifier: Scalar::random(&mut rng).into(),
             execution_price: FixedPoint::from_f64_round_down(10.),
             state: State::Completed,
             cancel_channel: None,
@@ -1,6 +1,9 @@
 //! Defines types related to Merkle trees within the system
 
-use circuit_types::{merkle::MerkleOpening, SizedMerkleOpening};
+use circuit_types::{
+    merkle::{MerkleOpening, MerkleRoot},
+    SizedMerkleOpening,
+};
 use constants::Scalar;
 use constants::MERKLE_HEIGHT;
 use itertools::Itertools;
@@ -96,7 +99,7 @@ impl MerkleAuthenticationPath {
     }
 
     /// Compute the root implied by the path
-    pub fn compute_root(&self) -> Scalar {
+    pub fn compute_root(&self) -> MerkleRoot {
         let mut current_index = self.leaf_index.clone();
         let mut current_value = self.value;
 
@@ -110,7 +113,7 @@ impl MerkleAuthenticationPath {
             current_index >>= 1;
         }
 
-        current_value
+        MerkleRoot::new(current_value)
     }
 }
 
@@ -3,7 +3,11 @@
 
 use std::fmt::{Display, Formatter, Result as FmtResult};
 
-use circuit_types::wallet::Nullifier;
+use circuit_types::{
+    order::{Order, OrderSide},
+    wallet::Nullifier,
+    Amount,
+};
 use serde::{Deserialize, Serialize};
 use util::get_current_time_seconds;
 
@@ -11,6 +15,7 @@ use super::{
     gossip::ClusterId,
     proof_bundles::{OrderValidityProofBundle, OrderValidityWitnessBundle},
     wallet::OrderIdentifier,
+    Price,
 };
 
 /// The state of a known order in the network
@@ -39,6 +44,20 @@ pub enum NetworkOrderState {
     Cancelled,
 }
 
+/// The reason a locally managed order was cancelled
+///
+/// The reason is set by the site that removes the order from its wallet
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CancellationReason {
+    /// The order was cancelled by its owner
+    UserCancelled,
+    /// The order outlived the relayer's configured order TTL
+    Expired,
+    /// The balance funding the order was withdrawn
+    InsufficientBalance,
+}
+
 /// Represents an order discovered either via gossip, or from within the local
 /// node's managed wallets
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -91,6 +110,15 @@ impl NetworkOrder {
         }
     }
 
+    /// Whether the order was received at or before `cutoff_ms`, in
+    /// milliseconds since the epoch
+    ///
+    /// The receipt timestamp is recorded in seconds, so the order is taken to
+    /// have been received at the end of that second
+    pub fn received_by(&self, cutoff_ms: u64) -> bool {
+        self.timestamp.saturating_mul(1000).saturating_add(999) <= cutoff_ms
+    }
+
     /// Whether the order is cancelled
     pub fn is_cancelled(&self) -> bool {
         self.state == NetworkOrderState::Cancelled
@@ -178,6 +206,66 @@ impl Display for NetworkOrderState {
     }
 }
 
+// ---------------
+// | Order Depth |
+// ---------------
+
+/// The aggregate amount of orders resting at a single price
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PriceLevel {
+    /// The price of the level, in units of quote per base
+    pub price: Price,
+    /// The total amount of the base token resting at this price
+    pub amount: Amount,
+}
+
+/// The depth of a book of orders on a single asset pair, aggregated by price
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct OrderBookDepth {
+    /// The buy side price levels, sorted from the highest price to the lowest
+    pub bids: Vec<PriceLevel>,
+    /// The sell side price levels, sorted from the lowest price to the highest
+    pub asks: Vec<PriceLevel>,
+}
+
+impl OrderBookDepth {
+    /// Aggregate a set of orders into price levels
+    ///
+    /// The caller is responsible for filtering the orders down to a single
+    /// asset pair
+    pub fn from_orders<'a>(orders: impl IntoIterator<Item = &'a Order>) -> Self {
+        let mut bids = Vec::new();
+        let mut asks = Vec::new();
+        for order in orders.into_iter().filter(|o| !o.is_zero()) {
+            let level = (order.worst_case_price.to_f64(), order.amount);
+            match order.side {
+                OrderSide::Buy => bids.push(level),
+                OrderSide::Sell => asks.push(level),
+            }
+        }
+
+        // Bids are sorted best (highest) price first, asks lowest price first
+        bids.sort_by(|a, b| b.0.total_cmp(&a.0));
+        asks.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        Self { bids: Self::merge_levels(bids), asks: Self::merge_levels(asks) }
+    }
+
+    /// Merge adjacent entries of a sorted list of (price, amount) pairs that
+    /// share a price
+    fn merge_levels(sorted: Vec<(Price, Amount)>) -> Vec<PriceLevel> {
+        let mut levels: Vec<PriceLevel> = Vec::new();
+        for (price, amount) in sorted {
+            match levels.last_mut() {
+                Some(level) if level.price == price => level.amount += amount,
+                _ => levels.push(PriceLevel { price, amount }),
+            }
+        }
+
+        levels
+    }
+}
+
 #[cfg(feature = "mocks")]
 pub mod test_helpers {
     //! Test helpers for creating dummy network orders
@@ -196,7 +284,7 @@ pub mod test_helpers {
         let mut rng = thread_rng();
         NetworkOrder {
             id: Uuid::new_v4(),
-            public_share_nullifier: Scalar::random(&mut rng),
+            public_share_nullifier: Scalar::random(&mut rng).into(),
             cluster: ClusterId::from_str("cluster").unwrap(),
             state: NetworkOrderState::Received,
             validity_proofs: None,
@@ -231,7 +319,7 @@ mod test {
         let mut rng = thread_rng();
         let order1 = NetworkOrder {
             id: Uuid::new_v4(),
-            public_share_nullifier: Scalar::random(&mut rng),
+            public_share_nullifier: Scalar::random(&mut rng).into(),
             local: true,
             cluster: ClusterId::from_str("cluster").unwrap(),
             state: NetworkOrderState::Cancelled,
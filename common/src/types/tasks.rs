@@ -15,6 +15,7 @@ use uuid::Uuid;
 use super::{
     gossip::WrappedPeerId,
     handshake::HandshakeState,
+    network_order::CancellationReason,
     proof_bundles::{MatchBundle, OrderValidityProofBundle, OrderValidityWitnessBundle},
     transfer_auth::ExternalTransferWithAuth,
     wallet::{KeyChain, OrderIdentifier, Wallet, WalletIdentifier},
@@ -22,6 +23,11 @@ use super::{
 
 /// The error message returned when a wallet's shares are invalid
 const INVALID_WALLET_SHARES: &str = "invalid wallet shares";
+/// Error message emitted when a wallet update does not carry the wallet's next
+/// nonce
+const ERR_STALE_UPDATE_NONCE: &str = "stale wallet update nonce";
+/// Error message emitted when an update nonce's signature does not verify
+const ERR_INVALID_NONCE_SIG: &str = "invalid update nonce sig";
 
 /// A type alias for the identifier underlying a task
 pub type TaskIdentifier = Uuid;
@@ -74,6 +80,49 @@ impl QueuedTaskState {
     }
 }
 
+/// The output of a completed task, reported to workers awaiting the task
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TaskOutput {
+    /// The output of a match settlement
+    SettleMatch {
+        /// The block in which the match was settled, `None` if the
+        /// counterparty submitted the match
+        settlement_block: Option<u64>,
+    },
+}
+
+/// Information about a task currently being run by the task driver
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RunningTaskInfo {
+    /// The ID of the task
+    pub id: TaskIdentifier,
+    /// The name of the task
+    pub name: String,
+    /// A description of the task's current state
+    pub state: String,
+    /// The wallets the task operates on
+    pub wallet_ids: Vec<WalletIdentifier>,
+    /// The time at which the task was started, in milliseconds since the unix
+    /// epoch
+    pub start_time: u64,
+}
+
+/// The gas used by a match settlement the local relayer submitted
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatchGasRecord {
+    /// The ID of the first party's order
+    pub party0_order_id: OrderIdentifier,
+    /// The ID of the second party's order
+    pub party1_order_id: OrderIdentifier,
+    /// The hash of the settlement transaction
+    pub tx_hash: String,
+    /// The gas used by the settlement transaction
+    pub gas_used: u64,
+    /// The time at which the settlement was recorded, in milliseconds since
+    /// the unix epoch
+    pub timestamp: u64,
+}
+
 /// A wrapper around the task descriptors
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[allow(clippy::large_enum_variant)]
@@ -118,6 +167,23 @@ impl TaskDescriptor {
         }
     }
 
+    /// Returns whether the task is requested by a client through the API,
+    /// rather than enqueued by the relayer to complete or maintain existing
+    /// work
+    pub fn is_client_requested(&self) -> bool {
+        match self {
+            TaskDescriptor::NewWallet(_)
+            | TaskDescriptor::LookupWallet(_)
+            | TaskDescriptor::UpdateWallet(_) => true,
+            TaskDescriptor::OfflineFee(_)
+            | TaskDescriptor::RelayerFee(_)
+            | TaskDescriptor::RedeemRelayerFee(_)
+            | TaskDescriptor::SettleMatch(_)
+            | TaskDescriptor::SettleMatchInternal(_)
+            | TaskDescriptor::UpdateMerkleProof(_) => false,
+        }
+    }
+
     /// Returns whether the task is a wallet task
     ///
     /// Currently all tasks are wallet tasks
@@ -336,16 +402,32 @@ pub struct UpdateWalletTaskDescriptor {
     /// A signature of the `VALID WALLET UPDATE` statement by the wallet's root
     /// key, the contract uses this to authorize the update
     pub wallet_update_signature: Vec<u8>,
+    /// The orders cancelled by the update, and the reason each was cancelled
+    #[serde(default)]
+    pub cancelled_orders: Vec<(OrderIdentifier, CancellationReason)>,
 }
 
 impl UpdateWalletTaskDescriptor {
     /// Constructor
+    ///
+    /// If the owner authorized the update for a nonce, the nonce must be the
+    /// old wallet's nonce plus one and the authorization must be signed over
+    /// the new wallet and that nonce. Otherwise the new wallet takes the old
+    /// wallet's next nonce
     pub fn new(
         transfer_with_auth: Option<ExternalTransferWithAuth>,
         old_wallet: Wallet,
-        new_wallet: Wallet,
+        mut new_wallet: Wallet,
         wallet_update_signature: Vec<u8>,
+        nonce_auth: Option<UpdateNonceAuth>,
     ) -> Result<Self, String> {
+        // Reject a nonce that has already been consumed, i.e. a replayed update
+        new_wallet.update_nonce = match &nonce_auth {
+            Some(auth) => auth.nonce,
+            None => old_wallet.update_nonce + 1,
+        };
+        check_wallet_update_nonce(&old_wallet, &new_wallet)?;
+
         // Check that the new wallet is properly reblinded
         if !new_wallet.check_wallet_shares() {
             return Err(INVALID_WALLET_SHARES.to_string());
@@ -356,13 +438,44 @@ impl UpdateWalletTaskDescriptor {
         verify_wallet_update_signature(&new_wallet, key, &wallet_update_signature)
             .map_err(|e| format!("invalid wallet update sig: {e}"))?;
 
+        // Check the signature binding the update to its nonce
+        if let Some(auth) = &nonce_auth {
+            verify_update_nonce_signature(&new_wallet, key, auth)
+                .map_err(|e| format!("{ERR_INVALID_NONCE_SIG}: {e}"))?;
+        }
+
         Ok(UpdateWalletTaskDescriptor {
             transfer: transfer_with_auth,
             old_wallet,
             new_wallet,
             wallet_update_signature,
+            cancelled_orders: Vec::new(),
         })
     }
+
+    /// Record the orders the update cancels, and the reason they are cancelled
+    pub fn with_cancelled_orders(
+        mut self,
+        order_ids: impl IntoIterator<Item = OrderIdentifier>,
+        reason: CancellationReason,
+    ) -> Self {
+        self.cancelled_orders.extend(order_ids.into_iter().map(|id| (id, reason)));
+        self
+    }
+}
+
+/// An owner's authorization of a wallet update at a single update nonce
+///
+/// The contract verifies the update's statement signature over the new
+/// wallet's commitment alone, so the nonce is signed separately and checked
+/// by the relayer
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpdateNonceAuth {
+    /// The update nonce authorized, the wallet's current nonce plus one
+    pub nonce: u64,
+    /// A signature by the wallet's root key over the new wallet's share
+    /// commitment and the nonce
+    pub signature: Vec<u8>,
 }
 
 impl From<UpdateWalletTaskDescriptor> for TaskDescriptor {
@@ -463,7 +576,6 @@ pub fn verify_wallet_update_signature(
     key: &PublicSigningKey,
     wallet_update_signature: &[u8],
 ) -> Result<(), String> {
-    let key: K256VerifyingKey = key.into();
     let new_wallet_comm = wallet.get_wallet_share_commitment();
 
     // Serialize the commitment, matches the contract's serialization here:
@@ -471,12 +583,60 @@ pub fn verify_wallet_update_signature(
     let comm_bytes = new_wallet_comm.to_biguint().to_bytes_be();
     let digest = keccak256(comm_bytes);
 
-    // Verify the signature
+    verify_digest_signature(digest, key, wallet_update_signature)
+}
+
+/// Compute the digest signed to authorize a wallet update at a nonce
+///
+/// The nonce is appended to the serialized share commitment of the new wallet,
+/// so a signature cannot be replayed under a different nonce
+pub fn update_nonce_digest(commitment: Scalar, nonce: u64) -> [u8; 32] {
+    let mut bytes = commitment.to_biguint().to_bytes_be();
+    bytes.extend_from_slice(&nonce.to_be_bytes());
+    keccak256(bytes)
+}
+
+/// Verify an owner's authorization of a wallet update at a nonce
+pub fn verify_update_nonce_signature(
+    wallet: &Wallet,
+    key: &PublicSigningKey,
+    auth: &UpdateNonceAuth,
+) -> Result<(), String> {
+    let digest = update_nonce_digest(wallet.get_wallet_share_commitment(), auth.nonce);
+    verify_digest_signature(digest, key, &auth.signature)
+}
+
+/// Verify a signature of the given digest by the given key
+fn verify_digest_signature(
+    digest: [u8; 32],
+    key: &PublicSigningKey,
+    signature: &[u8],
+) -> Result<(), String> {
+    let key: K256VerifyingKey = key.into();
     let addr = public_key_to_address(&key);
-    let sig = Signature::try_from(wallet_update_signature).map_err(|e| e.to_string())?;
+    let sig = Signature::try_from(signature).map_err(|e| e.to_string())?;
     sig.verify(digest, addr).map_err(|e| e.to_string())
 }
 
+/// Check that an update carries the next nonce of the wallet it is applied to
+///
+/// The nonce orders the updates of a wallet, an update whose nonce has been
+/// consumed is stale and is rejected
+pub fn check_wallet_update_nonce(
+    current_wallet: &Wallet,
+    new_wallet: &Wallet,
+) -> Result<(), String> {
+    let expected = current_wallet.update_nonce + 1;
+    if new_wallet.update_nonce != expected {
+        return Err(format!(
+            "{ERR_STALE_UPDATE_NONCE}: expected {expected}, got {}",
+            new_wallet.update_nonce
+        ));
+    }
+
+    Ok(())
+}
+
 // ---------
 // | Mocks |
 // ---------
@@ -539,10 +699,11 @@ pub mod mocks {
 mod test {
     use constants::Scalar;
 
-    use crate::types::wallet_mocks::mock_empty_wallet;
+    use crate::types::{wallet::Wallet, wallet_mocks::mock_empty_wallet};
 
     use super::{
-        mocks::gen_wallet_update_sig, NewWalletTaskDescriptor, UpdateWalletTaskDescriptor,
+        mocks::gen_wallet_update_sig, NewWalletTaskDescriptor, UpdateNonceAuth,
+        UpdateWalletTaskDescriptor,
     };
 
     /// Tests creating a new wallet task with an invalid secret sharing
@@ -555,6 +716,13 @@ mod test {
         NewWalletTaskDescriptor::new(wallet).unwrap();
     }
 
+    /// Sign an update of the wallet to itself at the given nonce
+    fn nonce_auth(wallet: &Wallet, nonce: u64) -> UpdateNonceAuth {
+        let comm = wallet.get_wallet_share_commitment();
+        let signature = wallet.sign_update_nonce(comm, nonce).unwrap().to_vec();
+        UpdateNonceAuth { nonce, signature }
+    }
+
     /// Tests creating an update wallet task with an invalid shares
     #[test]
     #[should_panic(expected = "invalid wallet shares")]
@@ -567,6 +735,7 @@ mod test {
             wallet.clone(),
             wallet,
             vec![],
+            None, // nonce_auth
         )
         .unwrap();
     }
@@ -583,6 +752,7 @@ mod test {
             wallet.clone(),
             wallet,
             sig,
+            None, // nonce_auth
         )
         .unwrap();
     }
@@ -595,12 +765,94 @@ mod test {
         let key = wallet.key_chain.secret_keys.sk_root.as_ref().unwrap();
         let sig = gen_wallet_update_sig(&wallet, key);
 
-        UpdateWalletTaskDescriptor::new(
+        let desc = UpdateWalletTaskDescriptor::new(
             None, // transfer
             wallet.clone(),
-            wallet,
+            wallet.clone(),
             sig,
+            None, // nonce_auth
         )
         .unwrap();
+        assert_eq!(desc.new_wallet.update_nonce, wallet.update_nonce + 1);
+    }
+
+    /// Tests that an update authorized for the wallet's next nonce is accepted
+    #[test]
+    fn test_next_update_nonce() {
+        let wallet = mock_empty_wallet();
+        let key = wallet.key_chain.secret_keys.sk_root.as_ref().unwrap();
+        let sig = gen_wallet_update_sig(&wallet, key);
+
+        let nonce = wallet.update_nonce + 1;
+        let desc = UpdateWalletTaskDescriptor::new(
+            None, // transfer
+            wallet.clone(),
+            wallet.clone(),
+            sig,
+            Some(nonce_auth(&wallet, nonce)),
+        )
+        .unwrap();
+        assert_eq!(desc.new_wallet.update_nonce, nonce);
+    }
+
+    /// Tests that a nonce other than the wallet's next nonce is rejected
+    #[test]
+    fn test_unexpected_update_nonce() {
+        let wallet = mock_empty_wallet();
+        let key = wallet.key_chain.secret_keys.sk_root.as_ref().unwrap();
+        let sig = gen_wallet_update_sig(&wallet, key);
+
+        for nonce in [wallet.update_nonce, wallet.update_nonce + 2] {
+            let res = UpdateWalletTaskDescriptor::new(
+                None, // transfer
+                wallet.clone(),
+                wallet.clone(),
+                sig.clone(),
+                Some(nonce_auth(&wallet, nonce)),
+            );
+            assert!(res.unwrap_err().contains("stale wallet update nonce"));
+        }
+    }
+
+    /// Tests that replaying a signed update under a fresh nonce once the first
+    /// update has applied is rejected
+    #[test]
+    fn test_replayed_update_rejected() {
+        let wallet = mock_empty_wallet();
+        let key = wallet.key_chain.secret_keys.sk_root.as_ref().unwrap();
+        let sig = gen_wallet_update_sig(&wallet, key);
+        let auth = nonce_auth(&wallet, wallet.update_nonce + 1);
+
+        // The first update succeeds and is applied to the wallet
+        let desc = UpdateWalletTaskDescriptor::new(
+            None, // transfer
+            wallet.clone(),
+            wallet.clone(),
+            sig.clone(),
+            Some(auth.clone()),
+        )
+        .unwrap();
+        let applied_wallet = desc.new_wallet;
+
+        // The same signature and nonce against the updated wallet is stale
+        let res = UpdateWalletTaskDescriptor::new(
+            None, // transfer
+            applied_wallet.clone(),
+            wallet.clone(),
+            sig.clone(),
+            Some(auth.clone()),
+        );
+        assert!(res.unwrap_err().contains("stale wallet update nonce"));
+
+        // The same signature under the wallet's next nonce does not verify
+        let replayed = UpdateNonceAuth { nonce: applied_wallet.update_nonce + 1, ..auth };
+        let res = UpdateWalletTaskDescriptor::new(
+            None, // transfer
+            applied_wallet,
+            wallet,
+            sig,
+            Some(replayed),
+        );
+        assert!(res.unwrap_err().contains("invalid update nonce sig"));
     }
 }
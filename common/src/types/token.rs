@@ -711,4 +711,24 @@ impl Token {
         let decimal_adjustment = 10u128.pow(decimals as u32);
         amount as f64 / decimal_adjustment as f64
     }
+
+    /// Formats the amount of the token as an exact decimal string, accounting
+    /// for the associated number of decimals.
+    ///
+    /// Trailing zeros in the fractional part are trimmed. Returns `None` if the
+    /// token's decimals are unknown, or too large for the adjustment to fit in
+    /// a `u128`
+    pub fn format_decimal(&self, amount: u128) -> Option<String> {
+        let decimals = self.get_decimals()? as usize;
+        let decimal_adjustment = 10u128.checked_pow(decimals as u32)?;
+
+        let whole = amount / decimal_adjustment;
+        let frac = format!("{:0decimals$}", amount % decimal_adjustment);
+        let frac = frac.trim_end_matches('0');
+        if frac.is_empty() {
+            Some(whole.to_string())
+        } else {
+            Some(format!("{whole}.{frac}"))
+        }
+    }
 }
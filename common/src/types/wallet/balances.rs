@@ -1,4 +1,14 @@
 //! Wallet helpers for balances in the wallet
+//!
+//! The wallet update and reblind proofs link a wallet's old and new balances
+//! by position, so balances follow a canonical ordering:
+//!     - Balances are kept in insertion order, an existing balance never
+//!       changes position
+//!     - A new mint is appended after the last balance or, if the wallet is
+//!       full, takes the position of the first zero'd balance
+//!     - A removed balance is replaced in place by a default balance
+//!     - Trailing default balances are not stored, the circuit representation
+//!       pads the balances with defaults at the end
 
 use std::iter;
 
@@ -46,6 +56,8 @@ impl Wallet {
     }
 
     /// Get a list of balances in order in their circuit representation
+    ///
+    /// The list is padded with default balances at the end
     pub fn get_balances_list(&self) -> [Balance; MAX_BALANCES] {
         self.balances
             .clone()
@@ -100,10 +112,27 @@ impl Wallet {
     /// Remove a balance from the wallet, replacing it with a default balance
     pub fn remove_balance(&mut self, mint: &BigUint) -> Option<Balance> {
         // Replace the balance with a default balance to preserve the balance order for
-        // wallet update proofs
-        let bal = self.get_balance_mut(mint)?;
-        *bal = Balance::default();
+        // wallet update proofs. The placeholder is keyed by the default mint so that
+        // the removed mint may be deposited again
+        let idx = self.get_balance_index(mint)?;
+        let bal = self.balances.get_index(idx)?.clone();
+        self.balances.replace_at_index(idx, BigUint::default(), Balance::default());
 
-        Some(bal.clone())
+        Some(bal)
+    }
+
+    /// Bring the balances into their canonical ordering by dropping trailing
+    /// default balances
+    ///
+    /// This does not change the circuit representation of the wallet, so it
+    /// is safe to apply before constructing shares
+    pub(crate) fn canonicalize_balances(&mut self) {
+        let n_occupied = self
+            .balances
+            .iter()
+            .rposition(|(_, balance)| !balance.is_default())
+            .map(|idx| idx + 1)
+            .unwrap_or(0);
+        self.balances.truncate(n_occupied);
     }
 }
@@ -59,6 +59,7 @@ pub fn derive_wallet_keychain(eth_key: &EthWallet, chain_id: u64) -> Result<KeyC
     Ok(KeyChain {
         public_keys: PublicKeyChain { pk_root, pk_match },
         secret_keys: PrivateKeyChain { sk_root: Some(sk_root), sk_match },
+        scoped_keys: vec![],
     })
 }
 
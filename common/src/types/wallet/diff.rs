@@ -0,0 +1,129 @@
+//! Wallet helpers for computing the difference between two wallets
+//!
+//! Used to detect wallet updates that would not change the wallet, for which
+//! proving a state transition is wasted work
+
+use num_bigint::BigUint;
+
+use crate::keyed_list::KeyedList;
+
+use super::{OrderIdentifier, Wallet};
+
+/// The difference between two versions of a wallet
+///
+/// Only the plaintext wallet state is compared; the blinder and shares change
+/// on every reblind and so are not considered part of the diff
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WalletDiff {
+    /// The IDs of orders added, removed, or modified
+    pub orders: Vec<OrderIdentifier>,
+    /// The mints of balances added, removed, or modified
+    pub balances: Vec<BigUint>,
+    /// Whether the keychain changed
+    pub keys_changed: bool,
+    /// Whether the match fee changed
+    pub match_fee_changed: bool,
+    /// Whether the managing cluster changed
+    pub managing_cluster_changed: bool,
+}
+
+impl WalletDiff {
+    /// Returns whether the diff is empty, i.e. the two wallets hold the same
+    /// state
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+            && self.balances.is_empty()
+            && !self.keys_changed
+            && !self.match_fee_changed
+            && !self.managing_cluster_changed
+    }
+}
+
+impl Wallet {
+    /// Compute the difference between this wallet and another
+    pub fn diff(&self, other: &Wallet) -> WalletDiff {
+        WalletDiff {
+            orders: changed_keys(&self.orders, &other.orders),
+            balances: changed_keys(&self.balances, &other.balances),
+            keys_changed: self.key_chain.public_keys != other.key_chain.public_keys,
+            match_fee_changed: self.match_fee != other.match_fee,
+            managing_cluster_changed: self.managing_cluster != other.managing_cluster,
+        }
+    }
+}
+
+/// Get the keys whose values differ between two keyed lists, including keys
+/// present in only one of the lists
+fn changed_keys<K, V>(old: &KeyedList<K, V>, new: &KeyedList<K, V>) -> Vec<K>
+where
+    K: Clone + Eq,
+    V: Clone + PartialEq,
+{
+    let modified_or_removed =
+        old.iter().filter(|(k, v)| new.get(k) != Some(v)).map(|(k, _)| k.clone());
+    let added = new.keys().filter(|k| !old.contains_key(k)).cloned();
+
+    modified_or_removed.chain(added).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use circuit_types::{balance::Balance, fixed_point::FixedPoint};
+    use num_bigint::BigUint;
+    use uuid::Uuid;
+
+    use crate::types::wallet::mocks::{mock_empty_wallet, mock_order};
+
+    /// Tests that a wallet has an empty diff with itself and with a reblinded
+    /// copy of itself
+    #[test]
+    fn test_diff_identical() {
+        let mut wallet = mock_empty_wallet();
+        wallet.add_order(Uuid::new_v4(), mock_order()).unwrap();
+        wallet.add_balance(Balance::new_from_mint_and_amount(BigUint::from(1u8), 10)).unwrap();
+
+        assert!(wallet.diff(&wallet).is_empty());
+
+        let mut reblinded = wallet.clone();
+        reblinded.reblind_wallet();
+        assert!(wallet.diff(&reblinded).is_empty());
+    }
+
+    /// Tests the diff of wallets differing in a single field
+    #[test]
+    fn test_diff_single_field() {
+        let order_id = Uuid::new_v4();
+        let mint = BigUint::from(1u8);
+        let mut wallet = mock_empty_wallet();
+        wallet.add_order(order_id, mock_order()).unwrap();
+        wallet.add_balance(Balance::new_from_mint_and_amount(mint.clone(), 10)).unwrap();
+
+        // Modify an order
+        let mut new_wallet = wallet.clone();
+        new_wallet.get_order_mut(&order_id).unwrap().amount += 1;
+        let diff = wallet.diff(&new_wallet);
+        assert!(!diff.is_empty());
+        assert_eq!(diff.orders, vec![order_id]);
+        assert!(diff.balances.is_empty());
+
+        // Add an order
+        let new_id = Uuid::new_v4();
+        let mut new_wallet = wallet.clone();
+        new_wallet.add_order(new_id, mock_order()).unwrap();
+        assert_eq!(wallet.diff(&new_wallet).orders, vec![new_id]);
+
+        // Modify a balance
+        let mut new_wallet = wallet.clone();
+        new_wallet.balances.get_mut(&mint).unwrap().amount = 0;
+        let diff = wallet.diff(&new_wallet);
+        assert!(diff.orders.is_empty());
+        assert_eq!(diff.balances, vec![mint]);
+
+        // Modify the match fee
+        let mut new_wallet = wallet.clone();
+        new_wallet.match_fee = FixedPoint::from_f64_round_down(0.01);
+        let diff = wallet.diff(&new_wallet);
+        assert!(diff.match_fee_changed);
+        assert!(diff.orders.is_empty() && diff.balances.is_empty() && !diff.keys_changed);
+    }
+}
@@ -4,6 +4,8 @@ use constants::Scalar;
 use ethers::{signers::Wallet as EthersWallet, types::Signature, utils::keccak256};
 use k256::ecdsa::SigningKey;
 
+use crate::types::tasks::update_nonce_digest;
+
 use super::Wallet;
 
 /// Error message emitted when the wallet does not have an `sk_root` value
@@ -23,6 +25,17 @@ impl Wallet {
         // Sign the commitment
         wallet.sign_hash(digest.into()).map_err(|e| e.to_string())
     }
+
+    /// Sign a wallet transition commitment at the given update nonce with the
+    /// wallet's keychain
+    pub fn sign_update_nonce(&self, commitment: Scalar, nonce: u64) -> Result<Signature, String> {
+        let root_key = self.key_chain.secret_keys.sk_root.as_ref().ok_or(ERR_NO_SK_ROOT)?;
+        let key = SigningKey::try_from(root_key)?;
+        let wallet = EthersWallet::from(key);
+
+        let digest = update_nonce_digest(commitment, nonce);
+        wallet.sign_hash(digest.into()).map_err(|e| e.to_string())
+    }
 }
 
 #[cfg(test)]
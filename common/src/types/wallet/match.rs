@@ -1,43 +1,99 @@
 //! Match settlement helpers
 
-use circuit_types::{native_helpers::create_wallet_shares_from_private, r#match::MatchResult};
+use circuit_types::{
+    balance::Balance, native_helpers::create_wallet_shares_from_private, r#match::MatchResult,
+    Amount,
+};
 use util::matching_engine::compute_fee_obligation;
 
 use super::{OrderIdentifier, Wallet};
 
+/// Error message emitted when a match is applied to an order not in the wallet
+const ERR_ORDER_NOT_FOUND: &str = "order not found in wallet";
+/// Error message emitted when a match exceeds the volume of its order
+const ERR_ORDER_OVERFILLED: &str = "match exceeds order volume";
+/// Error message emitted when the wallet has no balance to send from
+const ERR_SEND_BALANCE_NOT_FOUND: &str = "send balance not found in wallet";
+/// Error message emitted when a match exceeds the send balance
+const ERR_BALANCE_UNDERFLOW: &str = "match exceeds send balance";
+/// Error message emitted when a match overflows the receive balance
+const ERR_BALANCE_OVERFLOW: &str = "receive balance overflow";
+
+/// The fill of an order resulting from a match settled into its wallet
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OrderFill {
+    /// The order that was filled
+    pub order_id: OrderIdentifier,
+    /// The base amount filled by the match
+    pub filled_amount: Amount,
+    /// The base amount remaining on the order after the match
+    pub remaining_amount: Amount,
+}
+
+impl OrderFill {
+    /// Whether the match filled the order completely
+    pub fn is_full(&self) -> bool {
+        self.remaining_amount == 0
+    }
+}
+
 impl Wallet {
     /// Settle a match on the given order into the wallet
+    ///
+    /// A partial fill leaves the order in place with its residual volume. A
+    /// full fill leaves the order zero'd rather than removing it, as the
+    /// on-chain wallet shares only reduce the order's amount; the owner may
+    /// remove the order in a later update
+    ///
+    /// The wallet is left unmodified if the match cannot be applied
     pub fn apply_match(
         &mut self,
         match_res: &MatchResult,
         order_id: &OrderIdentifier,
-    ) -> Result<(), String> {
-        // Subtract the matched volume from the order
-        let match_fee = self.match_fee;
-        let order = self.get_order_mut(order_id).unwrap();
-        order.amount =
-            order.amount.checked_sub(match_res.base_amount).expect("order volume underflow");
+    ) -> Result<OrderFill, String> {
+        let order = self.get_order(order_id).ok_or_else(|| ERR_ORDER_NOT_FOUND.to_string())?;
+        let side = order.side;
+        let remaining_amount = order
+            .amount
+            .checked_sub(match_res.base_amount)
+            .ok_or_else(|| ERR_ORDER_OVERFILLED.to_string())?;
 
         // Select the correct mints and amounts based on the order side
-        let (send_mint, send_amt) = match_res.send_mint_amount(order.side);
-        let (recv_mint, recv_amt) = match_res.receive_mint_amount(order.side);
-        let fees = compute_fee_obligation(match_fee, order.side, match_res);
+        let (send_mint, send_amt) = match_res.send_mint_amount(side);
+        let (recv_mint, recv_amt) = match_res.receive_mint_amount(side);
+        let fees = compute_fee_obligation(self.match_fee, side, match_res);
         let net_recv = recv_amt - fees.total();
 
-        // Update the balances
-        let send_balance = self.get_balance_mut(&send_mint).unwrap();
-        send_balance.amount = send_balance.amount.checked_sub(send_amt).expect("balance underflow");
-
-        let recv_balance = self.get_balance_mut_or_default(&recv_mint);
-        recv_balance.amount = recv_balance.amount.checked_add(net_recv).expect("balance overflow");
-        recv_balance.relayer_fee_balance = recv_balance
-            .relayer_fee_balance
-            .checked_add(fees.relayer_fee)
-            .expect("relayer fee balance overflow");
-        recv_balance.protocol_fee_balance = recv_balance
-            .protocol_fee_balance
-            .checked_add(fees.protocol_fee)
-            .expect("protocol fee balance overflow");
+        // Validate the balance updates before modifying the wallet
+        let send_balance =
+            self.get_balance(&send_mint).ok_or_else(|| ERR_SEND_BALANCE_NOT_FOUND.to_string())?;
+        let new_send_amount = send_balance
+            .amount
+            .checked_sub(send_amt)
+            .ok_or_else(|| ERR_BALANCE_UNDERFLOW.to_string())?;
+
+        let recv_balance = self
+            .get_balance(&recv_mint)
+            .cloned()
+            .unwrap_or_else(|| Balance::new_from_mint(recv_mint.clone()));
+        let overflow = || ERR_BALANCE_OVERFLOW.to_string();
+        let new_recv_balance = Balance {
+            amount: recv_balance.amount.checked_add(net_recv).ok_or_else(overflow)?,
+            relayer_fee_balance: recv_balance
+                .relayer_fee_balance
+                .checked_add(fees.relayer_fee)
+                .ok_or_else(overflow)?,
+            protocol_fee_balance: recv_balance
+                .protocol_fee_balance
+                .checked_add(fees.protocol_fee)
+                .ok_or_else(overflow)?,
+            ..recv_balance
+        };
+
+        // Apply the match to the order and balances
+        self.get_order_mut(order_id).unwrap().amount = remaining_amount;
+        self.get_balance_mut(&send_mint).unwrap().amount = new_send_amount;
+        *self.get_balance_mut_or_default(&recv_mint) = new_recv_balance;
 
         // Update the public shares of the wallet, reblinding the wallet should be done
         // separately
@@ -50,6 +106,10 @@ impl Wallet {
 
         // Invalidate the Merkle opening
         self.invalidate_merkle_opening();
-        Ok(())
+        Ok(OrderFill {
+            order_id: *order_id,
+            filled_amount: match_res.base_amount,
+            remaining_amount,
+        })
     }
 }
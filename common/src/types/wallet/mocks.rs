@@ -46,6 +46,7 @@ pub fn mock_empty_wallet() -> Wallet {
         key_chain: KeyChain {
             public_keys: PublicKeyChain { pk_root, pk_match },
             secret_keys: PrivateKeyChain { sk_root, sk_match },
+            scoped_keys: vec![],
         },
         blinder: Scalar::random(&mut rng),
         match_fee: FixedPoint::from_integer(0),
@@ -57,6 +58,7 @@ pub fn mock_empty_wallet() -> Wallet {
             Scalar::random(&mut rng)
         })),
         merkle_proof: Some(mock_merkle_path()),
+        update_nonce: 0,
         merkle_staleness: Arc::new(AtomicUsize::default()),
     };
 
@@ -4,6 +4,7 @@
 
 mod balances;
 pub mod derivation;
+mod diff;
 mod keychain;
 mod r#match;
 #[cfg(feature = "mocks")]
@@ -12,6 +13,8 @@ mod orders;
 mod shares;
 mod types;
 
+pub use diff::WalletDiff;
+pub use r#match::OrderFill;
 pub use types::*;
 
 // ----------------
@@ -20,13 +23,39 @@ pub use types::*;
 
 #[cfg(test)]
 mod test {
-    use circuit_types::balance::Balance;
+    use circuit_types::{balance::Balance, r#match::MatchResult};
     use constants::{MAX_BALANCES, MAX_ORDERS};
     use num_bigint::BigUint;
     use rand::{distributions::uniform::SampleRange, thread_rng};
     use uuid::Uuid;
 
-    use crate::types::wallet::mocks::{mock_empty_wallet, mock_order};
+    use crate::types::wallet::{
+        mocks::{mock_empty_wallet, mock_order},
+        OrderIdentifier, Wallet,
+    };
+
+    /// Build a wallet holding a buy order for 10 units of the base and the
+    /// quote balance to pay for it, returning the match of `base_amount`
+    /// against it at a price of 2
+    fn wallet_with_order(base_amount: u128) -> (Wallet, OrderIdentifier, MatchResult) {
+        let mut wallet = mock_empty_wallet();
+        let order = mock_order();
+        let quote_balance = Balance::new_from_mint_and_amount(order.quote_mint.clone(), 100);
+        wallet.add_balance(quote_balance).unwrap();
+
+        let id = Uuid::new_v4();
+        wallet.add_order(id, order.clone()).unwrap();
+
+        let match_res = MatchResult {
+            quote_mint: order.quote_mint,
+            base_mint: order.base_mint,
+            quote_amount: 2 * base_amount,
+            base_amount,
+            direction: false,
+            min_amount_order_index: false,
+        };
+        (wallet, id, match_res)
+    }
 
     /// Tests adding a balance to an empty wallet
     #[test]
@@ -96,6 +125,69 @@ mod test {
         wallet.add_balance(balance).unwrap();
     }
 
+    /// Tests that depositing a new mint into a wallet recovered from its shares
+    /// preserves the positions of the existing balances
+    #[test]
+    fn test_deposit_new_mint_preserves_positions() {
+        let mut wallet = mock_empty_wallet();
+        let balance1 = Balance::new_from_mint_and_amount(BigUint::from(1u8), 10);
+        let balance2 = Balance::new_from_mint_and_amount(BigUint::from(2u8), 10);
+        wallet.add_balance(balance1.clone()).unwrap();
+        wallet.add_balance(balance2.clone()).unwrap();
+        wallet.reblind_wallet();
+
+        // Recover the wallet from its shares, as is done after a match settles; this
+        // fills the balances with default padding
+        let (private_shares, public_shares) =
+            (wallet.private_shares.clone(), wallet.blinded_public_shares.clone());
+        wallet.update_from_shares(&private_shares, &public_shares);
+        let old_balances = wallet.get_balances_list();
+
+        // Deposit a new mint
+        let balance3 = Balance::new_from_mint_and_amount(BigUint::from(3u8), 10);
+        wallet.add_balance(balance3.clone()).unwrap();
+        wallet.reblind_wallet();
+
+        let new_balances = wallet.get_balances_list();
+        assert_eq!(new_balances[..2], old_balances[..2]);
+        assert_eq!(new_balances[2], balance3);
+        assert!(new_balances[3..].iter().all(Balance::is_default));
+        assert!(wallet.check_wallet_shares());
+    }
+
+    /// Tests that removing a balance leaves a default balance in its place and
+    /// that the removed mint may be deposited again without moving others
+    #[test]
+    fn test_remove_balance_preserves_positions() {
+        let mut wallet = mock_empty_wallet();
+        let balances = (1u8..=3)
+            .map(|i| Balance::new_from_mint_and_amount(BigUint::from(i), 10))
+            .collect::<Vec<_>>();
+        for balance in balances.iter() {
+            wallet.add_balance(balance.clone()).unwrap();
+        }
+
+        // Remove the middle balance
+        let removed = wallet.remove_balance(&balances[1].mint).unwrap();
+        wallet.reblind_wallet();
+        assert_eq!(removed, balances[1]);
+
+        let list = wallet.get_balances_list();
+        assert_eq!(list[0], balances[0]);
+        assert!(list[1].is_default());
+        assert_eq!(list[2], balances[2]);
+
+        // Deposit the removed mint again
+        wallet.add_balance(balances[1].clone()).unwrap();
+        wallet.reblind_wallet();
+
+        let list = wallet.get_balances_list();
+        assert_eq!(list[0], balances[0]);
+        assert_eq!(list[2], balances[2]);
+        assert_eq!(wallet.get_balance(&balances[1].mint), Some(&balances[1]));
+        assert!(wallet.check_wallet_shares());
+    }
+
     /// Tests adding an order that appends to the wallet
     #[test]
     fn test_add_order_append() {
@@ -159,4 +251,39 @@ mod test {
         let order = mock_order();
         wallet.add_order(id, order).unwrap();
     }
+
+    /// Tests that a partial fill leaves the residual order in place
+    #[test]
+    fn test_apply_match_partial_fill() {
+        let (mut wallet, id, match_res) = wallet_with_order(4 /* base_amount */);
+        let fill = wallet.apply_match(&match_res, &id).unwrap();
+
+        assert_eq!(fill.filled_amount, 4);
+        assert_eq!(fill.remaining_amount, 6);
+        assert!(!fill.is_full());
+        assert_eq!(wallet.get_order(&id).unwrap().amount, 6);
+        assert_eq!(wallet.get_balance(&match_res.quote_mint).unwrap().amount, 92);
+    }
+
+    /// Tests that a full fill leaves the order zero'd
+    #[test]
+    fn test_apply_match_full_fill() {
+        let (mut wallet, id, match_res) = wallet_with_order(10 /* base_amount */);
+        let fill = wallet.apply_match(&match_res, &id).unwrap();
+
+        assert!(fill.is_full());
+        assert!(wallet.get_order(&id).unwrap().is_zero());
+        assert_eq!(wallet.get_balance(&match_res.quote_mint).unwrap().amount, 80);
+    }
+
+    /// Tests that a match exceeding the order's volume is rejected without
+    /// modifying the wallet
+    #[test]
+    fn test_apply_match_overfill() {
+        let (mut wallet, id, match_res) = wallet_with_order(11 /* base_amount */);
+        let original = wallet.clone();
+
+        assert!(wallet.apply_match(&match_res, &id).is_err());
+        assert_eq!(wallet, original);
+    }
 }
@@ -49,19 +49,35 @@ impl Wallet {
     // -----------
 
     /// Reblind the wallet, consuming the next set of blinders and secret shares
+    ///
+    /// The balances are brought into their canonical ordering first, so that
+    /// the new shares are constructed over positionally stable balances
     pub fn reblind_wallet(&mut self) {
+        // The CSPRNGs are seeded by the last share of each of the previous chains
         let private_shares_serialized: Vec<Scalar> = self.private_shares.to_scalars();
+        let n_shares = private_shares_serialized.len();
+        let share_seed = private_shares_serialized[n_shares - 2];
+        let blinder_seed = private_shares_serialized[n_shares - 1];
+
+        self.reblind_wallet_from_seeds(share_seed, blinder_seed);
+    }
+
+    /// Reblind the wallet with the given seeds for the share and blinder
+    /// CSPRNGs, in place of the seeds held in the wallet's current shares
+    ///
+    /// Used to rotate seeds that may have been compromised, subsequent calls to
+    /// `reblind_wallet` continue the chains from the new seeds
+    pub fn reblind_wallet_from_seeds(&mut self, share_seed: Scalar, blinder_seed: Scalar) {
+        self.canonicalize_balances();
+        let n_shares = self.private_shares.to_scalars().len();
 
         // Sample a new blinder and private secret share
-        let n_shares = private_shares_serialized.len();
-        let blinder_and_private_share =
-            evaluate_hash_chain(private_shares_serialized[n_shares - 1], 2 /* length */);
+        let blinder_and_private_share = evaluate_hash_chain(blinder_seed, 2 /* length */);
         let new_blinder = blinder_and_private_share[0];
         let new_blinder_private_share = blinder_and_private_share[1];
 
         // Sample new secret shares for the wallet
-        let mut new_private_shares =
-            evaluate_hash_chain(private_shares_serialized[n_shares - 2], n_shares - 1);
+        let mut new_private_shares = evaluate_hash_chain(share_seed, n_shares - 1);
         new_private_shares.push(new_blinder_private_share);
 
         let (new_private_share, new_public_share) = create_wallet_shares_from_private(
@@ -88,6 +104,7 @@ impl Wallet {
 
         self.blinder = wallet.blinder;
         self.balances = wallet.balances.into_iter().map(|b| (b.mint.clone(), b)).collect();
+        self.canonicalize_balances();
 
         // Preserve the order_ids, the indexmap should give a consistent ordering
         // between orders
@@ -12,7 +12,7 @@ use circuit_types::{
     balance::Balance,
     elgamal::EncryptionKey,
     fixed_point::FixedPoint,
-    keychain::{PublicKeyChain, SecretIdentificationKey, SecretSigningKey},
+    keychain::{PublicKeyChain, PublicSigningKey, SecretIdentificationKey, SecretSigningKey},
     native_helpers::create_wallet_shares_with_randomness,
     order::Order,
     traits::BaseType,
@@ -55,6 +55,52 @@ pub struct KeyChain {
     pub public_keys: PublicKeyChain,
     /// The secret keys in the wallet
     pub secret_keys: PrivateKeyChain,
+    /// Sub-keys delegated a subset of the root key's permissions
+    ///
+    /// These are enforced by the relayer's API only, the contract and circuits
+    /// know nothing of them
+    #[serde(default)]
+    pub scoped_keys: Vec<ScopedKey>,
+}
+
+/// An operation on a wallet that a scoped key may be authorized for
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeyScope {
+    /// Place or update orders in the wallet
+    PlaceOrders,
+    /// Cancel orders in the wallet
+    CancelOrders,
+    /// Deposit into the wallet
+    Deposit,
+    /// Withdraw from or remove balances in the wallet
+    Withdraw,
+}
+
+/// A signing key that authorizes a fixed set of operations on a wallet
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScopedKey {
+    /// The public key that signs requests
+    pub key: PublicSigningKey,
+    /// The operations the key is authorized for
+    pub scopes: Vec<KeyScope>,
+    /// The time in milliseconds since the epoch after which the key is no
+    /// longer accepted, or `None` if the key does not expire
+    #[serde(default)]
+    pub expiry: Option<u64>,
+}
+
+impl ScopedKey {
+    /// Whether the key is authorized for the given operation
+    pub fn has_scope(&self, scope: KeyScope) -> bool {
+        self.scopes.contains(&scope)
+    }
+
+    /// Whether the key has expired at the given time, in milliseconds since
+    /// the epoch
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expiry.is_some_and(|expiry| now >= expiry)
+    }
 }
 
 /// The Merkle opening from the wallet shares' commitment to the global root
@@ -89,6 +135,11 @@ pub struct Wallet {
     /// The authentication paths for the public and private shares of the wallet
     #[serde(default)]
     pub merkle_proof: Option<WalletAuthenticationPath>,
+    /// The number of updates applied to the wallet, an update must carry the
+    /// next nonce, and an owner may sign the update for that nonce to bind its
+    /// signature to a single update
+    #[serde(default)]
+    pub update_nonce: u64,
     /// The staleness of the Merkle proof, i.e. the number of Merkle root
     /// updates that have occurred since the wallet's Merkle proof was last
     /// updated
@@ -97,6 +148,41 @@ pub struct Wallet {
     pub merkle_staleness: Arc<AtomicUsize>,
 }
 
+/// An inconsistency between the wallets in the state and the index mapping
+/// orders to the wallets that manage them
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum WalletIndexInconsistency {
+    /// An index entry maps an order to a wallet that does not exist
+    MissingWallet {
+        /// The indexed order
+        order_id: OrderIdentifier,
+        /// The wallet the order is indexed to
+        wallet_id: WalletIdentifier,
+    },
+    /// An index entry maps an order to a wallet that does not contain it
+    OrphanedEntry {
+        /// The indexed order
+        order_id: OrderIdentifier,
+        /// The wallet the order is indexed to
+        wallet_id: WalletIdentifier,
+    },
+    /// A wallet contains an order that is not indexed to it
+    UnindexedOrder {
+        /// The order missing from the index
+        order_id: OrderIdentifier,
+        /// The wallet containing the order
+        wallet_id: WalletIdentifier,
+    },
+    /// An order is contained in more than one wallet
+    DuplicateOrder {
+        /// The duplicated order
+        order_id: OrderIdentifier,
+        /// The wallets containing the order
+        wallet_ids: Vec<WalletIdentifier>,
+    },
+}
+
 impl From<Wallet> for SizedCircuitWallet {
     fn from(wallet: Wallet) -> Self {
         SizedCircuitWallet {
@@ -133,6 +219,7 @@ impl Wallet {
             private_shares: dummy_shares,
             blinder: Scalar::zero(),
             merkle_proof: None,
+            update_nonce: 0,
             merkle_staleness: Arc::new(AtomicUsize::new(0)),
         };
 
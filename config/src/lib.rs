@@ -3,26 +3,34 @@
 mod token_remaps;
 
 use arbitrum_client::constants::Chain;
-use circuit_types::{elgamal::DecryptionKey, fixed_point::FixedPoint};
+use circuit_types::{elgamal::DecryptionKey, fixed_point::FixedPoint, Amount};
 use clap::Parser;
 use colored::*;
 use common::types::{
     exchange::Exchange,
     gossip::{ClusterId, WrappedPeerId},
+    handshake::PriceSelection,
+    token::Token,
 };
 use ed25519_dalek::{Digest, Keypair as DalekKeypair, Sha512, SignatureError};
-use ethers::{core::rand::thread_rng, signers::LocalWallet};
+use ethers::{core::rand::thread_rng, signers::LocalWallet, types::Address};
 use libp2p::{identity::Keypair, Multiaddr, PeerId};
 use rand_core::OsRng;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashMap,
     env, fs,
     net::{IpAddr, SocketAddr},
+    num::NonZeroUsize,
     str::FromStr,
+    thread,
 };
 use token_remaps::setup_token_remaps;
 use toml::{value::Map, Value};
-use util::arbitrum::{parse_addr_from_deployments_file, DARKPOOL_PROXY_CONTRACT_KEY};
+use util::{
+    arbitrum::{parse_addr_from_deployments_file, DARKPOOL_PROXY_CONTRACT_KEY},
+    hex::biguint_from_hex_string,
+};
 
 /// The dummy message used for checking elliptic curve key pairs
 const DUMMY_MESSAGE: &str = "signature check";
@@ -55,6 +63,63 @@ struct Cli {
     /// Defaults to 20 basis points
     #[clap(long, value_parser, default_value = "0.002")]
     pub match_take_rate: f64,
+    /// The minimum base amount the internal matching engine will settle a match for, per base 
+    /// token. Specified as space separated `<ERC-20 address>=<amount>` pairs
+    /// 
+    /// Tokens without an entry have no minimum
+    #[clap(long, value_parser, num_args=1.., value_delimiter=' ')]
+    pub min_match_base_amounts: Vec<String>,
+    /// The number of order pairs held in the handshake cache, which prevents already matched 
+    /// pairs from being proposed again
+    #[clap(long, value_parser, default_value = "500")]
+    pub handshake_cache_size: usize,
+    /// The amount of time to wait for a peer to respond to a match proposal before resending it
+    #[clap(long, value_parser, default_value = "10000")]
+    pub price_agreement_timeout_ms: u64,
+    /// The number of times a match proposal is resent to an unresponsive peer before the 
+    /// handshake is failed
+    #[clap(long, value_parser, default_value = "2")]
+    pub price_agreement_retries: usize,
+    /// The policy by which the internal matching engine selects the execution price of a match; 
+    /// one of `midpoint`, `maker-favorable`, or `taker-favorable`
+    #[clap(long, value_parser, default_value = "midpoint")]
+    pub price_selection: PriceSelection,
+    /// The spread from the midpoint, in basis points, at which the `maker-favorable` and 
+    /// `taker-favorable` price selection policies execute a match
+    #[clap(long, value_parser, default_value = "10")]
+    pub price_selection_spread_bps: u32,
+    /// Whether to prefer matching a local order internally over handshaking with a peer when 
+    /// both are possible, internal matches avoid the network and MPC cost of a handshake
+    #[clap(long, value_parser, default_value = "true")]
+    pub prefer_internal_matches: bool,
+    /// Whether to allow proposing a match between two orders managed by the same wallet to a peer. 
+    /// Self-trades are skipped by default, this is intended for testing. Internal matches never 
+    /// pair orders in the same wallet
+    #[clap(long, value_parser, default_value = "false")]
+    pub allow_self_trades: bool,
+    /// The minimum time in milliseconds an order must rest after it is received before it is 
+    /// considered for a match, mitigating quote-sniping. Zero disables the rest period
+    #[clap(long, value_parser, default_value = "0")]
+    pub min_order_rest_ms: u64,
+    /// The number of handshake messages per second a peer may send before its messages are 
+    /// dropped. Zero disables the limit
+    #[clap(long, value_parser, default_value = "20")]
+    pub handshake_rate_limit: u32,
+    /// The number of threads the handshake executor may use to execute handshakes
+    #[clap(long, value_parser, default_value = "8")]
+    pub handshake_executor_threads: usize,
+    /// The minimum base amount of an order accepted by the API, per base token. Specified as 
+    /// space separated `<ERC-20 address>=<amount>` pairs
+    /// 
+    /// Tokens without an entry have no minimum
+    #[clap(long, value_parser, num_args=1.., value_delimiter=' ')]
+    pub min_order_amounts: Vec<String>,
+    /// The maximum base amount of an order accepted by the API, per base token. Specified as 
+    /// space separated `<ERC-20 address>=<amount>` pairs
+    /// 
+    /// Tokens without an entry have no maximum
+    #[clap(long, value_parser, num_args=1.., value_delimiter=' ')]
+    pub max_order_amounts: Vec<String>,
 
     // -----------------------
     // | Environment Configs |
@@ -66,6 +131,10 @@ struct Cli {
     /// The address of the darkpool contract, defaults to the internal testnet deployment
     #[clap(long, value_parser, default_value = "0xe1080224b632a93951a7cfa33eeea9fd81558b5e")]
     pub contract_address: String,
+    /// The address to which the darkpool is expected to route protocol fees, the relayer
+    /// refuses to start if the contract's fee recipient differs
+    #[clap(long, value_parser)]
+    pub protocol_fee_recipient: Option<String>,
     /// The path to the file containing deployments info for the darkpool contract
     #[clap(long, value_parser)]
     pub deployments_file: Option<String>,
@@ -88,6 +157,12 @@ struct Cli {
     /// The known public IP address of the local peer
     #[clap(long, value_parser)] 
     pub public_ip: Option<SocketAddr>,
+    /// The maximum size in bytes of any message accepted from a peer, applied to every gossip 
+    /// request and response (including raft traffic) and every pubsub message. Larger messages 
+    /// are rejected before they are decoded, so the limit must fit the largest message the 
+    /// network sends, e.g. a page of orders with their validity proofs
+    #[clap(long, value_parser, default_value = "104857600")]
+    pub max_message_bytes: usize,
     
     // -------------------------
     // | Cluster Configuration |
@@ -126,12 +201,153 @@ struct Cli {
     /// managed wallets. After this threshold is exceeded, the Merkle proof will be updated
     #[clap(long, value_parser, default_value = "100")]
     pub max_merkle_staleness: usize,
+    /// The number of roots short of the contract's root history window at which an order's 
+    /// validity proofs are considered expiring and are re-proven against a fresh root
+    #[clap(long, value_parser, default_value = "5")]
+    pub proof_expiry_margin: usize,
+    /// The maximum number of blocks to span in a single `eth_getLogs` query when indexing events
+    #[clap(long, value_parser, default_value = "10000")]
+    pub event_block_window: u64,
+    /// The maximum gas a transaction may be estimated to use, transactions estimated above this 
+    /// are not submitted
+    #[clap(long, value_parser, default_value = "100000000")]
+    pub gas_limit_ceiling: u64,
+    /// The number of jobs queued for the proof manager above which the API server rejects
+    /// requests that would enqueue more proofs
+    #[clap(long, value_parser, default_value = "1000")]
+    pub proof_queue_high_water_mark: usize,
+    /// The number of threads in the proof manager's proving pool, defaults to the host's 
+    /// available parallelism
+    #[clap(long, value_parser)]
+    pub proof_manager_threads: Option<usize>,
+    /// The number of block confirmations to await on a match settlement before updating local 
+    /// state, the block including the transaction counts as the first confirmation
+    #[clap(long, value_parser, default_value = "1")]
+    pub match_confirmation_depth: u64,
+    /// The maximum number of wallet lookups that may run concurrently, further lookups are 
+    /// queued until a running lookup finishes
+    #[clap(long, value_parser, default_value = "4")]
+    pub max_concurrent_wallet_lookups: usize,
+    /// The window in milliseconds within which ready match settlements are batched into a single 
+    /// submission. Matches are settled individually if unset, or if the darkpool client cannot settle
+    /// a batch in a single transaction
+    #[clap(long, value_parser)]
+    pub match_batch_window_ms: Option<u64>,
+    /// The interval in milliseconds after which a wallet that has not changed is reblinded, 
+    /// rotating its on-chain commitment. Only wallets whose root key the relayer holds are 
+    /// reblinded, idle wallets are left as is if unset
+    #[clap(long, value_parser)]
+    pub reblind_idle_interval_ms: Option<u64>,
+    /// The time in milliseconds after it is received that a locally managed order is cancelled. 
+    /// Only orders in wallets whose root key the relayer holds expire, orders do not expire if 
+    /// unset
+    #[clap(long, value_parser)]
+    pub order_ttl_ms: Option<u64>,
+    /// The maximum number of times a task that fails before its commit point is restarted from 
+    /// its descriptor, further pre-commit failures retry the failed step. Failures past the commit 
+    /// point always retry the failed step
+    #[clap(long, value_parser, default_value = "2")]
+    pub max_task_restarts: usize,
+    /// The maximum number of validity proofs for remote orders held in the order book, proofs 
+    /// beyond this are evicted least recently used first and refetched from the managing peer
+    #[clap(long, value_parser, default_value = "10000")]
+    pub max_remote_validity_proofs: usize,
+    /// The maximum number of wallets this node may manage, creating or looking up a wallet past 
+    /// this limit fails. Unbounded if not set
+    #[clap(long, value_parser)]
+    pub max_managed_wallets: Option<usize>,
+    /// Verify that each wallet applied to the state has shares that reconstruct its contents and 
+    /// commit to its claimed Merkle leaf, rejecting wallets that do not. This is a cluster-wide 
+    /// setting, every node applies the check to the replicated log and must set it identically. 
+    /// Disabled by default as the check is costly
+    #[clap(long, value_parser)]
+    pub verify_wallet_shares: bool,
+    /// The number of consecutive heartbeat intervals a peer may miss before it is expired from 
+    /// the peer index, and removed from the raft group if it is a cluster peer
+    #[clap(long, value_parser, default_value = "3")]
+    pub max_missed_heartbeats: u64,
+    /// The number of raft ticks a follower waits without hearing from the leader before starting 
+    /// an election, the randomized timeout is drawn from `[election_tick, 2 * election_tick)`
+    #[clap(long, value_parser, default_value = "10")]
+    pub raft_election_tick: usize,
+    /// The number of raft ticks between heartbeats sent by the leader, must be less than the 
+    /// election tick
+    #[clap(long, value_parser, default_value = "2")]
+    pub raft_heartbeat_tick: usize,
+    /// The number of applied raft entries after which the node snapshots its state and compacts 
+    /// its log
+    #[clap(long, value_parser, default_value = "10000")]
+    pub raft_snapshot_interval_entries: u64,
+    /// The time in milliseconds after which the node snapshots its state and compacts its log, 
+    /// if it has applied any entries since the last compaction
+    #[clap(long, value_parser, default_value = "600000")]
+    pub raft_snapshot_interval_ms: u64,
+    /// A namespace prefixed to the relayer's DB tables and mixed into its raft IDs, allowing 
+    /// test harnesses to co-host isolated clusters in one process
+    #[clap(long, value_parser)]
+    pub cluster_namespace: Option<String>,
     /// Flag to disable the price reporter
     #[clap(long, value_parser)]
     pub disable_price_reporter: bool,
     /// Disables exchanges for price reporting
     #[clap(long, value_parser, num_args=1.., value_delimiter=' ')]
     pub disabled_exchanges: Vec<Exchange>,
+    /// The weight of each exchange in the median price, so that more liquid venues dominate. 
+    /// Specified as space separated `<exchange>=<weight>` pairs
+    /// 
+    /// Exchanges without an entry have a weight of one
+    #[clap(long, value_parser, num_args=1.., value_delimiter=' ')]
+    pub exchange_weights: Vec<String>,
+    /// Fixed prices for pairs without a reliable exchange feed, reported as an `override` source 
+    /// in the median. Specified as space separated `<base address>-<quote address>=<price>` pairs
+    #[clap(long, value_parser, num_args=1.., value_delimiter=' ')]
+    pub price_overrides: Vec<String>,
+    /// Absolute bounds on a pair's price, reports outside of which are dropped before the median 
+    /// is taken. Specified as space separated `<base address>-<quote address>=<min>:<max>` pairs
+    #[clap(long, value_parser, num_args=1.., value_delimiter=' ')]
+    pub price_bounds: Vec<String>,
+    /// The maximum size in bytes of a single websocket frame read from an exchange, larger 
+    /// frames are rejected and the connection is re-established
+    #[clap(long, value_parser, default_value = "1048576")]
+    pub exchange_ws_max_frame_size: usize,
+    /// The maximum size in bytes of the buffer a websocket message from an exchange is read 
+    /// into, larger messages are rejected and the connection is re-established
+    #[clap(long, value_parser, default_value = "4194304")]
+    pub exchange_ws_max_buffer_size: usize,
+    /// The half-life in milliseconds of an exponential moving average applied to published 
+    /// median prices. Smoothed reports are flagged as such, smoothing is disabled if not set
+    #[clap(long, value_parser)]
+    pub price_ema_half_life_ms: Option<u64>,
+    /// The time in milliseconds a pair may go without a fresh price from any source before a 
+    /// `PriceFeedDown` event is published for it
+    #[clap(long, value_parser, default_value = "30000")]
+    pub price_feed_grace_period_ms: u64,
+    /// The number of pairs whose median prices are computed and published concurrently 
+    #[clap(long, value_parser, default_value = "8")]
+    pub price_median_concurrency: usize,
+    /// The interval in milliseconds at which keepalive pings are sent on each exchange 
+    /// websocket connection
+    #[clap(long, value_parser, default_value = "15000")]
+    pub exchange_keepalive_interval_ms: u64,
+    /// The number of consecutive keepalive pings an exchange may leave unanswered before its 
+    /// connection is re-established
+    #[clap(long, value_parser, default_value = "2")]
+    pub exchange_max_missed_pongs: usize,
+    /// Permit plaintext `ws://` exchange connections, e.g. for devnet feeds. By default only 
+    /// `wss://` connections are made
+    #[clap(long, value_parser)]
+    pub allow_insecure_exchange_ws: bool,
+    /// The hosts each exchange's websocket URL must point at, connections to any other host are 
+    /// rejected. Specified as space separated `<exchange>=<host>` pairs
+    #[clap(long, value_parser, num_args=1.., value_delimiter=' ')]
+    pub exchange_hosts: Vec<String>,
+    /// The certificates each exchange's websocket server may present, connections presenting 
+    /// any other certificate are rejected. Specified as space separated `<exchange>=<fingerprint>` 
+    /// pairs, where the fingerprint is the hex encoded SHA-256 hash of the DER certificate
+    /// 
+    /// An exchange may be given several pins to allow for certificate rotation
+    #[clap(long, value_parser, num_args=1.., value_delimiter=' ')]
+    pub exchange_cert_pins: Vec<String>,
     /// Whether or not to run the relayer in debug mode
     #[clap(short, long, value_parser)]
     pub debug: bool,
@@ -151,6 +367,13 @@ struct Cli {
     /// The HTTP addressable Arbitrum JSON-RPC node
     #[clap(long = "rpc-url", value_parser)]
     pub rpc_url: Option<String>,
+    /// Fallback Arbitrum JSON-RPC nodes, in priority order, used when the primary is unreachable
+    #[clap(long = "fallback-rpc-urls", value_parser, num_args=1.., value_delimiter=' ')]
+    pub fallback_rpc_urls: Vec<String>,
+    /// The timeout in milliseconds on a single JSON-RPC request, after which the request fails 
+    /// and fails over to the next RPC endpoint
+    #[clap(long, value_parser, default_value = "10000")]
+    pub rpc_timeout_ms: u64,
     /// The Arbitrum private key used to send transactions
     /// 
     /// Defaults to the devnet pre-funded key
@@ -159,6 +382,11 @@ struct Cli {
     /// The key used to decrypt fee payments
     #[clap(long = "fee-decryption-key", value_parser)]
     pub fee_decryption_key: Option<String>,
+    /// The token required on requests to admin API routes
+    /// 
+    /// Admin routes are disabled if this is not set
+    #[clap(long = "admin-api-key", value_parser)]
+    pub admin_api_key: Option<String>,
 
     // -------------
     // | Telemetry |
@@ -198,6 +426,43 @@ pub struct RelayerConfig {
     /// The take rate of this relayer on a managed match, i.e. the amount of the
     /// received asset that the relayer takes as a fee
     pub match_take_rate: FixedPoint,
+    /// The minimum base amount the internal matching engine will settle a
+    /// match for, keyed by base token
+    pub min_match_base_amounts: HashMap<Token, Amount>,
+    /// The number of order pairs held in the handshake cache
+    pub handshake_cache_size: usize,
+    /// The amount of time to wait for a peer to respond to a match proposal
+    /// before resending it
+    pub price_agreement_timeout_ms: u64,
+    /// The number of times a match proposal is resent to an unresponsive peer
+    /// before the handshake is failed
+    pub price_agreement_retries: usize,
+    /// The policy by which the internal matching engine selects the execution
+    /// price of a match
+    pub price_selection: PriceSelection,
+    /// The spread from the midpoint, in basis points, at which the
+    /// maker-favorable and taker-favorable policies execute a match
+    pub price_selection_spread_bps: u32,
+    /// Whether to prefer matching a local order internally over handshaking
+    /// with a peer when both are possible
+    pub prefer_internal_matches: bool,
+    /// Whether to allow matching two orders managed by the same wallet
+    pub allow_self_trades: bool,
+    /// The minimum time in milliseconds an order must rest after it is
+    /// received before it is considered for a match
+    pub min_order_rest_ms: u64,
+    /// The number of handshake messages per second a peer may send before its
+    /// messages are dropped, zero disables the limit
+    pub handshake_rate_limit: u32,
+    /// The number of threads the handshake executor may use to execute
+    /// handshakes
+    pub handshake_executor_threads: usize,
+    /// The minimum base amount of an order accepted by the API, keyed by base
+    /// token
+    pub min_order_amounts: HashMap<Token, Amount>,
+    /// The maximum base amount of an order accepted by the API, keyed by base
+    /// token
+    pub max_order_amounts: HashMap<Token, Amount>,
 
     // -----------------------
     // | Environment Configs |
@@ -206,6 +471,9 @@ pub struct RelayerConfig {
     pub chain_id: Chain,
     /// The address of the contract in the target network
     pub contract_address: String,
+    /// The address to which the darkpool is expected to route protocol fees,
+    /// checked against the contract on startup if set
+    pub protocol_fee_recipient: Option<Address>,
 
     // ----------------------------
     // | Networking Configuration |
@@ -216,6 +484,9 @@ pub struct RelayerConfig {
     pub bind_addr: IpAddr,
     /// The known public IP address of the local peer
     pub public_ip: Option<SocketAddr>,
+    /// The maximum size in bytes of any gossip request, response, or pubsub
+    /// message accepted from a peer
+    pub max_message_bytes: usize,
 
     // -------------------------
     // | Cluster Configuration |
@@ -242,11 +513,107 @@ pub struct RelayerConfig {
     /// Merkle proofs for managed wallets. After this threshold is exceeded,
     /// the Merkle proof will be updated
     pub max_merkle_staleness: usize,
+    /// The number of roots short of the contract's root history window at
+    /// which an order's validity proofs are considered expiring and are
+    /// re-proven against a fresh root
+    pub proof_expiry_margin: usize,
+    /// The maximum number of blocks to span in a single `eth_getLogs` query
+    /// when indexing events
+    pub event_block_window: u64,
+    /// The maximum gas a transaction may be estimated to use, transactions
+    /// estimated above this are not submitted
+    pub gas_limit_ceiling: u64,
+    /// The number of jobs queued for the proof manager above which the API
+    /// server rejects requests that would enqueue more proofs
+    pub proof_queue_high_water_mark: usize,
+    /// The number of threads in the proof manager's proving pool
+    pub proof_manager_threads: usize,
+    /// The number of block confirmations to await on a match settlement
+    /// before updating local state
+    pub match_confirmation_depth: u64,
+    /// The maximum number of wallet lookups that may run concurrently
+    pub max_concurrent_wallet_lookups: usize,
+    /// The window in milliseconds within which ready match settlements are
+    /// batched, settlements are submitted individually if unset or if the
+    /// darkpool client cannot settle a batch in a single transaction
+    pub match_batch_window_ms: Option<u64>,
+    /// The interval in milliseconds after which an unchanged wallet is
+    /// reblinded, idle wallets are not reblinded if unset
+    pub reblind_idle_interval_ms: Option<u64>,
+    /// The time in milliseconds after it is received that a locally managed
+    /// order is cancelled, orders do not expire if unset
+    pub order_ttl_ms: Option<u64>,
+    /// The maximum number of times a task failing before its commit point is
+    /// restarted from its descriptor
+    pub max_task_restarts: usize,
+    /// The maximum number of validity proofs for remote orders held in the
+    /// order book
+    pub max_remote_validity_proofs: usize,
+    /// The maximum number of wallets this node may manage, unbounded if not
+    /// set
+    pub max_managed_wallets: Option<usize>,
+    /// Whether wallets are verified against their contents and claimed
+    /// commitment as they are applied
+    ///
+    /// Every node in a cluster applies the check to the replicated log, so it
+    /// must be set identically across the cluster
+    pub verify_wallet_shares: bool,
+    /// The number of consecutive heartbeat intervals a peer may miss before
+    /// it is expired
+    pub max_missed_heartbeats: u64,
+    /// The number of raft ticks a follower waits without hearing from the
+    /// leader before starting an election
+    pub raft_election_tick: usize,
+    /// The number of raft ticks between heartbeats sent by the leader
+    pub raft_heartbeat_tick: usize,
+    /// The number of applied raft entries after which the log is compacted
+    pub raft_snapshot_interval_entries: u64,
+    /// The time in milliseconds after which the log is compacted, if entries
+    /// have been applied since the last compaction
+    pub raft_snapshot_interval_ms: u64,
+    /// A namespace prefixed to the relayer's DB tables and mixed into its
+    /// raft IDs, allowing isolated clusters to be co-hosted in one process
+    pub cluster_namespace: Option<String>,
     /// Whether to disable the price reporter if e.g. we are streaming from a
     /// dedicated external API gateway node in the cluster
     pub disable_price_reporter: bool,
     /// The exchanges explicitly disabled for price reports
     pub disabled_exchanges: Vec<Exchange>,
+    /// The weight of each exchange in the median price
+    pub exchange_weights: HashMap<Exchange, f64>,
+    /// The fixed prices of pairs without a reliable exchange feed, keyed by
+    /// `(base, quote)`
+    pub price_overrides: HashMap<(Token, Token), f64>,
+    /// The absolute `(min, max)` bounds on each pair's price, keyed by
+    /// `(base, quote)`
+    pub price_bounds: HashMap<(Token, Token), (f64, f64)>,
+    /// The maximum size in bytes of a websocket frame read from an exchange
+    pub exchange_ws_max_frame_size: usize,
+    /// The maximum size in bytes of a websocket message buffered from an
+    /// exchange
+    pub exchange_ws_max_buffer_size: usize,
+    /// The half-life in milliseconds of the moving average applied to
+    /// published median prices, if smoothing is enabled
+    pub price_ema_half_life_ms: Option<u64>,
+    /// The time in milliseconds a pair may go without a fresh price before
+    /// its feeds are reported down
+    pub price_feed_grace_period_ms: u64,
+    /// The number of pairs whose median prices are computed and published
+    /// concurrently
+    pub price_median_concurrency: usize,
+    /// The interval in milliseconds at which keepalive pings are sent on each
+    /// exchange connection
+    pub exchange_keepalive_interval_ms: u64,
+    /// The number of consecutive keepalive pings an exchange may leave
+    /// unanswered before its connection is re-established
+    pub exchange_max_missed_pongs: usize,
+    /// Whether plaintext `ws://` exchange connections are permitted
+    pub allow_insecure_exchange_ws: bool,
+    /// The hosts each exchange's websocket URL is pinned to
+    pub exchange_hosts: HashMap<Exchange, String>,
+    /// The hex encoded SHA-256 fingerprints of the certificates each
+    /// exchange's websocket server may present
+    pub exchange_cert_pins: HashMap<Exchange, Vec<String>>,
     /// Whether or not the relayer is in debug mode
     pub debug: bool,
 
@@ -261,12 +628,20 @@ pub struct RelayerConfig {
     pub coinbase_api_secret: Option<String>,
     /// The HTTP addressable Arbitrum JSON-RPC node
     pub rpc_url: Option<String>,
+    /// Fallback Arbitrum JSON-RPC nodes, in priority order, used when the
+    /// primary is unreachable
+    pub fallback_rpc_urls: Vec<String>,
+    /// The timeout in milliseconds on a single JSON-RPC request
+    pub rpc_timeout_ms: u64,
     /// The Arbitrum private key used to send transactions
     pub arbitrum_private_key: LocalWallet,
     /// The Ethereum RPC node websocket address to dial for on-chain data
     pub eth_websocket_addr: Option<String>,
     /// The decryption key used to settle managed match fees
     pub fee_decryption_key: DecryptionKey,
+    /// The token required on requests to admin API routes, admin routes are
+    /// disabled if this is not set
+    pub admin_api_key: Option<String>,
 
     // -------------
     // | Telemetry |
@@ -299,8 +674,22 @@ impl Clone for RelayerConfig {
     fn clone(&self) -> Self {
         Self {
             match_take_rate: self.match_take_rate,
+            min_match_base_amounts: self.min_match_base_amounts.clone(),
+            handshake_cache_size: self.handshake_cache_size,
+            price_agreement_timeout_ms: self.price_agreement_timeout_ms,
+            price_agreement_retries: self.price_agreement_retries,
+            price_selection: self.price_selection,
+            price_selection_spread_bps: self.price_selection_spread_bps,
+            prefer_internal_matches: self.prefer_internal_matches,
+            allow_self_trades: self.allow_self_trades,
+            min_order_rest_ms: self.min_order_rest_ms,
+            handshake_rate_limit: self.handshake_rate_limit,
+            handshake_executor_threads: self.handshake_executor_threads,
+            min_order_amounts: self.min_order_amounts.clone(),
+            max_order_amounts: self.max_order_amounts.clone(),
             chain_id: self.chain_id,
             contract_address: self.contract_address.clone(),
+            protocol_fee_recipient: self.protocol_fee_recipient,
             bootstrap_servers: self.bootstrap_servers.clone(),
             p2p_port: self.p2p_port,
             http_port: self.http_port,
@@ -308,18 +697,55 @@ impl Clone for RelayerConfig {
             p2p_key: self.p2p_key.clone(),
             db_path: self.db_path.clone(),
             max_merkle_staleness: self.max_merkle_staleness,
+            proof_expiry_margin: self.proof_expiry_margin,
+            event_block_window: self.event_block_window,
+            gas_limit_ceiling: self.gas_limit_ceiling,
+            proof_queue_high_water_mark: self.proof_queue_high_water_mark,
+            proof_manager_threads: self.proof_manager_threads,
+            match_confirmation_depth: self.match_confirmation_depth,
+            max_concurrent_wallet_lookups: self.max_concurrent_wallet_lookups,
+            match_batch_window_ms: self.match_batch_window_ms,
+            reblind_idle_interval_ms: self.reblind_idle_interval_ms,
+            order_ttl_ms: self.order_ttl_ms,
+            max_task_restarts: self.max_task_restarts,
+            max_remote_validity_proofs: self.max_remote_validity_proofs,
+            max_managed_wallets: self.max_managed_wallets,
+            verify_wallet_shares: self.verify_wallet_shares,
+            max_missed_heartbeats: self.max_missed_heartbeats,
+            raft_election_tick: self.raft_election_tick,
+            raft_heartbeat_tick: self.raft_heartbeat_tick,
+            raft_snapshot_interval_entries: self.raft_snapshot_interval_entries,
+            raft_snapshot_interval_ms: self.raft_snapshot_interval_ms,
+            cluster_namespace: self.cluster_namespace.clone(),
             allow_local: self.allow_local,
+            max_message_bytes: self.max_message_bytes,
             bind_addr: self.bind_addr,
             public_ip: self.public_ip,
             disable_price_reporter: self.disable_price_reporter,
             disabled_exchanges: self.disabled_exchanges.clone(),
+            exchange_weights: self.exchange_weights.clone(),
+            price_overrides: self.price_overrides.clone(),
+            price_bounds: self.price_bounds.clone(),
+            exchange_ws_max_frame_size: self.exchange_ws_max_frame_size,
+            exchange_ws_max_buffer_size: self.exchange_ws_max_buffer_size,
+            price_ema_half_life_ms: self.price_ema_half_life_ms,
+            price_feed_grace_period_ms: self.price_feed_grace_period_ms,
+            price_median_concurrency: self.price_median_concurrency,
+            exchange_keepalive_interval_ms: self.exchange_keepalive_interval_ms,
+            exchange_max_missed_pongs: self.exchange_max_missed_pongs,
+            allow_insecure_exchange_ws: self.allow_insecure_exchange_ws,
+            exchange_hosts: self.exchange_hosts.clone(),
+            exchange_cert_pins: self.exchange_cert_pins.clone(),
             cluster_keypair: DalekKeypair::from_bytes(&self.cluster_keypair.to_bytes()).unwrap(),
             cluster_id: self.cluster_id.clone(),
             coinbase_api_key: self.coinbase_api_key.clone(),
             coinbase_api_secret: self.coinbase_api_secret.clone(),
             rpc_url: self.rpc_url.clone(),
+            fallback_rpc_urls: self.fallback_rpc_urls.clone(),
+            rpc_timeout_ms: self.rpc_timeout_ms,
             arbitrum_private_key: self.arbitrum_private_key.clone(),
             fee_decryption_key: self.fee_decryption_key,
+            admin_api_key: self.admin_api_key.clone(),
             eth_websocket_addr: self.eth_websocket_addr.clone(),
             debug: self.debug,
             otlp_enabled: self.otlp_enabled,
@@ -392,6 +818,12 @@ fn parse_config_from_args(cli_args: Cli) -> Result<RelayerConfig, String> {
     let arbitrum_private_key =
         LocalWallet::from_str(&cli_args.arbitrum_private_key).map_err(|e| e.to_string())?;
     let fee_decryption_key = parse_decryption_key(cli_args.fee_decryption_key)?;
+    let protocol_fee_recipient = cli_args
+        .protocol_fee_recipient
+        .map(|addr| {
+            addr.parse::<Address>().map_err(|e| format!("invalid protocol fee recipient: {e}"))
+        })
+        .transpose()?;
 
     // Parse the p2p keypair or generate one
     let p2p_key = if let Some(keypair) = cli_args.p2p_key {
@@ -402,6 +834,76 @@ fn parse_config_from_args(cli_args: Cli) -> Result<RelayerConfig, String> {
     };
 
     let cluster_id = ClusterId::new(&keypair.public);
+    let min_match_base_amounts =
+        parse_token_amounts(&cli_args.min_match_base_amounts, "minimum match size")?;
+    let min_order_amounts = parse_token_amounts(&cli_args.min_order_amounts, "minimum order size")?;
+    let max_order_amounts = parse_token_amounts(&cli_args.max_order_amounts, "maximum order size")?;
+    let exchange_weights = parse_exchange_weights(&cli_args.exchange_weights)?;
+    let exchange_hosts = parse_exchange_hosts(&cli_args.exchange_hosts)?;
+    let exchange_cert_pins = parse_exchange_cert_pins(&cli_args.exchange_cert_pins)?;
+    let price_overrides = parse_price_overrides(&cli_args.price_overrides)?;
+    let price_bounds = parse_price_bounds(&cli_args.price_bounds)?;
+    let proof_manager_threads = parse_proof_manager_threads(cli_args.proof_manager_threads)?;
+    if cli_args.handshake_executor_threads == 0 {
+        return Err("handshake executor threads must be positive".to_string());
+    }
+    if cli_args.handshake_cache_size == 0 {
+        return Err("handshake cache size must be positive".to_string());
+    }
+    if cli_args.rpc_timeout_ms == 0 {
+        return Err("RPC timeout must be positive".to_string());
+    }
+    if cli_args.proof_expiry_margin == 0 {
+        return Err("proof expiry margin must be positive".to_string());
+    }
+    if cli_args.max_concurrent_wallet_lookups == 0 {
+        return Err("max concurrent wallet lookups must be positive".to_string());
+    }
+    if cli_args.raft_snapshot_interval_entries == 0 {
+        return Err("raft snapshot interval entries must be positive".to_string());
+    }
+    if cli_args.raft_snapshot_interval_ms == 0 {
+        return Err("raft snapshot interval must be positive".to_string());
+    }
+    if cli_args.max_managed_wallets == Some(0) {
+        return Err("max managed wallets must be positive".to_string());
+    }
+    if cli_args.match_batch_window_ms == Some(0) {
+        return Err("match batch window must be positive".to_string());
+    }
+    if cli_args.reblind_idle_interval_ms == Some(0) {
+        return Err("idle reblind interval must be positive".to_string());
+    }
+    if cli_args.order_ttl_ms == Some(0) {
+        return Err("order ttl must be positive".to_string());
+    }
+    if cli_args.price_ema_half_life_ms == Some(0) {
+        return Err("price EMA half-life must be positive".to_string());
+    }
+    if cli_args.event_block_window == 0 {
+        return Err("event block window must be positive".to_string());
+    }
+    if cli_args.max_message_bytes == 0 {
+        return Err("max message bytes must be positive".to_string());
+    }
+    if cli_args.price_feed_grace_period_ms == 0 {
+        return Err("price feed grace period must be positive".to_string());
+    }
+    if cli_args.price_median_concurrency == 0 {
+        return Err("price median concurrency must be positive".to_string());
+    }
+    if cli_args.exchange_keepalive_interval_ms == 0 {
+        return Err("exchange keepalive interval must be positive".to_string());
+    }
+    if cli_args.exchange_max_missed_pongs == 0 {
+        return Err("exchange max missed pongs must be positive".to_string());
+    }
+    if cli_args.max_missed_heartbeats == 0 {
+        return Err("max missed heartbeats must be positive".to_string());
+    }
+    if cli_args.price_selection_spread_bps >= 10_000 {
+        return Err("price selection spread must be below 10000 bps".to_string());
+    }
 
     // Parse the bootstrap servers into multiaddrs
     let mut parsed_bootstrap_addrs: Vec<(WrappedPeerId, Multiaddr)> = Vec::new();
@@ -415,27 +917,78 @@ fn parse_config_from_args(cli_args: Cli) -> Result<RelayerConfig, String> {
 
     let mut config = RelayerConfig {
         match_take_rate: FixedPoint::from_f64_round_down(cli_args.match_take_rate),
+        min_match_base_amounts,
+        handshake_cache_size: cli_args.handshake_cache_size,
+        price_agreement_timeout_ms: cli_args.price_agreement_timeout_ms,
+        price_agreement_retries: cli_args.price_agreement_retries,
+        price_selection: cli_args.price_selection,
+        price_selection_spread_bps: cli_args.price_selection_spread_bps,
+        prefer_internal_matches: cli_args.prefer_internal_matches,
+        allow_self_trades: cli_args.allow_self_trades,
+        min_order_rest_ms: cli_args.min_order_rest_ms,
+        handshake_rate_limit: cli_args.handshake_rate_limit,
+        handshake_executor_threads: cli_args.handshake_executor_threads,
+        min_order_amounts,
+        max_order_amounts,
         chain_id: cli_args.chain_id,
         contract_address: cli_args.contract_address,
+        protocol_fee_recipient,
         bootstrap_servers: parsed_bootstrap_addrs,
         p2p_port: cli_args.p2p_port,
         http_port: cli_args.http_port,
         websocket_port: cli_args.websocket_port,
         allow_local: cli_args.allow_local,
+        max_message_bytes: cli_args.max_message_bytes,
         max_merkle_staleness: cli_args.max_merkle_staleness,
+        proof_expiry_margin: cli_args.proof_expiry_margin,
+        event_block_window: cli_args.event_block_window,
+        gas_limit_ceiling: cli_args.gas_limit_ceiling,
+        proof_queue_high_water_mark: cli_args.proof_queue_high_water_mark,
+        proof_manager_threads,
+        match_confirmation_depth: cli_args.match_confirmation_depth,
+        max_concurrent_wallet_lookups: cli_args.max_concurrent_wallet_lookups,
+        match_batch_window_ms: cli_args.match_batch_window_ms,
+        reblind_idle_interval_ms: cli_args.reblind_idle_interval_ms,
+        order_ttl_ms: cli_args.order_ttl_ms,
+        max_task_restarts: cli_args.max_task_restarts,
+        max_remote_validity_proofs: cli_args.max_remote_validity_proofs,
+        max_managed_wallets: cli_args.max_managed_wallets,
+        verify_wallet_shares: cli_args.verify_wallet_shares,
+        max_missed_heartbeats: cli_args.max_missed_heartbeats,
+        raft_election_tick: cli_args.raft_election_tick,
+        raft_heartbeat_tick: cli_args.raft_heartbeat_tick,
+        raft_snapshot_interval_entries: cli_args.raft_snapshot_interval_entries,
+        raft_snapshot_interval_ms: cli_args.raft_snapshot_interval_ms,
+        cluster_namespace: cli_args.cluster_namespace,
         p2p_key,
         db_path: cli_args.db_path,
         bind_addr: cli_args.bind_addr,
         public_ip: cli_args.public_ip,
         disable_price_reporter: cli_args.disable_price_reporter,
         disabled_exchanges: cli_args.disabled_exchanges,
+        exchange_weights,
+        price_overrides,
+        price_bounds,
+        exchange_ws_max_frame_size: cli_args.exchange_ws_max_frame_size,
+        exchange_ws_max_buffer_size: cli_args.exchange_ws_max_buffer_size,
+        price_ema_half_life_ms: cli_args.price_ema_half_life_ms,
+        price_feed_grace_period_ms: cli_args.price_feed_grace_period_ms,
+        price_median_concurrency: cli_args.price_median_concurrency,
+        exchange_keepalive_interval_ms: cli_args.exchange_keepalive_interval_ms,
+        exchange_max_missed_pongs: cli_args.exchange_max_missed_pongs,
+        allow_insecure_exchange_ws: cli_args.allow_insecure_exchange_ws,
+        exchange_hosts,
+        exchange_cert_pins,
         cluster_keypair: keypair,
         cluster_id,
         coinbase_api_key: cli_args.coinbase_api_key,
         coinbase_api_secret: cli_args.coinbase_api_secret,
         rpc_url: cli_args.rpc_url,
+        fallback_rpc_urls: cli_args.fallback_rpc_urls,
+        rpc_timeout_ms: cli_args.rpc_timeout_ms,
         arbitrum_private_key,
         fee_decryption_key,
+        admin_api_key: cli_args.admin_api_key,
         eth_websocket_addr: cli_args.eth_websocket_addr,
         debug: cli_args.debug,
         otlp_enabled: cli_args.otlp_enabled,
@@ -539,6 +1092,135 @@ fn set_contract_from_file(config: &mut RelayerConfig, file: Option<String>) -> R
     Ok(())
 }
 
+/// Parse per-token amounts from `<address>=<amount>` pairs, the description
+/// names the amount in error messages
+fn parse_token_amounts(
+    pairs: &[String],
+    description: &str,
+) -> Result<HashMap<Token, Amount>, String> {
+    let mut amounts = HashMap::with_capacity(pairs.len());
+    for pair in pairs.iter() {
+        let (addr, amount) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("invalid {description}, expected <addr>=<amount>: {pair}"))?;
+        let mint = biguint_from_hex_string(addr)?;
+        let amount = amount.parse::<Amount>().map_err(|e| e.to_string())?;
+        amounts.insert(Token::from_addr_biguint(&mint), amount);
+    }
+
+    Ok(amounts)
+}
+
+/// Parse the per-exchange median weights from `<exchange>=<weight>` pairs
+fn parse_exchange_weights(pairs: &[String]) -> Result<HashMap<Exchange, f64>, String> {
+    let mut weights = HashMap::with_capacity(pairs.len());
+    for pair in pairs.iter() {
+        let (exchange, weight) = pair.split_once('=').ok_or_else(|| {
+            format!("invalid exchange weight, expected <exchange>=<weight>: {pair}")
+        })?;
+        let exchange = Exchange::from_str(exchange)?;
+        let weight = weight.parse::<f64>().map_err(|e| e.to_string())?;
+        if !weight.is_finite() || weight <= 0. {
+            return Err(format!("exchange weight must be positive: {pair}"));
+        }
+
+        weights.insert(exchange, weight);
+    }
+
+    Ok(weights)
+}
+
+/// Parse the pinned exchange hosts from `<exchange>=<host>` pairs
+fn parse_exchange_hosts(pairs: &[String]) -> Result<HashMap<Exchange, String>, String> {
+    let mut hosts = HashMap::with_capacity(pairs.len());
+    for pair in pairs.iter() {
+        let (exchange, host) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("invalid exchange host, expected <exchange>=<host>: {pair}"))?;
+        let exchange = Exchange::from_str(exchange)?;
+        if host.is_empty() {
+            return Err(format!("exchange host must be non-empty: {pair}"));
+        }
+
+        hosts.insert(exchange, host.to_string());
+    }
+
+    Ok(hosts)
+}
+
+/// Parse the pinned exchange certificates from `<exchange>=<fingerprint>`
+/// pairs, normalizing each fingerprint to lowercase hex
+fn parse_exchange_cert_pins(pairs: &[String]) -> Result<HashMap<Exchange, Vec<String>>, String> {
+    let mut pins: HashMap<Exchange, Vec<String>> = HashMap::new();
+    for pair in pairs.iter() {
+        let (exchange, fingerprint) = pair.split_once('=').ok_or_else(|| {
+            format!("invalid certificate pin, expected <exchange>=<fingerprint>: {pair}")
+        })?;
+        let exchange = Exchange::from_str(exchange)?;
+        if fingerprint.len() != 64 || !fingerprint.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("certificate pin must be a hex encoded SHA-256 hash: {pair}"));
+        }
+
+        pins.entry(exchange).or_default().push(fingerprint.to_ascii_lowercase());
+    }
+
+    Ok(pins)
+}
+
+/// Parse the per-pair price overrides from `<base>-<quote>=<price>` pairs
+fn parse_price_overrides(pairs: &[String]) -> Result<HashMap<(Token, Token), f64>, String> {
+    let mut overrides = HashMap::with_capacity(pairs.len());
+    for pair in pairs.iter() {
+        let err = || format!("invalid price override, expected <base>-<quote>=<price>: {pair}");
+        let (tokens, price) = pair.split_once('=').ok_or_else(err)?;
+        let (base, quote) = tokens.split_once('-').ok_or_else(err)?;
+
+        let base = Token::from_addr_biguint(&biguint_from_hex_string(base)?);
+        let quote = Token::from_addr_biguint(&biguint_from_hex_string(quote)?);
+        let price = price.parse::<f64>().map_err(|e| e.to_string())?;
+        if !price.is_finite() || price <= 0. {
+            return Err(format!("price override must be positive: {pair}"));
+        }
+
+        overrides.insert((base, quote), price);
+    }
+
+    Ok(overrides)
+}
+
+/// Parse the per-pair price bounds from `<base>-<quote>=<min>:<max>` pairs
+fn parse_price_bounds(pairs: &[String]) -> Result<HashMap<(Token, Token), (f64, f64)>, String> {
+    let mut bounds = HashMap::with_capacity(pairs.len());
+    for pair in pairs.iter() {
+        let err = || format!("invalid price bound, expected <base>-<quote>=<min>:<max>: {pair}");
+        let (tokens, range) = pair.split_once('=').ok_or_else(err)?;
+        let (base, quote) = tokens.split_once('-').ok_or_else(err)?;
+        let (min, max) = range.split_once(':').ok_or_else(err)?;
+
+        let base = Token::from_addr_biguint(&biguint_from_hex_string(base)?);
+        let quote = Token::from_addr_biguint(&biguint_from_hex_string(quote)?);
+        let min = min.parse::<f64>().map_err(|e| e.to_string())?;
+        let max = max.parse::<f64>().map_err(|e| e.to_string())?;
+        if !min.is_finite() || !max.is_finite() || min <= 0. || min > max {
+            return Err(format!("price bounds must be positive and ordered: {pair}"));
+        }
+
+        bounds.insert((base, quote), (min, max));
+    }
+
+    Ok(bounds)
+}
+
+/// Resolve the size of the proof manager's thread pool, defaulting to the
+/// host's available parallelism
+fn parse_proof_manager_threads(threads: Option<usize>) -> Result<usize, String> {
+    match threads {
+        Some(0) => Err("proof manager threads must be positive".to_string()),
+        Some(n) => Ok(n),
+        None => Ok(thread::available_parallelism().map(NonZeroUsize::get).unwrap_or(1)),
+    }
+}
+
 /// Parse the relayer's decryption key from a string
 pub fn parse_decryption_key(key_str: Option<String>) -> Result<DecryptionKey, String> {
     if let Some(k) = key_str {
@@ -576,11 +1258,71 @@ fn validate_cluster_keypair(keypair: &DalekKeypair) -> Result<(), SignatureError
 
 #[cfg(test)]
 mod test {
-    use crate::RelayerConfig;
+    use clap::Parser;
+    use common::types::{exchange::Exchange, token::Token};
+    use util::hex::biguint_from_hex_string;
+
+    use crate::{
+        parse_config_from_args, parse_exchange_cert_pins, parse_price_bounds, Cli, RelayerConfig,
+    };
 
     /// Test that the default config parses
     #[test]
     fn test_default_config() {
         RelayerConfig::default();
     }
+
+    /// Tests that the protocol fee recipient is parsed as an address, and that
+    /// a malformed address is rejected
+    #[test]
+    fn test_protocol_fee_recipient() {
+        let addr = "0x000000000000000000000000000000000000dead";
+        let cli = Cli::parse_from(["relayer", "--protocol-fee-recipient", addr]);
+        let config = parse_config_from_args(cli).unwrap();
+        assert_eq!(config.protocol_fee_recipient, Some(addr.parse().unwrap()));
+
+        let cli = Cli::parse_from(["relayer", "--protocol-fee-recipient", "0xabc"]);
+        let err = parse_config_from_args(cli).unwrap_err();
+        assert!(err.contains("invalid protocol fee recipient"));
+    }
+
+    /// Tests that an event block window of zero is rejected
+    #[test]
+    fn test_zero_event_block_window() {
+        let cli = Cli::parse_from(["relayer", "--event-block-window", "0"]);
+        let err = parse_config_from_args(cli).unwrap_err();
+        assert_eq!(err, "event block window must be positive");
+    }
+
+    /// Tests that an invalid chain is rejected with a descriptive error
+    #[test]
+    fn test_invalid_chain() {
+        let err = Cli::try_parse_from(["relayer", "--chain-id", "foo"]).unwrap_err();
+        assert!(err.to_string().contains("unknown chain \"foo\""));
+    }
+
+    /// Tests that certificate pins are grouped by exchange and normalized, and
+    /// that malformed fingerprints are rejected
+    #[test]
+    fn test_exchange_cert_pins() {
+        let pin = "AB".repeat(32);
+        let pins = parse_exchange_cert_pins(&[format!("kraken={pin}")]).unwrap();
+        assert_eq!(pins.get(&Exchange::Kraken), Some(&vec!["ab".repeat(32)]));
+
+        assert!(parse_exchange_cert_pins(&["kraken=abcd".to_string()]).is_err());
+        assert!(parse_exchange_cert_pins(&[format!("kraken={}", "zz".repeat(32))]).is_err());
+    }
+
+    /// Tests that price bounds are keyed by pair, so that bounds on one pair
+    /// do not apply to another pair sharing its base
+    #[test]
+    fn test_price_bounds_keyed_by_pair() {
+        let bounds = parse_price_bounds(&["0x1-0x2=90:110".to_string()]).unwrap();
+        let (base, quote, other_quote) = ("0x1", "0x2", "0x3");
+        let token = |addr: &str| Token::from_addr_biguint(&biguint_from_hex_string(addr).unwrap());
+
+        assert_eq!(bounds.get(&(token(base), token(quote))), Some(&(90., 110.)));
+        assert_eq!(bounds.get(&(token(base), token(other_quote))), None);
+        assert!(parse_price_bounds(&["0x1=90:110".to_string()]).is_err());
+    }
 }
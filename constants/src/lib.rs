@@ -110,5 +110,9 @@ pub const TESTNET_DEPLOY_BLOCK: u64 = 0;
 /// match computation with a peer
 pub const HANDSHAKE_STATUS_TOPIC: &str = "handshakes";
 
+/// The topic published to when the local raft node observes a change in
+/// cluster leadership
+pub const RAFT_LEADERSHIP_TOPIC: &str = "raft-leadership";
+
 /// The topic published to when a state change occurs on an order
 pub const ORDER_STATE_CHANGE_TOPIC: &str = "order-state";
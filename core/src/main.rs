@@ -13,7 +13,7 @@
 mod error;
 mod setup;
 
-use std::{process::exit, thread, time::Duration};
+use std::{process::exit, sync::Arc, thread, time::Duration};
 
 use api_server::worker::{ApiServer, ApiServerConfig};
 use arbitrum_client::client::{ArbitrumClient, ArbitrumClientConfig};
@@ -24,11 +24,11 @@ use external_api::bus_message::SystemBusMessage;
 use gossip_server::{server::GossipServer, worker::GossipServerConfig};
 use handshake_manager::{manager::HandshakeManager, worker::HandshakeManagerConfig};
 use job_types::gossip_server::new_gossip_server_queue;
-use job_types::handshake_manager::new_handshake_manager_queue;
+use job_types::handshake_manager::{new_handshake_manager_queue, new_order_match_stats_ledger};
 use job_types::network_manager::new_network_manager_queue;
 use job_types::price_reporter::new_price_reporter_queue;
 use job_types::proof_manager::new_proof_manager_queue;
-use job_types::task_driver::new_task_driver_queue;
+use job_types::task_driver::{new_match_gas_ledger, new_task_driver_queue, new_task_registry};
 use network_manager::{manager::NetworkManager, worker::NetworkManagerConfig};
 use price_reporter::{manager::PriceReporter, worker::PriceReporterConfig};
 use proof_manager::{proof_manager::ProofManager, worker::ProofManagerConfig};
@@ -139,11 +139,28 @@ async fn main() -> Result<(), CoordinatorError> {
         darkpool_addr: args.contract_address.clone(),
         chain: args.chain_id,
         rpc_url: args.rpc_url.unwrap(),
+        fallback_rpc_urls: args.fallback_rpc_urls.clone(),
+        rpc_timeout_ms: args.rpc_timeout_ms,
+        event_block_window: args.event_block_window,
+        gas_limit_ceiling: args.gas_limit_ceiling,
         arb_priv_key: args.arbitrum_private_key.clone(),
     })
     .await
     .map_err(|e| CoordinatorError::Arbitrum(e.to_string()))?;
 
+    // Refuse to start against a contract whose Merkle tree has a different
+    // height, the relayer's openings would not verify
+    arbitrum_client.validate_merkle_height().await.map_err(err_str!(CoordinatorError::Arbitrum))?;
+
+    // Refuse to start if the contract routes protocol fees elsewhere than the
+    // operator expects, settlements would pay fees to an unexpected address
+    if let Some(expected) = args.protocol_fee_recipient {
+        arbitrum_client
+            .validate_fee_recipient(expected)
+            .await
+            .map_err(err_str!(CoordinatorError::Arbitrum))?;
+    }
+
     // ----------------
     // | Worker Setup |
     // ----------------
@@ -152,6 +169,9 @@ async fn main() -> Result<(), CoordinatorError> {
 
     // Build a task driver that may be used to spawn long-lived asynchronous tasks
     // that are common among workers
+    let task_registry = new_task_registry();
+    let match_gas_ledger = new_match_gas_ledger();
+    let order_stats = new_order_match_stats_ledger();
     let task_driver_config = TaskDriverConfig::new(
         task_receiver,
         arbitrum_client.clone(),
@@ -159,6 +179,14 @@ async fn main() -> Result<(), CoordinatorError> {
         proof_generation_worker_sender.clone(),
         system_bus.clone(),
         global_state.clone(),
+        task_registry.clone(),
+        args.match_confirmation_depth,
+        match_gas_ledger.clone(),
+        args.match_batch_window_ms,
+        args.max_concurrent_wallet_lookups,
+        args.reblind_idle_interval_ms,
+        args.order_ttl_ms,
+        args.max_task_restarts,
     );
     let mut task_driver = TaskDriver::new(task_driver_config).expect("failed to build task driver");
     task_driver.start().expect("failed to start task driver");
@@ -171,6 +199,7 @@ async fn main() -> Result<(), CoordinatorError> {
     let (proof_manager_cancel_sender, proof_manager_cancel_receiver) = watch::channel(());
     let mut proof_manager = ProofManager::new(ProofManagerConfig {
         job_queue: proof_generation_worker_receiver,
+        num_threads: args.proof_manager_threads,
         cancel_channel: proof_manager_cancel_receiver,
     })
     .expect("failed to build proof generation module");
@@ -194,6 +223,7 @@ async fn main() -> Result<(), CoordinatorError> {
         port: args.p2p_port,
         bind_addr: args.bind_addr,
         known_public_addr: args.public_ip,
+        max_message_bytes: args.max_message_bytes,
         allow_local: args.allow_local,
         cluster_id: args.cluster_id.clone(),
         cluster_keypair: Some(args.cluster_keypair),
@@ -220,7 +250,8 @@ async fn main() -> Result<(), CoordinatorError> {
         local_addr: network_manager.local_addr.clone(),
         cluster_id: args.cluster_id,
         bootstrap_servers: args.bootstrap_servers,
-        arbitrum_client: arbitrum_client.clone(),
+        max_missed_heartbeats: args.max_missed_heartbeats,
+        arbitrum_client: Arc::new(arbitrum_client.clone()),
         global_state: global_state.clone(),
         job_sender: gossip_worker_sender.clone(),
         job_receiver: Some(gossip_worker_receiver).into(),
@@ -243,6 +274,18 @@ async fn main() -> Result<(), CoordinatorError> {
         job_sender: handshake_worker_sender.clone(),
         task_queue: task_sender.clone(),
         system_bus: system_bus.clone(),
+        min_match_base_amounts: args.min_match_base_amounts.clone(),
+        handshake_cache_size: args.handshake_cache_size,
+        price_agreement_timeout_ms: args.price_agreement_timeout_ms,
+        price_agreement_retries: args.price_agreement_retries,
+        price_selection: args.price_selection,
+        price_selection_spread_bps: args.price_selection_spread_bps,
+        prefer_internal_matches: args.prefer_internal_matches,
+        allow_self_trades: args.allow_self_trades,
+        min_rest_ms: args.min_order_rest_ms,
+        handshake_rate_limit: args.handshake_rate_limit,
+        executor_threads: args.handshake_executor_threads,
+        order_stats: order_stats.clone(),
         cancel_channel: handshake_cancel_receiver,
     })
     .expect("failed to build handshake manager");
@@ -262,6 +305,19 @@ async fn main() -> Result<(), CoordinatorError> {
         eth_websocket_addr: args.eth_websocket_addr,
         disabled: args.disable_price_reporter,
         disabled_exchanges: args.disabled_exchanges,
+        exchange_weights: args.exchange_weights,
+        price_overrides: args.price_overrides,
+        price_bounds: args.price_bounds,
+        ws_max_frame_size: args.exchange_ws_max_frame_size,
+        ws_max_buffer_size: args.exchange_ws_max_buffer_size,
+        ema_half_life_ms: args.price_ema_half_life_ms,
+        feed_grace_period_ms: args.price_feed_grace_period_ms,
+        median_concurrency: args.price_median_concurrency,
+        keepalive_interval_ms: args.exchange_keepalive_interval_ms,
+        max_missed_pongs: args.exchange_max_missed_pongs,
+        allow_insecure_ws: args.allow_insecure_exchange_ws,
+        exchange_hosts: args.exchange_hosts,
+        exchange_cert_pins: args.exchange_cert_pins,
     })
     .expect("failed to build price reporter manager");
     price_reporter_manager.start().expect("failed to start price reporter manager");
@@ -273,6 +329,7 @@ async fn main() -> Result<(), CoordinatorError> {
     let (chain_listener_cancel_sender, chain_listener_cancel_receiver) = watch::channel(());
     let mut chain_listener = OnChainEventListener::new(OnChainEventListenerConfig {
         max_root_staleness: args.max_merkle_staleness,
+        proof_expiry_margin: args.proof_expiry_margin,
         arbitrum_client: arbitrum_client.clone(),
         global_state: global_state.clone(),
         handshake_manager_job_queue: handshake_worker_sender,
@@ -293,9 +350,16 @@ async fn main() -> Result<(), CoordinatorError> {
         websocket_port: args.websocket_port,
         network_sender: network_sender.clone(),
         global_state: global_state.clone(),
+        task_registry,
+        match_gas_ledger,
+        order_stats,
+        admin_api_key: args.admin_api_key.clone(),
         system_bus,
         price_reporter_work_queue: price_reporter_worker_sender,
         proof_generation_work_queue: proof_generation_worker_sender,
+        proof_queue_high_water_mark: args.proof_queue_high_water_mark,
+        min_order_amounts: args.min_order_amounts.clone(),
+        max_order_amounts: args.max_order_amounts.clone(),
         cancel_channel: api_cancel_receiver,
     })
     .expect("failed to build api server");
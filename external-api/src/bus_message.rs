@@ -1,16 +1,18 @@
 //! Defines types broadcast onto the system bus and thereby websockets
 
+use circuit_types::Amount;
 use common::types::{
     exchange::PriceReport,
     gossip::{PeerInfo, WrappedPeerId},
-    network_order::NetworkOrder,
+    handshake::{MatchReceipt, PairMatchVolume, PriceSelection},
+    network_order::{CancellationReason, NetworkOrder},
     tasks::TaskIdentifier,
     token::Token,
     wallet::{OrderIdentifier, Wallet as StateWallet, WalletIdentifier},
 };
 use serde::Serialize;
 
-use crate::types::ApiWallet;
+use crate::{http::wallet::WalletUpdateDelta, types::ApiWallet};
 
 // ----------------------------
 // | System Bus Message Types |
@@ -21,6 +23,12 @@ pub const NETWORK_TOPOLOGY_TOPIC: &str = "network-topology";
 /// The system bus topic published to for all wallet updates, not those given by
 /// Id
 pub const ALL_WALLET_UPDATES_TOPIC: &str = "wallet-updates";
+/// The system bus topic published to when a pair's price feeds go down or
+/// recover
+pub const PRICE_FEED_STATUS_TOPIC: &str = "price-feed-status";
+/// The system bus topic on which the volume matched on each pair is
+/// periodically published
+pub const MATCH_VOLUME_TOPIC: &str = "match-volume";
 
 /// Get the topic name for a given wallet
 pub fn wallet_topic_name(wallet_id: &WalletIdentifier) -> String {
@@ -60,6 +68,55 @@ pub enum SystemBusMessage {
         /// The timestamp of the event
         timestamp: u64,
     },
+    /// A message indicating that a match from a handshake has been settled
+    MatchSettled {
+        /// The receipt of the settled match
+        receipt: MatchReceipt,
+        /// The timestamp of the event
+        timestamp: u64,
+    },
+    /// A message carrying the cumulative volume matched on each pair
+    MatchVolume {
+        /// The volume matched on each pair
+        volumes: Vec<PairMatchVolume>,
+    },
+    /// A message indicating that a handshake with a peer has failed
+    HandshakeFailed {
+        /// The order_id of the local party
+        local_order_id: OrderIdentifier,
+        /// The order_id of the remote peer
+        peer_order_id: OrderIdentifier,
+        /// The reason the handshake failed
+        reason: String,
+        /// The timestamp of the event
+        timestamp: u64,
+    },
+    /// A message indicating that the internal matching engine has submitted a
+    /// match between two locally managed orders for settlement
+    InternalMatch {
+        /// The order_id of the order the engine was run on
+        taker_order_id: OrderIdentifier,
+        /// The order_id of the resting order it was matched against
+        maker_order_id: OrderIdentifier,
+        /// The price at which the match executes
+        execution_price: f64,
+        /// The policy used to select the execution price
+        price_selection: PriceSelection,
+        /// The timestamp of the event
+        timestamp: u64,
+    },
+
+    // -- Replication -- //
+    /// A message indicating that the local raft node observed a change in
+    /// cluster leadership, including the local node losing leadership
+    RaftLeadershipChanged {
+        /// Whether the local node is now the leader
+        is_leader: bool,
+        /// The raft ID of the new leader, zero while no leader is known
+        leader_id: u64,
+        /// The raft term in which the change was observed
+        term: u64,
+    },
 
     // -- Order Book -- //
     /// A message indicating that a new order has come into the network order
@@ -74,6 +131,25 @@ pub enum SystemBusMessage {
         /// The new state of the order
         order: NetworkOrder,
     },
+    /// A message indicating that a locally managed order has been removed from
+    /// its wallet
+    OrderCancelled {
+        /// The order that was cancelled
+        order_id: OrderIdentifier,
+        /// Why the order was cancelled
+        reason: CancellationReason,
+    },
+    /// A message indicating that a match on a locally managed order has been
+    /// settled into its wallet
+    OrderFilled {
+        /// The order that was filled
+        order_id: OrderIdentifier,
+        /// The base amount filled by the match
+        filled_amount: Amount,
+        /// The base amount remaining on the order, zero if the order was
+        /// filled completely
+        remaining_amount: Amount,
+    },
 
     // -- Network Updates -- //
     /// A new peer has been discovered on the network
@@ -93,6 +169,22 @@ pub enum SystemBusMessage {
     /// A message indicating that a new individual exchange PriceReport has been
     /// published
     PriceReportExchange(PriceReport),
+    /// A message indicating that no source has reported a fresh price for a
+    /// pair for longer than the configured grace period
+    PriceFeedDown {
+        /// The base token of the pair
+        base: Token,
+        /// The quote token of the pair
+        quote: Token,
+    },
+    /// A message indicating that a fresh source has reported on a pair
+    /// previously reported down
+    PriceFeedRestored {
+        /// The base token of the pair
+        base: Token,
+        /// The quote token of the pair
+        quote: Token,
+    },
 
     // -- Tasks -- //
     /// A message indicating that a task has
@@ -110,6 +202,15 @@ pub enum SystemBusMessage {
         wallet: Box<ApiWallet>,
     },
 
+    /// A message carrying the orders and balances changed by a wallet update,
+    /// published once the update is final
+    WalletUpdateDelta {
+        /// The ID of the updated wallet
+        wallet_id: WalletIdentifier,
+        /// The changes made by the update
+        delta: Box<WalletUpdateDelta>,
+    },
+
     /// A message indicating an internal (gossip metadata) update has been
     /// made to a wallet
     InternalWalletUpdate {
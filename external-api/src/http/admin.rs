@@ -0,0 +1,73 @@
+//! Defines API types for admin routes, used by operators to inspect the
+//! relayer
+
+use common::types::{
+    proof_bundles::OrderValidityProofBundle, tasks::MatchGasRecord,
+    wallet::WalletIndexInconsistency,
+};
+use serde::{Deserialize, Serialize};
+
+/// The request type to verify an order validity proof bundle
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VerifyProofRequest {
+    /// The proof bundle to verify
+    pub bundle: OrderValidityProofBundle,
+}
+
+/// The result of verifying a single proof in a bundle
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProofVerificationResult {
+    /// The name of the proof
+    pub proof: String,
+    /// Whether the proof verified
+    pub valid: bool,
+    /// The error verifying the proof, if it is invalid
+    pub error: Option<String>,
+}
+
+/// The response type to a request to verify an order validity proof bundle
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VerifyProofResponse {
+    /// Whether every proof in the bundle verified
+    pub valid: bool,
+    /// The result of verifying each proof in the bundle
+    pub results: Vec<ProofVerificationResult>,
+}
+
+/// The response type to a request to check the consistency of the wallet index
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WalletIndexConsistencyResponse {
+    /// Whether the wallet index is consistent
+    pub consistent: bool,
+    /// The inconsistencies found in the index
+    pub inconsistencies: Vec<WalletIndexInconsistency>,
+}
+
+/// The response type to a request for the gas used by match settlements
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetMatchGasResponse {
+    /// The gas used by each match the local relayer settled, oldest first
+    pub matches: Vec<MatchGasRecord>,
+}
+
+/// The request type to drain the relayer ahead of a shutdown
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DrainRequest {
+    /// The time in milliseconds to wait for in-flight tasks to complete,
+    /// defaulting to the relayer's drain timeout if not set
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
+/// The response type to a request to drain the relayer
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DrainResponse {
+    /// Whether the relayer may be stopped without orphaning in-flight work
+    pub ready_to_stop: bool,
+    /// The number of local tasks still in flight when the drain finished
+    pub remaining_tasks: usize,
+    /// The number of handshakes still in flight when the drain finished
+    pub remaining_handshakes: usize,
+}
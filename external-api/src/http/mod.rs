@@ -1,7 +1,9 @@
 //! Groups API types for the HTTP API
 
+use common::types::handshake::PairMatchVolume;
 use serde::{Deserialize, Serialize};
 
+pub mod admin;
 pub mod network;
 pub mod order_book;
 pub mod price_report;
@@ -14,3 +16,14 @@ pub struct PingResponse {
     /// The timestamp when the response is sent
     pub timestamp: u128,
 }
+
+/// The response type to fetch the volume matched on each pair
+///
+/// The volumes are those matched by the queried node since it last started;
+/// they are held in memory, are not shared across the cluster, and reset on
+/// restart
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetMatchVolumeResponse {
+    /// The volume matched by the node on each pair since it started
+    pub volumes: Vec<PairMatchVolume>,
+}
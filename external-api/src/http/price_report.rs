@@ -10,6 +10,7 @@ use serde::{Deserialize, Serialize};
 
 /// A request to get the health of each exchange for a given token pair
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct GetExchangeHealthStatesRequest {
     /// The base token
     pub base_token: Token,
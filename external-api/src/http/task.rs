@@ -1,6 +1,6 @@
 //! Defines API types for task status introspection
 
-use common::types::tasks::{QueuedTask, QueuedTaskState, TaskIdentifier};
+use common::types::tasks::{QueuedTask, QueuedTaskState, RunningTaskInfo, TaskIdentifier};
 use serde::Serialize;
 
 /// The response type for a request to fetch task status
@@ -38,3 +38,11 @@ pub struct TaskQueueListResponse {
     /// The list of tasks on a wallet
     pub tasks: Vec<TaskStatus>,
 }
+
+/// The response type for a request to list the tasks running in the task
+/// driver
+#[derive(Clone, Debug, Serialize)]
+pub struct GetRunningTasksResponse {
+    /// The running tasks, ordered by start time
+    pub tasks: Vec<RunningTaskInfo>,
+}
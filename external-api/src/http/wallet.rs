@@ -1,20 +1,63 @@
 //! Groups API type definitions for wallet API operations
 
-use circuit_types::balance::Balance;
-use common::types::{tasks::TaskIdentifier, wallet::WalletIdentifier};
+use common::types::{
+    handshake::OrderMatchStats,
+    tasks::{TaskIdentifier, UpdateNonceAuth},
+    wallet::{OrderIdentifier, Wallet, WalletIdentifier},
+};
 use num_bigint::BigUint;
 use serde::{Deserialize, Serialize};
+use util::hex::biguint_to_hex_string;
 use uuid::Uuid;
 
 use crate::{
     deserialize_biguint_from_hex_string, serialize_biguint_to_hex_string,
-    types::{ApiKeychain, ApiOrder, ApiWallet},
+    types::{ApiBalance, ApiKeychain, ApiOrder, ApiScopedKey, ApiWallet},
 };
 
 // --------------------
 // | Wallet API Types |
 // --------------------
 
+/// The minimal set of changes to a wallet's plaintext state made by an update
+///
+/// The proof of an update covers the whole wallet, but clients and indexers
+/// may apply the delta to a cached wallet rather than refetching it
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct WalletUpdateDelta {
+    /// The orders added or modified by the update
+    pub updated_orders: Vec<ApiOrder>,
+    /// The IDs of the orders removed by the update
+    pub removed_orders: Vec<OrderIdentifier>,
+    /// The balances added or modified by the update
+    pub updated_balances: Vec<ApiBalance>,
+    /// The mints of the balances removed by the update, as hex strings
+    pub removed_balances: Vec<String>,
+}
+
+impl WalletUpdateDelta {
+    /// Compute the delta from the old wallet to the new wallet
+    pub fn new(old_wallet: &Wallet, new_wallet: &Wallet) -> Self {
+        let diff = old_wallet.diff(new_wallet);
+        let mut delta = Self::default();
+        for id in diff.orders.into_iter() {
+            match new_wallet.orders.get(&id) {
+                Some(order) => delta.updated_orders.push((id, order.clone()).into()),
+                None => delta.removed_orders.push(id),
+            }
+        }
+
+        for mint in diff.balances.into_iter() {
+            match new_wallet.balances.get(&mint) {
+                Some(balance) => delta.updated_balances.push(balance.clone().into()),
+                None => delta.removed_balances.push(biguint_to_hex_string(&mint)),
+            }
+        }
+
+        delta
+    }
+}
+
 /// The response type to get a wallet's information
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct GetWalletResponse {
@@ -24,6 +67,7 @@ pub struct GetWalletResponse {
 
 /// The request type to create a new wallet
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct CreateWalletRequest {
     /// The wallet info to be created
     pub wallet: ApiWallet,
@@ -41,6 +85,7 @@ pub struct CreateWalletResponse {
 
 /// The request type to find a wallet in contract storage and begin managing it
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct FindWalletRequest {
     /// The ID to handle the wallet by
     pub wallet_id: WalletIdentifier,
@@ -61,6 +106,22 @@ pub struct FindWalletResponse {
     pub task_id: TaskIdentifier,
 }
 
+/// The request type to grant a scoped key on a wallet
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GrantScopedKeyRequest {
+    /// The key to grant, replacing any existing grant to the same key
+    pub key: ApiScopedKey,
+}
+
+/// The request type to revoke a scoped key on a wallet
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RevokeScopedKeyRequest {
+    /// The public key to revoke, hex encoded
+    pub key: String,
+}
+
 // ---------------------------
 // | Wallet Orders API Types |
 // ---------------------------
@@ -78,8 +139,16 @@ pub struct GetOrderByIdResponse {
     pub order: ApiOrder,
 }
 
+/// The response type to get the match statistics of an order
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetOrderStatsResponse {
+    /// The match statistics of the order
+    pub stats: OrderMatchStats,
+}
+
 /// The request type to add a new order to a given wallet
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct CreateOrderRequest {
     /// The order to be created
     pub order: ApiOrder,
@@ -87,6 +156,10 @@ pub struct CreateOrderRequest {
     /// VALID WALLET UPDATE by `sk_root`. This allows the contract
     /// to guarantee that the wallet updates are properly authorized
     pub statement_sig: Vec<u8>,
+    /// The owner's authorization of the update at the wallet's next update
+    /// nonce, updates without it are not bound to a nonce
+    #[serde(default)]
+    pub nonce_auth: Option<UpdateNonceAuth>,
 }
 
 /// The response type to a request that adds a new order to a wallet
@@ -96,10 +169,13 @@ pub struct CreateOrderResponse {
     pub id: Uuid,
     /// The ID of the internal task created for the operation
     pub task_id: TaskIdentifier,
+    /// The changes made to the wallet by the update
+    pub delta: WalletUpdateDelta,
 }
 
 /// The request type to update an order
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct UpdateOrderRequest {
     /// The order to be updated
     pub order: ApiOrder,
@@ -107,6 +183,10 @@ pub struct UpdateOrderRequest {
     /// VALID WALLET UPDATE by `sk_root`. This allows the contract
     /// to guarantee that the wallet updates are properly authorized
     pub statement_sig: Vec<u8>,
+    /// The owner's authorization of the update at the wallet's next update
+    /// nonce, updates without it are not bound to a nonce
+    #[serde(default)]
+    pub nonce_auth: Option<UpdateNonceAuth>,
 }
 
 /// The response type to update an order
@@ -114,15 +194,22 @@ pub struct UpdateOrderRequest {
 pub struct UpdateOrderResponse {
     /// The ID of the task allocated for this request
     pub task_id: TaskIdentifier,
+    /// The changes made to the wallet by the update
+    pub delta: WalletUpdateDelta,
 }
 
 /// The request type to cancel a given order
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct CancelOrderRequest {
     /// A signature of the circuit statement used in the proof of
     /// VALID WALLET UPDATE by `sk_root`. This allows the contract
     /// to guarantee that the wallet updates are properly authorized
     pub statement_sig: Vec<u8>,
+    /// The owner's authorization of the update at the wallet's next update
+    /// nonce, updates without it are not bound to a nonce
+    #[serde(default)]
+    pub nonce_auth: Option<UpdateNonceAuth>,
 }
 
 /// The response type to a request to cancel a given order
@@ -132,6 +219,44 @@ pub struct CancelOrderResponse {
     pub task_id: TaskIdentifier,
     /// The order information of the now-cancelled order
     pub order: ApiOrder,
+    /// The changes made to the wallet by the update
+    pub delta: WalletUpdateDelta,
+}
+
+/// The request type to cancel all orders in a wallet
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CancelAllOrdersRequest {
+    /// A signature of the circuit statement used in the proof of
+    /// VALID WALLET UPDATE by `sk_root`. This allows the contract
+    /// to guarantee that the wallet updates are properly authorized
+    pub statement_sig: Vec<u8>,
+    /// The owner's authorization of the update at the wallet's next update
+    /// nonce, updates without it are not bound to a nonce
+    #[serde(default)]
+    pub nonce_auth: Option<UpdateNonceAuth>,
+}
+
+/// The response type to a request to cancel all orders in a wallet
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CancelAllOrdersResponse {
+    /// The ID of the task allocated for this request
+    pub task_id: TaskIdentifier,
+    /// The IDs of the cancelled orders
+    pub order_ids: Vec<OrderIdentifier>,
+    /// The changes made to the wallet by the update
+    pub delta: WalletUpdateDelta,
+}
+
+/// The response type to a request to rotate a wallet's CSPRNG seeds
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RotateSeedResponse {
+    /// The ID of the task allocated for this request
+    pub task_id: TaskIdentifier,
+    /// The new seed of the wallet's share CSPRNG, hex encoded
+    pub share_seed: String,
+    /// The new seed of the wallet's blinder CSPRNG, hex encoded
+    pub blinder_seed: String,
 }
 
 // -----------------------------
@@ -142,18 +267,19 @@ pub struct CancelOrderResponse {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct GetBalancesResponse {
     /// The balances in the given wallet
-    pub balances: Vec<Balance>,
+    pub balances: Vec<ApiBalance>,
 }
 
 /// The response type to get a single balance by mint
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct GetBalanceByMintResponse {
     /// The requested balance
-    pub balance: Balance,
+    pub balance: ApiBalance,
 }
 
 /// The request type to deposit a balance into the darkpool
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct DepositBalanceRequest {
     /// The arbitrum account contract address to send the balance from
     #[serde(
@@ -176,6 +302,10 @@ pub struct DepositBalanceRequest {
     /// TODO: For now this is just a blob, we will add this feature in
     /// a follow up
     pub wallet_commitment_sig: Vec<u8>,
+    /// The owner's authorization of the update at the wallet's next update
+    /// nonce, updates without it are not bound to a nonce
+    #[serde(default)]
+    pub nonce_auth: Option<UpdateNonceAuth>,
     /// The nonce used in the associated Permit2 permit
     pub permit_nonce: BigUint,
     /// The deadline used in the associated Permit2 permit
@@ -192,10 +322,13 @@ pub struct DepositBalanceResponse {
     /// The ID of the internal task created for this request
     /// May be used by the client to query task status
     pub task_id: TaskIdentifier,
+    /// The changes made to the wallet by the update
+    pub delta: WalletUpdateDelta,
 }
 
 /// The request type to withdraw a balance from the Darkpool
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct WithdrawBalanceRequest {
     /// The destination address to withdraw the balance to
     #[serde(
@@ -212,6 +345,10 @@ pub struct WithdrawBalanceRequest {
     /// TODO: For now this is just a blob, we will add this feature in
     /// a follow up
     pub wallet_commitment_sig: Vec<u8>,
+    /// The owner's authorization of the update at the wallet's next update
+    /// nonce, updates without it are not bound to a nonce
+    #[serde(default)]
+    pub nonce_auth: Option<UpdateNonceAuth>,
     /// A signature over the external transfer, allowing the contract
     /// to guarantee that the withdrawal is directed at the correct
     /// recipient
@@ -223,10 +360,36 @@ pub struct WithdrawBalanceRequest {
 pub struct WithdrawBalanceResponse {
     /// The ID of the task allocated for this operation
     pub task_id: TaskIdentifier,
+    /// The changes made to the wallet by the update
+    pub delta: WalletUpdateDelta,
+}
+
+/// The request type to remove a zero balance from a wallet
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RemoveBalanceRequest {
+    /// A signature of the circuit statement used in the proof of
+    /// VALID WALLET UPDATE by `sk_root`. This allows the contract
+    /// to guarantee that the wallet updates are properly authorized
+    pub statement_sig: Vec<u8>,
+    /// The owner's authorization of the update at the wallet's next update
+    /// nonce, updates without it are not bound to a nonce
+    #[serde(default)]
+    pub nonce_auth: Option<UpdateNonceAuth>,
+}
+
+/// The response type to a request to remove a balance
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RemoveBalanceResponse {
+    /// The ID of the task allocated for this operation
+    pub task_id: TaskIdentifier,
+    /// The changes made to the wallet by the update
+    pub delta: WalletUpdateDelta,
 }
 
 /// The request type to create an internal transfer to another darkpool wallet
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct InternalTransferRequest {
     /// A signature of the circuit statement used in the proof of
     /// VALID WALLET UPDATE by `sk_root`. This allows the contract
@@ -235,6 +398,10 @@ pub struct InternalTransferRequest {
     /// TODO: For now this is just a blob, we will add this feature in
     /// a follow up
     pub statement_sig: Vec<u8>,
+    /// The owner's authorization of the update at the wallet's next update
+    /// nonce, updates without it are not bound to a nonce
+    #[serde(default)]
+    pub nonce_auth: Option<UpdateNonceAuth>,
     /// The recipient's settle key
     #[serde(
         serialize_with = "serialize_biguint_to_hex_string",
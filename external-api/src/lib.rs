@@ -73,8 +73,79 @@ where
 
 #[cfg(test)]
 mod test {
+    use circuit_types::{fixed_point::FixedPoint, order::OrderSide};
+    use num_bigint::BigUint;
+    use serde_json::{json, Value};
+    use uuid::Uuid;
+
+    use crate::{
+        http::wallet::{CancelOrderRequest, GetBalancesResponse, GetOrdersResponse},
+        types::{ApiBalance, ApiOrder, ApiOrderType},
+    };
+
     use super::EmptyRequestResponse;
 
+    /// Serialize a default API order with its side replaced by the given value
+    fn order_json_with_side(side: Value) -> Value {
+        let mut order = serde_json::to_value(ApiOrder::default()).unwrap();
+        order["side"] = side;
+        order
+    }
+
+    /// A fixed order used to snapshot the serialized order shape
+    fn snapshot_order() -> ApiOrder {
+        ApiOrder {
+            id: Uuid::nil(),
+            quote_mint: BigUint::from(1u8),
+            base_mint: BigUint::from(2u8),
+            side: OrderSide::Sell,
+            type_: ApiOrderType::Midpoint,
+            worst_case_price: FixedPoint::from_integer(10),
+            amount: 100,
+            decimals: Some(6),
+            amount_decimal: Some("0.0001".to_string()),
+        }
+    }
+
+    /// The serialized shape of `snapshot_order`
+    fn snapshot_order_json() -> Value {
+        json!({
+            "id": "00000000-0000-0000-0000-000000000000",
+            "quote_mint": "0x1",
+            "base_mint": "0x2",
+            "side": "Sell",
+            "type": "Midpoint",
+            "worst_case_price": 10.0,
+            "amount": 100,
+            "decimals": 6,
+            "amount_decimal": "0.0001",
+        })
+    }
+
+    /// A fixed balance used to snapshot the serialized balance shape
+    fn snapshot_balance() -> ApiBalance {
+        ApiBalance {
+            mint: BigUint::from(2u8),
+            amount: 100,
+            relayer_fee_balance: 1,
+            protocol_fee_balance: 2,
+            decimals: Some(6),
+            amount_decimal: Some("0.0001".to_string()),
+        }
+    }
+
+    /// The serialized shape of `snapshot_balance`
+    fn snapshot_balance_json() -> Value {
+        json!({
+            "mint": "0x2",
+            "amount": 100,
+            "relayer_fee_balance": 1,
+            "protocol_fee_balance": 2,
+            "decimals": 6,
+            "amount_decimal": "0.0001",
+        })
+    }
+
     /// Tests empty request/response serialization, expected behavior is that it
     /// serializes to and from the string "null"
     #[test]
@@ -86,4 +157,78 @@ mod test {
         // Test deserialization from empty json struct encoded as a string
         let _req: EmptyRequestResponse = serde_json::from_str("null").unwrap();
     }
+
+    /// Tests that an order's side deserializes from its variant name
+    #[test]
+    fn test_order_side_valid() {
+        for side in [OrderSide::Buy, OrderSide::Sell] {
+            let json = order_json_with_side(serde_json::to_value(side).unwrap());
+            let order: ApiOrder = serde_json::from_value(json).unwrap();
+            assert_eq!(order.side, side);
+        }
+    }
+
+    /// Tests that an order's side is rejected at deserialization when given
+    /// numerically or as an unknown variant, so that an out-of-range side
+    /// never reaches proof generation
+    #[test]
+    fn test_order_side_invalid() {
+        for side in [json!(0), json!(1), json!(2), json!("Short")] {
+            let json = order_json_with_side(side);
+            assert!(serde_json::from_value::<ApiOrder>(json).is_err());
+        }
+    }
+
+    /// Tests that the serialized shape of an order is stable
+    ///
+    /// Integrators depend on this shape, a failure here is a breaking change
+    /// to the API
+    #[test]
+    fn test_order_snapshot() {
+        let order = serde_json::to_value(snapshot_order()).unwrap();
+        assert_eq!(order, snapshot_order_json());
+
+        // The token annotations are omitted when not known
+        let order = ApiOrder { decimals: None, amount_decimal: None, ..snapshot_order() };
+        let mut expected = snapshot_order_json();
+        expected.as_object_mut().unwrap().remove("decimals");
+        expected.as_object_mut().unwrap().remove("amount_decimal");
+        assert_eq!(serde_json::to_value(order).unwrap(), expected);
+    }
+
+    /// Tests that the serialized shape of a balance is stable
+    #[test]
+    fn test_balance_snapshot() {
+        let balance = serde_json::to_value(snapshot_balance()).unwrap();
+        assert_eq!(balance, snapshot_balance_json());
+
+        // The balance round trips, including amounts beyond the range of a u64
+        let balance = ApiBalance { amount: u128::MAX, ..snapshot_balance() };
+        let json = serde_json::to_string(&balance).unwrap();
+        assert_eq!(serde_json::from_str::<ApiBalance>(&json).unwrap(), balance);
+    }
+
+    /// Tests that the serialized shapes of the order and balance getter
+    /// responses are stable
+    #[test]
+    fn test_response_snapshots() {
+        let orders = GetOrdersResponse { orders: vec![snapshot_order()] };
+        let expected = json!({ "orders": [snapshot_order_json()] });
+        assert_eq!(serde_json::to_value(orders).unwrap(), expected);
+
+        let balances = GetBalancesResponse { balances: vec![snapshot_balance()] };
+        let expected = json!({ "balances": [snapshot_balance_json()] });
+        assert_eq!(serde_json::to_value(balances).unwrap(), expected);
+    }
+
+    /// Tests that request types reject unknown fields rather than silently
+    /// dropping them
+    #[test]
+    fn test_request_unknown_fields() {
+        let req = json!({ "statement_sig": [1, 2, 3] });
+        assert!(serde_json::from_value::<CancelOrderRequest>(req).is_ok());
+
+        let req = json!({ "statement_sig": [1, 2, 3], "statment_sig": [] });
+        assert!(serde_json::from_value::<CancelOrderRequest>(req).is_err());
+    }
 }
@@ -14,7 +14,8 @@ use circuit_types::{
 use common::types::{
     gossip::PeerInfo as IndexedPeerInfo,
     network_order::{NetworkOrder, NetworkOrderState},
-    wallet::{KeyChain, OrderIdentifier, PrivateKeyChain, Wallet},
+    token::Token,
+    wallet::{KeyChain, KeyScope, OrderIdentifier, PrivateKeyChain, ScopedKey, Wallet},
 };
 use itertools::Itertools;
 use num_bigint::BigUint;
@@ -59,6 +60,10 @@ pub struct ApiWallet {
     pub private_shares: Vec<BigUint>,
     /// The wallet blinder, used to blind wallet secret shares
     pub blinder: BigUint,
+    /// The number of updates applied to the wallet, the next update may be
+    /// authorized for this nonce plus one
+    #[serde(default)]
+    pub update_nonce: u64,
 }
 
 /// Conversion from a wallet that has been indexed in the global state to the
@@ -90,6 +95,7 @@ impl From<Wallet> for ApiWallet {
             blinded_public_shares,
             private_shares,
             blinder: scalar_to_biguint(&wallet.blinder),
+            update_nonce: wallet.update_nonce,
         }
     }
 }
@@ -124,6 +130,7 @@ impl TryFrom<ApiWallet> for Wallet {
             blinded_public_shares,
             private_shares,
             merkle_proof: None,
+            update_nonce: 0,
             merkle_staleness: Default::default(),
         })
     }
@@ -158,6 +165,25 @@ pub struct ApiOrder {
     pub worst_case_price: FixedPoint,
     /// The order size
     pub amount: Amount,
+    /// The number of decimals of the base token, included on request when
+    /// the token is known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decimals: Option<u8>,
+    /// The order size as a decimal string in units of the base token,
+    /// included on request when the token is known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount_decimal: Option<String>,
+}
+
+impl ApiOrder {
+    /// Annotate the order with the base token's decimals and the decimal
+    /// form of its size
+    pub fn with_decimals(mut self) -> Self {
+        let token = Token::from_addr_biguint(&self.base_mint);
+        self.decimals = token.get_decimals();
+        self.amount_decimal = token.format_decimal(self.amount);
+        self
+    }
 }
 
 impl From<(OrderIdentifier, Order)> for ApiOrder {
@@ -170,6 +196,8 @@ impl From<(OrderIdentifier, Order)> for ApiOrder {
             type_: ApiOrderType::Midpoint,
             worst_case_price: order.worst_case_price,
             amount: order.amount,
+            decimals: None,
+            amount_decimal: None,
         }
     }
 }
@@ -186,6 +214,69 @@ impl From<ApiOrder> for Order {
     }
 }
 
+/// A balance in a wallet, optionally annotated with its token's decimals
+///
+/// The balance's fields are written out rather than flattened, `serde` cannot
+/// deserialize `u128` fields through a flattened struct
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiBalance {
+    /// The mint (ERC-20 token address) of the token in the balance
+    #[serde(
+        serialize_with = "serialize_biguint_to_hex_string",
+        deserialize_with = "deserialize_biguint_from_hex_string"
+    )]
+    pub mint: BigUint,
+    /// The amount of the given token stored in this balance
+    pub amount: Amount,
+    /// The amount of this balance owed to the managing relayer cluster
+    pub relayer_fee_balance: Amount,
+    /// The amount of this balance owed to the protocol
+    pub protocol_fee_balance: Amount,
+    /// The number of decimals of the balance's token, included on request
+    /// when the token is known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decimals: Option<u8>,
+    /// The balance's amount as a decimal string, included on request when
+    /// the token is known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount_decimal: Option<String>,
+}
+
+impl ApiBalance {
+    /// Annotate the balance with its token's decimals and the decimal form of
+    /// its amount
+    pub fn with_decimals(mut self) -> Self {
+        let token = Token::from_addr_biguint(&self.mint);
+        self.decimals = token.get_decimals();
+        self.amount_decimal = token.format_decimal(self.amount);
+        self
+    }
+}
+
+impl From<Balance> for ApiBalance {
+    fn from(balance: Balance) -> Self {
+        ApiBalance {
+            mint: balance.mint,
+            amount: balance.amount,
+            relayer_fee_balance: balance.relayer_fee_balance,
+            protocol_fee_balance: balance.protocol_fee_balance,
+            decimals: None,
+            amount_decimal: None,
+        }
+    }
+}
+
+impl From<ApiBalance> for Balance {
+    fn from(balance: ApiBalance) -> Self {
+        Balance {
+            mint: balance.mint,
+            amount: balance.amount,
+            relayer_fee_balance: balance.relayer_fee_balance,
+            protocol_fee_balance: balance.protocol_fee_balance,
+        }
+    }
+}
+
 /// The type of order, currently limit or midpoint
 #[derive(Clone, Default, Debug, Serialize, Deserialize)]
 pub enum ApiOrderType {
@@ -230,7 +321,7 @@ impl From<NetworkOrder> for ApiNetworkOrder {
     fn from(order: NetworkOrder) -> Self {
         ApiNetworkOrder {
             id: order.id,
-            public_share_nullifier: scalar_to_biguint(&order.public_share_nullifier),
+            public_share_nullifier: scalar_to_biguint(&order.public_share_nullifier.inner()),
             local: order.local,
             cluster: order.cluster.to_string(),
             state: order.state,
@@ -248,6 +339,9 @@ pub struct ApiKeychain {
     pub public_keys: ApiPublicKeychain,
     /// The private keychain
     pub private_keys: ApiPrivateKeychain,
+    /// The scoped sub-keys of the wallet
+    #[serde(default)]
+    pub scoped_keys: Vec<ApiScopedKey>,
 }
 
 /// A public keychain for the API wallet
@@ -268,6 +362,41 @@ pub struct ApiPrivateKeychain {
     pub sk_match: String,
 }
 
+/// A scoped sub-key for the API wallet
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ApiScopedKey {
+    /// The public key that signs requests
+    pub key: String,
+    /// The operations the key is authorized for
+    pub scopes: Vec<KeyScope>,
+    /// The time in milliseconds since the epoch after which the key is no
+    /// longer accepted, or `None` if the key does not expire
+    #[serde(default)]
+    pub expiry: Option<u64>,
+}
+
+impl From<ScopedKey> for ApiScopedKey {
+    fn from(key: ScopedKey) -> Self {
+        Self {
+            key: public_sign_key_to_hex_string(&key.key),
+            scopes: key.scopes,
+            expiry: key.expiry,
+        }
+    }
+}
+
+impl TryFrom<ApiScopedKey> for ScopedKey {
+    type Error = String;
+
+    fn try_from(key: ApiScopedKey) -> Result<Self, Self::Error> {
+        Ok(ScopedKey {
+            key: public_sign_key_from_hex_string(&key.key)?,
+            scopes: key.scopes,
+            expiry: key.expiry,
+        })
+    }
+}
+
 impl From<KeyChain> for ApiKeychain {
     fn from(keys: KeyChain) -> Self {
         Self {
@@ -279,6 +408,7 @@ impl From<KeyChain> for ApiKeychain {
                 sk_root: keys.secret_keys.sk_root.map(|k| nonnative_scalar_to_hex_string(&k)),
                 sk_match: scalar_to_hex_string(&keys.secret_keys.sk_match.key),
             },
+            scoped_keys: keys.scoped_keys.into_iter().map(ApiScopedKey::from).collect(),
         }
     }
 }
@@ -304,6 +434,11 @@ impl TryFrom<ApiKeychain> for KeyChain {
                     key: scalar_from_hex_string(&keys.private_keys.sk_match)?,
                 },
             },
+            scoped_keys: keys
+                .scoped_keys
+                .into_iter()
+                .map(ScopedKey::try_from)
+                .collect::<Result<Vec<_>, String>>()?,
         })
     }
 }
@@ -98,6 +98,11 @@ pub enum MatchRejectionReason {
     NoValidityProof,
     /// The prices proposed by the peer are not accepted by the rejecting peer
     NoPriceAgreement,
+    /// The rejecting peer is draining ahead of a shutdown
+    Draining,
+    /// The local order proposed has not rested for the rejecting peer's
+    /// minimum rest duration
+    NotRested,
 }
 
 /// Go forward with a handshake after a proposed order pair is setup
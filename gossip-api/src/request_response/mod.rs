@@ -8,7 +8,10 @@ use crate::{check_signature, sign_message, GossipDestination};
 use self::{
     handshake::HandshakeMessage,
     heartbeat::{BootstrapRequest, HeartbeatMessage, PeerInfoRequest, PeerInfoResponse},
-    orderbook::{OrderInfoRequest, OrderInfoResponse},
+    orderbook::{
+        OrderBookSnapshotRequest, OrderBookSnapshotResponse, OrderInfoRequest, OrderInfoResponse,
+        ValidityProofRequest, ValidityProofResponse,
+    },
     raft::RaftMessage,
 };
 
@@ -84,6 +87,10 @@ pub enum GossipRequest {
     // --- Order Book --- //
     /// A request for order information from a peer
     OrderInfo(OrderInfoRequest),
+    /// A request for the validity proofs of an order from a peer managing it
+    ValidityProof(ValidityProofRequest),
+    /// A request for a page of a peer's matchable orders
+    OrderBookSnapshot(OrderBookSnapshotRequest),
 }
 
 impl GossipRequest {
@@ -101,6 +108,8 @@ impl GossipRequest {
             GossipRequest::PeerInfo(..) => false,
             GossipRequest::Handshake { .. } => false,
             GossipRequest::OrderInfo(..) => false,
+            GossipRequest::ValidityProof(..) => false,
+            GossipRequest::OrderBookSnapshot(..) => false,
         }
     }
 
@@ -114,6 +123,8 @@ impl GossipRequest {
             GossipRequest::Heartbeat(..) => GossipDestination::GossipServer,
             GossipRequest::PeerInfo(..) => GossipDestination::GossipServer,
             GossipRequest::OrderInfo(..) => GossipDestination::GossipServer,
+            GossipRequest::ValidityProof(..) => GossipDestination::GossipServer,
+            GossipRequest::OrderBookSnapshot(..) => GossipDestination::GossipServer,
             GossipRequest::Handshake { .. } => GossipDestination::HandshakeManager,
         }
     }
@@ -181,6 +192,10 @@ pub enum GossipResponse {
     PeerInfo(PeerInfoResponse),
     /// A response to a request for order information
     OrderInfo(OrderInfoResponse),
+    /// A response to a request for an order's validity proofs
+    ValidityProof(ValidityProofResponse),
+    /// A response with a page of a peer's matchable orders
+    OrderBookSnapshot(OrderBookSnapshotResponse),
 }
 
 impl GossipResponse {
@@ -195,6 +210,8 @@ impl GossipResponse {
             GossipResponse::Handshake { .. } => false,
             GossipResponse::OrderInfo(..) => false,
             GossipResponse::PeerInfo(..) => false,
+            GossipResponse::ValidityProof(..) => false,
+            GossipResponse::OrderBookSnapshot(..) => false,
         }
     }
 
@@ -205,6 +222,8 @@ impl GossipResponse {
             GossipResponse::Heartbeat(..) => GossipDestination::GossipServer,
             GossipResponse::PeerInfo(..) => GossipDestination::GossipServer,
             GossipResponse::OrderInfo(..) => GossipDestination::GossipServer,
+            GossipResponse::ValidityProof(..) => GossipDestination::GossipServer,
+            GossipResponse::OrderBookSnapshot(..) => GossipDestination::GossipServer,
             GossipResponse::Handshake { .. } => GossipDestination::HandshakeManager,
         }
     }
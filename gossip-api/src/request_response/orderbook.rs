@@ -1,8 +1,14 @@
 //! Types for request response about order book info
 
-use common::types::{network_order::NetworkOrder, wallet::OrderIdentifier};
+use common::types::{
+    network_order::NetworkOrder, proof_bundles::OrderValidityProofBundle, wallet::OrderIdentifier,
+};
 use serde::{Deserialize, Serialize};
 
+/// The maximum number of orders returned in a single page of an order book
+/// snapshot
+pub const MAX_SNAPSHOT_PAGE_SIZE: usize = 100;
+
 /// The message type used to request order information from a peer
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct OrderInfoRequest {
@@ -16,3 +22,198 @@ pub struct OrderInfoResponse {
     /// The info for the requested orders, if they were found
     pub order_info: Vec<NetworkOrder>,
 }
+
+/// The message type used to request the validity proofs of an order from a
+/// peer in the cluster managing it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ValidityProofRequest {
+    /// The ID of the order
+    pub order_id: OrderIdentifier,
+}
+
+/// The message type used to respond with the validity proofs of an order
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ValidityProofResponse {
+    /// The ID of the order
+    pub order_id: OrderIdentifier,
+    /// The validity proofs for the order, if the peer has them
+    pub proof_bundle: Option<OrderValidityProofBundle>,
+}
+
+/// The message type used to request a page of a peer's matchable orders, used
+/// by a joining node to quickly populate its view of the order book
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OrderBookSnapshotRequest {
+    /// The order after which the page begins, the first page is requested if
+    /// not set
+    pub after: Option<OrderIdentifier>,
+    /// The maximum number of orders to return, capped at
+    /// `MAX_SNAPSHOT_PAGE_SIZE`
+    pub page_size: usize,
+}
+
+impl OrderBookSnapshotRequest {
+    /// Request the first page of a peer's order book
+    pub fn first_page() -> Self {
+        Self { after: None, page_size: MAX_SNAPSHOT_PAGE_SIZE }
+    }
+}
+
+/// The message type used to respond with a page of matchable orders
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OrderBookSnapshotResponse {
+    /// The orders in the page, along with their validity proofs
+    pub orders: Vec<NetworkOrder>,
+    /// The request for the next page, if more orders remain
+    pub next_page: Option<OrderBookSnapshotRequest>,
+}
+
+impl OrderBookSnapshotResponse {
+    /// Build the page of the given orders requested by `req`
+    ///
+    /// Orders are paged in order of their identifiers so that a page boundary
+    /// is stable between requests
+    pub fn paginate(mut orders: Vec<NetworkOrder>, req: &OrderBookSnapshotRequest) -> Self {
+        orders.sort_by_key(|order| order.id);
+        let start = match req.after {
+            Some(after) => orders.partition_point(|order| order.id <= after),
+            None => 0,
+        };
+
+        let page_size = req.page_size.clamp(1, MAX_SNAPSHOT_PAGE_SIZE);
+        let mut page = orders.split_off(start);
+        let next_page = if page.len() > page_size {
+            page.truncate(page_size);
+            page.last().map(|order| OrderBookSnapshotRequest { after: Some(order.id), page_size })
+        } else {
+            None
+        };
+
+        Self { orders: page, next_page }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use common::types::{
+        network_order::{test_helpers::dummy_network_order, NetworkOrder},
+        proof_bundles::mocks::dummy_validity_proof_bundle,
+        wallet::OrderIdentifier,
+    };
+
+    use crate::{
+        request_response::{GossipRequest, GossipResponse},
+        GossipDestination,
+    };
+
+    use super::{
+        OrderBookSnapshotRequest, OrderBookSnapshotResponse, ValidityProofRequest,
+        ValidityProofResponse, MAX_SNAPSHOT_PAGE_SIZE,
+    };
+
+    /// Build a set of dummy orders with validity proofs attached
+    fn dummy_orders(n: usize) -> Vec<NetworkOrder> {
+        (0..n)
+            .map(|_| {
+                let mut order = dummy_network_order();
+                order.validity_proofs = Some(dummy_validity_proof_bundle());
+                order
+            })
+            .collect()
+    }
+
+    /// Tests a round trip of a validity proof request and its response through
+    /// the wire format
+    #[test]
+    fn test_validity_proof_round_trip() {
+        let order_id = OrderIdentifier::new_v4();
+
+        // The request
+        let req = GossipRequest::ValidityProof(ValidityProofRequest { order_id });
+        assert!(!req.requires_cluster_auth());
+        assert!(matches!(req.destination(), GossipDestination::GossipServer));
+
+        let serialized = serde_json::to_vec(&req).unwrap();
+        let deserialized: GossipRequest = serde_json::from_slice(&serialized).unwrap();
+        let requested_id = match deserialized {
+            GossipRequest::ValidityProof(req) => req.order_id,
+            req => panic!("unexpected request: {req:?}"),
+        };
+        assert_eq!(requested_id, order_id);
+
+        // The response
+        let proof_bundle = dummy_validity_proof_bundle();
+        let expected_nullifier = proof_bundle.reblind_proof.statement.original_shares_nullifier;
+        let resp = GossipResponse::ValidityProof(ValidityProofResponse {
+            order_id: requested_id,
+            proof_bundle: Some(proof_bundle),
+        });
+        assert!(!resp.requires_cluster_auth());
+        assert!(matches!(resp.destination(), GossipDestination::GossipServer));
+
+        let serialized = serde_json::to_vec(&resp).unwrap();
+        let deserialized: GossipResponse = serde_json::from_slice(&serialized).unwrap();
+        let resp = match deserialized {
+            GossipResponse::ValidityProof(resp) => resp,
+            resp => panic!("unexpected response: {resp:?}"),
+        };
+        assert_eq!(resp.order_id, order_id);
+
+        let bundle = resp.proof_bundle.unwrap();
+        assert_eq!(bundle.reblind_proof.statement.original_shares_nullifier, expected_nullifier);
+    }
+
+    /// Tests paging through a peer's order book snapshot, sending each request
+    /// and response through the wire format
+    #[test]
+    fn test_order_book_snapshot_round_trip() {
+        const N_ORDERS: usize = 5;
+        const PAGE_SIZE: usize = 2;
+
+        let orders = dummy_orders(N_ORDERS);
+        let mut expected_ids: Vec<_> = orders.iter().map(|order| order.id).collect();
+        expected_ids.sort();
+
+        let mut next_req = Some(OrderBookSnapshotRequest { after: None, page_size: PAGE_SIZE });
+        let mut received_ids = Vec::new();
+        while let Some(req) = next_req {
+            let req = GossipRequest::OrderBookSnapshot(req);
+            assert!(!req.requires_cluster_auth());
+            assert!(matches!(req.destination(), GossipDestination::GossipServer));
+
+            let serialized = serde_json::to_vec(&req).unwrap();
+            let req = match serde_json::from_slice(&serialized).unwrap() {
+                GossipRequest::OrderBookSnapshot(req) => req,
+                req => panic!("unexpected request: {req:?}"),
+            };
+
+            let resp = GossipResponse::OrderBookSnapshot(OrderBookSnapshotResponse::paginate(
+                orders.clone(),
+                &req,
+            ));
+            let serialized = serde_json::to_vec(&resp).unwrap();
+            let resp = match serde_json::from_slice(&serialized).unwrap() {
+                GossipResponse::OrderBookSnapshot(resp) => resp,
+                resp => panic!("unexpected response: {resp:?}"),
+            };
+
+            assert!(resp.orders.len() <= PAGE_SIZE);
+            assert!(resp.orders.iter().all(|order| order.validity_proofs.is_some()));
+            received_ids.extend(resp.orders.iter().map(|order| order.id));
+            next_req = resp.next_page;
+        }
+
+        assert_eq!(received_ids, expected_ids);
+    }
+
+    /// Tests that a snapshot page is bounded regardless of the requested size
+    #[test]
+    fn test_order_book_snapshot_bounded() {
+        let orders = dummy_orders(MAX_SNAPSHOT_PAGE_SIZE + 1);
+        let req = OrderBookSnapshotRequest { after: None, page_size: usize::MAX };
+
+        let resp = OrderBookSnapshotResponse::paginate(orders, &req);
+        assert_eq!(resp.orders.len(), MAX_SNAPSHOT_PAGE_SIZE);
+        assert!(resp.next_page.is_some());
+    }
+}
@@ -6,7 +6,7 @@
 #![deny(clippy::needless_pass_by_value)]
 #![deny(clippy::needless_pass_by_ref_mut)]
 
-use std::mem;
+use std::{mem, sync::Arc};
 
 use api_server::worker::{ApiServer, ApiServerConfig};
 use arbitrum_client::client::{ArbitrumClient, ArbitrumClientConfig};
@@ -26,8 +26,8 @@ use job_types::{
         new_gossip_server_queue, GossipServerJob, GossipServerQueue, GossipServerReceiver,
     },
     handshake_manager::{
-        new_handshake_manager_queue, HandshakeExecutionJob, HandshakeManagerQueue,
-        HandshakeManagerReceiver,
+        new_handshake_manager_queue, new_order_match_stats_ledger, HandshakeExecutionJob,
+        HandshakeManagerQueue, HandshakeManagerReceiver, OrderMatchStatsLedger,
     },
     network_manager::{
         new_network_manager_queue, NetworkManagerJob, NetworkManagerQueue, NetworkManagerReceiver,
@@ -38,7 +38,10 @@ use job_types::{
     proof_manager::{
         new_proof_manager_queue, ProofManagerJob, ProofManagerQueue, ProofManagerReceiver,
     },
-    task_driver::{new_task_driver_queue, TaskDriverJob, TaskDriverQueue, TaskDriverReceiver},
+    task_driver::{
+        new_match_gas_ledger, new_task_driver_queue, new_task_registry, MatchGasLedger,
+        TaskDriverJob, TaskDriverQueue, TaskDriverReceiver, TaskRegistry,
+    },
 };
 use libp2p::Multiaddr;
 use network_manager::{manager::NetworkManager, worker::NetworkManagerConfig};
@@ -94,6 +97,12 @@ pub struct MockNodeController {
     bus: SystemBus<SystemBusMessage>,
     /// The global state (if initialized)
     state: Option<State>,
+    /// The registry of tasks running in the task driver
+    task_registry: TaskRegistry,
+    /// The ledger of gas used by match settlements
+    match_gas_ledger: MatchGasLedger,
+    /// The match statistics of each locally managed order
+    order_stats: OrderMatchStatsLedger,
 
     // --- Worker Queues --- //
     /// The network manager's queue
@@ -131,6 +140,9 @@ impl MockNodeController {
             arbitrum_client: None,
             bus,
             state: None,
+            task_registry: new_task_registry(),
+            match_gas_ledger: new_match_gas_ledger(),
+            order_stats: new_order_match_stats_ledger(),
             network_queue: (network_sender, default_option(network_recv)),
             raft_queue: (raft_sender, default_option(raft_recv)),
             gossip_queue: (gossip_sender, default_option(gossip_recv)),
@@ -240,6 +252,10 @@ impl MockNodeController {
             darkpool_addr: self.config.contract_address.clone(),
             chain: self.config.chain_id,
             rpc_url: self.config.rpc_url.clone().unwrap(),
+            fallback_rpc_urls: self.config.fallback_rpc_urls.clone(),
+            rpc_timeout_ms: self.config.rpc_timeout_ms,
+            event_block_window: self.config.event_block_window,
+            gas_limit_ceiling: self.config.gas_limit_ceiling,
             arb_priv_key: self.config.arbitrum_private_key.clone(),
         };
 
@@ -292,6 +308,14 @@ impl MockNodeController {
             proof_queue,
             bus,
             state,
+            self.task_registry.clone(),
+            self.config.match_confirmation_depth,
+            self.match_gas_ledger.clone(),
+            self.config.match_batch_window_ms,
+            self.config.max_concurrent_wallet_lookups,
+            self.config.reblind_idle_interval_ms,
+            self.config.order_ttl_ms,
+            self.config.max_task_restarts,
         );
         let mut driver = TaskDriver::new(conf).expect("Failed to create task driver");
         driver.start().expect("Failed to start task driver");
@@ -312,6 +336,7 @@ impl MockNodeController {
             port: config.p2p_port,
             bind_addr: config.bind_addr,
             known_public_addr: config.public_ip,
+            max_message_bytes: config.max_message_bytes,
             allow_local: config.allow_local,
             cluster_id: config.cluster_id.clone(),
             cluster_keypair: Some(self.clone_cluster_key()),
@@ -349,7 +374,8 @@ impl MockNodeController {
             local_addr: self.local_addr.clone(),
             cluster_id: config.cluster_id.clone(),
             bootstrap_servers: config.bootstrap_servers.clone(),
-            arbitrum_client,
+            max_missed_heartbeats: config.max_missed_heartbeats,
+            arbitrum_client: Arc::new(arbitrum_client),
             global_state: state,
             job_sender,
             job_receiver: default_option(job_receiver),
@@ -381,6 +407,18 @@ impl MockNodeController {
             job_receiver: Some(job_receiver),
             task_queue,
             system_bus,
+            min_match_base_amounts: self.config.min_match_base_amounts.clone(),
+            handshake_cache_size: self.config.handshake_cache_size,
+            price_agreement_timeout_ms: self.config.price_agreement_timeout_ms,
+            price_agreement_retries: self.config.price_agreement_retries,
+            price_selection: self.config.price_selection,
+            price_selection_spread_bps: self.config.price_selection_spread_bps,
+            prefer_internal_matches: self.config.prefer_internal_matches,
+            allow_self_trades: self.config.allow_self_trades,
+            min_rest_ms: self.config.min_order_rest_ms,
+            handshake_rate_limit: self.config.handshake_rate_limit,
+            executor_threads: self.config.handshake_executor_threads,
+            order_stats: self.order_stats.clone(),
             cancel_channel,
         };
         let mut manager = HandshakeManager::new(conf).expect("Failed to create handshake manager");
@@ -402,6 +440,19 @@ impl MockNodeController {
             eth_websocket_addr: config.eth_websocket_addr.clone(),
             disabled: config.disable_price_reporter,
             disabled_exchanges: config.disabled_exchanges.clone(),
+            exchange_weights: config.exchange_weights.clone(),
+            price_overrides: config.price_overrides.clone(),
+            price_bounds: config.price_bounds.clone(),
+            ws_max_frame_size: config.exchange_ws_max_frame_size,
+            ws_max_buffer_size: config.exchange_ws_max_buffer_size,
+            ema_half_life_ms: config.price_ema_half_life_ms,
+            feed_grace_period_ms: config.price_feed_grace_period_ms,
+            median_concurrency: config.price_median_concurrency,
+            keepalive_interval_ms: config.exchange_keepalive_interval_ms,
+            max_missed_pongs: config.exchange_max_missed_pongs,
+            allow_insecure_ws: config.allow_insecure_exchange_ws,
+            exchange_hosts: config.exchange_hosts.clone(),
+            exchange_cert_pins: config.exchange_cert_pins.clone(),
             job_receiver: default_option(job_receiver),
             system_bus,
             cancel_channel,
@@ -437,6 +488,7 @@ impl MockNodeController {
 
         let conf = OnChainEventListenerConfig {
             max_root_staleness: config.max_merkle_staleness,
+            proof_expiry_margin: config.proof_expiry_margin,
             arbitrum_client,
             global_state,
             handshake_manager_job_queue,
@@ -460,6 +512,9 @@ impl MockNodeController {
         let system_bus = self.bus.clone();
         let price_reporter_work_queue = self.price_queue.0.clone();
         let proof_generation_work_queue = self.proof_queue.0.clone();
+        let task_registry = self.task_registry.clone();
+        let match_gas_ledger = self.match_gas_ledger.clone();
+        let order_stats = self.order_stats.clone();
         let cancel_channel = mock_cancel();
 
         let conf = ApiServerConfig {
@@ -467,9 +522,16 @@ impl MockNodeController {
             websocket_port: config.websocket_port,
             network_sender,
             global_state,
+            task_registry,
+            match_gas_ledger,
+            order_stats,
+            admin_api_key: config.admin_api_key.clone(),
             system_bus,
             price_reporter_work_queue,
             proof_generation_work_queue,
+            proof_queue_high_water_mark: config.proof_queue_high_water_mark,
+            min_order_amounts: config.min_order_amounts.clone(),
+            max_order_amounts: config.max_order_amounts.clone(),
             cancel_channel,
         };
 
@@ -487,7 +549,8 @@ impl MockNodeController {
         let job_queue = self.proof_queue.1.take().unwrap();
         let cancel_channel = mock_cancel();
 
-        let conf = ProofManagerConfig { job_queue, cancel_channel };
+        let num_threads = self.config.proof_manager_threads;
+        let conf = ProofManagerConfig { job_queue, num_threads, cancel_channel };
 
         let mut manager = ProofManager::new(conf).expect("Failed to create proof manager");
         manager.start().expect("Failed to start proof manager");
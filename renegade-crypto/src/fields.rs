@@ -151,6 +151,13 @@ pub fn u256_to_scalar(a: &U256) -> Scalar {
     Scalar::from_be_bytes_mod_order(&buf)
 }
 
+/// Convert a U256 to a scalar, returning `None` if the value is not a
+/// canonical field element, i.e. if the conversion would reduce it modulo the
+/// field order
+pub fn u256_to_scalar_checked(a: &U256) -> Option<Scalar> {
+    (u256_to_biguint(a) < get_scalar_field_modulus()).then(|| u256_to_scalar(a))
+}
+
 /// Convert a U256 to a BigUint
 pub fn u256_to_biguint(a: &U256) -> BigUint {
     let mut buf = [0u8; 32];
@@ -166,10 +173,14 @@ pub fn u256_to_biguint(a: &U256) -> BigUint {
 #[cfg(test)]
 mod field_helper_test {
     use constants::Scalar;
+    use ethers_core::types::U256;
     use num_bigint::BigInt;
     use rand::{thread_rng, Rng, RngCore};
 
-    use crate::fields::{bigint_to_scalar, bigint_to_scalar_bits, scalar_to_bigint};
+    use crate::fields::{
+        bigint_to_scalar, bigint_to_scalar_bits, biguint_to_u256, get_scalar_field_modulus,
+        scalar_to_bigint, scalar_to_u256, u256_to_scalar_checked,
+    };
 
     #[test]
     fn test_scalar_to_bigint() {
@@ -206,4 +217,19 @@ mod field_helper_test {
         assert_eq!(res.len(), scalar_bits.len());
         assert_eq!(res, scalar_bits);
     }
+
+    #[test]
+    fn test_u256_to_scalar_checked() {
+        // Canonical values convert
+        let scalar = Scalar::random(&mut thread_rng());
+        assert_eq!(u256_to_scalar_checked(&scalar_to_u256(&scalar)), Some(scalar));
+
+        let max_scalar = -Scalar::one();
+        assert_eq!(u256_to_scalar_checked(&scalar_to_u256(&max_scalar)), Some(max_scalar));
+
+        // Values at or above the field order are rejected
+        let modulus = biguint_to_u256(&get_scalar_field_modulus());
+        assert_eq!(u256_to_scalar_checked(&modulus), None);
+        assert_eq!(u256_to_scalar_checked(&U256::MAX), None);
+    }
 }
@@ -8,6 +8,31 @@ pub use poseidon2::*;
 #[cfg(feature = "non-wasm")]
 pub use mpc_type_interface::*;
 
+/// The default domain separation tag of the CSPRNG stream that samples wallet
+/// blinders and the private shares of the blinders
+pub const BLINDER_CSPRNG_DOMAIN: u64 = 1;
+/// The default domain separation tag of the CSPRNG stream that samples the
+/// private secret shares of a wallet
+pub const SHARE_CSPRNG_DOMAIN: u64 = 2;
+
+/// The derivation of a CSPRNG stream
+///
+/// `Legacy` streams hash only the seed of each sample, this is the derivation
+/// that the deployed circuits and verification keys expect, and that existing
+/// wallets are recovered with. `Tagged` streams absorb the given domain
+/// separation tag before the seed of each sample, so that streams with
+/// different tags remain distinct even if their seeds collide. Wallet shares
+/// stay on the legacy derivation until circuits and verification keys for the
+/// tagged derivation are deployed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CsprngDomain {
+    /// The un-tagged derivation
+    #[default]
+    Legacy,
+    /// The derivation separated by the given domain tag
+    Tagged(u64),
+}
+
 #[cfg(feature = "non-wasm")]
 mod mpc_type_interface {
     //! Defines the interface for the Poseidon 2 sponge
@@ -19,19 +44,26 @@ mod mpc_type_interface {
     use ::constants::Scalar;
     use itertools::Itertools;
 
-    use super::Poseidon2Sponge;
+    use super::{CsprngDomain, Poseidon2Sponge};
 
     /// A hash chain from a seed used to compute CSPRNG values
     pub struct PoseidonCSPRNG {
         /// The seed of the CSPRNG, this is chained into a hash function
         /// to give pseudorandom values
         state: Scalar,
+        /// The derivation of the stream
+        domain: CsprngDomain,
     }
 
     impl PoseidonCSPRNG {
         /// Constructor
         pub fn new(seed: Scalar) -> Self {
-            Self { state: seed }
+            Self::new_in_domain(seed, CsprngDomain::Legacy)
+        }
+
+        /// Create a CSPRNG with the given derivation
+        pub fn new_in_domain(seed: Scalar, domain: CsprngDomain) -> Self {
+            Self { state: seed, domain }
         }
     }
 
@@ -39,7 +71,12 @@ mod mpc_type_interface {
         type Item = Scalar;
 
         fn next(&mut self) -> Option<Self::Item> {
-            let hash_res = compute_poseidon_hash(&[self.state]);
+            let hash_res = match self.domain {
+                CsprngDomain::Legacy => compute_poseidon_hash(&[self.state]),
+                CsprngDomain::Tagged(tag) => {
+                    compute_poseidon_hash(&[Scalar::from(tag), self.state])
+                },
+            };
             self.state = hash_res;
 
             Some(hash_res)
@@ -57,13 +94,26 @@ mod mpc_type_interface {
 
     /// Compute a chained Poseidon hash of the given length from the given seed
     pub fn evaluate_hash_chain(seed: Scalar, length: usize) -> Vec<Scalar> {
+        evaluate_hash_chain_in_domain(seed, length, CsprngDomain::Legacy)
+    }
+
+    /// Compute a chained Poseidon hash of the given length from the given seed
+    /// with the given derivation
+    pub fn evaluate_hash_chain_in_domain(
+        seed: Scalar,
+        length: usize,
+        domain: CsprngDomain,
+    ) -> Vec<Scalar> {
         let mut seed = seed.inner();
         let mut res = Vec::with_capacity(length);
 
         for _ in 0..length {
             // Create a new hasher to reset the internal state
             let mut hasher = Poseidon2Sponge::new();
-            seed = hasher.hash(&[seed]);
+            seed = match domain {
+                CsprngDomain::Legacy => hasher.hash(&[seed]),
+                CsprngDomain::Tagged(tag) => hasher.hash(&[Scalar::from(tag).inner(), seed]),
+            };
 
             res.push(Scalar::new(seed));
         }
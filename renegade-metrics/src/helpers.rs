@@ -10,6 +10,7 @@ use crate::labels::{
     MATCH_QUOTE_VOLUME_METRIC, NUM_DEPOSITS_METRICS, NUM_WITHDRAWALS_METRICS,
     WITHDRAWAL_VOLUME_METRIC,
 };
+use crate::match_volume::MATCH_VOLUMES;
 
 /// Get the human-readable asset and volume of
 /// the given mint and amount.
@@ -54,8 +55,10 @@ pub fn maybe_record_transfer_metrics(transfer: &Option<ExternalTransferWithAuth>
     }
 }
 
-/// Record the volume of base/quote assets moved in a match
+/// Record the volume of base/quote assets moved in a match, accruing it to the
+/// match's pair
 pub fn record_match_volume(match_result: &MatchResult) {
+    MATCH_VOLUMES.record(match_result);
     record_volume(&match_result.base_mint, match_result.base_amount, MATCH_BASE_VOLUME_METRIC);
     record_volume(&match_result.quote_mint, match_result.quote_amount, MATCH_QUOTE_VOLUME_METRIC);
 }
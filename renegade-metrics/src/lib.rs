@@ -9,3 +9,5 @@
 
 pub mod helpers;
 pub mod labels;
+pub mod match_volume;
+pub mod registry;
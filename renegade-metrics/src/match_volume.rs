@@ -0,0 +1,94 @@
+//! Accumulates the volume matched on each token pair
+//!
+//! The ledger is updated as matches settle, and is read periodically to
+//! publish each pair's cumulative volume for analytics
+//!
+//! The ledger is per-node and held only in memory: it counts the matches this
+//! node settles and resets when the node restarts
+
+use std::{collections::HashMap, sync::Mutex};
+
+use circuit_types::r#match::MatchResult;
+use common::types::{handshake::PairMatchVolume, token::Token};
+use lazy_static::lazy_static;
+
+lazy_static! {
+    /// The global ledger of matched volume
+    pub static ref MATCH_VOLUMES: MatchVolumeLedger = MatchVolumeLedger::default();
+}
+
+/// The cumulative volume matched on each token pair, keyed by `(base, quote)`
+#[derive(Debug, Default)]
+pub struct MatchVolumeLedger {
+    /// The volume matched on each pair
+    volumes: Mutex<HashMap<(Token, Token), PairMatchVolume>>,
+}
+
+impl MatchVolumeLedger {
+    /// Accrue the volume of a settled match to its pair
+    pub fn record(&self, match_result: &MatchResult) {
+        let base = Token::from_addr_biguint(&match_result.base_mint);
+        let quote = Token::from_addr_biguint(&match_result.quote_mint);
+
+        let mut volumes = self.volumes.lock().expect("match volumes poisoned");
+        let volume = volumes
+            .entry((base.clone(), quote.clone()))
+            .or_insert_with(|| PairMatchVolume { base, quote, ..Default::default() });
+
+        volume.base_volume += match_result.base_amount;
+        volume.quote_volume += match_result.quote_amount;
+        volume.num_matches += 1;
+    }
+
+    /// Get the volume matched on each pair, ordered by the pair's addresses
+    pub fn volumes(&self) -> Vec<PairMatchVolume> {
+        let volumes = self.volumes.lock().expect("match volumes poisoned");
+        let mut volumes = volumes.values().cloned().collect::<Vec<_>>();
+        volumes.sort_by(|a, b| {
+            (a.base.get_addr(), a.quote.get_addr()).cmp(&(b.base.get_addr(), b.quote.get_addr()))
+        });
+
+        volumes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use circuit_types::r#match::MatchResult;
+    use common::types::token::Token;
+    use num_bigint::BigUint;
+
+    use super::MatchVolumeLedger;
+
+    /// Build a match result on the given pair
+    fn match_result(base: u8, quote: u8, base_amount: u128, quote_amount: u128) -> MatchResult {
+        MatchResult {
+            base_mint: BigUint::from(base),
+            quote_mint: BigUint::from(quote),
+            base_amount,
+            quote_amount,
+            ..Default::default()
+        }
+    }
+
+    /// Tests that volume accrues to each pair across multiple settled matches
+    #[test]
+    fn test_volume_accrues() {
+        let ledger = MatchVolumeLedger::default();
+        ledger.record(&match_result(1, 2, 10, 20));
+        ledger.record(&match_result(1, 2, 5, 15));
+        ledger.record(&match_result(3, 2, 7, 1));
+
+        let volumes = ledger.volumes();
+        assert_eq!(volumes.len(), 2);
+
+        let pair = &volumes[0];
+        assert_eq!(pair.base, Token::from_addr_biguint(&BigUint::from(1u8)));
+        assert_eq!(pair.quote, Token::from_addr_biguint(&BigUint::from(2u8)));
+        assert_eq!((pair.base_volume, pair.quote_volume, pair.num_matches), (15, 35, 2));
+
+        let pair = &volumes[1];
+        assert_eq!(pair.base, Token::from_addr_biguint(&BigUint::from(3u8)));
+        assert_eq!((pair.base_volume, pair.quote_volume, pair.num_matches), (7, 1, 1));
+    }
+}
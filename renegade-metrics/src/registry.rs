@@ -0,0 +1,316 @@
+//! An in-process registry of relayer internals, exported in the Prometheus
+//! text exposition format
+//!
+//! Unlike the metrics recorded through the `metrics` facade, which are pushed
+//! to a StatsD agent, these are held in atomics and rendered on scrape
+
+use std::{
+    fmt::Write,
+    sync::atomic::{AtomicI64, AtomicU64, Ordering},
+    time::Duration,
+};
+
+use lazy_static::lazy_static;
+
+/// The prefix applied to all exported metric names
+const METRIC_PREFIX: &str = "renegade";
+
+/// The upper bounds, in seconds, of the proof generation latency buckets
+const PROOF_LATENCY_BUCKETS: &[f64] = &[0.1, 0.25, 0.5, 1., 2.5, 5., 10., 30., 60.];
+
+lazy_static! {
+    /// The global registry of relayer metrics
+    pub static ref RELAYER_METRICS: RelayerMetrics = RelayerMetrics::new();
+}
+
+// ----------------
+// | Metric Types |
+// ----------------
+
+/// A monotonically increasing counter
+#[derive(Debug, Default)]
+pub struct Counter(AtomicU64);
+
+impl Counter {
+    /// Increment the counter by one
+    pub fn increment(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Get the current value of the counter
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A value that may move up or down
+#[derive(Debug, Default)]
+pub struct Gauge(AtomicI64);
+
+impl Gauge {
+    /// Set the value of the gauge
+    pub fn set(&self, value: i64) {
+        self.0.store(value, Ordering::Relaxed);
+    }
+
+    /// Increment the gauge by one
+    pub fn increment(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Decrement the gauge by one
+    pub fn decrement(&self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// Get the current value of the gauge
+    pub fn get(&self) -> i64 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Increment the gauge, returning a guard that decrements it when dropped
+    ///
+    /// The decrement runs even if the holder unwinds, so a panic cannot leave
+    /// the gauge permanently raised
+    pub fn track(&self) -> GaugeGuard<'_> {
+        self.increment();
+        GaugeGuard(self)
+    }
+}
+
+/// A guard holding a gauge incremented, see `Gauge::track`
+#[derive(Debug)]
+pub struct GaugeGuard<'a>(&'a Gauge);
+
+impl Drop for GaugeGuard<'_> {
+    fn drop(&mut self) {
+        self.0.decrement();
+    }
+}
+
+/// A histogram of durations over a fixed set of buckets
+#[derive(Debug)]
+pub struct Histogram {
+    /// The upper bounds of the buckets, in seconds
+    bounds: &'static [f64],
+    /// The number of observations falling in each bucket, non-cumulative
+    bucket_counts: Vec<AtomicU64>,
+    /// The sum of all observations, in microseconds
+    sum_micros: AtomicU64,
+    /// The total number of observations
+    count: AtomicU64,
+}
+
+impl Histogram {
+    /// Construct a histogram with the given bucket bounds
+    pub fn new(bounds: &'static [f64]) -> Self {
+        let bucket_counts = bounds.iter().map(|_| AtomicU64::new(0)).collect();
+        Self { bounds, bucket_counts, sum_micros: AtomicU64::new(0), count: AtomicU64::new(0) }
+    }
+
+    /// Record an observation
+    pub fn observe(&self, duration: Duration) {
+        let secs = duration.as_secs_f64();
+        if let Some(idx) = self.bounds.iter().position(|bound| secs <= *bound) {
+            self.bucket_counts[idx].fetch_add(1, Ordering::Relaxed);
+        }
+
+        self.sum_micros.fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+// ------------
+// | Registry |
+// ------------
+
+/// The set of relayer metrics exported for scraping
+#[derive(Debug)]
+pub struct RelayerMetrics {
+    /// The number of locally managed orders that are ready for a match
+    pub active_orders: Gauge,
+    /// The number of tasks currently being run by the task driver
+    pub in_flight_tasks: Gauge,
+    /// The number of handshakes that resulted in a match
+    pub handshakes_succeeded: Counter,
+    /// The number of handshakes that failed
+    pub handshakes_failed: Counter,
+    /// Whether the local node is the raft leader, one if so, zero otherwise
+    pub raft_leader: Gauge,
+    /// The latency of proof generation in the proof manager
+    pub proof_generation_latency: Histogram,
+}
+
+impl Default for RelayerMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RelayerMetrics {
+    /// Constructor
+    pub fn new() -> Self {
+        Self {
+            active_orders: Gauge::default(),
+            in_flight_tasks: Gauge::default(),
+            handshakes_succeeded: Counter::default(),
+            handshakes_failed: Counter::default(),
+            raft_leader: Gauge::default(),
+            proof_generation_latency: Histogram::new(PROOF_LATENCY_BUCKETS),
+        }
+    }
+
+    /// Render the registry in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        write_gauge(
+            &mut out,
+            "active_orders",
+            "The number of locally managed orders that are ready for a match",
+            &self.active_orders,
+        );
+        write_gauge(
+            &mut out,
+            "in_flight_tasks",
+            "The number of tasks currently being run by the task driver",
+            &self.in_flight_tasks,
+        );
+        write_counter(
+            &mut out,
+            "handshakes_succeeded_total",
+            "The number of handshakes that resulted in a match",
+            &self.handshakes_succeeded,
+        );
+        write_counter(
+            &mut out,
+            "handshakes_failed_total",
+            "The number of handshakes that failed",
+            &self.handshakes_failed,
+        );
+        write_gauge(
+            &mut out,
+            "raft_leader",
+            "Whether the local node is the raft leader",
+            &self.raft_leader,
+        );
+        write_histogram(
+            &mut out,
+            "proof_generation_latency_seconds",
+            "The latency of proof generation",
+            &self.proof_generation_latency,
+        );
+
+        out
+    }
+}
+
+// -------------
+// | Rendering |
+// -------------
+
+/// Write the `HELP` and `TYPE` header lines of a metric
+fn write_header(out: &mut String, name: &str, help: &str, kind: &str) {
+    writeln!(out, "# HELP {METRIC_PREFIX}_{name} {help}").unwrap();
+    writeln!(out, "# TYPE {METRIC_PREFIX}_{name} {kind}").unwrap();
+}
+
+/// Write a counter to the exposition
+fn write_counter(out: &mut String, name: &str, help: &str, counter: &Counter) {
+    write_header(out, name, help, "counter");
+    writeln!(out, "{METRIC_PREFIX}_{name} {}", counter.get()).unwrap();
+}
+
+/// Write a gauge to the exposition
+fn write_gauge(out: &mut String, name: &str, help: &str, gauge: &Gauge) {
+    write_header(out, name, help, "gauge");
+    writeln!(out, "{METRIC_PREFIX}_{name} {}", gauge.get()).unwrap();
+}
+
+/// Write a histogram to the exposition, with cumulative buckets
+fn write_histogram(out: &mut String, name: &str, help: &str, histogram: &Histogram) {
+    write_header(out, name, help, "histogram");
+
+    let mut cumulative = 0;
+    for (bound, count) in histogram.bounds.iter().zip(histogram.bucket_counts.iter()) {
+        cumulative += count.load(Ordering::Relaxed);
+        writeln!(out, "{METRIC_PREFIX}_{name}_bucket{{le=\"{bound}\"}} {cumulative}").unwrap();
+    }
+
+    let count = histogram.count.load(Ordering::Relaxed);
+    let sum = histogram.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.;
+    writeln!(out, "{METRIC_PREFIX}_{name}_bucket{{le=\"+Inf\"}} {count}").unwrap();
+    writeln!(out, "{METRIC_PREFIX}_{name}_sum {sum}").unwrap();
+    writeln!(out, "{METRIC_PREFIX}_{name}_count {count}").unwrap();
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, time::Duration};
+
+    use super::RelayerMetrics;
+
+    /// Parse a Prometheus text exposition into a map from sample (name and
+    /// labels) to value, panicking on malformed lines
+    fn parse_exposition(text: &str) -> HashMap<String, f64> {
+        let mut samples = HashMap::new();
+        for line in text.lines() {
+            if line.starts_with("# HELP ") || line.starts_with("# TYPE ") {
+                assert!(line.split_whitespace().count() >= 4, "malformed header: {line}");
+                continue;
+            }
+
+            let (sample, value) = line.rsplit_once(' ').expect("malformed sample");
+            let value: f64 = value.parse().expect("invalid sample value");
+            samples.insert(sample.to_string(), value);
+        }
+
+        samples
+    }
+
+    /// Tests that the rendered registry parses and contains the expected
+    /// metrics
+    #[test]
+    fn test_exposition_format() {
+        let metrics = RelayerMetrics::new();
+        metrics.active_orders.set(3);
+        metrics.in_flight_tasks.increment();
+        metrics.handshakes_succeeded.increment();
+        metrics.handshakes_failed.increment();
+        metrics.handshakes_failed.increment();
+        metrics.raft_leader.set(1);
+        metrics.proof_generation_latency.observe(Duration::from_millis(200));
+        metrics.proof_generation_latency.observe(Duration::from_secs(2));
+        metrics.proof_generation_latency.observe(Duration::from_secs(120));
+
+        let samples = parse_exposition(&metrics.render());
+        assert_eq!(samples["renegade_active_orders"], 3.);
+        assert_eq!(samples["renegade_in_flight_tasks"], 1.);
+        assert_eq!(samples["renegade_handshakes_succeeded_total"], 1.);
+        assert_eq!(samples["renegade_handshakes_failed_total"], 2.);
+        assert_eq!(samples["renegade_raft_leader"], 1.);
+
+        // Buckets are cumulative, the last observation only falls in `+Inf`
+        let name = "renegade_proof_generation_latency_seconds";
+        assert_eq!(samples[&format!("{name}_bucket{{le=\"0.1\"}}")], 0.);
+        assert_eq!(samples[&format!("{name}_bucket{{le=\"0.25\"}}")], 1.);
+        assert_eq!(samples[&format!("{name}_bucket{{le=\"2.5\"}}")], 2.);
+        assert_eq!(samples[&format!("{name}_bucket{{le=\"60\"}}")], 2.);
+        assert_eq!(samples[&format!("{name}_bucket{{le=\"+Inf\"}}")], 3.);
+        assert_eq!(samples[&format!("{name}_count")], 3.);
+        assert!((samples[&format!("{name}_sum")] - 122.2).abs() < 1e-6);
+    }
+
+    /// Tests that a tracked gauge is decremented when its holder panics
+    #[test]
+    fn test_gauge_guard_on_panic() {
+        let metrics = RelayerMetrics::new();
+        let res = std::panic::catch_unwind(|| {
+            let _guard = metrics.in_flight_tasks.track();
+            assert_eq!(metrics.in_flight_tasks.get(), 1);
+            panic!("task panicked");
+        });
+
+        assert!(res.is_err());
+        assert_eq!(metrics.in_flight_tasks.get(), 0);
+    }
+}
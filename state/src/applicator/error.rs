@@ -9,8 +9,16 @@ use crate::storage::error::StorageError;
 /// The error type emitted by the storage applicator
 #[derive(Debug)]
 pub enum StateApplicatorError {
+    /// A wallet reuses the blinder of the given distinct wallet
+    BlinderReuse(WalletIdentifier),
     /// An error enqueueing a task
     EnqueueTask(String),
+    /// A wallet was added with the ID of an existing wallet but different
+    /// contents
+    ConflictingWallet(WalletIdentifier),
+    /// The shares of the given wallet do not match its contents or its
+    /// claimed commitment
+    InvalidWalletShares(WalletIdentifier),
     /// Missing keys in the database necessary for a tx
     MissingEntry(String),
     /// An error interacting with storage
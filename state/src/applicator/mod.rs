@@ -3,10 +3,11 @@
 
 use std::sync::Arc;
 
-use common::types::gossip::ClusterId;
+use common::types::{gossip::ClusterId, wallet::Wallet};
 use external_api::bus_message::SystemBusMessage;
 use job_types::{handshake_manager::HandshakeManagerQueue, task_driver::TaskDriverQueue};
 use system_bus::SystemBus;
+use tracing::warn;
 
 use crate::{storage::db::DB, StateTransition};
 
@@ -39,6 +40,12 @@ pub struct StateApplicatorConfig {
     pub db: Arc<DB>,
     /// A handle to the system bus used for internal pubsub
     pub system_bus: SystemBus<SystemBusMessage>,
+    /// Whether to verify that an applied wallet's shares are consistent with
+    /// its contents and claimed commitment
+    ///
+    /// This is a cluster-wide setting; a node configured differently from
+    /// its peers would diverge from them on the same log entry
+    pub verify_wallet_shares: bool,
 }
 
 /// The applicator applies state updates to the global state and persists them
@@ -63,11 +70,23 @@ impl StateApplicator {
     /// Handle a state transition
     pub fn handle_state_transition(&self, transition: StateTransition) -> Result<()> {
         match transition {
-            StateTransition::AddWallet { wallet } => self.add_wallet(&wallet),
-            StateTransition::UpdateWallet { wallet } => self.update_wallet(&wallet),
+            StateTransition::AddWallet { wallet } => {
+                self.check_wallet_shares(&wallet)?;
+                self.add_wallet(&wallet)
+            },
+            StateTransition::UpdateWallet { wallet } => {
+                self.check_wallet_shares(&wallet)?;
+                self.update_wallet(&wallet)
+            },
             StateTransition::AddOrderValidityBundle { order_id, proof, witness } => {
                 self.add_order_validity_proof(order_id, proof, witness)
             },
+            StateTransition::GrantScopedKey { wallet_id, key } => {
+                self.grant_scoped_key(wallet_id, key)
+            },
+            StateTransition::RevokeScopedKey { wallet_id, key } => {
+                self.revoke_scoped_key(wallet_id, &key)
+            },
             StateTransition::AppendTask { task } => self.append_task(&task),
             StateTransition::PopTask { task_id } => self.pop_task(task_id),
             StateTransition::TransitionTask { task_id, state } => {
@@ -79,6 +98,29 @@ impl StateApplicator {
         }
     }
 
+    /// Reject a wallet whose shares do not reconstruct its contents, or whose
+    /// shares do not commit to the value its Merkle opening authenticates, if
+    /// share verification is enabled
+    ///
+    /// The check runs as the transition is applied, so that followers verify
+    /// the wallets replicated to them rather than trusting the proposer
+    fn check_wallet_shares(&self, wallet: &Wallet) -> Result<()> {
+        if !self.config.verify_wallet_shares {
+            return Ok(());
+        }
+
+        let commitment_mismatch = wallet
+            .merkle_proof
+            .as_ref()
+            .is_some_and(|path| path.value != wallet.get_wallet_share_commitment());
+        if commitment_mismatch || !wallet.check_wallet_shares() {
+            warn!("wallet {} shares do not match its contents or commitment", wallet.wallet_id);
+            return Err(StateApplicatorError::InvalidWalletShares(wallet.wallet_id));
+        }
+
+        Ok(())
+    }
+
     /// Get a reference to the db
     fn db(&self) -> &DB {
         &self.config.db
@@ -125,6 +167,7 @@ mod test_helpers {
             handshake_manager_queue,
             system_bus: SystemBus::new(),
             cluster_id: ClusterId::from_str("test-cluster").unwrap(),
+            verify_wallet_shares: false,
         };
 
         StateApplicator::new(config).unwrap()
@@ -1,13 +1,21 @@
 //! Applicator methods for the wallet index, separated out for discoverability
 
-use common::types::{network_order::NetworkOrder, wallet::Wallet};
+use circuit_types::keychain::PublicSigningKey;
+use common::types::{
+    network_order::NetworkOrder,
+    wallet::{ScopedKey, Wallet, WalletIdentifier},
+};
 use external_api::bus_message::{wallet_topic_name, SystemBusMessage};
 use itertools::Itertools;
 use libmdbx::RW;
+use tracing::{info, warn};
 
 use crate::storage::tx::StateTxn;
 
-use super::{Result, StateApplicator};
+use super::{error::StateApplicatorError, Result, StateApplicator};
+
+/// The error message emitted when a wallet is missing from the index
+const ERR_WALLET_MISSING: &str = "wallet not found in index";
 
 impl StateApplicator {
     // -------------
@@ -18,10 +26,25 @@ impl StateApplicator {
     ///
     /// This may happen, for example, when a new wallet is created by
     /// a user on one cluster node, and the others must replicate it
+    ///
+    /// Re-adding an identical wallet, e.g. on a retried proposal, is a no-op.
+    /// Errors if a different wallet with the same ID exists
     pub fn add_wallet(&self, wallet: &Wallet) -> Result<()> {
-        // Add the wallet to the wallet indices
         let tx = self.db().new_write_tx()?;
+        if let Some(existing) = tx.get_wallet(&wallet.wallet_id)? {
+            if existing != *wallet {
+                warn!("rejecting wallet {}, conflicts with existing wallet", wallet.wallet_id);
+                return Err(StateApplicatorError::ConflictingWallet(wallet.wallet_id));
+            }
+
+            info!("wallet {} already added, skipping", wallet.wallet_id);
+            return Ok(tx.commit()?);
+        }
+        Self::check_blinder_reuse(wallet, &tx)?;
+
+        // Add the wallet to the wallet indices
         tx.index_orders(&wallet.wallet_id, &wallet.orders.keys().cloned().collect_vec())?;
+        tx.index_blinder(&wallet.blinder, &wallet.wallet_id)?;
         tx.write_wallet(wallet)?;
         tx.commit()?;
 
@@ -46,23 +69,41 @@ impl StateApplicator {
     /// possibly stale -- contract state
     pub fn update_wallet(&self, wallet: &Wallet) -> Result<()> {
         let tx = self.db().new_write_tx()?;
+        let wallet = &Self::with_stored_scoped_keys(wallet, &tx)?;
+        Self::check_blinder_reuse(wallet, &tx)?;
+        let old_wallet = tx.get_wallet(&wallet.wallet_id)?;
 
         // Any new orders in the wallet should be added to the orderbook
+        //
+        // An order left unchanged by the update keeps the time it was received, so
+        // that updates to the rest of the wallet do not reset its age
+        let old_orders = old_wallet.as_ref().map(|w| w.orders.clone()).unwrap_or_default();
         let nullifier = wallet.get_wallet_nullifier();
-        for (id, _order) in wallet.orders.iter().filter(|(_id, order)| !order.is_zero()) {
-            self.add_local_order_with_tx(
-                NetworkOrder::new(
-                    *id,
-                    nullifier,
-                    self.config.cluster_id.clone(),
-                    true, // local
-                ),
-                &tx,
-            )?;
+        for (id, order) in wallet.orders.iter().filter(|(_id, order)| !order.is_zero()) {
+            let mut network_order = NetworkOrder::new(
+                *id,
+                nullifier,
+                self.config.cluster_id.clone(),
+                true, // local
+            );
+            if old_orders.get(id) == Some(order)
+                && let Some(existing) = tx.get_order_info(id)?
+            {
+                network_order.timestamp = existing.timestamp;
+            }
+
+            self.add_local_order_with_tx(network_order, &tx)?;
         }
 
-        // Update the order -> wallet mapping and index the wallet
+        // Update the order -> wallet mapping and index the wallet, replacing its
+        // superseded blinder so that the blinder index holds one entry per wallet
         tx.index_orders(&wallet.wallet_id, &wallet.orders.keys().cloned().collect_vec())?;
+        if let Some(old) = &old_wallet
+            && old.blinder != wallet.blinder
+        {
+            tx.remove_blinder(&old.blinder)?;
+        }
+        tx.index_blinder(&wallet.blinder, &wallet.wallet_id)?;
         tx.write_wallet(wallet)?;
         tx.commit()?;
 
@@ -76,10 +117,83 @@ impl StateApplicator {
         Ok(())
     }
 
+    /// Grant a scoped key on a wallet, replacing any existing grant to the
+    /// same key
+    pub fn grant_scoped_key(&self, wallet_id: WalletIdentifier, key: ScopedKey) -> Result<()> {
+        self.update_scoped_keys(wallet_id, |keys| {
+            keys.retain(|k| k.key != key.key);
+            keys.push(key);
+        })
+    }
+
+    /// Revoke a scoped key on a wallet
+    ///
+    /// Revoking a key that was never granted is a no-op
+    pub fn revoke_scoped_key(
+        &self,
+        wallet_id: WalletIdentifier,
+        key: &PublicSigningKey,
+    ) -> Result<()> {
+        self.update_scoped_keys(wallet_id, |keys| keys.retain(|k| k.key != *key))
+    }
+
     // -----------
     // | Helpers |
     // -----------
 
+    /// Apply a change to the scoped keys of a wallet in the index
+    fn update_scoped_keys<F: FnOnce(&mut Vec<ScopedKey>)>(
+        &self,
+        wallet_id: WalletIdentifier,
+        f: F,
+    ) -> Result<()> {
+        let tx = self.db().new_write_tx()?;
+        let mut wallet = tx
+            .get_wallet(&wallet_id)?
+            .ok_or_else(|| StateApplicatorError::MissingEntry(ERR_WALLET_MISSING.to_string()))?;
+        f(&mut wallet.key_chain.scoped_keys);
+        tx.write_wallet(&wallet)?;
+        tx.commit()?;
+
+        let wallet_topic = wallet_topic_name(&wallet_id);
+        self.system_bus().publish(
+            wallet_topic,
+            SystemBusMessage::WalletUpdate { wallet: Box::new(wallet.into()) },
+        );
+
+        Ok(())
+    }
+
+    /// Reject a wallet whose blinder is the current blinder of a distinct
+    /// wallet
+    ///
+    /// A wallet's blinder is revealed alongside its public shares, so two
+    /// wallets sharing a blinder may be linked by an observer. The check runs
+    /// against the replicated blinder index as the transition is applied, so
+    /// every node reaches the same decision on the same log entry
+    fn check_blinder_reuse(wallet: &Wallet, tx: &StateTxn<RW>) -> Result<()> {
+        match tx.get_wallet_for_blinder(&wallet.blinder)? {
+            Some(existing) if existing != wallet.wallet_id => {
+                warn!("wallet {} reuses the blinder of wallet {existing}", wallet.wallet_id);
+                Err(StateApplicatorError::BlinderReuse(existing))
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Replace the scoped keys of an updated wallet with those in the index
+    ///
+    /// Scoped keys change only through grants and revocations, so an update
+    /// built from a copy of the wallet read before a grant must not drop it
+    fn with_stored_scoped_keys(wallet: &Wallet, tx: &StateTxn<RW>) -> Result<Wallet> {
+        let mut wallet = wallet.clone();
+        if let Some(existing) = tx.get_wallet(&wallet.wallet_id)? {
+            wallet.key_chain.scoped_keys = existing.key_chain.scoped_keys;
+        }
+
+        Ok(wallet)
+    }
+
     /// Add an order within a given transaction
     pub(crate) fn add_local_order_with_tx(
         &self,
@@ -100,12 +214,15 @@ impl StateApplicator {
 #[cfg(all(test, feature = "all-tests"))]
 pub(crate) mod test {
     use common::types::{
-        wallet::Wallet,
+        wallet::{KeyScope, ScopedKey, Wallet},
         wallet_mocks::{mock_empty_wallet, mock_order},
     };
     use uuid::Uuid;
 
-    use crate::{applicator::test_helpers::mock_applicator, ORDER_TO_WALLET_TABLE, WALLETS_TABLE};
+    use crate::{
+        applicator::{error::StateApplicatorError, test_helpers::mock_applicator},
+        StateTransition, BLINDER_TO_WALLET_TABLE, ORDER_TO_WALLET_TABLE, WALLETS_TABLE,
+    };
 
     // -----------
     // | Helpers |
@@ -157,4 +274,186 @@ pub(crate) mod test {
 
         assert_eq!(wallet, expected_wallet);
     }
+
+    /// Tests that an order left unchanged by a wallet update keeps the time it
+    /// was received, while a modified order is treated as newly received
+    #[test]
+    fn test_update_preserves_order_timestamps() {
+        let applicator = mock_applicator();
+        let (unchanged_id, modified_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut wallet = mock_empty_wallet();
+        applicator.add_wallet(&wallet).unwrap();
+        wallet.orders.insert(unchanged_id, mock_order());
+        wallet.orders.insert(modified_id, mock_order());
+        applicator.update_wallet(&wallet).unwrap();
+
+        // Backdate both orders
+        let tx = applicator.db().new_write_tx().unwrap();
+        for id in [unchanged_id, modified_id] {
+            let mut order = tx.get_order_info(&id).unwrap().unwrap();
+            order.timestamp = 1;
+            tx.write_order(&order).unwrap();
+        }
+        tx.commit().unwrap();
+
+        wallet.orders.get_mut(&modified_id).unwrap().amount += 1;
+        applicator.update_wallet(&wallet).unwrap();
+
+        let tx = applicator.db().new_read_tx().unwrap();
+        let unchanged = tx.get_order_info(&unchanged_id).unwrap().unwrap();
+        let modified = tx.get_order_info(&modified_id).unwrap().unwrap();
+        assert_eq!(unchanged.timestamp, 1);
+        assert!(modified.timestamp > 1);
+    }
+
+    /// Tests that re-adding an identical wallet is a no-op
+    #[test]
+    fn test_add_duplicate_wallet() {
+        let applicator = mock_applicator();
+        let mut wallet = mock_empty_wallet();
+        wallet.orders.insert(Uuid::new_v4(), mock_order());
+
+        applicator.add_wallet(&wallet).unwrap();
+        applicator.add_wallet(&wallet).unwrap();
+
+        let indexed: Wallet =
+            applicator.db().read(WALLETS_TABLE, &wallet.wallet_id).unwrap().unwrap();
+        assert_eq!(indexed, wallet);
+    }
+
+    /// Tests that adding a different wallet with the ID of an existing wallet
+    /// is rejected, leaving the existing wallet in place
+    #[test]
+    fn test_add_conflicting_wallet() {
+        let applicator = mock_applicator();
+        let wallet = mock_empty_wallet();
+        applicator.add_wallet(&wallet).unwrap();
+
+        let mut conflicting = wallet.clone();
+        conflicting.orders.insert(Uuid::new_v4(), mock_order());
+        let res = applicator.add_wallet(&conflicting);
+        assert!(
+            matches!(res, Err(StateApplicatorError::ConflictingWallet(id)) if id == wallet.wallet_id)
+        );
+
+        let indexed: Wallet =
+            applicator.db().read(WALLETS_TABLE, &wallet.wallet_id).unwrap().unwrap();
+        assert_eq!(indexed, wallet);
+    }
+
+    /// Tests that grants and revocations of scoped keys survive wallet updates
+    /// built from a copy of the wallet read before the grant
+    #[test]
+    fn test_scoped_key_grant_and_revoke() {
+        let applicator = mock_applicator();
+        let mut wallet = mock_empty_wallet();
+        applicator.add_wallet(&wallet).unwrap();
+
+        let delegate = mock_empty_wallet().key_chain.public_keys.pk_root;
+        let key =
+            ScopedKey { key: delegate.clone(), scopes: vec![KeyScope::PlaceOrders], expiry: None };
+        applicator.grant_scoped_key(wallet.wallet_id, key.clone()).unwrap();
+
+        // An update from a stale copy of the wallet keeps the grant
+        wallet.orders.insert(Uuid::new_v4(), mock_order());
+        applicator.update_wallet(&wallet).unwrap();
+        let indexed: Wallet =
+            applicator.db().read(WALLETS_TABLE, &wallet.wallet_id).unwrap().unwrap();
+        assert_eq!(indexed.key_chain.scoped_keys, vec![key]);
+        assert_eq!(indexed.orders, wallet.orders);
+
+        // Revoking the key removes it
+        applicator.revoke_scoped_key(wallet.wallet_id, &delegate).unwrap();
+        let indexed: Wallet =
+            applicator.db().read(WALLETS_TABLE, &wallet.wallet_id).unwrap().unwrap();
+        assert!(indexed.key_chain.scoped_keys.is_empty());
+    }
+
+    /// Tests that wallets whose shares do not match their contents or claimed
+    /// commitment are rejected as they are applied, leaving the existing
+    /// wallet in place
+    #[test]
+    fn test_tampered_wallet_update_rejected() {
+        let mut applicator = mock_applicator();
+        applicator.config.verify_wallet_shares = true;
+
+        // A wallet whose opening authenticates its share commitment is applied
+        let mut wallet = mock_empty_wallet();
+        wallet.merkle_proof.as_mut().unwrap().value = wallet.get_wallet_share_commitment();
+        applicator
+            .handle_state_transition(StateTransition::AddWallet { wallet: wallet.clone() })
+            .unwrap();
+
+        // An order added without re-sharing the wallet is rejected
+        let mut tampered = wallet.clone();
+        tampered.orders.insert(Uuid::new_v4(), mock_order());
+        let res = applicator
+            .handle_state_transition(StateTransition::UpdateWallet { wallet: tampered.clone() });
+        assert!(
+            matches!(res, Err(StateApplicatorError::InvalidWalletShares(id)) if id == wallet.wallet_id)
+        );
+        let indexed: Wallet =
+            applicator.db().read(WALLETS_TABLE, &wallet.wallet_id).unwrap().unwrap();
+        assert_eq!(indexed, wallet);
+
+        // Consistent shares that do not commit to the claimed value are rejected
+        let mut mismatched = tampered.clone();
+        mismatched.reblind_wallet();
+        let res = applicator
+            .handle_state_transition(StateTransition::UpdateWallet { wallet: mismatched.clone() });
+        assert!(
+            matches!(res, Err(StateApplicatorError::InvalidWalletShares(id)) if id == wallet.wallet_id)
+        );
+
+        // Once the opening authenticates the new shares, the update applies
+        mismatched.merkle_proof.as_mut().unwrap().value = mismatched.get_wallet_share_commitment();
+        applicator
+            .handle_state_transition(StateTransition::UpdateWallet { wallet: mismatched.clone() })
+            .unwrap();
+        let indexed: Wallet =
+            applicator.db().read(WALLETS_TABLE, &wallet.wallet_id).unwrap().unwrap();
+        assert_eq!(indexed, mismatched);
+    }
+
+    /// Tests that a wallet reusing the blinder of a distinct wallet is rejected
+    /// as it is applied, and that a wallet's superseded blinder is dropped from
+    /// the blinder index
+    #[test]
+    fn test_blinder_reuse_rejected() {
+        let applicator = mock_applicator();
+        let mut wallet = mock_empty_wallet();
+        applicator
+            .handle_state_transition(StateTransition::AddWallet { wallet: wallet.clone() })
+            .unwrap();
+
+        // A distinct wallet reusing the blinder is rejected, by add or update
+        let mut colliding = mock_empty_wallet();
+        colliding.blinder = wallet.blinder;
+        let res = applicator
+            .handle_state_transition(StateTransition::AddWallet { wallet: colliding.clone() });
+        assert!(
+            matches!(res, Err(StateApplicatorError::BlinderReuse(id)) if id == wallet.wallet_id)
+        );
+        let res = applicator
+            .handle_state_transition(StateTransition::UpdateWallet { wallet: colliding.clone() });
+        assert!(
+            matches!(res, Err(StateApplicatorError::BlinderReuse(id)) if id == wallet.wallet_id)
+        );
+        let indexed: Option<Wallet> =
+            applicator.db().read(WALLETS_TABLE, &colliding.wallet_id).unwrap();
+        assert!(indexed.is_none());
+
+        // Reblinding the wallet replaces its entry in the blinder index
+        let old_blinder = wallet.blinder;
+        wallet.reblind_wallet();
+        applicator
+            .handle_state_transition(StateTransition::UpdateWallet { wallet: wallet.clone() })
+            .unwrap();
+
+        let db = applicator.db();
+        let old_entry: Option<Uuid> = db.read(BLINDER_TO_WALLET_TABLE, &old_blinder).unwrap();
+        let new_entry: Option<Uuid> = db.read(BLINDER_TO_WALLET_TABLE, &wallet.blinder).unwrap();
+        assert_eq!(old_entry, None);
+        assert_eq!(new_entry, Some(wallet.wallet_id));
+    }
 }
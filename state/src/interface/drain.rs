@@ -0,0 +1,256 @@
+//! Drains the local node ahead of a graceful shutdown
+//!
+//! A draining node accepts no new orders or matches, waits for the tasks and
+//! handshakes it is executing to complete, and hands raft leadership to another
+//! voter. Once drained the node may be stopped without orphaning in-flight work
+
+use std::{
+    sync::atomic::Ordering,
+    time::{Duration, Instant},
+};
+
+use common::types::tasks::TaskIdentifier;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{error::StateError, replication::error::ReplicationError, State, StateTransition};
+
+/// The interval at which a drain polls for in-flight tasks and leadership
+const DRAIN_POLL_INTERVAL_MS: u64 = 100; // 100 ms
+/// The amount of time a drain waits for leadership to move to another voter
+const LEADERSHIP_TRANSFER_TIMEOUT_MS: u64 = 5_000; // 5 seconds
+
+/// The outcome of draining the local node
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DrainOutcome {
+    /// Whether the node may be stopped, i.e. its in-flight tasks and
+    /// handshakes completed and it no longer holds raft leadership
+    pub ready_to_stop: bool,
+    /// The number of local tasks still in flight when the drain finished
+    pub remaining_tasks: usize,
+    /// The number of handshakes still in flight when the drain finished
+    pub remaining_handshakes: usize,
+}
+
+impl State {
+    // -----------
+    // | Getters |
+    // -----------
+
+    /// Whether the local node is draining, in which case it accepts no new
+    /// orders or matches
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::Relaxed)
+    }
+
+    /// Get the IDs of the queued and running tasks executed by the local node
+    pub fn get_local_task_ids(&self) -> Result<Vec<TaskIdentifier>, StateError> {
+        let self_id = self.get_peer_id()?;
+        let tx = self.db.new_read_tx()?;
+        let mut local_tasks = Vec::new();
+        for task_id in tx.get_all_task_ids()? {
+            if let Some(task) = tx.get_task(&task_id)?
+                && task.executor == self_id
+            {
+                local_tasks.push(task_id);
+            }
+        }
+        tx.commit()?;
+
+        Ok(local_tasks)
+    }
+
+    /// Get the number of handshakes the local node is participating in
+    pub fn num_active_handshakes(&self) -> usize {
+        self.active_handshakes.read().unwrap().len()
+    }
+
+    // -----------
+    // | Setters |
+    // -----------
+
+    /// Record a handshake the local node is participating in
+    pub fn add_active_handshake(&self, request_id: Uuid) {
+        self.active_handshakes.write().unwrap().insert(request_id);
+    }
+
+    /// Record that a handshake the local node participated in has finished
+    pub fn remove_active_handshake(&self, request_id: &Uuid) {
+        self.active_handshakes.write().unwrap().remove(request_id);
+    }
+
+    /// Drain the local node ahead of a shutdown
+    ///
+    /// New orders and matches are rejected from the moment the drain begins.
+    /// The drain then waits up to `timeout` for the local node's in-flight
+    /// tasks and handshakes to complete, and transfers raft leadership away if
+    /// the local node holds it. The returned outcome signals whether the node
+    /// is ready to stop
+    ///
+    /// If the drain is aborted while waiting, it returns without transferring
+    /// leadership and the node is not ready to stop
+    pub async fn begin_drain(&self, timeout: Duration) -> Result<DrainOutcome, StateError> {
+        self.draining.store(true, Ordering::Relaxed);
+        info!("draining local node, awaiting in-flight tasks and handshakes");
+
+        let deadline = Instant::now() + timeout;
+        let (remaining_tasks, remaining_handshakes) = self.await_in_flight_work(deadline).await?;
+        if !self.is_draining() {
+            info!("drain aborted");
+            return Ok(DrainOutcome {
+                ready_to_stop: false,
+                remaining_tasks,
+                remaining_handshakes,
+            });
+        }
+
+        if remaining_tasks > 0 || remaining_handshakes > 0 {
+            warn!(
+                "drain timed out with {remaining_tasks} tasks and {remaining_handshakes} handshakes in flight"
+            );
+        }
+
+        let leadership_moved = self.transfer_leadership().await?;
+        let ready_to_stop = remaining_tasks == 0 && remaining_handshakes == 0 && leadership_moved;
+        info!("local node drained, ready to stop: {ready_to_stop}");
+
+        Ok(DrainOutcome { ready_to_stop, remaining_tasks, remaining_handshakes })
+    }
+
+    /// Abort a drain, the local node resumes accepting new orders and matches
+    ///
+    /// A drain waiting on in-flight work returns once it observes the abort
+    pub fn abort_drain(&self) {
+        if self.draining.swap(false, Ordering::Relaxed) {
+            info!("aborting drain of local node");
+        }
+    }
+
+    // -----------
+    // | Helpers |
+    // -----------
+
+    /// Wait until the local node's in-flight tasks and handshakes complete,
+    /// the deadline passes, or the drain is aborted, returning the number of
+    /// tasks and handshakes still in flight
+    async fn await_in_flight_work(&self, deadline: Instant) -> Result<(usize, usize), StateError> {
+        let poll_interval = Duration::from_millis(DRAIN_POLL_INTERVAL_MS);
+        loop {
+            let tasks = self.get_local_task_ids()?.len();
+            let handshakes = self.num_active_handshakes();
+            let done = tasks == 0 && handshakes == 0;
+            if done || Instant::now() >= deadline || !self.is_draining() {
+                return Ok((tasks, handshakes));
+            }
+
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+
+    /// Transfer raft leadership away from the local node if it is the leader,
+    /// returning whether the local node may stop without holding leadership
+    ///
+    /// A node with no other voter to hand leadership to may stop as is
+    async fn transfer_leadership(&self) -> Result<bool, StateError> {
+        if !self.is_leader() {
+            return Ok(true);
+        }
+
+        let waiter = self.send_proposal(StateTransition::TransferLeadership)?;
+        match waiter.await {
+            Err(StateError::Replication(ReplicationError::LeadershipTransfer(e))) => {
+                info!("not transferring leadership: {e}");
+                return Ok(true);
+            },
+            res => res?,
+        }
+
+        let deadline = Instant::now() + Duration::from_millis(LEADERSHIP_TRANSFER_TIMEOUT_MS);
+        let poll_interval = Duration::from_millis(DRAIN_POLL_INTERVAL_MS);
+        while self.is_leader() && Instant::now() < deadline {
+            tokio::time::sleep(poll_interval).await;
+        }
+
+        Ok(!self.is_leader())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use common::types::tasks::{mocks::mock_task_descriptor, TaskQueueKey};
+    use uuid::Uuid;
+
+    use super::DRAIN_POLL_INTERVAL_MS;
+    use crate::{error::StateError, test_helpers::mock_state};
+
+    /// The drain timeout used in tests
+    const DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Tests that new work is rejected while the node drains, and that the
+    /// drain completes once the in-flight task does
+    #[tokio::test]
+    async fn test_drain() {
+        let state = mock_state();
+        let (task_id, waiter) =
+            state.append_task(mock_task_descriptor(TaskQueueKey::new_v4())).unwrap();
+        waiter.await.unwrap();
+        assert_eq!(state.get_local_task_ids().unwrap(), vec![task_id]);
+
+        let drain = tokio::spawn({
+            let state = state.clone();
+            async move { state.begin_drain(DRAIN_TIMEOUT).await }
+        });
+        while !state.is_draining() {
+            tokio::task::yield_now().await;
+        }
+
+        // New work is rejected during the drain
+        let res = state.append_task(mock_task_descriptor(TaskQueueKey::new_v4()));
+        assert!(matches!(res, Err(StateError::Draining)));
+        assert!(!drain.is_finished());
+
+        // The in-flight task completes, after which the node is ready to stop
+        state.pop_task(task_id).unwrap().await.unwrap();
+        let outcome = drain.await.unwrap().unwrap();
+        assert!(outcome.ready_to_stop);
+        assert_eq!(outcome.remaining_tasks, 0);
+    }
+
+    /// Tests that a drain waits for in-flight handshakes, and that an aborted
+    /// drain returns and resumes accepting new work
+    #[tokio::test]
+    async fn test_drain_awaits_handshakes_and_aborts() {
+        let state = mock_state();
+        let request_id = Uuid::new_v4();
+        state.add_active_handshake(request_id);
+
+        let drain = tokio::spawn({
+            let state = state.clone();
+            async move { state.begin_drain(DRAIN_TIMEOUT).await }
+        });
+        while !state.is_draining() {
+            tokio::task::yield_now().await;
+        }
+
+        // The drain waits on the handshake, aborting it resumes new work
+        tokio::time::sleep(Duration::from_millis(2 * DRAIN_POLL_INTERVAL_MS)).await;
+        assert!(!drain.is_finished());
+        state.abort_drain();
+        let outcome = drain.await.unwrap().unwrap();
+        assert!(!outcome.ready_to_stop);
+        assert_eq!(outcome.remaining_handshakes, 1);
+        assert!(!state.is_draining());
+        let (task_id, waiter) =
+            state.append_task(mock_task_descriptor(TaskQueueKey::new_v4())).unwrap();
+        waiter.await.unwrap();
+
+        // Once the work finishes a new drain completes
+        state.remove_active_handshake(&request_id);
+        state.pop_task(task_id).unwrap().await.unwrap();
+        let outcome = state.begin_drain(DRAIN_TIMEOUT).await.unwrap();
+        assert!(outcome.ready_to_stop);
+        assert_eq!(outcome.remaining_handshakes, 0);
+    }
+}
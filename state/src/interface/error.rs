@@ -3,15 +3,23 @@
 use core::fmt::Display;
 use std::error::Error;
 
+use common::types::wallet::WalletIdentifier;
+
 use crate::{replication::error::ReplicationError, storage::error::StorageError};
 
 /// The state error type
 #[derive(Debug)]
 pub enum StateError {
+    /// A wallet reuses the blinder of the given distinct wallet
+    BlinderReuse(WalletIdentifier),
     /// A database error
     Db(StorageError),
+    /// The local node is draining and accepts no new work
+    Draining,
     /// Invalid state update passed to the interface
     InvalidUpdate(String),
+    /// The node already manages the configured maximum number of wallets
+    MaxManagedWallets(usize),
     /// An error sending a proposal to the replication layer
     Proposal(String),
     /// An error in the replication substrate
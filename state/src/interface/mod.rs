@@ -1,17 +1,20 @@
 //! The `interface` module defines the interface to the state, methods for
 //! proposing state transitions and reading from state
 
+pub mod drain;
 pub mod error;
 pub mod node_metadata;
 pub mod notifications;
 pub mod order_book;
 pub mod peer_index;
+pub mod proof_store;
 pub mod raft;
 pub mod task_queue;
 pub mod wallet_index;
 
 use std::{
-    sync::{Arc, RwLock},
+    collections::HashSet,
+    sync::{atomic::AtomicBool, Arc, RwLock},
     thread,
 };
 
@@ -26,6 +29,7 @@ use job_types::{
 };
 use system_bus::SystemBus;
 use util::err_str;
+use uuid::Uuid;
 
 use crate::{
     replication::{
@@ -34,13 +38,17 @@ use crate::{
             gossip::GossipRaftNetwork,
             traits::{RaftMessageReceiver, RaftNetwork},
         },
-        raft_node::{ReplicationNode, ReplicationNodeConfig},
+        raft_node::{build_raft_config, LeaderGuard, ReplicationNode, ReplicationNodeConfig},
     },
     storage::db::{DbConfig, DB},
     Proposal, StateTransition,
 };
 
-use self::{error::StateError, notifications::ProposalWaiter};
+use self::{
+    error::StateError,
+    notifications::ProposalWaiter,
+    proof_store::{SharedValidityProofStore, ValidityProofStore},
+};
 
 /// The default tick interval for the raft node
 const DEFAULT_TICK_INTERVAL_MS: u64 = 10; // 10 milliseconds
@@ -66,6 +74,17 @@ pub struct State {
     translation_map: SharedPeerIdTranslationMap,
     /// The system bus for sending notifications to other workers
     bus: SystemBus<SystemBusMessage>,
+    /// The bounded store of remote order validity proofs
+    proof_store: SharedValidityProofStore,
+    /// The leadership status of the local raft node
+    leader_guard: LeaderGuard,
+    /// Whether the local node is draining ahead of a shutdown
+    draining: Arc<AtomicBool>,
+    /// The request IDs of the handshakes the local node is participating in,
+    /// a drain waits for these to finish
+    active_handshakes: Arc<RwLock<HashSet<Uuid>>>,
+    /// The maximum number of wallets the node may manage, unbounded if not set
+    max_managed_wallets: Option<usize>,
 }
 
 impl State {
@@ -78,7 +97,9 @@ impl State {
         handshake_manager_queue: HandshakeManagerQueue,
         system_bus: SystemBus<SystemBusMessage>,
     ) -> Result<Self, StateError> {
-        let shared_map = Arc::new(RwLock::new(PeerIdTranslationMap::default()));
+        let translation_map =
+            PeerIdTranslationMap::new_with_namespace(config.cluster_namespace.clone());
+        let shared_map = Arc::new(RwLock::new(translation_map));
         let network = GossipRaftNetwork::new(network_outbound, raft_inbound, shared_map.clone());
         Self::new_with_network_and_map(
             config,
@@ -98,7 +119,9 @@ impl State {
         handshake_manager_queue: HandshakeManagerQueue,
         system_bus: SystemBus<SystemBusMessage>,
     ) -> Result<Self, StateError> {
-        let shared_map = Arc::new(RwLock::new(PeerIdTranslationMap::default()));
+        let translation_map =
+            PeerIdTranslationMap::new_with_namespace(config.cluster_namespace.clone());
+        let shared_map = Arc::new(RwLock::new(translation_map));
         Self::new_with_network_and_map(
             config,
             network,
@@ -119,7 +142,9 @@ impl State {
         translation_map: SharedPeerIdTranslationMap,
     ) -> Result<Self, StateError> {
         // Open up the DB
-        let db = DB::new(&DbConfig { path: config.db_path.clone() }).map_err(StateError::Db)?;
+        let db_config =
+            DbConfig { path: config.db_path.clone(), namespace: config.cluster_namespace.clone() };
+        let db = DB::new(&db_config).map_err(StateError::Db)?;
         let db = Arc::new(db);
 
         // Setup the tables in the DB
@@ -146,6 +171,7 @@ impl State {
         // Start the raft in a new thread
         let raft = ReplicationNode::new_with_config(replication_config, &raft_config)
             .map_err(StateError::Replication)?;
+        let leader_guard = raft.leader_guard();
         thread::spawn(move || {
             raft.run().expect("Raft node failed");
         });
@@ -157,8 +183,14 @@ impl State {
             proposal_queue: Arc::new(proposal_send),
             bus: system_bus,
             translation_map,
+            proof_store: ValidityProofStore::new_shared(config.max_remote_validity_proofs),
+            leader_guard,
+            draining: Arc::new(AtomicBool::new(false)),
+            active_handshakes: Arc::new(RwLock::new(HashSet::new())),
+            max_managed_wallets: config.max_managed_wallets,
         };
         self_.setup_node_metadata(config)?;
+        self_.rebuild_proof_store()?;
         Ok(self_)
     }
 
@@ -169,8 +201,10 @@ impl State {
     /// Build the raft config for the node
     fn build_raft_config(relayer_config: &RelayerConfig) -> RaftConfig {
         let peer_id = relayer_config.p2p_key.public().to_peer_id();
-        let raft_id = PeerIdTranslationMap::get_raft_id(&WrappedPeerId(peer_id));
-        RaftConfig { id: raft_id, ..Default::default() }
+        let namespace = relayer_config.cluster_namespace.as_deref();
+        let raft_id =
+            PeerIdTranslationMap::get_namespaced_raft_id(namespace, &WrappedPeerId(peer_id));
+        build_raft_config(raft_id, relayer_config)
     }
 
     /// Send a proposal to the raft node
@@ -203,4 +237,12 @@ mod test {
         let actual_wallet = state.get_wallet(&wallet.wallet_id).unwrap().unwrap();
         assert_eq!(expected_wallet, actual_wallet);
     }
+
+    /// Test that a single node state elects itself leader
+    #[tokio::test]
+    async fn test_single_node_leader() {
+        let state = mock_state();
+        assert!(state.is_leader());
+        assert!(state.leader_guard().should_run("test"));
+    }
 }
@@ -8,12 +8,14 @@
 use circuit_types::wallet::Nullifier;
 use common::types::{
     gossip::WrappedPeerId,
-    network_order::NetworkOrder,
+    network_order::{NetworkOrder, OrderBookDepth},
     proof_bundles::{OrderValidityProofBundle, OrderValidityWitnessBundle},
-    wallet::OrderIdentifier,
+    wallet::{OrderIdentifier, WalletIdentifier},
 };
 use constants::ORDER_STATE_CHANGE_TOPIC;
 use external_api::bus_message::SystemBusMessage;
+use libmdbx::TransactionKind;
+use num_bigint::BigUint;
 use rand::{
     distributions::{Distribution, WeightedIndex},
     seq::SliceRandom,
@@ -22,8 +24,11 @@ use rand::{
 use util::res_some;
 
 use crate::{
-    error::StateError, notifications::ProposalWaiter, storage::error::StorageError, State,
-    StateTransition,
+    error::StateError,
+    interface::proof_store::ValidityProofStore,
+    notifications::ProposalWaiter,
+    storage::{error::StorageError, tx::StateTxn},
+    State, StateTransition,
 };
 
 /// The error message emitted when a caller attempts to add a local order
@@ -89,6 +94,7 @@ impl State {
         order_id: &OrderIdentifier,
     ) -> Result<Option<OrderValidityProofBundle>, StateError> {
         let order = self.get_order(order_id)?;
+        self.proof_store.lock().unwrap().touch(order_id);
         Ok(order.and_then(|o| o.validity_proofs))
     }
 
@@ -169,22 +175,8 @@ impl State {
 
         let mut res = Vec::new();
         for id in local_order_ids.into_iter() {
-            if let Some(info) = tx.get_order_info(&id)? {
-                // Check that there are no tasks in the queue for the containing wallet
-                // This avoids unnecessary preemptions or possible dropped matches
-                let wallet_id = match tx.get_wallet_for_order(&info.id)? {
-                    None => continue,
-                    Some(wallet) => wallet,
-                };
-
-                if !tx.is_queue_empty(&wallet_id)? || tx.is_queue_paused(&wallet_id)? {
-                    continue;
-                }
-
-                // Check that the order itself is ready for a match
-                if info.ready_for_match() {
-                    res.push(id);
-                }
+            if Self::get_wallet_if_matchable(&id, &tx)?.is_some() {
+                res.push(id);
             }
         }
 
@@ -192,10 +184,47 @@ impl State {
         Ok(res)
     }
 
+    /// Aggregate the depth of the locally managed orders on the given pair
+    ///
+    /// Only orders that are ready for a match are counted, so orders whose
+    /// wallet is locked by a task are excluded
+    pub fn aggregate_local_depth(
+        &self,
+        base: &BigUint,
+        quote: &BigUint,
+    ) -> Result<OrderBookDepth, StateError> {
+        let tx = self.db.new_read_tx()?;
+
+        let mut orders = Vec::new();
+        for id in tx.get_local_orders()?.into_iter() {
+            let wallet_id = match Self::get_wallet_if_matchable(&id, &tx)? {
+                None => continue,
+                Some(wallet_id) => wallet_id,
+            };
+
+            let order = tx.get_wallet(&wallet_id)?.and_then(|w| w.orders.get(&id).cloned());
+            if let Some(order) = order
+                && order.base_mint == *base
+                && order.quote_mint == *quote
+            {
+                orders.push(order);
+            }
+        }
+
+        tx.commit()?;
+        Ok(OrderBookDepth::from_orders(orders.iter()))
+    }
+
     /// Choose an order to handshake with according to their priorities
     ///
+    /// Only orders received at or before `received_before`, a timestamp in
+    /// milliseconds, are considered
+    ///
     /// TODO: Optimize this method if necessary
-    pub fn choose_handshake_order(&self) -> Result<Option<OrderIdentifier>, StateError> {
+    pub fn choose_handshake_order(
+        &self,
+        received_before: u64,
+    ) -> Result<Option<OrderIdentifier>, StateError> {
         let tx = self.db.new_read_tx()?;
 
         // Get all orders and filter by those that are not managed internally and ready
@@ -203,7 +232,9 @@ impl State {
         let mut all_orders = tx.get_all_orders()?;
 
         let my_cluster = tx.get_cluster_id()?;
-        all_orders.retain(|o| o.cluster != my_cluster && o.ready_for_match());
+        all_orders.retain(|o| {
+            o.cluster != my_cluster && o.ready_for_match() && o.received_by(received_before)
+        });
 
         // Get the priorities of each order
         let mut priorities = Vec::with_capacity(all_orders.len());
@@ -251,6 +282,9 @@ impl State {
     }
 
     /// Add a validity proof to an order
+    ///
+    /// The number of remote order proofs held is bounded, adding a proof beyond
+    /// this bound evicts the least recently used proof from the book
     pub fn add_order_validity_proof(
         &self,
         order_id: OrderIdentifier,
@@ -265,7 +299,16 @@ impl State {
             return Err(StateError::InvalidUpdate(ERR_LOCAL_ORDER.to_string()));
         }
 
+        // Hold the store's lock through the commit so that it stays consistent with
+        // the proofs in the book, the store is only updated once the commit succeeds
+        let mut proof_store = self.proof_store.lock().unwrap();
+        if let Some(evicted) = proof_store.eviction_candidate(&order_id) {
+            tx.clear_validity_proof(&evicted)?;
+        }
+
         tx.commit()?;
+        proof_store.insert(order_id);
+        drop(proof_store);
 
         // Push a notification to the system bus
         self.bus.publish(
@@ -285,23 +328,116 @@ impl State {
         self.send_proposal(StateTransition::AddOrderValidityBundle { order_id, proof, witness })
     }
 
+    /// Rebuild the proof store from the remote order proofs held in the book
+    ///
+    /// The store is held in memory, so on startup it must be repopulated from
+    /// the proofs persisted in the DB. Proofs beyond the store's capacity are
+    /// evicted from the book
+    pub fn rebuild_proof_store(&self) -> Result<(), StateError> {
+        let tx = self.db.new_write_tx()?;
+        let mut proof_store = self.proof_store.lock().unwrap();
+        let mut rebuilt = ValidityProofStore::new(proof_store.capacity());
+        for order in tx.get_all_orders()? {
+            if order.local || order.validity_proofs.is_none() {
+                continue;
+            }
+
+            if let Some(evicted) = rebuilt.insert(order.id) {
+                tx.clear_validity_proof(&evicted)?;
+            }
+        }
+
+        tx.commit()?;
+        *proof_store = rebuilt;
+        Ok(())
+    }
+
     /// Nullify all orders on the given nullifier
     pub fn nullify_orders(&self, nullifier: Nullifier) -> Result<(), StateError> {
         let tx = self.db.new_write_tx()?;
+        let order_ids = tx.get_orders_by_nullifier(nullifier)?;
         tx.nullify_orders(nullifier)?;
 
-        Ok(tx.commit()?)
+        // Cancelled orders no longer hold a proof
+        let mut proof_store = self.proof_store.lock().unwrap();
+        tx.commit()?;
+        order_ids.iter().for_each(|id| proof_store.remove(id));
+
+        Ok(())
+    }
+
+    // -----------
+    // | Helpers |
+    // -----------
+
+    /// Get the wallet managing a local order if the order may be matched
+    ///
+    /// Returns `None` if the order is not ready for a match, or if its wallet
+    /// has queued tasks or a paused queue
+    fn get_wallet_if_matchable<T: TransactionKind>(
+        order_id: &OrderIdentifier,
+        tx: &StateTxn<'_, T>,
+    ) -> Result<Option<WalletIdentifier>, StateError> {
+        let info = res_some!(tx.get_order_info(order_id)?);
+
+        // Check that there are no tasks in the queue for the containing wallet
+        // This avoids unnecessary preemptions or possible dropped matches
+        let wallet_id = res_some!(tx.get_wallet_for_order(&info.id)?);
+        if !tx.is_queue_empty(&wallet_id)? || tx.is_queue_paused(&wallet_id)? {
+            return Ok(None);
+        }
+
+        // Check that the order itself is ready for a match
+        Ok(info.ready_for_match().then_some(wallet_id))
     }
 }
 
 #[cfg(test)]
 mod test {
+    use circuit_types::{
+        fixed_point::FixedPoint,
+        order::{Order, OrderSide},
+        Amount,
+    };
     use common::types::{
-        network_order::{test_helpers::dummy_network_order, NetworkOrderState},
-        proof_bundles::mocks::dummy_validity_proof_bundle,
+        network_order::{test_helpers::dummy_network_order, NetworkOrderState, PriceLevel},
+        proof_bundles::mocks::{dummy_validity_proof_bundle, dummy_validity_witness_bundle},
+        tasks::mocks::mock_queued_task,
+        wallet::{OrderIdentifier, Wallet},
+        wallet_mocks::mock_empty_wallet,
     };
+    use num_bigint::BigUint;
 
-    use crate::test_helpers::mock_state;
+    use crate::{
+        interface::proof_store::ValidityProofStore,
+        test_helpers::{mock_state, mock_state_config, mock_state_with_config},
+        State,
+    };
+
+    /// Add a wallet holding the given orders to the state, attaching validity
+    /// proofs to those orders marked as proven
+    async fn add_wallet_with_orders(state: &State, orders: &[(Order, bool)]) -> Wallet {
+        let mut wallet = mock_empty_wallet();
+        state.new_wallet(wallet.clone()).unwrap().await.unwrap();
+
+        let mut proven = Vec::new();
+        for (order, is_proven) in orders.iter() {
+            let id = OrderIdentifier::new_v4();
+            wallet.add_order(id, order.clone()).unwrap();
+            if *is_proven {
+                proven.push(id);
+            }
+        }
+        state.update_wallet(wallet.clone()).unwrap().await.unwrap();
+
+        for id in proven {
+            let proof = dummy_validity_proof_bundle();
+            let witness = dummy_validity_witness_bundle();
+            state.add_local_order_validity_bundle(id, proof, witness).unwrap().await.unwrap();
+        }
+
+        wallet
+    }
 
     /// Test adding an order to the state
     #[test]
@@ -381,6 +517,61 @@ mod test {
         assert!(stored_order.validity_proofs.is_some());
     }
 
+    /// Tests that remote order proofs beyond the configured bound are evicted,
+    /// and that a refetched proof is held again
+    #[test]
+    fn test_validity_proof_eviction() {
+        let mut config = mock_state_config();
+        config.max_remote_validity_proofs = 1;
+        let state = mock_state_with_config(&config);
+
+        let order1 = dummy_network_order();
+        let order2 = dummy_network_order();
+        state.add_order(order1.clone()).unwrap();
+        state.add_order(order2.clone()).unwrap();
+
+        // Adding a second proof evicts the first, the order remains verified
+        state.add_order_validity_proof(order1.id, dummy_validity_proof_bundle()).unwrap();
+        state.add_order_validity_proof(order2.id, dummy_validity_proof_bundle()).unwrap();
+
+        assert!(state.get_validity_proofs(&order1.id).unwrap().is_none());
+        assert!(state.get_validity_proofs(&order2.id).unwrap().is_some());
+        let stored_order = state.get_order(&order1.id).unwrap().unwrap();
+        assert_eq!(stored_order.state, NetworkOrderState::Verified);
+
+        // Refetching the first proof evicts the second
+        state.add_order_validity_proof(order1.id, dummy_validity_proof_bundle()).unwrap();
+        assert!(state.get_validity_proofs(&order1.id).unwrap().is_some());
+        assert!(state.get_validity_proofs(&order2.id).unwrap().is_none());
+    }
+
+    /// Tests that the proof store is rebuilt from the proofs held in the book,
+    /// as it is on startup
+    #[test]
+    fn test_rebuild_proof_store() {
+        let mut config = mock_state_config();
+        config.max_remote_validity_proofs = 2;
+        let state = mock_state_with_config(&config);
+
+        let (order1, order2, order3) =
+            (dummy_network_order(), dummy_network_order(), dummy_network_order());
+        for order in [&order1, &order2, &order3] {
+            state.add_order(order.clone()).unwrap();
+        }
+        state.add_order_validity_proof(order1.id, dummy_validity_proof_bundle()).unwrap();
+
+        // Simulate a restart, which loses the in-memory store
+        *state.proof_store.lock().unwrap() = ValidityProofStore::new(2 /* capacity */);
+        state.rebuild_proof_store().unwrap();
+        assert!(state.proof_store.lock().unwrap().contains(&order1.id));
+
+        // The rebuilt store accounts for the persisted proof when evicting
+        state.add_order_validity_proof(order2.id, dummy_validity_proof_bundle()).unwrap();
+        state.add_order_validity_proof(order3.id, dummy_validity_proof_bundle()).unwrap();
+        assert!(state.get_validity_proofs(&order1.id).unwrap().is_none());
+        assert!(state.get_validity_proofs(&order3.id).unwrap().is_some());
+    }
+
     /// Tests nullifying an order
     #[test]
     fn test_nullify_order() {
@@ -400,4 +591,53 @@ mod test {
         let stored_order = state.get_order(&order.id).unwrap().unwrap();
         assert_eq!(stored_order.state, NetworkOrderState::Cancelled);
     }
+
+    /// Tests aggregating the depth of the locally managed orders on a pair
+    #[tokio::test]
+    async fn test_aggregate_local_depth() {
+        let state = mock_state();
+        let base = BigUint::from(1u8);
+        let quote = BigUint::from(2u8);
+        let order = |side, price, amount| Order {
+            quote_mint: quote.clone(),
+            base_mint: base.clone(),
+            side,
+            amount,
+            worst_case_price: FixedPoint::from_integer(price),
+        };
+
+        // A wallet with two bids at the same level, and an ask without validity
+        // proofs that cannot yet be matched
+        add_wallet_with_orders(
+            &state,
+            &[
+                (order(OrderSide::Buy, 100, 10), true),
+                (order(OrderSide::Buy, 100, 5), true),
+                (order(OrderSide::Buy, 90, 3), true),
+                (order(OrderSide::Sell, 110, 7), true),
+                (order(OrderSide::Sell, 105, 4), false),
+            ],
+        )
+        .await;
+
+        // A wallet locked by a queued task
+        let locked =
+            add_wallet_with_orders(&state, &[(order(OrderSide::Sell, 105, 20), true)]).await;
+        let task = mock_queued_task(locked.wallet_id).descriptor;
+        state.append_task(task).unwrap().1.await.unwrap();
+
+        // A wallet with an ask on the pair and a bid on a different pair
+        let mut other_pair = order(OrderSide::Buy, 100, 50);
+        other_pair.base_mint = BigUint::from(3u8);
+        add_wallet_with_orders(
+            &state,
+            &[(order(OrderSide::Sell, 110, 2), true), (other_pair, true)],
+        )
+        .await;
+
+        let level = |price: u64, amount: Amount| PriceLevel { price: price as f64, amount };
+        let depth = state.aggregate_local_depth(&base, &quote).unwrap();
+        assert_eq!(depth.bids, vec![level(100, 15), level(90, 3)]);
+        assert_eq!(depth.asks, vec![level(110, 9)]);
+    }
 }
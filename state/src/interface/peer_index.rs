@@ -74,6 +74,33 @@ impl State {
         Ok(info.into_keys().collect())
     }
 
+    /// Get the peers that have not heartbeated within their expiry window as
+    /// of `now`, in seconds since the epoch
+    ///
+    /// Peers in the local cluster are held to `cluster_window` seconds, all
+    /// others to `remote_window` seconds. The local peer is never expired
+    pub fn get_expired_peers(
+        &self,
+        now: u64,
+        cluster_window: u64,
+        remote_window: u64,
+    ) -> Result<Vec<PeerInfo>, StateError> {
+        let my_id = self.get_peer_id()?;
+        let my_cluster = self.get_cluster_id()?;
+        let expired = self
+            .get_peer_info_map()?
+            .into_values()
+            .filter(|info| info.peer_id != my_id)
+            .filter(|info| {
+                let window =
+                    if info.cluster_id == my_cluster { cluster_window } else { remote_window };
+                now.saturating_sub(info.get_last_heartbeat()) > window
+            })
+            .collect();
+
+        Ok(expired)
+    }
+
     /// Given a list of peers, return the ones that are not in the peer index
     pub fn get_missing_peers(
         &self,
@@ -191,6 +218,7 @@ mod test {
     use std::str::FromStr;
 
     use common::types::gossip::{mocks::mock_peer, ClusterId};
+    use itertools::Itertools;
 
     use crate::test_helpers::mock_state;
 
@@ -289,4 +317,51 @@ mod test {
 
         assert_eq!(missing_peers, expected);
     }
+
+    /// Tests finding expired peers as the clock advances past each expiry
+    /// window
+    #[test]
+    fn test_get_expired_peers() {
+        const CLUSTER_WINDOW: u64 = 10;
+        const REMOTE_WINDOW: u64 = 30;
+
+        let state = mock_state();
+        let mut cluster_peer = mock_peer();
+        let mut remote_peer = mock_peer();
+        cluster_peer.cluster_id = state.get_cluster_id().unwrap();
+        remote_peer.cluster_id = ClusterId::from_str("test-cluster-2").unwrap();
+
+        state.add_peer(cluster_peer.clone()).unwrap();
+        state.add_peer(remote_peer.clone()).unwrap();
+        let last_heartbeat =
+            |peer_id| state.get_peer_info(peer_id).unwrap().unwrap().get_last_heartbeat();
+        let cluster_heartbeat = last_heartbeat(&cluster_peer.peer_id);
+        let remote_heartbeat = last_heartbeat(&remote_peer.peer_id);
+        let expired_at = |now: u64| {
+            let mut expired = state
+                .get_expired_peers(now, CLUSTER_WINDOW, REMOTE_WINDOW)
+                .unwrap()
+                .into_iter()
+                .map(|info| info.peer_id)
+                .collect_vec();
+            expired.sort();
+            expired
+        };
+
+        // Neither peer has missed its window
+        assert!(expired_at(cluster_heartbeat).is_empty());
+        assert!(expired_at(cluster_heartbeat + CLUSTER_WINDOW).is_empty());
+
+        // The cluster peer expires first
+        assert_eq!(expired_at(cluster_heartbeat + CLUSTER_WINDOW + 1), vec![cluster_peer.peer_id]);
+
+        // Then the remote peer
+        let mut expected = vec![cluster_peer.peer_id, remote_peer.peer_id];
+        expected.sort();
+        assert_eq!(expired_at(remote_heartbeat + REMOTE_WINDOW + 1), expected);
+
+        // Once removed, an expired peer is no longer returned
+        state.remove_peer(cluster_peer.peer_id).unwrap();
+        assert_eq!(expired_at(remote_heartbeat + REMOTE_WINDOW + 1), vec![remote_peer.peer_id]);
+    }
 }
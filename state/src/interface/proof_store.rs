@@ -0,0 +1,137 @@
+//! Tracks the remote orders whose validity proofs are held in the order book
+//!
+//! Remote order proofs are large and are only needed when handshaking on the
+//! order, so we bound the number held in the book and evict the least recently
+//! used. An evicted proof is refetched from the cluster managing the order the
+//! next time a handshake needs it
+
+use std::{
+    num::NonZeroUsize,
+    sync::{Arc, Mutex},
+};
+
+use common::types::wallet::OrderIdentifier;
+use lru::LruCache;
+
+/// A type alias for a proof store shared between state handles
+pub type SharedValidityProofStore = Arc<Mutex<ValidityProofStore>>;
+
+/// An LRU over the remote orders that hold a validity proof in the book
+pub struct ValidityProofStore {
+    /// The orders holding a proof, ordered by recency of use
+    lru: LruCache<OrderIdentifier, ()>,
+}
+
+impl ValidityProofStore {
+    /// Create a new proof store holding at most `capacity` proofs
+    ///
+    /// The capacity is clamped to at least one proof
+    pub fn new(capacity: usize) -> Self {
+        let cap = NonZeroUsize::new(capacity.max(1)).unwrap();
+        Self { lru: LruCache::new(cap) }
+    }
+
+    /// Create a new shared proof store
+    pub fn new_shared(capacity: usize) -> SharedValidityProofStore {
+        Arc::new(Mutex::new(Self::new(capacity)))
+    }
+
+    /// The maximum number of proofs held by the store
+    pub fn capacity(&self) -> usize {
+        self.lru.cap().get()
+    }
+
+    /// The number of proofs tracked by the store
+    pub fn len(&self) -> usize {
+        self.lru.len()
+    }
+
+    /// Whether the store is empty
+    pub fn is_empty(&self) -> bool {
+        self.lru.is_empty()
+    }
+
+    /// Whether the store holds a proof for the given order
+    pub fn contains(&self, order_id: &OrderIdentifier) -> bool {
+        self.lru.contains(order_id)
+    }
+
+    /// Record that a proof is held for the given order, marking it as most
+    /// recently used
+    ///
+    /// Returns the order whose proof should be evicted to stay within capacity,
+    /// if any
+    pub fn insert(&mut self, order_id: OrderIdentifier) -> Option<OrderIdentifier> {
+        if self.lru.get(&order_id).is_some() {
+            return None;
+        }
+
+        self.lru.push(order_id, ()).map(|(evicted, _)| evicted)
+    }
+
+    /// The order whose proof would be evicted by inserting a proof for the
+    /// given order, if any
+    ///
+    /// Does not modify the store, so that the eviction may be committed to the
+    /// book before the store is updated
+    pub fn eviction_candidate(&self, order_id: &OrderIdentifier) -> Option<OrderIdentifier> {
+        if self.lru.contains(order_id) || self.lru.len() < self.capacity() {
+            return None;
+        }
+
+        self.lru.peek_lru().map(|(id, _)| *id)
+    }
+
+    /// Mark the proof of the given order as used, if it is held
+    pub fn touch(&mut self, order_id: &OrderIdentifier) {
+        self.lru.get(order_id);
+    }
+
+    /// Stop tracking the proof of an order, e.g. once the order is cancelled
+    pub fn remove(&mut self, order_id: &OrderIdentifier) {
+        self.lru.pop(order_id);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use common::types::wallet::OrderIdentifier;
+
+    use super::ValidityProofStore;
+
+    /// Tests that the least recently used proof is evicted at capacity
+    #[test]
+    fn test_eviction_order() {
+        let mut store = ValidityProofStore::new(2 /* capacity */);
+        let (id1, id2, id3) =
+            (OrderIdentifier::new_v4(), OrderIdentifier::new_v4(), OrderIdentifier::new_v4());
+
+        assert_eq!(store.insert(id1), None);
+        assert_eq!(store.insert(id2), None);
+
+        // Use the first proof, the second is now least recently used
+        store.touch(&id1);
+        assert_eq!(store.eviction_candidate(&id1), None);
+        assert_eq!(store.eviction_candidate(&id3), Some(id2));
+        assert!(store.contains(&id2));
+        assert_eq!(store.insert(id3), Some(id2));
+        assert!(store.contains(&id1) && store.contains(&id3));
+        assert_eq!(store.len(), 2);
+    }
+
+    /// Tests that re-inserting or removing a tracked order does not evict
+    #[test]
+    fn test_reinsert_and_remove() {
+        let mut store = ValidityProofStore::new(2 /* capacity */);
+        let (id1, id2, id3) =
+            (OrderIdentifier::new_v4(), OrderIdentifier::new_v4(), OrderIdentifier::new_v4());
+
+        store.insert(id1);
+        store.insert(id2);
+        assert_eq!(store.insert(id1), None);
+
+        store.remove(&id2);
+        assert_eq!(store.insert(id3), None);
+        assert!(!store.contains(&id2));
+    }
+}
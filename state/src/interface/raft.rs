@@ -3,11 +3,26 @@
 use common::types::gossip::WrappedPeerId;
 
 use crate::{
-    error::StateError, notifications::ProposalWaiter,
-    replication::network::address_translation::PeerIdTranslationMap, State, StateTransition,
+    error::StateError, notifications::ProposalWaiter, replication::raft_node::LeaderGuard, State,
+    StateTransition,
 };
 
 impl State {
+    // -----------
+    // | Getters |
+    // -----------
+
+    /// Whether the local node is the leader of its raft cluster
+    pub fn is_leader(&self) -> bool {
+        self.leader_guard.is_leader()
+    }
+
+    /// Get a guard on the local node's leadership, used by sweep tasks to run
+    /// destructive maintenance only on the leader
+    pub fn leader_guard(&self) -> LeaderGuard {
+        self.leader_guard.clone()
+    }
+
     // -----------
     // | Setters |
     // -----------
@@ -15,8 +30,10 @@ impl State {
     /// Add a raft peer as a learner to the local cluster
     pub fn add_raft_learner(&self, peer_id: WrappedPeerId) -> Result<ProposalWaiter, StateError> {
         // Add the peer ID to the translation map
-        self.translation_map.write().expect("translation map poisoned").insert(peer_id);
-        let raft_id = PeerIdTranslationMap::get_raft_id(&peer_id);
+        let mut map = self.translation_map.write().expect("translation map poisoned");
+        map.insert(peer_id);
+        let raft_id = map.raft_id(&peer_id);
+        drop(map);
 
         let transition = StateTransition::AddRaftLearner { peer_id: raft_id };
         self.send_proposal(transition)
@@ -27,8 +44,17 @@ impl State {
     /// We do not remove the peer from the translation map, as the leader may
     /// still attempt to contact the peer to forward the removal message
     pub fn remove_raft_peer(&self, peer_id: WrappedPeerId) -> Result<ProposalWaiter, StateError> {
-        let raft_id = PeerIdTranslationMap::get_raft_id(&peer_id);
+        let raft_id =
+            self.translation_map.read().expect("translation map poisoned").raft_id(&peer_id);
         let transition = StateTransition::RemoveRaftPeer { peer_id: raft_id };
         self.send_proposal(transition)
     }
+
+    /// Resync the local node's state from a snapshot of the leader's state
+    ///
+    /// The waiter resolves once the snapshot is installed; the local node
+    /// accepts no new proposals until then
+    pub fn resync_from_leader(&self) -> Result<ProposalWaiter, StateError> {
+        self.send_proposal(StateTransition::ResyncFromLeader)
+    }
 }
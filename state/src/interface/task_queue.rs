@@ -34,6 +34,15 @@ impl State {
         Ok(tasks)
     }
 
+    /// Whether or not the task queue is paused
+    pub fn is_task_queue_paused(&self, key: &TaskQueueKey) -> Result<bool, StateError> {
+        let tx = self.db.new_read_tx()?;
+        let paused = tx.is_queue_paused(key)?;
+        tx.commit()?;
+
+        Ok(paused)
+    }
+
     /// Get the task queue key that a task modifies
     pub fn get_task_queue_key(
         &self,
@@ -85,10 +94,16 @@ impl State {
     // -----------
 
     /// Append a task to the queue
+    ///
+    /// Client requested tasks are rejected while the local node drains
     pub fn append_task(
         &self,
         task: TaskDescriptor,
     ) -> Result<(TaskIdentifier, ProposalWaiter), StateError> {
+        if self.is_draining() && task.is_client_requested() {
+            return Err(StateError::Draining);
+        }
+
         // Pick a task ID and create a task from the description
         let id = TaskIdentifier::new_v4();
         let self_id = self.get_peer_id()?;
@@ -3,8 +3,13 @@
 //! Wallet index updates must go through raft consensus so that the leader may
 //! order them
 
-use circuit_types::order::Order;
-use common::types::wallet::{OrderIdentifier, Wallet, WalletIdentifier};
+use std::collections::{HashMap, HashSet};
+
+use circuit_types::{keychain::PublicSigningKey, order::Order};
+use common::types::wallet::{
+    OrderIdentifier, ScopedKey, Wallet, WalletIdentifier, WalletIndexInconsistency,
+};
+use tracing::warn;
 use util::res_some;
 
 use crate::{error::StateError, notifications::ProposalWaiter, State, StateTransition};
@@ -54,17 +59,284 @@ impl State {
         Ok(wallets)
     }
 
+    /// Walk the wallet index and check that every order maps back to exactly
+    /// one wallet that contains it
+    ///
+    /// Returns the inconsistencies found, which is empty for a consistent index
+    pub fn verify_wallet_index_consistency(
+        &self,
+    ) -> Result<Vec<WalletIndexInconsistency>, StateError> {
+        let tx = self.db.new_read_tx()?;
+        let wallets = tx.get_all_wallets()?;
+        let index = tx.get_order_wallet_index()?;
+        tx.commit()?;
+
+        Ok(find_index_inconsistencies(&wallets, &index))
+    }
+
     // -----------
     // | Setters |
     // -----------
 
     /// Propose a new wallet to be added to the index
     pub fn new_wallet(&self, wallet: Wallet) -> Result<ProposalWaiter, StateError> {
+        self.check_blinder_reuse(&wallet)?;
         self.send_proposal(StateTransition::AddWallet { wallet })
     }
 
     /// Update a wallet in the index
     pub fn update_wallet(&self, wallet: Wallet) -> Result<ProposalWaiter, StateError> {
+        self.check_blinder_reuse(&wallet)?;
         self.send_proposal(StateTransition::UpdateWallet { wallet })
     }
+
+    /// Grant a scoped key on a wallet
+    ///
+    /// Scoped keys are relayer metadata, so the grant is applied directly
+    /// rather than through a wallet update task
+    pub fn grant_scoped_key(
+        &self,
+        wallet_id: WalletIdentifier,
+        key: ScopedKey,
+    ) -> Result<ProposalWaiter, StateError> {
+        self.send_proposal(StateTransition::GrantScopedKey { wallet_id, key })
+    }
+
+    /// Revoke a scoped key on a wallet
+    pub fn revoke_scoped_key(
+        &self,
+        wallet_id: WalletIdentifier,
+        key: PublicSigningKey,
+    ) -> Result<ProposalWaiter, StateError> {
+        self.send_proposal(StateTransition::RevokeScopedKey { wallet_id, key })
+    }
+
+    // -----------
+    // | Helpers |
+    // -----------
+
+    /// Check that managing the given wallet does not exceed the maximum number
+    /// of managed wallets
+    ///
+    /// A wallet already in the index does not count against the limit. Callers
+    /// check before a wallet is committed on-chain or looked up, as the index
+    /// must accept a wallet once it exists on-chain. Wallets admitted
+    /// concurrently may briefly exceed the limit
+    pub fn check_wallet_capacity(&self, wallet_id: &WalletIdentifier) -> Result<(), StateError> {
+        let max_wallets = match self.max_managed_wallets {
+            Some(max) => max,
+            None => return Ok(()),
+        };
+
+        let tx = self.db.new_read_tx()?;
+        let exists = tx.get_wallet(wallet_id)?.is_some();
+        let n_wallets = tx.count_wallets()?;
+        tx.commit()?;
+
+        if !exists && n_wallets >= max_wallets {
+            warn!("rejecting wallet {wallet_id}, node manages {max_wallets} wallets");
+            return Err(StateError::MaxManagedWallets(max_wallets));
+        }
+
+        Ok(())
+    }
+
+    /// Reject a wallet whose blinder is the current blinder of a distinct
+    /// wallet
+    ///
+    /// The applicator enforces the check on every node as the transition is
+    /// applied; checking before proposing rejects a reused blinder without a
+    /// round trip through raft
+    fn check_blinder_reuse(&self, wallet: &Wallet) -> Result<(), StateError> {
+        let tx = self.db.new_read_tx()?;
+        let existing = tx.get_wallet_for_blinder(&wallet.blinder)?;
+        tx.commit()?;
+
+        match existing {
+            Some(existing) if existing != wallet.wallet_id => {
+                warn!("wallet {} reuses the blinder of wallet {existing}", wallet.wallet_id);
+                Err(StateError::BlinderReuse(existing))
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Compare the wallets against the order to wallet index, returning any
+/// inconsistencies between the two
+fn find_index_inconsistencies(
+    wallets: &[Wallet],
+    index: &[(OrderIdentifier, WalletIdentifier)],
+) -> Vec<WalletIndexInconsistency> {
+    let wallet_ids: HashSet<WalletIdentifier> = wallets.iter().map(|w| w.wallet_id).collect();
+    let index_map: HashMap<OrderIdentifier, WalletIdentifier> = index.iter().copied().collect();
+
+    // Map each order to the wallets containing it
+    let mut owners: HashMap<OrderIdentifier, Vec<WalletIdentifier>> = HashMap::new();
+    for wallet in wallets.iter() {
+        for order_id in wallet.orders.keys() {
+            owners.entry(*order_id).or_default().push(wallet.wallet_id);
+        }
+    }
+
+    // Check that each index entry points to a wallet containing the order
+    let mut inconsistencies = Vec::new();
+    for (order_id, wallet_id) in index.iter().copied() {
+        if !wallet_ids.contains(&wallet_id) {
+            inconsistencies.push(WalletIndexInconsistency::MissingWallet { order_id, wallet_id });
+        } else if !owners.get(&order_id).is_some_and(|o| o.contains(&wallet_id)) {
+            inconsistencies.push(WalletIndexInconsistency::OrphanedEntry { order_id, wallet_id });
+        }
+    }
+
+    // Check that each order is contained in one wallet and indexed to it
+    let mut owners = owners.into_iter().collect::<Vec<_>>();
+    owners.sort_by_key(|(order_id, _)| *order_id);
+    for (order_id, mut wallet_ids) in owners {
+        if wallet_ids.len() > 1 {
+            wallet_ids.sort();
+            inconsistencies.push(WalletIndexInconsistency::DuplicateOrder { order_id, wallet_ids });
+        } else if index_map.get(&order_id) != Some(&wallet_ids[0]) {
+            let wallet_id = wallet_ids[0];
+            inconsistencies.push(WalletIndexInconsistency::UnindexedOrder { order_id, wallet_id });
+        }
+    }
+
+    inconsistencies
+}
+
+#[cfg(test)]
+mod test {
+    use common::types::{
+        wallet::{OrderIdentifier, Wallet, WalletIdentifier, WalletIndexInconsistency},
+        wallet_mocks::{mock_empty_wallet, mock_order},
+    };
+
+    use crate::{
+        error::StateError,
+        test_helpers::{mock_state, mock_state_config, mock_state_with_config},
+        State,
+    };
+
+    /// Add a wallet holding `n_orders` orders to the state
+    async fn add_wallet_with_orders(state: &State, n_orders: usize) -> Wallet {
+        let mut wallet = mock_empty_wallet();
+        for _ in 0..n_orders {
+            wallet.add_order(OrderIdentifier::new_v4(), mock_order()).unwrap();
+        }
+
+        state.new_wallet(wallet.clone()).unwrap().await.unwrap();
+        wallet
+    }
+
+    /// Tests that a wallet whose blinder was used by a distinct wallet is
+    /// rejected before it is proposed, while a wallet with a unique blinder is
+    /// accepted
+    #[tokio::test]
+    async fn test_blinder_reuse_rejected() {
+        let state = mock_state();
+        let wallet = add_wallet_with_orders(&state, 0).await;
+
+        // The same wallet may be re-applied with its blinder
+        state.update_wallet(wallet.clone()).unwrap().await.unwrap();
+
+        // A distinct wallet reusing the blinder is rejected
+        let mut colliding = mock_empty_wallet();
+        colliding.blinder = wallet.blinder;
+        let res = state.new_wallet(colliding.clone());
+        assert!(matches!(res, Err(StateError::BlinderReuse(id)) if id == wallet.wallet_id));
+        assert!(state.get_wallet(&colliding.wallet_id).unwrap().is_none());
+
+        // A distinct wallet with a unique blinder is accepted
+        add_wallet_with_orders(&state, 0).await;
+    }
+
+    /// Tests that new wallets are rejected past the maximum number of managed
+    /// wallets
+    #[tokio::test]
+    async fn test_max_managed_wallets() {
+        const MAX_WALLETS: usize = 2;
+        let mut config = mock_state_config();
+        config.max_managed_wallets = Some(MAX_WALLETS);
+        let state = mock_state_with_config(&config);
+
+        let mut wallets = Vec::new();
+        for _ in 0..MAX_WALLETS {
+            wallets.push(add_wallet_with_orders(&state, 0).await);
+        }
+
+        // A wallet past the cap is rejected
+        let wallet = mock_empty_wallet();
+        let res = state.check_wallet_capacity(&wallet.wallet_id);
+        assert!(matches!(res, Err(StateError::MaxManagedWallets(MAX_WALLETS))));
+
+        // An already managed wallet does not count against the cap
+        state.check_wallet_capacity(&wallets[0].wallet_id).unwrap();
+    }
+
+    /// Tests that wallets added through the state produce a consistent index
+    #[tokio::test]
+    async fn test_consistent_index() {
+        let state = mock_state();
+        add_wallet_with_orders(&state, 2).await;
+        add_wallet_with_orders(&state, 3).await;
+
+        let report = state.verify_wallet_index_consistency().unwrap();
+        assert!(report.is_empty());
+    }
+
+    /// Tests that each kind of inconsistency is reported
+    #[tokio::test]
+    async fn test_inconsistent_index() {
+        let state = mock_state();
+        let wallet = add_wallet_with_orders(&state, 1).await;
+        let indexed_order = *wallet.orders.keys().next().unwrap();
+
+        // An order removed from the wallet but left in the index, an index entry
+        // for a missing wallet, and a wallet order that was never indexed
+        let removed_order = OrderIdentifier::new_v4();
+        let missing_wallet = WalletIdentifier::new_v4();
+        let missing_wallet_order = OrderIdentifier::new_v4();
+        let mut unindexed_wallet = mock_empty_wallet();
+        let unindexed_order = OrderIdentifier::new_v4();
+        unindexed_wallet.add_order(unindexed_order, mock_order()).unwrap();
+
+        // A second wallet containing the first wallet's order
+        let mut duplicate_wallet = mock_empty_wallet();
+        duplicate_wallet.add_order(indexed_order, mock_order()).unwrap();
+
+        let tx = state.db.new_write_tx().unwrap();
+        tx.index_orders(&wallet.wallet_id, &[removed_order]).unwrap();
+        tx.index_orders(&missing_wallet, &[missing_wallet_order]).unwrap();
+        tx.write_wallet(&unindexed_wallet).unwrap();
+        tx.write_wallet(&duplicate_wallet).unwrap();
+        tx.commit().unwrap();
+
+        let report = state.verify_wallet_index_consistency().unwrap();
+        let mut duplicate_owners = vec![wallet.wallet_id, duplicate_wallet.wallet_id];
+        duplicate_owners.sort();
+        let expected = [
+            WalletIndexInconsistency::OrphanedEntry {
+                order_id: removed_order,
+                wallet_id: wallet.wallet_id,
+            },
+            WalletIndexInconsistency::MissingWallet {
+                order_id: missing_wallet_order,
+                wallet_id: missing_wallet,
+            },
+            WalletIndexInconsistency::UnindexedOrder {
+                order_id: unindexed_order,
+                wallet_id: unindexed_wallet.wallet_id,
+            },
+            WalletIndexInconsistency::DuplicateOrder {
+                order_id: indexed_order,
+                wallet_ids: duplicate_owners,
+            },
+        ];
+
+        assert_eq!(report.len(), expected.len());
+        for inconsistency in expected.iter() {
+            assert!(report.contains(inconsistency), "missing {inconsistency:?}");
+        }
+    }
 }
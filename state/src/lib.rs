@@ -15,10 +15,11 @@
 #![feature(io_error_more)]
 #![feature(generic_const_exprs)]
 
+use circuit_types::keychain::PublicSigningKey;
 use common::types::{
     proof_bundles::{OrderValidityProofBundle, OrderValidityWitnessBundle},
     tasks::{QueuedTask, QueuedTaskState, TaskIdentifier, TaskQueueKey},
-    wallet::{OrderIdentifier, Wallet},
+    wallet::{OrderIdentifier, ScopedKey, Wallet, WalletIdentifier},
 };
 use replication::{error::ReplicationError, RaftPeerId};
 use serde::{Deserialize, Serialize};
@@ -54,6 +55,9 @@ pub(crate) const ORDERS_TABLE: &str = "orders";
 pub(crate) const ORDER_TO_WALLET_TABLE: &str = "order-to-wallet";
 /// The name of the db table that stores wallet information
 pub(crate) const WALLETS_TABLE: &str = "wallet-info";
+/// The name of the db table that maps each wallet's current blinder to the
+/// wallet
+pub(crate) const BLINDER_TO_WALLET_TABLE: &str = "blinder-to-wallet";
 
 /// The name of the db table that stores task queues
 pub(crate) const TASK_QUEUE_TABLE: &str = "task-queues";
@@ -86,6 +90,10 @@ pub enum StateTransition {
         proof: OrderValidityProofBundle,
         witness: OrderValidityWitnessBundle,
     },
+    /// Grant a scoped key on a wallet, replacing any grant to the same key
+    GrantScopedKey { wallet_id: WalletIdentifier, key: ScopedKey },
+    /// Revoke a wallet's scoped key
+    RevokeScopedKey { wallet_id: WalletIdentifier, key: PublicSigningKey },
 
     // --- Task Queue --- //
     /// Add a task to the task queue
@@ -108,6 +116,15 @@ pub enum StateTransition {
     AddRaftPeer { peer_id: RaftPeerId },
     /// Remove a raft peer from the local consensus cluster
     RemoveRaftPeer { peer_id: RaftPeerId },
+    /// Discard the local node's state and reinstall it from a snapshot of the
+    /// leader's state
+    ///
+    /// Handled by the local replication node, never appended to the log
+    ResyncFromLeader,
+    /// Hand raft leadership from the local node to another voter
+    ///
+    /// Handled by the local replication node, never appended to the log
+    TransferLeadership,
 }
 
 impl From<StateTransition> for Proposal {
@@ -156,7 +173,7 @@ pub mod test_helpers {
     pub fn mock_db() -> DB {
         // Open the DB
         let path = tmp_db_path();
-        let config = DbConfig { path: path.to_string() };
+        let config = DbConfig { path: path.to_string(), namespace: None };
 
         let db = DB::new(&config).unwrap();
 
@@ -177,12 +194,32 @@ pub mod test_helpers {
 
     /// Create a mock state instance with the given task queue
     pub fn mock_state_with_task_queue(task_queue: TaskDriverQueue) -> State {
-        let config =
-            RelayerConfig { db_path: tmp_db_path(), allow_local: true, ..Default::default() };
+        let config = mock_state_config();
+        mock_state_with_config_and_queue(&config, task_queue)
+    }
+
+    /// Create a mock state instance with the given config
+    pub fn mock_state_with_config(config: &RelayerConfig) -> State {
+        let (task_queue, recv) = new_task_driver_queue();
+        mem::forget(recv);
+        mock_state_with_config_and_queue(config, task_queue)
+    }
+
+    /// The default relayer config used for a mock state, callers may override
+    /// fields before passing it to `mock_state_with_config`
+    pub fn mock_state_config() -> RelayerConfig {
+        RelayerConfig { db_path: tmp_db_path(), allow_local: true, ..Default::default() }
+    }
+
+    /// Create a mock state instance with the given config and task queue
+    fn mock_state_with_config_and_queue(
+        config: &RelayerConfig,
+        task_queue: TaskDriverQueue,
+    ) -> State {
         let (_controller, mut nets) = MockNetwork::new_n_way_mesh(1 /* n_nodes */);
         let (handshake_manager_queue, _recv) = new_handshake_manager_queue();
         let state = State::new_with_network(
-            &config,
+            config,
             nets.remove(0),
             task_queue,
             handshake_manager_queue,
@@ -14,12 +14,19 @@ pub enum ReplicationError {
     Applicator(StateApplicatorError),
     /// Error applying a config change to the raft cluster
     ConfChange(String),
+    /// A log entry in storage could not be decoded
+    CorruptLogEntry {
+        /// The index of the corrupt entry
+        index: u64,
+    },
     /// A value was not found in storage
     EntryNotFound,
     /// Error parsing a stored value
     ParseValue(String),
     /// An error reading from the proposal queue
     ProposalQueue(String),
+    /// An error transferring leadership away from the local node
+    LeadershipTransfer(String),
     /// An error from the raft library
     Raft(RaftError),
     /// An error receiving a message
@@ -28,6 +35,8 @@ pub enum ReplicationError {
     SendMessage(IOError),
     /// An error sending a response to a proposal
     ProposalResponse(String),
+    /// An error resyncing the local node from a leader snapshot
+    Resync(String),
     /// An error serializing a value
     SerializeValue(String),
     /// An error interacting with storage
@@ -52,14 +61,16 @@ impl From<ReplicationError> for RaftError {
     fn from(value: ReplicationError) -> Self {
         match value {
             ReplicationError::Applicator(_)
+            | ReplicationError::LeadershipTransfer(_)
             | ReplicationError::ProposalQueue(_)
+            | ReplicationError::Resync(_)
             | ReplicationError::SerializeValue(_) => RaftError::ProposalDropped,
             ReplicationError::ConfChange(e) => RaftError::ConfChangeError(e.to_string()),
             ReplicationError::EntryNotFound => RaftError::Store(RaftStorageError::Unavailable),
             ReplicationError::Raft(e) => e,
             ReplicationError::Storage(e) => e.into(),
-            ReplicationError::ParseValue(s) => {
-                RaftError::Store(RaftStorageError::Other(Box::new(ReplicationError::ParseValue(s))))
+            e @ (ReplicationError::ParseValue(_) | ReplicationError::CorruptLogEntry { .. }) => {
+                RaftError::Store(RaftStorageError::Other(Box::new(e)))
             },
             ReplicationError::SendMessage(e) | ReplicationError::RecvMessage(e) => RaftError::Io(e),
             ReplicationError::ProposalResponse(e) => {
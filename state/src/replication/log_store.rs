@@ -4,6 +4,7 @@
 
 use std::{cmp::Ordering, sync::Arc};
 
+use libmdbx::TransactionKind;
 use protobuf::Message;
 use raft::{
     eraftpb::{ConfState, HardState},
@@ -11,11 +12,16 @@ use raft::{
     Error as RaftError, GetEntriesContext, RaftState, Result as RaftResult, Storage,
     StorageError as RaftStorageError,
 };
+use tracing::warn;
 
 use crate::storage::{
-    db::DB,
+    db::{deserialize_value, serialize_value, DB},
     error::StorageError,
-    tx::raft_log::{lsn_to_key, parse_lsn, RAFT_LOGS_TABLE, RAFT_METADATA_TABLE},
+    tx::{
+        raft_log::{lsn_to_key, parse_lsn, RAFT_LOGS_TABLE, RAFT_METADATA_TABLE},
+        snapshot::AppStateSnapshot,
+        StateTxn,
+    },
 };
 
 use super::error::ReplicationError;
@@ -83,13 +89,131 @@ impl LogStore {
         Ok(tx.commit()?)
     }
 
+    /// Delete all log entries before the given index, e.g. to compact the log
+    /// up to a snapshot
+    pub fn truncate_prefix(&self, up_to: u64) -> Result<(), ReplicationError> {
+        let tx = self.db.new_write_tx()?;
+        tx.delete_log_range(..up_to)?;
+
+        Ok(tx.commit()?)
+    }
+
+    /// Snapshot the consensus state at the given applied index and delete the
+    /// log entries up to and including it
+    ///
+    /// The application state is read from the database when a snapshot is
+    /// requested, so only the snapshot metadata is written here. Returns
+    /// `false` without modifying the log if a snapshot at or past the index is
+    /// already stored, e.g. one installed from the leader
+    pub fn compact(&self, index: u64) -> Result<bool, ReplicationError> {
+        let tx = self.db.new_write_tx()?;
+        let mut metadata = tx.read_snapshot_metadata()?;
+        if index <= metadata.index {
+            tx.commit()?;
+            return Ok(false);
+        }
+
+        metadata.term = tx.read_log_entry(index)?.term;
+        metadata.index = index;
+        metadata.set_conf_state(tx.read_conf_state()?);
+        tx.write_snapshot_metadata(metadata)?;
+        tx.delete_log_range(..=index)?;
+        tx.commit()?;
+
+        Ok(true)
+    }
+
+    /// Delete all log entries at or after the given index
+    pub fn truncate_suffix(&self, from: u64) -> Result<(), ReplicationError> {
+        let tx = self.db.new_write_tx()?;
+        tx.delete_log_range(from..)?;
+
+        Ok(tx.commit()?)
+    }
+
     /// Apply a snapshot to the log store
+    ///
+    /// If the snapshot carries application state, the wallet index and order
+    /// book are replaced with it in the same transaction as the metadata
     pub fn apply_snapshot(&self, snapshot: &RaftSnapshot) -> Result<(), ReplicationError> {
         let tx = self.db.new_write_tx()?;
         tx.apply_snapshot(snapshot)?;
 
+        let data = snapshot.get_data();
+        if !data.is_empty() {
+            let app_state: AppStateSnapshot = deserialize_value(data)?;
+            tx.install_app_state_snapshot(&app_state)?;
+        }
+
         Ok(tx.commit()?)
     }
+
+    /// Truncate the log at its first corrupt entry past the applied index,
+    /// returning the index truncated from, if any
+    ///
+    /// Run at startup, before the raft reads the log. Entries at or below the
+    /// applied index, i.e. the stored snapshot's index, are never truncated.
+    /// The commit index is lowered to the end of the truncated log, and the
+    /// caller must request a snapshot from the leader to replace the entries
+    pub fn recover_corrupt_entries(&self) -> Result<Option<u64>, ReplicationError> {
+        let tx = self.db.new_write_tx()?;
+        let applied = tx.read_snapshot_metadata()?.index;
+        let Some(index) = tx.find_corrupt_entry(applied + 1)? else {
+            tx.commit()?;
+            return Ok(None);
+        };
+
+        warn!("corrupt raft log entry at index {index}, truncating log");
+        tx.delete_log_range(index..)?;
+        let mut hard_state = tx.read_hard_state()?;
+        if hard_state.commit >= index {
+            hard_state.commit = index - 1;
+            tx.apply_hard_state(hard_state)?;
+        }
+        tx.commit()?;
+
+        Ok(Some(index))
+    }
+
+    // -----------
+    // | Helpers |
+    // -----------
+
+    /// Build a snapshot holding only the consensus metadata, without the
+    /// application state
+    ///
+    /// Used directly when only the snapshot's index or term is needed, to avoid
+    /// reading the application state from the database
+    fn snapshot_metadata<T: TransactionKind>(
+        tx: &StateTxn<'_, T>,
+        request_index: u64,
+    ) -> RaftResult<RaftSnapshot> {
+        let mut snap = RaftSnapshot::default();
+        let md = snap.mut_metadata();
+
+        // Read the snapshot metadata from the metadata table
+        let hard_state = tx.read_hard_state()?;
+        md.index = hard_state.commit;
+        md.term = hard_state.term;
+
+        let stored_metadata = tx.read_snapshot_metadata()?;
+        md.term = match md.index.cmp(&stored_metadata.index) {
+            Ordering::Equal => stored_metadata.term,
+            Ordering::Greater => tx.read_log_entry(md.index).map(|entry| entry.term)?,
+            Ordering::Less => {
+                return Err(RaftError::Store(RaftStorageError::SnapshotOutOfDate));
+            },
+        };
+
+        if md.index < request_index {
+            md.index = request_index;
+        }
+
+        let conf_state = tx.read_conf_state()?;
+        md.set_conf_state(conf_state);
+
+        Ok(snap)
+    }
 }
 
 impl Storage for LogStore {
@@ -108,6 +232,9 @@ impl Storage for LogStore {
     /// in bytes
     ///
     /// Entries are in the range [low, high) and are returned in ascending order
+    ///
+    /// An entry in the range that cannot be decoded fails the read with a
+    /// `CorruptLogEntry` error, the log is repaired at startup
     fn entries(
         &self,
         low: u64,
@@ -124,10 +251,17 @@ impl Storage for LogStore {
         let mut entries = Vec::new();
         let mut remaining_space = max_size.into().map(|v| v as u32).unwrap_or(u32::MAX);
 
-        for record in cursor.into_iter().map(|entry| {
-            entry.map_err(RaftError::from).map(|(key, value)| (key, value.into_inner()))
-        }) {
-            let (key, entry) = record?;
+        // The index of the next entry expected under the cursor
+        let mut next_index = low;
+        for record in cursor.into_iter() {
+            let (key, entry) = match record {
+                Ok((key, value)) => (key, value.into_inner()),
+                Err(StorageError::Deserialization(_)) if next_index < high => {
+                    return Err(ReplicationError::CorruptLogEntry { index: next_index }.into());
+                },
+                Err(StorageError::Deserialization(_)) => break,
+                Err(e) => return Err(e.into()),
+            };
             let lsn = parse_lsn(&key).map_err(RaftError::from)?;
 
             // If we've reached the end of the range, break
@@ -145,8 +279,10 @@ impl Storage for LogStore {
             // Otherwise, add the entry to the list and update the remaining space
             entries.push(entry);
             remaining_space = remaining_space.saturating_sub(size);
+            next_index = lsn + 1;
         }
 
+        tx.commit()?;
         Ok(entries)
     }
 
@@ -154,16 +290,18 @@ impl Storage for LogStore {
     fn term(&self, idx: u64) -> RaftResult<u64> {
         let tx = self.db.new_read_tx()?;
         match tx.read_log_entry(idx).map(|entry| entry.term) {
-            // Check the snapshot if not found
+            // Check the snapshot if not found, entries before it have been compacted
             Err(StorageError::NotFound(_)) => {
-                if let Ok(snap) = self.snapshot(idx, UNUSED)
-                    && snap.get_metadata().get_index() == idx
-                {
-                    Ok(snap.get_metadata().get_term())
-                } else {
-                    Err(RaftError::Store(RaftStorageError::Unavailable))
+                let snapshot = tx.read_snapshot_metadata()?;
+                match idx.cmp(&snapshot.index) {
+                    Ordering::Equal => Ok(snapshot.term),
+                    Ordering::Less => Err(RaftError::Store(RaftStorageError::Compacted)),
+                    Ordering::Greater => Err(RaftError::Store(RaftStorageError::Unavailable)),
                 }
             },
+            Err(StorageError::Deserialization(_)) => {
+                Err(ReplicationError::CorruptLogEntry { index: idx }.into())
+            },
             res => res.map_err(RaftError::from),
         }
     }
@@ -178,7 +316,7 @@ impl Storage for LogStore {
             Some((key, _)) => parse_lsn(&key).map_err(RaftError::from),
             None => {
                 let snapshot_idx =
-                    self.snapshot(0 /* request_idx */, UNUSED)?.get_metadata().get_index();
+                    Self::snapshot_metadata(&tx, 0 /* request_idx */)?.get_metadata().get_index();
 
                 Ok(snapshot_idx + 1)
             },
@@ -195,7 +333,7 @@ impl Storage for LogStore {
             Some((key, _)) => parse_lsn(&key).map_err(RaftError::from),
             None => {
                 let snapshot_idx =
-                    self.snapshot(0 /* request_idx */, UNUSED)?.get_metadata().get_index();
+                    Self::snapshot_metadata(&tx, 0 /* request_idx */)?.get_metadata().get_index();
 
                 Ok(snapshot_idx)
             },
@@ -206,33 +344,19 @@ impl Storage for LogStore {
     ///
     /// A snapshot index mustn't be less than `request_index`
     ///
+    /// Alongside the raft metadata, the snapshot carries the wallet index and
+    /// order book so that a follower may catch up without replaying the log
+    ///
     /// The `to` field indicates the peer this will be sent to, unused here
     fn snapshot(&self, request_index: u64, _to: u64) -> RaftResult<RaftSnapshot> {
+        // Read the metadata and application state in one transaction so that the
+        // two are consistent
         let tx = self.db.new_read_tx()?;
-        let mut snap = RaftSnapshot::default();
-        let md = snap.mut_metadata();
-
-        // Read the snapshot metadata from the metadata table
-        let hard_state = tx.read_hard_state()?;
-        md.index = hard_state.commit;
-        md.term = hard_state.term;
-
-        let stored_metadata = tx.read_snapshot_metadata()?;
-        md.term = match md.index.cmp(&stored_metadata.index) {
-            Ordering::Equal => stored_metadata.term,
-            Ordering::Greater => tx.read_log_entry(md.index).map(|entry| entry.term)?,
-            Ordering::Less => {
-                return Err(RaftError::Store(RaftStorageError::SnapshotOutOfDate));
-            },
-        };
-
-        if md.index < request_index {
-            md.index = request_index;
-        }
-
-        let conf_state = tx.read_conf_state()?;
-        md.set_conf_state(conf_state);
+        let mut snap = Self::snapshot_metadata(&tx, request_index)?;
+        let app_state = tx.read_app_state_snapshot()?;
+        tx.commit()?;
 
+        snap.data = serialize_value(&app_state)?.into();
         Ok(snap)
     }
 }
@@ -241,6 +365,9 @@ impl Storage for LogStore {
 mod test {
     use std::sync::Arc;
 
+    use common::types::{
+        network_order::test_helpers::dummy_network_order, wallet_mocks::mock_empty_wallet,
+    };
     use protobuf::Message;
     use raft::{
         prelude::{ConfState, Entry as RaftEntry, HardState, Snapshot, SnapshotMetadata},
@@ -248,9 +375,15 @@ mod test {
     };
     use rand::{seq::IteratorRandom, thread_rng};
 
-    use crate::test_helpers::mock_db;
+    use crate::{
+        storage::{
+            db::{serialize_value, DB},
+            tx::raft_log::{lsn_to_key, RAFT_LOGS_TABLE},
+        },
+        test_helpers::mock_db,
+    };
 
-    use super::LogStore;
+    use super::{LogStore, UNUSED};
 
     // -----------
     // | Helpers |
@@ -347,6 +480,57 @@ mod test {
         assert_eq!(snap_res.get_metadata(), snap.get_metadata());
     }
 
+    /// Tests installing a snapshot carrying application state on a follower
+    #[test]
+    fn test_install_app_state_snapshot() {
+        const N: usize = 5;
+        let leader_db = Arc::new(mock_db());
+        let follower_db = Arc::new(mock_db());
+        let leader = LogStore::new(leader_db.clone()).unwrap();
+        let follower = LogStore::new(follower_db.clone()).unwrap();
+
+        // Populate the leader's wallet index and order book
+        let tx = leader_db.new_write_tx().unwrap();
+        for _ in 0..N {
+            let order = dummy_network_order();
+            tx.write_order(&order).unwrap();
+            tx.write_order_priority(&order).unwrap();
+            tx.write_wallet(&mock_empty_wallet()).unwrap();
+        }
+        tx.commit().unwrap();
+
+        // Give the follower stale state that the snapshot should replace
+        let tx = follower_db.new_write_tx().unwrap();
+        tx.write_wallet(&mock_empty_wallet()).unwrap();
+        tx.commit().unwrap();
+
+        // Install the leader's snapshot on the follower
+        let snap = leader.snapshot(0 /* request_index */, UNUSED).unwrap();
+        assert!(!snap.get_data().is_empty());
+        follower.apply_snapshot(&snap).unwrap();
+
+        // The follower's application state should match the leader's
+        let read_app_state = |db: &DB| {
+            let tx = db.new_read_tx().unwrap();
+            let wallets = tx.get_all_wallets().unwrap();
+            let orders = tx.get_all_orders().unwrap();
+            let snapshot = tx.read_app_state_snapshot().unwrap();
+            (wallets, orders.into_iter().map(|o| o.id).collect::<Vec<_>>(), snapshot)
+        };
+
+        let (leader_wallets, leader_orders, leader_snap) = read_app_state(&leader_db);
+        let (follower_wallets, follower_orders, follower_snap) = read_app_state(&follower_db);
+        assert_eq!(leader_wallets.len(), N);
+        assert_eq!(leader_orders.len(), N);
+        assert_eq!(follower_wallets, leader_wallets);
+        assert_eq!(follower_orders, leader_orders);
+        assert_eq!(follower_snap, leader_snap);
+
+        // The raft metadata should be applied as well
+        let follower_snap = follower.snapshot(0 /* request_index */, UNUSED).unwrap();
+        assert_eq!(follower_snap.get_metadata(), snap.get_metadata());
+    }
+
     // -------------------
     // | Log Entry Tests |
     // -------------------
@@ -446,4 +630,97 @@ mod test {
         assert_eq!(entries_res.len(), n_entries);
         assert_eq!(entries_res, &entries[low..(low + entries_res.len())]);
     }
+
+    /// Tests truncating a prefix and a suffix of the log
+    #[test]
+    fn test_log_truncation() {
+        const N: usize = 100;
+        const PREFIX: u64 = 30;
+        const SUFFIX: u64 = 80;
+        let store = mock_log_store();
+
+        let entries = empty_entries(N);
+        add_entry_batch(&store, &entries);
+
+        // Truncate a prefix of the log
+        store.truncate_prefix(PREFIX).unwrap();
+        assert_eq!(store.first_index().unwrap(), PREFIX);
+        assert_eq!(store.last_index().unwrap(), (N - 1) as u64);
+
+        let entries_res = store
+            .entries(0, N as u64, None, GetEntriesContext::empty(false /* can_async */))
+            .unwrap();
+        assert_eq!(entries_res, &entries[PREFIX as usize..]);
+
+        // Truncate a suffix of the log
+        store.truncate_suffix(SUFFIX).unwrap();
+        assert_eq!(store.first_index().unwrap(), PREFIX);
+        assert_eq!(store.last_index().unwrap(), SUFFIX - 1);
+
+        let entries_res = store
+            .entries(PREFIX, SUFFIX, None, GetEntriesContext::empty(false /* can_async */))
+            .unwrap();
+        assert_eq!(entries_res, &entries[PREFIX as usize..SUFFIX as usize]);
+    }
+
+    /// Overwrite the log entry at the given index with bytes that do not decode
+    fn corrupt_entry(store: &LogStore, index: u64) {
+        let key_bytes = serialize_value(&lsn_to_key(index)).unwrap();
+        let tx = store.db.new_write_tx().unwrap();
+        tx.inner().write_raw(RAFT_LOGS_TABLE, &key_bytes, &[0xff; 3]).unwrap();
+        tx.commit().unwrap();
+    }
+
+    /// Tests that reading a corrupt entry fails rather than returning a
+    /// shortened log
+    #[test]
+    fn test_corrupt_entry_read() {
+        const N: usize = 10;
+        const CORRUPT_INDEX: u64 = 5;
+        let store = mock_log_store();
+        add_entry_batch(&store, &empty_entries(N));
+        corrupt_entry(&store, CORRUPT_INDEX);
+
+        let res = store.entries(0, N as u64, None, GetEntriesContext::empty(false /* can_async */));
+        let err = res.unwrap_err().to_string();
+        assert!(err.contains(&format!("CorruptLogEntry {{ index: {CORRUPT_INDEX} }}")));
+
+        // The log is left intact
+        assert_eq!(store.last_index().unwrap(), (N - 1) as u64);
+    }
+
+    /// Tests that recovery truncates the log at the first corrupt entry past
+    /// the applied index and lowers the commit index to match
+    #[test]
+    fn test_corrupt_entry_recovery() {
+        const N: usize = 10;
+        const APPLIED: u64 = 3;
+        let store = mock_log_store();
+        let entries = empty_entries(N);
+        add_entry_batch(&store, &entries);
+
+        let mut snap = Snapshot::new();
+        snap.mut_metadata().index = APPLIED;
+        store.apply_snapshot(&snap).unwrap();
+        let mut hard_state = HardState::new();
+        hard_state.commit = (N - 1) as u64;
+        store.apply_hard_state(hard_state).unwrap();
+
+        // A corrupt applied entry is left in place, the first corrupt entry past
+        // the applied index is truncated from
+        corrupt_entry(&store, APPLIED - 1);
+        corrupt_entry(&store, 7);
+        corrupt_entry(&store, 6);
+        assert_eq!(store.recover_corrupt_entries().unwrap(), Some(6));
+        assert_eq!(store.last_index().unwrap(), 5);
+        assert_eq!(store.initial_state().unwrap().hard_state.commit, 5);
+
+        let entries_res = store
+            .entries(APPLIED, 6, None, GetEntriesContext::empty(false /* can_async */))
+            .unwrap();
+        assert_eq!(entries_res, &entries[APPLIED as usize..6]);
+
+        // A clean log needs no recovery
+        assert_eq!(store.recover_corrupt_entries().unwrap(), None);
+    }
 }
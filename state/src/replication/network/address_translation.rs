@@ -17,20 +17,33 @@ pub type SharedPeerIdTranslationMap = Arc<RwLock<PeerIdTranslationMap>>;
 pub struct PeerIdTranslationMap {
     /// The underlying mapping
     map: HashMap<u64, WrappedPeerId>,
+    /// The cluster namespace mixed into raft IDs, if any
+    namespace: Option<String>,
 }
 
 impl PeerIdTranslationMap {
     /// Constructor
     pub fn new() -> Self {
-        Self { map: HashMap::new() }
+        Self::new_with_namespace(None)
+    }
+
+    /// Constructor for a map whose raft IDs are derived within the given
+    /// cluster namespace
+    pub fn new_with_namespace(namespace: Option<String>) -> Self {
+        Self { map: HashMap::new(), namespace }
     }
 
     /// Insert a new peer ID
     pub fn insert(&mut self, peer_id: WrappedPeerId) {
-        let raft_id = Self::get_raft_id(&peer_id);
+        let raft_id = self.raft_id(&peer_id);
         self.map.insert(raft_id, peer_id);
     }
 
+    /// Translate a peer ID to a raft ID within the map's namespace
+    pub fn raft_id(&self, peer_id: &WrappedPeerId) -> u64 {
+        Self::get_namespaced_raft_id(self.namespace.as_deref(), peer_id)
+    }
+
     /// Get a peer ID from a raft ID
     pub fn get_peer_id(&self, raft_id: u64) -> Option<WrappedPeerId> {
         self.map.get(&raft_id).cloned()
@@ -43,4 +56,15 @@ impl PeerIdTranslationMap {
     pub fn get_raft_id(peer_id: &WrappedPeerId) -> u64 {
         fxhash64(&peer_id)
     }
+
+    /// Translate a peer ID to a raft ID within a cluster namespace
+    ///
+    /// The namespace is hashed alongside the peer ID so that the same peer
+    /// takes a distinct raft ID in each namespace
+    pub fn get_namespaced_raft_id(namespace: Option<&str>, peer_id: &WrappedPeerId) -> u64 {
+        match namespace {
+            Some(namespace) => fxhash64(&(namespace, peer_id)),
+            None => Self::get_raft_id(peer_id),
+        }
+    }
 }
@@ -3,12 +3,16 @@
 
 use std::{
     collections::HashMap,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     thread,
     time::{Duration, Instant},
 };
 
 use config::RelayerConfig;
+use constants::RAFT_LEADERSHIP_TOPIC;
 use crossbeam::channel::{Receiver as CrossbeamReceiver, TryRecvError};
 use external_api::bus_message::SystemBusMessage;
 use job_types::{handshake_manager::HandshakeManagerQueue, task_driver::TaskDriverQueue};
@@ -19,13 +23,14 @@ use raft::{
         ConfChangeSingle, ConfChangeType, ConfChangeV2, Entry, EntryType, HardState,
         Message as RaftMessage, Snapshot,
     },
-    Config as RaftConfig, Error as RaftError, RawNode, StateRole, Storage, INVALID_ID,
+    Config as RaftConfig, Error as RaftError, RawNode, SoftState, StateRole, Storage, INVALID_ID,
 };
 use rand::{thread_rng, RngCore};
+use renegade_metrics::registry::RELAYER_METRICS;
 use slog::Logger;
 use system_bus::SystemBus;
 use tokio::sync::oneshot::Sender as OneshotSender;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 use tracing_slog::TracingSlogDrain;
 use util::err_str;
 use uuid::Uuid;
@@ -54,6 +59,9 @@ const PROMOTION_INTERVAL_MS: u64 = 1_000; // 1 second
 /// This is the number of log entries that a learner may be behind a leader and
 /// still considered for promotion
 const PROMOTION_ENTRY_THRESHOLD: u64 = 5;
+/// The amount of time a resync may await a snapshot from the leader before it
+/// is abandoned
+const RESYNC_TIMEOUT_MS: u64 = 30_000; // 30 seconds
 
 /// Error message emitted when the proposal queue is disconnected
 const PROPOSAL_QUEUE_DISCONNECTED: &str = "Proposal queue disconnected";
@@ -61,6 +69,51 @@ const PROPOSAL_QUEUE_DISCONNECTED: &str = "Proposal queue disconnected";
 const ERR_PROPOSAL_RESPONSE: &str = "Failed to send proposal response";
 /// Error message emitted when an invalid ID is found in a proposal's context
 const ERR_INVALID_PROPOSAL_ID: &str = "Invalid proposal ID";
+/// Error message emitted when a resync is requested on the leader
+const ERR_RESYNC_ON_LEADER: &str = "the leader cannot resync from itself";
+/// Error message emitted when a resync is requested while one is in progress
+const ERR_RESYNC_IN_PROGRESS: &str = "a resync is already in progress";
+/// Error message emitted when a resync does not receive a snapshot in time
+const ERR_RESYNC_TIMEOUT: &str = "timed out awaiting a snapshot from the leader";
+/// Error message emitted when leadership is transferred away from a follower
+const ERR_TRANSFER_NOT_LEADER: &str = "the local node is not the leader";
+/// Error message emitted when the leader has no voter to transfer leadership to
+const ERR_NO_TRANSFEREE: &str = "no other voter to transfer leadership to";
+
+/// A shared handle on the local node's leadership status
+///
+/// Background jobs that perform destructive maintenance (e.g. expiry sweeps)
+/// must run on exactly one node, so they check this guard and skip their work
+/// on followers, which observe the results through replication instead
+#[derive(Clone, Default)]
+pub struct LeaderGuard {
+    /// Whether the local node is currently the raft leader
+    is_leader: Arc<AtomicBool>,
+}
+
+impl LeaderGuard {
+    /// Whether the local node is currently the raft leader
+    pub fn is_leader(&self) -> bool {
+        self.is_leader.load(Ordering::Relaxed)
+    }
+
+    /// Whether the named sweep should run on the local node
+    ///
+    /// Returns `false` on followers, which skip the sweep
+    pub fn should_run(&self, sweep: &str) -> bool {
+        let is_leader = self.is_leader();
+        if !is_leader {
+            debug!("skipping {sweep} sweep, local node is not the raft leader");
+        }
+
+        is_leader
+    }
+
+    /// Set the leadership status of the local node
+    fn set_leader(&self, is_leader: bool) {
+        self.is_leader.store(is_leader, Ordering::Relaxed);
+    }
+}
 
 /// The config for the local replication node
 #[derive(Clone)]
@@ -99,8 +152,33 @@ pub struct ReplicationNode<N: RaftNetwork> {
     network: N,
     /// A handle on the database underlying the state
     db: Arc<DB>,
+    /// A handle to the system-global bus, used to publish leadership changes
+    system_bus: SystemBus<SystemBusMessage>,
+    /// The raft ID of the last leader observed by the local node
+    leader_id: u64,
+    /// The leader ID, term, and local leadership last published to the bus,
+    /// used to detect changes in leadership
+    last_leadership: (u64, u64, bool),
+    /// The leadership status of the local node, shared with the state
+    /// interface
+    leader_guard: LeaderGuard,
     /// Maps proposal IDs to a response channel for the proposal
     proposal_responses: HashMap<Uuid, OneshotSender<Result<(), ReplicationError>>>,
+    /// The proposal ID and start time of an in-progress resync, if one exists
+    ///
+    /// New proposals are left in the queue until the resync completes
+    resync: Option<(Uuid, Instant)>,
+    /// The number of applied entries after which the log is compacted
+    snapshot_interval_entries: u64,
+    /// The interval after which the log is compacted, if entries have been
+    /// applied since the last compaction
+    snapshot_interval: Duration,
+    /// The index through which the log was last compacted, and the time at
+    /// which it was compacted
+    last_compaction: (u64, Instant),
+    /// The index at which the log was truncated over a corrupt entry at
+    /// startup, held until a snapshot is requested from the leader
+    pending_snapshot_request: Option<u64>,
 }
 
 impl<N: RaftNetwork> ReplicationNode<N> {
@@ -108,8 +186,9 @@ impl<N: RaftNetwork> ReplicationNode<N> {
     pub fn new(config: ReplicationNodeConfig<N>) -> Result<Self, ReplicationError> {
         // TODO: Replace random node ID with the first 8 bytes of the local peer ID
         let my_id = thread_rng().next_u64();
+        let raft_config = build_raft_config(my_id, &config.relayer_config);
 
-        Self::new_with_config(config, &RaftConfig { id: my_id, ..Default::default() })
+        Self::new_with_config(config, &raft_config)
     }
 
     /// Creates a new replication node with a given raft config
@@ -119,6 +198,10 @@ impl<N: RaftNetwork> ReplicationNode<N> {
     ) -> Result<Self, ReplicationError> {
         // Build the log store on top of the DB
         let store = LogStore::new(config.db.clone())?;
+
+        // Repair the log before the stored snapshot is reset, so that entries at or
+        // below the applied index are left intact
+        let pending_snapshot_request = store.recover_corrupt_entries()?;
         Self::setup_storage(raft_config.id, &store)?;
 
         // Build a state applicator to handle state transitions
@@ -128,10 +211,15 @@ impl<N: RaftNetwork> ReplicationNode<N> {
             task_queue: config.task_queue,
             handshake_manager_queue: config.handshake_manager_queue,
             db: config.db.clone(),
-            system_bus: config.system_bus,
+            system_bus: config.system_bus.clone(),
+            verify_wallet_shares: config.relayer_config.verify_wallet_shares,
         })
         .map_err(ReplicationError::Applicator)?;
 
+        let snapshot_interval_entries = config.relayer_config.raft_snapshot_interval_entries;
+        let snapshot_interval =
+            Duration::from_millis(config.relayer_config.raft_snapshot_interval_ms);
+
         // Build an slog logger and connect it to the tracing logger
         let tracing_drain = TracingSlogDrain;
         let logger = Logger::root(tracing_drain, slog::o!());
@@ -146,7 +234,16 @@ impl<N: RaftNetwork> ReplicationNode<N> {
             proposal_queue: config.proposal_queue,
             network: config.network,
             db: config.db,
+            system_bus: config.system_bus,
+            leader_id: INVALID_ID,
+            last_leadership: (INVALID_ID, 0, false),
+            leader_guard: LeaderGuard::default(),
             proposal_responses: HashMap::new(),
+            resync: None,
+            snapshot_interval_entries,
+            snapshot_interval,
+            last_compaction: (0, Instant::now()),
+            pending_snapshot_request,
         })
     }
 
@@ -190,14 +287,16 @@ impl<N: RaftNetwork> ReplicationNode<N> {
         loop {
             thread::sleep(poll_interval);
 
-            // Check for new proposals
-            while let Some(Proposal { transition, response }) =
-                self.proposal_queue.try_recv().map(Some).or_else(|e| match e {
-                    TryRecvError::Empty => Ok(None),
-                    TryRecvError::Disconnected => Err(ReplicationError::ProposalQueue(
-                        PROPOSAL_QUEUE_DISCONNECTED.to_string(),
-                    )),
-                })?
+            // Check for new proposals, these are held in the queue during a resync so
+            // that they are not applied to state that is about to be replaced
+            while self.resync.is_none()
+                && let Some(Proposal { transition, response }) =
+                    self.proposal_queue.try_recv().map(Some).or_else(|e| match e {
+                        TryRecvError::Empty => Ok(None),
+                        TryRecvError::Disconnected => Err(ReplicationError::ProposalQueue(
+                            PROPOSAL_QUEUE_DISCONNECTED.to_string(),
+                        )),
+                    })?
             {
                 // Generate a unique ID for the proposal
                 let id = Uuid::new_v4();
@@ -228,6 +327,10 @@ impl<N: RaftNetwork> ReplicationNode<N> {
             if last_tick.elapsed() >= tick_interval {
                 self.inner.tick();
                 self.process_ready_state()?;
+                self.request_snapshot_if_truncated();
+                self.check_resync_timeout()?;
+                self.maybe_compact()?;
+                RELAYER_METRICS.raft_leader.set(self.is_leader() as i64);
 
                 last_tick = Instant::now();
             }
@@ -250,6 +353,8 @@ impl<N: RaftNetwork> ReplicationNode<N> {
             StateTransition::AddRaftLearner { peer_id } => self.add_learner(id, *peer_id),
             StateTransition::AddRaftPeer { peer_id } => self.add_peer(id, *peer_id),
             StateTransition::RemoveRaftPeer { peer_id } => self.remove_peer(id, *peer_id),
+            StateTransition::ResyncFromLeader => self.start_resync(id),
+            StateTransition::TransferLeadership => self.transfer_leadership(id),
             _ => {
                 let ctx = id.to_bytes_le().to_vec();
                 let payload = serde_json::to_vec(&proposal)
@@ -451,6 +556,11 @@ impl<N: RaftNetwork> ReplicationNode<N> {
             self.update_hard_state(hard_state)?;
         }
 
+        // Publish any change in leadership
+        if let Some(soft_state) = ready.ss() {
+            self.update_soft_state(soft_state);
+        }
+
         // Send persisted messages to peers
         self.send_outbound_messages(ready.take_persisted_messages())?;
 
@@ -477,8 +587,24 @@ impl<N: RaftNetwork> ReplicationNode<N> {
     }
 
     /// Apply a raft snapshot from the ready state
+    ///
+    /// The snapshot carries the leader's wallet index and order book, which
+    /// replace the local application state when installed
     fn apply_snapshot(&mut self, snapshot: &Snapshot) -> Result<(), ReplicationError> {
-        self.inner.mut_store().apply_snapshot(snapshot)
+        self.inner.mut_store().apply_snapshot(snapshot)?;
+
+        // A snapshot completes any in-progress resync
+        if let Some((id, start)) = self.resync.take() {
+            let index = snapshot.get_metadata().index;
+            info!(
+                "node-{} resynced from leader snapshot at index {index} in {:?}",
+                self.id(),
+                start.elapsed()
+            );
+            self.notify_proposal_sender(&id, Ok(()))?;
+        }
+
+        Ok(())
     }
 
     /// Commit entries from the ready state and apply them to the state machine
@@ -551,6 +677,186 @@ impl<N: RaftNetwork> ReplicationNode<N> {
         self.inner.mut_store().apply_hard_state(hard_state)
     }
 
+    /// Update the last known leader from the ready state's soft state,
+    /// publishing a message to the system bus if leadership has changed
+    ///
+    /// A change is published when the leader, its term, or the local node's
+    /// role as leader changes. This covers the local node gaining or losing
+    /// leadership, the cluster losing its leader, and a leader's re-election
+    fn update_soft_state(&mut self, soft_state: &SoftState) {
+        let is_leader = soft_state.raft_state == StateRole::Leader;
+        self.leader_guard.set_leader(is_leader);
+
+        let leader_id = soft_state.leader_id;
+        let term = self.inner.raft.term;
+        let leadership = (leader_id, term, is_leader);
+        if leadership == self.last_leadership {
+            return;
+        }
+        self.last_leadership = leadership;
+
+        if leader_id == INVALID_ID {
+            info!("node-{} lost track of the leader in term {term}", self.id());
+        } else {
+            self.leader_id = leader_id;
+            info!("node-{} observed leader {leader_id} in term {term}", self.id());
+        }
+
+        self.system_bus.publish(
+            RAFT_LEADERSHIP_TOPIC.to_string(),
+            SystemBusMessage::RaftLeadershipChanged { is_leader, leader_id, term },
+        );
+    }
+
+    /// Request a snapshot from the leader if the log was truncated at a
+    /// corrupt entry on startup
+    ///
+    /// The request is held until the local node follows a known leader, the
+    /// leader holds the entries the local log is missing
+    fn request_snapshot_if_truncated(&mut self) {
+        let Some(index) = self.pending_snapshot_request else {
+            return;
+        };
+        if self.inner.raft.state != StateRole::Follower || self.inner.raft.leader_id == INVALID_ID {
+            return;
+        }
+
+        self.pending_snapshot_request = None;
+        warn!("node-{} truncated corrupt log at index {index}, requesting snapshot", self.id());
+        if let Err(e) = self.inner.request_snapshot() {
+            warn!("node-{} could not request snapshot: {e}", self.id());
+        }
+    }
+
+    // --------------
+    // | Leadership |
+    // --------------
+
+    /// Transfer leadership to the most up-to-date voter other than the local
+    /// node
+    ///
+    /// The proposal is notified once the transfer begins, the new leader is
+    /// elected asynchronously
+    fn transfer_leadership(&mut self, id: Uuid) -> Result<(), ReplicationError> {
+        if !self.is_leader() {
+            return Err(ReplicationError::LeadershipTransfer(ERR_TRANSFER_NOT_LEADER.to_string()));
+        }
+
+        let my_id = self.id();
+        let transferee = self
+            .get_config_state()?
+            .voters
+            .into_iter()
+            .filter(|voter| *voter != my_id)
+            .max_by_key(|voter| {
+                self.inner.raft.prs().get(*voter).map(|pr| pr.matched).unwrap_or_default()
+            })
+            .ok_or_else(|| ReplicationError::LeadershipTransfer(ERR_NO_TRANSFEREE.to_string()))?;
+
+        info!("node-{my_id} transferring leadership to node-{transferee}");
+        self.inner.transfer_leader(transferee);
+        self.notify_proposal_sender(&id, Ok(()))
+    }
+
+    // ----------
+    // | Resync |
+    // ----------
+
+    /// Begin a resync of the local node's state from the leader
+    ///
+    /// The proposal is notified once the leader's snapshot is installed
+    fn start_resync(&mut self, id: Uuid) -> Result<(), ReplicationError> {
+        if self.is_leader() {
+            return Err(ReplicationError::Resync(ERR_RESYNC_ON_LEADER.to_string()));
+        }
+        if self.resync.is_some() {
+            return Err(ReplicationError::Resync(ERR_RESYNC_IN_PROGRESS.to_string()));
+        }
+
+        self.inner.request_snapshot().map_err(ReplicationError::Raft)?;
+        info!("node-{} requested a snapshot from leader {} to resync", self.id(), self.leader_id);
+        self.resync = Some((id, Instant::now()));
+
+        Ok(())
+    }
+
+    /// Abandon an in-progress resync if the leader has not sent a snapshot
+    /// within the timeout
+    fn check_resync_timeout(&mut self) -> Result<(), ReplicationError> {
+        let timeout = Duration::from_millis(RESYNC_TIMEOUT_MS);
+        let Some((id, start)) = self.resync else {
+            return Ok(());
+        };
+        if start.elapsed() < timeout {
+            return Ok(());
+        }
+
+        self.resync = None;
+
+        warn!("node-{} resync timed out, resuming proposals", self.id());
+        self.notify_proposal_sender(
+            &id,
+            Err(ReplicationError::Resync(ERR_RESYNC_TIMEOUT.to_string())),
+        )
+    }
+
+    // --------------
+    // | Compaction |
+    // --------------
+
+    /// Snapshot the state and compact the log once the configured number of
+    /// entries have been applied, or the configured interval has elapsed,
+    /// since the last compaction
+    ///
+    /// Compaction runs on the raft thread, so it never overlaps a snapshot
+    /// install or another compaction. It is skipped during a resync, as the
+    /// leader's snapshot is about to replace the local state
+    fn maybe_compact(&mut self) -> Result<(), ReplicationError> {
+        if self.resync.is_some() {
+            return Ok(());
+        }
+
+        let (last_index, last_time) = self.last_compaction;
+        let index = self.compaction_index();
+        if index <= last_index {
+            return Ok(());
+        }
+
+        let entries_due = index - last_index >= self.snapshot_interval_entries;
+        let interval_due = last_time.elapsed() >= self.snapshot_interval;
+        if !entries_due && !interval_due {
+            return Ok(());
+        }
+
+        if self.inner.mut_store().compact(index)? {
+            info!("node-{} compacted raft log through index {index}", self.id());
+        }
+
+        self.last_compaction = (index, Instant::now());
+        Ok(())
+    }
+
+    /// The index through which the local log may be compacted
+    ///
+    /// Only applied entries are compacted. The leader also retains the entries
+    /// its peers have yet to replicate, so that they catch up from the log
+    /// rather than from a full snapshot
+    fn compaction_index(&self) -> u64 {
+        let applied = self.inner.raft.raft_log.applied;
+        if !self.is_leader() {
+            return applied;
+        }
+
+        let my_id = self.id();
+        self.inner
+            .raft
+            .prs()
+            .iter()
+            .filter(|(id, _)| **id != my_id)
+            .map(|(_, progress)| progress.matched)
+            .fold(applied, u64::min)
+    }
+
     // -----------
     // | Helpers |
     // -----------
@@ -565,6 +871,12 @@ impl<N: RaftNetwork> ReplicationNode<N> {
         self.inner.raft.id
     }
 
+    /// Get a handle on the leadership status of the local node, which remains
+    /// valid after the node is moved into its own thread
+    pub fn leader_guard(&self) -> LeaderGuard {
+        self.leader_guard.clone()
+    }
+
     /// Get the config state stored in the log
     fn get_config_state(&self) -> Result<ConfState, ReplicationError> {
         let tx = self.db.new_read_tx()?;
@@ -611,6 +923,16 @@ impl<N: RaftNetwork> ReplicationNode<N> {
 // | Helpers |
 // -----------
 
+/// Build the raft config for a node, taking its timing from the relayer config
+pub fn build_raft_config(id: u64, relayer_config: &RelayerConfig) -> RaftConfig {
+    RaftConfig {
+        id,
+        election_tick: relayer_config.raft_election_tick,
+        heartbeat_tick: relayer_config.raft_heartbeat_tick,
+        ..Default::default()
+    }
+}
+
 /// Parse a proposal ID from an entry
 fn parse_proposal_id(entry: &Entry) -> Result<Uuid, ReplicationError> {
     let id_bytes = entry
@@ -634,11 +956,13 @@ pub(crate) mod test_helpers {
     };
 
     use crossbeam::channel::{unbounded, Receiver as CrossbeamReceiver, Sender};
+    use external_api::bus_message::SystemBusMessage;
     use job_types::{
         handshake_manager::new_handshake_manager_queue, task_driver::new_task_driver_queue,
     };
     use raft::prelude::Config as RaftConfig;
     use system_bus::SystemBus;
+    use tokio::sync::oneshot::{self, Receiver as OneshotReceiver};
 
     use crate::{
         replication::{
@@ -651,7 +975,7 @@ pub(crate) mod test_helpers {
         Proposal, StateTransition,
     };
 
-    use super::{ReplicationNode, ReplicationNodeConfig};
+    use super::{LeaderGuard, ReplicationNode, ReplicationNodeConfig};
 
     /// A mock cluster, holds the handles of the threads running each node, as
     /// well as references to their databases and proposal queues
@@ -662,6 +986,8 @@ pub(crate) mod test_helpers {
         dbs: Vec<Arc<DB>>,
         /// The proposal senders of the nodes
         proposal_senders: Vec<Sender<Proposal>>,
+        /// The leader guards of the nodes
+        leader_guards: Vec<LeaderGuard>,
         /// The network controller
         controller: MockNetworkController,
     }
@@ -669,8 +995,38 @@ pub(crate) mod test_helpers {
     impl MockReplicationCluster {
         /// Create a mock cluster of nodes
         pub fn new(n_nodes: usize) -> Self {
+            let buses = (0..n_nodes).map(|_| SystemBus::new()).collect();
+            Self::new_with_buses(buses)
+        }
+
+        /// Create a mock cluster of nodes, one for each of the given system
+        /// buses
+        ///
+        /// Allows a test to subscribe to a node's bus before the cluster is
+        /// started
+        pub fn new_with_buses(buses: Vec<SystemBus<SystemBusMessage>>) -> Self {
+            let dbs = buses.iter().map(|_| Arc::new(mock_db())).collect();
+            Self::new_with_buses_and_dbs(buses, dbs)
+        }
+
+        /// Create a mock cluster of nodes, one for each of the given
+        /// databases
+        ///
+        /// Allows a test to co-host clusters on shared databases by passing
+        /// handles in distinct namespaces
+        pub fn new_with_dbs(dbs: Vec<Arc<DB>>) -> Self {
+            let buses = dbs.iter().map(|_| SystemBus::new()).collect();
+            Self::new_with_buses_and_dbs(buses, dbs)
+        }
+
+        /// Create a mock cluster of nodes, one for each pair of system bus
+        /// and database
+        fn new_with_buses_and_dbs(
+            mut buses: Vec<SystemBus<SystemBusMessage>>,
+            dbs: Vec<Arc<DB>>,
+        ) -> Self {
+            let n_nodes = buses.len();
             let (controller, mut nets) = MockNetwork::new_n_way_mesh(n_nodes);
-            let dbs = (0..n_nodes).map(|_| Arc::new(mock_db())).collect::<Vec<_>>();
 
             let mut senders = Vec::new();
             let mut receivers = Vec::new();
@@ -686,19 +1042,22 @@ pub(crate) mod test_helpers {
                 dbs[0].clone(),
                 receivers.remove(0),
                 nets.remove(0),
+                buses.remove(0),
             );
 
             let followers = (1..n_nodes)
                 .zip(receivers)
-                .map(|(i, recv)| {
-                    mock_follower((i + 1) as u64, dbs[i].clone(), recv, nets.remove(0))
+                .zip(buses)
+                .map(|((i, recv), bus)| {
+                    mock_follower((i + 1) as u64, dbs[i].clone(), recv, nets.remove(0), bus)
                 })
                 .collect::<Vec<_>>();
 
             // Spawn each node in a separate thread
-            let handles = vec![leader]
+            let nodes = vec![leader].into_iter().chain(followers).collect::<Vec<_>>();
+            let leader_guards = nodes.iter().map(|node| node.leader_guard()).collect();
+            let handles = nodes
                 .into_iter()
-                .chain(followers)
                 .enumerate()
                 .map(|(i, node)| spawn_node(i as u64 + 1, node))
                 .collect::<Vec<_>>();
@@ -714,7 +1073,7 @@ pub(crate) mod test_helpers {
                 thread::sleep(Duration::from_millis(50))
             }
 
-            Self { handles, dbs, proposal_senders: senders, controller }
+            Self { handles, dbs, proposal_senders: senders, leader_guards, controller }
         }
 
         /// Get a reference to the `n`th node's DB
@@ -724,6 +1083,13 @@ pub(crate) mod test_helpers {
             self.dbs[node_id - 1].clone()
         }
 
+        /// Get the leader guard of the `n`th node
+        ///
+        /// We 1-index here to match the node IDs
+        pub fn leader_guard(&self, node_id: usize) -> LeaderGuard {
+            self.leader_guards[node_id - 1].clone()
+        }
+
         /// Send a proposal to the `n`th node
         ///
         /// We 1-index here to match the node IDs
@@ -731,6 +1097,20 @@ pub(crate) mod test_helpers {
             self.proposal_senders[node_id - 1].send(proposal.into()).unwrap();
         }
 
+        /// Send a proposal to the `n`th node, returning a channel on which the
+        /// result of the proposal is sent
+        ///
+        /// We 1-index here to match the node IDs
+        pub fn send_proposal_with_response(
+            &self,
+            node_id: usize,
+            transition: StateTransition,
+        ) -> OneshotReceiver<Result<(), ReplicationError>> {
+            let (response, recv) = oneshot::channel();
+            self.proposal_senders[node_id - 1].send(Proposal { transition, response }).unwrap();
+            recv
+        }
+
         /// Disconnect the given path between two nodes
         pub fn disconnect(&self, from: RaftPeerId, to: RaftPeerId) {
             self.controller.disconnect(from, to);
@@ -757,8 +1137,9 @@ pub(crate) mod test_helpers {
         db: Arc<DB>,
         proposal_queue: CrossbeamReceiver<Proposal>,
         network: MockNetwork,
+        system_bus: SystemBus<SystemBusMessage>,
     ) -> ReplicationNode<MockNetwork> {
-        mock_replication_node(id, db, proposal_queue, network)
+        mock_replication_node(id, db, proposal_queue, network, system_bus)
     }
 
     /// Create a follower node
@@ -767,11 +1148,13 @@ pub(crate) mod test_helpers {
         db: Arc<DB>,
         proposal_queue: CrossbeamReceiver<Proposal>,
         network: MockNetwork,
+        system_bus: SystemBus<SystemBusMessage>,
     ) -> ReplicationNode<MockNetwork> {
         mock_replication_node_with_config(
             db,
             proposal_queue,
             network,
+            system_bus,
             &RaftConfig {
                 id,
                 election_tick: 20,
@@ -789,11 +1172,13 @@ pub(crate) mod test_helpers {
         db: Arc<DB>,
         proposal_queue: CrossbeamReceiver<Proposal>,
         network: MockNetwork,
+        system_bus: SystemBus<SystemBusMessage>,
     ) -> ReplicationNode<MockNetwork> {
         mock_replication_node_with_config(
             db,
             proposal_queue,
             network,
+            system_bus,
             // Build a raft node that has high tick frequency and low leader timeout intervals to
             // speed up tests. In unit tests there is no practical latency issue, so we can set the
             // timeouts to the minimum values they may validly take
@@ -813,6 +1198,7 @@ pub(crate) mod test_helpers {
         db: Arc<DB>,
         proposal_queue: CrossbeamReceiver<Proposal>,
         network: MockNetwork,
+        system_bus: SystemBus<SystemBusMessage>,
         raft_config: &RaftConfig,
     ) -> ReplicationNode<MockNetwork> {
         let (task_queue, task_recv) = new_task_driver_queue();
@@ -829,7 +1215,7 @@ pub(crate) mod test_helpers {
                 task_queue,
                 handshake_manager_queue,
                 db,
-                system_bus: SystemBus::new(),
+                system_bus,
             },
             raft_config,
         )
@@ -863,20 +1249,25 @@ mod test {
         wallet::{Wallet, WalletIdentifier},
         wallet_mocks::mock_empty_wallet,
     };
+    use config::RelayerConfig;
+    use constants::RAFT_LEADERSHIP_TOPIC;
     use crossbeam::channel::unbounded;
+    use external_api::bus_message::SystemBusMessage;
     use job_types::{
         handshake_manager::new_handshake_manager_queue, task_driver::new_task_driver_queue,
     };
     use rand::{thread_rng, Rng};
+    use system_bus::{SystemBus, TopicReader};
+    use tokio::sync::oneshot;
 
     use crate::{
         replication::{
             network::traits::test_helpers::MockNetwork,
-            raft_node::test_helpers::MockReplicationCluster,
+            raft_node::test_helpers::{mock_leader, spawn_node, MockReplicationCluster},
         },
         storage::db::DB,
         test_helpers::mock_db,
-        StateTransition, WALLETS_TABLE,
+        Proposal, StateTransition, WALLETS_TABLE,
     };
 
     use super::{ReplicationNode, ReplicationNodeConfig};
@@ -886,6 +1277,23 @@ mod test {
         db.read(WALLETS_TABLE, &wallet_id).unwrap().unwrap()
     }
 
+    /// Read leadership changes from the given reader until one announcing the
+    /// given leader is found, returning whether the local node is the leader
+    async fn await_leader(reader: &mut TopicReader<SystemBusMessage>, leader: u64) -> bool {
+        loop {
+            let msg = tokio::time::timeout(Duration::from_secs(1), reader.next_message())
+                .await
+                .expect("timed out awaiting leadership change");
+
+            if let SystemBusMessage::RaftLeadershipChanged { is_leader, leader_id, term } = msg {
+                assert!(term > 0);
+                if leader_id == leader {
+                    return is_leader;
+                }
+            }
+        }
+    }
+
     /// Tests that the constructor works properly, largely this means testing
     /// that the `LogStore` initialization is compatible with the `raft`
     /// setup
@@ -910,6 +1318,39 @@ mod test {
         let _node = ReplicationNode::new(node_config).unwrap();
     }
 
+    /// Tests that the raft timing is taken from the relayer config
+    #[test]
+    fn test_configured_timing() {
+        const ELECTION_TICK: usize = 30;
+        const HEARTBEAT_TICK: usize = 5;
+
+        let db = Arc::new(mock_db());
+        let (_, net, _) = MockNetwork::new_duplex_conn();
+
+        let (_, proposal_receiver) = unbounded();
+        let (task_queue, _recv) = new_task_driver_queue();
+        let (handshake_manager_queue, _recv) = new_handshake_manager_queue();
+        let relayer_config = RelayerConfig {
+            raft_election_tick: ELECTION_TICK,
+            raft_heartbeat_tick: HEARTBEAT_TICK,
+            ..Default::default()
+        };
+        let node_config = ReplicationNodeConfig {
+            tick_period_ms: 10,
+            relayer_config,
+            proposal_queue: proposal_receiver,
+            network: net,
+            task_queue,
+            handshake_manager_queue,
+            db: db.clone(),
+            system_bus: Default::default(),
+        };
+        let node = ReplicationNode::new(node_config).unwrap();
+
+        assert_eq!(node.inner.raft.election_timeout(), ELECTION_TICK);
+        assert_eq!(node.inner.raft.heartbeat_timeout(), HEARTBEAT_TICK);
+    }
+
     /// Tests handling a proposal to add a wallet
     #[test]
     fn test_proposal_add_wallet() {
@@ -959,6 +1400,60 @@ mod test {
         cluster.assert_no_crashes();
     }
 
+    /// Tests that both nodes of a cluster publish the election of a leader
+    #[tokio::test]
+    async fn test_leadership_change_message() {
+        let buses = vec![SystemBus::new(), SystemBus::new()];
+        let mut readers = buses
+            .iter()
+            .map(|bus| bus.subscribe(RAFT_LEADERSHIP_TOPIC.to_string()))
+            .collect::<Vec<_>>();
+
+        let cluster = MockReplicationCluster::new_with_buses(buses);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // The first node is elected leader, the second follows it
+        assert!(await_leader(&mut readers[0], 1 /* leader */).await);
+        assert!(!await_leader(&mut readers[1], 1 /* leader */).await);
+        cluster.assert_no_crashes();
+    }
+
+    /// Tests that a leader losing its leadership publishes the change, as does
+    /// the node that takes over
+    #[tokio::test]
+    async fn test_leadership_loss_message() {
+        let buses = vec![SystemBus::new(), SystemBus::new()];
+        let mut readers = buses
+            .iter()
+            .map(|bus| bus.subscribe(RAFT_LEADERSHIP_TOPIC.to_string()))
+            .collect::<Vec<_>>();
+
+        let cluster = MockReplicationCluster::new_with_buses(buses);
+        assert!(await_leader(&mut readers[0], 1 /* leader */).await);
+        assert!(!await_leader(&mut readers[1], 1 /* leader */).await);
+
+        // Hand leadership to the second node
+        let recv = cluster.send_proposal_with_response(1, StateTransition::TransferLeadership);
+        recv.await.unwrap().unwrap();
+
+        assert!(!await_leader(&mut readers[0], 2 /* leader */).await);
+        assert!(await_leader(&mut readers[1], 2 /* leader */).await);
+        cluster.assert_no_crashes();
+    }
+
+    /// Tests that only the leader's guard allows a sweep to run
+    #[test]
+    fn test_leader_guard() {
+        let cluster = MockReplicationCluster::new(2 /* n_nodes */);
+        thread::sleep(Duration::from_millis(100));
+
+        let leader = cluster.leader_guard(1 /* node_id */);
+        let follower = cluster.leader_guard(2 /* node_id */);
+        assert!(leader.should_run("test"));
+        assert!(!follower.should_run("test"));
+        cluster.assert_no_crashes();
+    }
+
     /// Tests proposing to followers in a larger cluster
     #[test]
     fn test_many_node_consensus() {
@@ -1043,4 +1538,125 @@ mod test {
 
         assert_eq!(wallet, expected_wallet);
     }
+
+    /// Tests resyncing a follower whose state has diverged from the leader's
+    #[test]
+    fn test_resync_from_leader() {
+        let cluster = MockReplicationCluster::new(2 /* n_nodes */);
+
+        // Replicate a wallet to both nodes
+        let wallet = mock_empty_wallet();
+        cluster.send_proposal(1, StateTransition::AddWallet { wallet: wallet.clone() });
+        thread::sleep(Duration::from_millis(100));
+
+        // Diverge the follower by writing a wallet outside of consensus and
+        // removing the replicated wallet
+        let follower_db = cluster.db(2);
+        let diverged_wallet = mock_empty_wallet();
+        follower_db.write(WALLETS_TABLE, &diverged_wallet.wallet_id, &diverged_wallet).unwrap();
+        follower_db.delete(WALLETS_TABLE, &wallet.wallet_id).unwrap();
+
+        // The leader cannot resync from itself
+        let res = cluster.send_proposal_with_response(1, StateTransition::ResyncFromLeader);
+        assert!(res.blocking_recv().unwrap().is_err());
+
+        // Resync the follower
+        let res = cluster.send_proposal_with_response(2, StateTransition::ResyncFromLeader);
+        res.blocking_recv().unwrap().unwrap();
+
+        // The follower's state should match the leader's
+        let found_wallet = find_wallet_in_db(wallet.wallet_id, &follower_db);
+        let diverged: Option<Wallet> =
+            follower_db.read(WALLETS_TABLE, &diverged_wallet.wallet_id).unwrap();
+
+        assert_eq!(found_wallet, wallet);
+        assert!(diverged.is_none());
+        cluster.assert_no_crashes();
+    }
+
+    /// Tests two clusters co-hosted on the same databases in separate
+    /// namespaces, neither of which observes the other's state
+    #[test]
+    fn test_namespaced_clusters() {
+        const N: usize = 2;
+        let dbs = (0..N).map(|_| mock_db()).collect::<Vec<_>>();
+        let namespaced_dbs = |namespace: &str| {
+            dbs.iter()
+                .map(|db| {
+                    let db = db.namespaced(namespace);
+                    let tx = db.new_write_tx().unwrap();
+                    tx.setup_tables().unwrap();
+                    tx.commit().unwrap();
+
+                    Arc::new(db)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let cluster1 = MockReplicationCluster::new_with_dbs(namespaced_dbs("cluster1"));
+        let cluster2 = MockReplicationCluster::new_with_dbs(namespaced_dbs("cluster2"));
+
+        // Propose a different wallet to each cluster
+        let wallet1 = mock_empty_wallet();
+        let wallet2 = mock_empty_wallet();
+        cluster1.send_proposal(1, StateTransition::AddWallet { wallet: wallet1.clone() });
+        cluster2.send_proposal(1, StateTransition::AddWallet { wallet: wallet2.clone() });
+        thread::sleep(Duration::from_millis(100));
+
+        // Each cluster replicates only its own wallet
+        for node_id in 1..=N {
+            let db1 = cluster1.db(node_id);
+            let db2 = cluster2.db(node_id);
+            assert_eq!(find_wallet_in_db(wallet1.wallet_id, &db1), wallet1);
+            assert_eq!(find_wallet_in_db(wallet2.wallet_id, &db2), wallet2);
+
+            let leaked1: Option<Wallet> = db2.read(WALLETS_TABLE, &wallet1.wallet_id).unwrap();
+            let leaked2: Option<Wallet> = db1.read(WALLETS_TABLE, &wallet2.wallet_id).unwrap();
+            assert!(leaked1.is_none());
+            assert!(leaked2.is_none());
+        }
+
+        cluster1.assert_no_crashes();
+        cluster2.assert_no_crashes();
+    }
+
+    /// Tests that the log is snapshotted and compacted once the configured
+    /// number of entries have been applied, with the applied state retained
+    #[test]
+    fn test_automatic_compaction() {
+        const SNAPSHOT_INTERVAL_ENTRIES: u64 = 5;
+        const N_WALLETS: u64 = 2 * SNAPSHOT_INTERVAL_ENTRIES;
+
+        let db = Arc::new(mock_db());
+        let (_controller, mut nets) = MockNetwork::new_n_way_mesh(1 /* n_nodes */);
+        let (proposal_sender, proposal_receiver) = unbounded();
+        let mut node =
+            mock_leader(1, db.clone(), proposal_receiver, nets.remove(0), SystemBus::new());
+        node.snapshot_interval_entries = SNAPSHOT_INTERVAL_ENTRIES;
+        let handle = spawn_node(1, node);
+        thread::sleep(Duration::from_millis(50));
+
+        // Apply enough entries to pass the threshold
+        let wallets = (0..N_WALLETS).map(|_| mock_empty_wallet()).collect::<Vec<_>>();
+        for wallet in wallets.iter() {
+            let (response, recv) = oneshot::channel();
+            let transition = StateTransition::AddWallet { wallet: wallet.clone() };
+            proposal_sender.send(Proposal { transition, response }).unwrap();
+            recv.blocking_recv().unwrap().unwrap();
+        }
+        thread::sleep(Duration::from_millis(100));
+
+        // A snapshot is stored past the threshold, and the log compacted through it
+        let tx = db.new_read_tx().unwrap();
+        let snapshot = tx.read_snapshot_metadata().unwrap();
+        assert!(snapshot.index >= SNAPSHOT_INTERVAL_ENTRIES);
+        assert!(tx.read_log_entry(snapshot.index).is_err());
+        tx.commit().unwrap();
+
+        // The applied state is retained
+        for wallet in wallets.iter() {
+            assert_eq!(find_wallet_in_db(wallet.wallet_id, &db), *wallet);
+        }
+        assert!(!handle.is_finished());
+    }
 }
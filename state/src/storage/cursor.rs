@@ -217,8 +217,9 @@ impl<'txn, T: TransactionKind, K: Key, V: Value> IntoIterator for DbCursor<'txn,
     type IntoIter = DbCursorIter<'txn, T, K, V>;
 
     fn into_iter(mut self) -> Self::IntoIter {
-        // Setup the initial value for the iterator
-        let initial = self.get_current().unwrap();
+        // Setup the initial value for the iterator, an error reading the first
+        // value is yielded as the first item
+        let initial = self.get_current().transpose();
         DbCursorIter { initial, cursor: self }
     }
 }
@@ -226,7 +227,7 @@ impl<'txn, T: TransactionKind, K: Key, V: Value> IntoIterator for DbCursor<'txn,
 /// The iterator type for the cursor
 pub struct DbCursorIter<'txn, T: TransactionKind, K: Key, V: Value> {
     /// The initial value of the iterator
-    initial: Option<(K, V)>,
+    initial: Option<Result<(K, V), StorageError>>,
     /// The underlying cursor
     cursor: DbCursor<'txn, T, K, V>,
 }
@@ -247,8 +248,8 @@ impl<'txn, T: TransactionKind, K: Key, V: Value> Iterator for DbCursorIter<'txn,
     type Item = Result<(K, V), StorageError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some((k, v)) = self.initial.take() {
-            return Some(Ok((k, v)));
+        if let Some(res) = self.initial.take() {
+            return Some(res);
         }
 
         // Increment the cursor
@@ -3,7 +3,7 @@
 //! We serialize types using the `flexbuffers` format (a schema-less version of
 //! `flatbuffers`): https://flatbuffers.dev/flexbuffers.html
 
-use std::{ops::Bound, path::Path};
+use std::{ops::Bound, path::Path, sync::Arc};
 
 use libmdbx::{Database, Geometry, WriteMap, RO, RW};
 use serde::{Deserialize, Serialize};
@@ -15,7 +15,11 @@ use super::{
 };
 
 /// The number of tables to open in the database
-const NUM_TABLES: usize = 12;
+const NUM_TABLES: usize = 13;
+/// The number of table namespaces that may share a database
+///
+/// Each namespace holds its own copy of the relayer's tables
+const MAX_NAMESPACES: usize = 4;
 /// The total maximum size of the DB in bytes
 const MAX_DB_SIZE_BYTES: usize = 1 << 36; // 64 GB
 
@@ -43,6 +47,8 @@ pub(crate) fn deserialize_value<V: for<'de> Deserialize<'de>>(
 pub struct DbConfig {
     /// The path to open the database at
     pub path: String,
+    /// The namespace prefixed to the name of every table, if any
+    pub namespace: Option<String>,
 }
 
 /// The persistent storage layer for the relayer's state machine
@@ -50,7 +56,12 @@ pub struct DbConfig {
 /// Contains a reference to an `mdbx` instance
 pub struct DB {
     /// The underlying `mdbx` instance
-    db: Database<WriteMap>,
+    db: Arc<Database<WriteMap>>,
+    /// The namespace prefixed to the name of every table, if any
+    ///
+    /// Handles in different namespaces may share the underlying instance
+    /// without observing each other's tables
+    namespace: Option<String>,
 }
 
 impl DB {
@@ -63,12 +74,17 @@ impl DB {
         };
 
         let db = Database::new()
-            .set_max_tables(NUM_TABLES)
+            .set_max_tables(NUM_TABLES * MAX_NAMESPACES)
             .set_geometry(db_geom)
             .open(db_path)
             .map_err(StorageError::OpenDb)?;
 
-        Ok(Self { db })
+        Ok(Self { db: Arc::new(db), namespace: config.namespace.clone() })
+    }
+
+    /// Get a handle on the same `mdbx` instance in the given namespace
+    pub fn namespaced(&self, namespace: &str) -> Self {
+        Self { db: self.db.clone(), namespace: Some(namespace.to_string()) }
     }
 
     /// Create a new table in the database
@@ -125,7 +141,7 @@ impl DB {
     /// Create a new raw read-only transaction
     pub fn new_raw_read_tx(&self) -> Result<DbTxn<RO>, StorageError> {
         let txn = self.db.begin_ro_txn().map_err(StorageError::BeginTx)?;
-        Ok(DbTxn::new(txn))
+        Ok(DbTxn::new(txn, self.namespace.as_deref()))
     }
 
     /// Create a new read-write transaction
@@ -136,7 +152,8 @@ impl DB {
 
     /// Create a new read-write transaction
     pub fn new_raw_write_tx(&self) -> Result<DbTxn<RW>, StorageError> {
-        self.db.begin_rw_txn().map_err(StorageError::BeginTx).map(DbTxn::new)
+        let txn = self.db.begin_rw_txn().map_err(StorageError::BeginTx)?;
+        Ok(DbTxn::new(txn, self.namespace.as_deref()))
     }
 
     /// Flush the database to disk
@@ -330,7 +347,7 @@ mod test {
         // Create a mock DB and table
         let tempdir = tempdir().unwrap();
         let path = tempdir.path().to_str().unwrap().to_string();
-        let db = Arc::new(DB::new(&DbConfig { path: path.clone() }).unwrap());
+        let db = Arc::new(DB::new(&DbConfig { path: path.clone(), namespace: None }).unwrap());
 
         db.create_table(TABLE_NAME).unwrap();
 
@@ -374,7 +391,7 @@ mod test {
         let path = tempdir.path().to_str().unwrap().to_string();
 
         // Create a mock DB and table
-        let db = DB::new(&DbConfig { path: path.clone() }).unwrap();
+        let db = DB::new(&DbConfig { path: path.clone(), namespace: None }).unwrap();
 
         // Set a key
         let key = "test_key".to_string();
@@ -390,7 +407,7 @@ mod test {
         drop(db);
 
         // Re-open the database at the same path and read the value
-        let db = DB::new(&DbConfig { path }).unwrap();
+        let db = DB::new(&DbConfig { path, namespace: None }).unwrap();
 
         let tx = db.new_raw_read_tx().unwrap();
         let val: Option<TestValue> = tx.read(TABLE_NAME, &key).unwrap();
@@ -10,16 +10,18 @@ pub mod node_metadata;
 pub mod order_book;
 pub mod peer_index;
 pub mod raft_log;
+pub mod snapshot;
 pub mod task_queue;
 pub mod wallet_index;
 
-use std::collections::VecDeque;
+use std::{borrow::Cow, collections::VecDeque};
 
 use libmdbx::{Table, TableFlags, Transaction, TransactionKind, WriteFlags, WriteMap, RW};
 
 use crate::{
-    CLUSTER_MEMBERSHIP_TABLE, NODE_METADATA_TABLE, ORDERS_TABLE, ORDER_TO_WALLET_TABLE,
-    PEER_INFO_TABLE, PRIORITIES_TABLE, TASK_QUEUE_TABLE, TASK_TO_KEY_TABLE, WALLETS_TABLE,
+    BLINDER_TO_WALLET_TABLE, CLUSTER_MEMBERSHIP_TABLE, NODE_METADATA_TABLE, ORDERS_TABLE,
+    ORDER_TO_WALLET_TABLE, PEER_INFO_TABLE, PRIORITIES_TABLE, TASK_QUEUE_TABLE, TASK_TO_KEY_TABLE,
+    WALLETS_TABLE,
 };
 
 use self::raft_log::RAFT_METADATA_TABLE;
@@ -96,6 +98,7 @@ impl<'db> StateTxn<'db, RW> {
             ORDERS_TABLE,
             ORDER_TO_WALLET_TABLE,
             WALLETS_TABLE,
+            BLINDER_TO_WALLET_TABLE,
             TASK_QUEUE_TABLE,
             TASK_TO_KEY_TABLE,
             NODE_METADATA_TABLE,
@@ -212,12 +215,14 @@ impl<'db> StateTxn<'db, RW> {
 pub struct DbTxn<'db, T: TransactionKind> {
     /// The underlying `mdbx` transaction
     txn: Transaction<'db, T, WriteMap>,
+    /// The namespace prefixed to the name of every table, if any
+    namespace: Option<&'db str>,
 }
 
 impl<'db, T: TransactionKind> DbTxn<'db, T> {
     /// Constructor
-    pub fn new(txn: Transaction<'db, T, WriteMap>) -> Self {
-        Self { txn }
+    pub fn new(txn: Transaction<'db, T, WriteMap>, namespace: Option<&'db str>) -> Self {
+        Self { txn, namespace }
     }
 
     /// Get a key from the database
@@ -242,6 +247,35 @@ impl<'db, T: TransactionKind> DbTxn<'db, T> {
         Ok(DbCursor::new(cursor))
     }
 
+    /// Read all key-value pairs in a table as raw bytes, in key order
+    ///
+    /// Neither keys nor values are deserialized, so that a table may be
+    /// copied between databases without knowledge of its types
+    pub fn read_table_bytes(
+        &self,
+        table_name: &str,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, StorageError> {
+        let table = self.open_table(table_name)?;
+        let mut cursor = self.txn.cursor(&table).map_err(StorageError::TxOp)?;
+
+        let mut pairs = Vec::new();
+        let mut next = cursor.first::<CowBuffer, CowBuffer>().map_err(StorageError::TxOp)?;
+        while let Some((key, value)) = next {
+            pairs.push((key.into_owned(), value.into_owned()));
+            next = cursor.next::<CowBuffer, CowBuffer>().map_err(StorageError::TxOp)?;
+        }
+
+        Ok(pairs)
+    }
+
+    /// Get the number of entries in a table, read from the table's stats
+    /// rather than by walking its keys
+    pub fn table_len(&self, table_name: &str) -> Result<usize, StorageError> {
+        let table = self.open_table(table_name)?;
+        let stat = self.txn.table_stat(&table).map_err(StorageError::TxOp)?;
+        Ok(stat.entries())
+    }
+
     /// Commit the transaction
     pub fn commit(self) -> Result<(), StorageError> {
         self.txn.commit().map_err(StorageError::Commit).map(|_| ())
@@ -267,7 +301,16 @@ impl<'db, T: TransactionKind> DbTxn<'db, T> {
 
     /// Open a table if the transaction has not done so already
     fn open_table(&self, table_name: &str) -> Result<Table, StorageError> {
-        self.txn.open_table(Some(table_name)).map_err(StorageError::OpenTable)
+        let name = self.namespaced_table(table_name);
+        self.txn.open_table(Some(&name)).map_err(StorageError::OpenTable)
+    }
+
+    /// Get the name of a table in the transaction's namespace
+    fn namespaced_table<'a>(&self, table_name: &'a str) -> Cow<'a, str> {
+        match self.namespace {
+            Some(namespace) => Cow::Owned(format!("{namespace}/{table_name}")),
+            None => Cow::Borrowed(table_name),
+        }
     }
 }
 
@@ -275,8 +318,9 @@ impl<'db, T: TransactionKind> DbTxn<'db, T> {
 impl<'db> DbTxn<'db, RW> {
     /// Create a new table in the database
     pub fn create_table(&self, table_name: &str) -> Result<(), StorageError> {
+        let name = self.namespaced_table(table_name);
         self.txn
-            .create_table(Some(table_name), TableFlags::default())
+            .create_table(Some(&name), TableFlags::default())
             .map_err(StorageError::TxOp)
             .map(|_| ())
     }
@@ -302,6 +346,25 @@ impl<'db> DbTxn<'db, RW> {
         self.txn.del(&table, key_bytes, None /* data */).map_err(StorageError::TxOp)
     }
 
+    /// Remove all keys from a table, leaving the table itself in place
+    pub fn clear_table(&self, table_name: &str) -> Result<(), StorageError> {
+        let table = self.open_table(table_name)?;
+        self.txn.clear_table(&table).map_err(StorageError::TxOp)
+    }
+
+    /// Set a key in the database from already serialized key and value bytes
+    pub fn write_raw(
+        &self,
+        table_name: &str,
+        key_bytes: &[u8],
+        value_bytes: &[u8],
+    ) -> Result<(), StorageError> {
+        let table = self.open_table(table_name)?;
+        self.txn
+            .put(&table, key_bytes, value_bytes, WriteFlags::default())
+            .map_err(StorageError::TxOp)
+    }
+
     // -----------
     // | Helpers |
     // -----------
@@ -317,10 +380,7 @@ impl<'db> DbTxn<'db, RW> {
         let key_bytes = serialize_value(key)?;
 
         // Set the value
-        let table = self.open_table(table_name)?;
-        self.txn
-            .put(&table, key_bytes, value_bytes, WriteFlags::default())
-            .map_err(StorageError::TxOp)
+        self.write_raw(table_name, &key_bytes, value_bytes)
     }
 }
 
@@ -205,6 +205,20 @@ impl<'db> StateTxn<'db, RW> {
         self.write_order(&order)
     }
 
+    /// Drop the validity proof of an order without changing its state
+    ///
+    /// Used to evict remote order proofs, which may be refetched from the
+    /// cluster managing the order
+    pub fn clear_validity_proof(&self, order_id: &OrderIdentifier) -> Result<(), StorageError> {
+        let mut order = match self.get_order_info(order_id)? {
+            Some(order) => order,
+            None => return Ok(()),
+        };
+        order.validity_proofs = None;
+
+        self.write_order(&order)
+    }
+
     /// Cancel an order in the order book
     pub fn cancel_order(&self, order_id: &OrderIdentifier) -> Result<(), StorageError> {
         let mut order = self.get_order_info_or_err(order_id)?;
@@ -352,7 +366,7 @@ mod test {
         // Attach a validity proof to the order
         let mut rng = thread_rng();
         let mut proof = dummy_validity_proof_bundle();
-        let nullifier = Scalar::random(&mut rng);
+        let nullifier = Scalar::random(&mut rng).into();
         let mut reblind_proof = dummy_valid_reblind_bundle();
         reblind_proof.statement.original_shares_nullifier = nullifier;
         proof.reblind_proof = Arc::new(reblind_proof);
@@ -1,6 +1,6 @@
 //! High level transaction interface for accessing the raft log
 
-use std::cmp;
+use std::{cmp, ops::RangeBounds};
 
 use libmdbx::{TransactionKind, RW};
 use raft::eraftpb::{
@@ -93,6 +93,29 @@ impl<'db, T: TransactionKind> StateTxn<'db, T> {
         Ok(stored_metadata.into_inner())
     }
 
+    /// Find the lowest index at or after `from` whose log entry cannot be
+    /// decoded, if any
+    pub fn find_corrupt_entry(&self, from: u64) -> Result<Option<u64>, ReplicationError> {
+        // Iterate over the keys alone, a corrupt value must not halt the scan
+        let cursor = self.inner().cursor::<String, ()>(RAFT_LOGS_TABLE)?;
+        let mut corrupt = None;
+        for key in cursor.into_iter().keys() {
+            let index = parse_lsn(&key?)?;
+            if index < from || corrupt.is_some_and(|c| c <= index) {
+                continue;
+            }
+
+            match self.read_log_entry(index) {
+                Err(StorageError::Deserialization(_)) => corrupt = Some(index),
+                res => {
+                    res?;
+                },
+            }
+        }
+
+        Ok(corrupt)
+    }
+
     /// A helper to construct a cursor over the logs
     pub fn logs_cursor(
         &self,
@@ -118,6 +141,13 @@ impl<'db> StateTxn<'db, RW> {
         self.inner().write(RAFT_METADATA_TABLE, &HARD_STATE_KEY.to_string(), &value)
     }
 
+    /// Write the snapshot metadata to the log store, leaving the hard state
+    /// untouched
+    pub fn write_snapshot_metadata(&self, metadata: SnapshotMetadata) -> Result<(), StorageError> {
+        let value = ProtoStorageWrapper(metadata);
+        self.inner().write(RAFT_METADATA_TABLE, &SNAPSHOT_METADATA_KEY.to_string(), &value)
+    }
+
     /// Append entries to the raft log
     pub fn append_log_entries(&self, entries: Vec<RaftEntry>) -> Result<(), StorageError> {
         let tx = self.inner();
@@ -131,6 +161,25 @@ impl<'db> StateTxn<'db, RW> {
         Ok(())
     }
 
+    /// Delete all log entries with an index in the given range
+    pub fn delete_log_range<R: RangeBounds<u64>>(&self, range: R) -> Result<(), ReplicationError> {
+        // Iterate over the keys alone, a corrupt value must not halt the scan
+        let cursor = self.inner().cursor::<String, ()>(RAFT_LOGS_TABLE)?;
+        let mut to_delete = Vec::new();
+        for key in cursor.into_iter().keys() {
+            let key = key?;
+            if range.contains(&parse_lsn(&key)?) {
+                to_delete.push(key);
+            }
+        }
+
+        for key in to_delete.iter() {
+            self.inner().delete(RAFT_LOGS_TABLE, key)?;
+        }
+
+        Ok(())
+    }
+
     /// Apply a snapshot to the log store
     pub fn apply_snapshot(&self, snapshot: &RaftSnapshot) -> Result<(), StorageError> {
         let tx = self.inner();
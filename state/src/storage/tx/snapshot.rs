@@ -0,0 +1,73 @@
+//! Helpers for capturing and installing the application state carried in a
+//! raft snapshot
+//!
+//! The raft metadata alone is not enough for a follower to catch up from a
+//! snapshot, so the snapshot additionally carries the wallet index and order
+//! book tables, copied byte-for-byte from the leader's database
+
+use libmdbx::{TransactionKind, RW};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    storage::error::StorageError, BLINDER_TO_WALLET_TABLE, ORDERS_TABLE, ORDER_TO_WALLET_TABLE,
+    PRIORITIES_TABLE, WALLETS_TABLE,
+};
+
+use super::StateTxn;
+
+/// The tables whose contents are carried in a snapshot
+pub const SNAPSHOT_TABLES: [&str; 5] =
+    [ORDERS_TABLE, PRIORITIES_TABLE, ORDER_TO_WALLET_TABLE, WALLETS_TABLE, BLINDER_TO_WALLET_TABLE];
+
+/// The application state carried in a raft snapshot
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppStateSnapshot {
+    /// The serialized key-value pairs of each snapshotted table, indexed by
+    /// table name
+    pub tables: Vec<(String, Vec<(Vec<u8>, Vec<u8>)>)>,
+}
+
+// -----------
+// | Getters |
+// -----------
+
+impl<'db, T: TransactionKind> StateTxn<'db, T> {
+    /// Read the application state to be carried in a snapshot
+    pub fn read_app_state_snapshot(&self) -> Result<AppStateSnapshot, StorageError> {
+        let mut tables = Vec::with_capacity(SNAPSHOT_TABLES.len());
+        for table in SNAPSHOT_TABLES {
+            let pairs = self.inner().read_table_bytes(table)?;
+            tables.push((table.to_string(), pairs));
+        }
+
+        Ok(AppStateSnapshot { tables })
+    }
+}
+
+// -----------
+// | Setters |
+// -----------
+
+impl<'db> StateTxn<'db, RW> {
+    /// Install the application state from a snapshot
+    ///
+    /// The snapshotted tables are cleared first, so that the local state
+    /// exactly matches the state the snapshot was taken from
+    pub fn install_app_state_snapshot(
+        &self,
+        snapshot: &AppStateSnapshot,
+    ) -> Result<(), StorageError> {
+        for (table, pairs) in snapshot.tables.iter() {
+            if !SNAPSHOT_TABLES.contains(&table.as_str()) {
+                return Err(StorageError::Other(format!("unexpected snapshot table: {table}")));
+            }
+
+            self.inner().clear_table(table)?;
+            for (key, value) in pairs.iter() {
+                self.inner().write_raw(table, key, value)?;
+            }
+        }
+
+        Ok(())
+    }
+}
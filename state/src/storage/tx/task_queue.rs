@@ -53,6 +53,15 @@ impl<'db, T: TransactionKind> StateTxn<'db, T> {
         self.inner().read(TASK_TO_KEY_TABLE, task_id)
     }
 
+    /// Get the IDs of all tasks in any queue
+    pub fn get_all_task_ids(&self) -> Result<Vec<TaskIdentifier>, StorageError> {
+        let cursor =
+            self.inner().cursor::<TaskIdentifier, TaskQueueKey>(TASK_TO_KEY_TABLE)?.into_iter();
+        let task_ids = cursor.keys().collect::<Result<Vec<_>, _>>()?;
+
+        Ok(task_ids)
+    }
+
     /// Get the task by ID
     pub fn get_task(&self, task_id: &TaskIdentifier) -> Result<Option<QueuedTask>, StorageError> {
         // Get the key for the task
@@ -1,9 +1,12 @@
 //! Helpers for accessing wallet index information in the database
 
 use common::types::wallet::{OrderIdentifier, Wallet, WalletAuthenticationPath, WalletIdentifier};
+use constants::Scalar;
 use libmdbx::{TransactionKind, RW};
 
-use crate::{storage::error::StorageError, ORDER_TO_WALLET_TABLE, WALLETS_TABLE};
+use crate::{
+    storage::error::StorageError, BLINDER_TO_WALLET_TABLE, ORDER_TO_WALLET_TABLE, WALLETS_TABLE,
+};
 
 use super::StateTxn;
 
@@ -28,6 +31,14 @@ impl<'db, T: TransactionKind> StateTxn<'db, T> {
         self.inner().read(ORDER_TO_WALLET_TABLE, order_id)
     }
 
+    /// Get the wallet whose current blinder is the given blinder, if any
+    pub fn get_wallet_for_blinder(
+        &self,
+        blinder: &Scalar,
+    ) -> Result<Option<WalletIdentifier>, StorageError> {
+        self.inner().read(BLINDER_TO_WALLET_TABLE, blinder)
+    }
+
     /// Get all the wallets in the database
     pub fn get_all_wallets(&self) -> Result<Vec<Wallet>, StorageError> {
         // Create a cursor and take only the values
@@ -37,6 +48,22 @@ impl<'db, T: TransactionKind> StateTxn<'db, T> {
 
         Ok(wallets)
     }
+
+    /// Get the number of wallets in the database
+    pub fn count_wallets(&self) -> Result<usize, StorageError> {
+        self.inner().table_len(WALLETS_TABLE)
+    }
+
+    /// Get every entry in the order to wallet index
+    pub fn get_order_wallet_index(
+        &self,
+    ) -> Result<Vec<(OrderIdentifier, WalletIdentifier)>, StorageError> {
+        let index_cursor = self
+            .inner()
+            .cursor::<OrderIdentifier, WalletIdentifier>(ORDER_TO_WALLET_TABLE)?
+            .into_iter();
+        index_cursor.collect()
+    }
 }
 
 // -----------
@@ -75,6 +102,20 @@ impl<'db> StateTxn<'db, RW> {
 
         Ok(())
     }
+
+    /// Record that the given wallet has used the given blinder
+    pub fn index_blinder(
+        &self,
+        blinder: &Scalar,
+        wallet_id: &WalletIdentifier,
+    ) -> Result<(), StorageError> {
+        self.inner().write(BLINDER_TO_WALLET_TABLE, blinder, wallet_id)
+    }
+
+    /// Remove a blinder from the blinder to wallet index
+    pub fn remove_blinder(&self, blinder: &Scalar) -> Result<(), StorageError> {
+        self.inner().delete(BLINDER_TO_WALLET_TABLE, blinder).map(|_| ())
+    }
 }
 
 // ---------
@@ -87,9 +128,12 @@ mod test {
         wallet::{OrderIdentifier, WalletIdentifier},
         wallet_mocks::{mock_empty_wallet, mock_merkle_path},
     };
+    use constants::Scalar;
     use itertools::Itertools;
 
-    use crate::{test_helpers::mock_db, ORDER_TO_WALLET_TABLE, WALLETS_TABLE};
+    use crate::{
+        test_helpers::mock_db, BLINDER_TO_WALLET_TABLE, ORDER_TO_WALLET_TABLE, WALLETS_TABLE,
+    };
 
     /// Tests adding a wallet then retrieving it
     #[test]
@@ -157,6 +201,32 @@ mod test {
         assert_eq!(wallets_res, wallets);
     }
 
+    /// Tests indexing a wallet's blinder then retrieving the wallet by it
+    #[test]
+    fn test_get_wallet_for_blinder() {
+        let db = mock_db();
+        db.create_table(BLINDER_TO_WALLET_TABLE).unwrap();
+
+        let wallet = mock_empty_wallet();
+        let tx = db.new_write_tx().unwrap();
+        tx.index_blinder(&wallet.blinder, &wallet.wallet_id).unwrap();
+        tx.commit().unwrap();
+
+        let tx = db.new_read_tx().unwrap();
+        let wallet_res = tx.get_wallet_for_blinder(&wallet.blinder).unwrap();
+        assert_eq!(wallet_res, Some(wallet.wallet_id));
+        assert_eq!(tx.get_wallet_for_blinder(&(wallet.blinder + Scalar::one())).unwrap(), None);
+        tx.commit().unwrap();
+
+        // Once removed, the blinder no longer maps to the wallet
+        let tx = db.new_write_tx().unwrap();
+        tx.remove_blinder(&wallet.blinder).unwrap();
+        tx.commit().unwrap();
+
+        let tx = db.new_read_tx().unwrap();
+        assert_eq!(tx.get_wallet_for_blinder(&wallet.blinder).unwrap(), None);
+    }
+
     /// Tests adding a wallet for an order then retrieving it
     #[test]
     fn test_get_wallet_for_order() {
@@ -84,6 +84,26 @@ pub fn match_orders_with_max_amount(
     })
 }
 
+/// Validate that an execution price is acceptable to both orders in a match
+///
+/// The `VALID MATCH SETTLE` circuit constrains the price to each order's limit,
+/// checking natively lets callers fail fast rather than at proof time
+pub fn validate_execution_price(o1: &Order, o2: &Order, price: FixedPoint) -> Result<(), String> {
+    for (i, order) in [o1, o2].into_iter().enumerate() {
+        if !order.price_in_range(price) {
+            return Err(format!(
+                "execution price {} outside the range of order {} ({:?}, worst case price {})",
+                price.to_f64(),
+                i + 1,
+                order.side,
+                order.worst_case_price.to_f64(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
 /// Compute the maximum matchable amount for an order and balance
 pub fn compute_max_amount(price: &FixedPoint, order: &Order, balance: &Balance) -> Amount {
     match order.side {
@@ -179,7 +199,7 @@ mod tests {
 
     use crate::matching_engine::compute_fee_obligation;
 
-    use super::{apply_match_to_shares, match_orders};
+    use super::{apply_match_to_shares, match_orders, validate_execution_price};
     use circuit_types::{
         balance::Balance,
         fixed_point::FixedPoint,
@@ -460,6 +480,32 @@ mod tests {
         assert!(res.is_none());
     }
 
+    /// Tests validating execution prices within both orders' ranges
+    #[test]
+    fn test_execution_price_in_range() {
+        let order1 = ORDER1.clone();
+        let order2 = ORDER2.clone();
+
+        for price in [SELL_SIDE_WORST_CASE_PRICE, 7., BUY_SIDE_WORST_CASE_PRICE] {
+            validate_execution_price(&order1, &order2, price.into()).unwrap();
+        }
+    }
+
+    /// Tests validating execution prices outside of either order's range
+    #[test]
+    fn test_execution_price_out_of_range() {
+        let order1 = ORDER1.clone();
+        let order2 = ORDER2.clone();
+
+        // Above the buy side's worst case price
+        let price = BUY_SIDE_WORST_CASE_PRICE + 1.;
+        assert!(validate_execution_price(&order1, &order2, price.into()).is_err());
+
+        // Below the sell side's worst case price
+        let price = SELL_SIDE_WORST_CASE_PRICE - 1.;
+        assert!(validate_execution_price(&order1, &order2, price.into()).is_err());
+    }
+
     // --------------------
     // | Settlement Tests |
     // --------------------
@@ -4,15 +4,19 @@ use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use base64::engine::{general_purpose as b64_general_purpose, Engine};
 use circuit_types::keychain::PublicSigningKey;
+use common::types::wallet::{KeyChain, KeyScope};
 use hyper::HeaderMap;
 use k256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+use util::get_current_time_millis;
 
-use crate::error::{bad_request, unauthorized, ApiServerError};
+use crate::error::{bad_request, forbidden, unauthorized, ApiServerError};
 
 /// Header name for the HTTP auth signature
 const RENEGADE_AUTH_HEADER_NAME: &str = "renegade-auth";
 /// Header name for the expiration timestamp of a signature
 const RENEGADE_SIG_EXPIRATION_HEADER_NAME: &str = "renegade-auth-expiration";
+/// Header name for the admin API token
+pub(crate) const RENEGADE_ADMIN_AUTH_HEADER_NAME: &str = "renegade-admin-auth";
 
 /// Error displayed when the signature format is invalid
 const ERR_SIG_FORMAT_INVALID: &str = "signature format invalid";
@@ -26,6 +30,16 @@ const ERR_EXPIRATION_FORMAT_INVALID: &str = "could not parse signature expiratio
 const ERR_EXPIRED: &str = "signature expired";
 /// Error displayed when signature verification fails on a request
 const ERR_SIG_VERIFICATION_FAILED: &str = "signature verification failed";
+/// Error displayed when a scoped key signs a request it is not authorized for
+pub(crate) const ERR_KEY_OUT_OF_SCOPE: &str = "signing key is not authorized for this operation";
+/// Error displayed when a scoped key signs a request after its expiry
+pub(crate) const ERR_KEY_EXPIRED: &str = "signing key has expired";
+/// Error displayed when an admin route is requested but no admin key is set
+const ERR_ADMIN_DISABLED: &str = "admin routes are disabled";
+/// Error displayed when the admin token header is missing
+const ERR_ADMIN_TOKEN_MISSING: &str = "admin token missing from headers";
+/// Error displayed when the admin token does not match the configured key
+const ERR_ADMIN_TOKEN_INVALID: &str = "admin token invalid";
 
 /// Authenticates a wallet request using the given key
 ///
@@ -55,6 +69,63 @@ pub fn authenticate_wallet_request(
     validate_expiring_signature(body, expiration, &signature, &root_key)
 }
 
+/// Authenticates a wallet request using any key in the wallet's keychain
+///
+/// The root key is authorized for every operation. A scoped key is accepted
+/// only if the route requires a scope, the key has been granted it, and the
+/// key has not expired
+pub fn authenticate_scoped_wallet_request(
+    headers: &HeaderMap,
+    body: &[u8],
+    key_chain: &KeyChain,
+    scope: Option<KeyScope>,
+) -> Result<(), ApiServerError> {
+    let root_res = authenticate_wallet_request(headers, body, &key_chain.public_keys.pk_root);
+    if root_res.is_ok() {
+        return root_res;
+    }
+
+    // Fall back to the scoped keys, surfacing the root key's error if none of
+    // them signed the request
+    let signer = key_chain
+        .scoped_keys
+        .iter()
+        .find(|k| authenticate_wallet_request(headers, body, &k.key).is_ok());
+    let now = get_current_time_millis() as u64;
+    match (signer, scope) {
+        (None, _) => root_res,
+        (Some(key), _) if key.is_expired(now) => Err(unauthorized(ERR_KEY_EXPIRED.to_string())),
+        (Some(key), Some(scope)) if key.has_scope(scope) => Ok(()),
+        (Some(_), _) => Err(forbidden(ERR_KEY_OUT_OF_SCOPE.to_string())),
+    }
+}
+
+/// Authenticates an admin request against the configured admin API key
+///
+/// Admin routes are disabled, i.e. every request is rejected, when no key is
+/// configured
+pub fn authenticate_admin_request(
+    headers: &HeaderMap,
+    admin_api_key: Option<&str>,
+) -> Result<(), ApiServerError> {
+    let expected = admin_api_key.ok_or_else(|| unauthorized(ERR_ADMIN_DISABLED.to_string()))?;
+    let token = headers
+        .get(RENEGADE_ADMIN_AUTH_HEADER_NAME)
+        .ok_or_else(|| unauthorized(ERR_ADMIN_TOKEN_MISSING.to_string()))?;
+
+    if !constant_time_eq(token.as_bytes(), expected.as_bytes()) {
+        return Err(unauthorized(ERR_ADMIN_TOKEN_INVALID.to_string()));
+    }
+
+    Ok(())
+}
+
+/// Compare two byte strings in time independent of the position at which
+/// they first differ
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
 /// Parse a signature from the given header
 fn parse_signature_from_header(headers: &HeaderMap) -> Result<Signature, ApiServerError> {
     let b64_signature: &str = headers
@@ -98,12 +169,20 @@ fn validate_expiring_signature(
 #[cfg(test)]
 mod test {
     use base64::engine::{general_purpose as b64_general_purpose, Engine};
-    use hyper::{header::HeaderValue, HeaderMap};
+    use common::types::{
+        wallet::{KeyScope, ScopedKey},
+        wallet_mocks::mock_empty_wallet,
+    };
+    use hyper::{header::HeaderValue, HeaderMap, StatusCode};
     use k256::ecdsa::{signature::Signer, Signature, SigningKey};
     use rand::thread_rng;
 
+    use crate::error::ApiServerError;
+
     use super::{
-        authenticate_wallet_request, RENEGADE_AUTH_HEADER_NAME, RENEGADE_SIG_EXPIRATION_HEADER_NAME,
+        authenticate_admin_request, authenticate_scoped_wallet_request,
+        authenticate_wallet_request, RENEGADE_ADMIN_AUTH_HEADER_NAME, RENEGADE_AUTH_HEADER_NAME,
+        RENEGADE_SIG_EXPIRATION_HEADER_NAME,
     };
 
     /// A message to sign for testing
@@ -168,4 +247,103 @@ mod test {
         let res = authenticate_wallet_request(&headers, MSG, &key.verifying_key().into());
         assert!(res.is_err());
     }
+
+    /// Tests admin authentication with the configured token
+    #[test]
+    fn test_admin_auth_valid() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RENEGADE_ADMIN_AUTH_HEADER_NAME, HeaderValue::from_static("admin-key"));
+
+        let res = authenticate_admin_request(&headers, Some("admin-key"));
+        assert!(res.is_ok());
+    }
+
+    /// Tests admin authentication with a missing or incorrect token, and with
+    /// admin routes disabled
+    #[test]
+    fn test_admin_auth_invalid() {
+        // Missing token
+        let res = authenticate_admin_request(&HeaderMap::new(), Some("admin-key"));
+        assert!(res.is_err());
+
+        // Incorrect token
+        let mut headers = HeaderMap::new();
+        headers.insert(RENEGADE_ADMIN_AUTH_HEADER_NAME, HeaderValue::from_static("admin-ke"));
+        let res = authenticate_admin_request(&headers, Some("admin-key"));
+        assert!(res.is_err());
+
+        // No admin key configured
+        headers.insert(RENEGADE_ADMIN_AUTH_HEADER_NAME, HeaderValue::from_static("admin-key"));
+        let res = authenticate_admin_request(&headers, None);
+        assert!(res.is_err());
+    }
+
+    /// Tests a scoped key signing an operation it is authorized for
+    #[test]
+    fn test_scoped_key_in_scope() {
+        let key = random_key();
+        let mut wallet = mock_empty_wallet();
+        wallet.key_chain.scoped_keys.push(ScopedKey {
+            key: key.verifying_key().into(),
+            scopes: vec![KeyScope::PlaceOrders, KeyScope::CancelOrders],
+            expiry: None,
+        });
+
+        let headers = build_headers(&key);
+        let res = authenticate_scoped_wallet_request(
+            &headers,
+            MSG,
+            &wallet.key_chain,
+            Some(KeyScope::PlaceOrders),
+        );
+        assert!(res.is_ok());
+    }
+
+    /// Tests a scoped key signing an operation it is not authorized for
+    #[test]
+    fn test_scoped_key_out_of_scope() {
+        let key = random_key();
+        let mut wallet = mock_empty_wallet();
+        wallet.key_chain.scoped_keys.push(ScopedKey {
+            key: key.verifying_key().into(),
+            scopes: vec![KeyScope::PlaceOrders, KeyScope::CancelOrders],
+            expiry: None,
+        });
+
+        // A withdrawal is out of the key's scope
+        let headers = build_headers(&key);
+        let res = authenticate_scoped_wallet_request(
+            &headers,
+            MSG,
+            &wallet.key_chain,
+            Some(KeyScope::Withdraw),
+        );
+        assert!(matches!(res, Err(ApiServerError::HttpStatusCode(StatusCode::FORBIDDEN, _))));
+
+        // As is a route that requires the root key
+        let res = authenticate_scoped_wallet_request(&headers, MSG, &wallet.key_chain, None);
+        assert!(matches!(res, Err(ApiServerError::HttpStatusCode(StatusCode::FORBIDDEN, _))));
+    }
+
+    /// Tests a scoped key signing an in-scope operation after its expiry
+    #[test]
+    fn test_scoped_key_expired() {
+        let key = random_key();
+        let mut wallet = mock_empty_wallet();
+        let now = util::get_current_time_millis() as u64;
+        wallet.key_chain.scoped_keys.push(ScopedKey {
+            key: key.verifying_key().into(),
+            scopes: vec![KeyScope::PlaceOrders],
+            expiry: Some(now - 1),
+        });
+
+        let headers = build_headers(&key);
+        let res = authenticate_scoped_wallet_request(
+            &headers,
+            MSG,
+            &wallet.key_chain,
+            Some(KeyScope::PlaceOrders),
+        );
+        assert!(matches!(res, Err(ApiServerError::HttpStatusCode(StatusCode::UNAUTHORIZED, _))));
+    }
 }
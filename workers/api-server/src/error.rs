@@ -40,6 +40,10 @@ impl From<ApiServerError> for Response<Body> {
             ApiServerError::HttpStatusCode(status, message) => {
                 build_response_from_status_code(status, message)
             },
+            // A draining node is temporarily unable to accept new work
+            ApiServerError::State(StateError::Draining) => {
+                build_response_from_status_code(StatusCode::SERVICE_UNAVAILABLE, err.to_string())
+            },
             _ => build_500_response(err.to_string()),
         }
     }
@@ -55,6 +59,11 @@ pub(crate) fn unauthorized(e: String) -> ApiServerError {
     ApiServerError::HttpStatusCode(StatusCode::UNAUTHORIZED, e)
 }
 
+/// Create an `ApiServerError` with a 403 forbidden code
+pub(crate) fn forbidden(e: String) -> ApiServerError {
+    ApiServerError::HttpStatusCode(StatusCode::FORBIDDEN, e)
+}
+
 /// Create an `ApiServerError` with a 404 not found code
 pub(crate) fn not_found(e: String) -> ApiServerError {
     ApiServerError::HttpStatusCode(StatusCode::NOT_FOUND, e)
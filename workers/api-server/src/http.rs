@@ -4,6 +4,7 @@ use async_trait::async_trait;
 use common::types::{
     gossip::{ClusterId, WrappedPeerId},
     tasks::TaskIdentifier,
+    wallet::KeyScope,
 };
 use external_api::{http::PingResponse, EmptyRequestResponse};
 use hyper::{
@@ -25,6 +26,13 @@ use uuid::Uuid;
 use crate::error::{bad_request, not_found};
 
 use self::{
+    admin::{
+        AdminAbortDrainHandler, AdminDrainHandler, AdminGetTasksHandler, AdminMatchGasHandler,
+        AdminResyncHandler, AdminVerifyProofHandler, AdminWalletIndexConsistencyHandler,
+        ADMIN_ABORT_DRAIN_ROUTE, ADMIN_DRAIN_ROUTE, ADMIN_GET_TASKS_ROUTE, ADMIN_MATCH_GAS_ROUTE,
+        ADMIN_RESYNC_ROUTE, ADMIN_VERIFY_PROOF_ROUTE, ADMIN_WALLET_INDEX_CONSISTENCY_ROUTE,
+    },
+    metrics::{GetMatchVolumeHandler, MetricsHandler, GET_MATCH_VOLUME_ROUTE, METRICS_ROUTE},
     network::{
         GetClusterInfoHandler, GetNetworkTopologyHandler, GetPeerInfoHandler,
         GET_CLUSTER_INFO_ROUTE, GET_NETWORK_TOPOLOGY_ROUTE, GET_PEER_INFO_ROUTE,
@@ -38,11 +46,14 @@ use self::{
         GetTaskQueueHandler, GetTaskStatusHandler, GET_TASK_QUEUE_ROUTE, GET_TASK_STATUS_ROUTE,
     },
     wallet::{
-        CancelOrderHandler, CreateOrderHandler, CreateWalletHandler, DepositBalanceHandler,
-        FindWalletHandler, GetBalanceByMintHandler, GetBalancesHandler, GetOrderByIdHandler,
-        GetOrdersHandler, GetWalletHandler, UpdateOrderHandler, WithdrawBalanceHandler,
-        CANCEL_ORDER_ROUTE, CREATE_WALLET_ROUTE, DEPOSIT_BALANCE_ROUTE, FIND_WALLET_ROUTE,
-        GET_BALANCES_ROUTE, GET_BALANCE_BY_MINT_ROUTE, GET_ORDER_BY_ID_ROUTE, GET_WALLET_ROUTE,
+        CancelAllOrdersHandler, CancelOrderHandler, CreateOrderHandler, CreateWalletHandler,
+        DepositBalanceHandler, FindWalletHandler, GetBalanceByMintHandler, GetBalancesHandler,
+        GetOrderByIdHandler, GetOrderStatsHandler, GetOrdersHandler, GetWalletHandler,
+        GrantScopedKeyHandler, RemoveBalanceHandler, RevokeScopedKeyHandler, RotateSeedHandler,
+        UpdateOrderHandler, WithdrawBalanceHandler, CANCEL_ALL_ORDERS_ROUTE, CANCEL_ORDER_ROUTE,
+        CREATE_WALLET_ROUTE, DEPOSIT_BALANCE_ROUTE, FIND_WALLET_ROUTE, GET_BALANCES_ROUTE,
+        GET_BALANCE_BY_MINT_ROUTE, GET_ORDER_BY_ID_ROUTE, GET_ORDER_STATS_ROUTE, GET_WALLET_ROUTE,
+        GRANT_SCOPED_KEY_ROUTE, REMOVE_BALANCE_ROUTE, REVOKE_SCOPED_KEY_ROUTE, ROTATE_SEED_ROUTE,
         UPDATE_ORDER_ROUTE, WALLET_ORDERS_ROUTE, WITHDRAW_BALANCE_ROUTE,
     },
 };
@@ -53,6 +64,8 @@ use super::{
     worker::ApiServerConfig,
 };
 
+mod admin;
+mod metrics;
 mod network;
 mod order_book;
 mod price_report;
@@ -95,6 +108,9 @@ const CLUSTER_ID_URL_PARAM: &str = "cluster_id";
 const PEER_ID_URL_PARAM: &str = "peer_id";
 /// The :task_id param in a URL
 const TASK_ID_URL_PARAM: &str = "task_id";
+/// The ?decimals query param, requests that amounts be annotated with their
+/// token's decimals
+const DECIMALS_QUERY_PARAM: &str = "decimals";
 
 /// A helper to parse out a mint from a URL param
 pub(super) fn parse_mint_from_params(params: &UrlParams) -> Result<BigUint, ApiServerError> {
@@ -160,6 +176,14 @@ pub(super) fn parse_task_id_from_params(
         .map_err(|_| bad_request(ERR_TASK_ID_PARSE.to_string()))
 }
 
+/// A helper to parse whether amounts should be annotated with their token's
+/// decimals from the query params
+///
+/// Raw amounts are returned unless `?decimals=true` is given
+pub(super) fn parse_decimals_from_params(params: &UrlParams) -> bool {
+    params.get(DECIMALS_QUERY_PARAM).is_some_and(|v| v == "true")
+}
+
 /// A wrapper around the router and task management operations that
 /// the worker may delegate to
 
@@ -182,7 +206,12 @@ impl HttpServer {
     /// Build a router and register routes on it
     fn build_router(config: &ApiServerConfig, global_state: State) -> Router {
         // Build the router and register its routes
-        let mut router = Router::new(global_state.clone());
+        let mut router = Router::new(
+            global_state.clone(),
+            config.admin_api_key.clone(),
+            config.proof_generation_work_queue.clone(),
+            config.proof_queue_high_water_mark,
+        );
 
         // The "/exchangeHealthStates" route
         router.add_route(
@@ -200,6 +229,21 @@ impl HttpServer {
             PingHandler::new(),
         );
 
+        // The "/metrics" route
+        router.add_route(
+            &Method::GET,
+            METRICS_ROUTE.to_string(),
+            false, // auth_required
+            MetricsHandler::new(global_state.clone()),
+        );
+
+        // The "/metrics/match-volume" route
+        router.add_admin_route(
+            &Method::GET,
+            GET_MATCH_VOLUME_ROUTE.to_string(),
+            GetMatchVolumeHandler::new(),
+        );
+
         // The "/task/:id" route
         router.add_route(
             &Method::GET,
@@ -208,6 +252,55 @@ impl HttpServer {
             GetTaskStatusHandler::new(global_state.clone()),
         );
 
+        // The "/admin/tasks" route
+        router.add_admin_route(
+            &Method::GET,
+            ADMIN_GET_TASKS_ROUTE.to_string(),
+            AdminGetTasksHandler::new(config.task_registry.clone()),
+        );
+
+        // The "/admin/verify-proof" route
+        router.add_admin_route(
+            &Method::POST,
+            ADMIN_VERIFY_PROOF_ROUTE.to_string(),
+            AdminVerifyProofHandler::new(),
+        );
+
+        // The "/admin/resync" route
+        router.add_admin_route(
+            &Method::POST,
+            ADMIN_RESYNC_ROUTE.to_string(),
+            AdminResyncHandler::new(global_state.clone()),
+        );
+
+        // The "/admin/wallet-index-consistency" route
+        router.add_admin_route(
+            &Method::GET,
+            ADMIN_WALLET_INDEX_CONSISTENCY_ROUTE.to_string(),
+            AdminWalletIndexConsistencyHandler::new(global_state.clone()),
+        );
+
+        // The "/admin/drain" route
+        router.add_admin_route(
+            &Method::POST,
+            ADMIN_DRAIN_ROUTE.to_string(),
+            AdminDrainHandler::new(global_state.clone()),
+        );
+
+        // The "/admin/drain/abort" route
+        router.add_admin_route(
+            &Method::POST,
+            ADMIN_ABORT_DRAIN_ROUTE.to_string(),
+            AdminAbortDrainHandler::new(global_state.clone()),
+        );
+
+        // The "/admin/match-gas" route
+        router.add_admin_route(
+            &Method::GET,
+            ADMIN_MATCH_GAS_ROUTE.to_string(),
+            AdminMatchGasHandler::new(config.match_gas_ledger.clone()),
+        );
+
         // The "/task_queue/:wallet_id" route
         router.add_route(
             &Method::GET,
@@ -225,7 +318,7 @@ impl HttpServer {
         );
 
         // The "/wallet" route
-        router.add_route(
+        router.add_throttled_route(
             &Method::POST,
             CREATE_WALLET_ROUTE.to_string(),
             false, // auth_required
@@ -233,13 +326,37 @@ impl HttpServer {
         );
 
         // The "/wallet/lookup" route
-        router.add_route(
+        router.add_throttled_route(
             &Method::POST,
             FIND_WALLET_ROUTE.to_string(),
             false, // auth_required
             FindWalletHandler::new(global_state.clone()),
         );
 
+        // The "/wallet/:id/rotate-seed" route
+        router.add_throttled_route(
+            &Method::POST,
+            ROTATE_SEED_ROUTE.to_string(),
+            true, // auth_required
+            RotateSeedHandler::new(global_state.clone()),
+        );
+
+        // The "/wallet/:id/scoped-keys/grant" route, scoped keys may not grant keys
+        router.add_route(
+            &Method::POST,
+            GRANT_SCOPED_KEY_ROUTE.to_string(),
+            true, // auth_required
+            GrantScopedKeyHandler::new(global_state.clone()),
+        );
+
+        // The "/wallet/:id/scoped-keys/revoke" route
+        router.add_route(
+            &Method::POST,
+            REVOKE_SCOPED_KEY_ROUTE.to_string(),
+            true, // auth_required
+            RevokeScopedKeyHandler::new(global_state.clone()),
+        );
+
         // Getter for the "/wallet/:id/orders" route
         router.add_route(
             &Method::GET,
@@ -249,11 +366,15 @@ impl HttpServer {
         );
 
         // Post to the "/wallet/:id/orders" route
-        router.add_route(
+        router.add_scoped_route(
             &Method::POST,
             WALLET_ORDERS_ROUTE.to_string(),
-            true, // auth_required
-            CreateOrderHandler::new(global_state.clone()),
+            KeyScope::PlaceOrders,
+            CreateOrderHandler::new(
+                global_state.clone(),
+                config.min_order_amounts.clone(),
+                config.max_order_amounts.clone(),
+            ),
         );
 
         // The "/wallet/:id/orders/:id" route
@@ -264,22 +385,42 @@ impl HttpServer {
             GetOrderByIdHandler::new(global_state.clone()),
         );
 
-        // The "/wallet/:id/orders/:id/update" route
+        // The "/wallet/:id/orders/:id/stats" route
         router.add_route(
+            &Method::GET,
+            GET_ORDER_STATS_ROUTE.to_string(),
+            true, // auth_required
+            GetOrderStatsHandler::new(global_state.clone(), config.order_stats.clone()),
+        );
+
+        // The "/wallet/:id/orders/:id/update" route
+        router.add_scoped_route(
             &Method::POST,
             UPDATE_ORDER_ROUTE.to_string(),
-            true, // auth_required
-            UpdateOrderHandler::new(global_state.clone()),
+            KeyScope::PlaceOrders,
+            UpdateOrderHandler::new(
+                global_state.clone(),
+                config.min_order_amounts.clone(),
+                config.max_order_amounts.clone(),
+            ),
         );
 
         // The "/wallet/:id/orders/:id/cancel" route
-        router.add_route(
+        router.add_scoped_route(
             &Method::POST,
             CANCEL_ORDER_ROUTE.to_string(),
-            true, // auth_required
+            KeyScope::CancelOrders,
             CancelOrderHandler::new(global_state.clone()),
         );
 
+        // The "/wallet/:id/orders/cancel-all" route
+        router.add_scoped_route(
+            &Method::POST,
+            CANCEL_ALL_ORDERS_ROUTE.to_string(),
+            KeyScope::CancelOrders,
+            CancelAllOrdersHandler::new(global_state.clone()),
+        );
+
         // The "/wallet/:id/balances" route
         router.add_route(
             &Method::GET,
@@ -297,21 +438,29 @@ impl HttpServer {
         );
 
         // The "/wallet/:id/balances/deposit" route
-        router.add_route(
+        router.add_scoped_route(
             &Method::POST,
             DEPOSIT_BALANCE_ROUTE.to_string(),
-            true, // auth_required
+            KeyScope::Deposit,
             DepositBalanceHandler::new(global_state.clone()),
         );
 
         // The "/wallet/:id/balances/:mint/withdraw" route
-        router.add_route(
+        router.add_scoped_route(
             &Method::POST,
             WITHDRAW_BALANCE_ROUTE.to_string(),
-            true, // auth_required
+            KeyScope::Withdraw,
             WithdrawBalanceHandler::new(global_state.clone()),
         );
 
+        // The "/wallet/:id/balances/:mint/remove" route
+        router.add_scoped_route(
+            &Method::POST,
+            REMOVE_BALANCE_ROUTE.to_string(),
+            KeyScope::Withdraw,
+            RemoveBalanceHandler::new(global_state.clone()),
+        );
+
         // The "/order_book/orders" route
         router.add_route(
             &Method::GET,
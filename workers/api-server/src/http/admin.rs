@@ -0,0 +1,400 @@
+//! Groups admin API handlers, used by operators to inspect the relayer
+//!
+//! Admin routes are authenticated by the admin API key rather than a wallet
+//! signature
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use circuits::zk_circuits::validity_bundle::verify_sized_validity_proofs;
+use external_api::{
+    http::{
+        admin::{
+            DrainRequest, DrainResponse, GetMatchGasResponse, ProofVerificationResult,
+            VerifyProofRequest, VerifyProofResponse, WalletIndexConsistencyResponse,
+        },
+        task::GetRunningTasksResponse,
+    },
+    EmptyRequestResponse,
+};
+use hyper::HeaderMap;
+use job_types::task_driver::{MatchGasLedger, TaskRegistry};
+use state::State;
+use tokio::task::spawn_blocking;
+use util::err_str;
+
+use crate::{
+    error::{internal_error, ApiServerError},
+    router::{TypedHandler, UrlParams},
+};
+
+// ---------------
+// | HTTP Routes |
+// ---------------
+
+/// List the tasks running in the task driver
+pub(super) const ADMIN_GET_TASKS_ROUTE: &str = "/v0/admin/tasks";
+/// Verify an order validity proof bundle
+pub(super) const ADMIN_VERIFY_PROOF_ROUTE: &str = "/v0/admin/verify-proof";
+/// Resync the local node's state from a snapshot of the raft leader's state
+pub(super) const ADMIN_RESYNC_ROUTE: &str = "/v0/admin/resync";
+/// Check that the wallet index is consistent with the wallets in the state
+pub(super) const ADMIN_WALLET_INDEX_CONSISTENCY_ROUTE: &str = "/v0/admin/wallet-index-consistency";
+/// List the gas used by the match settlements the local relayer submitted
+pub(super) const ADMIN_MATCH_GAS_ROUTE: &str = "/v0/admin/match-gas";
+/// Drain the local node ahead of a shutdown
+pub(super) const ADMIN_DRAIN_ROUTE: &str = "/v0/admin/drain";
+/// Abort a drain of the local node, resuming new orders and matches
+pub(super) const ADMIN_ABORT_DRAIN_ROUTE: &str = "/v0/admin/drain/abort";
+
+/// The default time to wait for in-flight tasks when draining the node
+const DEFAULT_DRAIN_TIMEOUT_MS: u64 = 60_000; // 1 minute
+
+// ------------------
+// | Route Handlers |
+// ------------------
+
+/// Handler for the GET /admin/tasks route
+pub struct AdminGetTasksHandler {
+    /// The registry of tasks running in the task driver
+    task_registry: TaskRegistry,
+}
+
+impl AdminGetTasksHandler {
+    /// Constructor
+    pub fn new(task_registry: TaskRegistry) -> Self {
+        Self { task_registry }
+    }
+}
+
+#[async_trait]
+impl TypedHandler for AdminGetTasksHandler {
+    type Request = EmptyRequestResponse;
+    type Response = GetRunningTasksResponse;
+
+    async fn handle_typed(
+        &self,
+        _headers: HeaderMap,
+        _req: Self::Request,
+        _params: UrlParams,
+    ) -> Result<Self::Response, ApiServerError> {
+        let mut tasks = self.task_registry.read().unwrap().values().cloned().collect::<Vec<_>>();
+        tasks.sort_by_key(|task| task.start_time);
+
+        Ok(GetRunningTasksResponse { tasks })
+    }
+}
+
+/// Handler for the GET /admin/match-gas route
+pub struct AdminMatchGasHandler {
+    /// The ledger of gas used by match settlements
+    match_gas_ledger: MatchGasLedger,
+}
+
+impl AdminMatchGasHandler {
+    /// Constructor
+    pub fn new(match_gas_ledger: MatchGasLedger) -> Self {
+        Self { match_gas_ledger }
+    }
+}
+
+#[async_trait]
+impl TypedHandler for AdminMatchGasHandler {
+    type Request = EmptyRequestResponse;
+    type Response = GetMatchGasResponse;
+
+    async fn handle_typed(
+        &self,
+        _headers: HeaderMap,
+        _req: Self::Request,
+        _params: UrlParams,
+    ) -> Result<Self::Response, ApiServerError> {
+        // The ledger holds records in the order they were recorded
+        let matches = self.match_gas_ledger.read().unwrap().iter().cloned().collect();
+        Ok(GetMatchGasResponse { matches })
+    }
+}
+
+/// Handler for the POST /admin/verify-proof route
+///
+/// Verifies each proof in an order validity bundle independently so that the
+/// failing proof in a disputed match may be identified
+#[derive(Default)]
+pub struct AdminVerifyProofHandler;
+
+impl AdminVerifyProofHandler {
+    /// Constructor
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl TypedHandler for AdminVerifyProofHandler {
+    type Request = VerifyProofRequest;
+    type Response = VerifyProofResponse;
+
+    async fn handle_typed(
+        &self,
+        _headers: HeaderMap,
+        req: Self::Request,
+        _params: UrlParams,
+    ) -> Result<Self::Response, ApiServerError> {
+        // Verification is compute bound, so we run it off the async runtime
+        let bundle = req.bundle;
+        let results = spawn_blocking(move || {
+            let reblind = bundle.copy_reblind_proof();
+            let commitments = bundle.copy_commitment_proof();
+            verify_sized_validity_proofs(
+                &reblind.statement,
+                &reblind.proof,
+                &commitments.statement,
+                &commitments.proof,
+                &bundle.linking_proof,
+            )
+        })
+        .await
+        .map_err(|e| internal_error(e.to_string()))?;
+
+        let results = results
+            .into_iter()
+            .map(|(proof, res)| ProofVerificationResult {
+                proof: proof.to_string(),
+                valid: res.is_ok(),
+                error: res.err().map(|e| e.to_string()),
+            })
+            .collect::<Vec<_>>();
+        let valid = results.iter().all(|res| res.valid);
+
+        Ok(VerifyProofResponse { valid, results })
+    }
+}
+
+/// Handler for the POST /admin/resync route
+///
+/// Requests a snapshot from the raft leader and reinstalls the local state
+/// from it, responding once the snapshot is installed. The local node accepts
+/// no new proposals while the resync is in progress
+pub struct AdminResyncHandler {
+    /// A handle on the relayer-global state
+    global_state: State,
+}
+
+impl AdminResyncHandler {
+    /// Constructor
+    pub fn new(global_state: State) -> Self {
+        Self { global_state }
+    }
+}
+
+#[async_trait]
+impl TypedHandler for AdminResyncHandler {
+    type Request = EmptyRequestResponse;
+    type Response = EmptyRequestResponse;
+
+    async fn handle_typed(
+        &self,
+        _headers: HeaderMap,
+        _req: Self::Request,
+        _params: UrlParams,
+    ) -> Result<Self::Response, ApiServerError> {
+        let waiter = self.global_state.resync_from_leader()?;
+        waiter.await.map_err(err_str!(internal_error))?;
+
+        Ok(EmptyRequestResponse {})
+    }
+}
+
+/// Handler for the POST /admin/drain route
+///
+/// Stops the local node accepting new orders and matches, waits for its
+/// in-flight tasks and handshakes and hands off raft leadership, then responds
+/// with whether the node is ready to stop
+pub struct AdminDrainHandler {
+    /// A handle on the relayer-global state
+    global_state: State,
+}
+
+impl AdminDrainHandler {
+    /// Constructor
+    pub fn new(global_state: State) -> Self {
+        Self { global_state }
+    }
+}
+
+#[async_trait]
+impl TypedHandler for AdminDrainHandler {
+    type Request = DrainRequest;
+    type Response = DrainResponse;
+
+    async fn handle_typed(
+        &self,
+        _headers: HeaderMap,
+        req: Self::Request,
+        _params: UrlParams,
+    ) -> Result<Self::Response, ApiServerError> {
+        let timeout = Duration::from_millis(req.timeout_ms.unwrap_or(DEFAULT_DRAIN_TIMEOUT_MS));
+        let outcome = self.global_state.begin_drain(timeout).await?;
+
+        Ok(DrainResponse {
+            ready_to_stop: outcome.ready_to_stop,
+            remaining_tasks: outcome.remaining_tasks,
+            remaining_handshakes: outcome.remaining_handshakes,
+        })
+    }
+}
+
+/// Handler for the POST /admin/drain/abort route
+///
+/// Aborts a drain of the local node, after which it accepts new orders and
+/// matches again. A drain in progress responds once it observes the abort
+pub struct AdminAbortDrainHandler {
+    /// A handle on the relayer-global state
+    global_state: State,
+}
+
+impl AdminAbortDrainHandler {
+    /// Constructor
+    pub fn new(global_state: State) -> Self {
+        Self { global_state }
+    }
+}
+
+#[async_trait]
+impl TypedHandler for AdminAbortDrainHandler {
+    type Request = EmptyRequestResponse;
+    type Response = EmptyRequestResponse;
+
+    async fn handle_typed(
+        &self,
+        _headers: HeaderMap,
+        _req: Self::Request,
+        _params: UrlParams,
+    ) -> Result<Self::Response, ApiServerError> {
+        self.global_state.abort_drain();
+        Ok(EmptyRequestResponse {})
+    }
+}
+
+/// Handler for the GET /admin/wallet-index-consistency route
+///
+/// Walks the wallet index and reports any orders that do not map back to
+/// exactly one wallet containing them
+pub struct AdminWalletIndexConsistencyHandler {
+    /// A handle on the relayer-global state
+    global_state: State,
+}
+
+impl AdminWalletIndexConsistencyHandler {
+    /// Constructor
+    pub fn new(global_state: State) -> Self {
+        Self { global_state }
+    }
+}
+
+#[async_trait]
+impl TypedHandler for AdminWalletIndexConsistencyHandler {
+    type Request = EmptyRequestResponse;
+    type Response = WalletIndexConsistencyResponse;
+
+    async fn handle_typed(
+        &self,
+        _headers: HeaderMap,
+        _req: Self::Request,
+        _params: UrlParams,
+    ) -> Result<Self::Response, ApiServerError> {
+        let inconsistencies = self.global_state.verify_wallet_index_consistency()?;
+        Ok(WalletIndexConsistencyResponse {
+            consistent: inconsistencies.is_empty(),
+            inconsistencies,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use common::types::tasks::RunningTaskInfo;
+    use hyper::{body::to_bytes, header::HeaderValue, Body, Method, Request, StatusCode};
+    use job_types::{proof_manager::new_proof_manager_queue, task_driver::new_task_registry};
+    use state::test_helpers::mock_state;
+    use uuid::Uuid;
+
+    use crate::{auth::RENEGADE_ADMIN_AUTH_HEADER_NAME, router::Router};
+
+    use super::{AdminGetTasksHandler, ADMIN_GET_TASKS_ROUTE};
+
+    /// The admin key configured on the test router
+    const ADMIN_KEY: &str = "admin-key";
+
+    /// Build a router serving the admin tasks route over a registry holding a
+    /// single task
+    fn setup_router() -> (Router, RunningTaskInfo) {
+        let registry = new_task_registry();
+        let task = RunningTaskInfo {
+            id: Uuid::new_v4(),
+            name: "update-wallet".to_string(),
+            state: "Proving".to_string(),
+            wallet_ids: vec![Uuid::new_v4()],
+            start_time: 1,
+        };
+        registry.write().unwrap().insert(task.id, task.clone());
+
+        let (proof_queue, _) = new_proof_manager_queue();
+        let mut router = Router::new(
+            mock_state(),
+            Some(ADMIN_KEY.to_string()),
+            proof_queue,
+            usize::MAX, // proof_queue_high_water_mark
+        );
+        router.add_admin_route(
+            &Method::GET,
+            ADMIN_GET_TASKS_ROUTE.to_string(),
+            AdminGetTasksHandler::new(registry),
+        );
+
+        (router, task)
+    }
+
+    /// Send a request to the admin tasks route with the given admin token
+    async fn get_tasks(router: &Router, token: Option<&'static str>) -> (StatusCode, Vec<u8>) {
+        let mut req = Request::new(Body::empty());
+        if let Some(token) = token {
+            req.headers_mut()
+                .insert(RENEGADE_ADMIN_AUTH_HEADER_NAME, HeaderValue::from_static(token));
+        }
+
+        let resp = router.handle_req(Method::GET, ADMIN_GET_TASKS_ROUTE.to_string(), req).await;
+        let status = resp.status();
+        let body = to_bytes(resp.into_body()).await.unwrap().to_vec();
+        (status, body)
+    }
+
+    /// Tests listing tasks with the admin key
+    #[tokio::test]
+    async fn test_admin_tasks_authorized() {
+        let (router, task) = setup_router();
+        let (status, body) = get_tasks(&router, Some(ADMIN_KEY)).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let resp: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let tasks = resp["tasks"].as_array().unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0]["id"], task.id.to_string());
+        assert_eq!(tasks[0]["name"], task.name);
+        assert_eq!(tasks[0]["state"], task.state);
+        assert_eq!(tasks[0]["wallet_ids"][0], task.wallet_ids[0].to_string());
+        assert_eq!(tasks[0]["start_time"], task.start_time);
+    }
+
+    /// Tests that listing tasks without the admin key is rejected
+    #[tokio::test]
+    async fn test_admin_tasks_unauthorized() {
+        let (router, _) = setup_router();
+
+        let (status, _) = get_tasks(&router, None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let (status, _) = get_tasks(&router, Some("wrong-key")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,95 @@
+//! Groups the metrics scrape handler
+
+use async_trait::async_trait;
+use external_api::{http::GetMatchVolumeResponse, EmptyRequestResponse};
+use hyper::{header::CONTENT_TYPE, Body, HeaderMap, Request, Response};
+use renegade_metrics::{match_volume::MATCH_VOLUMES, registry::RELAYER_METRICS};
+use state::State;
+use tracing::error;
+
+use crate::{
+    error::ApiServerError,
+    router::{build_500_response, Handler, TypedHandler, UrlParams},
+};
+
+// ---------------
+// | HTTP Routes |
+// ---------------
+
+/// Prometheus scrape route
+pub(super) const METRICS_ROUTE: &str = "/metrics";
+/// Returns the volume this node has matched on each pair, an admin route
+pub(super) const GET_MATCH_VOLUME_ROUTE: &str = "/v0/metrics/match-volume";
+
+/// The content type of the Prometheus text exposition format
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+// ------------------
+// | Route Handlers |
+// ------------------
+
+/// Handler for the metrics route, renders the relayer's metrics registry in
+/// the Prometheus text exposition format
+///
+/// This implements `Handler` directly as the response is not JSON
+#[derive(Clone)]
+pub(crate) struct MetricsHandler {
+    /// A copy of the relayer-global state
+    state: State,
+}
+
+impl MetricsHandler {
+    /// Constructor
+    pub fn new(state: State) -> Self {
+        Self { state }
+    }
+}
+
+#[async_trait]
+impl Handler for MetricsHandler {
+    async fn handle(&self, _req: Request<Body>, _url_params: UrlParams) -> Response<Body> {
+        // Gauges derived from the global state are sampled at scrape time
+        match self.state.get_locally_matchable_orders() {
+            Ok(orders) => RELAYER_METRICS.active_orders.set(orders.len() as i64),
+            Err(e) => {
+                error!("error sampling active orders: {e}");
+                return build_500_response(e.to_string());
+            },
+        }
+
+        Response::builder()
+            .header(CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)
+            .body(Body::from(RELAYER_METRICS.render()))
+            .unwrap()
+    }
+}
+
+/// Handler for the GET "/v0/metrics/match-volume" route
+///
+/// The volumes are read from the node's in-memory `MATCH_VOLUMES` ledger.
+/// They cover only the matches settled by this node, not the cluster or the
+/// network, and reset to zero when the node restarts
+#[derive(Clone, Debug, Default)]
+pub(crate) struct GetMatchVolumeHandler;
+
+impl GetMatchVolumeHandler {
+    /// Constructor
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl TypedHandler for GetMatchVolumeHandler {
+    type Request = EmptyRequestResponse;
+    type Response = GetMatchVolumeResponse;
+
+    async fn handle_typed(
+        &self,
+        _headers: HeaderMap,
+        _req: Self::Request,
+        _params: UrlParams,
+    ) -> Result<Self::Response, ApiServerError> {
+        Ok(GetMatchVolumeResponse { volumes: MATCH_VOLUMES.volumes() })
+    }
+}
@@ -1,41 +1,59 @@
 //! Groups wallet API handlers and definitions
 
+use std::collections::HashMap;
+
 use async_trait::async_trait;
 use circuit_types::{
-    balance::Balance, native_helpers::create_wallet_shares_from_private, order::Order,
+    balance::Balance, native_helpers::create_wallet_shares_from_private, order::Order, Amount,
     SizedWallet as SizedCircuitWallet,
 };
 use common::types::{
+    network_order::CancellationReason,
     tasks::{
-        LookupWalletTaskDescriptor, NewWalletTaskDescriptor, TaskDescriptor, TaskIdentifier,
+        verify_update_nonce_signature, verify_wallet_update_signature, LookupWalletTaskDescriptor,
+        NewWalletTaskDescriptor, TaskDescriptor, TaskIdentifier, UpdateNonceAuth,
         UpdateWalletTaskDescriptor,
     },
+    token::Token,
     transfer_auth::{DepositAuth, ExternalTransferWithAuth, WithdrawalAuth},
-    wallet::{KeyChain, Wallet, WalletIdentifier},
+    wallet::{KeyChain, KeyScope, ScopedKey, Wallet, WalletIdentifier},
 };
+use constants::Scalar;
 use external_api::{
     http::wallet::{
-        CancelOrderRequest, CancelOrderResponse, CreateOrderRequest, CreateOrderResponse,
-        CreateWalletRequest, CreateWalletResponse, DepositBalanceRequest, DepositBalanceResponse,
-        FindWalletRequest, FindWalletResponse, GetBalanceByMintResponse, GetBalancesResponse,
-        GetOrderByIdResponse, GetOrdersResponse, GetWalletResponse, UpdateOrderRequest,
-        UpdateOrderResponse, WithdrawBalanceRequest, WithdrawBalanceResponse,
+        CancelAllOrdersRequest, CancelAllOrdersResponse, CancelOrderRequest, CancelOrderResponse,
+        CreateOrderRequest, CreateOrderResponse, CreateWalletRequest, CreateWalletResponse,
+        DepositBalanceRequest, DepositBalanceResponse, FindWalletRequest, FindWalletResponse,
+        GetBalanceByMintResponse, GetBalancesResponse, GetOrderByIdResponse, GetOrderStatsResponse,
+        GetOrdersResponse, GetWalletResponse, GrantScopedKeyRequest, RemoveBalanceRequest,
+        RemoveBalanceResponse, RevokeScopedKeyRequest, RotateSeedResponse, UpdateOrderRequest,
+        UpdateOrderResponse, WalletUpdateDelta, WithdrawBalanceRequest, WithdrawBalanceResponse,
     },
-    types::ApiOrder,
+    types::{ApiBalance, ApiOrder},
     EmptyRequestResponse,
 };
 use hyper::HeaderMap;
+use itertools::Itertools;
+use job_types::handshake_manager::OrderMatchStatsLedger;
 use num_traits::ToPrimitive;
+use rand::thread_rng;
 use renegade_crypto::fields::biguint_to_scalar;
 use state::State;
-use util::{err_str, hex::jubjub_to_hex_string};
+use util::{
+    err_str, get_current_time_millis,
+    hex::{jubjub_to_hex_string, public_sign_key_from_hex_string, scalar_to_hex_string},
+};
 
 use crate::{
-    error::{bad_request, internal_error, not_found, ApiServerError},
+    auth::{ERR_KEY_EXPIRED, ERR_KEY_OUT_OF_SCOPE},
+    error::{bad_request, forbidden, internal_error, not_found, unauthorized, ApiServerError},
     router::{TypedHandler, UrlParams, ERR_WALLET_NOT_FOUND},
 };
 
-use super::{parse_mint_from_params, parse_order_id_from_params, parse_wallet_id_from_params};
+use super::{
+    parse_decimals_from_params, parse_mint_from_params, parse_order_id_from_params,
+    parse_wallet_id_from_params,
+};
 
 // -----------
 // | Helpers |
@@ -43,7 +61,8 @@ use super::{parse_mint_from_params, parse_order_id_from_params, parse_wallet_id_
 
 /// Find the wallet for the given id in the global state
 ///
-/// Attempts to acquire the lock for an update on the wallet
+/// No lock is taken on the wallet; concurrent updates are serialized by the
+/// wallet's task queue
 fn find_wallet_for_update(
     wallet_id: WalletIdentifier,
     state: &State,
@@ -52,6 +71,99 @@ fn find_wallet_for_update(
     state.get_wallet(&wallet_id)?.ok_or_else(|| not_found(ERR_WALLET_NOT_FOUND.to_string()))
 }
 
+/// Check that an update changes the wallet, so that no proof is generated for
+/// a no-op update
+fn ensure_wallet_changed(old_wallet: &Wallet, new_wallet: &Wallet) -> Result<(), ApiServerError> {
+    if old_wallet.diff(new_wallet).is_empty() {
+        return Err(bad_request(ERR_NO_WALLET_CHANGES.to_string()));
+    }
+
+    Ok(())
+}
+
+/// Resolve the statement signature and nonce authorization of a wallet
+/// update into ones by the wallet's root key
+///
+/// The contract verifies the statement signature against the root key, so a
+/// root signature is passed through unchanged. A signature by an unexpired
+/// scoped key granted `scope` is accepted in its place, and the relayer signs
+/// on the key's behalf, which requires it to hold the root key. A withdrawal's
+/// transfer signature is not re-signed; its destination must still be
+/// authorized by the root key
+fn authorize_update(
+    old_wallet: &Wallet,
+    new_wallet: &Wallet,
+    statement_sig: Vec<u8>,
+    nonce_auth: Option<UpdateNonceAuth>,
+    scope: KeyScope,
+) -> Result<(Vec<u8>, Option<UpdateNonceAuth>), ApiServerError> {
+    let pk_root = &old_wallet.key_chain.public_keys.pk_root;
+    if verify_wallet_update_signature(new_wallet, pk_root, &statement_sig).is_ok() {
+        return Ok((statement_sig, nonce_auth));
+    }
+
+    // An update signed by neither the root key nor a scoped key is rejected
+    // with the root key's verification error by the task constructor
+    let signer = old_wallet
+        .key_chain
+        .scoped_keys
+        .iter()
+        .find(|k| verify_wallet_update_signature(new_wallet, &k.key, &statement_sig).is_ok());
+    let signer = match signer {
+        Some(key) => key,
+        None => return Ok((statement_sig, nonce_auth)),
+    };
+
+    if signer.is_expired(get_current_time_millis() as u64) {
+        return Err(unauthorized(ERR_KEY_EXPIRED.to_string()));
+    }
+    if !signer.has_scope(scope) {
+        return Err(forbidden(ERR_KEY_OUT_OF_SCOPE.to_string()));
+    }
+    if let Some(auth) = nonce_auth.as_ref() {
+        verify_update_nonce_signature(new_wallet, &signer.key, auth).map_err(bad_request)?;
+    }
+
+    // Sign the update with the root key on the scoped key's behalf
+    let comm = new_wallet.get_wallet_share_commitment();
+    let statement_sig = old_wallet
+        .sign_commitment(comm)
+        .map_err(|_| forbidden(ERR_DELEGATION_UNSUPPORTED.to_string()))?
+        .to_vec();
+    let nonce_auth = nonce_auth
+        .map(|auth| {
+            let signature = old_wallet.sign_update_nonce(comm, auth.nonce)?.to_vec();
+            Ok(UpdateNonceAuth { nonce: auth.nonce, signature })
+        })
+        .transpose()
+        .map_err(|_: String| forbidden(ERR_DELEGATION_UNSUPPORTED.to_string()))?;
+
+    Ok((statement_sig, nonce_auth))
+}
+
+/// Check that an order's amount is within the configured limits for its base
+/// token
+fn validate_order_amount(
+    order: &Order,
+    min_order_amounts: &HashMap<Token, Amount>,
+    max_order_amounts: &HashMap<Token, Amount>,
+) -> Result<(), ApiServerError> {
+    let base = Token::from_addr_biguint(&order.base_mint);
+    if let Some(min) = min_order_amounts.get(&base)
+        && order.amount < *min
+    {
+        return Err(bad_request(format!("{ERR_ORDER_BELOW_MIN}: {min}")));
+    }
+
+    if let Some(max) = max_order_amounts.get(&base)
+        && order.amount > *max
+    {
+        return Err(bad_request(format!("{ERR_ORDER_ABOVE_MAX}: {max}")));
+    }
+
+    Ok(())
+}
+
 /// Append a task to a task queue and await consensus on this queue update
 async fn append_task_and_await(
     task: TaskDescriptor,
@@ -73,14 +185,24 @@ pub(super) const CREATE_WALLET_ROUTE: &str = "/v0/wallet";
 pub(super) const FIND_WALLET_ROUTE: &str = "/v0/wallet/lookup";
 /// Returns the wallet information for the given id
 pub(super) const GET_WALLET_ROUTE: &str = "/v0/wallet/:wallet_id";
+/// Rotates the CSPRNG seeds of a wallet's shares and blinder
+pub(super) const ROTATE_SEED_ROUTE: &str = "/v0/wallet/:wallet_id/rotate-seed";
+/// Grants a scoped key on a wallet
+pub(super) const GRANT_SCOPED_KEY_ROUTE: &str = "/v0/wallet/:wallet_id/scoped-keys/grant";
+/// Revokes a scoped key on a wallet
+pub(super) const REVOKE_SCOPED_KEY_ROUTE: &str = "/v0/wallet/:wallet_id/scoped-keys/revoke";
 /// Route to the orders of a given wallet
 pub(super) const WALLET_ORDERS_ROUTE: &str = "/v0/wallet/:wallet_id/orders";
 /// Returns a single order by the given identifier
 pub(super) const GET_ORDER_BY_ID_ROUTE: &str = "/v0/wallet/:wallet_id/orders/:order_id";
+/// Returns the match statistics of a given order
+pub(super) const GET_ORDER_STATS_ROUTE: &str = "/v0/wallet/:wallet_id/orders/:order_id/stats";
 /// Updates a given order
 pub(super) const UPDATE_ORDER_ROUTE: &str = "/v0/wallet/:wallet_id/orders/:order_id/update";
 /// Cancels a given order
 pub(super) const CANCEL_ORDER_ROUTE: &str = "/v0/wallet/:wallet_id/orders/:order_id/cancel";
+/// Route to cancel all orders in a wallet
+pub(super) const CANCEL_ALL_ORDERS_ROUTE: &str = "/v0/wallet/:wallet_id/orders/cancel-all";
 /// Returns the balances within a given wallet
 pub(super) const GET_BALANCES_ROUTE: &str = "/v0/wallet/:wallet_id/balances";
 /// Returns the balance associated with the given mint
@@ -89,6 +211,8 @@ pub(super) const GET_BALANCE_BY_MINT_ROUTE: &str = "/v0/wallet/:wallet_id/balanc
 pub(super) const DEPOSIT_BALANCE_ROUTE: &str = "/v0/wallet/:wallet_id/balances/deposit";
 /// Withdraws an ERC-20 token from the darkpool
 pub(super) const WITHDRAW_BALANCE_ROUTE: &str = "/v0/wallet/:wallet_id/balances/:mint/withdraw";
+/// Removes a zero balance from a wallet
+pub(super) const REMOVE_BALANCE_ROUTE: &str = "/v0/wallet/:wallet_id/balances/:mint/remove";
 
 // ------------------
 // | Error Messages |
@@ -99,6 +223,24 @@ pub(super) const WITHDRAW_BALANCE_ROUTE: &str = "/v0/wallet/:wallet_id/balances/
 const ERR_INSUFFICIENT_BALANCE: &str = "insufficient balance";
 /// Error message displayed when a given order cannot be found
 const ERR_ORDER_NOT_FOUND: &str = "order not found";
+/// Error message displayed when a wallet has no orders to cancel
+const ERR_NO_ORDERS_TO_CANCEL: &str = "wallet has no orders to cancel";
+/// Error message displayed when an update would not change the wallet
+const ERR_NO_WALLET_CHANGES: &str = "update does not change the wallet";
+/// Error message displayed when a given balance cannot be found
+const ERR_BALANCE_NOT_FOUND: &str = "balance not found";
+/// Error message displayed when removing a balance that is not zero
+const ERR_BALANCE_NOT_ZERO: &str = "only a zero balance may be removed";
+/// Error message displayed when an order is below the minimum amount for its
+/// base token
+const ERR_ORDER_BELOW_MIN: &str = "order amount below minimum";
+/// Error message displayed when an order is above the maximum amount for its
+/// base token
+const ERR_ORDER_ABOVE_MAX: &str = "order amount above maximum";
+/// Error message displayed when a scoped key signs an update for a wallet
+/// whose root key the relayer does not hold
+const ERR_DELEGATION_UNSUPPORTED: &str =
+    "scoped keys may only update wallets whose root key the relayer holds";
 
 // -------------------------
 // | Wallet Route Handlers |
@@ -164,6 +306,10 @@ impl TypedHandler for CreateWalletHandler {
         mut req: Self::Request,
         _params: UrlParams,
     ) -> Result<Self::Response, ApiServerError> {
+        // Check capacity before the wallet is committed on-chain, after which the
+        // node must index it
+        self.global_state.check_wallet_capacity(&req.wallet.id)?;
+
         // Overwrite the managing cluster and the match fee with the configured values
         let relayer_key = self.global_state.get_fee_decryption_key()?.public_key();
         let relayer_take_rate = self.global_state.get_relayer_take_rate()?;
@@ -217,6 +363,8 @@ impl TypedHandler for FindWalletHandler {
         req: Self::Request,
         _params: UrlParams,
     ) -> Result<Self::Response, ApiServerError> {
+        self.global_state.check_wallet_capacity(&req.wallet_id)?;
+
         // Create a task in thew driver to find and prove validity for
         // the wallet
         let key_chain: KeyChain =
@@ -235,6 +383,131 @@ impl TypedHandler for FindWalletHandler {
     }
 }
 
+/// Handler for the POST /wallet/:id/rotate-seed route
+///
+/// The update is signed with the wallet's `sk_root`, so seeds may only be
+/// rotated for wallets whose root key is held by the relayer
+pub struct RotateSeedHandler {
+    /// A copy of the relayer-global state
+    global_state: State,
+}
+
+impl RotateSeedHandler {
+    /// Constructor
+    pub fn new(global_state: State) -> Self {
+        Self { global_state }
+    }
+}
+
+#[async_trait]
+impl TypedHandler for RotateSeedHandler {
+    type Request = EmptyRequestResponse;
+    type Response = RotateSeedResponse;
+
+    async fn handle_typed(
+        &self,
+        _headers: HeaderMap,
+        _req: Self::Request,
+        params: UrlParams,
+    ) -> Result<Self::Response, ApiServerError> {
+        let wallet_id = parse_wallet_id_from_params(&params)?;
+        let old_wallet = find_wallet_for_update(wallet_id, &self.global_state)?;
+
+        // Reblind the wallet from freshly sampled seeds, leaving its contents unchanged
+        let mut rng = thread_rng();
+        let (share_seed, blinder_seed) = (Scalar::random(&mut rng), Scalar::random(&mut rng));
+        let mut new_wallet = old_wallet.clone();
+        new_wallet.reblind_wallet_from_seeds(share_seed, blinder_seed);
+
+        let comm = new_wallet.get_wallet_share_commitment();
+        let statement_sig = old_wallet.sign_commitment(comm).map_err(bad_request)?.to_vec();
+        let task = UpdateWalletTaskDescriptor::new(
+            None, // transfer
+            old_wallet,
+            new_wallet,
+            statement_sig,
+            None, // nonce_auth
+        )
+        .map_err(bad_request)?;
+
+        // Propose the task and await for it to be enqueued
+        let task_id = append_task_and_await(task.into(), &self.global_state).await?;
+        Ok(RotateSeedResponse {
+            task_id,
+            share_seed: scalar_to_hex_string(&share_seed),
+            blinder_seed: scalar_to_hex_string(&blinder_seed),
+        })
+    }
+}
+
+/// Handler for the POST /wallet/:id/scoped-keys/grant route
+pub struct GrantScopedKeyHandler {
+    /// A copy of the relayer-global state
+    global_state: State,
+}
+
+impl GrantScopedKeyHandler {
+    /// Constructor
+    pub fn new(global_state: State) -> Self {
+        Self { global_state }
+    }
+}
+
+#[async_trait]
+impl TypedHandler for GrantScopedKeyHandler {
+    type Request = GrantScopedKeyRequest;
+    type Response = EmptyRequestResponse;
+
+    async fn handle_typed(
+        &self,
+        _headers: HeaderMap,
+        req: Self::Request,
+        params: UrlParams,
+    ) -> Result<Self::Response, ApiServerError> {
+        let wallet_id = parse_wallet_id_from_params(&params)?;
+        let key = ScopedKey::try_from(req.key).map_err(bad_request)?;
+        find_wallet_for_update(wallet_id, &self.global_state)?;
+
+        let waiter = self.global_state.grant_scoped_key(wallet_id, key)?;
+        waiter.await.map_err(err_str!(internal_error))?;
+        Ok(EmptyRequestResponse {})
+    }
+}
+
+/// Handler for the POST /wallet/:id/scoped-keys/revoke route
+pub struct RevokeScopedKeyHandler {
+    /// A copy of the relayer-global state
+    global_state: State,
+}
+
+impl RevokeScopedKeyHandler {
+    /// Constructor
+    pub fn new(global_state: State) -> Self {
+        Self { global_state }
+    }
+}
+
+#[async_trait]
+impl TypedHandler for RevokeScopedKeyHandler {
+    type Request = RevokeScopedKeyRequest;
+    type Response = EmptyRequestResponse;
+
+    async fn handle_typed(
+        &self,
+        _headers: HeaderMap,
+        req: Self::Request,
+        params: UrlParams,
+    ) -> Result<Self::Response, ApiServerError> {
+        let wallet_id = parse_wallet_id_from_params(&params)?;
+        let key = public_sign_key_from_hex_string(&req.key).map_err(bad_request)?;
+        find_wallet_for_update(wallet_id, &self.global_state)?;
+
+        let waiter = self.global_state.revoke_scoped_key(wallet_id, key)?;
+        waiter.await.map_err(err_str!(internal_error))?;
+        Ok(EmptyRequestResponse {})
+    }
+}
+
 // -------------------------
 // | Orders Route Handlers |
 // -------------------------
@@ -271,7 +544,13 @@ impl TypedHandler for GetOrdersHandler {
             .ok_or_else(|| not_found(ERR_WALLET_NOT_FOUND.to_string()))?;
 
         wallet.remove_default_elements();
-        let orders = wallet.orders.into_iter().map(ApiOrder::from).collect();
+        let with_decimals = parse_decimals_from_params(&params);
+        let orders = wallet
+            .orders
+            .into_iter()
+            .map(ApiOrder::from)
+            .map(|order| if with_decimals { order.with_decimals() } else { order })
+            .collect();
         Ok(GetOrdersResponse { orders })
     }
 }
@@ -311,23 +590,80 @@ impl TypedHandler for GetOrderByIdHandler {
             .ok_or_else(|| not_found(ERR_WALLET_NOT_FOUND.to_string()))?;
 
         if let Some(order) = wallet.orders.get(&order_id).cloned() {
-            Ok(GetOrderByIdResponse { order: (order_id, order).into() })
+            let mut order: ApiOrder = (order_id, order).into();
+            if parse_decimals_from_params(&params) {
+                order = order.with_decimals();
+            }
+
+            Ok(GetOrderByIdResponse { order })
         } else {
             Err(not_found(ERR_ORDER_NOT_FOUND.to_string()))
         }
     }
 }
 
+/// Handler for the GET /wallet/:id/orders/:id/stats route
+#[derive(Clone)]
+pub struct GetOrderStatsHandler {
+    /// A copy of the relayer-global state
+    pub global_state: State,
+    /// The match statistics of each locally managed order
+    pub order_stats: OrderMatchStatsLedger,
+}
+
+impl GetOrderStatsHandler {
+    /// Constructor
+    pub fn new(global_state: State, order_stats: OrderMatchStatsLedger) -> Self {
+        Self { global_state, order_stats }
+    }
+}
+
+#[async_trait]
+impl TypedHandler for GetOrderStatsHandler {
+    type Request = EmptyRequestResponse;
+    type Response = GetOrderStatsResponse;
+
+    async fn handle_typed(
+        &self,
+        _headers: HeaderMap,
+        _req: Self::Request,
+        params: UrlParams,
+    ) -> Result<Self::Response, ApiServerError> {
+        let wallet_id = parse_wallet_id_from_params(&params)?;
+        let order_id = parse_order_id_from_params(&params)?;
+
+        let wallet = self
+            .global_state
+            .get_wallet(&wallet_id)?
+            .ok_or_else(|| not_found(ERR_WALLET_NOT_FOUND.to_string()))?;
+        if !wallet.orders.contains_key(&order_id) {
+            return Err(not_found(ERR_ORDER_NOT_FOUND.to_string()));
+        }
+
+        // An order the handshake manager has not yet attempted to match has no stats
+        let stats = self.order_stats.read().unwrap().peek(&order_id).copied().unwrap_or_default();
+        Ok(GetOrderStatsResponse { stats })
+    }
+}
+
 /// Handler for the POST /wallet/:id/orders route
 pub struct CreateOrderHandler {
     /// A copy of the relayer-global state
     global_state: State,
+    /// The minimum base amount of a new order, keyed by base token
+    min_order_amounts: HashMap<Token, Amount>,
+    /// The maximum base amount of a new order, keyed by base token
+    max_order_amounts: HashMap<Token, Amount>,
 }
 
 impl CreateOrderHandler {
     /// Constructor
-    pub fn new(global_state: State) -> Self {
-        Self { global_state }
+    pub fn new(
+        global_state: State,
+        min_order_amounts: HashMap<Token, Amount>,
+        max_order_amounts: HashMap<Token, Amount>,
+    ) -> Self {
+        Self { global_state, min_order_amounts, max_order_amounts }
     }
 }
 
@@ -344,27 +680,37 @@ impl TypedHandler for CreateOrderHandler {
     ) -> Result<Self::Response, ApiServerError> {
         let id = req.order.id;
         let wallet_id = parse_wallet_id_from_params(&params)?;
+        let new_order: Order = req.order.into();
+        validate_order_amount(&new_order, &self.min_order_amounts, &self.max_order_amounts)?;
 
         // Lookup the wallet in the global state
         let old_wallet = find_wallet_for_update(wallet_id, &self.global_state)?;
         let mut new_wallet = old_wallet.clone();
-        let new_order: Order = req.order.into();
 
         // Check that the timestamp is not too old, then add to the wallet
         new_wallet.add_order(id, new_order).map_err(bad_request)?;
         new_wallet.reblind_wallet();
 
+        let delta = WalletUpdateDelta::new(&old_wallet, &new_wallet);
+        let (statement_sig, nonce_auth) = authorize_update(
+            &old_wallet,
+            &new_wallet,
+            req.statement_sig,
+            req.nonce_auth,
+            KeyScope::PlaceOrders,
+        )?;
         let task = UpdateWalletTaskDescriptor::new(
             None, // transfer
             old_wallet,
             new_wallet,
-            req.statement_sig,
+            statement_sig,
+            nonce_auth,
         )
         .map_err(bad_request)?;
 
         // Propose the task and await for it to be enqueued
         let task_id = append_task_and_await(task.into(), &self.global_state).await?;
-        Ok(CreateOrderResponse { id, task_id })
+        Ok(CreateOrderResponse { id, task_id, delta })
     }
 }
 
@@ -372,12 +718,20 @@ impl TypedHandler for CreateOrderHandler {
 pub struct UpdateOrderHandler {
     /// A copy of the relayer-global state
     global_state: State,
+    /// The minimum base amount of an updated order, keyed by base token
+    min_order_amounts: HashMap<Token, Amount>,
+    /// The maximum base amount of an updated order, keyed by base token
+    max_order_amounts: HashMap<Token, Amount>,
 }
 
 impl UpdateOrderHandler {
     /// Constructor
-    pub fn new(global_state: State) -> Self {
-        Self { global_state }
+    pub fn new(
+        global_state: State,
+        min_order_amounts: HashMap<Token, Amount>,
+        max_order_amounts: HashMap<Token, Amount>,
+    ) -> Self {
+        Self { global_state, min_order_amounts, max_order_amounts }
     }
 }
 
@@ -394,6 +748,8 @@ impl TypedHandler for UpdateOrderHandler {
     ) -> Result<Self::Response, ApiServerError> {
         let wallet_id = parse_wallet_id_from_params(&params)?;
         let order_id = parse_order_id_from_params(&params)?;
+        let new_order: Order = req.order.into();
+        validate_order_amount(&new_order, &self.min_order_amounts, &self.max_order_amounts)?;
 
         // Lookup the wallet in the global state
         let old_wallet = find_wallet_for_update(wallet_id, &self.global_state)?;
@@ -401,8 +757,6 @@ impl TypedHandler for UpdateOrderHandler {
         // Pop the old order and replace it with a new one
         let mut new_wallet = old_wallet.clone();
 
-        let new_order: Order = req.order.into();
-
         // We edit the value of the underlying map in-place (as opposed to `pop` and
         // `insert`) to maintain ordering of the orders. This is important for
         // the circuit, which relies on the order of the orders to be consistent
@@ -412,19 +766,29 @@ impl TypedHandler for UpdateOrderHandler {
             .get_mut(&order_id)
             .ok_or_else(|| not_found(ERR_ORDER_NOT_FOUND.to_string()))?;
         *order = new_order;
+        ensure_wallet_changed(&old_wallet, &new_wallet)?;
         new_wallet.reblind_wallet();
 
+        let delta = WalletUpdateDelta::new(&old_wallet, &new_wallet);
+        let (statement_sig, nonce_auth) = authorize_update(
+            &old_wallet,
+            &new_wallet,
+            req.statement_sig,
+            req.nonce_auth,
+            KeyScope::PlaceOrders,
+        )?;
         let task = UpdateWalletTaskDescriptor::new(
             None, // transfer
             old_wallet,
             new_wallet,
-            req.statement_sig,
+            statement_sig,
+            nonce_auth,
         )
         .map_err(bad_request)?;
 
         // Propose the task and await for it to be enqueued
         let task_id = append_task_and_await(task.into(), &self.global_state).await?;
-        Ok(UpdateOrderResponse { task_id })
+        Ok(UpdateOrderResponse { task_id, delta })
     }
 }
 
@@ -466,17 +830,97 @@ impl TypedHandler for CancelOrderHandler {
             .ok_or_else(|| not_found(ERR_ORDER_NOT_FOUND.to_string()))?;
         new_wallet.reblind_wallet();
 
+        let delta = WalletUpdateDelta::new(&old_wallet, &new_wallet);
+        let (statement_sig, nonce_auth) = authorize_update(
+            &old_wallet,
+            &new_wallet,
+            req.statement_sig,
+            req.nonce_auth,
+            KeyScope::CancelOrders,
+        )?;
         let task = UpdateWalletTaskDescriptor::new(
             None, // transfer
             old_wallet,
             new_wallet,
+            statement_sig,
+            nonce_auth,
+        )
+        .map_err(bad_request)?
+        .with_cancelled_orders([order_id], CancellationReason::UserCancelled);
+
+        // Propose the task and await for it to be enqueued
+        let task_id = append_task_and_await(task.into(), &self.global_state).await?;
+        Ok(CancelOrderResponse { task_id, order: (order_id, order).into(), delta })
+    }
+}
+
+/// Handler for the POST /wallet/:id/orders/cancel-all route
+pub struct CancelAllOrdersHandler {
+    /// A copy of the relayer-global state
+    global_state: State,
+}
+
+impl CancelAllOrdersHandler {
+    /// Constructor
+    pub fn new(global_state: State) -> Self {
+        Self { global_state }
+    }
+}
+
+#[async_trait]
+impl TypedHandler for CancelAllOrdersHandler {
+    type Request = CancelAllOrdersRequest;
+    type Response = CancelAllOrdersResponse;
+
+    async fn handle_typed(
+        &self,
+        _headers: HeaderMap,
+        req: Self::Request,
+        params: UrlParams,
+    ) -> Result<Self::Response, ApiServerError> {
+        let wallet_id = parse_wallet_id_from_params(&params)?;
+
+        // Lookup the wallet in the global state
+        let old_wallet = find_wallet_for_update(wallet_id, &self.global_state)?;
+
+        // Remove all non-default orders from the new wallet in a single update
+        let mut new_wallet = old_wallet.clone();
+        let order_ids = new_wallet
+            .orders
+            .iter()
+            .filter(|(_, order)| !order.is_default())
+            .map(|(id, _)| *id)
+            .collect_vec();
+        if order_ids.is_empty() {
+            return Err(bad_request(ERR_NO_ORDERS_TO_CANCEL.to_string()));
+        }
+
+        for order_id in order_ids.iter() {
+            new_wallet.orders.remove(order_id);
+        }
+        new_wallet.reblind_wallet();
+
+        let delta = WalletUpdateDelta::new(&old_wallet, &new_wallet);
+        let (statement_sig, nonce_auth) = authorize_update(
+            &old_wallet,
+            &new_wallet,
             req.statement_sig,
+            req.nonce_auth,
+            KeyScope::CancelOrders,
+        )?;
+        let task = UpdateWalletTaskDescriptor::new(
+            None, // transfer
+            old_wallet,
+            new_wallet,
+            statement_sig,
+            nonce_auth,
         )
-        .map_err(bad_request)?;
+        .map_err(bad_request)?
+        .with_cancelled_orders(order_ids.clone(), CancellationReason::UserCancelled);
 
         // Propose the task and await for it to be enqueued
         let task_id = append_task_and_await(task.into(), &self.global_state).await?;
-        Ok(CancelOrderResponse { task_id, order: (order_id, order).into() })
+        Ok(CancelAllOrdersResponse { task_id, order_ids, delta })
     }
 }
 
@@ -513,7 +957,14 @@ impl TypedHandler for GetBalancesHandler {
         if let Some(mut wallet) = self.global_state.get_wallet(&wallet_id)? {
             // Filter out the default balances used to pad the wallet to the circuit size
             wallet.remove_default_elements();
-            let balances = wallet.get_balances_list().to_vec();
+            let with_decimals = parse_decimals_from_params(&params);
+            let balances = wallet
+                .get_balances_list()
+                .iter()
+                .cloned()
+                .map(ApiBalance::from)
+                .map(|balance| if with_decimals { balance.with_decimals() } else { balance })
+                .collect();
 
             Ok(GetBalancesResponse { balances })
         } else {
@@ -553,6 +1004,11 @@ impl TypedHandler for GetBalanceByMintHandler {
         if let Some(wallet) = self.global_state.get_wallet(&wallet_id)? {
             let balance =
                 wallet.get_balance(&mint).cloned().unwrap_or_else(|| Balance::new_from_mint(mint));
+            let mut balance = ApiBalance::from(balance);
+            if parse_decimals_from_params(&params) {
+                balance = balance.with_decimals();
+            }
+
             Ok(GetBalanceByMintResponse { balance })
         } else {
             Err(not_found(ERR_WALLET_NOT_FOUND.to_string()))
@@ -596,6 +1052,7 @@ impl TypedHandler for DepositBalanceHandler {
         let bal = Balance::new_from_mint_and_amount(req.mint.clone(), amount);
 
         new_wallet.add_balance(bal).map_err(bad_request)?;
+        ensure_wallet_changed(&old_wallet, &new_wallet)?;
         new_wallet.reblind_wallet();
 
         let deposit_with_auth = ExternalTransferWithAuth::deposit(
@@ -609,17 +1066,26 @@ impl TypedHandler for DepositBalanceHandler {
             },
         );
 
+        let delta = WalletUpdateDelta::new(&old_wallet, &new_wallet);
+        let (statement_sig, nonce_auth) = authorize_update(
+            &old_wallet,
+            &new_wallet,
+            req.wallet_commitment_sig,
+            req.nonce_auth,
+            KeyScope::Deposit,
+        )?;
         let task = UpdateWalletTaskDescriptor::new(
             Some(deposit_with_auth),
             old_wallet,
             new_wallet,
-            req.wallet_commitment_sig,
+            statement_sig,
+            nonce_auth,
         )
         .map_err(bad_request)?;
 
         // Propose the task and await for it to be enqueued
         let task_id = append_task_and_await(task.into(), &self.global_state).await?;
-        Ok(DepositBalanceResponse { task_id })
+        Ok(DepositBalanceResponse { task_id, delta })
     }
 }
 
@@ -658,13 +1124,30 @@ impl TypedHandler for WithdrawBalanceHandler {
         let withdrawal_amount = req.amount.to_u128().unwrap();
 
         let mut new_wallet = old_wallet.clone();
-        if let Some(balance) = new_wallet.balances.get_mut(&mint)
-            && balance.amount >= withdrawal_amount
-        {
-            balance.amount -= withdrawal_amount;
+        let remaining = match new_wallet.balances.get_mut(&mint) {
+            Some(balance) if balance.amount >= withdrawal_amount => {
+                balance.amount -= withdrawal_amount;
+                balance.amount
+            },
+            _ => return Err(bad_request(ERR_INSUFFICIENT_BALANCE.to_string())),
+        };
+        ensure_wallet_changed(&old_wallet, &new_wallet)?;
+
+        // Withdrawing the full balance cancels the orders it funds
+        let unfunded = if remaining == 0 {
+            let ids = new_wallet
+                .orders
+                .iter()
+                .filter(|(_, order)| order.send_mint() == &mint)
+                .map(|(id, _)| *id)
+                .collect_vec();
+            for id in ids.iter() {
+                new_wallet.orders.remove(id);
+            }
+            ids
         } else {
-            return Err(bad_request(ERR_INSUFFICIENT_BALANCE.to_string()));
-        }
+            Vec::new()
+        };
         new_wallet.reblind_wallet();
 
         let withdrawal_with_auth = ExternalTransferWithAuth::withdrawal(
@@ -674,16 +1157,681 @@ impl TypedHandler for WithdrawBalanceHandler {
             WithdrawalAuth { external_transfer_signature: req.external_transfer_sig },
         );
 
+        let delta = WalletUpdateDelta::new(&old_wallet, &new_wallet);
+        let (statement_sig, nonce_auth) = authorize_update(
+            &old_wallet,
+            &new_wallet,
+            req.wallet_commitment_sig,
+            req.nonce_auth,
+            KeyScope::Withdraw,
+        )?;
         let task = UpdateWalletTaskDescriptor::new(
             Some(withdrawal_with_auth),
             old_wallet,
             new_wallet,
-            req.wallet_commitment_sig,
+            statement_sig,
+            nonce_auth,
+        )
+        .map_err(bad_request)?
+        .with_cancelled_orders(unfunded, CancellationReason::InsufficientBalance);
+
+        // Propose the task and await for it to be enqueued
+        let task_id = append_task_and_await(task.into(), &self.global_state).await?;
+        Ok(WithdrawBalanceResponse { task_id, delta })
+    }
+}
+
+/// Handler for the POST /wallet/:id/balances/:mint/remove route
+pub struct RemoveBalanceHandler {
+    /// A copy of the relayer-global state
+    global_state: State,
+}
+
+impl RemoveBalanceHandler {
+    /// Constructor
+    pub fn new(global_state: State) -> Self {
+        Self { global_state }
+    }
+}
+
+#[async_trait]
+impl TypedHandler for RemoveBalanceHandler {
+    type Request = RemoveBalanceRequest;
+    type Response = RemoveBalanceResponse;
+
+    async fn handle_typed(
+        &self,
+        _headers: HeaderMap,
+        req: Self::Request,
+        params: UrlParams,
+    ) -> Result<Self::Response, ApiServerError> {
+        // Parse the wallet ID and mint from the params
+        let wallet_id = parse_wallet_id_from_params(&params)?;
+        let mint = parse_mint_from_params(&params)?;
+
+        // Lookup the wallet in the global state
+        let old_wallet = find_wallet_for_update(wallet_id, &self.global_state)?;
+
+        // Only a zero balance may be removed, the slot is replaced by a default balance
+        let balance = old_wallet
+            .get_balance(&mint)
+            .ok_or_else(|| not_found(ERR_BALANCE_NOT_FOUND.to_string()))?;
+        if !balance.is_zero() {
+            return Err(bad_request(ERR_BALANCE_NOT_ZERO.to_string()));
+        }
+
+        let mut new_wallet = old_wallet.clone();
+        new_wallet.remove_balance(&mint);
+        ensure_wallet_changed(&old_wallet, &new_wallet)?;
+        new_wallet.reblind_wallet();
+
+        let delta = WalletUpdateDelta::new(&old_wallet, &new_wallet);
+        let (statement_sig, nonce_auth) = authorize_update(
+            &old_wallet,
+            &new_wallet,
+            req.statement_sig,
+            req.nonce_auth,
+            KeyScope::Withdraw,
+        )?;
+        let task = UpdateWalletTaskDescriptor::new(
+            None, // transfer
+            old_wallet,
+            new_wallet,
+            statement_sig,
+            nonce_auth,
         )
         .map_err(bad_request)?;
 
         // Propose the task and await for it to be enqueued
         let task_id = append_task_and_await(task.into(), &self.global_state).await?;
-        Ok(WithdrawBalanceResponse { task_id })
+        Ok(RemoveBalanceResponse { task_id, delta })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use circuit_types::{
+        balance::Balance,
+        order::{Order, OrderSide},
+        Amount,
+    };
+    use common::types::network_order::CancellationReason;
+    use common::types::tasks::{verify_wallet_update_signature, TaskDescriptor};
+    use common::types::{
+        token::Token,
+        wallet::{KeyScope, OrderIdentifier, Wallet},
+        wallet_mocks::{mock_empty_wallet, mock_order},
+    };
+    use external_api::{
+        http::wallet::{
+            CancelAllOrdersRequest, CancelOrderRequest, CreateOrderRequest, CreateOrderResponse,
+            CreateWalletRequest, DepositBalanceRequest, FindWalletRequest, GrantScopedKeyRequest,
+            RemoveBalanceRequest, RemoveBalanceResponse, RevokeScopedKeyRequest,
+            UpdateOrderRequest, WithdrawBalanceRequest,
+        },
+        types::ApiScopedKey,
+        EmptyRequestResponse,
+    };
+    use hyper::{HeaderMap, StatusCode};
+    use itertools::Itertools;
+    use num_bigint::BigUint;
+    use num_traits::Num;
+    use state::{
+        error::StateError,
+        test_helpers::{mock_state, mock_state_config, mock_state_with_config},
+        State,
+    };
+    use util::hex::public_sign_key_to_hex_string;
+
+    use crate::{
+        error::ApiServerError,
+        http::{DECIMALS_QUERY_PARAM, MINT_URL_PARAM, ORDER_ID_URL_PARAM, WALLET_ID_URL_PARAM},
+        router::{TypedHandler, UrlParams},
+    };
+
+    use super::{
+        CancelAllOrdersHandler, CancelOrderHandler, CreateOrderHandler, CreateWalletHandler,
+        DepositBalanceHandler, FindWalletHandler, GetBalancesHandler, GrantScopedKeyHandler,
+        RemoveBalanceHandler, RevokeScopedKeyHandler, RotateSeedHandler, UpdateOrderHandler,
+        WithdrawBalanceHandler,
+    };
+
+    /// The minimum order amount configured in tests
+    const MIN_ORDER_AMOUNT: Amount = 5;
+    /// The maximum order amount configured in tests
+    const MAX_ORDER_AMOUNT: Amount = 100;
+
+    /// Add a wallet holding a balance of the given amount to the state, then
+    /// request removal of the balance
+    async fn remove_balance(
+        state: &State,
+        amount: u128,
+    ) -> (Wallet, Result<RemoveBalanceResponse, ApiServerError>) {
+        let mint = BigUint::from(1u8);
+        let mut wallet = mock_empty_wallet();
+        wallet.add_balance(Balance::new_from_mint_and_amount(mint.clone(), amount)).unwrap();
+        state.new_wallet(wallet.clone()).unwrap().await.unwrap();
+
+        // Sign the wallet the relayer is expected to construct
+        let mut new_wallet = wallet.clone();
+        new_wallet.remove_balance(&mint);
+        new_wallet.reblind_wallet();
+        let comm = new_wallet.get_wallet_share_commitment();
+        let statement_sig = wallet.sign_commitment(comm).unwrap().to_vec();
+
+        let mut params = UrlParams::new();
+        params.insert(WALLET_ID_URL_PARAM.to_string(), wallet.wallet_id.to_string());
+        params.insert(MINT_URL_PARAM.to_string(), format!("{mint:#x}"));
+        let handler = RemoveBalanceHandler::new(state.clone());
+        let req = RemoveBalanceRequest { statement_sig, nonce_auth: None };
+        let res = handler.handle_typed(HeaderMap::new(), req, params);
+
+        (wallet, res.await)
+    }
+
+    /// Add an empty wallet to the state, then request creation of an order of
+    /// the given amount under the test order amount limits
+    async fn create_order(
+        state: &State,
+        amount: Amount,
+    ) -> (Wallet, Result<CreateOrderResponse, ApiServerError>) {
+        let wallet = mock_empty_wallet();
+        state.new_wallet(wallet.clone()).unwrap().await.unwrap();
+
+        let id = OrderIdentifier::new_v4();
+        let order = Order { amount, ..mock_order() };
+        let base = Token::from_addr_biguint(&order.base_mint);
+        let min_amounts = HashMap::from([(base.clone(), MIN_ORDER_AMOUNT)]);
+        let max_amounts = HashMap::from([(base, MAX_ORDER_AMOUNT)]);
+
+        // Sign the wallet the relayer is expected to construct
+        let mut new_wallet = wallet.clone();
+        new_wallet.add_order(id, order.clone()).unwrap();
+        new_wallet.reblind_wallet();
+        let comm = new_wallet.get_wallet_share_commitment();
+        let statement_sig = wallet.sign_commitment(comm).unwrap().to_vec();
+
+        let mut params = UrlParams::new();
+        params.insert(WALLET_ID_URL_PARAM.to_string(), wallet.wallet_id.to_string());
+        let req = CreateOrderRequest { order: (id, order).into(), statement_sig, nonce_auth: None };
+        let handler = CreateOrderHandler::new(state.clone(), min_amounts, max_amounts);
+        let res = handler.handle_typed(HeaderMap::new(), req, params);
+
+        (wallet, res.await)
+    }
+
+    /// Request creation of an order in the given wallet, signed by the root
+    /// key of `signer`
+    async fn create_order_signed_by(
+        state: &State,
+        wallet: &Wallet,
+        signer: &Wallet,
+    ) -> Result<CreateOrderResponse, ApiServerError> {
+        let id = OrderIdentifier::new_v4();
+        let mut new_wallet = wallet.clone();
+        new_wallet.add_order(id, mock_order()).unwrap();
+        new_wallet.reblind_wallet();
+        let comm = new_wallet.get_wallet_share_commitment();
+        let statement_sig = signer.sign_commitment(comm).unwrap().to_vec();
+
+        let mut params = UrlParams::new();
+        params.insert(WALLET_ID_URL_PARAM.to_string(), wallet.wallet_id.to_string());
+        let req = CreateOrderRequest {
+            order: (id, mock_order()).into(),
+            statement_sig,
+            nonce_auth: None,
+        };
+        let handler = CreateOrderHandler::new(state.clone(), HashMap::new(), HashMap::new());
+        handler.handle_typed(HeaderMap::new(), req, params).await
+    }
+
+    /// Tests that updates signed by a scoped key are accepted only on the
+    /// routes the key is granted, and rejected once the key is revoked
+    #[tokio::test]
+    async fn test_scoped_key_updates() {
+        let state = mock_state();
+        let mut wallet = mock_empty_wallet();
+        let order_id = OrderIdentifier::new_v4();
+        wallet.add_order(order_id, mock_order()).unwrap();
+        wallet.reblind_wallet();
+        state.new_wallet(wallet.clone()).unwrap().await.unwrap();
+
+        // Grant a key that may only place orders
+        let delegate = mock_empty_wallet();
+        let delegate_key = public_sign_key_to_hex_string(&delegate.key_chain.public_keys.pk_root);
+        let key = ApiScopedKey {
+            key: delegate_key.clone(),
+            scopes: vec![KeyScope::PlaceOrders],
+            expiry: None,
+        };
+        let mut params = UrlParams::new();
+        params.insert(WALLET_ID_URL_PARAM.to_string(), wallet.wallet_id.to_string());
+        let grant = GrantScopedKeyHandler::new(state.clone());
+        grant
+            .handle_typed(HeaderMap::new(), GrantScopedKeyRequest { key }, params.clone())
+            .await
+            .unwrap();
+
+        // The key may place an order, which the relayer signs with the root key
+        create_order_signed_by(&state, &wallet, &delegate).await.unwrap();
+        let tasks = state.get_queued_tasks(&wallet.wallet_id).unwrap();
+        let desc = match &tasks[0].descriptor {
+            TaskDescriptor::UpdateWallet(desc) => desc,
+            desc => panic!("expected a wallet update, got {desc:?}"),
+        };
+        let pk_root = &wallet.key_chain.public_keys.pk_root;
+        verify_wallet_update_signature(&desc.new_wallet, pk_root, &desc.wallet_update_signature)
+            .unwrap();
+
+        // The key may not cancel an order
+        let mut new_wallet = wallet.clone();
+        new_wallet.orders.remove(&order_id);
+        new_wallet.reblind_wallet();
+        let comm = new_wallet.get_wallet_share_commitment();
+        let statement_sig = delegate.sign_commitment(comm).unwrap().to_vec();
+        let mut cancel_params = params.clone();
+        cancel_params.insert(ORDER_ID_URL_PARAM.to_string(), order_id.to_string());
+        let cancel = CancelOrderHandler::new(state.clone());
+        let req = CancelOrderRequest { statement_sig, nonce_auth: None };
+        let res = cancel.handle_typed(HeaderMap::new(), req, cancel_params).await;
+        assert!(matches!(res, Err(ApiServerError::HttpStatusCode(StatusCode::FORBIDDEN, _))));
+
+        // Once revoked, the key may no longer place orders
+        let revoke = RevokeScopedKeyHandler::new(state.clone());
+        let req = RevokeScopedKeyRequest { key: delegate_key };
+        revoke.handle_typed(HeaderMap::new(), req, params).await.unwrap();
+
+        let res = create_order_signed_by(&state, &wallet, &delegate).await;
+        assert!(matches!(res, Err(ApiServerError::HttpStatusCode(StatusCode::BAD_REQUEST, _))));
+        assert_eq!(state.get_queued_tasks(&wallet.wallet_id).unwrap().len(), 1);
+    }
+
+    /// Tests that an order within the configured limits is accepted
+    #[tokio::test]
+    async fn test_create_order_in_range() {
+        let state = mock_state();
+        let (wallet, res) = create_order(&state, MIN_ORDER_AMOUNT).await;
+
+        let resp = res.unwrap();
+        let tasks = state.get_queued_tasks(&wallet.wallet_id).unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].id, resp.task_id);
+    }
+
+    /// Tests that an order below the minimum amount is rejected
+    #[tokio::test]
+    async fn test_create_order_below_min() {
+        let state = mock_state();
+        let (wallet, res) = create_order(&state, MIN_ORDER_AMOUNT - 1).await;
+
+        match res {
+            Err(ApiServerError::HttpStatusCode(StatusCode::BAD_REQUEST, msg)) => {
+                assert!(msg.contains(&MIN_ORDER_AMOUNT.to_string()))
+            },
+            _ => panic!("expected a bad request"),
+        }
+        assert!(state.get_queued_tasks(&wallet.wallet_id).unwrap().is_empty());
+    }
+
+    /// Tests that an order above the maximum amount is rejected
+    #[tokio::test]
+    async fn test_create_order_above_max() {
+        let state = mock_state();
+        let (wallet, res) = create_order(&state, MAX_ORDER_AMOUNT + 1).await;
+
+        match res {
+            Err(ApiServerError::HttpStatusCode(StatusCode::BAD_REQUEST, msg)) => {
+                assert!(msg.contains(&MAX_ORDER_AMOUNT.to_string()))
+            },
+            _ => panic!("expected a bad request"),
+        }
+        assert!(state.get_queued_tasks(&wallet.wallet_id).unwrap().is_empty());
+    }
+
+    /// Tests that the delta of a single-balance deposit holds only the updated
+    /// balance
+    #[tokio::test]
+    async fn test_deposit_delta() {
+        let state = mock_state();
+        let (mint, other_mint) = (BigUint::from(1u8), BigUint::from(2u8));
+
+        // Add a wallet holding an order and two balances to the state
+        let mut wallet = mock_empty_wallet();
+        wallet.add_order(OrderIdentifier::new_v4(), mock_order()).unwrap();
+        wallet.add_balance(Balance::new_from_mint_and_amount(mint.clone(), 10)).unwrap();
+        wallet.add_balance(Balance::new_from_mint_and_amount(other_mint, 10)).unwrap();
+        wallet.reblind_wallet();
+        state.new_wallet(wallet.clone()).unwrap().await.unwrap();
+
+        // Sign the wallet the relayer is expected to construct
+        let mut new_wallet = wallet.clone();
+        new_wallet.add_balance(Balance::new_from_mint_and_amount(mint.clone(), 5)).unwrap();
+        new_wallet.reblind_wallet();
+        let comm = new_wallet.get_wallet_share_commitment();
+        let wallet_commitment_sig = wallet.sign_commitment(comm).unwrap().to_vec();
+
+        let req = DepositBalanceRequest {
+            from_addr: BigUint::from(3u8),
+            mint: mint.clone(),
+            amount: BigUint::from(5u8),
+            wallet_commitment_sig,
+            nonce_auth: None,
+            permit_nonce: BigUint::from(0u8),
+            permit_deadline: BigUint::from(0u8),
+            permit_signature: vec![],
+        };
+        let mut params = UrlParams::new();
+        params.insert(WALLET_ID_URL_PARAM.to_string(), wallet.wallet_id.to_string());
+        let handler = DepositBalanceHandler::new(state.clone());
+        let resp = handler.handle_typed(HeaderMap::new(), req, params).await.unwrap();
+
+        // Only the deposited balance appears in the delta
+        let delta = resp.delta;
+        assert!(delta.updated_orders.is_empty());
+        assert!(delta.removed_orders.is_empty());
+        assert!(delta.removed_balances.is_empty());
+        assert_eq!(delta.updated_balances.len(), 1);
+        let updated_balance = Balance::from(delta.updated_balances[0].clone());
+        assert_eq!(updated_balance, Balance::new_from_mint_and_amount(mint, 15));
+    }
+
+    /// Tests cancelling all orders of a multi-order wallet in a single update
+    #[tokio::test]
+    async fn test_cancel_all_orders() {
+        let state = mock_state();
+
+        // Add a wallet with several orders to the state
+        let mut wallet = mock_empty_wallet();
+        let order_ids = (0..3).map(|_| OrderIdentifier::new_v4()).collect_vec();
+        for id in order_ids.iter() {
+            wallet.add_order(*id, mock_order()).unwrap();
+        }
+        wallet.reblind_wallet();
+        state.new_wallet(wallet.clone()).unwrap().await.unwrap();
+
+        // Sign the wallet the relayer is expected to construct
+        let mut new_wallet = wallet.clone();
+        for id in order_ids.iter() {
+            new_wallet.orders.remove(id);
+        }
+        new_wallet.reblind_wallet();
+        let comm = new_wallet.get_wallet_share_commitment();
+        let statement_sig = wallet.sign_commitment(comm).unwrap().to_vec();
+
+        // Cancel all orders
+        let mut params = UrlParams::new();
+        params.insert(WALLET_ID_URL_PARAM.to_string(), wallet.wallet_id.to_string());
+        let handler = CancelAllOrdersHandler::new(state.clone());
+        let resp = handler
+            .handle_typed(
+                HeaderMap::new(),
+                CancelAllOrdersRequest { statement_sig, nonce_auth: None },
+                params,
+            )
+            .await
+            .unwrap();
+
+        // A single task should be enqueued for the wallet
+        assert_eq!(resp.order_ids, order_ids);
+        let tasks = state.get_queued_tasks(&wallet.wallet_id).unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].id, resp.task_id);
+
+        // Each order is cancelled at the user's request
+        let cancelled = match &tasks[0].descriptor {
+            TaskDescriptor::UpdateWallet(desc) => desc.cancelled_orders.clone(),
+            desc => panic!("expected a wallet update, got {desc:?}"),
+        };
+        let expected = order_ids.iter().map(|id| (*id, CancellationReason::UserCancelled));
+        assert_eq!(cancelled, expected.collect_vec());
+    }
+
+    /// Tests that withdrawing the full balance of a mint cancels the orders it
+    /// funds, and only those orders
+    #[tokio::test]
+    async fn test_full_withdrawal_cancels_funded_orders() {
+        let state = mock_state();
+
+        // Add a wallet with a buy order funded by its quote balance, and a sell
+        // order on the same pair
+        let buy = Order { side: OrderSide::Buy, ..mock_order() };
+        let sell = Order { side: OrderSide::Sell, ..buy.clone() };
+        let (buy_id, sell_id) = (OrderIdentifier::new_v4(), OrderIdentifier::new_v4());
+        let mint = buy.quote_mint.clone();
+
+        let mut wallet = mock_empty_wallet();
+        wallet.add_order(buy_id, buy).unwrap();
+        wallet.add_order(sell_id, sell).unwrap();
+        wallet.add_balance(Balance::new_from_mint_and_amount(mint.clone(), 10)).unwrap();
+        wallet.reblind_wallet();
+        state.new_wallet(wallet.clone()).unwrap().await.unwrap();
+
+        // Sign the wallet the relayer is expected to construct
+        let mut new_wallet = wallet.clone();
+        new_wallet.balances.get_mut(&mint).unwrap().amount = 0;
+        new_wallet.orders.remove(&buy_id);
+        new_wallet.reblind_wallet();
+        let comm = new_wallet.get_wallet_share_commitment();
+        let wallet_commitment_sig = wallet.sign_commitment(comm).unwrap().to_vec();
+
+        let req = WithdrawBalanceRequest {
+            destination_addr: BigUint::from(3u8),
+            amount: BigUint::from(10u8),
+            wallet_commitment_sig,
+            nonce_auth: None,
+            external_transfer_sig: vec![],
+        };
+        let mut params = UrlParams::new();
+        params.insert(WALLET_ID_URL_PARAM.to_string(), wallet.wallet_id.to_string());
+        params.insert(MINT_URL_PARAM.to_string(), format!("{mint:#x}"));
+        let handler = WithdrawBalanceHandler::new(state.clone());
+        handler.handle_typed(HeaderMap::new(), req, params).await.unwrap();
+
+        // The funded order is cancelled for lack of balance, the other is kept
+        let tasks = state.get_queued_tasks(&wallet.wallet_id).unwrap();
+        assert_eq!(tasks.len(), 1);
+        let desc = match &tasks[0].descriptor {
+            TaskDescriptor::UpdateWallet(desc) => desc,
+            desc => panic!("expected a wallet update, got {desc:?}"),
+        };
+        assert_eq!(desc.cancelled_orders, vec![(buy_id, CancellationReason::InsufficientBalance)]);
+        assert!(!desc.new_wallet.orders.contains_key(&buy_id));
+        assert!(desc.new_wallet.orders.contains_key(&sell_id));
+    }
+
+    /// Tests that rotating a wallet's seeds reblinds the wallet without
+    /// changing its balances or orders
+    #[tokio::test]
+    async fn test_rotate_seed() {
+        let state = mock_state();
+
+        let mut wallet = mock_empty_wallet();
+        let balance = Balance::new_from_mint_and_amount(BigUint::from(1u8), 10);
+        wallet.add_balance(balance).unwrap();
+        wallet.add_order(OrderIdentifier::new_v4(), mock_order()).unwrap();
+        wallet.reblind_wallet();
+        state.new_wallet(wallet.clone()).unwrap().await.unwrap();
+
+        let mut params = UrlParams::new();
+        params.insert(WALLET_ID_URL_PARAM.to_string(), wallet.wallet_id.to_string());
+        let handler = RotateSeedHandler::new(state.clone());
+        let resp =
+            handler.handle_typed(HeaderMap::new(), EmptyRequestResponse {}, params).await.unwrap();
+
+        // The enqueued update preserves the wallet's contents under a new blinder
+        let tasks = state.get_queued_tasks(&wallet.wallet_id).unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].id, resp.task_id);
+
+        let new_wallet = match &tasks[0].descriptor {
+            TaskDescriptor::UpdateWallet(desc) => desc.new_wallet.clone(),
+            desc => panic!("expected a wallet update, got {desc:?}"),
+        };
+        assert_eq!(new_wallet.get_balances_list(), wallet.get_balances_list());
+        assert_eq!(new_wallet.get_orders_list(), wallet.get_orders_list());
+        assert_ne!(new_wallet.blinder, wallet.blinder);
+        assert!(new_wallet.check_wallet_shares());
+    }
+
+    /// Tests that an order update that does not change the wallet is rejected
+    /// without enqueuing a task
+    #[tokio::test]
+    async fn test_update_order_no_changes() {
+        let state = mock_state();
+
+        let mut wallet = mock_empty_wallet();
+        let order_id = OrderIdentifier::new_v4();
+        let order = mock_order();
+        wallet.add_order(order_id, order.clone()).unwrap();
+        state.new_wallet(wallet.clone()).unwrap().await.unwrap();
+
+        // Update the order to its current value
+        let mut params = UrlParams::new();
+        params.insert(WALLET_ID_URL_PARAM.to_string(), wallet.wallet_id.to_string());
+        params.insert(ORDER_ID_URL_PARAM.to_string(), order_id.to_string());
+        let req = UpdateOrderRequest {
+            order: (order_id, order).into(),
+            statement_sig: vec![],
+            nonce_auth: None,
+        };
+        let handler = UpdateOrderHandler::new(state.clone(), HashMap::new(), HashMap::new());
+        let res = handler.handle_typed(HeaderMap::new(), req, params).await;
+
+        assert!(matches!(res, Err(ApiServerError::HttpStatusCode(StatusCode::BAD_REQUEST, _))));
+        assert!(state.get_queued_tasks(&wallet.wallet_id).unwrap().is_empty());
+    }
+
+    /// Tests that updating an order to an amount below the minimum is rejected
+    /// without enqueuing a task
+    #[tokio::test]
+    async fn test_update_order_below_min() {
+        let state = mock_state();
+
+        let mut wallet = mock_empty_wallet();
+        let order_id = OrderIdentifier::new_v4();
+        let order = Order { amount: MIN_ORDER_AMOUNT, ..mock_order() };
+        wallet.add_order(order_id, order.clone()).unwrap();
+        state.new_wallet(wallet.clone()).unwrap().await.unwrap();
+
+        // Sign the wallet the relayer would construct for the update
+        let updated = Order { amount: MIN_ORDER_AMOUNT - 1, ..order };
+        let mut new_wallet = wallet.clone();
+        *new_wallet.orders.get_mut(&order_id).unwrap() = updated.clone();
+        new_wallet.reblind_wallet();
+        let comm = new_wallet.get_wallet_share_commitment();
+        let statement_sig = wallet.sign_commitment(comm).unwrap().to_vec();
+
+        let base = Token::from_addr_biguint(&updated.base_mint);
+        let min_amounts = HashMap::from([(base, MIN_ORDER_AMOUNT)]);
+        let mut params = UrlParams::new();
+        params.insert(WALLET_ID_URL_PARAM.to_string(), wallet.wallet_id.to_string());
+        params.insert(ORDER_ID_URL_PARAM.to_string(), order_id.to_string());
+        let req = UpdateOrderRequest {
+            order: (order_id, updated).into(),
+            statement_sig,
+            nonce_auth: None,
+        };
+        let handler = UpdateOrderHandler::new(state.clone(), min_amounts, HashMap::new());
+        let res = handler.handle_typed(HeaderMap::new(), req, params).await;
+
+        match res {
+            Err(ApiServerError::HttpStatusCode(StatusCode::BAD_REQUEST, msg)) => {
+                assert!(msg.contains(&MIN_ORDER_AMOUNT.to_string()))
+            },
+            _ => panic!("expected a bad request"),
+        }
+        assert!(state.get_queued_tasks(&wallet.wallet_id).unwrap().is_empty());
+    }
+
+    /// Tests removing a zero balance from a wallet
+    #[tokio::test]
+    async fn test_remove_zero_balance() {
+        let state = mock_state();
+        let (wallet, res) = remove_balance(&state, 0 /* amount */).await;
+
+        let resp = res.unwrap();
+        let tasks = state.get_queued_tasks(&wallet.wallet_id).unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].id, resp.task_id);
+    }
+
+    /// Tests that removing a non-zero balance is rejected
+    #[tokio::test]
+    async fn test_remove_non_zero_balance() {
+        let state = mock_state();
+        let (wallet, res) = remove_balance(&state, 10 /* amount */).await;
+
+        assert!(matches!(res, Err(ApiServerError::HttpStatusCode(StatusCode::BAD_REQUEST, _))));
+        assert!(state.get_queued_tasks(&wallet.wallet_id).unwrap().is_empty());
+    }
+
+    /// Tests annotating balances with their token's decimals
+    #[tokio::test]
+    async fn test_get_balances_with_decimals() {
+        // WETH, 18 decimals
+        let mint = BigUint::from_str_radix(
+            "c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2",
+            16, // radix
+        )
+        .unwrap();
+        let amount = 1_500_000_000_000_000_000; // 1.5 WETH
+
+        let state = mock_state();
+        let mut wallet = mock_empty_wallet();
+        wallet.add_balance(Balance::new_from_mint_and_amount(mint, amount)).unwrap();
+        state.new_wallet(wallet.clone()).unwrap().await.unwrap();
+
+        let handler = GetBalancesHandler::new(state.clone());
+        let mut params = UrlParams::new();
+        params.insert(WALLET_ID_URL_PARAM.to_string(), wallet.wallet_id.to_string());
+
+        // Raw amounts are returned by default
+        let res = handler
+            .handle_typed(HeaderMap::new(), EmptyRequestResponse {}, params.clone())
+            .await
+            .unwrap();
+        assert_eq!(res.balances[0].amount, amount);
+        assert!(res.balances[0].decimals.is_none());
+        assert!(res.balances[0].amount_decimal.is_none());
+
+        // Request decimals
+        params.insert(DECIMALS_QUERY_PARAM.to_string(), "true".to_string());
+        let res =
+            handler.handle_typed(HeaderMap::new(), EmptyRequestResponse {}, params).await.unwrap();
+        assert_eq!(res.balances[0].amount, amount);
+        assert_eq!(res.balances[0].decimals, Some(18));
+        assert_eq!(res.balances[0].amount_decimal.as_deref(), Some("1.5"));
+    }
+
+    /// Tests that wallet creation and lookup are rejected before a task is
+    /// enqueued once the node manages its maximum number of wallets
+    #[tokio::test]
+    async fn test_max_managed_wallets() {
+        let mut config = mock_state_config();
+        config.max_managed_wallets = Some(1);
+        let state = mock_state_with_config(&config);
+        state.new_wallet(mock_empty_wallet()).unwrap().await.unwrap();
+
+        // Creating a new wallet is rejected before it is committed on-chain
+        let wallet = mock_empty_wallet();
+        let req = CreateWalletRequest { wallet: wallet.clone().into() };
+        let handler = CreateWalletHandler::new(state.clone());
+        let res = handler.handle_typed(HeaderMap::new(), req, UrlParams::new()).await;
+        assert!(matches!(res, Err(ApiServerError::State(StateError::MaxManagedWallets(1)))));
+        assert!(state.get_queued_tasks(&wallet.wallet_id).unwrap().is_empty());
+
+        // Looking up an unmanaged wallet is rejected before it is indexed
+        let req = FindWalletRequest {
+            wallet_id: wallet.wallet_id,
+            blinder_seed: BigUint::from(1u8),
+            secret_share_seed: BigUint::from(2u8),
+            key_chain: wallet.key_chain.clone().into(),
+        };
+        let handler = FindWalletHandler::new(state.clone());
+        let res = handler.handle_typed(HeaderMap::new(), req, UrlParams::new()).await;
+        assert!(matches!(res, Err(ApiServerError::State(StateError::MaxManagedWallets(1)))));
+        assert!(state.get_queued_tasks(&wallet.wallet_id).unwrap().is_empty());
     }
 }
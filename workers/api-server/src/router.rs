@@ -3,8 +3,12 @@
 use std::{collections::HashMap, iter};
 
 use async_trait::async_trait;
-use hyper::{body::to_bytes, Body, HeaderMap, Method, Request, Response, StatusCode};
+use common::types::wallet::KeyScope;
+use hyper::{
+    body::to_bytes, header::RETRY_AFTER, Body, HeaderMap, Method, Request, Response, StatusCode,
+};
 use itertools::Itertools;
+use job_types::proof_manager::{proof_queue_depth, ProofManagerQueue};
 use matchit::Router as MatchRouter;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use state::State;
@@ -13,10 +17,15 @@ use tracing::{debug, instrument};
 use crate::error::{bad_request, not_found};
 
 use super::{
-    auth::authenticate_wallet_request, error::ApiServerError, http::parse_wallet_id_from_params,
+    auth::{authenticate_admin_request, authenticate_scoped_wallet_request},
+    error::ApiServerError,
+    http::parse_wallet_id_from_params,
 };
 
 /// A type alias for URL generic params maps, i.e. /path/to/resource/:id
+///
+/// Query params, i.e. /path/to/resource?key=value, are merged into the same
+/// map, but never shadow a path param
 pub(super) type UrlParams = HashMap<String, String>;
 
 /// The maximum time an OPTIONS request to our HTTP API may be cached, we go
@@ -24,6 +33,12 @@ pub(super) type UrlParams = HashMap<String, String>;
 const PREFLIGHT_CACHE_TIME: &str = "7200"; // 2 hours, Chromium max
 /// Error message displayed when a wallet cannot be found in the global state
 pub(super) const ERR_WALLET_NOT_FOUND: &str = "wallet not found";
+/// Error message displayed when a request is shed because the proof queue is
+/// above its high-water mark
+const ERR_PROOF_QUEUE_SATURATED: &str = "proof queue is saturated, retry later";
+/// The number of seconds a client is asked to wait before retrying a request
+/// shed because the proof queue is saturated
+const PROOF_QUEUE_RETRY_AFTER_SECS: &str = "5";
 
 // -----------
 // | Helpers |
@@ -44,6 +59,26 @@ pub(super) fn build_500_response(err: String) -> Response<Body> {
     Response::builder().status(StatusCode::INTERNAL_SERVER_ERROR).body(Body::from(err)).unwrap()
 }
 
+/// Builds an HTTP 503 (Service Unavailable) response asking the client to
+/// retry after the given number of seconds
+pub(super) fn build_503_response(err: String, retry_after_secs: &str) -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::SERVICE_UNAVAILABLE)
+        .header(RETRY_AFTER, retry_after_secs)
+        .body(Body::from(err))
+        .unwrap()
+}
+
+/// Parse the `key=value` pairs of a URL query string
+///
+/// A key given without a value maps to the empty string
+fn parse_query_params(query: &str) -> impl Iterator<Item = (String, String)> + '_ {
+    query.split('&').filter(|pair| !pair.is_empty()).map(|pair| {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        (key.to_string(), value.to_string())
+    })
+}
+
 /// Builds an empty HTTP XXX response
 pub(super) fn build_response_from_status_code(
     status_code: StatusCode,
@@ -141,24 +176,48 @@ impl<
     }
 }
 
+/// The authentication required on a route
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RouteAuth {
+    /// No authentication is required
+    None,
+    /// A signature by the wallet's `sk_root` is required, or by a scoped key
+    /// granted the given scope
+    Wallet(Option<KeyScope>),
+    /// The admin API key is required
+    Admin,
+}
+
 /// Wrapper around a matchit router that allows different HTTP request types to
 /// be matches
 pub struct Router {
     /// The underlying router
     ///
-    /// Holds a tuple of the handler and a boolean indicating whether
-    /// wallet authentication (sk_root) signature is required for the request
-    router: MatchRouter<(Box<dyn Handler>, bool)>,
+    /// Holds a tuple of the handler, the authentication required for the
+    /// request, and whether the route is throttled by the proof queue depth
+    router: MatchRouter<(Box<dyn Handler>, RouteAuth, bool)>,
     /// A copy of the relayer global state, used to lookup wallet keys for
     /// authentication
     global_state: State,
+    /// The token required on admin routes, if admin routes are enabled
+    admin_api_key: Option<String>,
+    /// A sender to the proof manager's queue, used to check the queue depth
+    proof_queue: ProofManagerQueue,
+    /// The proof queue depth above which requests to throttled routes are
+    /// rejected
+    proof_queue_high_water_mark: usize,
 }
 
 impl Router {
     /// Create a new router with no routes established
-    pub fn new(global_state: State) -> Self {
+    pub fn new(
+        global_state: State,
+        admin_api_key: Option<String>,
+        proof_queue: ProofManagerQueue,
+        proof_queue_high_water_mark: usize,
+    ) -> Self {
         let router = MatchRouter::new();
-        Self { router, global_state }
+        Self { router, global_state, admin_api_key, proof_queue, proof_queue_high_water_mark }
     }
 
     /// Helper to build a routable path from a method and a concrete route
@@ -185,12 +244,76 @@ impl Router {
         route: String,
         auth_required: bool,
         handler: H,
+    ) {
+        let auth = if auth_required { RouteAuth::Wallet(None) } else { RouteAuth::None };
+        self.add_route_with_auth(method, route, auth, false /* throttled */, handler);
+    }
+
+    /// Add a route whose handler enqueues proofs to the router
+    ///
+    /// Requests to the route are rejected while the proof queue is above its
+    /// high-water mark
+    pub fn add_throttled_route<H: Handler + 'static>(
+        &mut self,
+        method: &Method,
+        route: String,
+        auth_required: bool,
+        handler: H,
+    ) {
+        let auth = if auth_required { RouteAuth::Wallet(None) } else { RouteAuth::None };
+        self.add_route_with_auth(method, route, auth, true /* throttled */, handler);
+    }
+
+    /// Add a throttled route that a scoped key may authenticate to if it is
+    /// granted the given scope
+    ///
+    /// The wallet's root key may always authenticate to the route
+    pub fn add_scoped_route<H: Handler + 'static>(
+        &mut self,
+        method: &Method,
+        route: String,
+        scope: KeyScope,
+        handler: H,
+    ) {
+        self.add_route_with_auth(
+            method,
+            route,
+            RouteAuth::Wallet(Some(scope)),
+            true, // throttled
+            handler,
+        );
+    }
+
+    /// Add a route to the router that requires the admin API key
+    pub fn add_admin_route<H: Handler + 'static>(
+        &mut self,
+        method: &Method,
+        route: String,
+        handler: H,
+    ) {
+        self.add_route_with_auth(
+            method,
+            route,
+            RouteAuth::Admin,
+            false, // throttled
+            handler,
+        );
+    }
+
+    /// Add a route to the router with the given authentication and throttling
+    fn add_route_with_auth<H: Handler + 'static>(
+        &mut self,
+        method: &Method,
+        route: String,
+        auth: RouteAuth,
+        throttled: bool,
+        handler: H,
     ) {
         debug!("Attached handler to route {route} with method {method}");
         let full_route = Self::create_full_route(method, route);
 
         self.router
-            .insert(full_route, (Box::new(handler), auth_required))
+            .insert(full_route, (Box::new(handler), auth, throttled))
             .expect("error attaching handler to route");
     }
 
@@ -215,7 +338,7 @@ impl Router {
 
             // Dispatch to handler
             if let Ok(matched_path) = self.router.at(&full_route) {
-                let (handler, auth_required) = matched_path.value;
+                let (handler, auth, throttled) = matched_path.value;
                 let params = matched_path.params;
 
                 // Clone the params to take ownership
@@ -223,13 +346,33 @@ impl Router {
                 for (key, value) in params.iter() {
                     params_map.insert(key.to_string(), value.to_string());
                 }
+                for (key, value) in parse_query_params(req.uri().query().unwrap_or_default()) {
+                    params_map.entry(key).or_insert(value);
+                }
 
-                if *auth_required
-                    && let Err(e) = self.check_wallet_auth(&params_map, &mut req).await
-                {
-                    e.into()
+                // Shed load on throttled routes before the proof queue backlog
+                // grows without bound
+                if *throttled && self.proof_queue_saturated() {
+                    build_503_response(
+                        ERR_PROOF_QUEUE_SATURATED.to_string(),
+                        PROOF_QUEUE_RETRY_AFTER_SECS,
+                    )
                 } else {
-                    handler.as_ref().handle(req, params_map).await
+                    let auth_res = match auth {
+                        RouteAuth::None => Ok(()),
+                        RouteAuth::Wallet(scope) => {
+                            self.check_wallet_auth(&params_map, *scope, &mut req).await
+                        },
+                        RouteAuth::Admin => {
+                            authenticate_admin_request(req.headers(), self.admin_api_key.as_deref())
+                        },
+                    };
+
+                    if let Err(e) = auth_res {
+                        e.into()
+                    } else {
+                        handler.as_ref().handle(req, params_map).await
+                    }
                 }
             } else {
                 build_404_response(format!("Route {route} for method {method} not found"))
@@ -241,6 +384,11 @@ impl Router {
         res
     }
 
+    /// Whether the proof queue is above its high-water mark
+    fn proof_queue_saturated(&self) -> bool {
+        proof_queue_depth(&self.proof_queue) > self.proof_queue_high_water_mark
+    }
+
     /// Handle an options request
     fn handle_options_req(&self, route: &str) -> Response<Body> {
         // Get the set of allowed methods for this route
@@ -268,10 +416,12 @@ impl Router {
             .unwrap()
     }
 
-    /// Validate a signature of the request's body by sk_root of the wallet
+    /// Validate a signature of the request's body by sk_root of the wallet, or
+    /// by a key granted the route's scope
     async fn check_wallet_auth(
         &self,
         url_params: &HashMap<String, String>,
+        scope: Option<KeyScope>,
         req: &mut Request<Body>,
     ) -> Result<(), ApiServerError> {
         // Parse the wallet ID from the URL params
@@ -288,11 +438,7 @@ impl Router {
             to_bytes(req.body_mut()).await.map_err(|err| bad_request(err.to_string()))?;
 
         // Authenticated the request
-        authenticate_wallet_request(
-            req.headers(),
-            &req_body,
-            &wallet.key_chain.public_keys.pk_root,
-        )?;
+        authenticate_scoped_wallet_request(req.headers(), &req_body, &wallet.key_chain, scope)?;
 
         // Reconstruct the body, the above manipulation consumed the body from the
         // request object
@@ -300,3 +446,103 @@ impl Router {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::iter;
+
+    use async_trait::async_trait;
+    use circuit_types::{traits::BaseType, wallet::WalletShare};
+    use circuits::zk_circuits::valid_wallet_create::{
+        ValidWalletCreateStatement, ValidWalletCreateWitness,
+    };
+    use constants::Scalar;
+    use external_api::EmptyRequestResponse;
+    use hyper::{header::RETRY_AFTER, Body, HeaderMap, Method, Request, Response, StatusCode};
+    use job_types::proof_manager::{new_proof_manager_queue, ProofJob, ProofManagerJob};
+    use state::test_helpers::mock_state;
+    use tokio::sync::oneshot;
+
+    use crate::error::ApiServerError;
+
+    use super::{Router, TypedHandler, UrlParams, PROOF_QUEUE_RETRY_AFTER_SECS};
+
+    /// A route that enqueues proofs
+    const THROTTLED_ROUTE: &str = "/v0/throttled";
+    /// A route that does not enqueue proofs
+    const UNTHROTTLED_ROUTE: &str = "/v0/unthrottled";
+
+    /// A handler that returns an empty response
+    struct EmptyHandler;
+
+    #[async_trait]
+    impl TypedHandler for EmptyHandler {
+        type Request = EmptyRequestResponse;
+        type Response = EmptyRequestResponse;
+
+        async fn handle_typed(
+            &self,
+            _headers: HeaderMap,
+            _req: Self::Request,
+            _params: UrlParams,
+        ) -> Result<Self::Response, ApiServerError> {
+            Ok(EmptyRequestResponse {})
+        }
+    }
+
+    /// Create a dummy proof job to fill the proof queue with
+    fn mock_proof_job() -> ProofManagerJob {
+        let shares = || WalletShare::from_scalars(&mut iter::repeat(Scalar::zero()));
+        let (response_channel, _) = oneshot::channel();
+
+        ProofManagerJob {
+            type_: ProofJob::ValidWalletCreate {
+                witness: ValidWalletCreateWitness { private_wallet_share: shares() },
+                statement: ValidWalletCreateStatement {
+                    private_shares_commitment: Scalar::zero(),
+                    public_wallet_shares: shares(),
+                },
+            },
+            response_channel,
+        }
+    }
+
+    /// Send a request with an empty body to the router
+    async fn send_req(router: &Router, method: Method, route: &str) -> Response<Body> {
+        router.handle_req(method, route.to_string(), Request::new(Body::empty())).await
+    }
+
+    /// Tests that throttled routes are rejected while the proof queue is above
+    /// its high-water mark
+    #[tokio::test]
+    async fn test_proof_queue_backpressure() {
+        let (proof_queue, proof_recv) = new_proof_manager_queue();
+        let mut router = Router::new(
+            mock_state(),
+            None, // admin_api_key
+            proof_queue.clone(),
+            1, // proof_queue_high_water_mark
+        );
+        router.add_throttled_route(&Method::POST, THROTTLED_ROUTE.to_string(), false, EmptyHandler);
+        router.add_route(&Method::GET, UNTHROTTLED_ROUTE.to_string(), false, EmptyHandler);
+
+        // At the high-water mark requests are still served
+        proof_queue.send(mock_proof_job()).unwrap();
+        let resp = send_req(&router, Method::POST, THROTTLED_ROUTE).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // Above the high-water mark throttled routes are rejected
+        proof_queue.send(mock_proof_job()).unwrap();
+        let resp = send_req(&router, Method::POST, THROTTLED_ROUTE).await;
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(resp.headers()[RETRY_AFTER], PROOF_QUEUE_RETRY_AFTER_SECS);
+
+        let resp = send_req(&router, Method::GET, UNTHROTTLED_ROUTE).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // Once the proof manager drains the queue, requests are served again
+        proof_recv.try_recv().unwrap();
+        let resp = send_req(&router, Method::POST, THROTTLED_ROUTE).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+}
@@ -4,13 +4,15 @@ use std::{net::SocketAddr, sync::Arc};
 
 use constants::{HANDSHAKE_STATUS_TOPIC, ORDER_STATE_CHANGE_TOPIC};
 use external_api::{
-    bus_message::{SystemBusMessage, SystemBusMessageWithTopic, NETWORK_TOPOLOGY_TOPIC},
+    bus_message::{
+        SystemBusMessage, SystemBusMessageWithTopic, NETWORK_TOPOLOGY_TOPIC,
+        PRICE_FEED_STATUS_TOPIC,
+    },
     websocket::{ClientWebsocketMessage, SubscriptionResponse, WebsocketMessage},
 };
 use futures::{stream::SplitSink, SinkExt, StreamExt};
 use hyper::{http::HeaderValue, HeaderMap};
 use matchit::Router;
-use system_bus::TopicReader;
 use tokio::net::{TcpListener, TcpStream};
 use tokio_stream::StreamMap;
 use tokio_tungstenite::{accept_async, WebSocketStream};
@@ -24,8 +26,8 @@ use crate::{
 };
 
 use self::{
-    handler::{DefaultHandler, WebsocketTopicHandler},
-    price_report::PriceReporterHandler,
+    handler::{DefaultHandler, SubscriptionStream, WebsocketTopicHandler},
+    price_report::{BulkPriceReportHandler, PriceReporterHandler},
     task::TaskStatusHandler,
     wallet::WalletTopicHandler,
 };
@@ -60,6 +62,12 @@ const HANDSHAKE_ROUTE: &str = "/v0/handshake";
 const WALLET_ROUTE: &str = "/v0/wallet/:wallet_id";
 /// The price report topic, events about price updates are streamed
 const PRICE_REPORT_ROUTE: &str = "/v0/price_report/:source/:base/:quote";
+/// The bulk price report topic, streams median price updates for a comma
+/// separated list of `<base>-<quote>` pairs
+const BULK_PRICE_REPORT_ROUTE: &str = "/v0/price_reports/:pairs";
+/// The price feed status topic, streams events when a pair's price feeds go
+/// down or recover
+const PRICE_FEED_STATUS_ROUTE: &str = "/v0/price_feed_status";
 /// The order book topic, streams events about known network orders
 const ORDER_BOOK_ROUTE: &str = "/v0/order_book";
 /// The network topic, streams events about network peers
@@ -125,6 +133,29 @@ impl WebsocketServer {
             )
             .unwrap();
 
+        // The "/v0/price_reports/:pairs" route
+        router
+            .insert(
+                BULK_PRICE_REPORT_ROUTE,
+                Box::new(BulkPriceReportHandler::new(
+                    config.price_reporter_work_queue.clone(),
+                    config.system_bus.clone(),
+                )),
+            )
+            .unwrap();
+
+        // The "/v0/price_feed_status" route
+        router
+            .insert(
+                PRICE_FEED_STATUS_ROUTE,
+                Box::new(DefaultHandler::new_with_remap(
+                    false, // authenticated
+                    PRICE_FEED_STATUS_TOPIC.to_string(),
+                    config.system_bus.clone(),
+                )),
+            )
+            .unwrap();
+
         // The "/v0/order_book" route
         router
             .insert(
@@ -207,7 +238,7 @@ impl WebsocketServer {
         // We would prefer it to return `Poll::Pending` in this case, so we
         // enter a dummy stream into the map.
         let dummy_reader = self.config.system_bus.subscribe(DUMMY_SUBSCRIPTION_TOPIC.to_string());
-        subscriptions.insert(DUMMY_SUBSCRIPTION_TOPIC.to_string(), dummy_reader);
+        subscriptions.insert(DUMMY_SUBSCRIPTION_TOPIC.to_string(), dummy_reader.boxed());
 
         // Begin the listener loop
         loop {
@@ -250,7 +281,7 @@ impl WebsocketServer {
     async fn handle_incoming_ws_message(
         &self,
         message: Message,
-        client_subscriptions: &mut StreamMap<String, TopicReader<SystemBusMessage>>,
+        client_subscriptions: &mut StreamMap<String, SubscriptionStream>,
         write_stream: &mut SplitSink<WebSocketStream<TcpStream>, Message>,
     ) -> Result<(), ApiServerError> {
         if let Message::Text(msg_text) = message {
@@ -290,7 +321,7 @@ impl WebsocketServer {
     async fn handle_subscription_message(
         &self,
         message: ClientWebsocketMessage,
-        client_subscriptions: &mut StreamMap<String, TopicReader<SystemBusMessage>>,
+        client_subscriptions: &mut StreamMap<String, SubscriptionStream>,
     ) -> Result<SubscriptionResponse, ApiServerError> {
         // Update local subscriptions
         match message.body {
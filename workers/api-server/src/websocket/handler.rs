@@ -8,10 +8,17 @@
 
 use async_trait::async_trait;
 use external_api::bus_message::SystemBusMessage;
-use system_bus::{SystemBus, TopicReader};
+use futures::stream::{BoxStream, StreamExt};
+use system_bus::SystemBus;
 
 use crate::{error::ApiServerError, router::UrlParams};
 
+/// The stream of system bus messages backing a websocket subscription
+///
+/// A subscription may read from several bus topics, the `TopicReader`s
+/// inside the stream unsubscribe from their topics when it is dropped
+pub type SubscriptionStream = BoxStream<'static, SystemBusMessage>;
+
 /// The main trait that route handlers implement for their topic, handles any
 /// custom logic required to process a websocket subscribe/unsubscribe request
 #[async_trait]
@@ -21,7 +28,7 @@ pub trait WebsocketTopicHandler: Send + Sync {
         &self,
         topic: String,
         route_params: &UrlParams,
-    ) -> Result<TopicReader<SystemBusMessage>, ApiServerError>;
+    ) -> Result<SubscriptionStream, ApiServerError>;
     /// Handle a request to unsubscribe from a topic
     async fn handle_unsubscribe_message(
         &self,
@@ -64,13 +71,13 @@ impl WebsocketTopicHandler for DefaultHandler {
         &self,
         topic: String,
         _route_params: &UrlParams,
-    ) -> Result<TopicReader<SystemBusMessage>, ApiServerError> {
+    ) -> Result<SubscriptionStream, ApiServerError> {
         let bus_topic = match self.topic_remap {
             Some(ref remap) => remap.clone(),
             None => topic,
         };
 
-        Ok(self.system_bus.subscribe(bus_topic))
+        Ok(self.system_bus.subscribe(bus_topic).boxed())
     }
 
     /// Unsubscribe does nothing, `TopicReader`s handle their own cleanup
@@ -3,15 +3,17 @@
 use async_trait::async_trait;
 use common::types::token::Token;
 use external_api::bus_message::{price_report_topic_name, SystemBusMessage};
+use futures::stream::{select_all, StreamExt};
+use itertools::Itertools;
 use job_types::price_reporter::{PriceReporterJob, PriceReporterQueue};
-use system_bus::{SystemBus, TopicReader};
+use system_bus::SystemBus;
 
 use crate::{
     error::{bad_request, ApiServerError},
     router::UrlParams,
 };
 
-use super::handler::WebsocketTopicHandler;
+use super::handler::{SubscriptionStream, WebsocketTopicHandler};
 
 // ------------------
 // | Error Messages |
@@ -21,6 +23,11 @@ use super::handler::WebsocketTopicHandler;
 const ERR_MISSING_PARAMS: &str = "route missing parameters";
 /// The error message given when communication with the price reporter fails
 const ERR_SENDING_MESSAGE: &str = "error sending message to price reporter";
+/// The error message given when a bulk subscription pair is malformed
+const ERR_INVALID_PAIR: &str = "invalid pair, expected <base>-<quote>";
+
+/// The source name under which the price reporter publishes median reports
+const MEDIAN_SOURCE: &str = "median";
 
 // ----------------
 // | URL Captures |
@@ -32,6 +39,9 @@ const PRICE_SOURCE_URL_PARAM: &str = "source";
 const BASE_MINT_URL_PARAM: &str = "base";
 /// The quote mint url param to fetch a price report for
 const QUOTE_MINT_URL_PARAM: &str = "quote";
+/// The comma separated `<base>-<quote>` pairs of a bulk price report
+/// subscription
+const PAIRS_URL_PARAM: &str = "pairs";
 
 /// Parse a price report source from a set of URL params
 fn parse_source_from_url_params(params: &UrlParams) -> Result<String, ApiServerError> {
@@ -57,9 +67,41 @@ fn parse_quote_mint_from_url_params(params: &UrlParams) -> Result<String, ApiSer
         .cloned()
 }
 
-// -----------
-// | Handler |
-// -----------
+/// Parse the distinct pairs of a bulk subscription from a URL param
+fn parse_pairs_from_url_params(params: &UrlParams) -> Result<Vec<(Token, Token)>, ApiServerError> {
+    let pairs = params
+        .get(&PAIRS_URL_PARAM.to_string())
+        .ok_or_else(|| bad_request(ERR_MISSING_PARAMS.to_string()))?;
+
+    pairs
+        .split(',')
+        .map(|pair| {
+            let (base, quote) = pair
+                .split_once('-')
+                .filter(|(base, quote)| !base.is_empty() && !quote.is_empty())
+                .ok_or_else(|| bad_request(format!("{ERR_INVALID_PAIR}: {pair}")))?;
+            Ok((Token::from_addr(base), Token::from_addr(quote)))
+        })
+        .process_results(|pairs| pairs.unique().collect())
+}
+
+/// Start a price reporting stream for the pair in the price reporter
+fn start_price_reporter(
+    price_reporter_work_queue: &PriceReporterQueue,
+    base: &Token,
+    quote: &Token,
+) -> Result<(), ApiServerError> {
+    price_reporter_work_queue
+        .send(PriceReporterJob::StartPriceReporter {
+            base_token: base.clone(),
+            quote_token: quote.clone(),
+        })
+        .map_err(|_| ApiServerError::WebsocketServerFailure(ERR_SENDING_MESSAGE.to_string()))
+}
+
+// ------------
+// | Handlers |
+// ------------
 
 /// The handler that manages a subscription to a price report
 #[derive(Clone)]
@@ -90,21 +132,15 @@ impl WebsocketTopicHandler for PriceReporterHandler {
         &self,
         _topic: String,
         route_params: &UrlParams,
-    ) -> Result<TopicReader<SystemBusMessage>, ApiServerError> {
+    ) -> Result<SubscriptionStream, ApiServerError> {
         // Parse the source, base mint, and quote mint from the route
         let source = parse_source_from_url_params(route_params)?;
         let base = Token::from_addr(&parse_base_mint_from_url_params(route_params)?);
         let quote = Token::from_addr(&parse_quote_mint_from_url_params(route_params)?);
 
         // Start a price reporting stream in the manager
-        self.price_reporter_work_queue
-            .send(PriceReporterJob::StartPriceReporter {
-                base_token: base.clone(),
-                quote_token: quote.clone(),
-            })
-            .map_err(|_| ApiServerError::WebsocketServerFailure(ERR_SENDING_MESSAGE.to_string()))?;
-
-        Ok(self.system_bus.subscribe(price_report_topic_name(&source, &base, &quote)))
+        start_price_reporter(&self.price_reporter_work_queue, &base, &quote)?;
+        Ok(self.system_bus.subscribe(price_report_topic_name(&source, &base, &quote)).boxed())
     }
 
     /// Handle an unsubscribe message from the price reporter
@@ -123,3 +159,134 @@ impl WebsocketTopicHandler for PriceReporterHandler {
         false
     }
 }
+
+/// The handler that manages a subscription to the median price reports of
+/// several pairs over a single topic
+///
+/// Reports from each pair are multiplexed onto the subscription, the pair of a
+/// report is given by its base and quote tokens
+#[derive(Clone)]
+pub struct BulkPriceReportHandler {
+    /// A sender to the price reporter's work queue
+    price_reporter_work_queue: PriceReporterQueue,
+    /// A reference to the relayer-global system bus
+    system_bus: SystemBus<SystemBusMessage>,
+}
+
+impl BulkPriceReportHandler {
+    /// Constructor
+    pub fn new(
+        price_reporter_work_queue: PriceReporterQueue,
+        system_bus: SystemBus<SystemBusMessage>,
+    ) -> Self {
+        Self { price_reporter_work_queue, system_bus }
+    }
+}
+
+#[async_trait]
+impl WebsocketTopicHandler for BulkPriceReportHandler {
+    /// Handle a subscription to the median price reports of a set of pairs
+    ///
+    /// Starts a price reporter for each pair and merges the pairs' topics into
+    /// one stream
+    async fn handle_subscribe_message(
+        &self,
+        _topic: String,
+        route_params: &UrlParams,
+    ) -> Result<SubscriptionStream, ApiServerError> {
+        let pairs = parse_pairs_from_url_params(route_params)?;
+
+        let mut readers = Vec::with_capacity(pairs.len());
+        for (base, quote) in pairs.iter() {
+            start_price_reporter(&self.price_reporter_work_queue, base, quote)?;
+            let topic = price_report_topic_name(MEDIAN_SOURCE, base, quote);
+            readers.push(self.system_bus.subscribe(topic));
+        }
+
+        Ok(select_all(readers).boxed())
+    }
+
+    /// Unsubscribe does nothing, dropping the merged stream drops the reader on
+    /// each pair's topic
+    async fn handle_unsubscribe_message(
+        &self,
+        _topic: String,
+        _route_params: &UrlParams,
+    ) -> Result<(), ApiServerError> {
+        Ok(())
+    }
+
+    fn requires_wallet_auth(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use common::types::{exchange::PriceReport, token::Token};
+    use external_api::bus_message::{price_report_topic_name, SystemBusMessage};
+    use futures::StreamExt;
+    use job_types::price_reporter::{new_price_reporter_queue, PriceReporterJob};
+    use system_bus::SystemBus;
+
+    use crate::{router::UrlParams, websocket::handler::WebsocketTopicHandler};
+
+    use super::{BulkPriceReportHandler, MEDIAN_SOURCE, PAIRS_URL_PARAM};
+
+    /// Tests that a bulk subscription delivers the median reports of each of
+    /// its pairs
+    #[tokio::test]
+    async fn test_bulk_price_report_subscription() {
+        let (queue, mut job_recv) = new_price_reporter_queue();
+        let bus = SystemBus::new();
+        let handler = BulkPriceReportHandler::new(queue, bus.clone());
+
+        let pair1 = (Token::from_addr("0x01"), Token::from_addr("0x02"));
+        let pair2 = (Token::from_addr("0x03"), Token::from_addr("0x02"));
+        let pairs = format!(
+            "{}-{},{}-{}",
+            pair1.0.get_addr(),
+            pair1.1.get_addr(),
+            pair2.0.get_addr(),
+            pair2.1.get_addr()
+        );
+
+        let mut params = UrlParams::new();
+        params.insert(PAIRS_URL_PARAM.to_string(), pairs);
+        let mut stream = handler.handle_subscribe_message(String::new(), &params).await.unwrap();
+
+        // A price reporter is started for each pair
+        for (base, quote) in [&pair1, &pair2] {
+            match job_recv.try_recv().unwrap() {
+                PriceReporterJob::StartPriceReporter { base_token, quote_token } => {
+                    assert_eq!((&base_token, &quote_token), (base, quote))
+                },
+                _ => panic!("expected a price reporter to be started"),
+            }
+        }
+
+        // Publish a median report for each pair, both are delivered on the stream
+        for (base, quote) in [&pair1, &pair2] {
+            let report = PriceReport {
+                base_token: base.clone(),
+                quote_token: quote.clone(),
+                ..Default::default()
+            };
+            let topic = price_report_topic_name(MEDIAN_SOURCE, base, quote);
+            bus.publish(topic, SystemBusMessage::PriceReportMedian(report));
+        }
+
+        let mut delivered = Vec::new();
+        for _ in 0..2 {
+            match stream.next().await.unwrap() {
+                SystemBusMessage::PriceReportMedian(report) => {
+                    delivered.push((report.base_token, report.quote_token))
+                },
+                msg => panic!("unexpected message: {msg:?}"),
+            }
+        }
+
+        assert!(delivered.contains(&pair1));
+        assert!(delivered.contains(&pair2));
+    }
+}
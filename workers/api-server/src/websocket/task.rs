@@ -6,8 +6,9 @@
 
 use async_trait::async_trait;
 use external_api::bus_message::{task_topic_name, SystemBusMessage};
+use futures::StreamExt;
 use state::State;
-use system_bus::{SystemBus, TopicReader};
+use system_bus::SystemBus;
 
 use crate::{
     error::{not_found, ApiServerError},
@@ -15,7 +16,7 @@ use crate::{
     router::UrlParams,
 };
 
-use super::handler::WebsocketTopicHandler;
+use super::handler::{SubscriptionStream, WebsocketTopicHandler};
 
 /// Error displayed when the given task cannot be found
 const ERR_TASK_MISSING: &str = "task not found";
@@ -46,7 +47,7 @@ impl WebsocketTopicHandler for TaskStatusHandler {
         &self,
         _topic: String,
         route_params: &UrlParams,
-    ) -> Result<TopicReader<SystemBusMessage>, ApiServerError> {
+    ) -> Result<SubscriptionStream, ApiServerError> {
         // Parse the task ID from the route params
         let task_id = parse_task_id_from_params(route_params)?;
 
@@ -56,7 +57,7 @@ impl WebsocketTopicHandler for TaskStatusHandler {
         }
 
         // Subscribe to the topic
-        Ok(self.system_bus.subscribe(task_topic_name(&task_id)))
+        Ok(self.system_bus.subscribe(task_topic_name(&task_id)).boxed())
     }
 
     async fn handle_unsubscribe_message(
@@ -1,8 +1,9 @@
 //! Handler definitions for wallet websocket topics
 use async_trait::async_trait;
 use external_api::bus_message::{wallet_topic_name, SystemBusMessage};
+use futures::StreamExt;
 use state::State;
-use system_bus::{SystemBus, TopicReader};
+use system_bus::SystemBus;
 
 use crate::{
     error::{not_found, ApiServerError},
@@ -10,7 +11,7 @@ use crate::{
     router::UrlParams,
 };
 
-use super::handler::WebsocketTopicHandler;
+use super::handler::{SubscriptionStream, WebsocketTopicHandler};
 
 // ------------------
 // | Error Messages |
@@ -46,7 +47,7 @@ impl WebsocketTopicHandler for WalletTopicHandler {
         &self,
         _topic: String,
         route_params: &UrlParams,
-    ) -> Result<TopicReader<SystemBusMessage>, ApiServerError> {
+    ) -> Result<SubscriptionStream, ApiServerError> {
         // Parse the wallet ID from the topic captures
         let wallet_id = parse_wallet_id_from_params(route_params)?;
 
@@ -56,7 +57,7 @@ impl WebsocketTopicHandler for WalletTopicHandler {
         }
 
         // Subscribe to the topic
-        Ok(self.system_bus.subscribe(wallet_topic_name(&wallet_id)))
+        Ok(self.system_bus.subscribe(wallet_topic_name(&wallet_id)).boxed())
     }
 
     /// Does nothing for now, `TopicReader`s clean themselves up
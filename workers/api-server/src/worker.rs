@@ -1,14 +1,24 @@
 //! Defines the implementation of the `Worker` trait for the ApiServer
 
-use common::{types::CancelChannel, worker::Worker};
+use circuit_types::Amount;
+use common::{
+    types::{token::Token, CancelChannel},
+    worker::Worker,
+};
 use external_api::bus_message::SystemBusMessage;
 use futures::executor::block_on;
 use job_types::{
-    network_manager::NetworkManagerQueue, price_reporter::PriceReporterQueue,
+    handshake_manager::OrderMatchStatsLedger,
+    network_manager::NetworkManagerQueue,
+    price_reporter::PriceReporterQueue,
     proof_manager::ProofManagerQueue,
+    task_driver::{MatchGasLedger, TaskRegistry},
 };
 use state::State;
-use std::thread::{self, JoinHandle};
+use std::{
+    collections::HashMap,
+    thread::{self, JoinHandle},
+};
 use system_bus::SystemBus;
 use tokio::{
     runtime::{Builder as TokioBuilder, Runtime},
@@ -49,8 +59,27 @@ pub struct ApiServerConfig {
     pub price_reporter_work_queue: PriceReporterQueue,
     /// The worker job queue for the ProofGenerationManager
     pub proof_generation_work_queue: ProofManagerQueue,
+    /// The proof queue depth above which the API server rejects requests that
+    /// would enqueue more proofs
+    pub proof_queue_high_water_mark: usize,
+    /// The minimum base amount of an order accepted by the API, keyed by base
+    /// token
+    pub min_order_amounts: HashMap<Token, Amount>,
+    /// The maximum base amount of an order accepted by the API, keyed by base
+    /// token
+    pub max_order_amounts: HashMap<Token, Amount>,
     /// The relayer-global state
     pub global_state: State,
+    /// The registry of tasks running in the task driver
+    pub task_registry: TaskRegistry,
+    /// The ledger of gas used by match settlements, written by the task driver
+    pub match_gas_ledger: MatchGasLedger,
+    /// The match statistics of each locally managed order, written by the
+    /// handshake manager
+    pub order_stats: OrderMatchStatsLedger,
+    /// The token required on requests to admin routes, admin routes are
+    /// disabled if this is not set
+    pub admin_api_key: Option<String>,
     /// The system pubsub bus that all workers have access to
     /// The ApiServer uses this bus to forward internal events onto open
     /// websocket connections
@@ -9,6 +9,8 @@
 //!   information about where in the contract history the wallet was last
 //!   updated: potentially down to the exact transaction. So fresh Merkle paths
 //!   give privacy.
+//! - Proof expiry: Order validity proofs built against a root that is about to
+//!   leave the contract's root history are re-proven against a fresh root
 
 #![deny(unsafe_code)]
 #![deny(missing_docs)]
@@ -20,4 +22,5 @@
 
 pub mod error;
 pub mod listener;
+pub mod proof_expiry;
 pub mod worker;
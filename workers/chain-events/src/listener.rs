@@ -1,24 +1,29 @@
 //! Defines the core implementation of the on-chain event listener
 
-use std::{sync::atomic::Ordering, thread::JoinHandle};
+use std::{collections::HashSet, sync::atomic::Ordering, thread::JoinHandle};
 
 use arbitrum_client::{
     abi::{DarkpoolContractEvents, NodeChangedFilter, NullifierSpentFilter},
     client::ArbitrumClient,
     constants::{MERKLE_NODE_CHANGED_EVENT_NAME, NULLIFIER_SPENT_EVENT_NAME},
+    helpers::u256_to_canonical_scalar,
 };
+use circuit_types::wallet::Nullifier;
 use common::types::{tasks::UpdateMerkleProofTaskDescriptor, wallet::Wallet, CancelChannel};
+use constants::MERKLE_ROOT_HISTORY_LENGTH;
 use ethers::{prelude::StreamExt, types::Filter};
 use job_types::{
     handshake_manager::{HandshakeExecutionJob, HandshakeManagerQueue},
     network_manager::NetworkManagerQueue,
     proof_manager::ProofManagerQueue,
 };
-use renegade_crypto::fields::u256_to_scalar;
 use state::State;
 use tracing::{error, info, instrument, warn};
 
-use super::error::OnChainEventListenerError;
+use super::{
+    error::OnChainEventListenerError,
+    proof_expiry::{reprove_expiring_orders, RootHistory},
+};
 
 // -------------
 // | Constants |
@@ -30,6 +35,20 @@ use super::error::OnChainEventListenerError;
 /// children to count the staleness of Merkle proofs
 const ROOT_CHILDREN_HEIGHT: u8 = 0;
 
+/// The maximum staleness to allow on a Merkle proof, the configured staleness
+/// bounded to the contract's root history window
+///
+/// The margin is the number of roots short of the window at which a wallet's
+/// Merkle proof is refreshed, leaving time for the update to complete before
+/// the proof's root is rotated out
+fn bounded_root_staleness(
+    max_root_staleness: usize,
+    root_history_length: usize,
+    margin: usize,
+) -> usize {
+    max_root_staleness.min(root_history_length.saturating_sub(margin))
+}
+
 // ----------
 // | Worker |
 // ----------
@@ -39,6 +58,9 @@ const ROOT_CHILDREN_HEIGHT: u8 = 0;
 pub struct OnChainEventListenerConfig {
     /// The maximum root staleness to allow in Merkle proofs
     pub max_root_staleness: usize,
+    /// The number of roots short of the contract's root history window at
+    /// which order validity proofs are re-proven against a fresh root
+    pub proof_expiry_margin: usize,
     /// An arbitrum client for listening to events
     pub arbitrum_client: ArbitrumClient,
     /// A copy of the relayer global state
@@ -75,14 +97,25 @@ pub struct OnChainEventListenerExecutor {
     config: OnChainEventListenerConfig,
     /// A copy of the relayer-global state
     global_state: State,
+    /// The maximum root staleness to allow in Merkle proofs, bounded to the
+    /// contract's root history window once it is fetched
+    max_root_staleness: usize,
+    /// The Merkle roots observed in the contract's root history window
+    root_history: RootHistory,
 }
 
 impl OnChainEventListenerExecutor {
     /// Create a new executor
     pub fn new(config: OnChainEventListenerConfig) -> Self {
         let global_state = config.global_state.clone();
+        let max_root_staleness = bounded_root_staleness(
+            config.max_root_staleness,
+            MERKLE_ROOT_HISTORY_LENGTH,
+            config.proof_expiry_margin,
+        );
+        let root_history = RootHistory::new(MERKLE_ROOT_HISTORY_LENGTH);
 
-        Self { config, global_state }
+        Self { config, global_state, max_root_staleness, root_history }
     }
 
     /// Shorthand for fetching a reference to the arbitrum client
@@ -91,7 +124,22 @@ impl OnChainEventListenerExecutor {
     }
 
     /// The main execution loop for the executor
-    pub async fn execute(self) -> Result<(), OnChainEventListenerError> {
+    pub async fn execute(mut self) -> Result<(), OnChainEventListenerError> {
+        // Bound the proof staleness to the contract's root history window, falling
+        // back to the default window if the contract cannot be queried
+        match self.arbitrum_client().get_root_history_length().await {
+            Ok(len) => {
+                self.max_root_staleness = bounded_root_staleness(
+                    self.config.max_root_staleness,
+                    len,
+                    self.config.proof_expiry_margin,
+                );
+                self.root_history.set_capacity(len);
+            },
+            Err(e) => warn!("error fetching root history length, using default: {e}"),
+        }
+        info!("refreshing Merkle proofs beyond a staleness of {}", self.max_root_staleness);
+
         // Get the current block number to start from
         let starting_block_number = self
             .arbitrum_client()
@@ -125,7 +173,7 @@ impl OnChainEventListenerExecutor {
     /// Handle an event from the contract
     #[instrument(skip_all, err)]
     async fn handle_event(
-        &self,
+        &mut self,
         event: DarkpoolContractEvents,
     ) -> Result<(), OnChainEventListenerError> {
         // Dispatch based on key
@@ -151,7 +199,9 @@ impl OnChainEventListenerExecutor {
         event: &NullifierSpentFilter,
     ) -> Result<(), OnChainEventListenerError> {
         // Send an MPC shootdown request to the handshake manager
-        let nullifier = u256_to_scalar(&event.nullifier);
+        let nullifier = u256_to_canonical_scalar(&event.nullifier)
+            .map(Nullifier::new)
+            .map_err(|err| OnChainEventListenerError::Arbitrum(err.to_string()))?;
         self.config
             .handshake_manager_job_queue
             .send(HandshakeExecutionJob::MpcShootdown { nullifier })
@@ -165,7 +215,7 @@ impl OnChainEventListenerExecutor {
 
     /// Handle an internal node update to the contract's Merkle tree
     async fn handle_internal_node_update(
-        &self,
+        &mut self,
         event: NodeChangedFilter,
     ) -> Result<(), OnChainEventListenerError> {
         // Skip events that are not root children updates
@@ -173,10 +223,27 @@ impl OnChainEventListenerExecutor {
             return Ok(());
         }
 
+        // Record the new root and re-prove any orders whose validity proofs are
+        // about to leave the contract's root history
+        let root = self
+            .arbitrum_client()
+            .get_merkle_root()
+            .await
+            .map_err(|err| OnChainEventListenerError::Arbitrum(err.to_string()))?;
+        self.root_history.push(root);
+        let reproven: HashSet<_> = reprove_expiring_orders(
+            &self.global_state,
+            &self.root_history,
+            self.config.proof_expiry_margin,
+        )
+        .await?
+        .into_iter()
+        .collect();
+
         for wallet in self.global_state.get_all_wallets()?.into_iter() {
             // Increment the staleness on the wallet
             let last_val = wallet.merkle_staleness.fetch_add(1, Ordering::Relaxed);
-            if last_val > self.config.max_root_staleness {
+            if last_val > self.max_root_staleness && !reproven.contains(&wallet.wallet_id) {
                 self.update_wallet_merkle_path(wallet).await?;
             }
         }
@@ -199,3 +266,25 @@ impl OnChainEventListenerExecutor {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::bounded_root_staleness;
+
+    /// Tests that the root staleness respects the contract's history window
+    #[test]
+    fn test_bounded_root_staleness() {
+        const HISTORY_LENGTH: usize = 30;
+        const MARGIN: usize = 5;
+
+        // A configured staleness beyond the window is bounded to the window
+        let staleness = bounded_root_staleness(100, HISTORY_LENGTH, MARGIN);
+        assert_eq!(staleness, HISTORY_LENGTH - MARGIN);
+
+        // A configured staleness within the window is unchanged
+        assert_eq!(bounded_root_staleness(10, HISTORY_LENGTH, MARGIN), 10);
+
+        // A window smaller than the margin refreshes proofs on every new root
+        assert_eq!(bounded_root_staleness(100, MARGIN - 1, MARGIN), 0);
+    }
+}
@@ -0,0 +1,247 @@
+//! Detects order validity proofs built against Merkle roots that are about to
+//! leave the contract's root history
+//!
+//! The contract only accepts proofs of `VALID REBLIND` against one of its most
+//! recent roots, so an order whose validity proofs reference an older root can
+//! no longer be matched. The listener records each root it observes, and
+//! re-proves the orders of any wallet whose proofs near the end of this window
+
+use std::collections::VecDeque;
+
+use circuit_types::merkle::MerkleRoot;
+use common::types::{
+    tasks::{TaskDescriptor, UpdateMerkleProofTaskDescriptor},
+    wallet::{Wallet, WalletIdentifier},
+};
+use state::State;
+use tracing::info;
+
+use crate::error::OnChainEventListenerError;
+
+/// The most recent Merkle roots observed in the contract, bounded to the
+/// length of the contract's root history
+#[derive(Clone, Debug)]
+pub struct RootHistory {
+    /// The observed roots, oldest first
+    roots: VecDeque<MerkleRoot>,
+    /// The length of the contract's root history
+    capacity: usize,
+}
+
+impl RootHistory {
+    /// Constructor
+    pub fn new(capacity: usize) -> Self {
+        Self { roots: VecDeque::with_capacity(capacity), capacity }
+    }
+
+    /// Set the length of the contract's root history, evicting the oldest
+    /// roots beyond it
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.roots.len() > capacity {
+            self.roots.pop_front();
+        }
+    }
+
+    /// Record a new root
+    pub fn push(&mut self, root: MerkleRoot) {
+        self.roots.push_back(root);
+        if self.roots.len() > self.capacity {
+            self.roots.pop_front();
+        }
+    }
+
+    /// The number of roots observed since the given root, zero for the current
+    /// root and `None` if the root is not in the history
+    pub fn root_age(&self, root: &MerkleRoot) -> Option<usize> {
+        self.roots.iter().rev().position(|r| r == root)
+    }
+
+    /// Whether a proof against the given root is within `margin` roots of
+    /// leaving the contract's root history
+    ///
+    /// A root missing from a full history has already left the contract's
+    /// history. A root missing from a partial history predates the listener and
+    /// cannot be aged, so it is left to the wallet's staleness counter
+    pub fn is_expiring(&self, root: &MerkleRoot, margin: usize) -> bool {
+        match self.root_age(root) {
+            Some(age) => age + margin >= self.capacity,
+            None => self.roots.len() >= self.capacity,
+        }
+    }
+}
+
+/// Enqueue a Merkle proof update, which re-proves the wallet's orders against a
+/// fresh root, for each wallet holding an order with expiring validity proofs
+///
+/// The check runs on every new root, so wallets with a Merkle proof update
+/// already queued or in flight are skipped rather than re-proven again.
+/// Returns the IDs of the wallets being re-proven
+pub async fn reprove_expiring_orders(
+    state: &State,
+    history: &RootHistory,
+    margin: usize,
+) -> Result<Vec<WalletIdentifier>, OnChainEventListenerError> {
+    let mut reproven = Vec::new();
+    for wallet in state.get_all_wallets()?.into_iter() {
+        if !has_expiring_proofs(state, &wallet, history, margin)?
+            || has_pending_reproof(state, &wallet.wallet_id)?
+        {
+            continue;
+        }
+
+        info!("re-proving orders of wallet {} against a fresh root", wallet.wallet_id);
+        reproven.push(wallet.wallet_id);
+        let task = UpdateMerkleProofTaskDescriptor::new(wallet).unwrap();
+        let (_task_id, waiter) = state.append_task(task.into())?;
+        waiter.await?;
+    }
+
+    Ok(reproven)
+}
+
+/// Whether any of the wallet's orders hold validity proofs against an expiring
+/// root
+fn has_expiring_proofs(
+    state: &State,
+    wallet: &Wallet,
+    history: &RootHistory,
+    margin: usize,
+) -> Result<bool, OnChainEventListenerError> {
+    for order_id in wallet.orders.keys() {
+        if let Some(proofs) = state.get_validity_proofs(order_id)? {
+            let root = proofs.reblind_proof.statement.merkle_root;
+            if history.is_expiring(&root, margin) {
+                return Ok(true);
+            }
+        }
+    }
+
+    Ok(false)
+}
+
+/// Whether a Merkle proof update is queued or running for the wallet
+fn has_pending_reproof(
+    state: &State,
+    wallet_id: &WalletIdentifier,
+) -> Result<bool, OnChainEventListenerError> {
+    let tasks = state.get_queued_tasks(wallet_id)?;
+    Ok(tasks.iter().any(|task| matches!(task.descriptor, TaskDescriptor::UpdateMerkleProof(_))))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use circuit_types::{merkle::MerkleRoot, order::Order};
+    use common::types::{
+        proof_bundles::mocks::{dummy_validity_proof_bundle, dummy_validity_witness_bundle},
+        tasks::TaskDescriptor,
+        wallet::{OrderIdentifier, Wallet},
+        wallet_mocks::mock_empty_wallet,
+    };
+    use constants::Scalar;
+    use state::{test_helpers::mock_state, State};
+
+    use super::{reprove_expiring_orders, RootHistory};
+
+    /// The length of the root history used in tests
+    const HISTORY_LENGTH: usize = 10;
+    /// The expiry margin used in tests
+    const MARGIN: usize = 3;
+
+    /// Build a distinct root for the given index
+    fn root(i: u64) -> MerkleRoot {
+        Scalar::from(i).into()
+    }
+
+    /// Add a wallet to the state holding a single order proven against the
+    /// given root
+    async fn add_wallet_proven_at(state: &State, root: MerkleRoot) -> Wallet {
+        let mut wallet = mock_empty_wallet();
+        state.new_wallet(wallet.clone()).unwrap().await.unwrap();
+
+        let order_id = OrderIdentifier::new_v4();
+        wallet.add_order(order_id, Order::default()).unwrap();
+        state.update_wallet(wallet.clone()).unwrap().await.unwrap();
+
+        let mut proof = dummy_validity_proof_bundle();
+        Arc::make_mut(&mut proof.reblind_proof).statement.merkle_root = root;
+        let witness = dummy_validity_witness_bundle();
+        state.add_local_order_validity_bundle(order_id, proof, witness).unwrap().await.unwrap();
+
+        wallet
+    }
+
+    /// Tests aging and expiring roots in the history
+    #[test]
+    fn test_root_expiry() {
+        let mut history = RootHistory::new(HISTORY_LENGTH);
+        for i in 0..HISTORY_LENGTH as u64 {
+            history.push(root(i));
+        }
+
+        // The newest root is fresh, the oldest within the margin is expiring
+        let newest = root(HISTORY_LENGTH as u64 - 1);
+        assert_eq!(history.root_age(&newest), Some(0));
+        assert!(!history.is_expiring(&newest, MARGIN));
+        assert!(history.is_expiring(&root(MARGIN as u64 - 1), MARGIN));
+        assert!(!history.is_expiring(&root(MARGIN as u64), MARGIN));
+
+        // A root rotated out of a full history has expired
+        history.push(root(HISTORY_LENGTH as u64));
+        assert_eq!(history.root_age(&root(0)), None);
+        assert!(history.is_expiring(&root(0), MARGIN));
+
+        // An unknown root in a partial history cannot be aged
+        let partial = RootHistory::new(HISTORY_LENGTH);
+        assert!(!partial.is_expiring(&root(0), MARGIN));
+    }
+
+    /// Tests that an order proven against a stale root is re-proven, while an
+    /// order proven against a fresh root is not
+    #[tokio::test]
+    async fn test_stale_rooted_order_reproven() {
+        let state = mock_state();
+        let stale_wallet = add_wallet_proven_at(&state, root(0)).await;
+        let fresh_wallet = add_wallet_proven_at(&state, root(HISTORY_LENGTH as u64 - 1)).await;
+
+        let mut history = RootHistory::new(HISTORY_LENGTH);
+        for i in 0..HISTORY_LENGTH as u64 {
+            history.push(root(i));
+        }
+
+        let reproven = reprove_expiring_orders(&state, &history, MARGIN).await.unwrap();
+        assert_eq!(reproven, vec![stale_wallet.wallet_id]);
+
+        // A Merkle proof update is queued for the stale wallet alone
+        let tasks = state.get_queued_tasks(&stale_wallet.wallet_id).unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert!(matches!(tasks[0].descriptor, TaskDescriptor::UpdateMerkleProof(_)));
+        assert!(state.get_queued_tasks(&fresh_wallet.wallet_id).unwrap().is_empty());
+    }
+
+    /// Tests that a wallet whose re-proof is queued is not re-proven again as
+    /// the next root arrives
+    #[tokio::test]
+    async fn test_pending_reproof_not_duplicated() {
+        let state = mock_state();
+        let wallet = add_wallet_proven_at(&state, root(0)).await;
+
+        let mut history = RootHistory::new(HISTORY_LENGTH);
+        for i in 0..HISTORY_LENGTH as u64 {
+            history.push(root(i));
+        }
+
+        let reproven = reprove_expiring_orders(&state, &history, MARGIN).await.unwrap();
+        assert_eq!(reproven, vec![wallet.wallet_id]);
+
+        // The next root arrives before the re-proof runs
+        history.push(root(HISTORY_LENGTH as u64));
+        let reproven = reprove_expiring_orders(&state, &history, MARGIN).await.unwrap();
+        assert!(reproven.is_empty());
+
+        let tasks = state.get_queued_tasks(&wallet.wallet_id).unwrap();
+        assert_eq!(tasks.len(), 1);
+    }
+}
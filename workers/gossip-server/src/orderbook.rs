@@ -1,7 +1,8 @@
 //! Groups handlers for updating and managing order book state in response to
 //! events elsewhere in the local node or the network
 
-use circuit_types::wallet::Nullifier;
+use arbitrum_client::traits::DarkpoolClient;
+use circuit_types::{merkle::MerkleRoot, wallet::Nullifier};
 use circuits::{
     verify_singleprover_proof,
     zk_circuits::{
@@ -10,7 +11,7 @@ use circuits::{
     },
 };
 use common::types::{
-    gossip::ClusterId,
+    gossip::{ClusterId, WrappedPeerId},
     network_order::{NetworkOrder, NetworkOrderState},
     proof_bundles::OrderValidityProofBundle,
     wallet::OrderIdentifier,
@@ -18,9 +19,16 @@ use common::types::{
 use futures::executor::block_on;
 use gossip_api::{
     pubsub::orderbook::OrderBookManagementMessage,
-    request_response::{orderbook::OrderInfoResponse, GossipResponse},
+    request_response::{
+        orderbook::{
+            OrderBookSnapshotRequest, OrderBookSnapshotResponse, OrderInfoResponse,
+            ValidityProofResponse, MAX_SNAPSHOT_PAGE_SIZE,
+        },
+        GossipRequest, GossipResponse,
+    },
 };
-use tracing::debug;
+use job_types::network_manager::NetworkManagerJob;
+use tracing::{debug, warn};
 use util::err_str;
 
 use super::{errors::GossipError, server::GossipProtocolExecutor};
@@ -30,6 +38,50 @@ const ERR_NULLIFIER_USED: &str = "invalid nullifier, already used";
 /// Error message emitted when a Merkle root is not found in the contract
 /// history
 const ERR_INVALID_MERKLE_ROOT: &str = "invalid merkle root, not in contract history";
+/// Error message emitted when a validity proof is received for an unknown order
+const ERR_ORDER_NOT_FOUND: &str = "order not found in state";
+/// Error message emitted when a validity proof does not match the nullifier
+/// known for its order
+const ERR_NULLIFIER_MISMATCH: &str = "validity proof nullifier does not match order";
+
+/// The maximum number of pages of a single peer's order book snapshot that are
+/// followed
+const MAX_SNAPSHOT_PAGES: usize = 100;
+/// The maximum number of orders indexed from a single peer's order book
+/// snapshot
+const MAX_SNAPSHOT_ORDERS: usize = MAX_SNAPSHOT_PAGES * MAX_SNAPSHOT_PAGE_SIZE;
+
+/// The local node's progress through a peer's order book snapshot
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct SnapshotProgress {
+    /// The number of pages received from the peer
+    pages: usize,
+    /// The number of orders received from the peer
+    orders: usize,
+    /// The cursor of the last page requested from the peer
+    cursor: Option<OrderIdentifier>,
+}
+
+impl SnapshotProgress {
+    /// Whether the next page of the peer's snapshot should be requested
+    ///
+    /// A page's cursor must move forward through the peer's order book, and
+    /// pages are only followed until either bound is reached, so that a
+    /// misbehaving peer cannot page the local node indefinitely
+    fn should_follow(&self, peer: &WrappedPeerId, next: &OrderBookSnapshotRequest) -> bool {
+        if next.after <= self.cursor {
+            warn!("order book snapshot cursor from {peer} does not advance, ending snapshot");
+            return false;
+        }
+
+        if self.pages >= MAX_SNAPSHOT_PAGES || self.orders >= MAX_SNAPSHOT_ORDERS {
+            warn!("order book snapshot from {peer} exceeds its bounds, ending snapshot");
+            return false;
+        }
+
+        true
+    }
+}
 
 impl GossipProtocolExecutor {
     // --------------------
@@ -48,6 +100,37 @@ impl GossipProtocolExecutor {
         Ok(GossipResponse::OrderInfo(resp))
     }
 
+    /// Handles a request for the validity proofs of an order from a peer
+    pub(crate) fn handle_validity_proof_request(
+        &self,
+        order_id: OrderIdentifier,
+    ) -> Result<GossipResponse, GossipError> {
+        let proof_bundle = self.global_state.get_validity_proofs(&order_id)?;
+        let resp = ValidityProofResponse { order_id, proof_bundle };
+        Ok(GossipResponse::ValidityProof(resp))
+    }
+
+    /// Handles a request for a page of the local node's matchable orders
+    ///
+    /// Only orders with verified validity proofs are included, so that the
+    /// requesting peer may verify and index them immediately
+    pub(crate) fn handle_order_book_snapshot_request(
+        &self,
+        req: &OrderBookSnapshotRequest,
+    ) -> Result<GossipResponse, GossipError> {
+        let matchable_orders = self
+            .global_state
+            .get_all_orders()?
+            .into_iter()
+            .filter(|order| {
+                order.state == NetworkOrderState::Verified && order.validity_proofs.is_some()
+            })
+            .collect();
+
+        let resp = OrderBookSnapshotResponse::paginate(matchable_orders, req);
+        Ok(GossipResponse::OrderBookSnapshot(resp))
+    }
+
     // ---------------------
     // | Inbound Responses |
     // ---------------------
@@ -57,46 +140,92 @@ impl GossipProtocolExecutor {
         &self,
         order_info: Vec<NetworkOrder>,
     ) -> Result<(), GossipError> {
-        for mut order in order_info.into_iter() {
-            let order_id = order.id;
+        for order in order_info.into_iter() {
+            self.index_order_info(order).await?;
+        }
 
-            // Skip local orders, their state is added on wallet update through raft
-            // consensus
-            let is_local = order.cluster == self.global_state.get_cluster_id()?;
-            if is_local {
-                debug!("skipping local order {order_id}");
-                continue;
+        Ok(())
+    }
+
+    /// Handles a page of a peer's order book snapshot
+    ///
+    /// The orders are indexed as if they had been requested by ID, skipping
+    /// those that fail validation, and the next page is requested from the peer
+    /// if more orders remain and the snapshot is within its bounds
+    pub(crate) async fn handle_order_book_snapshot_response(
+        &self,
+        peer: WrappedPeerId,
+        resp: OrderBookSnapshotResponse,
+    ) -> Result<(), GossipError> {
+        let OrderBookSnapshotResponse { mut orders, next_page } = resp;
+
+        // Record the page against the peer's progress, ending the snapshot if the
+        // next page should not be followed
+        let next_page = {
+            let mut snapshots = self.snapshot_progress.write().await;
+            let progress = snapshots.entry(peer).or_default();
+            orders.truncate(MAX_SNAPSHOT_ORDERS.saturating_sub(progress.orders));
+            progress.pages += 1;
+            progress.orders += orders.len();
+
+            let next_page = next_page.filter(|next| progress.should_follow(&peer, next));
+            match &next_page {
+                Some(next) => progress.cursor = next.after,
+                None => {
+                    snapshots.remove(&peer);
+                },
             }
 
-            // Move fields out of `order_info` before transferring ownership
-            let proof = order.validity_proofs.take();
-
-            order.state = NetworkOrderState::Received;
-            order.local = is_local;
-            self.global_state.add_order(order)?;
-
-            // If there is a proof attached to the order, verify it and transition to
-            // `Verified`. If the order is locally managed, the raft consensus will take
-            // care of indexing the order
-            if let Some(proof_bundle) = proof {
-                // Spawn a blocking task to avoid consuming the gossip server's thread pool
-                let self_clone = self.clone();
-                let bundle_clone = proof_bundle.clone();
-                tokio::task::spawn_blocking(move || {
-                    block_on(self_clone.verify_validity_proofs(&bundle_clone))
-                })
-                .await
-                .unwrap()?;
-
-                // Update the state of the order to `Verified` by attaching the verified
-                // validity proof
-                self.global_state.add_order_validity_proof(order_id, proof_bundle)?;
+            next_page
+        };
+
+        for order in orders.into_iter() {
+            let order_id = order.id;
+            if let Err(e) = self.index_order_info(order).await {
+                warn!("skipping order {order_id} in order book snapshot from {peer}: {e}");
             }
         }
 
+        if let Some(next_req) = next_page {
+            let req = GossipRequest::OrderBookSnapshot(next_req);
+            self.network_channel
+                .send(NetworkManagerJob::request(peer, req))
+                .map_err(err_str!(GossipError::SendMessage))?;
+        }
+
         Ok(())
     }
 
+    /// Handles a response to a request for an order's validity proofs
+    ///
+    /// The proofs are checked against the nullifier already known for the
+    /// order before being verified and indexed
+    pub(crate) async fn handle_validity_proof_response(
+        &self,
+        resp: ValidityProofResponse,
+    ) -> Result<(), GossipError> {
+        let ValidityProofResponse { order_id, proof_bundle } = resp;
+        let proof_bundle = match proof_bundle {
+            Some(bundle) => bundle,
+            None => {
+                debug!("peer has no validity proofs for order {order_id}");
+                return Ok(());
+            },
+        };
+
+        let order = self
+            .global_state
+            .get_order(&order_id)?
+            .ok_or_else(|| GossipError::MissingState(ERR_ORDER_NOT_FOUND.to_string()))?;
+        if proof_bundle.reblind_proof.statement.original_shares_nullifier
+            != order.public_share_nullifier
+        {
+            return Err(GossipError::ValidReblindVerification(ERR_NULLIFIER_MISMATCH.to_string()));
+        }
+
+        self.handle_new_validity_proof(order_id, order.cluster, proof_bundle).await
+    }
+
     // -------------------
     // | Pubsub Messages |
     // -------------------
@@ -180,6 +309,47 @@ impl GossipProtocolExecutor {
     // | Helpers |
     // -----------
 
+    /// Index an order received from a peer, verifying its validity proofs if
+    /// attached
+    ///
+    /// The proofs are verified before the order is added, so that an order
+    /// with invalid proofs is not indexed
+    async fn index_order_info(&self, mut order: NetworkOrder) -> Result<(), GossipError> {
+        let order_id = order.id;
+
+        // Skip local orders, their state is added on wallet update through raft
+        // consensus
+        let is_local = order.cluster == self.global_state.get_cluster_id()?;
+        if is_local {
+            debug!("skipping local order {order_id}");
+            return Ok(());
+        }
+
+        // Move fields out of `order_info` before transferring ownership
+        let proof = order.validity_proofs.take();
+        if let Some(proof_bundle) = proof.clone() {
+            // Spawn a blocking task to avoid consuming the gossip server's thread pool
+            let self_clone = self.clone();
+            tokio::task::spawn_blocking(move || {
+                block_on(self_clone.verify_validity_proofs(&proof_bundle))
+            })
+            .await
+            .unwrap()?;
+        }
+
+        order.state = NetworkOrderState::Received;
+        order.local = is_local;
+        self.global_state.add_order(order)?;
+
+        // If the order's proofs were verified, transition it to `Verified` by
+        // attaching them
+        if let Some(proof_bundle) = proof {
+            self.global_state.add_order_validity_proof(order_id, proof_bundle)?;
+        }
+
+        Ok(())
+    }
+
     /// Verify the validity proofs (`VALID REBLIND` and `VALID COMMITMENTS`) of
     /// an incoming order
     ///
@@ -199,16 +369,7 @@ impl GossipProtocolExecutor {
         self.assert_nullifier_unused(reblind_proof.statement.original_shares_nullifier).await?;
 
         // Check that the Merkle root is a valid historical root
-        if !self
-            .arbitrum_client()
-            .check_merkle_root_valid(reblind_proof.statement.merkle_root)
-            .await
-            .map_err(err_str!(GossipError::Arbitrum))?
-        {
-            return Err(GossipError::ValidCommitmentVerification(
-                ERR_INVALID_MERKLE_ROOT.to_string(),
-            ));
-        }
+        self.assert_root_in_history(reblind_proof.statement.merkle_root).await?;
 
         // Verify the reblind proof
         verify_singleprover_proof::<SizedValidReblind>(
@@ -249,4 +410,334 @@ impl GossipProtocolExecutor {
             })
             .map_err(|err| GossipError::Arbitrum(err.to_string()))?
     }
+
+    /// Assert that a Merkle root is within the contract's root history
+    ///
+    /// A peer may replay an order whose validity proofs were built against a
+    /// root since rotated out of the history; such an order can no longer be
+    /// matched, so its proofs are rejected on ingestion
+    async fn assert_root_in_history(&self, root: MerkleRoot) -> Result<(), GossipError> {
+        let valid = self
+            .arbitrum_client()
+            .check_merkle_root_valid(root)
+            .await
+            .map_err(err_str!(GossipError::Arbitrum))?;
+        if !valid {
+            return Err(GossipError::ValidCommitmentVerification(
+                ERR_INVALID_MERKLE_ROOT.to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{str::FromStr, sync::Arc};
+
+    use arbitrum_client::mock::MockDarkpoolClient;
+    use circuit_types::{balance::Balance, wallet::Nullifier, SizedWallet as SizedCircuitWallet};
+    use circuits::{
+        singleprover_prove_with_hint,
+        zk_circuits::{
+            proof_linking::link_sized_commitments_reblind,
+            test_helpers::PUBLIC_KEYS,
+            valid_commitments::{
+                test_helpers::create_witness_and_statement_with_shares as commitments_witness_statement,
+                SizedValidCommitments,
+            },
+            valid_reblind::{
+                test_helpers::construct_witness_statement as reblind_witness_statement,
+                SizedValidReblind,
+            },
+        },
+    };
+    use common::types::{
+        gossip::{ClusterId, WrappedPeerId},
+        network_order::{test_helpers::dummy_network_order, NetworkOrder},
+        new_cancel_channel,
+        proof_bundles::{
+            mocks::dummy_validity_proof_bundle, OrderValidityProofBundle,
+            SizedValidCommitmentsBundle, SizedValidReblindBundle,
+        },
+        wallet::OrderIdentifier,
+        wallet_mocks::{mock_empty_wallet, mock_order},
+    };
+    use constants::{Scalar, MAX_BALANCES, MAX_ORDERS, MERKLE_HEIGHT};
+    use gossip_api::{
+        pubsub::orderbook::OrderBookManagementMessage,
+        request_response::{
+            orderbook::{
+                OrderBookSnapshotRequest, OrderBookSnapshotResponse, ValidityProofResponse,
+            },
+            GossipRequest,
+        },
+    };
+    use job_types::{
+        gossip_server::new_gossip_server_queue,
+        network_manager::{new_network_manager_queue, NetworkManagerJob, NetworkManagerReceiver},
+    };
+    use libp2p::Multiaddr;
+    use state::test_helpers::mock_state;
+
+    use crate::{errors::GossipError, server::GossipProtocolExecutor, worker::GossipServerConfig};
+
+    use super::ERR_INVALID_MERKLE_ROOT;
+
+    /// Create a gossip executor that queries the given darkpool client
+    fn mock_executor(client: MockDarkpoolClient) -> GossipProtocolExecutor {
+        mock_executor_with_network(client).0
+    }
+
+    /// Create a gossip executor that queries the given darkpool client, along
+    /// with the receiver of its outbound network requests
+    fn mock_executor_with_network(
+        client: MockDarkpoolClient,
+    ) -> (GossipProtocolExecutor, NetworkManagerReceiver) {
+        let state = mock_state();
+        let (job_sender, job_receiver) = new_gossip_server_queue();
+        let (network_sender, network_recv) = new_network_manager_queue();
+        let (_cancel_sender, cancel_channel) = new_cancel_channel();
+
+        let config = GossipServerConfig {
+            local_peer_id: WrappedPeerId::random(),
+            local_addr: Multiaddr::empty(),
+            cluster_id: state.get_cluster_id().unwrap(),
+            bootstrap_servers: vec![],
+            max_missed_heartbeats: 3,
+            arbitrum_client: Arc::new(client),
+            global_state: state.clone(),
+            job_sender,
+            job_receiver: None.into(),
+            network_sender: network_sender.clone(),
+            cancel_channel: cancel_channel.clone(),
+        };
+
+        let executor = GossipProtocolExecutor::new(
+            network_sender,
+            job_receiver,
+            state,
+            config,
+            cancel_channel,
+        )
+        .unwrap();
+        (executor, network_recv)
+    }
+
+    /// Build an order held by a remote cluster
+    fn remote_order() -> NetworkOrder {
+        let mut order = dummy_network_order();
+        order.cluster = ClusterId::from_str("remote-cluster").unwrap();
+        order
+    }
+
+    /// Build a snapshot page holding the given orders, pointing at the page
+    /// after `after`
+    fn snapshot_page(
+        orders: Vec<NetworkOrder>,
+        after: Option<OrderIdentifier>,
+    ) -> OrderBookSnapshotResponse {
+        let next_page =
+            after.map(|after| OrderBookSnapshotRequest { after: Some(after), page_size: 1 });
+        OrderBookSnapshotResponse { orders, next_page }
+    }
+
+    /// Prove a validity proof bundle for an order in a mock wallet
+    fn valid_proof_bundle() -> OrderValidityProofBundle {
+        let mut wallet = mock_empty_wallet();
+        let order = mock_order();
+        let balance = Balance::new_from_mint_and_amount(order.quote_mint.clone(), 1000);
+        wallet.add_balance(balance).unwrap();
+        wallet.add_order(OrderIdentifier::new_v4(), order).unwrap();
+
+        // The reblind witness authorizes the wallet with the test keys
+        let mut wallet: SizedCircuitWallet = wallet.into();
+        wallet.keys = PUBLIC_KEYS.clone();
+        let (reblind_witness, reblind_statement) =
+            reblind_witness_statement::<MAX_BALANCES, MAX_ORDERS, MERKLE_HEIGHT>(&wallet);
+
+        // Commit to the reblinded wallet's order
+        let private_share = reblind_witness.reblinded_wallet_private_shares.clone();
+        let public_share = reblind_witness.reblinded_wallet_public_shares.clone();
+        wallet.blinder = public_share.blinder + private_share.blinder;
+        let (comm_witness, comm_statement) =
+            commitments_witness_statement(&wallet, &public_share, &private_share);
+
+        let (reblind_proof, reblind_hint) =
+            singleprover_prove_with_hint::<SizedValidReblind>(reblind_witness, reblind_statement)
+                .unwrap();
+        let (comm_proof, comm_hint) =
+            singleprover_prove_with_hint::<SizedValidCommitments>(comm_witness, comm_statement)
+                .unwrap();
+        let linking_proof = link_sized_commitments_reblind(&reblind_hint, &comm_hint).unwrap();
+
+        OrderValidityProofBundle {
+            reblind_proof: Arc::new(SizedValidReblindBundle {
+                statement: reblind_statement,
+                proof: reblind_proof,
+            }),
+            commitment_proof: Arc::new(SizedValidCommitmentsBundle {
+                statement: comm_statement,
+                proof: comm_proof,
+            }),
+            linking_proof,
+        }
+    }
+
+    /// Index a remote order under the given nullifier, returning its ID
+    fn add_remote_order(
+        executor: &GossipProtocolExecutor,
+        nullifier: Nullifier,
+    ) -> OrderIdentifier {
+        let order_id = OrderIdentifier::new_v4();
+        let cluster = ClusterId::from_str("remote-cluster").unwrap();
+        let order = NetworkOrder::new(order_id, nullifier, cluster, false /* local */);
+        executor.global_state.add_order(order).unwrap();
+        order_id
+    }
+
+    /// Get the cursor of the snapshot page requested from the peer, if any
+    fn requested_cursor(network_recv: &mut NetworkManagerReceiver) -> Option<OrderIdentifier> {
+        match network_recv.try_recv() {
+            Ok(NetworkManagerJob::Request(_, GossipRequest::OrderBookSnapshot(req))) => req.after,
+            Ok(job) => panic!("unexpected network job: {job:?}"),
+            Err(_) => None,
+        }
+    }
+
+    /// Tests that a gossiped order proven against a root rotated out of the
+    /// contract's history is rejected on ingestion, while the same proofs are
+    /// accepted while their root remains in the history
+    ///
+    /// The proofs are otherwise valid, so only the root check rejects them
+    #[tokio::test]
+    async fn test_stale_root_rejected() {
+        let proof_bundle = valid_proof_bundle();
+        let root = proof_bundle.reblind_proof.statement.merkle_root;
+        let cluster = ClusterId::from_str("remote-cluster").unwrap();
+
+        // The proofs are accepted against a fresh root
+        let executor = mock_executor(MockDarkpoolClient::new());
+        let order_id = OrderIdentifier::new_v4();
+        let msg = OrderBookManagementMessage::OrderProofUpdated {
+            order_id,
+            cluster: cluster.clone(),
+            proof_bundle: proof_bundle.clone(),
+        };
+        executor.handle_orderbook_pubsub(msg).await.unwrap();
+        assert!(executor.global_state.get_validity_proofs(&order_id).unwrap().is_some());
+
+        // The same proofs are rejected once the root expires
+        let client = MockDarkpoolClient::new();
+        client.expire_root(root);
+        let executor = mock_executor(client);
+
+        let order_id = OrderIdentifier::new_v4();
+        let msg = OrderBookManagementMessage::OrderProofUpdated { order_id, cluster, proof_bundle };
+        let res = executor.handle_orderbook_pubsub(msg).await;
+
+        match res {
+            Err(GossipError::ValidCommitmentVerification(msg)) => {
+                assert_eq!(msg, ERR_INVALID_MERKLE_ROOT)
+            },
+            res => panic!("expected a stale root error, got {res:?}"),
+        }
+        assert!(!executor.global_state.contains_order(&order_id).unwrap());
+    }
+
+    /// Tests that a peer whose snapshot cursor does not advance is not paged
+    /// again
+    #[tokio::test]
+    async fn test_looping_snapshot_peer() {
+        let (executor, mut network_recv) = mock_executor_with_network(MockDarkpoolClient::new());
+        let peer = WrappedPeerId::random();
+
+        // A page whose cursor advances is followed
+        let (first, second) = (remote_order(), remote_order());
+        let page = snapshot_page(vec![first.clone()], Some(first.id));
+        executor.handle_order_book_snapshot_response(peer, page).await.unwrap();
+        assert_eq!(requested_cursor(&mut network_recv), Some(first.id));
+
+        // A page that repeats the cursor is not
+        let page = snapshot_page(vec![second.clone()], Some(first.id));
+        executor.handle_order_book_snapshot_response(peer, page).await.unwrap();
+        assert_eq!(requested_cursor(&mut network_recv), None);
+
+        // The orders in both pages are still indexed
+        assert!(executor.global_state.contains_order(&first.id).unwrap());
+        assert!(executor.global_state.contains_order(&second.id).unwrap());
+    }
+
+    /// Tests that an order failing validation is skipped without failing the
+    /// rest of its snapshot page
+    #[tokio::test]
+    async fn test_snapshot_skips_invalid_order() {
+        let proof_bundle = dummy_validity_proof_bundle();
+        let client = MockDarkpoolClient::new();
+        client.expire_root(proof_bundle.reblind_proof.statement.merkle_root);
+        let (executor, mut network_recv) = mock_executor_with_network(client);
+
+        let (good1, good2) = (remote_order(), remote_order());
+        let mut bad = remote_order();
+        bad.validity_proofs = Some(proof_bundle);
+
+        let last_id = good2.id;
+        let orders = vec![good1.clone(), bad.clone(), good2.clone()];
+        let page = snapshot_page(orders, Some(last_id));
+        executor.handle_order_book_snapshot_response(WrappedPeerId::random(), page).await.unwrap();
+
+        let state = &executor.global_state;
+        assert!(state.contains_order(&good1.id).unwrap());
+        assert!(state.contains_order(&good2.id).unwrap());
+        assert!(!state.contains_order(&bad.id).unwrap());
+        assert_eq!(requested_cursor(&mut network_recv), Some(last_id));
+    }
+
+    /// Tests that a validity proof response with a valid bundle for a known
+    /// order is verified and attached to the order
+    #[tokio::test]
+    async fn test_validity_proof_response_accepted() {
+        let executor = mock_executor(MockDarkpoolClient::new());
+        let proof_bundle = valid_proof_bundle();
+        let nullifier = proof_bundle.reblind_proof.statement.original_shares_nullifier;
+        let order_id = add_remote_order(&executor, nullifier);
+
+        let resp = ValidityProofResponse { order_id, proof_bundle: Some(proof_bundle) };
+        executor.handle_validity_proof_response(resp).await.unwrap();
+
+        let proofs = executor.global_state.get_validity_proofs(&order_id).unwrap();
+        assert!(proofs.is_some());
+    }
+
+    /// Tests that a validity proof response whose bundle does not match the
+    /// order's nullifier is rejected
+    #[tokio::test]
+    async fn test_validity_proof_response_nullifier_mismatch() {
+        let executor = mock_executor(MockDarkpoolClient::new());
+        let proof_bundle = valid_proof_bundle();
+        let order_id = add_remote_order(&executor, Nullifier::from(Scalar::one()));
+
+        let resp = ValidityProofResponse { order_id, proof_bundle: Some(proof_bundle) };
+        let res = executor.handle_validity_proof_response(resp).await;
+
+        assert!(matches!(res, Err(GossipError::ValidReblindVerification(_))));
+        assert!(executor.global_state.get_validity_proofs(&order_id).unwrap().is_none());
+    }
+
+    /// Tests that a validity proof response whose proofs fail verification is
+    /// rejected
+    #[tokio::test]
+    async fn test_validity_proof_response_invalid_proof() {
+        let executor = mock_executor(MockDarkpoolClient::new());
+        let proof_bundle = dummy_validity_proof_bundle();
+        let nullifier = proof_bundle.reblind_proof.statement.original_shares_nullifier;
+        let order_id = add_remote_order(&executor, nullifier);
+
+        let resp = ValidityProofResponse { order_id, proof_bundle: Some(proof_bundle) };
+        let res = executor.handle_validity_proof_response(resp).await;
+
+        assert!(matches!(res, Err(GossipError::ValidReblindVerification(_))));
+        assert!(executor.global_state.get_validity_proofs(&order_id).unwrap().is_none());
+    }
 }
@@ -21,12 +21,9 @@ use crate::{errors::GossipError, server::GossipProtocolExecutor};
 pub const HEARTBEAT_INTERVAL_MS: u64 = 10_000; // 10 seconds
 /// The interval at which to send heartbeats to cluster peer
 pub const CLUSTER_HEARTBEAT_INTERVAL_MS: u64 = 3_000; // 3 seconds
-/// The amount of time without a successful heartbeat before the local
-/// relayer should assume its peer has failed; for non-cluster peers
-pub const HEARTBEAT_FAILURE_MS: u64 = 20_000; // 20 seconds
-/// The amount of time without a successful heartbeat before the local
-/// relayer should assume its peer has failed; for cluster peers
-pub const CLUSTER_HEARTBEAT_FAILURE_MS: u64 = 7_000; // 7 seconds
+/// The interval at which to sweep the peer index for peers that have missed
+/// too many heartbeats
+pub const PEER_EXPIRY_SWEEP_INTERVAL_MS: u64 = 1_000; // 1 second
 /// The minimum amount of time between a peer's expiry and when it can be
 /// added back to the peer info
 pub(crate) const EXPIRY_INVISIBILITY_WINDOW_MS: u64 = 30_000; // 30 seconds
@@ -57,8 +54,7 @@ impl GossipProtocolExecutor {
         let msg = GossipRequest::Heartbeat(heartbeat_message);
         let job = NetworkManagerJob::Request(recipient_peer_id, msg);
 
-        self.network_channel.send(job).map_err(err_str!(GossipError::SendMessage))?;
-        self.maybe_expire_peer(recipient_peer_id).await
+        self.network_channel.send(job).map_err(err_str!(GossipError::SendMessage))
     }
 
     // ---------------------
@@ -105,34 +101,39 @@ impl GossipProtocolExecutor {
             .map_err(err_str!(GossipError::SendMessage))
     }
 
-    // -----------
-    // | Helpers |
-    // -----------
+    // ---------------
+    // | Peer Expiry |
+    // ---------------
 
-    /// Expires peers that have timed out due to consecutive failed heartbeats
-    async fn maybe_expire_peer(&self, peer_id: WrappedPeerId) -> Result<(), GossipError> {
-        // Find the peer's info in global state
-        let peer_info = self.global_state.get_peer_info(&peer_id)?;
-        if peer_info.is_none() {
-            info!("could not find info for peer {peer_id:?}");
-            return Ok(());
-        }
-        let peer_info = peer_info.unwrap();
+    /// Expires all peers that have missed more than the configured number of
+    /// consecutive heartbeats
+    ///
+    /// Expiring a cluster peer also proposes its removal from the raft group
+    pub async fn sweep_expired_peers(&self) -> Result<(), GossipError> {
+        let now = get_current_time_seconds();
 
         // Expire cluster peers sooner than non-cluster peers
-        let cluster_id = self.global_state.get_cluster_id()?;
-        let same_cluster = peer_info.get_cluster_id() == cluster_id;
-
-        let now = get_current_time_seconds();
-        let last_heartbeat = now - peer_info.get_last_heartbeat();
+        let max_missed = self.config.max_missed_heartbeats;
+        let cluster_window = expiry_window_secs(CLUSTER_HEARTBEAT_INTERVAL_MS, max_missed);
+        let remote_window = expiry_window_secs(HEARTBEAT_INTERVAL_MS, max_missed);
 
-        #[allow(clippy::if_same_then_else)]
-        if same_cluster && last_heartbeat < CLUSTER_HEARTBEAT_FAILURE_MS / 1000 {
-            return Ok(());
-        } else if !same_cluster && last_heartbeat < HEARTBEAT_FAILURE_MS / 1000 {
-            return Ok(());
+        let cluster_id = self.global_state.get_cluster_id()?;
+        let expired = self.global_state.get_expired_peers(now, cluster_window, remote_window)?;
+        for peer_info in expired {
+            let same_cluster = peer_info.get_cluster_id() == cluster_id;
+            self.expire_peer(peer_info.peer_id, same_cluster, now).await?;
         }
 
+        Ok(())
+    }
+
+    /// Expire a peer that has timed out due to consecutive failed heartbeats
+    async fn expire_peer(
+        &self,
+        peer_id: WrappedPeerId,
+        same_cluster: bool,
+        now: u64,
+    ) -> Result<(), GossipError> {
         // Remove expired peers from global state
         info!("Expiring peer {peer_id}");
         self.global_state.remove_peer(peer_id)?;
@@ -154,6 +155,10 @@ impl GossipProtocolExecutor {
         Ok(())
     }
 
+    // -----------
+    // | Helpers |
+    // -----------
+
     /// Records a successful heartbeat
     pub(super) fn record_heartbeat(&self, peer_id: &WrappedPeerId) -> Result<(), GossipError> {
         Ok(self.global_state.record_heartbeat(peer_id)?)
@@ -164,3 +169,9 @@ impl GossipProtocolExecutor {
         Ok(self.global_state.construct_heartbeat()?)
     }
 }
+
+/// The number of seconds without a successful heartbeat after which a peer
+/// heartbeated on the given interval is expired
+fn expiry_window_secs(heartbeat_interval_ms: u64, max_missed_heartbeats: u64) -> u64 {
+    heartbeat_interval_ms * max_missed_heartbeats / 1000
+}
@@ -16,17 +16,28 @@ impl HeartbeatTimer {
     /// Spawns two timers, one for sending intra-cluster heartbeat messages,
     /// another for inter-cluster The interval parameters specify how often
     /// the timers should cycle through all peers in their target list
+    ///
+    /// A third timer enqueues a sweep of expired peers on the given interval
     pub fn new(
         job_queue: GossipServerQueue,
         intra_cluster_interval_ms: u64,
         inter_cluster_interval_ms: u64,
+        expiry_sweep_interval_ms: u64,
         global_state: State,
     ) -> Self {
         // Narrowing cast is okay, precision is not important here
         let intra_cluster_wait_period = Duration::from_millis(intra_cluster_interval_ms);
         let inter_cluster_wait_period = Duration::from_millis(inter_cluster_interval_ms);
 
+        let expiry_sweep_period = Duration::from_millis(expiry_sweep_interval_ms);
+
         // Begin the timing loops
+        let job_queue_clone = job_queue.clone();
+        thread::Builder::new()
+            .name("peer-expiry-timer".to_string())
+            .spawn(move || Self::expiry_sweep_loop(job_queue_clone, expiry_sweep_period))
+            .unwrap();
+
         let job_queue_clone = job_queue.clone();
         let global_state_clone = global_state.clone();
         thread::Builder::new()
@@ -98,4 +109,19 @@ impl HeartbeatTimer {
             }
         }
     }
+
+    /// Timing loop for peer expiry sweeps
+    #[allow(clippy::needless_pass_by_value)]
+    fn expiry_sweep_loop(
+        job_queue: GossipServerQueue,
+        wait_period: Duration,
+    ) -> Result<(), GossipError> {
+        loop {
+            if let Err(err) = job_queue.send(GossipServerJob::SweepExpiredPeers) {
+                return Err(GossipError::TimerFailed(err.to_string()));
+            }
+
+            thread::sleep(wait_period);
+        }
+    }
 }
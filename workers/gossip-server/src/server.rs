@@ -4,7 +4,7 @@
 //! This file groups logic for creating the server as well as the central
 //! dispatch/execution loop of the workers
 
-use arbitrum_client::client::ArbitrumClient;
+use arbitrum_client::traits::DarkpoolClient;
 use common::{
     default_wrapper::DefaultWrapper,
     new_async_shared,
@@ -13,7 +13,10 @@ use common::{
 };
 use gossip_api::{
     pubsub::PubsubMessage,
-    request_response::{heartbeat::BootstrapRequest, GossipRequest, GossipResponse},
+    request_response::{
+        heartbeat::BootstrapRequest, orderbook::OrderBookSnapshotRequest, GossipRequest,
+        GossipResponse,
+    },
 };
 use job_types::{
     gossip_server::{GossipServerJob, GossipServerQueue, GossipServerReceiver},
@@ -22,6 +25,7 @@ use job_types::{
 use lru::LruCache;
 use state::State;
 use std::{
+    collections::HashMap,
     num::NonZeroUsize,
     thread::{self, Builder, JoinHandle},
     time::Duration,
@@ -29,9 +33,15 @@ use std::{
 use tracing::{error, info};
 use util::err_str;
 
-use crate::peer_discovery::{
-    heartbeat::{CLUSTER_HEARTBEAT_INTERVAL_MS, EXPIRY_CACHE_SIZE, HEARTBEAT_INTERVAL_MS},
-    heartbeat_timer::HeartbeatTimer,
+use crate::{
+    orderbook::SnapshotProgress,
+    peer_discovery::{
+        heartbeat::{
+            CLUSTER_HEARTBEAT_INTERVAL_MS, EXPIRY_CACHE_SIZE, HEARTBEAT_INTERVAL_MS,
+            PEER_EXPIRY_SWEEP_INTERVAL_MS,
+        },
+        heartbeat_timer::HeartbeatTimer,
+    },
 };
 
 use super::{errors::GossipError, worker::GossipServerConfig};
@@ -65,11 +75,12 @@ impl GossipServer {
     /// bootstrap peers and then advertising the local node's presence to the
     /// cluster
     pub fn bootstrap_into_network(&self) -> Result<(), GossipError> {
-        // Bootstrap into the network in two steps:
+        // Bootstrap into the network in four steps:
         //  1. Forward all bootstrap addresses to the network manager so it may dial
         //     them
         //  2. Send bootstrap requests to all bootstrapping peers
-        //  3. Send heartbeats to all peers for state sync
+        //  3. Request a snapshot of each bootstrapping peer's order book
+        //  4. Send heartbeats to all peers for state sync
         // Wait until all peers have been indexed before sending requests to give async
         // network manager time to index the peers in the case that these
         // messages are processed concurrently
@@ -91,7 +102,14 @@ impl GossipServer {
             self.config.network_sender.send(req).map_err(err_str!(GossipError::SendMessage))?;
         }
 
-        // 3. Send heartbeats to all known peers to sync state
+        // 3. Request order book snapshots from the bootstrapping peers
+        let req = GossipRequest::OrderBookSnapshot(OrderBookSnapshotRequest::first_page());
+        for (peer_id, _) in self.config.bootstrap_servers.iter() {
+            let req = NetworkManagerJob::request(*peer_id, req.clone());
+            self.config.network_sender.send(req).map_err(err_str!(GossipError::SendMessage))?;
+        }
+
+        // 4. Send heartbeats to all known peers to sync state
         let peer_ids = self.state().get_all_peers_ids(false /* include_self */)?;
         for peer in peer_ids.into_iter() {
             self.config
@@ -145,6 +163,9 @@ pub struct GossipProtocolExecutor {
     /// a peer is expired, it cannot be incorrectly re-discovered for some
     /// time, until its expiry has had time to propagate
     pub peer_expiry_cache: SharedLRUCache,
+    /// The progress of each peer's order book snapshot being followed by the
+    /// local node
+    pub(crate) snapshot_progress: AsyncShared<HashMap<WrappedPeerId, SnapshotProgress>>,
     /// The channel on which to receive jobs
     pub job_receiver: DefaultWrapper<Option<GossipServerReceiver>>,
     /// The channel to send outbound network requests on
@@ -173,6 +194,7 @@ impl GossipProtocolExecutor {
 
         Ok(Self {
             peer_expiry_cache,
+            snapshot_progress: new_async_shared(HashMap::new()),
             job_receiver: DefaultWrapper::new(Some(job_receiver)),
             network_channel,
             global_state,
@@ -182,8 +204,8 @@ impl GossipProtocolExecutor {
     }
 
     /// Shorthand to fetch the arbitrum client from the config
-    pub(super) fn arbitrum_client(&self) -> &ArbitrumClient {
-        &self.config.arbitrum_client
+    pub(super) fn arbitrum_client(&self) -> &dyn DarkpoolClient {
+        self.config.arbitrum_client.as_ref()
     }
 
     /// Runs the executor loop
@@ -193,11 +215,12 @@ impl GossipProtocolExecutor {
     ) -> Result<(), GossipError> {
         info!("Starting executor loop for heartbeat protocol executor...");
 
-        // Start a timer to enqueue outbound heartbeats
+        // Start a timer to enqueue outbound heartbeats and peer expiry sweeps
         HeartbeatTimer::new(
             job_sender,
             CLUSTER_HEARTBEAT_INTERVAL_MS,
             HEARTBEAT_INTERVAL_MS,
+            PEER_EXPIRY_SWEEP_INTERVAL_MS,
             self.global_state.clone(),
         );
 
@@ -230,6 +253,7 @@ impl GossipProtocolExecutor {
     async fn handle_job(&self, job: GossipServerJob) -> Result<(), GossipError> {
         match job {
             GossipServerJob::ExecuteHeartbeat(peer_id) => self.send_heartbeat(peer_id).await?,
+            GossipServerJob::SweepExpiredPeers => self.sweep_expired_peers().await?,
             GossipServerJob::NetworkRequest(peer_id, req, response_chan) => {
                 let resp = self.handle_request(peer_id, req).await?;
                 let job = NetworkManagerJob::response(resp, response_chan);
@@ -259,6 +283,8 @@ impl GossipProtocolExecutor {
             },
             GossipRequest::PeerInfo(req) => self.handle_peer_info_req(req.peer_ids),
             GossipRequest::OrderInfo(req) => self.handle_order_info_request(&req.order_ids),
+            GossipRequest::ValidityProof(req) => self.handle_validity_proof_request(req.order_id),
+            GossipRequest::OrderBookSnapshot(req) => self.handle_order_book_snapshot_request(&req),
             req => Err(GossipError::UnhandledRequest(format!("{req:?}"))),
         }
     }
@@ -275,6 +301,10 @@ impl GossipProtocolExecutor {
                 self.handle_order_info_response(resp.order_info).await
             },
             GossipResponse::PeerInfo(resp) => self.handle_peer_info_resp(resp.peer_info).await,
+            GossipResponse::ValidityProof(resp) => self.handle_validity_proof_response(resp).await,
+            GossipResponse::OrderBookSnapshot(resp) => {
+                self.handle_order_book_snapshot_response(peer, resp).await
+            },
             resp => Err(GossipError::UnhandledRequest(format!("{resp:?}"))),
         }
     }
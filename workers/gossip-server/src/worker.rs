@@ -1,6 +1,6 @@
 //! Implements the `Worker` trait for the GossipServer
 
-use arbitrum_client::client::ArbitrumClient;
+use arbitrum_client::traits::DarkpoolClient;
 use common::default_wrapper::DefaultWrapper;
 use common::types::gossip::{ClusterId, WrappedPeerId};
 use common::types::CancelChannel;
@@ -9,6 +9,7 @@ use job_types::gossip_server::{GossipServerQueue, GossipServerReceiver};
 use job_types::network_manager::NetworkManagerQueue;
 use libp2p::Multiaddr;
 use state::State;
+use std::sync::Arc;
 use std::thread::{Builder, JoinHandle};
 use tokio::runtime::Builder as RuntimeBuilder;
 
@@ -29,8 +30,11 @@ pub struct GossipServerConfig {
     pub cluster_id: ClusterId,
     /// The servers to bootstrap into the network with
     pub bootstrap_servers: Vec<(WrappedPeerId, Multiaddr)>,
+    /// The number of consecutive heartbeat intervals a peer may miss before
+    /// it is expired
+    pub max_missed_heartbeats: u64,
     /// The arbitrum client used for querying contract state
-    pub arbitrum_client: ArbitrumClient,
+    pub arbitrum_client: Arc<dyn DarkpoolClient>,
     /// A reference to the relayer-global state
     pub global_state: State,
     /// A job queue to send outbound heartbeat requests on
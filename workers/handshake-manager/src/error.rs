@@ -20,6 +20,8 @@ pub enum HandshakeManagerError {
     Multiprover(String),
     /// Necessary price data was not available for a token pair
     NoPriceData(String),
+    /// A peer did not respond to a match proposal within the allotted retries
+    PriceAgreementTimeout(String),
     /// Error sending a message to the network
     SendMessage(String),
     /// Error while setting up the handshake manager
@@ -77,6 +77,11 @@ impl<O: Clone + Eq + Hash + Ord> HandshakeCache<O> {
         self.lru_cache.len()
     }
 
+    /// Returns the maximum number of elements the cache holds
+    pub fn capacity(&self) -> usize {
+        self.lru_cache.cap().get()
+    }
+
     /// Computes the cache tuple from a given pair of identifiers
     ///
     /// The ordering of identifiers in the cache tuple is defined abstractly by
@@ -104,6 +109,17 @@ impl<O: Clone + Eq + Hash + Ord> HandshakeCache<O> {
         );
     }
 
+    /// Clear the invisibility window on the given pair, allowing it to be
+    /// scheduled again
+    ///
+    /// A pair marked completed remains cached
+    pub fn clear_invisible(&mut self, o1: O, o2: O) {
+        let key = Self::cache_tuple(o1, o2);
+        if let Some(HandshakeCacheState::Invisible { .. }) = self.lru_cache.peek(&key) {
+            self.lru_cache.pop(&key);
+        }
+    }
+
     /// Checks whether a given pair is cached
     pub fn contains(&self, o1: O, o2: O) -> bool {
         // If the cache contains the entry in the `Invisible` state and the invisibility
@@ -156,4 +172,17 @@ mod handshake_cache_tests {
         assert!(cache.contains(6, 7));
         assert!(cache.contains(7, 6));
     }
+
+    /// Tests that clearing a pair's invisibility leaves completed pairs cached
+    #[test]
+    fn test_clear_invisible() {
+        let mut cache = HandshakeCache::new(2 /* max_size */);
+        cache.mark_invisible(1, 2);
+        cache.mark_completed(3, 4);
+
+        cache.clear_invisible(2, 1);
+        cache.clear_invisible(3, 4);
+        assert!(!cache.contains(1, 2));
+        assert!(cache.contains(3, 4));
+    }
 }
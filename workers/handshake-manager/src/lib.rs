@@ -12,5 +12,6 @@
 pub mod error;
 mod handshake_cache;
 pub mod manager;
+mod rate_limiter;
 pub mod state;
 pub mod worker;
@@ -6,22 +6,25 @@ pub mod r#match;
 mod price_agreement;
 pub(crate) mod scheduler;
 
-use circuit_types::r#match::MatchResult;
+use ark_mpc::network::QuicTwoPartyNet;
+use circuit_types::{r#match::MatchResult, Amount};
 use common::{
     default_wrapper::{DefaultOption, DefaultWrapper},
     new_async_shared,
     types::{
         gossip::WrappedPeerId,
-        handshake::{ConnectionRole, HandshakeState},
+        handshake::{
+            ConnectionRole, HandshakeState, MatchReceipt, OrderMatchStats, PriceSelection,
+        },
         proof_bundles::{MatchBundle, OrderValidityProofBundle},
-        tasks::{SettleMatchTaskDescriptor, TaskDescriptor, TaskIdentifier},
+        tasks::{SettleMatchTaskDescriptor, TaskDescriptor, TaskIdentifier, TaskOutput},
         token::Token,
         wallet::OrderIdentifier,
         CancelChannel,
     },
 };
-use constants::HANDSHAKE_STATUS_TOPIC;
-use external_api::bus_message::SystemBusMessage;
+use constants::{SystemCurveGroup, HANDSHAKE_STATUS_TOPIC};
+use external_api::bus_message::{SystemBusMessage, MATCH_VOLUME_TOPIC};
 use futures::executor::block_on;
 use gossip_api::{
     pubsub::{
@@ -33,21 +36,25 @@ use gossip_api::{
     },
 };
 use job_types::{
-    handshake_manager::{HandshakeExecutionJob, HandshakeManagerReceiver},
+    handshake_manager::{HandshakeExecutionJob, HandshakeManagerReceiver, OrderMatchStatsLedger},
     network_manager::{NetworkManagerJob, NetworkManagerQueue},
     price_reporter::PriceReporterQueue,
     task_driver::{new_task_notification, TaskDriverJob, TaskDriverQueue},
 };
 use libp2p::request_response::ResponseChannel;
 use rand::{seq::SliceRandom, thread_rng};
-use renegade_metrics::helpers::record_match_volume;
+use renegade_metrics::{
+    helpers::record_match_volume, match_volume::MATCH_VOLUMES, registry::RELAYER_METRICS,
+};
 use state::State;
 use std::{
+    collections::HashMap,
+    sync::Arc,
     thread::JoinHandle,
-    time::{SystemTime, UNIX_EPOCH},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 use system_bus::SystemBus;
-use tracing::{error, info, info_span, Instrument};
+use tracing::{error, info, info_span, warn, Instrument};
 use util::err_str;
 use uuid::Uuid;
 
@@ -61,18 +68,20 @@ use self::{
 use super::{
     error::HandshakeManagerError,
     handshake_cache::{HandshakeCache, SharedHandshakeCache},
+    rate_limiter::PeerRateLimiter,
     state::HandshakeStateIndex,
-    worker::HandshakeManagerConfig,
+    worker::{HandshakeExecutorConfig, HandshakeManagerConfig},
 };
 
 // -------------
 // | Constants |
 // -------------
 
-/// The size of the LRU handshake cache
-pub(super) const HANDSHAKE_CACHE_SIZE: usize = 500;
-/// The number of threads executing handshakes
-pub(super) const HANDSHAKE_EXECUTOR_N_THREADS: usize = 8;
+/// The amount of time to wait for a peer to respond with a missing validity
+/// proof before aborting a match
+pub(super) const VALIDITY_PROOF_REQUEST_TIMEOUT_MS: u64 = 5_000; // 5 seconds
+/// The interval at which the volume matched on each pair is published
+const MATCH_VOLUME_PUBLISH_INTERVAL_MS: u64 = 10_000; // 10 seconds
 
 // -----------
 // | Helpers |
@@ -83,6 +92,19 @@ fn get_timestamp_millis() -> u64 {
     SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis().try_into().unwrap()
 }
 
+/// The latest receipt time in milliseconds of an order that has rested for
+/// `min_rest_ms` as of `now_ms`
+///
+/// Without a rest period every order is eligible, including those received in
+/// the current second
+pub(crate) fn rest_cutoff(min_rest_ms: u64, now_ms: u64) -> u64 {
+    if min_rest_ms == 0 {
+        return u64::MAX;
+    }
+
+    now_ms.saturating_sub(min_rest_ms)
+}
+
 // ------------------------
 // | Manager and Executor |
 // ------------------------
@@ -123,6 +145,32 @@ pub struct HandshakeExecutor {
     pub(crate) task_queue: TaskDriverQueue,
     /// The system bus used to publish internal broadcast messages
     pub(crate) system_bus: SystemBus<SystemBusMessage>,
+    /// The minimum base amount the internal matching engine will settle a
+    /// match for, keyed by base token
+    pub(crate) min_match_base_amounts: Arc<HashMap<Token, Amount>>,
+    /// The amount of time to wait for a peer to respond to a match proposal
+    /// before resending it
+    pub(crate) price_agreement_timeout: Duration,
+    /// The number of times a match proposal is resent to an unresponsive peer
+    /// before the handshake is failed
+    pub(crate) price_agreement_retries: usize,
+    /// The policy by which the internal matching engine selects the execution
+    /// price of a match
+    pub(crate) price_selection: PriceSelection,
+    /// The spread from the midpoint, in basis points, at which the
+    /// maker-favorable and taker-favorable policies execute a match
+    pub(crate) price_selection_spread_bps: u32,
+    /// The number of handshake messages per second a peer may send before its
+    /// messages are dropped, zero disables the limit
+    pub(crate) handshake_rate_limit: u32,
+    /// Whether to allow proposing a match between two orders managed by the
+    /// same wallet to a peer, internal matches never pair orders in one wallet
+    pub(crate) allow_self_trades: bool,
+    /// The minimum time in milliseconds an order must rest after it is
+    /// received before it is considered for a match
+    pub(crate) min_rest_ms: u64,
+    /// The match statistics of each locally managed order
+    pub(crate) order_stats: OrderMatchStatsLedger,
     /// The channel on which the coordinator thread may cancel handshake
     /// execution
     pub(crate) cancel: CancelChannel,
@@ -130,41 +178,47 @@ pub struct HandshakeExecutor {
 
 impl HandshakeExecutor {
     /// Create a new protocol executor
-    #[allow(clippy::too_many_arguments)]
-    pub fn new(
-        job_channel: HandshakeManagerReceiver,
-        network_channel: NetworkManagerQueue,
-        price_reporter_job_queue: PriceReporterQueue,
-        global_state: State,
-        task_queue: TaskDriverQueue,
-        system_bus: SystemBus<SystemBusMessage>,
-        cancel: CancelChannel,
-    ) -> Result<Self, HandshakeManagerError> {
+    pub fn new(config: HandshakeExecutorConfig) -> Result<Self, HandshakeManagerError> {
         // Build the handshake cache and state machine structures
-        let handshake_cache = new_async_shared(HandshakeCache::new(HANDSHAKE_CACHE_SIZE));
-        let handshake_state_index = HandshakeStateIndex::new(global_state.clone());
+        let handshake_cache = new_async_shared(HandshakeCache::new(config.handshake_cache_size));
+        let handshake_state_index = HandshakeStateIndex::new(config.global_state.clone());
 
         Ok(Self {
             handshake_cache,
             handshake_state_index,
-            job_channel: DefaultWrapper::new(Some(job_channel)),
-            network_channel,
-            price_reporter_job_queue,
-            global_state,
-            task_queue,
-            system_bus,
-            cancel,
+            job_channel: DefaultWrapper::new(Some(config.job_channel)),
+            network_channel: config.network_channel,
+            price_reporter_job_queue: config.price_reporter_job_queue,
+            global_state: config.global_state,
+            task_queue: config.task_queue,
+            system_bus: config.system_bus,
+            min_match_base_amounts: Arc::new(config.min_match_base_amounts),
+            price_agreement_timeout: Duration::from_millis(config.price_agreement_timeout_ms),
+            price_agreement_retries: config.price_agreement_retries,
+            price_selection: config.price_selection,
+            price_selection_spread_bps: config.price_selection_spread_bps,
+            handshake_rate_limit: config.handshake_rate_limit,
+            allow_self_trades: config.allow_self_trades,
+            min_rest_ms: config.min_rest_ms,
+            order_stats: config.order_stats,
+            cancel: config.cancel_channel,
         })
     }
 
     /// The main loop: dequeues jobs and forwards them to the thread pool
     pub async fn execution_loop(mut self) -> HandshakeManagerError {
         let mut job_channel = self.job_channel.take().unwrap();
+        let mut rate_limiter = PeerRateLimiter::new(self.handshake_rate_limit);
+        tokio::spawn(Self::publish_match_volumes(self.system_bus.clone()));
 
         loop {
             // Await the next job from the scheduler or elsewhere
             tokio::select! {
                 Some(job) = job_channel.recv() => {
+                    if !Self::admit_job(&job, &mut rate_limiter) {
+                        continue;
+                    }
+
                     let self_clone = self.clone();
                     tokio::task::spawn(async move {
                         if let Err(e) = self_clone.handle_handshake_job(job).await {
@@ -181,6 +235,44 @@ impl HandshakeExecutor {
             }
         }
     }
+
+    /// Periodically publish the cumulative volume matched on each pair
+    async fn publish_match_volumes(system_bus: SystemBus<SystemBusMessage>) {
+        let mut interval =
+            tokio::time::interval(Duration::from_millis(MATCH_VOLUME_PUBLISH_INTERVAL_MS));
+        loop {
+            interval.tick().await;
+            Self::publish_match_volume_snapshot(&system_bus);
+        }
+    }
+
+    /// Publish the cumulative volume matched on each pair, skipping the
+    /// snapshot if no worker listens on the topic
+    fn publish_match_volume_snapshot(system_bus: &SystemBus<SystemBusMessage>) {
+        let topic = MATCH_VOLUME_TOPIC.to_string();
+        if !system_bus.has_listeners(&topic) {
+            return;
+        }
+
+        let volumes = MATCH_VOLUMES.volumes();
+        system_bus.publish(topic, SystemBusMessage::MatchVolume { volumes });
+    }
+
+    /// Check an inbound job against the per-peer rate limiter, returning
+    /// whether the job should be executed
+    ///
+    /// A rejected job is dropped along with its response channel, which the
+    /// peer observes as a failed request
+    fn admit_job(job: &HandshakeExecutionJob, rate_limiter: &mut PeerRateLimiter) -> bool {
+        if let HandshakeExecutionJob::ProcessHandshakeMessage { peer_id, .. } = job
+            && !rate_limiter.check(peer_id)
+        {
+            warn!("peer {peer_id} exceeded handshake rate limit, dropping message");
+            return false;
+        }
+
+        true
+    }
 }
 
 /// Main event handler implementations; each of these methods are run inside the
@@ -192,6 +284,15 @@ impl HandshakeExecutor {
         job: HandshakeExecutionJob,
     ) -> Result<(), HandshakeManagerError> {
         match job {
+            // No new matches are started while the local node drains
+            HandshakeExecutionJob::PerformHandshake { order }
+            | HandshakeExecutionJob::InternalMatchingEngine { order }
+                if self.global_state.is_draining() =>
+            {
+                info!("local node is draining, skipping match on order {order}");
+                Ok(())
+            },
+
             // The timer thread has scheduled an outbound handshake
             HandshakeExecutionJob::PerformHandshake { order } => {
                 self.perform_handshake(order).await
@@ -244,75 +345,119 @@ impl HandshakeExecutor {
             // Indicates that the network manager has setup a network connection for a handshake to
             // execute over the local peer should connect and go forward with the MPC
             HandshakeExecutionJob::MpcNetSetup { request_id, party_id, net } => {
-                // Fetch the local handshake state to get an order for the MPC
-                let order_state =
-                    self.handshake_state_index.get_state(&request_id).await.ok_or_else(|| {
-                        HandshakeManagerError::InvalidRequest(format!(
-                            "request_id: {:?}",
-                            request_id
-                        ))
-                    })?;
-
-                // Mark the handshake cache entry as invisible to avoid re-scheduling
-                let o1_id = order_state.local_order_id;
-                let o2_id = order_state.peer_order_id;
-                self.handshake_cache.write().await.mark_invisible(o1_id, o2_id);
-
-                // Publish an internal event signalling that a match is beginning
-                self.system_bus.publish(
-                    HANDSHAKE_STATUS_TOPIC.to_string(),
-                    SystemBusMessage::HandshakeInProgress {
-                        local_order_id: order_state.local_order_id,
-                        peer_order_id: order_state.peer_order_id,
-                        timestamp: get_timestamp_millis(),
-                    },
-                );
-
-                // Fetch the validity proofs of the party
-                let (party0_proof, party1_proof) = {
-                    let local_validity_proof = self
-                        .global_state
-                        .get_validity_proofs(&order_state.local_order_id)?
-                        .ok_or_else(|| HandshakeManagerError::State(ERR_NO_PROOF.to_string()))?;
-                    let remote_validity_proof = self
-                        .global_state
-                        .get_validity_proofs(&order_state.peer_order_id)?
-                        .ok_or_else(|| HandshakeManagerError::State(ERR_NO_PROOF.to_string()))?;
-
-                    match order_state.role {
-                        ConnectionRole::Dialer => (local_validity_proof, remote_validity_proof),
-                        ConnectionRole::Listener => (remote_validity_proof, local_validity_proof),
+                let res = self.handle_mpc_net_setup(request_id, party_id, net).await;
+                if res.is_ok() {
+                    RELAYER_METRICS.handshakes_succeeded.increment();
+                } else {
+                    RELAYER_METRICS.handshakes_failed.increment();
+                    if let Some(state) = self.handshake_state_index.get_state(&request_id).await {
+                        self.update_order_stats(state.local_order_id, |s| s.failed += 1);
                     }
-                }; // locked_order_book released
-
-                // Run the MPC match process
-                let self_clone = self.clone();
-                let proof0_clone = party0_proof.clone();
-                let proof1_clone = party1_proof.clone();
-                let (match_bundle, match_result) = tokio::task::spawn_blocking(move || {
-                    block_on(self_clone.execute_match(
-                        request_id,
-                        party_id,
-                        proof0_clone,
-                        proof1_clone,
-                        net,
-                    ))
-                })
-                .await
-                .unwrap()?;
+                }
 
-                // Record the match in the cache
-                self.submit_match(party0_proof, party1_proof, order_state, match_bundle).await?;
-                self.record_completed_match(request_id, &match_result).await
+                res
             },
 
             // Indicates that in-flight MPCs on the given nullifier should be terminated
             HandshakeExecutionJob::MpcShootdown { nullifier } => {
                 self.handshake_state_index.shootdown_nullifier(nullifier).await
             },
+
+            // Indicates that a single in-flight match should be aborted
+            HandshakeExecutionJob::CancelMatch { request_id } => {
+                self.cancel_match(request_id).await
+            },
         }
     }
 
+    /// Abort the in-flight match with the given request ID
+    ///
+    /// Clears the invisibility window on the match's order pair so that the
+    /// pair may be scheduled again
+    async fn cancel_match(&self, request_id: Uuid) -> Result<(), HandshakeManagerError> {
+        let state = match self.handshake_state_index.cancel(&request_id).await? {
+            Some(state) => state,
+            None => {
+                warn!("no in-flight match to cancel for request {request_id}");
+                return Ok(());
+            },
+        };
+
+        info!("canceled match on request {request_id}");
+        let (local_order, peer_order) = (state.local_order_id, state.peer_order_id);
+        self.handshake_cache.write().await.clear_invisible(local_order, peer_order);
+        Ok(())
+    }
+
+    /// Execute the MPC for a handshake once the network manager has set up a
+    /// connection for it, then submit the resulting match for settlement
+    async fn handle_mpc_net_setup(
+        &self,
+        request_id: Uuid,
+        party_id: u64,
+        net: QuicTwoPartyNet<SystemCurveGroup>,
+    ) -> Result<(), HandshakeManagerError> {
+        // Fetch the local handshake state to get an order for the MPC
+        let order_state =
+            self.handshake_state_index.get_state(&request_id).await.ok_or_else(|| {
+                HandshakeManagerError::InvalidRequest(format!("request_id: {:?}", request_id))
+            })?;
+
+        // Mark the handshake cache entry as invisible to avoid re-scheduling
+        let o1_id = order_state.local_order_id;
+        let o2_id = order_state.peer_order_id;
+        self.handshake_cache.write().await.mark_invisible(o1_id, o2_id);
+
+        // Publish an internal event signalling that a match is beginning
+        self.system_bus.publish(
+            HANDSHAKE_STATUS_TOPIC.to_string(),
+            SystemBusMessage::HandshakeInProgress {
+                local_order_id: order_state.local_order_id,
+                peer_order_id: order_state.peer_order_id,
+                timestamp: get_timestamp_millis(),
+            },
+        );
+
+        // Fetch the validity proofs of the party
+        let (party0_proof, party1_proof) = {
+            let local_validity_proof = self
+                .global_state
+                .get_validity_proofs(&order_state.local_order_id)?
+                .ok_or_else(|| HandshakeManagerError::State(ERR_NO_PROOF.to_string()))?;
+            let remote_validity_proof =
+                match self.global_state.get_validity_proofs(&order_state.peer_order_id)? {
+                    Some(proof) => proof,
+                    None => self.fetch_validity_proof(order_state.peer_order_id).await?,
+                };
+
+            match order_state.role {
+                ConnectionRole::Dialer => (local_validity_proof, remote_validity_proof),
+                ConnectionRole::Listener => (remote_validity_proof, local_validity_proof),
+            }
+        }; // locked_order_book released
+
+        // Run the MPC match process
+        let self_clone = self.clone();
+        let proof0_clone = party0_proof.clone();
+        let proof1_clone = party1_proof.clone();
+        let (match_bundle, match_result) = tokio::task::spawn_blocking(move || {
+            block_on(self_clone.execute_match(
+                request_id,
+                party_id,
+                proof0_clone,
+                proof1_clone,
+                net,
+            ))
+        })
+        .await
+        .unwrap()?;
+
+        // Record the match in the cache
+        self.submit_match(party0_proof, party1_proof, order_state, match_bundle, &match_result)
+            .await?;
+        self.record_completed_match(request_id, &match_result).await
+    }
+
     // -----------
     // | Helpers |
     // -----------
@@ -378,21 +523,65 @@ impl HandshakeExecutor {
     async fn choose_match_proposal(&self, peer_order: OrderIdentifier) -> Option<OrderIdentifier> {
         let locked_handshake_cache = self.handshake_cache.read().await;
 
-        // Shuffle the locally managed orders to avoid always matching the same order
+        // Shuffle the rested, locally managed orders to avoid always matching the same
+        // order
         let mut rng = thread_rng();
-        let mut local_verified_orders = self.global_state.get_locally_matchable_orders().ok()?;
+        let local_orders = self.global_state.get_locally_matchable_orders().ok()?;
+        let mut local_verified_orders = self.filter_rested(local_orders).ok()?;
         local_verified_orders.shuffle(&mut rng);
 
-        // Choose the first order that isn't cached
+        // Choose the first order that isn't cached or a self-trade
         for order_id in local_verified_orders.iter() {
-            if !locked_handshake_cache.contains(*order_id, peer_order) {
-                return Some(*order_id);
+            if locked_handshake_cache.contains(*order_id, peer_order) {
+                continue;
+            }
+
+            match self.is_self_trade(order_id, &peer_order) {
+                Ok(false) => return Some(*order_id),
+                Ok(true) => {},
+                Err(e) => warn!("error checking self-trade for order {order_id}: {e}"),
             }
         }
 
         None
     }
 
+    /// The latest receipt time in milliseconds of an order that has rested for
+    /// the configured minimum duration
+    pub(crate) fn rest_cutoff(&self) -> u64 {
+        rest_cutoff(self.min_rest_ms, get_timestamp_millis())
+    }
+
+    /// Filter the given orders to those that have rested for the configured
+    /// minimum duration
+    fn filter_rested(
+        &self,
+        order_ids: Vec<OrderIdentifier>,
+    ) -> Result<Vec<OrderIdentifier>, HandshakeManagerError> {
+        let cutoff = self.rest_cutoff();
+        let orders = self.global_state.get_orders_batch(&order_ids)?;
+        Ok(orders.into_iter().flatten().filter(|o| o.received_by(cutoff)).map(|o| o.id).collect())
+    }
+
+    /// Whether matching the given orders is a disallowed self-trade, i.e. both
+    /// orders are managed by the same wallet
+    ///
+    /// A peer's order is only known to be managed by a local wallet if the
+    /// wallet is shared with the local cluster
+    fn is_self_trade(
+        &self,
+        order1: &OrderIdentifier,
+        order2: &OrderIdentifier,
+    ) -> Result<bool, HandshakeManagerError> {
+        if self.allow_self_trades {
+            return Ok(false);
+        }
+
+        let wallet1 = self.global_state.get_wallet_for_order(order1)?;
+        let wallet2 = self.global_state.get_wallet_for_order(order2)?;
+        Ok(wallet1.is_some() && wallet1 == wallet2)
+    }
+
     /// Record a match as completed in the various state objects
     async fn record_completed_match(
         &self,
@@ -416,10 +605,23 @@ impl HandshakeExecutor {
 
         // Record the volume of the match
         record_match_volume(match_result);
+        self.update_order_stats(state.local_order_id, |s| {
+            s.filled_amount += match_result.base_amount
+        });
 
         Ok(())
     }
 
+    /// Apply an update to the match statistics of the given order
+    pub(crate) fn update_order_stats<F: FnOnce(&mut OrderMatchStats)>(
+        &self,
+        order_id: OrderIdentifier,
+        update: F,
+    ) {
+        let mut stats = self.order_stats.write().unwrap();
+        update(stats.get_or_insert_mut(order_id, OrderMatchStats::default));
+    }
+
     /// Publish a cache sync message to the cluster and a local event indicating
     /// that a handshake has completed
     fn publish_completion_messages(
@@ -455,20 +657,23 @@ impl HandshakeExecutor {
     }
 
     /// Helper to spawn a task in the task driver that submits a match and
-    /// settles its result
+    /// settles its result, returning a receipt of the settlement
     async fn submit_match(
         &self,
         party0_proof: OrderValidityProofBundle,
         party1_proof: OrderValidityProofBundle,
         handshake_state: HandshakeState,
         match_bundle: MatchBundle,
-    ) -> Result<(), HandshakeManagerError> {
+        match_result: &MatchResult,
+    ) -> Result<MatchReceipt, HandshakeManagerError> {
         // Enqueue a task to settle the match
         let wallet_id = self
             .global_state
             .get_wallet_for_order(&handshake_state.local_order_id)?
             .ok_or_else(|| HandshakeManagerError::State(ERR_NO_WALLET.to_string()))?;
 
+        let local_order_id = handshake_state.local_order_id;
+        let peer_order_id = handshake_state.peer_order_id;
         let task: TaskDescriptor = SettleMatchTaskDescriptor::new(
             wallet_id,
             handshake_state,
@@ -485,14 +690,48 @@ impl HandshakeExecutor {
         let job = TaskDriverJob::RunImmediate { task_id, wallet_ids, task };
         self.task_queue.send(job).map_err(err_str!(HandshakeManagerError::SendMessage))?;
 
-        self.await_settlement_task(task_id).await
+        self.await_match_receipt(task_id, local_order_id, peer_order_id, match_result).await
+    }
+
+    /// Await the settlement task of a match, then publish a receipt of the
+    /// settlement on the handshake status topic
+    async fn await_match_receipt(
+        &self,
+        task_id: TaskIdentifier,
+        local_order_id: OrderIdentifier,
+        peer_order_id: OrderIdentifier,
+        match_result: &MatchResult,
+    ) -> Result<MatchReceipt, HandshakeManagerError> {
+        let settlement_block = match self.await_settlement_task(task_id).await? {
+            Some(TaskOutput::SettleMatch { settlement_block }) => settlement_block,
+            None => None,
+        };
+
+        let receipt = MatchReceipt {
+            task_id,
+            local_order_id,
+            peer_order_id,
+            base_amount: match_result.base_amount,
+            quote_amount: match_result.quote_amount,
+            settlement_block,
+        };
+        self.system_bus.publish(
+            HANDSHAKE_STATUS_TOPIC.to_string(),
+            SystemBusMessage::MatchSettled {
+                receipt: receipt.clone(),
+                timestamp: get_timestamp_millis(),
+            },
+        );
+
+        Ok(receipt)
     }
 
-    /// Await match settlement given the ID of the settlement task
+    /// Await match settlement given the ID of the settlement task, returning
+    /// the task's output
     async fn await_settlement_task(
         &self,
         task_id: TaskIdentifier,
-    ) -> Result<(), HandshakeManagerError> {
+    ) -> Result<Option<TaskOutput>, HandshakeManagerError> {
         // Create a oneshot channel to await the task's completion
         let (rx, job) = new_task_notification(task_id);
         self.task_queue.send(job).map_err(err_str!(HandshakeManagerError::SendMessage))?;
@@ -503,3 +742,431 @@ impl HandshakeExecutor {
             .map_err(err_str!(HandshakeManagerError::TaskError)) // TaskDriverError
     }
 }
+
+/// Helpers for building executors in tests
+#[cfg(test)]
+pub(crate) mod test_helpers {
+    use std::collections::HashMap;
+
+    use common::types::{handshake::PriceSelection, new_cancel_channel};
+    use job_types::{
+        handshake_manager::{new_handshake_manager_queue, new_order_match_stats_ledger},
+        network_manager::new_network_manager_queue,
+        price_reporter::new_price_reporter_queue,
+        task_driver::new_task_driver_queue,
+    };
+    use state::State;
+    use system_bus::SystemBus;
+
+    use crate::worker::HandshakeExecutorConfig;
+
+    /// Build an executor config with default parameters on the given state
+    ///
+    /// The receivers of the config's queues are dropped, tests that inspect
+    /// the executor's outbound jobs replace the relevant queue
+    pub(crate) fn mock_executor_config(state: State) -> HandshakeExecutorConfig {
+        let (_, job_channel) = new_handshake_manager_queue();
+        let (network_channel, _network_recv) = new_network_manager_queue();
+        let (price_reporter_job_queue, _price_reporter_recv) = new_price_reporter_queue();
+        let (task_queue, _task_recv) = new_task_driver_queue();
+        let (_cancel_sender, cancel_channel) = new_cancel_channel();
+
+        HandshakeExecutorConfig {
+            job_channel,
+            network_channel,
+            price_reporter_job_queue,
+            global_state: state,
+            task_queue,
+            system_bus: SystemBus::new(),
+            min_match_base_amounts: HashMap::new(),
+            handshake_cache_size: 500,
+            price_agreement_timeout_ms: 10_000,
+            price_agreement_retries: 2,
+            price_selection: PriceSelection::Midpoint,
+            price_selection_spread_bps: 0,
+            handshake_rate_limit: 100,
+            allow_self_trades: false,
+            min_rest_ms: 0,
+            order_stats: new_order_match_stats_ledger(),
+            cancel_channel,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use circuit_types::{
+        balance::Balance,
+        fixed_point::FixedPoint,
+        order::{Order, OrderSide},
+        r#match::MatchResult,
+    };
+    use common::types::{
+        gossip::WrappedPeerId,
+        handshake::{ConnectionRole, OrderMatchStats},
+        network_order::test_helpers::dummy_network_order,
+        proof_bundles::mocks::{dummy_validity_proof_bundle, dummy_validity_witness_bundle},
+        tasks::{TaskIdentifier, TaskOutput},
+        token::Token,
+        wallet::{OrderIdentifier, WalletIdentifier},
+        wallet_mocks::{mock_empty_wallet, mock_order},
+    };
+    use constants::HANDSHAKE_STATUS_TOPIC;
+    use crossbeam::channel::unbounded;
+    use external_api::bus_message::{SystemBusMessage, MATCH_VOLUME_TOPIC};
+    use gossip_api::request_response::handshake::{
+        HandshakeMessage, HandshakeMessageType, MatchRejectionReason, PriceVector,
+        ProposeMatchCandidate, RejectMatchCandidate,
+    };
+    use job_types::{
+        handshake_manager::{new_order_match_stats_ledger, HandshakeExecutionJob},
+        task_driver::{new_task_driver_queue, TaskDriverJob, TaskDriverQueue},
+    };
+    use num_bigint::BigUint;
+    use state::{test_helpers::mock_state, State};
+    use system_bus::SystemBus;
+    use util::get_current_time_seconds;
+    use uuid::Uuid;
+
+    use crate::worker::HandshakeExecutorConfig;
+
+    use super::{test_helpers::mock_executor_config, HandshakeExecutor};
+
+    /// Create an executor with default parameters on the given task queue and
+    /// system bus
+    fn mock_executor(
+        task_queue: TaskDriverQueue,
+        system_bus: SystemBus<SystemBusMessage>,
+    ) -> HandshakeExecutor {
+        mock_executor_with_state(mock_state(), task_queue, system_bus)
+    }
+
+    /// Create an executor with default parameters on the given state, task
+    /// queue, and system bus
+    fn mock_executor_with_state(
+        state: State,
+        task_queue: TaskDriverQueue,
+        system_bus: SystemBus<SystemBusMessage>,
+    ) -> HandshakeExecutor {
+        let config =
+            HandshakeExecutorConfig { task_queue, system_bus, ..mock_executor_config(state) };
+        HandshakeExecutor::new(config).unwrap()
+    }
+
+    /// Tests that the executor's handshake cache is sized from its config
+    #[tokio::test]
+    async fn test_custom_cache_size() {
+        let config = HandshakeExecutorConfig {
+            handshake_cache_size: 1_234,
+            ..mock_executor_config(mock_state())
+        };
+        let executor = HandshakeExecutor::new(config).unwrap();
+
+        assert_eq!(executor.handshake_cache.read().await.capacity(), 1_234);
+    }
+
+    /// Tests that a receipt is populated from the settlement task's output and
+    /// published once the task driver reports a successful settlement
+    #[tokio::test]
+    async fn test_match_receipt() {
+        let (task_queue, task_recv) = new_task_driver_queue();
+        let bus = SystemBus::new();
+        let mut reader = bus.subscribe(HANDSHAKE_STATUS_TOPIC.to_string());
+        let executor = mock_executor(task_queue, bus);
+
+        // Mock a task driver that settles the match in block 42
+        let task_id = TaskIdentifier::new_v4();
+        std::thread::spawn(move || match task_recv.recv().unwrap() {
+            TaskDriverJob::Notify { task_id: id, channel } => {
+                assert_eq!(id, task_id);
+                let output = TaskOutput::SettleMatch { settlement_block: Some(42) };
+                channel.send(Ok(Some(output))).unwrap();
+            },
+            _ => panic!("expected a notification request"),
+        });
+
+        let local_order_id = OrderIdentifier::new_v4();
+        let peer_order_id = OrderIdentifier::new_v4();
+        let match_result = MatchResult { base_amount: 10, quote_amount: 20, ..Default::default() };
+        let receipt = executor
+            .await_match_receipt(task_id, local_order_id, peer_order_id, &match_result)
+            .await
+            .unwrap();
+
+        assert_eq!(receipt.task_id, task_id);
+        assert_eq!(receipt.local_order_id, local_order_id);
+        assert_eq!(receipt.peer_order_id, peer_order_id);
+        assert_eq!(receipt.base_amount, 10);
+        assert_eq!(receipt.quote_amount, 20);
+        assert_eq!(receipt.settlement_block, Some(42));
+
+        match reader.next_message().await {
+            SystemBusMessage::MatchSettled { receipt: published, .. } => {
+                assert_eq!(published, receipt)
+            },
+            msg => panic!("unexpected message: {msg:?}"),
+        }
+    }
+
+    /// Tests that an order's stats count proposals, failures, and fills across
+    /// a sequence of match attempts
+    #[tokio::test]
+    async fn test_order_match_stats() {
+        let state = mock_state();
+        let local_order = dummy_network_order();
+        let peer_order = dummy_network_order();
+        state.add_order(local_order.clone()).unwrap();
+        state.add_order(peer_order.clone()).unwrap();
+
+        let order_stats = new_order_match_stats_ledger();
+        let config = HandshakeExecutorConfig {
+            order_stats: order_stats.clone(),
+            ..mock_executor_config(state)
+        };
+        let executor = HandshakeExecutor::new(config).unwrap();
+        let stats = || order_stats.read().unwrap().peek(&local_order.id).copied().unwrap();
+        let price = FixedPoint::from_integer(1);
+
+        // The peer rejects the first proposal
+        let request_id = Uuid::new_v4();
+        executor
+            .begin_handshake(
+                request_id,
+                ConnectionRole::Dialer,
+                peer_order.id,
+                local_order.id,
+                price,
+            )
+            .await
+            .unwrap();
+        let rejection = HandshakeMessage {
+            request_id,
+            message_type: HandshakeMessageType::Reject(RejectMatchCandidate {
+                peer_id: WrappedPeerId::random(),
+                peer_order: local_order.id,
+                sender_order: peer_order.id,
+                reason: MatchRejectionReason::NoPriceAgreement,
+            }),
+        };
+        executor.handle_handshake_message(request_id, rejection).await.unwrap();
+        assert_eq!(stats(), OrderMatchStats { proposed: 1, failed: 1, filled_amount: 0 });
+
+        // The second proposal completes a match
+        let request_id = Uuid::new_v4();
+        executor
+            .begin_handshake(
+                request_id,
+                ConnectionRole::Dialer,
+                peer_order.id,
+                local_order.id,
+                price,
+            )
+            .await
+            .unwrap();
+        let match_result = MatchResult { base_amount: 10, quote_amount: 20, ..Default::default() };
+        executor.record_completed_match(request_id, &match_result).await.unwrap();
+        assert_eq!(stats(), OrderMatchStats { proposed: 2, failed: 1, filled_amount: 10 });
+    }
+
+    /// Tests that canceling a match aborts it and makes its order pair
+    /// schedulable again, while a match on another pair continues
+    #[tokio::test]
+    async fn test_cancel_match() {
+        let state = mock_state();
+        let orders: Vec<_> = (0..4).map(|_| dummy_network_order()).collect();
+        for order in orders.iter() {
+            state.add_order(order.clone()).unwrap();
+        }
+
+        let (task_queue, _task_recv) = new_task_driver_queue();
+        let executor = mock_executor_with_state(state, task_queue, SystemBus::new());
+        let price = FixedPoint::from_integer(1);
+
+        // Begin a match on each of two order pairs
+        let mut matches = Vec::new();
+        for pair in orders.chunks(2) {
+            let request_id = Uuid::new_v4();
+            let (local, peer) = (pair[0].id, pair[1].id);
+            executor
+                .begin_handshake(request_id, ConnectionRole::Dialer, peer, local, price)
+                .await
+                .unwrap();
+
+            let (cancel_send, cancel_recv) = unbounded();
+            executor.handshake_state_index.in_progress(&request_id, cancel_send).await;
+            executor.handshake_cache.write().await.mark_invisible(local, peer);
+            matches.push((request_id, local, peer, cancel_recv));
+        }
+
+        // Cancel the first match
+        let (canceled_id, local, peer, canceled_recv) = &matches[0];
+        let job = HandshakeExecutionJob::CancelMatch { request_id: *canceled_id };
+        executor.handle_handshake_job(job).await.unwrap();
+
+        assert!(canceled_recv.try_recv().is_ok());
+        assert!(executor.handshake_state_index.get_state(canceled_id).await.is_none());
+        assert!(!executor.handshake_cache.read().await.contains(*local, *peer));
+
+        // The second match continues
+        let (running_id, local, peer, running_recv) = &matches[1];
+        assert!(running_recv.try_recv().is_err());
+        assert!(executor.handshake_state_index.get_state(running_id).await.is_some());
+        assert!(executor.handshake_cache.read().await.contains(*local, *peer));
+    }
+
+    /// Add a wallet to the state holding the given number of orders, each
+    /// ready for a match
+    async fn add_local_orders(state: &State, n: usize) -> Vec<OrderIdentifier> {
+        let mut wallet = mock_empty_wallet();
+        state.new_wallet(wallet.clone()).unwrap().await.unwrap();
+
+        let ids = (0..n).map(|_| OrderIdentifier::new_v4()).collect::<Vec<_>>();
+        for id in ids.iter() {
+            wallet.add_order(*id, mock_order()).unwrap();
+        }
+        state.update_wallet(wallet).unwrap().await.unwrap();
+
+        for id in ids.iter() {
+            let (proof, witness) = (dummy_validity_proof_bundle(), dummy_validity_witness_bundle());
+            state.add_local_order_validity_bundle(*id, proof, witness).unwrap().await.unwrap();
+        }
+        ids
+    }
+
+    /// Tests that a match proposal skips orders in the same wallet as the
+    /// peer's order
+    #[tokio::test]
+    async fn test_self_trade_skipped() {
+        const N_ATTEMPTS: usize = 10;
+        let state = mock_state();
+        let same_wallet = add_local_orders(&state, 2).await;
+        let (task_queue, _task_recv) = new_task_driver_queue();
+        let executor = mock_executor_with_state(state.clone(), task_queue, SystemBus::new());
+
+        // The only candidates are in the same wallet as the order
+        for _ in 0..N_ATTEMPTS {
+            assert_eq!(executor.choose_match_proposal(same_wallet[0]).await, None);
+        }
+
+        // An order in another wallet is proposed
+        let other_wallet = add_local_orders(&state, 1).await;
+        for _ in 0..N_ATTEMPTS {
+            let proposal = executor.choose_match_proposal(same_wallet[0]).await;
+            assert_eq!(proposal, Some(other_wallet[0]));
+        }
+    }
+
+    /// Tests that a peer's proposal on a local order that has not rested is
+    /// rejected
+    #[tokio::test]
+    async fn test_unrested_proposal_rejected() {
+        let state = mock_state();
+        let mut local_order = dummy_network_order();
+        local_order.timestamp = get_current_time_seconds();
+        state.add_order(local_order.clone()).unwrap();
+
+        let (task_queue, _task_recv) = new_task_driver_queue();
+        let mut executor = mock_executor_with_state(state, task_queue, SystemBus::new());
+        executor.min_rest_ms = 60_000;
+
+        let request_id = Uuid::new_v4();
+        let proposal = HandshakeMessage {
+            request_id,
+            message_type: HandshakeMessageType::Propose(ProposeMatchCandidate {
+                peer_id: WrappedPeerId::random(),
+                peer_order: local_order.id,
+                sender_order: OrderIdentifier::new_v4(),
+                price_vector: PriceVector(vec![]),
+            }),
+        };
+
+        let resp = executor.handle_handshake_message(request_id, proposal).await.unwrap();
+        match resp.map(|msg| msg.message_type) {
+            Some(HandshakeMessageType::Reject(RejectMatchCandidate {
+                reason: MatchRejectionReason::NotRested,
+                ..
+            })) => {},
+            msg => panic!("unexpected response: {msg:?}"),
+        }
+    }
+
+    /// Tests that a settled internal match accrues volume to its pair, and
+    /// that the volume is published on the match volume topic
+    #[tokio::test]
+    async fn test_internal_match_volume_published() {
+        // Mints unique to this test, as the volume ledger is shared
+        let base_mint = BigUint::from(0xb0a5e_u64);
+        let quote_mint = BigUint::from(0x90a7e_u64);
+        let price = FixedPoint::from_integer(10);
+        let buy = Order {
+            base_mint: base_mint.clone(),
+            quote_mint: quote_mint.clone(),
+            side: OrderSide::Buy,
+            amount: 100,
+            worst_case_price: price + FixedPoint::from_integer(1),
+        };
+        let sell = Order {
+            side: OrderSide::Sell,
+            worst_case_price: FixedPoint::from_integer(9),
+            ..buy.clone()
+        };
+
+        // Capitalize each order in its validity witness
+        let witness = |mint: &BigUint, amount| {
+            let mut bundle = dummy_validity_witness_bundle();
+            let mut commitment_witness = bundle.copy_commitment_witness();
+            commitment_witness.balance_send =
+                Balance { mint: mint.clone(), amount, ..Default::default() };
+            bundle.commitment_witness = Arc::new(commitment_witness);
+            bundle
+        };
+
+        // Mock a task driver that settles the match
+        let (task_queue, task_recv) = new_task_driver_queue();
+        std::thread::spawn(move || loop {
+            match task_recv.recv().unwrap() {
+                TaskDriverJob::RunImmediate { .. } => continue,
+                TaskDriverJob::Notify { channel, .. } => {
+                    channel.send(Ok(None)).unwrap();
+                    break;
+                },
+                _ => panic!("unexpected task driver job"),
+            }
+        });
+
+        let bus = SystemBus::new();
+        let mut reader = bus.subscribe(MATCH_VOLUME_TOPIC.to_string());
+        let executor = mock_executor(task_queue, bus.clone());
+        let did_match = executor
+            .try_match_and_settle(
+                buy,
+                sell,
+                OrderIdentifier::new_v4(),
+                OrderIdentifier::new_v4(),
+                WalletIdentifier::new_v4(),
+                WalletIdentifier::new_v4(),
+                price,
+                witness(&quote_mint, 10_000),
+                witness(&base_mint, 100),
+                dummy_validity_proof_bundle(),
+                dummy_validity_proof_bundle(),
+            )
+            .await
+            .unwrap();
+        assert!(did_match);
+
+        // The snapshot on the topic carries the pair's volume
+        HandshakeExecutor::publish_match_volume_snapshot(&bus);
+        let volumes = match reader.next_message().await {
+            SystemBusMessage::MatchVolume { volumes } => volumes,
+            msg => panic!("unexpected message: {msg:?}"),
+        };
+
+        let base = Token::from_addr_biguint(&base_mint);
+        let quote = Token::from_addr_biguint(&quote_mint);
+        let pair = volumes.into_iter().find(|v| v.base == base && v.quote == quote).unwrap();
+        assert_eq!((pair.base_volume, pair.quote_volume, pair.num_matches), (100, 1_000, 1));
+    }
+}
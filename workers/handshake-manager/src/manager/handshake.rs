@@ -4,26 +4,37 @@
 //!     2. Order selection
 //!     3. State management
 
+use std::time::Duration;
+
 use circuit_types::fixed_point::FixedPoint;
-use common::types::{handshake::ConnectionRole, wallet::OrderIdentifier};
+use common::types::{
+    handshake::ConnectionRole, proof_bundles::OrderValidityProofBundle, wallet::OrderIdentifier,
+};
+use constants::ORDER_STATE_CHANGE_TOPIC;
+use external_api::bus_message::SystemBusMessage;
 use gossip_api::{
     pubsub::{
         cluster::{ClusterManagementMessage, ClusterManagementMessageType},
         PubsubMessage,
     },
-    request_response::handshake::{
-        AcceptMatchCandidate, HandshakeMessage, HandshakeMessageType, MatchRejectionReason,
-        ProposeMatchCandidate, RejectMatchCandidate,
+    request_response::{
+        handshake::{
+            AcceptMatchCandidate, HandshakeMessage, HandshakeMessageType, MatchRejectionReason,
+            ProposeMatchCandidate, RejectMatchCandidate,
+        },
+        orderbook::ValidityProofRequest,
+        GossipRequest,
     },
 };
 use job_types::network_manager::{NetworkManagerControlSignal, NetworkManagerJob};
 use portpicker::pick_unused_port;
+use tracing::warn;
 use util::err_str;
 use uuid::Uuid;
 
 use crate::error::HandshakeManagerError;
 
-use super::HandshakeExecutor;
+use super::{HandshakeExecutor, VALIDITY_PROOF_REQUEST_TIMEOUT_MS};
 
 /// Error message emitted when a wallet cannot be looked up in the global state
 pub(crate) const ERR_NO_WALLET: &str = "wallet not found in state";
@@ -53,35 +64,34 @@ impl HandshakeExecutor {
             }
             let peer = managing_peer.unwrap();
 
-            // Send a handshake message to the given peer_id
+            // Determine the execution price for the new order
             let request_id = Uuid::new_v4();
             let price_vector = self.fetch_price_vector().await?;
+            let (base, quote) = self.token_pair_for_order(&local_order_id)?;
+            let (_, _, price) = price_vector
+                .find_pair(&base, &quote)
+                .ok_or_else(|| HandshakeManagerError::NoPriceData(ERR_NO_PRICE_DATA.to_string()))?;
+
+            self.begin_handshake(
+                request_id,
+                ConnectionRole::Dialer,
+                peer_order_id,
+                local_order_id,
+                FixedPoint::from_f64_round_down(price),
+            )
+            .await?;
+
+            // Propose the match to the given peer_id and await its agreement
             let message = HandshakeMessage {
                 request_id,
                 message_type: HandshakeMessageType::Propose(ProposeMatchCandidate {
                     peer_id: self.global_state.get_peer_id()?,
                     peer_order: peer_order_id,
                     sender_order: local_order_id,
-                    price_vector: price_vector.clone(),
+                    price_vector,
                 }),
             };
-            self.send_message(peer, message, None /* response_channel */)?;
-
-            // Determine the execution price for the new order
-            let (base, quote) = self.token_pair_for_order(&local_order_id)?;
-            let (_, _, price) = price_vector
-                .find_pair(&base, &quote)
-                .ok_or_else(|| HandshakeManagerError::NoPriceData(ERR_NO_PRICE_DATA.to_string()))?;
-
-            self.handshake_state_index
-                .new_handshake(
-                    request_id,
-                    ConnectionRole::Dialer,
-                    peer_order_id,
-                    local_order_id,
-                    FixedPoint::from_f64_round_down(price),
-                )
-                .await?;
+            self.negotiate_price_agreement(peer, message).await?;
         }
 
         Ok(())
@@ -103,7 +113,7 @@ impl HandshakeExecutor {
             // A peer has rejected a proposed match candidate, this can happen for a number of
             // reasons, enumerated by the `reason` field in the message
             HandshakeMessageType::Reject(resp) => {
-                self.handle_proposal_rejection(resp).await;
+                self.handle_proposal_rejection(request_id, resp).await;
                 Ok(None)
             },
 
@@ -119,9 +129,28 @@ impl HandshakeExecutor {
 
     /// Handles a proposal from a peer to initiate a match on a pair of orders
     ///
+    /// A proposal is handled once per request ID; a retried proposal receives
+    /// the response sent to the original rather than brokering another MPC
+    /// network
+    async fn handle_propose_match_candidate(
+        &self,
+        request_id: Uuid,
+        req: ProposeMatchCandidate,
+    ) -> Result<HandshakeMessage, HandshakeManagerError> {
+        if let Some(resp) = self.handshake_state_index.get_proposal_response(&request_id).await {
+            return Ok(resp);
+        }
+
+        let resp = self.respond_to_match_proposal(request_id, req).await?;
+        self.handshake_state_index.record_proposal_response(request_id, resp.clone()).await;
+        Ok(resp)
+    }
+
+    /// Decide whether to accept a peer's match proposal
+    ///
     /// The local peer first checks that this pair has not been matched, and
     /// then proceeds to broker an MPC network for it
-    async fn handle_propose_match_candidate(
+    async fn respond_to_match_proposal(
         &self,
         request_id: Uuid,
         req: ProposeMatchCandidate,
@@ -146,15 +175,14 @@ impl HandshakeExecutor {
             .ok_or_else(|| HandshakeManagerError::NoPriceData(ERR_NO_PRICE_DATA.to_string()))?;
 
         // Add an entry to the handshake state index
-        self.handshake_state_index
-            .new_handshake(
-                request_id,
-                ConnectionRole::Listener,
-                sender_order,
-                my_order,
-                FixedPoint::from_f64_round_down(execution_price),
-            )
-            .await?;
+        self.begin_handshake(
+            request_id,
+            ConnectionRole::Listener,
+            sender_order,
+            my_order,
+            FixedPoint::from_f64_round_down(execution_price),
+        )
+        .await?;
 
         // If the order pair has not been previously matched; broker an MPC connection
         // Choose a random open port to receive the connection on
@@ -198,8 +226,25 @@ impl HandshakeExecutor {
 
     /// Handles a rejected match proposal, possibly updating the cache for a
     /// missing entry
-    async fn handle_proposal_rejection(&self, resp: RejectMatchCandidate) {
-        let RejectMatchCandidate { peer_order: my_order, sender_order: peer_order, .. } = resp;
+    async fn handle_proposal_rejection(&self, request_id: Uuid, resp: RejectMatchCandidate) {
+        let RejectMatchCandidate { peer_order, sender_order, .. } = resp;
+        let state = match self
+            .handshake_state_index
+            .conclude_negotiation(&request_id, peer_order, sender_order)
+            .await
+        {
+            Some(state) => state,
+            None => {
+                warn!("dropping rejection of handshake {request_id}, not awaiting a response");
+                return;
+            },
+        };
+
+        // The negotiation has concluded, stop tracking the handshake
+        self.handshake_state_index.remove_handshake(&request_id).await;
+
+        let (my_order, peer_order) = (state.local_order_id, state.peer_order_id);
+        self.update_order_stats(my_order, |s| s.failed += 1);
         if let MatchRejectionReason::Cached = resp.reason {
             // Update the local cache
             self.handshake_cache.write().await.mark_completed(my_order, peer_order)
@@ -214,6 +259,15 @@ impl HandshakeExecutor {
         resp: AcceptMatchCandidate,
     ) -> Result<(), HandshakeManagerError> {
         let AcceptMatchCandidate { peer_id, order1, order2, .. } = resp;
+        if self
+            .handshake_state_index
+            .conclude_negotiation(&request_id, order1, order2)
+            .await
+            .is_none()
+        {
+            warn!("dropping acceptance of handshake {request_id}, not awaiting a response");
+            return Ok(());
+        }
 
         // Cache the result of a handshake
         self.handshake_cache.write().await.mark_completed(order1, order2);
@@ -234,14 +288,90 @@ impl HandshakeExecutor {
     // | Helpers |
     // -----------
 
+    /// Index a new handshake on the given order pair, counting it as a match
+    /// proposed on the local order
+    pub(crate) async fn begin_handshake(
+        &self,
+        request_id: Uuid,
+        role: ConnectionRole,
+        peer_order_id: OrderIdentifier,
+        local_order_id: OrderIdentifier,
+        execution_price: FixedPoint,
+    ) -> Result<(), HandshakeManagerError> {
+        self.handshake_state_index
+            .new_handshake(request_id, role, peer_order_id, local_order_id, execution_price)
+            .await?;
+        self.update_order_stats(local_order_id, |s| s.proposed += 1);
+
+        Ok(())
+    }
+
+    /// Request the validity proofs for an order from a peer in the cluster
+    /// managing it, and await their verification into the global state
+    ///
+    /// Errors if the proofs are not indexed within the request timeout
+    pub(crate) async fn fetch_validity_proof(
+        &self,
+        order_id: OrderIdentifier,
+    ) -> Result<OrderValidityProofBundle, HandshakeManagerError> {
+        let peer = self
+            .global_state
+            .get_peer_managing_order(&order_id)?
+            .ok_or_else(|| HandshakeManagerError::State(ERR_NO_PROOF.to_string()))?;
+
+        // Subscribe before sending the request so that the update is not missed, then
+        // check that the proof was not indexed in the meantime
+        let mut reader = self.system_bus.subscribe(ORDER_STATE_CHANGE_TOPIC.to_string());
+        if let Some(proof) = self.global_state.get_validity_proofs(&order_id)? {
+            return Ok(proof);
+        }
+
+        let req = GossipRequest::ValidityProof(ValidityProofRequest { order_id });
+        self.network_channel
+            .send(NetworkManagerJob::request(peer, req))
+            .map_err(err_str!(HandshakeManagerError::SendMessage))?;
+
+        // The gossip server verifies the response and attaches the proof to the order
+        let timeout = Duration::from_millis(VALIDITY_PROOF_REQUEST_TIMEOUT_MS);
+        let await_proof = async {
+            loop {
+                if let SystemBusMessage::OrderStateChange { order } = reader.next_message().await
+                    && order.id == order_id
+                    && let Some(proof) = order.validity_proofs
+                {
+                    return proof;
+                }
+            }
+        };
+
+        tokio::time::timeout(timeout, await_proof)
+            .await
+            .map_err(|_| HandshakeManagerError::State(ERR_NO_PROOF.to_string()))
+    }
+
     /// Check a match proposal, returning a rejection reason if the proposal
     /// cannot be accepted
     async fn check_match_proposal(
         &self,
         proposal: &ProposeMatchCandidate,
     ) -> Result<Option<MatchRejectionReason>, HandshakeManagerError> {
+        if self.global_state.is_draining() {
+            return Ok(Some(MatchRejectionReason::Draining));
+        }
+
         let ProposeMatchCandidate { peer_order: my_order, sender_order, price_vector, .. } =
             proposal;
+
+        // Do not accept handshakes on local orders that have not rested, the peer
+        // may not hold the local node's minimum rest duration
+        match self.global_state.get_order(my_order)? {
+            Some(order) if !order.received_by(self.rest_cutoff()) => {
+                return Ok(Some(MatchRejectionReason::NotRested));
+            },
+            Some(_) => {},
+            None => return Ok(Some(MatchRejectionReason::LocalOrderNotReady)),
+        }
+
         let peer_order_info = self.global_state.get_order(sender_order)?;
         if peer_order_info.is_none() || !peer_order_info.unwrap().ready_for_match() {
             return Ok(Some(MatchRejectionReason::NoValidityProof));
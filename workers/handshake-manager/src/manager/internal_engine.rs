@@ -1,15 +1,26 @@
 //! Defines logic for running the internal matching engine on a given order
 
-use circuit_types::{fixed_point::FixedPoint, order::Order};
+use std::collections::HashMap;
+
+use circuit_types::{
+    fixed_point::FixedPoint,
+    order::{Order, OrderSide},
+    r#match::MatchResult,
+    Amount,
+};
 use common::types::{
+    handshake::PriceSelection,
     network_order::NetworkOrder,
     proof_bundles::{OrderValidityProofBundle, OrderValidityWitnessBundle},
     tasks::{SettleMatchInternalTaskDescriptor, TaskDescriptor, TaskIdentifier},
+    token::Token,
     wallet::{OrderIdentifier, Wallet, WalletIdentifier},
 };
+use constants::HANDSHAKE_STATUS_TOPIC;
+use external_api::bus_message::SystemBusMessage;
 use job_types::task_driver::TaskDriverJob;
-use rand::{seq::SliceRandom, thread_rng};
-use tracing::{error, info};
+use renegade_metrics::helpers::record_match_volume;
+use tracing::{error, info, warn};
 use util::{err_str, matching_engine::match_orders, res_some};
 
 use crate::{
@@ -17,11 +28,76 @@ use crate::{
     manager::handshake::{ERR_NO_ORDER, ERR_NO_PRICE_DATA, ERR_NO_WALLET},
 };
 
-use super::HandshakeExecutor;
+use super::{get_timestamp_millis, HandshakeExecutor};
 
 /// Error emitted when proofs of validity cannot be found for an order
 const ERR_MISSING_PROOFS: &str = "validity proofs not found in global state";
 
+/// Whether a match's base amount falls below the configured minimum for its
+/// base token
+///
+/// Tokens without a configured minimum accept matches of any size
+fn below_min_match_size(
+    match_result: &MatchResult,
+    min_base_amounts: &HashMap<Token, Amount>,
+) -> bool {
+    let base = Token::from_addr_biguint(&match_result.base_mint);
+    min_base_amounts.get(&base).is_some_and(|min| match_result.base_amount < *min)
+}
+
+/// Select the execution price of a match between a taker order, the order the
+/// engine is run on, and a resting maker order under the given policy
+///
+/// The favorable policies move the price from the midpoint by `spread_bps` in
+/// the favored party's direction, then clamp it to the range both orders
+/// accept. The midpoint policy is never clamped, so a match it prices always
+/// executes at the midpoint. Returns `None` if the orders do not cross, if the
+/// midpoint falls outside either order's limit under the midpoint policy, or if
+/// the price is zero or unbounded
+fn select_execution_price(
+    policy: PriceSelection,
+    spread_bps: u32,
+    midpoint: FixedPoint,
+    taker: &Order,
+    maker: &Order,
+) -> Option<FixedPoint> {
+    // The spread moves the price up when it favors the seller
+    let maker_sells = maker.side == OrderSide::Sell;
+    let scale_bps = match policy {
+        PriceSelection::Midpoint => 10_000,
+        PriceSelection::MakerFavorable if maker_sells => 10_000 + spread_bps,
+        PriceSelection::MakerFavorable => 10_000u32.saturating_sub(spread_bps),
+        PriceSelection::TakerFavorable if maker_sells => 10_000u32.saturating_sub(spread_bps),
+        PriceSelection::TakerFavorable => 10_000 + spread_bps,
+    };
+    let price_f64 = midpoint.to_f64() * scale_bps as f64 / 10_000.;
+    let mut price = FixedPoint::from_f64_round_down(price_f64);
+
+    // Clamp the price to each order's limit, a midpoint execution is never
+    // clamped away from the midpoint
+    if policy != PriceSelection::Midpoint {
+        for order in [taker, maker] {
+            if !order.price_in_range(price) {
+                price = order.worst_case_price;
+            }
+        }
+    }
+    if !taker.price_in_range(price) || !maker.price_in_range(price) {
+        return None;
+    }
+
+    let price_f64 = price.to_f64();
+    (price_f64 > 0. && price_f64.is_finite()).then_some(price)
+}
+
+/// Sort the candidate orders for a match by priority, oldest first
+///
+/// Orders of equal age are ordered by their ID, so that cluster peers
+/// evaluating the same set of orders independently choose the same candidate
+fn prioritize_candidates(candidates: &mut [NetworkOrder]) {
+    candidates.sort_by_key(|order| (order.timestamp, order.id));
+}
+
 // ------------------------
 // | Matching Engine Impl |
 // ------------------------
@@ -40,10 +116,15 @@ impl HandshakeExecutor {
         order: OrderIdentifier,
     ) -> Result<(), HandshakeManagerError> {
         info!("Running internal matching engine on order {order}");
-        let mut rng = thread_rng();
 
-        // Lookup the order and its wallet
+        // Lookup the order and its wallet, an order that has not yet rested is left for
+        // the scheduler to match once it has
         let (network_order, wallet) = self.fetch_order_and_wallet(&order)?;
+        let rest_cutoff = self.rest_cutoff();
+        if !network_order.received_by(rest_cutoff) {
+            info!("order {order} has not rested, skipping internal match");
+            return Ok(());
+        }
         let my_order = wallet
             .orders
             .get(&network_order.id)
@@ -52,22 +133,29 @@ impl HandshakeExecutor {
             .get_validity_proof_and_witness(&network_order.id)?
             .ok_or_else(|| HandshakeManagerError::State(ERR_MISSING_PROOFS.to_string()))?;
 
-        // Sample a price to match the order at
-        let price = self.get_execution_price(&network_order.id).await?;
+        // Sample a midpoint price, the execution price of each candidate match is
+        // selected from it by the configured policy
+        let midpoint = self.get_execution_price(&network_order.id).await?;
 
-        // Fetch all other orders that are ready for matches
-        // Shuffle the ordering of the other orders for fairness
-        let mut other_orders = self.global_state.get_locally_matchable_orders()?;
-        other_orders.shuffle(&mut rng);
+        // Fetch all other rested orders that are ready for matches, in priority order
+        let matchable_ids = self.global_state.get_locally_matchable_orders()?;
+        let mut other_orders = self
+            .global_state
+            .get_orders_batch(&matchable_ids)?
+            .into_iter()
+            .flatten()
+            .filter(|o| o.received_by(rest_cutoff))
+            .collect::<Vec<_>>();
+        prioritize_candidates(&mut other_orders);
 
         // Match against each other order in the local book
-        for order_id in other_orders {
+        for order_id in other_orders.into_iter().map(|o| o.id) {
             // Same order
             if network_order.id == order_id {
                 continue;
             }
 
-            // Same wallet
+            // Same wallet, an internal match cannot settle a wallet against itself
             let other_wallet_id = self
                 .global_state
                 .get_wallet_for_order(&order_id)?
@@ -91,6 +179,19 @@ impl HandshakeExecutor {
 
             // If a match is successful, break from the loop, the settlement task will
             // re-enqueue a job for the internal engine to run again
+            let price = match select_execution_price(
+                self.price_selection,
+                self.price_selection_spread_bps,
+                midpoint,
+                my_order,
+                &order2,
+            ) {
+                Some(price) => price,
+                None => {
+                    warn!("no valid execution price for {} x {order_id}", network_order.id);
+                    continue;
+                },
+            };
             match self
                 .try_match_and_settle(
                     my_order.clone(),
@@ -126,7 +227,7 @@ impl HandshakeExecutor {
 
     /// Try a match and settle it if the two orders cross
     #[allow(clippy::too_many_arguments)]
-    async fn try_match_and_settle(
+    pub(super) async fn try_match_and_settle(
         &self,
         o1: Order,
         o2: Order,
@@ -148,7 +249,17 @@ impl HandshakeExecutor {
             None => return Ok(false),
         };
 
+        // Skip dust matches, the orders remain in the book and may be matched later
+        if below_min_match_size(&match_result, &self.min_match_base_amounts) {
+            info!(
+                "skipping match of {order_id1} x {order_id2}, base amount {} below minimum",
+                match_result.base_amount
+            );
+            return Ok(false);
+        }
+
         // Submit the match to the task driver
+        let settled_match = match_result.clone();
         let task: TaskDescriptor = SettleMatchInternalTaskDescriptor::new(
             price,
             order_id1,
@@ -169,10 +280,38 @@ impl HandshakeExecutor {
         let task_id = TaskIdentifier::new_v4();
         let job = TaskDriverJob::RunImmediate { task_id, wallet_ids, task };
         self.task_queue.send(job).map_err(err_str!(HandshakeManagerError::TaskError))?;
+        for order_id in [order_id1, order_id2] {
+            self.update_order_stats(order_id, |s| s.proposed += 1);
+        }
+
+        // Record the match and the policy that priced it
+        self.system_bus.publish(
+            HANDSHAKE_STATUS_TOPIC.to_string(),
+            SystemBusMessage::InternalMatch {
+                taker_order_id: order_id1,
+                maker_order_id: order_id2,
+                execution_price: price.to_f64(),
+                price_selection: self.price_selection,
+                timestamp: get_timestamp_millis(),
+            },
+        );
 
         // Await settlement, returning true to indicate a match was successfully
         // processed
-        self.await_settlement_task(task_id).await.map(|_| true)
+        let res = self.await_settlement_task(task_id).await;
+        for order_id in [order_id1, order_id2] {
+            self.update_order_stats(order_id, |s| match &res {
+                Ok(_) => s.filled_amount += settled_match.base_amount,
+                Err(_) => s.failed += 1,
+            });
+        }
+
+        // Record the volume of the match once settled
+        if res.is_ok() {
+            record_match_volume(&settled_match);
+        }
+
+        res.map(|_| true)
     }
 
     // -----------
@@ -227,3 +366,184 @@ impl HandshakeExecutor {
         Ok((order, wallet))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use circuit_types::{
+        balance::Balance,
+        fixed_point::FixedPoint,
+        order::{Order, OrderSide},
+    };
+    use common::types::{
+        handshake::PriceSelection,
+        network_order::{test_helpers::dummy_network_order, NetworkOrder},
+        token::Token,
+    };
+    use num_bigint::BigUint;
+    use rand::{seq::SliceRandom, thread_rng};
+    use util::matching_engine::match_orders;
+
+    use super::{below_min_match_size, prioritize_candidates, select_execution_price};
+
+    /// The base mint used in the tests
+    const BASE_MINT: u64 = 1;
+    /// The quote mint used in the tests
+    const QUOTE_MINT: u64 = 2;
+
+    /// Build a pair of crossing orders and balances capitalizing them, the
+    /// match between them is limited to `base_amount` by the seller's balance
+    fn crossing_orders(base_amount: u128) -> (Order, Order, Balance, Balance) {
+        let price = FixedPoint::from_integer(10);
+        let buy = Order {
+            quote_mint: BigUint::from(QUOTE_MINT),
+            base_mint: BigUint::from(BASE_MINT),
+            side: OrderSide::Buy,
+            amount: 1_000,
+            worst_case_price: price + FixedPoint::from_integer(1),
+        };
+        let sell = Order {
+            side: OrderSide::Sell,
+            worst_case_price: price - FixedPoint::from_integer(1),
+            ..buy.clone()
+        };
+
+        let buy_balance =
+            Balance { mint: BigUint::from(QUOTE_MINT), amount: 1_000_000, ..Default::default() };
+        let sell_balance =
+            Balance { mint: BigUint::from(BASE_MINT), amount: base_amount, ..Default::default() };
+
+        (buy, sell, buy_balance, sell_balance)
+    }
+
+    /// Tests that a match below the configured minimum for its base token is
+    /// skipped
+    #[test]
+    fn test_sub_threshold_match_skipped() {
+        let (o1, o2, b1, b2) = crossing_orders(5 /* base_amount */);
+        let price = FixedPoint::from_integer(10);
+        let match_result = match_orders(&o1, &o2, &b1, &b2, price).unwrap();
+        assert_eq!(match_result.base_amount, 5);
+
+        let base = Token::from_addr_biguint(&BigUint::from(BASE_MINT));
+        let mins = HashMap::from([(base, 10)]);
+        assert!(below_min_match_size(&match_result, &mins));
+    }
+
+    /// Tests that matches at or above the minimum, or on tokens without a
+    /// minimum, are not skipped
+    #[test]
+    fn test_threshold_boundaries() {
+        let (o1, o2, b1, b2) = crossing_orders(10 /* base_amount */);
+        let price = FixedPoint::from_integer(10);
+        let match_result = match_orders(&o1, &o2, &b1, &b2, price).unwrap();
+
+        let base = Token::from_addr_biguint(&BigUint::from(BASE_MINT));
+        let quote = Token::from_addr_biguint(&BigUint::from(QUOTE_MINT));
+        assert!(!below_min_match_size(&match_result, &HashMap::from([(base, 10)])));
+        assert!(!below_min_match_size(&match_result, &HashMap::from([(quote, 1_000)])));
+        assert!(!below_min_match_size(&match_result, &HashMap::new()));
+    }
+
+    /// A spread of 5% used in the price selection tests
+    const SPREAD_BPS: u32 = 500;
+
+    /// Tests the execution price selected under each policy for a crossing pair
+    #[test]
+    fn test_price_selection_policies() {
+        let (buy, sell, buy_balance, sell_balance) = crossing_orders(100 /* base_amount */);
+        let midpoint = FixedPoint::from_integer(10);
+
+        // The buy order is the taker, with a limit of 11; the seller's limit is 9
+        let cases = [
+            (PriceSelection::Midpoint, 10.),
+            (PriceSelection::MakerFavorable, 10.5),
+            (PriceSelection::TakerFavorable, 9.5),
+        ];
+        for (policy, expected) in cases {
+            let price = select_execution_price(policy, SPREAD_BPS, midpoint, &buy, &sell).unwrap();
+            assert_eq!(price, FixedPoint::from_f64_round_down(expected));
+
+            let match_result = match_orders(&buy, &sell, &buy_balance, &sell_balance, price);
+            assert_eq!(match_result.unwrap().quote_amount, (100. * expected) as u128);
+        }
+
+        // With the seller as the taker the favorable policies are reversed
+        let maker_favorable = PriceSelection::MakerFavorable;
+        let price = select_execution_price(maker_favorable, SPREAD_BPS, midpoint, &sell, &buy);
+        assert_eq!(price, Some(FixedPoint::from_f64_round_down(9.5)));
+        let taker_favorable = PriceSelection::TakerFavorable;
+        let price = select_execution_price(taker_favorable, SPREAD_BPS, midpoint, &sell, &buy);
+        assert_eq!(price, Some(FixedPoint::from_f64_round_down(10.5)));
+    }
+
+    /// Tests that a price outside either order's limit is clamped to the range
+    /// both orders accept
+    #[test]
+    fn test_price_selection_clamped() {
+        let (buy, sell, ..) = crossing_orders(100 /* base_amount */);
+        let midpoint = FixedPoint::from_integer(10);
+
+        // A 20% spread overshoots both the buyer's limit of 11 and the seller's
+        // limit of 9
+        let policy = PriceSelection::MakerFavorable;
+        let price = select_execution_price(policy, 2_000, midpoint, &buy, &sell);
+        assert_eq!(price, Some(buy.worst_case_price));
+        let policy = PriceSelection::TakerFavorable;
+        let price = select_execution_price(policy, 2_000, midpoint, &buy, &sell);
+        assert_eq!(price, Some(sell.worst_case_price));
+    }
+
+    /// Tests that no price is selected for orders that do not cross, for a
+    /// midpoint outside either order's limit, or when the price would be zero
+    #[test]
+    fn test_price_selection_rejected() {
+        let (buy, mut sell, ..) = crossing_orders(100 /* base_amount */);
+        let midpoint = FixedPoint::from_integer(10);
+        let policy = PriceSelection::Midpoint;
+
+        // The seller's limit is above the buyer's
+        let mut high_sell = sell.clone();
+        high_sell.worst_case_price = FixedPoint::from_integer(12);
+        assert!(select_execution_price(policy, 0, midpoint, &buy, &high_sell).is_none());
+
+        // A midpoint above the buyer's limit of 11, or below the seller's of 9, is
+        // not clamped to the limit and labelled a midpoint execution
+        for midpoint in [12, 8].map(FixedPoint::from_integer) {
+            assert!(select_execution_price(policy, 0, midpoint, &buy, &sell).is_none());
+        }
+
+        // A zero midpoint against a seller without a limit
+        sell.worst_case_price = FixedPoint::from_integer(0);
+        let zero = FixedPoint::from_integer(0);
+        assert!(select_execution_price(policy, 0, zero, &buy, &sell).is_none());
+    }
+
+    /// Tests that equal-priority candidates are ordered consistently
+    /// regardless of the order in which they are fetched
+    #[test]
+    fn test_equal_priority_tie_break() {
+        let mut rng = thread_rng();
+        let mut orders = (0..5).map(|_| dummy_network_order()).collect::<Vec<_>>();
+        orders.iter_mut().for_each(|o| o.timestamp = 100);
+
+        // An older order takes priority over the tied orders
+        let mut oldest = dummy_network_order();
+        oldest.timestamp = 1;
+        orders.push(oldest.clone());
+
+        let mut expected = orders.clone();
+        prioritize_candidates(&mut expected);
+        assert_eq!(expected[0].id, oldest.id);
+        assert!(expected[1..].windows(2).all(|w| w[0].id < w[1].id));
+
+        for _ in 0..10 {
+            orders.shuffle(&mut rng);
+            let mut candidates = orders.clone();
+            prioritize_candidates(&mut candidates);
+            let ids = |orders: &[NetworkOrder]| orders.iter().map(|o| o.id).collect::<Vec<_>>();
+            assert_eq!(ids(&candidates), ids(&expected));
+        }
+    }
+}
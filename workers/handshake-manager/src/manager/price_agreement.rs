@@ -2,14 +2,20 @@
 
 use std::collections::HashMap;
 
-use common::types::{exchange::PriceReporterState, token::Token, wallet::OrderIdentifier, Price};
-use gossip_api::request_response::handshake::PriceVector;
+use common::types::{
+    exchange::PriceReporterState, gossip::WrappedPeerId, token::Token, wallet::OrderIdentifier,
+    Price,
+};
+use constants::HANDSHAKE_STATUS_TOPIC;
+use external_api::bus_message::SystemBusMessage;
+use gossip_api::request_response::handshake::{HandshakeMessage, PriceVector};
 use job_types::price_reporter::{PriceReporterJob, PriceReporterQueue};
 use lazy_static::lazy_static;
 use tokio::sync::oneshot;
 use tracing::{error, instrument, warn};
+use uuid::Uuid;
 
-use super::{HandshakeExecutor, HandshakeManagerError};
+use super::{get_timestamp_millis, HandshakeExecutor, HandshakeManagerError};
 
 /// The maximum percentage deviation that is allowed between a peer's proposed
 /// price and a locally observed price before the proposed price is rejected
@@ -17,6 +23,11 @@ const MAX_PRICE_DEVIATION: f64 = 0.01;
 /// Error message emitted when price data could not be found for a given token
 /// pair
 const ERR_NO_PRICE_STREAM: &str = "price report not available for token pair";
+/// Error message emitted when a peer does not respond to a match proposal
+const ERR_PRICE_AGREEMENT_TIMEOUT: &str = "peer did not respond to match proposal";
+/// Error message emitted when a proposal is sent for a handshake that is not
+/// awaiting a response
+const ERR_NOT_NEGOTIATING: &str = "handshake is not awaiting a proposal response";
 
 // ----------------
 // | Quote Tokens |
@@ -130,6 +141,69 @@ pub fn init_price_streams(
 }
 
 impl HandshakeExecutor {
+    /// Send a match proposal to a peer and await its agreement on the proposed
+    /// prices
+    ///
+    /// The proposal is resent under the same request ID if the peer does not
+    /// respond within the price agreement timeout, up to the configured number
+    /// of retries. On exhaustion the handshake is failed and a
+    /// `HandshakeFailed` message is published
+    pub(super) async fn negotiate_price_agreement(
+        &self,
+        peer: WrappedPeerId,
+        proposal: HandshakeMessage,
+    ) -> Result<(), HandshakeManagerError> {
+        let request_id = proposal.request_id;
+        let notifier = self
+            .handshake_state_index
+            .negotiation_notifier(&request_id)
+            .await
+            .ok_or_else(|| HandshakeManagerError::State(ERR_NOT_NEGOTIATING.to_string()))?;
+
+        for attempt in 0..=self.price_agreement_retries {
+            if attempt > 0 {
+                warn!("peer {peer} did not respond to match proposal, retry {attempt}");
+            }
+
+            self.send_message(peer, proposal.clone(), None /* response_channel */)?;
+            let response = notifier.notified();
+            if tokio::time::timeout(self.price_agreement_timeout, response).await.is_ok() {
+                return Ok(());
+            }
+        }
+
+        // A response that concluded the negotiation after the last timeout owns
+        // the outcome of the handshake
+        if !self.handshake_state_index.abandon_negotiation(&request_id).await {
+            return Ok(());
+        }
+
+        let err =
+            HandshakeManagerError::PriceAgreementTimeout(ERR_PRICE_AGREEMENT_TIMEOUT.to_string());
+        self.fail_handshake(&request_id, err.clone()).await;
+        Err(err)
+    }
+
+    /// Transition a handshake to the error state and publish its failure
+    async fn fail_handshake(&self, request_id: &Uuid, err: HandshakeManagerError) {
+        let state = match self.handshake_state_index.get_state(request_id).await {
+            Some(state) => state,
+            None => return,
+        };
+        self.handshake_state_index.error(request_id, err.clone()).await;
+        self.update_order_stats(state.local_order_id, |s| s.failed += 1);
+
+        self.system_bus.publish(
+            HANDSHAKE_STATUS_TOPIC.to_string(),
+            SystemBusMessage::HandshakeFailed {
+                local_order_id: state.local_order_id,
+                peer_order_id: state.peer_order_id,
+                reason: err.to_string(),
+                timestamp: get_timestamp_millis(),
+            },
+        );
+    }
+
     /// Fetch a price vector from the price reporter
     pub(super) async fn fetch_price_vector(&self) -> Result<PriceVector, HandshakeManagerError> {
         // Enqueue jobs in the price manager to snapshot the midpoint for each pair
@@ -233,3 +307,268 @@ impl HandshakeExecutor {
         Ok(true)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use circuit_types::fixed_point::FixedPoint;
+    use common::types::{
+        gossip::WrappedPeerId,
+        handshake::ConnectionRole,
+        network_order::{test_helpers::dummy_network_order, NetworkOrder},
+    };
+    use constants::HANDSHAKE_STATUS_TOPIC;
+    use external_api::bus_message::SystemBusMessage;
+    use gossip_api::request_response::handshake::{
+        AcceptMatchCandidate, HandshakeMessage, HandshakeMessageType, MatchRejectionReason,
+        PriceVector, ProposeMatchCandidate, RejectMatchCandidate,
+    };
+    use job_types::network_manager::{
+        new_network_manager_queue, NetworkManagerControlSignal, NetworkManagerJob,
+        NetworkManagerQueue, NetworkManagerReceiver,
+    };
+    use state::{test_helpers::mock_state, State};
+    use system_bus::SystemBus;
+    use util::get_current_time_seconds;
+    use uuid::Uuid;
+
+    use crate::{
+        error::HandshakeManagerError,
+        manager::{test_helpers::mock_executor_config, HandshakeExecutor},
+        worker::HandshakeExecutorConfig,
+    };
+
+    /// The number of times the proposal is resent in the test
+    const RETRIES: usize = 2;
+
+    /// Create an executor on the given state, network queue, and system bus
+    /// that awaits the response to a proposal for the given timeout
+    fn mock_executor(
+        state: State,
+        network_channel: NetworkManagerQueue,
+        system_bus: SystemBus<SystemBusMessage>,
+        price_agreement_timeout_ms: u64,
+    ) -> HandshakeExecutor {
+        let config = HandshakeExecutorConfig {
+            network_channel,
+            system_bus,
+            price_agreement_timeout_ms,
+            price_agreement_retries: RETRIES,
+            ..mock_executor_config(state)
+        };
+        HandshakeExecutor::new(config).unwrap()
+    }
+
+    /// Build a proposal of the given order pair to a peer
+    fn proposal(
+        request_id: Uuid,
+        peer_id: WrappedPeerId,
+        local_order: &NetworkOrder,
+        peer_order: &NetworkOrder,
+    ) -> HandshakeMessage {
+        HandshakeMessage {
+            request_id,
+            message_type: HandshakeMessageType::Propose(ProposeMatchCandidate {
+                peer_id,
+                peer_order: peer_order.id,
+                sender_order: local_order.id,
+                price_vector: PriceVector(vec![]),
+            }),
+        }
+    }
+
+    /// Build a peer's acceptance of a proposal of the given order pair
+    fn acceptance(
+        request_id: Uuid,
+        local_order: &NetworkOrder,
+        peer_order: &NetworkOrder,
+    ) -> HandshakeMessage {
+        HandshakeMessage {
+            request_id,
+            message_type: HandshakeMessageType::Accept(AcceptMatchCandidate {
+                peer_id: WrappedPeerId::random(),
+                port: 0,
+                order1: peer_order.id,
+                order2: local_order.id,
+            }),
+        }
+    }
+
+    /// Drain the network queue, returning the number of MPC networks brokered
+    fn count_brokered_networks(network_recv: &mut NetworkManagerReceiver) -> usize {
+        let mut n_brokered = 0;
+        while let Ok(job) = network_recv.try_recv() {
+            if let NetworkManagerJob::Internal(NetworkManagerControlSignal::BrokerMpcNet {
+                ..
+            }) = job
+            {
+                n_brokered += 1;
+            }
+        }
+
+        n_brokered
+    }
+
+    /// Tests that a handshake with a peer that never responds to a match
+    /// proposal is failed after the configured retries
+    #[tokio::test]
+    async fn test_unresponsive_peer() {
+        let state = mock_state();
+        let local_order = dummy_network_order();
+        let peer_order = dummy_network_order();
+        state.add_order(local_order.clone()).unwrap();
+        state.add_order(peer_order.clone()).unwrap();
+
+        // The network receiver swallows all requests, i.e. the peer never responds
+        let (network_channel, mut network_recv) = new_network_manager_queue();
+        let bus = SystemBus::new();
+        let mut status_reader = bus.subscribe(HANDSHAKE_STATUS_TOPIC.to_string());
+        let executor = mock_executor(state.clone(), network_channel, bus, 10 /* timeout_ms */);
+
+        let request_id = Uuid::new_v4();
+        executor
+            .handshake_state_index
+            .new_handshake(
+                request_id,
+                ConnectionRole::Dialer,
+                peer_order.id,
+                local_order.id,
+                FixedPoint::from_integer(1),
+            )
+            .await
+            .unwrap();
+
+        let peer = WrappedPeerId::random();
+        let proposal = proposal(request_id, peer, &local_order, &peer_order);
+        let res = executor.negotiate_price_agreement(peer, proposal).await;
+        assert!(matches!(res, Err(HandshakeManagerError::PriceAgreementTimeout(_))));
+
+        // The proposal is sent once, then resent on each retry
+        let mut n_sent = 0;
+        while network_recv.try_recv().is_ok() {
+            n_sent += 1;
+        }
+        assert_eq!(n_sent, RETRIES + 1);
+
+        // The handshake is removed and its failure published
+        assert!(executor.handshake_state_index.get_state(&request_id).await.is_none());
+        match status_reader.next_message().await {
+            SystemBusMessage::HandshakeFailed { local_order_id, peer_order_id, .. } => {
+                assert_eq!(local_order_id, local_order.id);
+                assert_eq!(peer_order_id, peer_order.id);
+            },
+            msg => panic!("unexpected message: {msg:?}"),
+        }
+    }
+
+    /// Tests that responses to a proposal are acted on only while the local
+    /// node awaits one; an acceptance after the handshake fails and a
+    /// duplicated acceptance are dropped
+    #[tokio::test]
+    async fn test_stale_responses_dropped() {
+        let state = mock_state();
+        let local_order = dummy_network_order();
+        let peer_order = dummy_network_order();
+        state.add_order(local_order.clone()).unwrap();
+        state.add_order(peer_order.clone()).unwrap();
+
+        let (network_channel, mut network_recv) = new_network_manager_queue();
+        let mut executor =
+            mock_executor(state, network_channel, SystemBus::new(), 10 /* timeout_ms */);
+        let peer = WrappedPeerId::random();
+        let price = FixedPoint::from_integer(1);
+        let (local, remote) = (local_order.id, peer_order.id);
+
+        // An acceptance that arrives after the proposal times out is dropped
+        let request_id = Uuid::new_v4();
+        executor
+            .begin_handshake(request_id, ConnectionRole::Dialer, remote, local, price)
+            .await
+            .unwrap();
+        let propose = proposal(request_id, peer, &local_order, &peer_order);
+        let res = executor.negotiate_price_agreement(peer, propose).await;
+        assert!(matches!(res, Err(HandshakeManagerError::PriceAgreementTimeout(_))));
+
+        let accept = acceptance(request_id, &local_order, &peer_order);
+        assert!(executor.handle_handshake_message(request_id, accept).await.unwrap().is_none());
+        assert_eq!(count_brokered_networks(&mut network_recv), 0);
+        assert!(!executor.handshake_cache.read().await.contains(local, remote));
+
+        // An acceptance concludes the negotiation without waiting out the timeout
+        executor.price_agreement_timeout = Duration::from_secs(60);
+        let request_id = Uuid::new_v4();
+        executor
+            .begin_handshake(request_id, ConnectionRole::Dialer, remote, local, price)
+            .await
+            .unwrap();
+        let propose = proposal(request_id, peer, &local_order, &peer_order);
+        let negotiation = {
+            let executor = executor.clone();
+            tokio::spawn(async move { executor.negotiate_price_agreement(peer, propose).await })
+        };
+        network_recv.recv().await.unwrap(); // the proposal
+
+        let accept = acceptance(request_id, &local_order, &peer_order);
+        executor.handle_handshake_message(request_id, accept.clone()).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(1), negotiation).await.unwrap().unwrap().unwrap();
+        assert_eq!(count_brokered_networks(&mut network_recv), 1);
+
+        // A duplicated acceptance, or a rejection of the accepted proposal, is dropped
+        executor.handle_handshake_message(request_id, accept).await.unwrap();
+        let reject = HandshakeMessage {
+            request_id,
+            message_type: HandshakeMessageType::Reject(RejectMatchCandidate {
+                peer_id: peer,
+                peer_order: remote,
+                sender_order: local,
+                reason: MatchRejectionReason::NoPriceAgreement,
+            }),
+        };
+        executor.handle_handshake_message(request_id, reject).await.unwrap();
+        assert_eq!(count_brokered_networks(&mut network_recv), 0);
+        assert!(executor.handshake_state_index.get_state(&request_id).await.is_some());
+    }
+
+    /// Tests that a retried proposal receives the response sent to the
+    /// original, rather than being evaluated again
+    #[tokio::test]
+    async fn test_retried_proposal_idempotent() {
+        let state = mock_state();
+        let mut local_order = dummy_network_order();
+        local_order.timestamp = get_current_time_seconds();
+        state.add_order(local_order.clone()).unwrap();
+        let peer_order = dummy_network_order();
+
+        let (network_channel, _network_recv) = new_network_manager_queue();
+        let mut executor = mock_executor(state, network_channel, SystemBus::new(), 10_000);
+        executor.min_rest_ms = 60_000;
+
+        // The peer proposes a match on the local order, which has not rested
+        let request_id = Uuid::new_v4();
+        let peer = WrappedPeerId::random();
+        let propose = proposal(request_id, peer, &peer_order, &local_order);
+        let resp = executor.handle_handshake_message(request_id, propose.clone()).await.unwrap();
+        assert!(matches!(rejection_reason(resp), MatchRejectionReason::NotRested));
+
+        // Once the order rests, a retry of the proposal is answered as the original
+        executor.min_rest_ms = 0;
+        let resp = executor.handle_handshake_message(request_id, propose.clone()).await.unwrap();
+        assert!(matches!(rejection_reason(resp), MatchRejectionReason::NotRested));
+
+        // While a new proposal on the same orders is evaluated anew
+        let request_id = Uuid::new_v4();
+        let propose = HandshakeMessage { request_id, ..propose };
+        let resp = executor.handle_handshake_message(request_id, propose).await.unwrap();
+        assert!(matches!(rejection_reason(resp), MatchRejectionReason::NoValidityProof));
+    }
+
+    /// Get the reason a response rejects a proposal, panicking if the response
+    /// is not a rejection
+    fn rejection_reason(resp: Option<HandshakeMessage>) -> MatchRejectionReason {
+        match resp.map(|msg| msg.message_type) {
+            Some(HandshakeMessageType::Reject(RejectMatchCandidate { reason, .. })) => reason,
+            msg => panic!("unexpected response: {msg:?}"),
+        }
+    }
+}
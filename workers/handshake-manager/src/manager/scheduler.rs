@@ -1,21 +1,58 @@
 //! The handshake scheduler, periodically enqueues handshakes to be executed by
 //! the local node
 
-use std::time::Duration;
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
-use common::types::CancelChannel;
+use circuit_types::order::Order;
+use common::types::{
+    wallet::{OrderIdentifier, WalletIdentifier},
+    CancelChannel,
+};
 use job_types::handshake_manager::{HandshakeExecutionJob, HandshakeManagerQueue};
+use rand::{seq::IteratorRandom, thread_rng};
 use state::State;
 use tracing::info;
-use util::err_str;
+use util::{err_str, get_current_time_millis};
 
-use crate::error::HandshakeManagerError;
+use crate::{error::HandshakeManagerError, manager::rest_cutoff};
 
 /// How frequently a new handshake is initiated from the local peer
 pub(super) const HANDSHAKE_INTERVAL_MS: u64 = 2_000; // 2 seconds
 /// Number of nanoseconds in a millisecond, for convenience
 const NANOS_PER_MILLI: u64 = 1_000_000;
 
+/// A locally managed order that is ready for a match, along with the wallet
+/// that manages it
+type LocalCandidate = (OrderIdentifier, WalletIdentifier, Order);
+
+/// Find the local orders that may be matched internally against an order in
+/// another local wallet
+///
+/// Two orders are internal match candidates if they are on opposite sides of
+/// the same pair and their limit prices overlap; whether the orders cross at
+/// the current midpoint is left to the internal matching engine
+fn find_internal_matches(candidates: &[LocalCandidate]) -> HashSet<OrderIdentifier> {
+    candidates
+        .iter()
+        .filter_map(|(id, wallet_id, order)| {
+            let has_counterparty = candidates.iter().any(|(_, other_wallet_id, other)| {
+                other_wallet_id != wallet_id
+                    && other.base_mint == order.base_mint
+                    && other.quote_mint == order.quote_mint
+                    && other.side != order.side
+                    && order.price_in_range(other.worst_case_price)
+                    && other.price_in_range(order.worst_case_price)
+            });
+
+            has_counterparty.then_some(*id)
+        })
+        .collect()
+}
+
 /// Implements a timer that periodically enqueues jobs to the threadpool that
 /// tell the manager to send outbound handshake requests
 #[derive(Clone)]
@@ -26,6 +63,15 @@ pub struct HandshakeScheduler {
     global_state: State,
     /// The cancel channel to receive cancel signals on
     cancel: CancelChannel,
+    /// Whether to run the internal matching engine in place of a peer
+    /// handshake when a local order may be matched internally
+    prefer_internal_matches: bool,
+    /// The minimum time in milliseconds an order must rest after it is
+    /// received before it is scheduled for a match
+    min_rest_ms: u64,
+    /// The internal match candidates run through the internal matching engine
+    /// since the scheduler last fell back to a peer handshake
+    tried_internal_orders: Arc<Mutex<HashSet<OrderIdentifier>>>,
 }
 
 impl HandshakeScheduler {
@@ -34,8 +80,17 @@ impl HandshakeScheduler {
         job_sender: HandshakeManagerQueue,
         global_state: State,
         cancel: CancelChannel,
+        prefer_internal_matches: bool,
+        min_rest_ms: u64,
     ) -> Self {
-        Self { job_sender, global_state, cancel }
+        Self {
+            job_sender,
+            global_state,
+            cancel,
+            prefer_internal_matches,
+            min_rest_ms,
+            tried_internal_orders: Arc::new(Mutex::new(HashSet::new())),
+        }
     }
 
     /// The execution loop of the timer, periodically enqueues handshake jobs
@@ -49,11 +104,13 @@ impl HandshakeScheduler {
             tokio::select! {
                 // Enqueue handshakes periodically according to a timer
                 _ = tokio::time::sleep(refresh_interval) => {
-                    // Enqueue a job to handshake with the randomly selected peer
-                    if let Some(order) = self.global_state.choose_handshake_order().ok().flatten() {
+                    // Enqueue a job to match internally or handshake with a randomly
+                    // selected peer
+                    let now_ms = get_current_time_millis() as u64;
+                    if let Some(job) = self.next_job(now_ms).ok().flatten() {
                         if let Err(e) = self
                             .job_sender
-                            .send(HandshakeExecutionJob::PerformHandshake { order })
+                            .send(job)
                             .map_err(err_str!(HandshakeManagerError::SendMessage))
                         {
                             return e;
@@ -68,4 +125,278 @@ impl HandshakeScheduler {
             }
         }
     }
+
+    /// Choose the next job to enqueue
+    ///
+    /// Internal matches avoid the network and MPC cost of a peer handshake, so
+    /// if preferred, a local order with an internal match candidate is run
+    /// through the internal matching engine. Otherwise a peer's order is
+    /// sampled for a handshake
+    ///
+    /// Candidates may not cross at the midpoint, so each is tried once, in a
+    /// random order, before the scheduler falls back to a peer handshake and
+    /// begins a new round of internal matches
+    ///
+    /// Orders that have not rested for the minimum duration as of `now_ms` are
+    /// not scheduled, and no jobs are scheduled while the local node drains
+    fn next_job(
+        &self,
+        now_ms: u64,
+    ) -> Result<Option<HandshakeExecutionJob>, HandshakeManagerError> {
+        if self.global_state.is_draining() {
+            return Ok(None);
+        }
+
+        let cutoff = rest_cutoff(self.min_rest_ms, now_ms);
+        if self.prefer_internal_matches
+            && let Some(order) = self.choose_internal_match_order(cutoff)?
+        {
+            return Ok(Some(HandshakeExecutionJob::InternalMatchingEngine { order }));
+        }
+
+        let order = self.global_state.choose_handshake_order(cutoff)?;
+        Ok(order.map(|order| HandshakeExecutionJob::PerformHandshake { order }))
+    }
+
+    /// Choose a local order that may be matched internally and has not been
+    /// tried this round, if one exists
+    ///
+    /// Only orders received at or before `received_before`, a timestamp in
+    /// milliseconds, are considered
+    fn choose_internal_match_order(
+        &self,
+        received_before: u64,
+    ) -> Result<Option<OrderIdentifier>, HandshakeManagerError> {
+        let matchable_ids = self.global_state.get_locally_matchable_orders()?;
+        let rested = self.global_state.get_orders_batch(&matchable_ids)?.into_iter().flatten();
+
+        let mut candidates = Vec::new();
+        for id in rested.filter(|o| o.received_by(received_before)).map(|o| o.id) {
+            let wallet_id = self.global_state.get_wallet_for_order(&id)?;
+            let order = self.global_state.get_managed_order(&id)?;
+            if let (Some(wallet_id), Some(order)) = (wallet_id, order) {
+                candidates.push((id, wallet_id, order));
+            }
+        }
+
+        let matches = find_internal_matches(&candidates);
+        let mut tried = self.tried_internal_orders.lock().unwrap();
+        tried.retain(|id| matches.contains(id));
+
+        let order = matches.into_iter().filter(|id| !tried.contains(id)).choose(&mut thread_rng());
+        match order {
+            Some(id) => {
+                tried.insert(id);
+            },
+            // Every candidate has been tried, fall back to a peer handshake
+            None => tried.clear(),
+        }
+
+        Ok(order)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashSet, str::FromStr};
+
+    use circuit_types::{
+        fixed_point::FixedPoint,
+        order::{Order, OrderSide},
+    };
+    use common::types::{
+        gossip::ClusterId,
+        network_order::test_helpers::dummy_network_order,
+        new_cancel_channel,
+        proof_bundles::mocks::{dummy_validity_proof_bundle, dummy_validity_witness_bundle},
+        wallet::{OrderIdentifier, WalletIdentifier},
+        wallet_mocks::{mock_empty_wallet, mock_order},
+    };
+    use job_types::handshake_manager::{new_handshake_manager_queue, HandshakeExecutionJob};
+    use state::{test_helpers::mock_state, State};
+    use util::get_current_time_millis;
+    use uuid::Uuid;
+
+    use super::{find_internal_matches, HandshakeScheduler};
+
+    /// Build an order on the same pair as the given order, on the opposite side
+    fn counter_order(order: &Order) -> Order {
+        Order { side: order.side.opposite(), ..order.clone() }
+    }
+
+    /// Add a wallet to the state holding the given order, ready for a match
+    async fn add_local_order(state: &State, order: Order) -> OrderIdentifier {
+        let mut wallet = mock_empty_wallet();
+        state.new_wallet(wallet.clone()).unwrap().await.unwrap();
+
+        let id = OrderIdentifier::new_v4();
+        wallet.add_order(id, order).unwrap();
+        state.update_wallet(wallet).unwrap().await.unwrap();
+
+        let (proof, witness) = (dummy_validity_proof_bundle(), dummy_validity_witness_bundle());
+        state.add_local_order_validity_bundle(id, proof, witness).unwrap().await.unwrap();
+        id
+    }
+
+    /// Add a peer's order to the state, ready for a match
+    fn add_peer_order(state: &State) -> OrderIdentifier {
+        add_peer_order_at(state, 0 /* timestamp */)
+    }
+
+    /// Add a peer's order received at the given timestamp to the state, ready
+    /// for a match
+    fn add_peer_order_at(state: &State, timestamp: u64) -> OrderIdentifier {
+        let mut order = dummy_network_order();
+        order.timestamp = timestamp;
+        order.cluster = ClusterId::from_str("peer-cluster").unwrap();
+        order.validity_proofs = Some(dummy_validity_proof_bundle());
+        order.validity_proof_witnesses = Some(dummy_validity_witness_bundle());
+        state.add_order(order.clone()).unwrap();
+        order.id
+    }
+
+    /// Build a scheduler on the given state
+    fn mock_scheduler(state: State, prefer_internal_matches: bool) -> HandshakeScheduler {
+        mock_scheduler_with_rest(state, prefer_internal_matches, 0 /* min_rest_ms */)
+    }
+
+    /// Build a scheduler on the given state with the given order rest period
+    fn mock_scheduler_with_rest(
+        state: State,
+        prefer_internal_matches: bool,
+        min_rest_ms: u64,
+    ) -> HandshakeScheduler {
+        let (job_sender, _job_recv) = new_handshake_manager_queue();
+        let (_cancel_sender, cancel) = new_cancel_channel();
+        HandshakeScheduler::new(job_sender, state, cancel, prefer_internal_matches, min_rest_ms)
+    }
+
+    /// Tests finding internal match candidates among local orders
+    #[test]
+    fn test_find_internal_match() {
+        let order = mock_order();
+        let (wallet1, wallet2) = (WalletIdentifier::new_v4(), WalletIdentifier::new_v4());
+        let (id1, id2) = (Uuid::new_v4(), Uuid::new_v4());
+
+        // Orders on the same side do not match
+        let same_side = vec![(id1, wallet1, order.clone()), (id2, wallet2, order.clone())];
+        assert!(find_internal_matches(&same_side).is_empty());
+
+        // Orders in the same wallet do not match
+        let same_wallet =
+            vec![(id1, wallet1, order.clone()), (id2, wallet1, counter_order(&order))];
+        assert!(find_internal_matches(&same_wallet).is_empty());
+
+        // Orders with disjoint limit prices do not match
+        let mut expensive = counter_order(&order);
+        expensive.worst_case_price = order.worst_case_price + FixedPoint::from_integer(1);
+        let disjoint = vec![(id1, wallet1, order.clone()), (id2, wallet2, expensive)];
+        assert!(find_internal_matches(&disjoint).is_empty());
+
+        // Opposite orders in separate wallets match
+        let crossing = vec![(id1, wallet1, order.clone()), (id2, wallet2, counter_order(&order))];
+        assert_eq!(find_internal_matches(&crossing), HashSet::from([id1, id2]));
+    }
+
+    /// Tests that the scheduler prefers an internal match over a peer match
+    /// when both are available, and falls back to the peer match otherwise
+    #[tokio::test]
+    async fn test_prefers_internal_match() {
+        let state = mock_state();
+        let order = Order { side: OrderSide::Buy, ..mock_order() };
+        let buy_id = add_local_order(&state, order.clone()).await;
+        let sell_id = add_local_order(&state, counter_order(&order)).await;
+        let peer_order_id = add_peer_order(&state);
+
+        // With the preference enabled, the internal engine is run on each local
+        // order in turn
+        let scheduler = mock_scheduler(state.clone(), true /* prefer_internal_matches */);
+        let mut tried = HashSet::new();
+        for _ in 0..2 {
+            match scheduler.next_job(get_current_time_millis() as u64).unwrap() {
+                Some(HandshakeExecutionJob::InternalMatchingEngine { order }) => {
+                    tried.insert(order);
+                },
+                _ => panic!("expected an internal matching engine job"),
+            }
+        }
+        assert_eq!(tried, HashSet::from([buy_id, sell_id]));
+
+        // Once every candidate has been tried, the scheduler falls back to a peer
+        // handshake, then begins a new round of internal matches
+        match scheduler.next_job(get_current_time_millis() as u64).unwrap() {
+            Some(HandshakeExecutionJob::PerformHandshake { order }) => {
+                assert_eq!(order, peer_order_id)
+            },
+            _ => panic!("expected a peer handshake job"),
+        }
+        assert!(matches!(
+            scheduler.next_job(get_current_time_millis() as u64).unwrap(),
+            Some(HandshakeExecutionJob::InternalMatchingEngine { .. })
+        ));
+
+        // With the preference disabled, the peer's order is handshaked on
+        let scheduler = mock_scheduler(state, false /* prefer_internal_matches */);
+        match scheduler.next_job(get_current_time_millis() as u64).unwrap() {
+            Some(HandshakeExecutionJob::PerformHandshake { order }) => {
+                assert_eq!(order, peer_order_id)
+            },
+            _ => panic!("expected a peer handshake job"),
+        }
+    }
+
+    /// Tests that a fresh order is only scheduled once it has rested for the
+    /// configured minimum duration
+    #[tokio::test]
+    async fn test_order_rest_period() {
+        const MIN_REST_MS: u64 = 60_000;
+        let received_ms = get_current_time_millis() as u64;
+        let rested_ms = received_ms + MIN_REST_MS + 1_000;
+
+        // A fresh peer order is not handshaked on until it has rested
+        let state = mock_state();
+        let peer_order_id = add_peer_order_at(&state, received_ms / 1000);
+        let scheduler = mock_scheduler_with_rest(state, false, MIN_REST_MS);
+        assert!(scheduler.next_job(received_ms).unwrap().is_none());
+        match scheduler.next_job(rested_ms).unwrap() {
+            Some(HandshakeExecutionJob::PerformHandshake { order }) => {
+                assert_eq!(order, peer_order_id)
+            },
+            _ => panic!("expected a peer handshake job"),
+        }
+
+        // Fresh local orders are not matched internally until they have rested
+        let state = mock_state();
+        let order = Order { side: OrderSide::Buy, ..mock_order() };
+        add_local_order(&state, order.clone()).await;
+        add_local_order(&state, counter_order(&order)).await;
+        let scheduler = mock_scheduler_with_rest(state, true, MIN_REST_MS);
+        assert!(scheduler.next_job(received_ms).unwrap().is_none());
+        assert!(matches!(
+            scheduler.next_job(rested_ms).unwrap(),
+            Some(HandshakeExecutionJob::InternalMatchingEngine { .. })
+        ));
+    }
+
+    /// Tests that a rest period shorter than a second is measured in
+    /// milliseconds from the end of the second the order was received in
+    #[tokio::test]
+    async fn test_sub_second_rest_period() {
+        const MIN_REST_MS: u64 = 500;
+        let received_s = get_current_time_millis() as u64 / 1000;
+        let state = mock_state();
+        let peer_order_id = add_peer_order_at(&state, received_s);
+        let scheduler = mock_scheduler_with_rest(state, false, MIN_REST_MS);
+
+        // The order may have been received as late as the last millisecond of its
+        // second, so it has not rested until `MIN_REST_MS` after that
+        let received_by_ms = received_s * 1000 + 999;
+        assert!(scheduler.next_job(received_by_ms + MIN_REST_MS - 1).unwrap().is_none());
+        match scheduler.next_job(received_by_ms + MIN_REST_MS).unwrap() {
+            Some(HandshakeExecutionJob::PerformHandshake { order }) => {
+                assert_eq!(order, peer_order_id)
+            },
+            _ => panic!("expected a peer handshake job"),
+        }
+    }
 }
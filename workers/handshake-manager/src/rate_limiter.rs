@@ -0,0 +1,141 @@
+//! Implements a per-peer rate limiter on inbound handshake messages
+//!
+//! Each inbound handshake message spawns work on the executor, so a peer that
+//! floods messages may starve the executor's thread pool. The limiter holds a
+//! token bucket for each peer that refills at the configured rate, and
+//! messages that arrive at an empty bucket are rejected
+
+use std::{collections::HashMap, time::Instant};
+
+use common::types::gossip::WrappedPeerId;
+
+/// The number of peers tracked before idle buckets are pruned
+const MAX_TRACKED_PEERS: usize = 10_000;
+
+/// A token bucket for a single peer
+#[derive(Clone, Copy, Debug)]
+struct TokenBucket {
+    /// The number of messages the peer may currently send
+    tokens: f64,
+    /// The last time the bucket was refilled
+    last_refill: Instant,
+}
+
+/// Limits the rate at which each peer may send handshake messages
+#[derive(Debug)]
+pub struct PeerRateLimiter {
+    /// The number of messages per second each peer may send, this is also the
+    /// size of a peer's burst allowance
+    ///
+    /// A rate of zero disables the limiter
+    rate: u32,
+    /// The token bucket of each peer
+    buckets: HashMap<WrappedPeerId, TokenBucket>,
+}
+
+impl PeerRateLimiter {
+    /// Constructor
+    pub fn new(rate: u32) -> Self {
+        Self { rate, buckets: HashMap::new() }
+    }
+
+    /// Check whether a message from the given peer is within its rate limit,
+    /// consuming a token if so
+    pub fn check(&mut self, peer_id: &WrappedPeerId) -> bool {
+        self.check_at(peer_id, Instant::now())
+    }
+
+    /// Check a message from the given peer against the limit at the given time
+    fn check_at(&mut self, peer_id: &WrappedPeerId, now: Instant) -> bool {
+        if self.rate == 0 {
+            return true;
+        }
+
+        if !self.buckets.contains_key(peer_id) && self.buckets.len() >= MAX_TRACKED_PEERS {
+            self.prune_idle(now);
+        }
+
+        let capacity = self.rate as f64;
+        let bucket = self
+            .buckets
+            .entry(*peer_id)
+            .or_insert(TokenBucket { tokens: capacity, last_refill: now });
+
+        // Refill the bucket for the time elapsed since the last message
+        let elapsed = now.saturating_duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = f64::min(capacity, bucket.tokens + elapsed * capacity);
+        bucket.last_refill = now;
+
+        if bucket.tokens < 1. {
+            return false;
+        }
+
+        bucket.tokens -= 1.;
+        true
+    }
+
+    /// Remove the buckets of peers that have refilled completely, these peers
+    /// are indistinguishable from untracked peers
+    fn prune_idle(&mut self, now: Instant) {
+        let rate = self.rate as f64;
+        self.buckets.retain(|_, bucket| {
+            let elapsed = now.saturating_duration_since(bucket.last_refill).as_secs_f64();
+            bucket.tokens + elapsed * rate < rate
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use common::types::gossip::WrappedPeerId;
+
+    use super::PeerRateLimiter;
+
+    /// The rate used in tests
+    const RATE: u32 = 10;
+
+    /// Tests that a flooding peer is limited while a well-behaved peer is not
+    #[test]
+    fn test_flooding_peer_rejected() {
+        let mut limiter = PeerRateLimiter::new(RATE);
+        let flooder = WrappedPeerId::random();
+        let honest_peer = WrappedPeerId::random();
+        let now = Instant::now();
+
+        // The flooding peer exhausts its burst allowance
+        let n_accepted = (0..RATE * 5).filter(|_| limiter.check_at(&flooder, now)).count() as u32;
+        assert_eq!(n_accepted, RATE);
+
+        // The well-behaved peer sends at the permitted rate without rejection
+        let interval = Duration::from_secs(1) / RATE;
+        for i in 0..RATE * 3 {
+            assert!(limiter.check_at(&honest_peer, now + interval * i));
+        }
+    }
+
+    /// Tests that a limited peer is admitted again once its bucket refills
+    #[test]
+    fn test_limit_refills() {
+        let mut limiter = PeerRateLimiter::new(RATE);
+        let peer = WrappedPeerId::random();
+        let now = Instant::now();
+
+        (0..RATE).for_each(|_| assert!(limiter.check_at(&peer, now)));
+        assert!(!limiter.check_at(&peer, now));
+
+        // After a tenth of a second one message's worth of tokens is restored
+        let later = now + Duration::from_millis(100);
+        assert!(limiter.check_at(&peer, later));
+        assert!(!limiter.check_at(&peer, later));
+    }
+
+    /// Tests that a rate of zero disables the limiter
+    #[test]
+    fn test_disabled_limiter() {
+        let mut limiter = PeerRateLimiter::new(0);
+        let peer = WrappedPeerId::random();
+        assert!((0..1_000).all(|_| limiter.check(&peer)));
+    }
+}
@@ -2,10 +2,14 @@
 // TODO: Remove this lint allowance
 #![allow(dead_code)]
 
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    num::NonZeroUsize,
+    sync::Arc,
+};
 
 use super::error::HandshakeManagerError;
-use circuit_types::fixed_point::FixedPoint;
+use circuit_types::{fixed_point::FixedPoint, wallet::Nullifier};
 use common::{
     new_async_shared,
     types::{
@@ -14,13 +18,21 @@ use common::{
     },
     AsyncShared,
 };
-use constants::Scalar;
 use crossbeam::channel::Sender;
+use gossip_api::request_response::handshake::HandshakeMessage;
+use lru::LruCache;
 use state::State;
+use tokio::sync::Notify;
 use uuid::Uuid;
 
 /// Error message thrown when a nullifier cannot be found
 const ERR_NULLIFIER_MISSING: &str = "nullifier not found for order";
+/// Error message thrown when a handshake is indexed under a request ID that is
+/// already in use
+const ERR_DUPLICATE_REQUEST_ID: &str = "handshake already exists for request";
+/// The number of responses to peers' match proposals kept to answer retried
+/// proposals
+const PROPOSAL_RESPONSE_CACHE_SIZE: usize = 1_000;
 
 /// Holds state information for all in-flight handshake correspondences
 ///
@@ -31,7 +43,11 @@ pub struct HandshakeStateIndex {
     /// The underlying map of request identifiers to state machine instances
     state_map: AsyncShared<HashMap<Uuid, HandshakeState>>,
     /// A mapping from nullifier to a set of request_ids on that nullifier
-    nullifier_map: AsyncShared<HashMap<Scalar, HashSet<Uuid>>>,
+    nullifier_map: AsyncShared<HashMap<Nullifier, HashSet<Uuid>>>,
+    /// Notifiers for locally proposed handshakes awaiting the peer's response
+    negotiations: AsyncShared<HashMap<Uuid, Arc<Notify>>>,
+    /// The responses sent to peers' match proposals, keyed by request ID
+    proposal_responses: AsyncShared<LruCache<Uuid, HandshakeMessage>>,
     /// A copy of the relayer global state
     global_state: State,
 }
@@ -42,6 +58,10 @@ impl HandshakeStateIndex {
         Self {
             state_map: new_async_shared(HashMap::new()),
             nullifier_map: new_async_shared(HashMap::new()),
+            negotiations: new_async_shared(HashMap::new()),
+            proposal_responses: new_async_shared(LruCache::new(
+                NonZeroUsize::new(PROPOSAL_RESPONSE_CACHE_SIZE).unwrap(),
+            )),
             global_state,
         }
     }
@@ -71,8 +91,13 @@ impl HandshakeStateIndex {
             .ok_or_else(|| HandshakeManagerError::State(ERR_NULLIFIER_MISSING.to_string()))?;
 
         // Index by request ID
+        let is_dialer = matches!(role, ConnectionRole::Dialer);
         {
             let mut locked_state = self.state_map.write().await;
+            if locked_state.contains_key(&request_id) {
+                return Err(HandshakeManagerError::State(ERR_DUPLICATE_REQUEST_ID.to_string()));
+            }
+
             locked_state.insert(
                 request_id,
                 HandshakeState::new(
@@ -94,6 +119,13 @@ impl HandshakeStateIndex {
             locked_nullifier_map.entry(peer_nullifier).or_default().insert(request_id);
         } // locked_nullifier_map released
 
+        // A locally proposed handshake awaits the peer's response
+        if is_dialer {
+            self.negotiations.write().await.insert(request_id, Arc::default());
+        }
+
+        // Register the handshake so that a drain waits for it
+        self.global_state.add_active_handshake(request_id);
         Ok(())
     }
 
@@ -105,6 +137,8 @@ impl HandshakeStateIndex {
             let mut locked_state = self.state_map.write().await;
             locked_state.remove(request_id)
         }; // locked_state released
+        self.global_state.remove_active_handshake(request_id);
+        self.negotiations.write().await.remove(request_id);
 
         // Remove from the nullifier index
         if let Some(state) = state.clone() {
@@ -126,30 +160,93 @@ impl HandshakeStateIndex {
     /// Shootdown all active handshakes on a given nullifier
     pub async fn shootdown_nullifier(
         &self,
-        nullifier: Scalar,
+        nullifier: Nullifier,
     ) -> Result<(), HandshakeManagerError> {
         let requests = {
             let mut locked_nullifier_map = self.nullifier_map.write().await;
             locked_nullifier_map.remove(&nullifier).unwrap_or_default()
         }; // locked_nullifier_map released
 
-        // For each request, remove the state entry for the request and send a cancel
-        // signal over the request's cancel channel if one has already been
-        // allocated. The receiver of this channel is the worker running in the
-        // MPC runtime
         for request in requests.iter() {
-            if let Some(state) = self.remove_handshake(request).await
-                && let Some(channel) = state.cancel_channel
-            {
-                channel
-                    .send(())
-                    .map_err(|err| HandshakeManagerError::SendMessage(err.to_string()))?;
-            }
+            self.cancel(request).await?;
         }
 
         Ok(())
     }
 
+    /// Cancel a single handshake, returning its state if it was in flight
+    ///
+    /// Removes the state entry for the request and sends a cancel signal over
+    /// the request's cancel channel if one has already been allocated. The
+    /// receiver of this channel is the worker running in the MPC runtime
+    pub async fn cancel(
+        &self,
+        request_id: &Uuid,
+    ) -> Result<Option<HandshakeState>, HandshakeManagerError> {
+        let state = self.remove_handshake(request_id).await;
+        if let Some(channel) = state.as_ref().and_then(|s| s.cancel_channel.as_ref()) {
+            channel.send(()).map_err(|err| HandshakeManagerError::SendMessage(err.to_string()))?;
+        }
+
+        Ok(state)
+    }
+
+    // ------------------------
+    // | Proposal Negotiation |
+    // ------------------------
+
+    /// Get the notifier signalled when the peer responds to a locally
+    /// proposed handshake
+    ///
+    /// Returns `None` if the handshake is not awaiting a response
+    pub async fn negotiation_notifier(&self, request_id: &Uuid) -> Option<Arc<Notify>> {
+        self.negotiations.read().await.get(request_id).cloned()
+    }
+
+    /// Conclude the order negotiation of a locally proposed handshake on the
+    /// given order pair, notifying the task awaiting the peer's response
+    ///
+    /// Returns the handshake's state if it was awaiting the peer's response, in
+    /// which case the caller owns the outcome of the negotiation. Returns
+    /// `None` if the handshake is unknown, is on a different order pair, or
+    /// has already been concluded; e.g. for a duplicated response, or one
+    /// that arrives after the proposal timed out
+    pub async fn conclude_negotiation(
+        &self,
+        request_id: &Uuid,
+        order1: OrderIdentifier,
+        order2: OrderIdentifier,
+    ) -> Option<HandshakeState> {
+        let state = self.get_state(request_id).await?;
+        let pair = (state.local_order_id, state.peer_order_id);
+        if pair != (order1, order2) && pair != (order2, order1) {
+            return None;
+        }
+
+        let notifier = self.negotiations.write().await.remove(request_id)?;
+        notifier.notify_one();
+        Some(state)
+    }
+
+    /// Stop awaiting the peer's response to a locally proposed handshake
+    ///
+    /// Returns whether the handshake was still awaiting a response; if not, a
+    /// response concluded the negotiation first
+    pub async fn abandon_negotiation(&self, request_id: &Uuid) -> bool {
+        self.negotiations.write().await.remove(request_id).is_some()
+    }
+
+    /// Record the response sent to a peer's match proposal
+    pub async fn record_proposal_response(&self, request_id: Uuid, response: HandshakeMessage) {
+        self.proposal_responses.write().await.put(request_id, response);
+    }
+
+    /// Get the response sent to a peer's match proposal, if the proposal has
+    /// been handled
+    pub async fn get_proposal_response(&self, request_id: &Uuid) -> Option<HandshakeMessage> {
+        self.proposal_responses.write().await.get(request_id).cloned()
+    }
+
     // --------------------
     // | State Transition |
     // --------------------
@@ -1,25 +1,26 @@
 //! Implements the `Worker` trait for the handshake manager
 
-use std::thread::{Builder, JoinHandle};
+use std::{
+    collections::HashMap,
+    thread::{Builder, JoinHandle},
+};
 
-use common::types::CancelChannel;
+use circuit_types::Amount;
+use common::types::{handshake::PriceSelection, token::Token, CancelChannel};
 use common::worker::Worker;
 use external_api::bus_message::SystemBusMessage;
 use job_types::{
-    handshake_manager::{HandshakeManagerQueue, HandshakeManagerReceiver},
+    handshake_manager::{HandshakeManagerQueue, HandshakeManagerReceiver, OrderMatchStatsLedger},
     network_manager::NetworkManagerQueue,
     price_reporter::PriceReporterQueue,
     task_driver::TaskDriverQueue,
 };
 use state::State;
 use system_bus::SystemBus;
-use tokio::runtime::Builder as RuntimeBuilder;
+use tokio::runtime::{Builder as RuntimeBuilder, Runtime};
 use tracing::info;
 
-use crate::manager::{
-    init_price_streams, scheduler::HandshakeScheduler, HandshakeExecutor,
-    HANDSHAKE_EXECUTOR_N_THREADS,
-};
+use crate::manager::{init_price_streams, scheduler::HandshakeScheduler, HandshakeExecutor};
 
 use super::{error::HandshakeManagerError, manager::HandshakeManager};
 
@@ -40,11 +41,91 @@ pub struct HandshakeManagerConfig {
     pub task_queue: TaskDriverQueue,
     /// The system bus to which all workers have access
     pub system_bus: SystemBus<SystemBusMessage>,
+    /// The minimum base amount the internal matching engine will settle a
+    /// match for, keyed by base token
+    pub min_match_base_amounts: HashMap<Token, Amount>,
+    /// The number of order pairs held in the handshake cache
+    pub handshake_cache_size: usize,
+    /// The amount of time to wait for a peer to respond to a match proposal
+    /// before resending it
+    pub price_agreement_timeout_ms: u64,
+    /// The number of times a match proposal is resent to an unresponsive peer
+    /// before the handshake is failed
+    pub price_agreement_retries: usize,
+    /// The policy by which the internal matching engine selects the execution
+    /// price of a match
+    pub price_selection: PriceSelection,
+    /// The spread from the midpoint, in basis points, at which the
+    /// maker-favorable and taker-favorable policies execute a match
+    pub price_selection_spread_bps: u32,
+    /// Whether to prefer matching a local order internally over handshaking
+    /// with a peer when both are possible
+    pub prefer_internal_matches: bool,
+    /// Whether to allow proposing a match between two orders managed by the
+    /// same wallet to a peer
+    pub allow_self_trades: bool,
+    /// The minimum time in milliseconds an order must rest after it is
+    /// received before it is considered for a match
+    pub min_rest_ms: u64,
+    /// The number of handshake messages per second a peer may send before its
+    /// messages are dropped, zero disables the limit
+    pub handshake_rate_limit: u32,
+    /// The number of threads the executor may use to execute handshakes
+    pub executor_threads: usize,
+    /// The match statistics of each locally managed order, updated as the
+    /// manager proposes and settles matches
+    pub order_stats: OrderMatchStatsLedger,
     /// The channel on which the coordinator may mandate that the
     /// handshake manager cancel its execution
     pub cancel_channel: CancelChannel,
 }
 
+/// The config type for the handshake executor, built from the manager's config
+pub struct HandshakeExecutorConfig {
+    /// The job queue on which to receive handshake requests
+    pub job_channel: HandshakeManagerReceiver,
+    /// The channel on which to send outbound network requests
+    pub network_channel: NetworkManagerQueue,
+    /// The price reporter's job queue
+    pub price_reporter_job_queue: PriceReporterQueue,
+    /// The relayer-global state
+    pub global_state: State,
+    /// The queue used to send tasks to the driver
+    pub task_queue: TaskDriverQueue,
+    /// The system bus to which all workers have access
+    pub system_bus: SystemBus<SystemBusMessage>,
+    /// The minimum base amount the internal matching engine will settle a
+    /// match for, keyed by base token
+    pub min_match_base_amounts: HashMap<Token, Amount>,
+    /// The number of order pairs held in the handshake cache
+    pub handshake_cache_size: usize,
+    /// The amount of time to wait for a peer to respond to a match proposal
+    /// before resending it
+    pub price_agreement_timeout_ms: u64,
+    /// The number of times a match proposal is resent to an unresponsive peer
+    /// before the handshake is failed
+    pub price_agreement_retries: usize,
+    /// The policy by which the internal matching engine selects the execution
+    /// price of a match
+    pub price_selection: PriceSelection,
+    /// The spread from the midpoint, in basis points, at which the
+    /// maker-favorable and taker-favorable policies execute a match
+    pub price_selection_spread_bps: u32,
+    /// The number of handshake messages per second a peer may send before its
+    /// messages are dropped, zero disables the limit
+    pub handshake_rate_limit: u32,
+    /// Whether to allow proposing a match between two orders managed by the
+    /// same wallet to a peer
+    pub allow_self_trades: bool,
+    /// The minimum time in milliseconds an order must rest after it is
+    /// received before it is considered for a match
+    pub min_rest_ms: u64,
+    /// The match statistics of each locally managed order
+    pub order_stats: OrderMatchStatsLedger,
+    /// The channel on which the coordinator may cancel the executor
+    pub cancel_channel: CancelChannel,
+}
+
 impl Worker for HandshakeManager {
     type WorkerConfig = HandshakeManagerConfig;
     type Error = HandshakeManagerError;
@@ -56,16 +137,28 @@ impl Worker for HandshakeManager {
             config.job_sender.clone(),
             config.global_state.clone(),
             config.cancel_channel.clone(),
+            config.prefer_internal_matches,
+            config.min_rest_ms,
         );
-        let executor = HandshakeExecutor::new(
-            config.job_receiver.take().unwrap(),
-            config.network_channel.clone(),
-            config.price_reporter_job_queue.clone(),
-            config.global_state.clone(),
-            config.task_queue.clone(),
-            config.system_bus.clone(),
-            config.cancel_channel.clone(),
-        )?;
+        let executor = HandshakeExecutor::new(HandshakeExecutorConfig {
+            job_channel: config.job_receiver.take().unwrap(),
+            network_channel: config.network_channel.clone(),
+            price_reporter_job_queue: config.price_reporter_job_queue.clone(),
+            global_state: config.global_state.clone(),
+            task_queue: config.task_queue.clone(),
+            system_bus: config.system_bus.clone(),
+            min_match_base_amounts: config.min_match_base_amounts.clone(),
+            handshake_cache_size: config.handshake_cache_size,
+            price_agreement_timeout_ms: config.price_agreement_timeout_ms,
+            price_agreement_retries: config.price_agreement_retries,
+            price_selection: config.price_selection,
+            price_selection_spread_bps: config.price_selection_spread_bps,
+            handshake_rate_limit: config.handshake_rate_limit,
+            allow_self_trades: config.allow_self_trades,
+            min_rest_ms: config.min_rest_ms,
+            order_stats: config.order_stats.clone(),
+            cancel_channel: config.cancel_channel.clone(),
+        })?;
 
         Ok(HandshakeManager {
             config,
@@ -96,16 +189,12 @@ impl Worker for HandshakeManager {
 
         // Spawn both the executor and the scheduler in a thread
         let executor = self.executor.take().unwrap();
+        let executor_threads = self.config.executor_threads;
         let executor_handle = Builder::new()
             .name("handshake-executor-main".to_string())
             .spawn(move || {
                 // Build a Tokio runtime for the handshake manager
-                let runtime = RuntimeBuilder::new_multi_thread()
-                    .enable_all()
-                    .max_blocking_threads(HANDSHAKE_EXECUTOR_N_THREADS)
-                    .build()
-                    .unwrap();
-
+                let runtime = build_executor_runtime(executor_threads).unwrap();
                 runtime.block_on(executor.execution_loop())
             })
             .map_err(|err| HandshakeManagerError::SetupError(err.to_string()))?;
@@ -129,3 +218,56 @@ impl Worker for HandshakeManager {
         unimplemented!()
     }
 }
+
+/// Build the runtime on which the handshake executor runs, allowing at most
+/// `num_threads` handshakes to execute at once
+fn build_executor_runtime(num_threads: usize) -> Result<Runtime, HandshakeManagerError> {
+    RuntimeBuilder::new_multi_thread()
+        .enable_all()
+        .max_blocking_threads(num_threads)
+        .build()
+        .map_err(|err| HandshakeManagerError::SetupError(err.to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        thread,
+        time::Duration,
+    };
+
+    use futures::future::join_all;
+
+    use super::build_executor_runtime;
+
+    /// Tests that the executor runs no more handshakes at once than its
+    /// configured thread count
+    #[test]
+    fn test_executor_thread_count() {
+        const N_THREADS: usize = 3;
+        let runtime = build_executor_runtime(N_THREADS).unwrap();
+
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+        runtime.block_on(async {
+            let jobs = (0..2 * N_THREADS).map(|_| {
+                let running = running.clone();
+                let max_running = max_running.clone();
+                tokio::task::spawn_blocking(move || {
+                    let n = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_running.fetch_max(n, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(100));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                })
+            });
+
+            join_all(jobs).await;
+        });
+
+        assert_eq!(max_running.load(Ordering::SeqCst), N_THREADS);
+    }
+}
@@ -26,6 +26,8 @@ pub fn new_gossip_server_queue() -> (GossipServerQueue, GossipServerReceiver) {
 pub enum GossipServerJob {
     /// Execute a heartbeat to a given peer
     ExecuteHeartbeat(WrappedPeerId),
+    /// Expire the peers that have missed too many heartbeats
+    SweepExpiredPeers,
     /// An incoming gossip request
     NetworkRequest(WrappedPeerId, GossipRequest, ResponseChannel<AuthenticatedGossipResponse>),
     /// An incoming gossip response
@@ -1,11 +1,18 @@
 //! Jobs consumed by the handshake manager
 
+use std::num::NonZeroUsize;
+
 use ark_mpc::network::QuicTwoPartyNet;
 use circuit_types::wallet::Nullifier;
-use common::types::{gossip::WrappedPeerId, wallet::OrderIdentifier};
+use common::{
+    new_shared,
+    types::{gossip::WrappedPeerId, handshake::OrderMatchStats, wallet::OrderIdentifier},
+    Shared,
+};
 use constants::SystemCurveGroup;
 use gossip_api::request_response::{handshake::HandshakeMessage, AuthenticatedGossipResponse};
 use libp2p::request_response::ResponseChannel;
+use lru::LruCache;
 use tokio::sync::mpsc::{
     unbounded_channel, UnboundedReceiver as TokioReceiver, UnboundedSender as TokioSender,
 };
@@ -21,6 +28,22 @@ pub fn new_handshake_manager_queue() -> (HandshakeManagerQueue, HandshakeManager
     unbounded_channel()
 }
 
+/// The maximum number of orders whose match statistics are kept in the ledger
+const MAX_ORDER_MATCH_STATS: usize = 10_000;
+
+/// The match statistics of each locally managed order
+///
+/// The handshake manager updates the statistics as it proposes, fails, and
+/// completes matches, the API server reads from them. The ledger is bounded,
+/// evicting the statistics of the least recently updated order once full
+pub type OrderMatchStatsLedger = Shared<LruCache<OrderIdentifier, OrderMatchStats>>;
+
+/// Create a new, empty order match statistics ledger
+pub fn new_order_match_stats_ledger() -> OrderMatchStatsLedger {
+    let cap = NonZeroUsize::new(MAX_ORDER_MATCH_STATS).unwrap();
+    new_shared(LruCache::new(cap))
+}
+
 /// Represents a job for the handshake manager's thread pool to execute
 #[allow(clippy::large_enum_variant)]
 pub enum HandshakeExecutionJob {
@@ -64,6 +87,14 @@ pub enum HandshakeExecutionJob {
         /// on this nullifier are to be terminated
         nullifier: Nullifier,
     },
+    /// Indicates that the local peer should abort the in-flight match with the
+    /// given request ID, leaving other matches running
+    ///
+    /// The order pair of the match may be scheduled again once it is aborted
+    CancelMatch {
+        /// The ID of the handshake request to cancel
+        request_id: Uuid,
+    },
     /// Indicates that a cluster replica has initiated a match on the given
     /// order pair. The local peer should not schedule this order pair for a
     /// match for some duration
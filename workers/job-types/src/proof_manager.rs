@@ -18,6 +18,8 @@ use circuits::zk_circuits::{
     valid_wallet_create::{SizedValidWalletCreateStatement, SizedValidWalletCreateWitness},
     valid_wallet_update::{SizedValidWalletUpdateStatement, SizedValidWalletUpdateWitness},
 };
+use std::{error::Error, fmt::Display};
+
 use common::types::proof_bundles::ProofBundle;
 use crossbeam::channel::{unbounded, Receiver as CrossbeamReceiver, Sender as CrossbeamSender};
 use tokio::sync::oneshot::Sender;
@@ -32,18 +34,59 @@ pub fn new_proof_manager_queue() -> (ProofManagerQueue, ProofManagerReceiver) {
     unbounded()
 }
 
+/// Get the number of jobs waiting in the proof manager's queue
+///
+/// The queue is unbounded, so callers use this to shed load before the backlog
+/// grows without limit
+pub fn proof_queue_depth(queue: &ProofManagerQueue) -> usize {
+    queue.len()
+}
+
 // -------------
 // | Job Types |
 // -------------
 
+/// The result of a proof job, sent back along the job's response channel
+pub type ProofJobResult = Result<ProofBundle, ProofJobError>;
+
 /// Represents a job enqueued in the proof manager's work queue
 #[derive(Debug)]
 pub struct ProofManagerJob {
     /// The type of job being requested
     pub type_: ProofJob,
     /// The response channel to send the proof back along
-    pub response_channel: Sender<ProofBundle>,
+    pub response_channel: Sender<ProofJobResult>,
+}
+
+/// An error generating the proof for a job
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofJobError {
+    /// A description of the failure
+    pub message: String,
+    /// Whether the job may succeed if it is attempted again
+    pub retryable: bool,
+}
+
+impl ProofJobError {
+    /// A failure that may clear on a later attempt, e.g. the proof manager
+    /// dropping the job
+    pub fn retryable<T: ToString>(message: T) -> Self {
+        Self { message: message.to_string(), retryable: true }
+    }
+
+    /// A failure that recurs for every attempt at the same job, e.g. a witness
+    /// that does not satisfy the circuit
+    pub fn permanent<T: ToString>(message: T) -> Self {
+        Self { message: message.to_string(), retryable: false }
+    }
+}
+
+impl Display for ProofJobError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
 }
+impl Error for ProofJobError {}
 
 /// The job type and parameterization
 #[derive(Clone, Debug)]
@@ -1,8 +1,16 @@
 //! Job types for the task driver
 
-use common::types::{
-    tasks::{QueuedTask, TaskDescriptor, TaskIdentifier},
-    wallet::WalletIdentifier,
+use std::collections::{HashMap, VecDeque};
+
+use common::{
+    new_shared,
+    types::{
+        tasks::{
+            MatchGasRecord, QueuedTask, RunningTaskInfo, TaskDescriptor, TaskIdentifier, TaskOutput,
+        },
+        wallet::WalletIdentifier,
+    },
+    Shared,
 };
 use crossbeam::channel::{Receiver as CrossbeamReceiver, Sender as CrossbeamSender};
 use tokio::sync::oneshot::{
@@ -13,10 +21,37 @@ use tokio::sync::oneshot::{
 pub type TaskDriverQueue = CrossbeamSender<TaskDriverJob>;
 /// The queue receiver type to receive jobs for the task driver
 pub type TaskDriverReceiver = CrossbeamReceiver<TaskDriverJob>;
+/// The result of a task sent on a notification channel, a completed task may
+/// report an output
+pub type TaskNotification = Result<Option<TaskOutput>, String>;
 /// The sender type of a task notification channel
-pub type TaskNotificationSender = OneshotSender<Result<(), String>>;
+pub type TaskNotificationSender = OneshotSender<TaskNotification>;
 /// The receiver type of a task notification channel
-pub type TaskNotificationReceiver = OneshotReceiver<Result<(), String>>;
+pub type TaskNotificationReceiver = OneshotReceiver<TaskNotification>;
+
+/// The registry of tasks currently running in the task driver
+///
+/// The task driver writes to the registry as tasks start, step, and complete,
+/// other workers may read from it to inspect the driver
+pub type TaskRegistry = Shared<HashMap<TaskIdentifier, RunningTaskInfo>>;
+
+/// Create a new, empty task registry
+pub fn new_task_registry() -> TaskRegistry {
+    new_shared(HashMap::new())
+}
+
+/// The gas used by match settlements the local relayer submitted, in the order
+/// they were recorded
+///
+/// The task driver records settlements as they are submitted, the API server
+/// reads from the ledger for profitability analysis. A pair of orders matched
+/// more than once holds a record per settlement
+pub type MatchGasLedger = Shared<VecDeque<MatchGasRecord>>;
+
+/// Create a new, empty match gas ledger
+pub fn new_match_gas_ledger() -> MatchGasLedger {
+    new_shared(VecDeque::new())
+}
 
 /// Create a new task driver queue
 pub fn new_task_driver_queue() -> (TaskDriverQueue, TaskDriverReceiver) {
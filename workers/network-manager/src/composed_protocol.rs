@@ -34,13 +34,6 @@ use std::{
 
 use super::error::NetworkManagerError;
 
-// -------------
-// | Constants |
-// -------------
-
-/// The maximum size libp2p should allocate buffer space for
-const MAX_MESSAGE_SIZE: usize = 1_000_000_000;
-
 /// The composed behavior that handles all types of network requests that
 /// various workers need access to
 #[derive(NetworkBehaviour)]
@@ -61,14 +54,18 @@ pub struct ComposedNetworkBehavior {
 
 impl ComposedNetworkBehavior {
     /// Construct the behavior
+    ///
+    /// Every inbound request, response, and pubsub message is limited to
+    /// `max_message_bytes`, whatever its contents
     pub fn new(
         peer_id: PeerId,
         protocol_version: ProtocolVersion,
         keypair: &Keypair,
+        max_message_bytes: usize,
     ) -> Result<Self, NetworkManagerError> {
         // Construct the point-to-point request response protocol
         let request_response = RequestResponse::new(
-            RelayerGossipCodec::new(),
+            RelayerGossipCodec::new(max_message_bytes),
             iter::once((RelayerGossipProtocol::new(protocol_version), ProtocolSupport::Full)),
             Default::default(),
         );
@@ -80,7 +77,7 @@ impl ComposedNetworkBehavior {
         // Construct the pubsub network behavior
         let pubsub = Gossipsub::new(
             MessageAuthenticity::Signed(keypair.clone()),
-            GossipsubConfigBuilder::default().max_transmit_size(MAX_MESSAGE_SIZE).build().unwrap(),
+            GossipsubConfigBuilder::default().max_transmit_size(max_message_bytes).build().unwrap(),
         )
         .map_err(|err| NetworkManagerError::SetupError(err.to_string()))?;
 
@@ -181,12 +178,20 @@ impl ProtocolName for RelayerGossipProtocol {
 }
 
 /// The request/response codec used in the gossip protocol
-#[derive(Clone, Default)]
-pub struct RelayerGossipCodec;
+#[derive(Clone)]
+pub struct RelayerGossipCodec {
+    /// The maximum size in bytes of an inbound request or response
+    ///
+    /// The limit applies to every message on the protocol, including raft
+    /// traffic, and is checked against the length prefix before a message is
+    /// read or decoded
+    max_message_bytes: usize,
+}
+
 impl RelayerGossipCodec {
     /// Create a new instance of the marshal/unmarshal codec
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(max_message_bytes: usize) -> Self {
+        Self { max_message_bytes }
     }
 }
 
@@ -205,13 +210,12 @@ impl RequestResponseCodec for RelayerGossipCodec {
     where
         T: AsyncRead + Unpin + Send,
     {
-        let req_data = read_length_prefixed(io, MAX_MESSAGE_SIZE).await?;
+        let req_data = read_length_prefixed(io, self.max_message_bytes).await?;
         if req_data.is_empty() {
             return Err(IoError::new(ErrorKind::InvalidData, "empty request"));
         }
 
-        let deserialized: AuthenticatedGossipRequest = serde_json::from_slice(&req_data).unwrap();
-        Ok(deserialized)
+        serde_json::from_slice(&req_data).map_err(|e| IoError::new(ErrorKind::InvalidData, e))
     }
 
     /// Deserializes a read response
@@ -223,13 +227,12 @@ impl RequestResponseCodec for RelayerGossipCodec {
     where
         T: AsyncRead + Unpin + Send,
     {
-        let resp_data = read_length_prefixed(io, MAX_MESSAGE_SIZE).await?;
+        let resp_data = read_length_prefixed(io, self.max_message_bytes).await?;
         if resp_data.is_empty() {
             return Err(IoError::new(ErrorKind::InvalidData, "empty response"));
         }
 
-        let deserialized: AuthenticatedGossipResponse = serde_json::from_slice(&resp_data).unwrap();
-        Ok(deserialized)
+        serde_json::from_slice(&resp_data).map_err(|e| IoError::new(ErrorKind::InvalidData, e))
     }
 
     /// Serializes a write request
@@ -268,3 +271,47 @@ impl RequestResponseCodec for RelayerGossipCodec {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use futures::{executor::block_on, io::Cursor};
+    use libp2p::{core::upgrade::write_length_prefixed, request_response::Codec};
+    use std::io::ErrorKind;
+
+    use super::{ProtocolVersion, RelayerGossipCodec, RelayerGossipProtocol};
+
+    /// The message size limit used in tests
+    const MAX_MESSAGE_BYTES: usize = 1024;
+
+    /// Frame the given payload with its length prefix
+    fn framed(payload: &[u8]) -> Vec<u8> {
+        let mut buf = Cursor::new(Vec::new());
+        block_on(write_length_prefixed(&mut buf, payload)).unwrap();
+        buf.into_inner()
+    }
+
+    /// Tests that a payload over the message size limit is rejected from its
+    /// length prefix, before it is decoded
+    #[test]
+    fn test_oversized_payload_rejected() {
+        let mut codec = RelayerGossipCodec::new(MAX_MESSAGE_BYTES);
+        let protocol = RelayerGossipProtocol::new(ProtocolVersion::Version0);
+
+        // The payload is not valid JSON, so it would fail to decode were it read
+        let oversized = framed(&vec![b'x'; MAX_MESSAGE_BYTES + 1]);
+        let req = block_on(codec.read_request(&protocol, &mut Cursor::new(oversized.clone())));
+        let err = req.err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(err.to_string().contains("exceeds maximum"));
+
+        let resp = block_on(codec.read_response(&protocol, &mut Cursor::new(oversized)));
+        assert!(resp.err().unwrap().to_string().contains("exceeds maximum"));
+
+        // A payload within the limit is decoded, and fails as invalid data
+        let malformed = framed(&vec![b'x'; MAX_MESSAGE_BYTES]);
+        let req = block_on(codec.read_request(&protocol, &mut Cursor::new(malformed)));
+        let err = req.err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(!err.to_string().contains("exceeds maximum"));
+    }
+}
@@ -50,6 +50,9 @@ pub struct NetworkManagerConfig {
     /// The known public addr that the local node is listening behind, if one
     /// exists
     pub known_public_addr: Option<SocketAddr>,
+    /// The maximum size in bytes of any inbound gossip request, response, or
+    /// pubsub message, larger messages are rejected before they are decoded
+    pub max_message_bytes: usize,
     /// The channel on which to receive requests from other workers
     /// for outbound traffic
     /// This is wrapped in an option to allow the worker thread to take
@@ -148,6 +151,7 @@ impl Worker for NetworkManager {
             *self.local_peer_id,
             ProtocolVersion::Version0,
             &self.local_keypair,
+            self.config.max_message_bytes,
         )?;
 
         // Add any bootstrap addresses to the peer info table
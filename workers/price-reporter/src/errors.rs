@@ -14,15 +14,28 @@ pub enum ExchangeConnectionError {
     ConnectionHangup(String),
     /// An initial websocket subscription to a remote server failed.
     HandshakeFailure(String),
+    /// An exchange URL does not point at the host pinned for the exchange
+    HostMismatch(String),
+    /// An exchange URL does not use TLS, and plaintext connections are not
+    /// permitted
+    InsecureUrl(String),
     /// Could not parse a remote server message.
     InvalidMessage(String),
+    /// A remote server sent a frame or message larger than the configured
+    /// limits
+    MessageTooLarge(String),
     /// The maximum retry count was exceeded while trying to re-establish
     /// an exchange connection
     MaxRetries(Exchange),
+    /// A remote server left too many consecutive keepalive pings unanswered
+    MissedPongs(usize),
     /// No exchanges support the given token pair
     NoSupportedExchanges(Token, Token),
     /// Error sending on the `write` end of the websocket
     SendError(String),
+    /// The TLS handshake with a remote server failed, e.g. on an invalid
+    /// certificate
+    TlsFailure(String),
 }
 
 impl Error for ExchangeConnectionError {}
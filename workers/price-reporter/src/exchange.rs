@@ -51,6 +51,10 @@ pub async fn connect_exchange(
         Exchange::UniswapV3 => {
             Box::new(UniswapV3Connection::connect(base_token, quote_token, config).await?)
         },
+        // Overridden prices are configured rather than streamed
+        Exchange::Override => {
+            return Err(ExchangeConnectionError::NoSupportedExchanges(base_token, quote_token))
+        },
     })
 }
 
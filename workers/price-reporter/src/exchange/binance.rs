@@ -21,7 +21,8 @@ use crate::{errors::ExchangeConnectionError, worker::PriceReporterConfig};
 
 use super::{
     connection::{
-        parse_json_field, parse_json_from_message, ws_connect, ws_ping, ExchangeConnection,
+        parse_json_field, parse_json_from_message, track_pongs, ws_connect, ws_keepalive,
+        ws_read_error, ExchangeConnection, PongTracker,
     },
     get_current_time, InitializablePriceStream, PriceStreamType,
 };
@@ -52,6 +53,8 @@ pub struct BinanceConnection {
     price_stream: Box<dyn Stream<Item = PriceStreamType> + Unpin + Send>,
     /// The underlying write stream of the websocket
     write_stream: Box<dyn Sink<Message, Error = WsError> + Unpin + Send>,
+    /// Tracks the keepalive pings left unanswered by the exchange
+    pong_tracker: PongTracker,
 }
 
 impl BinanceConnection {
@@ -101,6 +104,7 @@ impl BinanceConnection {
             midpoint_price,
             reported_timestamp: None,
             local_timestamp: get_current_time(),
+            smoothed: false,
         })
     }
 
@@ -139,7 +143,7 @@ impl ExchangeConnection for BinanceConnection {
     async fn connect(
         base_token: Token,
         quote_token: Token,
-        _config: &PriceReporterConfig,
+        config: &PriceReporterConfig,
     ) -> Result<Self, ExchangeConnectionError>
     where
         Self: Sized,
@@ -150,10 +154,12 @@ impl ExchangeConnection for BinanceConnection {
 
         // Connect to the websocket
         let url = Self::websocket_url(&base_token, &quote_token);
-        let (write, read) = ws_connect(url).await?;
+        let (write, read) =
+            ws_connect(url, config.ws_config(), &config.tls_policy(Exchange::Binance)).await?;
 
         // Map the stream to process midpoint prices
-        let mapped_stream = read.filter_map(|message| async {
+        let pong_tracker = PongTracker::new(config.max_missed_pongs);
+        let mapped_stream = track_pongs(read, pong_tracker.clone()).filter_map(|message| async {
             match message.map(Self::midpoint_from_ws_message) {
                 // The result is for reading a message from the websocket, the inner result is for
                 // processing that message and returns an option. Flip the order of the option and
@@ -162,7 +168,7 @@ impl ExchangeConnection for BinanceConnection {
                 // Error on the incoming (filtered) stream
                 Err(e) => {
                     error!("Error reading message from Binance ws: {}", e);
-                    Some(Err(ExchangeConnectionError::ConnectionHangup(e.to_string())))
+                    Some(Err(ws_read_error(e)))
                 },
             }
         });
@@ -174,10 +180,14 @@ impl ExchangeConnection for BinanceConnection {
             initial_price_report.midpoint_price,
         );
 
-        Ok(Self { price_stream: Box::new(price_stream), write_stream: Box::new(write) })
+        Ok(Self {
+            price_stream: Box::new(price_stream),
+            write_stream: Box::new(write),
+            pong_tracker,
+        })
     }
 
     async fn send_keepalive(&mut self) -> Result<(), ExchangeConnectionError> {
-        ws_ping(&mut self.write_stream).await
+        ws_keepalive(&mut self.write_stream, Message::Ping(vec![]), &self.pong_tracker).await
     }
 }
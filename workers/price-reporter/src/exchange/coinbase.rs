@@ -20,7 +20,8 @@ use crate::{errors::ExchangeConnectionError, worker::PriceReporterConfig};
 
 use super::{
     connection::{
-        parse_json_field, parse_json_from_message, ws_connect, ws_ping, ExchangeConnection,
+        parse_json_field, parse_json_from_message, track_pongs, ws_connect, ws_keepalive,
+        ws_read_error, ExchangeConnection, PongTracker,
     },
     Exchange, InitializablePriceStream, PriceStreamType,
 };
@@ -55,6 +56,8 @@ pub struct CoinbaseConnection {
     price_stream: Box<dyn Stream<Item = PriceStreamType> + Unpin + Send>,
     /// The underlying write stream of the websocket
     write_stream: Box<dyn Sink<Message, Error = WsError> + Unpin + Send>,
+    /// Tracks the keepalive pings left unanswered by the exchange
+    pong_tracker: PongTracker,
 }
 
 /// The order book data stored locally by the connection
@@ -190,7 +193,8 @@ impl ExchangeConnection for CoinbaseConnection {
     ) -> Result<Self, ExchangeConnectionError> {
         // Build the base websocket connection
         let url = Self::websocket_url();
-        let (mut writer, read) = ws_connect(url).await?;
+        let (mut writer, read) =
+            ws_connect(url, config.ws_config(), &config.tls_policy(Exchange::Coinbase)).await?;
 
         // Subscribe to the order book
         let api_key = config
@@ -212,7 +216,8 @@ impl ExchangeConnection for CoinbaseConnection {
             .map_err(|err| ExchangeConnectionError::ConnectionHangup(err.to_string()))?;
 
         // Map the stream of Coinbase messages to one of midpoint prices
-        let mapped_stream = read.filter_map(move |message| {
+        let pong_tracker = PongTracker::new(config.max_missed_pongs);
+        let mapped_stream = track_pongs(read, pong_tracker.clone()).filter_map(move |message| {
             let mut order_book = CoinbaseOrderBookData::default();
             async move {
                 match message {
@@ -225,7 +230,7 @@ impl ExchangeConnection for CoinbaseConnection {
 
                     Err(e) => {
                         error!("Error reading message from Coinbase websocket: {e}");
-                        Some(Err(ExchangeConnectionError::ConnectionHangup(e.to_string())))
+                        Some(Err(ws_read_error(e)))
                     },
                 }
             }
@@ -235,11 +240,14 @@ impl ExchangeConnection for CoinbaseConnection {
         // stream
         let price_stream = InitializablePriceStream::new(Box::pin(mapped_stream));
 
-        Ok(Self { price_stream: Box::new(price_stream), write_stream: Box::new(writer) })
+        Ok(Self {
+            price_stream: Box::new(price_stream),
+            write_stream: Box::new(writer),
+            pong_tracker,
+        })
     }
 
     async fn send_keepalive(&mut self) -> Result<(), ExchangeConnectionError> {
-        // Send a ping message
-        ws_ping(&mut self.write_stream).await
+        ws_keepalive(&mut self.write_stream, Message::Ping(vec![]), &self.pong_tracker).await
     }
 }
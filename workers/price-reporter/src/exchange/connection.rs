@@ -7,15 +7,27 @@ use futures_util::{
     stream::{SplitSink, SplitStream},
     Sink, SinkExt, Stream,
 };
+use hmac_sha256::Hash;
+use rustls::{
+    client::{ServerCertVerified, ServerCertVerifier, WebPkiVerifier},
+    Certificate, ClientConfig, OwnedTrustAnchor, RootCertStore, ServerName,
+};
 use serde_json::Value;
 use std::{
+    io,
     str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
     time::{SystemTime, UNIX_EPOCH},
 };
 use tokio::net::TcpStream;
-use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+use tokio_tungstenite::{
+    connect_async_tls_with_config, tungstenite::Message, Connector, MaybeTlsStream, WebSocketStream,
+};
 use tracing::error;
-use tungstenite::Error as WsError;
+use tungstenite::{protocol::WebSocketConfig, Error as WsError};
 use url::Url;
 
 use crate::worker::PriceReporterConfig;
@@ -32,14 +44,24 @@ const PROTOCOL_VIOLATION_MSG: &str = "Protocol violation";
 const PONG_MESSAGE: &str = "pong";
 /// The message passed when a ws proxy resets
 const CLOUDFLARE_RESET_MESSAGE: &str = "CloudFlare WebSocket proxy restarting";
+/// The error message emitted when a server's certificate matches none of the
+/// certificates pinned for it
+const ERR_CERT_PIN_MISMATCH: &str = "server certificate does not match any pinned certificate";
 
 // -----------
 // | Helpers |
 // -----------
 
 /// Build a websocket connection to the given endpoint
+///
+/// The connection rejects frames and messages beyond the configured limits so
+/// that a misbehaving feed cannot grow the read buffer without bound. The URL
+/// must satisfy the given TLS policy, and the server's certificate is validated
+/// against the webpki trust roots and the policy's certificate pins
 pub(super) async fn ws_connect(
     url: Url,
+    ws_config: WebSocketConfig,
+    tls_policy: &TlsPolicy,
 ) -> Result<
     (
         SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>,
@@ -47,26 +69,85 @@ pub(super) async fn ws_connect(
     ),
     ExchangeConnectionError,
 > {
-    let ws_conn = match connect_async(url.clone()).await {
-        Ok((conn, _resp)) => conn,
-        Err(e) => {
-            error!("Cannot connect to the remote URL: {}", url);
-            return Err(ExchangeConnectionError::HandshakeFailure(e.to_string()));
-        },
-    };
+    tls_policy.check_url(&url)?;
+    let connector = tls_policy.connector();
+    let ws_conn =
+        match connect_async_tls_with_config(url.clone(), Some(ws_config), Some(connector)).await {
+            Ok((conn, _resp)) => conn,
+            Err(WsError::Tls(e)) => {
+                error!("TLS handshake with the remote URL failed: {}", url);
+                return Err(ExchangeConnectionError::TlsFailure(e.to_string()));
+            },
+            // rustls surfaces handshake failures as IO errors
+            Err(WsError::Io(e)) if is_tls_error(&e) => {
+                error!("TLS handshake with the remote URL failed: {}", url);
+                return Err(ExchangeConnectionError::TlsFailure(e.to_string()));
+            },
+            Err(e) => {
+                error!("Cannot connect to the remote URL: {}", url);
+                return Err(ExchangeConnectionError::HandshakeFailure(e.to_string()));
+            },
+        };
 
     let (ws_sink, ws_stream) = ws_conn.split();
     Ok((ws_sink, ws_stream))
 }
 
-/// Send a default ping message on the websocket
-pub(super) async fn ws_ping<S: Sink<Message, Error = WsError> + Unpin>(
+/// Whether an IO error raised while connecting was caused by the TLS handshake
+fn is_tls_error(err: &io::Error) -> bool {
+    err.get_ref().map_or(false, |inner| inner.is::<rustls::Error>())
+}
+
+/// Convert an error reading from an exchange websocket into a connection error
+///
+/// Both cases are handled by re-establishing the connection, but oversized
+/// messages are distinguished so that they are not mistaken for a hangup
+pub(super) fn ws_read_error(err: WsError) -> ExchangeConnectionError {
+    match err {
+        WsError::Capacity(e) => ExchangeConnectionError::MessageTooLarge(e.to_string()),
+        e => ExchangeConnectionError::ConnectionHangup(e.to_string()),
+    }
+}
+
+/// Send a keepalive ping on the websocket, recording it with the given pong
+/// tracker
+///
+/// Errors if the peer has left too many previous pings unanswered, in which
+/// case the connection should be re-established
+pub(super) async fn ws_keepalive<S: Sink<Message, Error = WsError> + Unpin>(
     ws_sink: &mut S,
+    ping: Message,
+    pong_tracker: &PongTracker,
 ) -> Result<(), ExchangeConnectionError> {
-    ws_sink
-        .send(Message::Ping(vec![]))
-        .await
-        .map_err(|e| ExchangeConnectionError::SendError(e.to_string()))
+    pong_tracker.record_ping()?;
+    ws_sink.send(ping).await.map_err(|e| ExchangeConnectionError::SendError(e.to_string()))
+}
+
+/// Wrap a websocket read stream so that the pongs it yields are recorded with
+/// the given tracker
+pub(super) fn track_pongs<S: Stream<Item = Result<Message, WsError>>>(
+    stream: S,
+    pong_tracker: PongTracker,
+) -> impl Stream<Item = Result<Message, WsError>> {
+    stream.inspect(move |message| {
+        if let Ok(message) = message
+            && is_pong(message)
+        {
+            pong_tracker.record_pong();
+        }
+    })
+}
+
+/// Whether the given message answers a keepalive ping
+///
+/// Okx answers its text pings with a text pong, other exchanges with a
+/// protocol pong
+fn is_pong(message: &Message) -> bool {
+    match message {
+        Message::Pong(_) => true,
+        Message::Text(text) => text == PONG_MESSAGE,
+        _ => false,
+    }
 }
 
 /// Helper to parse a value from a JSON response
@@ -128,6 +209,147 @@ pub fn get_current_time() -> u64 {
     SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64
 }
 
+// --------------
+// | TLS Policy |
+// --------------
+
+/// The transport security required of a connection to an exchange
+#[derive(Clone, Debug, Default)]
+pub struct TlsPolicy {
+    /// Whether plaintext `ws://` connections are permitted, e.g. for devnet
+    /// feeds
+    pub allow_insecure: bool,
+    /// The host the exchange's URL must point at, if pinned
+    pub pinned_host: Option<String>,
+    /// The hex encoded SHA-256 fingerprints of the certificates the
+    /// exchange's server may present, any certificate chaining to a trust root
+    /// is accepted if empty
+    pub cert_pins: Vec<String>,
+}
+
+impl TlsPolicy {
+    /// Check that the given URL satisfies the policy
+    pub fn check_url(&self, url: &Url) -> Result<(), ExchangeConnectionError> {
+        match url.scheme() {
+            "wss" => {},
+            "ws" if self.allow_insecure => {},
+            _ => return Err(ExchangeConnectionError::InsecureUrl(url.to_string())),
+        }
+
+        if let Some(host) = &self.pinned_host
+            && url.host_str() != Some(host.as_str())
+        {
+            return Err(ExchangeConnectionError::HostMismatch(url.to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Build the TLS connector for a connection under the policy
+    ///
+    /// The server's certificate is checked against the policy's pins during
+    /// the handshake, before its chain is validated against the webpki trust
+    /// roots for the URL's host
+    pub fn connector(&self) -> Connector {
+        let mut roots = RootCertStore::empty();
+        roots.add_server_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.0.iter().map(|ta| {
+            OwnedTrustAnchor::from_subject_spki_name_constraints(
+                ta.subject,
+                ta.spki,
+                ta.name_constraints,
+            )
+        }));
+
+        let verifier = PinnedCertVerifier {
+            inner: WebPkiVerifier::new(roots, None),
+            pins: self.cert_pins.clone(),
+        };
+        let config = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_custom_certificate_verifier(Arc::new(verifier))
+            .with_no_client_auth();
+
+        Connector::Rustls(Arc::new(config))
+    }
+}
+
+/// Verifies a server's certificate against a set of pinned certificates, then
+/// validates its chain as usual
+struct PinnedCertVerifier {
+    /// The verifier validating the certificate chain against the trust roots
+    inner: WebPkiVerifier,
+    /// The hex encoded SHA-256 fingerprints of the accepted certificates, any
+    /// certificate is accepted if empty
+    pins: Vec<String>,
+}
+
+impl ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        server_name: &ServerName,
+        scts: &mut dyn Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        if !self.pins.is_empty() {
+            let fingerprint = hex::encode(Hash::hash(&end_entity.0));
+            if !self.pins.contains(&fingerprint) {
+                return Err(rustls::Error::General(ERR_CERT_PIN_MISMATCH.to_string()));
+            }
+        }
+
+        self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            scts,
+            ocsp_response,
+            now,
+        )
+    }
+}
+
+// -----------------
+// | Pong Tracking |
+// -----------------
+
+/// Tracks the keepalive pings sent on a websocket that have not yet been
+/// answered by a pong
+#[derive(Clone, Debug)]
+pub struct PongTracker {
+    /// The number of pings sent since the last pong was received
+    outstanding_pings: Arc<AtomicUsize>,
+    /// The number of consecutive pings that may go unanswered before the
+    /// connection is considered dead
+    max_missed_pongs: usize,
+}
+
+impl PongTracker {
+    /// Constructor
+    pub fn new(max_missed_pongs: usize) -> Self {
+        Self { outstanding_pings: Arc::new(AtomicUsize::new(0)), max_missed_pongs }
+    }
+
+    /// Record that a ping is being sent
+    ///
+    /// Errors if the peer has already missed the maximum number of pongs
+    pub fn record_ping(&self) -> Result<(), ExchangeConnectionError> {
+        let missed = self.outstanding_pings.fetch_add(1, Ordering::Relaxed);
+        if missed >= self.max_missed_pongs {
+            return Err(ExchangeConnectionError::MissedPongs(missed));
+        }
+
+        Ok(())
+    }
+
+    /// Record that a pong was received, answering all outstanding pings
+    pub fn record_pong(&self) {
+        self.outstanding_pings.store(0, Ordering::Relaxed);
+    }
+}
+
 // --------------------------
 // | Connection Abstraction |
 // --------------------------
@@ -148,3 +370,187 @@ pub trait ExchangeConnection: Stream<Item = PriceStreamType> + Unpin + Send {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use futures::{SinkExt, StreamExt};
+    use hmac_sha256::Hash;
+    use rustls::{Certificate, PrivateKey, ServerConfig};
+    use tokio::net::TcpListener;
+    use tokio_rustls::TlsAcceptor;
+    use tokio_tungstenite::{accept_async, tungstenite::Message};
+    use tungstenite::protocol::WebSocketConfig;
+    use url::Url;
+
+    use crate::errors::ExchangeConnectionError;
+
+    use super::{
+        track_pongs, ws_connect, ws_keepalive, ws_read_error, PongTracker, TlsPolicy,
+        ERR_CERT_PIN_MISMATCH,
+    };
+
+    /// The frame size limit used in the test
+    const MAX_FRAME_SIZE: usize = 1024;
+
+    /// A TLS policy permitting the plaintext connections served in tests
+    fn insecure_policy() -> TlsPolicy {
+        TlsPolicy { allow_insecure: true, ..Default::default() }
+    }
+
+    /// Serve TLS handshakes on a local port with a self-signed certificate for
+    /// `localhost`, returning the port and the certificate's fingerprint
+    async fn serve_tls() -> (u16, String) {
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let cert_der = cert.serialize_der().unwrap();
+        let fingerprint = hex::encode(Hash::hash(&cert_der));
+        let server_config = ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![Certificate(cert_der)],
+                PrivateKey(cert.serialize_private_key_der()),
+            )
+            .unwrap();
+        let acceptor = TlsAcceptor::from(Arc::new(server_config));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let _ = acceptor.accept(stream).await;
+            }
+        });
+
+        (port, fingerprint)
+    }
+
+    /// Serve the given messages over a local websocket, one connection per
+    /// message
+    async fn serve_messages(messages: Vec<String>) -> Url {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            for msg in messages {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut ws = accept_async(stream).await.unwrap();
+                let _ = ws.send(Message::Text(msg)).await;
+            }
+        });
+
+        Url::parse(&format!("ws://{addr}")).unwrap()
+    }
+
+    /// Tests that an oversized frame is rejected and that the connection may
+    /// be re-established afterwards
+    #[tokio::test]
+    async fn test_oversized_frame_reconnect() {
+        let oversized = "a".repeat(MAX_FRAME_SIZE + 1);
+        let valid = "b".repeat(MAX_FRAME_SIZE / 2);
+        let url = serve_messages(vec![oversized, valid.clone()]).await;
+        let config = WebSocketConfig {
+            max_frame_size: Some(MAX_FRAME_SIZE),
+            max_message_size: Some(MAX_FRAME_SIZE),
+            ..Default::default()
+        };
+
+        // The oversized frame errors the stream rather than being buffered
+        let (_write, mut read) = ws_connect(url.clone(), config, &insecure_policy()).await.unwrap();
+        let err = read.next().await.unwrap().map_err(ws_read_error).unwrap_err();
+        assert!(matches!(err, ExchangeConnectionError::MessageTooLarge(_)));
+
+        // Reconnecting yields a healthy stream
+        let (_write, mut read) = ws_connect(url, config, &insecure_policy()).await.unwrap();
+        let msg = read.next().await.unwrap().unwrap();
+        assert_eq!(msg, Message::Text(valid));
+    }
+
+    /// Tests that pongs answering keepalive pings reset the tracker, and that
+    /// unanswered pings eventually error
+    #[tokio::test]
+    async fn test_missed_pongs() {
+        const MAX_MISSED_PONGS: usize = 1;
+
+        // The server answers pings as it reads them
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(stream).await.unwrap();
+            while let Some(Ok(_)) = ws.next().await {}
+        });
+
+        let url = Url::parse(&format!("ws://{addr}")).unwrap();
+        let (mut write, read) =
+            ws_connect(url, WebSocketConfig::default(), &insecure_policy()).await.unwrap();
+        let tracker = PongTracker::new(MAX_MISSED_PONGS);
+        let mut read = Box::pin(track_pongs(read, tracker.clone()));
+
+        // Answered pings never exhaust the tracker
+        for _ in 0..3 {
+            ws_keepalive(&mut write, Message::Ping(vec![]), &tracker).await.unwrap();
+            let msg = read.next().await.unwrap().unwrap();
+            assert!(matches!(msg, Message::Pong(_)));
+        }
+
+        // Pings sent without reading the pongs are unanswered
+        ws_keepalive(&mut write, Message::Ping(vec![]), &tracker).await.unwrap();
+        let err = ws_keepalive(&mut write, Message::Ping(vec![]), &tracker).await.unwrap_err();
+        assert!(matches!(err, ExchangeConnectionError::MissedPongs(MAX_MISSED_PONGS)));
+    }
+
+    /// Tests that a plaintext URL is rejected unless explicitly allowed
+    #[tokio::test]
+    async fn test_insecure_url_rejected() {
+        let url = serve_messages(vec!["a".to_string()]).await;
+        let res = ws_connect(url.clone(), WebSocketConfig::default(), &TlsPolicy::default()).await;
+        assert!(matches!(res, Err(ExchangeConnectionError::InsecureUrl(_))));
+
+        ws_connect(url, WebSocketConfig::default(), &insecure_policy()).await.unwrap();
+    }
+
+    /// Tests that a URL whose host differs from the pinned host is rejected
+    #[test]
+    fn test_pinned_host_mismatch() {
+        let policy =
+            TlsPolicy { pinned_host: Some("ws.kraken.com".to_string()), ..Default::default() };
+
+        let url = Url::parse("wss://ws.kraken.com").unwrap();
+        policy.check_url(&url).unwrap();
+
+        let url = Url::parse("wss://ws.kraken.example.com").unwrap();
+        let res = policy.check_url(&url);
+        assert!(matches!(res, Err(ExchangeConnectionError::HostMismatch(_))));
+    }
+
+    /// Tests that a server presenting a certificate other than the pinned one
+    /// fails the TLS handshake, and that a matching pin does not bypass chain
+    /// validation
+    #[tokio::test]
+    async fn test_cert_pin_mismatch() {
+        let (port, fingerprint) = serve_tls().await;
+        let url = Url::parse(&format!("wss://localhost:{port}")).unwrap();
+
+        // A certificate matching none of the pins is rejected
+        let policy = TlsPolicy { cert_pins: vec!["00".repeat(32)], ..Default::default() };
+        match ws_connect(url.clone(), WebSocketConfig::default(), &policy).await {
+            Err(ExchangeConnectionError::TlsFailure(msg)) => {
+                assert!(msg.contains(ERR_CERT_PIN_MISMATCH))
+            },
+            Err(e) => panic!("expected a TLS failure, got {e:?}"),
+            Ok(_) => panic!("expected a TLS failure"),
+        }
+
+        // The pinned self-signed certificate still fails chain validation
+        let policy = TlsPolicy { cert_pins: vec![fingerprint], ..Default::default() };
+        match ws_connect(url, WebSocketConfig::default(), &policy).await {
+            Err(ExchangeConnectionError::TlsFailure(msg)) => {
+                assert!(!msg.contains(ERR_CERT_PIN_MISMATCH))
+            },
+            Err(e) => panic!("expected a TLS failure, got {e:?}"),
+            Ok(_) => panic!("expected a TLS failure"),
+        }
+    }
+}
@@ -19,7 +19,8 @@ use crate::{errors::ExchangeConnectionError, worker::PriceReporterConfig};
 
 use super::{
     connection::{
-        parse_json_field_array, parse_json_from_message, ws_connect, ws_ping, ExchangeConnection,
+        parse_json_field_array, parse_json_from_message, track_pongs, ws_connect, ws_keepalive,
+        ws_read_error, ExchangeConnection, PongTracker,
     },
     InitializablePriceStream, PriceStreamType,
 };
@@ -60,6 +61,8 @@ pub struct KrakenConnection {
     price_stream: Box<dyn Stream<Item = PriceStreamType> + Unpin + Send>,
     /// The underlying write stream of the websocket
     write_stream: Box<dyn Sink<Message, Error = WsError> + Unpin + Send>,
+    /// Tracks the keepalive pings left unanswered by the exchange
+    pong_tracker: PongTracker,
 }
 
 impl KrakenConnection {
@@ -110,14 +113,15 @@ impl ExchangeConnection for KrakenConnection {
     async fn connect(
         base_token: Token,
         quote_token: Token,
-        _config: &PriceReporterConfig,
+        config: &PriceReporterConfig,
     ) -> Result<Self, ExchangeConnectionError>
     where
         Self: Sized,
     {
         // Connect to the websocket
         let url = Self::websocket_url();
-        let (mut write, read) = ws_connect(url).await?;
+        let (mut write, read) =
+            ws_connect(url, config.ws_config(), &config.tls_policy(Exchange::Kraken)).await?;
 
         // Subscribe to the asset pair spread topic
         let base_ticker = base_token.get_exchange_ticker(Exchange::Kraken);
@@ -138,7 +142,8 @@ impl ExchangeConnection for KrakenConnection {
             .map_err(|err| ExchangeConnectionError::ConnectionHangup(err.to_string()))?;
 
         // Map the stream to process midpoint prices
-        let mapped_stream = read.filter_map(|message| async {
+        let pong_tracker = PongTracker::new(config.max_missed_pongs);
+        let mapped_stream = track_pongs(read, pong_tracker.clone()).filter_map(|message| async {
             match message.map(Self::midpoint_from_ws_message) {
                 // The outer `Result` comes from reading the websocket stream
                 // Processing the stream messages returns a `Result<Option<..>>` which we
@@ -148,17 +153,21 @@ impl ExchangeConnection for KrakenConnection {
                 // Error reading from the websocket
                 Err(e) => {
                     error!("Error reading message from Kraken ws: {}", e);
-                    Some(Err(ExchangeConnectionError::ConnectionHangup(e.to_string())))
+                    Some(Err(ws_read_error(e)))
                 },
             }
         });
 
         // Build a price stream
         let price_stream = InitializablePriceStream::new(Box::pin(mapped_stream));
-        Ok(Self { price_stream: Box::new(price_stream), write_stream: Box::new(write) })
+        Ok(Self {
+            price_stream: Box::new(price_stream),
+            write_stream: Box::new(write),
+            pong_tracker,
+        })
     }
 
     async fn send_keepalive(&mut self) -> Result<(), ExchangeConnectionError> {
-        ws_ping(&mut self.write_stream).await
+        ws_keepalive(&mut self.write_stream, Message::Ping(vec![]), &self.pong_tracker).await
     }
 }
@@ -0,0 +1,98 @@
+//! Detects token pairs whose price feeds are entirely down
+//!
+//! Matching on a pair stalls once none of its sources report a fresh price.
+//! The monitor tracks how long a pair has gone without a fresh source and
+//! signals when that outage outlasts a grace period, and again once a source
+//! recovers, so that operators may alert on the outage
+
+/// A change in the health of a pair's price feeds
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeedTransition {
+    /// The pair has had no fresh source for longer than the grace period
+    Down,
+    /// A fresh source has reported on a pair previously marked down
+    Restored,
+}
+
+/// Tracks the health of the price feeds of a single pair
+#[derive(Clone, Debug)]
+pub struct FeedMonitor {
+    /// The time in milliseconds a pair may go without a fresh source before
+    /// it is marked down
+    grace_period_ms: u64,
+    /// The time in milliseconds at which the pair was first observed without a
+    /// fresh source, `None` if a fresh source was last observed
+    stale_since: Option<u64>,
+    /// Whether the pair is currently marked down
+    down: bool,
+}
+
+impl FeedMonitor {
+    /// Construct a monitor with the given grace period
+    pub fn new(grace_period_ms: u64) -> Self {
+        Self { grace_period_ms, stale_since: None, down: false }
+    }
+
+    /// Record the number of fresh sources observed at `now_ms`, returning the
+    /// transition in the pair's health, if any
+    pub fn observe(&mut self, fresh_sources: usize, now_ms: u64) -> Option<FeedTransition> {
+        if fresh_sources > 0 {
+            self.stale_since = None;
+            let was_down = std::mem::replace(&mut self.down, false);
+            return was_down.then_some(FeedTransition::Restored);
+        }
+
+        let stale_since = *self.stale_since.get_or_insert(now_ms);
+        if !self.down && now_ms.saturating_sub(stale_since) >= self.grace_period_ms {
+            self.down = true;
+            return Some(FeedTransition::Down);
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FeedMonitor, FeedTransition};
+
+    /// The grace period used in the tests
+    const GRACE_PERIOD_MS: u64 = 1_000;
+
+    /// Tests that a pair is marked down once it has gone without a fresh
+    /// source for the grace period, and only once
+    #[test]
+    fn test_feed_down() {
+        let mut monitor = FeedMonitor::new(GRACE_PERIOD_MS);
+        assert_eq!(monitor.observe(1, 0), None);
+
+        // The outage begins, and is tolerated within the grace period
+        assert_eq!(monitor.observe(0, 100), None);
+        assert_eq!(monitor.observe(0, 100 + GRACE_PERIOD_MS - 1), None);
+
+        // Once the grace period elapses the pair is marked down
+        assert_eq!(monitor.observe(0, 100 + GRACE_PERIOD_MS), Some(FeedTransition::Down));
+        assert_eq!(monitor.observe(0, 100 + 2 * GRACE_PERIOD_MS), None);
+    }
+
+    /// Tests that a pair marked down is restored once a fresh source reports,
+    /// while a brief outage is never reported
+    #[test]
+    fn test_feed_restored() {
+        let mut monitor = FeedMonitor::new(GRACE_PERIOD_MS);
+
+        // A brief outage recovers without either transition
+        assert_eq!(monitor.observe(0, 0), None);
+        assert_eq!(monitor.observe(2, GRACE_PERIOD_MS / 2), None);
+
+        // A full outage is reported, then restored
+        assert_eq!(monitor.observe(0, GRACE_PERIOD_MS), None);
+        assert_eq!(monitor.observe(0, 2 * GRACE_PERIOD_MS), Some(FeedTransition::Down));
+        assert_eq!(monitor.observe(1, 3 * GRACE_PERIOD_MS), Some(FeedTransition::Restored));
+        assert_eq!(monitor.observe(1, 4 * GRACE_PERIOD_MS), None);
+
+        // A subsequent outage restarts the grace period
+        assert_eq!(monitor.observe(0, 5 * GRACE_PERIOD_MS), None);
+        assert_eq!(monitor.observe(0, 6 * GRACE_PERIOD_MS), Some(FeedTransition::Down));
+    }
+}
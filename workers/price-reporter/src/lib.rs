@@ -12,10 +12,12 @@
 
 pub mod errors;
 pub mod exchange;
+pub mod feed_health;
 pub mod manager;
 #[cfg(feature = "mocks")]
 pub mod mock;
 pub mod reporter;
+pub mod smoothing;
 pub mod worker;
 
 /// The pubsub topic source name for median price reports
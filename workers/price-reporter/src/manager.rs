@@ -8,15 +8,20 @@ use common::types::token::Token;
 use common::types::CancelChannel;
 use common::{new_async_shared, AsyncShared};
 use job_types::price_reporter::{PriceReporterJob, PriceReporterReceiver};
+use std::time::Duration;
 use std::{collections::HashMap, thread::JoinHandle};
 use tokio::runtime::Runtime;
 use tokio::sync::oneshot::Sender as TokioSender;
+use tokio::time::MissedTickBehavior;
 use tracing::{error, info, info_span, warn, Instrument};
 use util::err_str;
 
 use crate::errors::{ExchangeConnectionError, PriceReporterError};
 
-use super::{reporter::Reporter, worker::PriceReporterConfig};
+use super::{
+    reporter::{MedianScheduler, Reporter, MEDIAN_PRICE_REPORT_INTERVAL_MS},
+    worker::PriceReporterConfig,
+};
 
 /// The PriceReporter worker is a wrapper around the
 /// PriceReporterExecutor, handling and dispatching jobs to the executor
@@ -64,6 +69,7 @@ impl PriceReporterExecutor {
     pub(super) async fn execution_loop(mut self) -> Result<(), PriceReporterError> {
         let mut job_receiver = self.job_receiver.take().unwrap();
         let mut cancel_channel = self.cancel_channel.take().unwrap();
+        tokio::spawn(self.clone().median_loop(cancel_channel.clone()));
 
         loop {
             tokio::select! {
@@ -93,6 +99,35 @@ impl PriceReporterExecutor {
         }
     }
 
+    /// Schedules the median price of every active pair once per interval,
+    /// across a pool bounded by the configured concurrency
+    ///
+    /// Each pair is scheduled independently of the others, so a tick never
+    /// waits on the medians scheduled by the last
+    async fn median_loop(self, mut cancel_channel: CancelChannel) {
+        let mut interval =
+            tokio::time::interval(Duration::from_millis(MEDIAN_PRICE_REPORT_INTERVAL_MS));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let scheduler =
+            MedianScheduler::new(self.config.system_bus.clone(), self.config.median_concurrency);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    let reporters =
+                        self.active_price_reporters.read().await.values().cloned().collect();
+                    scheduler.schedule(reporters);
+                },
+
+                // Await cancellation by the coordinator
+                _ = cancel_channel.changed() => {
+                    info!("median loop cancelled, shutting down...");
+                    return;
+                }
+            }
+        }
+    }
+
     /// Handles a job for the PriceReporter worker.
     pub(super) async fn handle_job(
         &mut self,
@@ -100,6 +100,7 @@ impl MockPriceReporter {
             midpoint_price: self.price,
             local_timestamp: timestamp,
             reported_timestamp: Some(timestamp as u128),
+            smoothed: false,
         });
 
         if let Err(e) = channel.send(state) {
@@ -125,6 +126,7 @@ impl MockPriceReporter {
             midpoint_price: self.price,
             local_timestamp: timestamp,
             reported_timestamp: Some(timestamp as u128),
+            smoothed: false,
         };
 
         let mut state = HashMap::new();
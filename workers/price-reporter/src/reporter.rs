@@ -7,21 +7,30 @@ use common::types::exchange::{
 };
 use common::types::token::Token;
 use common::types::Price;
-use external_api::bus_message::{price_report_topic_name, SystemBusMessage};
+use external_api::bus_message::{
+    price_report_topic_name, SystemBusMessage, PRICE_FEED_STATUS_TOPIC,
+};
 use futures_util::future::try_join_all;
 use itertools::Itertools;
 use statrs::statistics::{Data, Median};
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
 use std::time::Duration;
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 use system_bus::SystemBus;
-use tokio::time::Instant;
+use tokio::sync::Semaphore;
+use tokio::time::{interval_at, Instant, Interval, MissedTickBehavior};
 use tokio_stream::{StreamExt, StreamMap};
-use tracing::{error, info, warn};
-use util::get_current_time_seconds;
+use tracing::{debug, error, info, warn};
+use util::{get_current_time_millis, get_current_time_seconds};
 
 use crate::exchange::connect_exchange;
 use crate::exchange::connection::ExchangeConnection;
+use crate::feed_health::{FeedMonitor, FeedTransition};
+use crate::smoothing::EmaSmoother;
 
 use super::MEDIAN_SOURCE_NAME;
 use super::{errors::ExchangeConnectionError, worker::PriceReporterConfig};
@@ -43,9 +52,6 @@ const MIN_CONNECTIONS: usize = 1;
 /// the midpoint, then we pause matches until the prices stabilize.
 const MAX_DEVIATION: f64 = 0.02;
 
-/// The number of milliseconds to wait in between sending keepalive messages to
-/// the connections
-const KEEPALIVE_INTERVAL_MS: u64 = 15_000; // 15 seconds
 /// The number of milliseconds to wait in between retrying connections
 const CONN_RETRY_DELAY_MS: u64 = 2_000; // 2 seconds
 /// The number of milliseconds in which `MAX_CONN_RETRIES` failures will cause a
@@ -56,7 +62,13 @@ const MAX_CONN_RETRIES: usize = 5;
 
 /// The number of milliseconds to wait in between sending median price report
 /// updates
-const MEDIAN_PRICE_REPORT_INTERVAL_MS: u64 = 1_000; // 1 second
+pub(crate) const MEDIAN_PRICE_REPORT_INTERVAL_MS: u64 = 1_000; // 1 second
+/// The number of milliseconds a pair's median computation may occupy one of
+/// the concurrency slots before it stops counting against the limit
+const MEDIAN_SLOT_DEADLINE_MS: u64 = 100;
+/// The tolerance, relative to the total weight, within which a cumulative
+/// weight is considered to land exactly on the weighted median's midpoint
+const MEDIAN_WEIGHT_TOLERANCE: f64 = 1e-9;
 
 /// The price reporter handles opening connections to exchanges, and computing
 /// price reports and medians from the exchange data
@@ -69,6 +81,19 @@ pub struct Reporter {
     /// The shared memory map from exchange to most recent price
     /// and reporting timestamp
     exchange_info: AtomicPriceStreamState,
+    /// The weight of each supported exchange in the median price
+    exchange_weights: HashMap<Exchange, f64>,
+    /// The price configured by the operator for the pair, reported as the
+    /// `Override` pseudo-exchange
+    price_override: Option<Price>,
+    /// The absolute `(min, max)` bounds on the pair's price, reports outside
+    /// of which are dropped before aggregation
+    price_bounds: Option<(Price, Price)>,
+    /// The moving average applied to the median price, if smoothing is
+    /// enabled
+    smoother: Option<Arc<Mutex<EmaSmoother>>>,
+    /// The monitor tracking the health of the pair's feeds
+    feed_monitor: Arc<Mutex<FeedMonitor>>,
 }
 
 /// The state streamed from the connection multiplexer to the price reporter
@@ -131,11 +156,18 @@ impl Reporter {
         // Get the supported exchanges for the token pair
         let supported_exchanges =
             Self::compute_supported_exchanges_for_pair(&base_token, &quote_token, &config);
-        if supported_exchanges.is_empty() {
+        let price_override = config.price_override(&base_token, &quote_token);
+        if supported_exchanges.is_empty() && price_override.is_none() {
             warn!("No supported exchanges for {base_token}-{quote_token}");
             return Err(ExchangeConnectionError::NoSupportedExchanges(base_token, quote_token));
         }
 
+        let exchange_weights = supported_exchanges
+            .iter()
+            .chain(price_override.map(|_| &Exchange::Override))
+            .map(|exchange| (*exchange, config.exchange_weight(*exchange)))
+            .collect();
+
         // Create shared memory that the `ConnectionMuxer` will use to communicate with
         // the `Reporter`
         let shared_exchange_state =
@@ -150,23 +182,30 @@ impl Reporter {
             shared_exchange_state.clone(),
         );
 
-        tokio::spawn({
-            let base_token = base_token.clone();
-            let quote_token = quote_token.clone();
-            async move {
-                if let Err(e) = connection_muxer.execution_loop().await {
-                    error!("Error in ConnectionMuxer for {base_token}-{quote_token}: {e}");
+        if connection_muxer.exchanges.is_empty() {
+            info!("Reporting overridden price for {base_token}-{quote_token}");
+        } else {
+            tokio::spawn({
+                let base_token = base_token.clone();
+                let quote_token = quote_token.clone();
+                async move {
+                    if let Err(e) = connection_muxer.execution_loop().await {
+                        error!("Error in ConnectionMuxer for {base_token}-{quote_token}: {e}");
+                    }
                 }
-            }
-        });
-
-        // Spawn a thread to stream median price reports
-        let self_ = Self { base_token, quote_token, exchange_info: shared_exchange_state };
-
-        let self_clone = self_.clone();
-        tokio::spawn(async move { self_clone.median_streamer_loop(config.system_bus).await });
+            });
+        }
 
-        Ok(self_)
+        Ok(Self {
+            price_bounds: config.price_bounds(&base_token, &quote_token),
+            base_token,
+            quote_token,
+            exchange_info: shared_exchange_state,
+            exchange_weights,
+            price_override,
+            smoother: config.ema_half_life_ms.map(|h| Arc::new(Mutex::new(EmaSmoother::new(h)))),
+            feed_monitor: Arc::new(Mutex::new(FeedMonitor::new(config.feed_grace_period_ms))),
+        })
     }
 
     /// Non-blocking report of the latest ReporterState for the median
@@ -192,28 +231,77 @@ impl Reporter {
 
             exchange_connection_states.insert(*exchange, state);
         }
+
+        if let Some(report) = self.override_price_report() {
+            exchange_connection_states
+                .insert(Exchange::Override, ExchangeConnectionState::Nominal(report));
+        }
+
         exchange_connection_states
     }
 
+    /// Publish the pair's median price report to the system bus, along with
+    /// any change in the health of the pair's feeds
+    pub(crate) fn publish_median(&self, system_bus: &SystemBus<SystemBusMessage>) {
+        let now = get_current_time_millis() as u64;
+        let transition = self
+            .feed_monitor
+            .lock()
+            .expect("feed monitor poisoned")
+            .observe(self.fresh_source_count(), now);
+        if let Some(transition) = transition {
+            self.publish_feed_transition(system_bus, transition);
+        }
+
+        let topic_name =
+            price_report_topic_name(MEDIAN_SOURCE_NAME, &self.base_token, &self.quote_token);
+        if system_bus.has_listeners(&topic_name) {
+            if let PriceReporterState::Nominal(report) = self.get_state() {
+                system_bus.publish(topic_name, SystemBusMessage::PriceReportMedian(report));
+            }
+        }
+    }
+
     // -----------
     // | Helpers |
     // -----------
 
-    /// An execution loop that streams median price reports to the system bus
-    async fn median_streamer_loop(&self, system_bus: SystemBus<SystemBusMessage>) {
-        let topic_name =
-            price_report_topic_name(MEDIAN_SOURCE_NAME, &self.base_token, &self.quote_token);
+    /// The number of sources that have reported a price for the pair within
+    /// `MAX_REPORT_AGE_MS`, an overridden price is always fresh
+    fn fresh_source_count(&self) -> usize {
+        let now = get_current_time_seconds();
+        let fresh_exchanges = ALL_EXCHANGES
+            .iter()
+            .filter_map(|exchange| self.exchange_info.read_price(exchange))
+            .filter(|(price, ts)| {
+                *price != Price::default()
+                    && price.is_finite()
+                    && now.saturating_sub(*ts) * 1000 <= MAX_REPORT_AGE_MS
+            })
+            .count();
 
-        loop {
-            if system_bus.has_listeners(&topic_name) {
-                if let PriceReporterState::Nominal(report) = self.get_state() {
-                    system_bus
-                        .publish(topic_name.clone(), SystemBusMessage::PriceReportMedian(report));
-                }
-            }
+        fresh_exchanges + usize::from(self.price_override.is_some())
+    }
 
-            tokio::time::sleep(Duration::from_millis(MEDIAN_PRICE_REPORT_INTERVAL_MS)).await;
-        }
+    /// Publish a change in the health of the pair's feeds to the system bus
+    fn publish_feed_transition(
+        &self,
+        system_bus: &SystemBus<SystemBusMessage>,
+        transition: FeedTransition,
+    ) {
+        let (base, quote) = (self.base_token.clone(), self.quote_token.clone());
+        let message = match transition {
+            FeedTransition::Down => {
+                warn!("all price feeds down for {base}-{quote}");
+                SystemBusMessage::PriceFeedDown { base, quote }
+            },
+            FeedTransition::Restored => {
+                info!("price feeds restored for {base}-{quote}");
+                SystemBusMessage::PriceFeedRestored { base, quote }
+            },
+        };
+
+        system_bus.publish(PRICE_FEED_STATUS_TOPIC.to_string(), message);
     }
 
     /// Returns if this PriceReport is of a "Named" token pair (as opposed to an
@@ -251,6 +339,60 @@ impl Reporter {
             midpoint_price: price,
             local_timestamp,
             reported_timestamp: None,
+            smoothed: false,
+        }
+    }
+
+    /// Whether the price falls within the pair's configured bounds
+    ///
+    /// The check runs on every read of the median, so a dropped price is only
+    /// logged at debug level to avoid flooding the logs while a feed is out of
+    /// bounds
+    fn within_bounds(&self, price: Price) -> bool {
+        let Some((min, max)) = self.price_bounds else {
+            return true;
+        };
+
+        let in_bounds = min <= price && price <= max;
+        if !in_bounds {
+            debug!(
+                "dropping {}-{} price {price} outside of bounds [{min}, {max}]",
+                self.base_token, self.quote_token
+            );
+        }
+
+        in_bounds
+    }
+
+    /// Construct a price report from the price override, if one is configured
+    ///
+    /// The override is always fresh, so it is reported at the current time
+    fn override_price_report(&self) -> Option<PriceReport> {
+        self.price_override.map(|price| PriceReport {
+            exchange: Some(Exchange::Override),
+            ..self.price_report_from_price(price, get_current_time_seconds())
+        })
+    }
+
+    /// Compute the current ReporterState, smoothing the median price if a
+    /// moving average is configured
+    ///
+    /// Smoothed reports are flagged so that consumers may distinguish them
+    /// from the raw median
+    fn get_state(&self) -> PriceReporterState {
+        match (self.compute_state(), &self.smoother) {
+            (PriceReporterState::Nominal(report), Some(smoother)) => {
+                let now = get_current_time_millis() as u64;
+                let price =
+                    smoother.lock().expect("smoother poisoned").update(report.midpoint_price, now);
+
+                PriceReporterState::Nominal(PriceReport {
+                    midpoint_price: price,
+                    smoothed: true,
+                    ..report
+                })
+            },
+            (state, _) => state,
         }
     }
 
@@ -258,12 +400,17 @@ impl Reporter {
     /// ReporterState. We check for various issues (delayed prices, no
     /// data yet received, etc.), and if no issues are found, compute the
     /// median PriceReport
-    fn get_state(&self) -> PriceReporterState {
+    fn compute_state(&self) -> PriceReporterState {
         // If the Token pair is Unnamed, then we simply report the UniswapV3 price if
-        // one exists.
+        // one exists. An overridden price takes precedence, as the operator configured
+        // it for a pair without a reliable feed
         if !self.is_named() {
+            if let Some(report) = self.override_price_report() {
+                return PriceReporterState::Nominal(report);
+            }
+
             let (uni_price, uni_ts) = self.exchange_info.read_price(&Exchange::UniswapV3).unwrap();
-            if uni_price == Price::default() {
+            if uni_price == Price::default() || !self.within_bounds(uni_price) {
                 return PriceReporterState::NotEnoughDataReported(0);
             } else {
                 return PriceReporterState::Nominal(
@@ -273,11 +420,21 @@ impl Reporter {
         }
 
         // Collect all non-zero PriceReports and ensure that we have enough.
-        let (non_zero_prices, timestamps): (Vec<Price>, Vec<u64>) = ALL_EXCHANGES
+        let override_price = self.override_price_report().map(|report| {
+            let weight = self.exchange_weights[&Exchange::Override];
+            ((report.midpoint_price, weight), report.local_timestamp)
+        });
+        let (weighted_prices, timestamps): (Vec<(Price, f64)>, Vec<u64>) = ALL_EXCHANGES
             .iter()
-            .filter_map(|exchange| self.exchange_info.read_price(exchange))
-            .filter(|(price, _)| *price != Price::default() && price.is_finite())
+            .filter_map(|exchange| {
+                let (price, ts) = self.exchange_info.read_price(exchange)?;
+                Some(((price, self.exchange_weights[exchange]), ts))
+            })
+            .chain(override_price)
+            .filter(|((price, _), _)| *price != Price::default() && price.is_finite())
+            .filter(|((price, _), _)| self.within_bounds(*price))
             .unzip();
+        let non_zero_prices = weighted_prices.iter().map(|(price, _)| *price).collect_vec();
 
         // Ensure that we have enough data to create a median
         if non_zero_prices.len() < MIN_CONNECTIONS {
@@ -285,7 +442,7 @@ impl Reporter {
         }
 
         // Compute the median price report
-        let median_midpoint_price = Data::new(non_zero_prices.clone()).median();
+        let median_midpoint_price = weighted_median(weighted_prices);
         let median_ts =
             Data::new(timestamps.iter().map(|ts| *ts as f64).collect_vec()).median() as u64;
         let median_price_report = PriceReport {
@@ -295,6 +452,7 @@ impl Reporter {
             midpoint_price: median_midpoint_price,
             local_timestamp: median_ts,
             reported_timestamp: None,
+            smoothed: false,
         };
 
         // Check that the most recent timestamp is not too old
@@ -317,10 +475,119 @@ impl Reporter {
     }
 }
 
+/// A function publishing a single pair's median price report
+type PublishMedianFn = Arc<dyn Fn(&Reporter) + Send + Sync>;
+
+/// Schedules the publication of each pair's median price report
+///
+/// Pairs are scheduled independently: a pair whose previous median is still
+/// in flight is skipped, and a median computation holds one of the
+/// `concurrency` slots for at most `slot_deadline`. Pairs slower than the
+/// reporting interval therefore cannot delay the others, however many there
+/// are
+#[derive(Clone)]
+pub(crate) struct MedianScheduler {
+    /// The function publishing a single pair's median
+    publish: PublishMedianFn,
+    /// The slots bounding the number of medians computed at once
+    slots: Arc<Semaphore>,
+    /// The time a median computation may hold a slot before it stops counting
+    /// against the concurrency limit
+    slot_deadline: Duration,
+    /// The pairs whose medians are queued or being computed
+    in_flight: Arc<Mutex<HashSet<(Token, Token)>>>,
+}
+
+impl MedianScheduler {
+    /// Create a scheduler publishing medians to the given system bus
+    pub(crate) fn new(system_bus: SystemBus<SystemBusMessage>, concurrency: usize) -> Self {
+        let publish = Arc::new(move |reporter: &Reporter| reporter.publish_median(&system_bus));
+        Self::new_with(publish, concurrency, Duration::from_millis(MEDIAN_SLOT_DEADLINE_MS))
+    }
+
+    /// Create a scheduler that runs `publish` for each scheduled pair
+    fn new_with(publish: PublishMedianFn, concurrency: usize, slot_deadline: Duration) -> Self {
+        Self {
+            publish,
+            slots: Arc::new(Semaphore::new(concurrency)),
+            slot_deadline,
+            in_flight: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+
+    /// Schedule the median of each of the given pairs that is not already in
+    /// flight, without waiting for them to be published
+    pub(crate) fn schedule(&self, reporters: Vec<Reporter>) {
+        for reporter in reporters {
+            let pair = (reporter.base_token.clone(), reporter.quote_token.clone());
+            if !self.in_flight.lock().expect("in flight pairs poisoned").insert(pair.clone()) {
+                continue;
+            }
+
+            let self_clone = self.clone();
+            tokio::spawn(async move { self_clone.publish_pair(pair, reporter).await });
+        }
+    }
+
+    /// Publish a single pair's median once a slot is available
+    async fn publish_pair(self, pair: (Token, Token), reporter: Reporter) {
+        let slot = self.slots.clone().acquire_owned().await.expect("median slots closed");
+        let publish = self.publish.clone();
+        let mut handle = tokio::task::spawn_blocking(move || publish(&reporter));
+
+        // Free the slot once the deadline passes, the pair remains in flight
+        // until its median is published
+        let res = match tokio::time::timeout(self.slot_deadline, &mut handle).await {
+            Ok(res) => res,
+            Err(_) => {
+                drop(slot);
+                warn!("median for {}-{} exceeded its deadline", pair.0, pair.1);
+                handle.await
+            },
+        };
+
+        if let Err(e) = res {
+            error!("error publishing median for {}-{}: {e}", pair.0, pair.1);
+        }
+        self.in_flight.lock().expect("in flight pairs poisoned").remove(&pair);
+    }
+}
+
+/// Compute the weighted median of a set of `(price, weight)` pairs
+///
+/// The weighted median is the price at which the cumulative weight of the
+/// sorted prices first exceeds half the total weight. If the cumulative weight
+/// lands on half, the two prices straddling the midpoint are averaged, so that
+/// equal weights reproduce the unweighted median
+///
+/// Landing on half is checked within `MEDIAN_WEIGHT_TOLERANCE` of the total
+/// weight, so that rounding in the summed weights does not pick one side
+fn weighted_median(mut prices: Vec<(Price, f64)>) -> Price {
+    prices.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    let total_weight = prices.iter().map(|(_, weight)| weight).sum::<f64>();
+    let half_weight = total_weight / 2.;
+    let tolerance = total_weight * MEDIAN_WEIGHT_TOLERANCE;
+
+    let mut cumulative_weight = 0.;
+    for (i, (price, weight)) in prices.iter().enumerate() {
+        cumulative_weight += weight;
+        if (cumulative_weight - half_weight).abs() <= tolerance && i + 1 < prices.len() {
+            return (price + prices[i + 1].0) / 2.;
+        } else if cumulative_weight > half_weight {
+            return *price;
+        }
+    }
+
+    prices.last().map(|(price, _)| *price).unwrap_or_default()
+}
+
 // -------------------
 // | ConnectionMuxer |
 // -------------------
 
+/// The set of live exchange connections, multiplexed into a single stream
+type ConnectionMap = StreamMap<Exchange, Box<dyn ExchangeConnection>>;
+
 /// The connection muxer manages a set of websocket connections abstracted as
 /// `ExchangeConnection`s. It is responsible for restarting connections that
 /// fail, and communicating the latest price reports to the `Reporter` via
@@ -365,8 +632,7 @@ impl ConnectionMuxer {
     /// Start the connection muxer
     pub async fn execution_loop(mut self) -> Result<(), ExchangeConnectionError> {
         // Start a keepalive timer
-        let delay = tokio::time::sleep(Duration::from_millis(KEEPALIVE_INTERVAL_MS));
-        tokio::pin!(delay);
+        let mut keepalive = keepalive_timer(self.config.keepalive_interval());
 
         // Build a map of connections to multiplex from
         let mut stream_map = self.initialize_connections().await?;
@@ -374,14 +640,10 @@ impl ConnectionMuxer {
         loop {
             tokio::select! {
                 // Keepalive timer
-                _ = &mut delay => {
-                    for exchange in stream_map.values_mut() {
-                        if let Err(e) = exchange.send_keepalive().await {
-                            error!("Error sending keepalive to exchange: {e}");
-                        }
+                _ = keepalive.tick() => {
+                    for exchange in send_keepalives(&mut stream_map).await {
+                        self.reconnect(exchange, &mut stream_map).await;
                     }
-
-                    delay.as_mut().reset(Instant::now() + Duration::from_millis(KEEPALIVE_INTERVAL_MS));
                 },
 
                 // New price streamed from an exchange
@@ -407,7 +669,8 @@ impl ConnectionMuxer {
                                         exchange: Some(exchange),
                                         midpoint_price: price,
                                         local_timestamp: ts,
-                                        reported_timestamp: None
+                                        reported_timestamp: None,
+                                        smoothed: false,
                                     }),
                                 );
                             },
@@ -415,22 +678,7 @@ impl ConnectionMuxer {
                             Err(e) => {
                                 // Restart the connection
                                 error!("Error streaming from {exchange}: {e}, restarting connection...");
-                                loop {
-                                    match self.retry_connection(exchange).await {
-                                        Ok(conn) => {
-                                            info!("Successfully reconnected to {exchange}");
-                                            stream_map.insert(exchange, conn);
-                                        }
-                                        Err(ExchangeConnectionError::MaxRetries(_)) => {
-                                            error!("Max retries ({MAX_CONN_RETRIES}) exceeded, unable to connect to {exchange}... removing from data sources");
-                                            stream_map.remove(&exchange);
-                                            break;
-                                        }
-                                        _ => {
-                                            warn!("Connection retry attempt failed");
-                                        },
-                                    }
-                                }
+                                self.reconnect(exchange, &mut stream_map).await;
                             }
 
                         }
@@ -444,7 +692,7 @@ impl ConnectionMuxer {
     /// `StreamMap` for multiplexing
     async fn initialize_connections<'a>(
         &mut self,
-    ) -> Result<StreamMap<Exchange, Box<dyn ExchangeConnection>>, ExchangeConnectionError> {
+    ) -> Result<ConnectionMap, ExchangeConnectionError> {
         // We do not use a more convenient stream here for concurrent init because of:
         //   https://github.com/rust-lang/rust/issues/102211
         // In specific, streams in async blocks sometimes have lifetimes erased which
@@ -466,6 +714,30 @@ impl ConnectionMuxer {
         Ok(self.exchanges.clone().into_iter().zip(conns.into_iter()).collect::<StreamMap<_, _>>())
     }
 
+    /// Re-establishes a failed exchange connection, removing the exchange from
+    /// the data sources if the maximum number of retries is exceeded
+    async fn reconnect(&mut self, exchange: Exchange, stream_map: &mut ConnectionMap) {
+        loop {
+            match self.retry_connection(exchange).await {
+                Ok(conn) => {
+                    info!("Successfully reconnected to {exchange}");
+                    stream_map.insert(exchange, conn);
+                    break;
+                },
+                Err(ExchangeConnectionError::MaxRetries(_)) => {
+                    error!(
+                        "Max retries ({MAX_CONN_RETRIES}) exceeded, unable to connect to {exchange}... removing from data sources"
+                    );
+                    stream_map.remove(&exchange);
+                    break;
+                },
+                _ => {
+                    warn!("Connection retry attempt failed");
+                },
+            }
+        }
+    }
+
     /// Retries an exchange connection after it has failed
     async fn retry_connection(
         &mut self,
@@ -492,3 +764,396 @@ impl ConnectionMuxer {
         connect_exchange(&self.base_token, &self.quote_token, &self.config, exchange).await
     }
 }
+
+/// Build the timer on which keepalive pings are sent, the first tick fires one
+/// interval from now
+fn keepalive_timer(interval: Duration) -> Interval {
+    let mut timer = interval_at(Instant::now() + interval, interval);
+    timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    timer
+}
+
+/// Send a keepalive ping on each connection
+///
+/// Returns the exchanges whose connections left too many pings unanswered and
+/// must be re-established
+async fn send_keepalives(stream_map: &mut ConnectionMap) -> Vec<Exchange> {
+    let mut dead_connections = Vec::new();
+    for (exchange, conn) in stream_map.iter_mut() {
+        match conn.send_keepalive().await {
+            Ok(()) => {},
+            Err(e @ ExchangeConnectionError::MissedPongs(_)) => {
+                warn!("Keepalive to {exchange} failed: {e}, restarting connection...");
+                dead_connections.push(*exchange);
+            },
+            Err(e) => error!("Error sending keepalive to {exchange}: {e}"),
+        }
+    }
+
+    dead_connections
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::HashMap,
+        pin::Pin,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+        task::{Context, Poll},
+        time::Duration,
+    };
+
+    use async_trait::async_trait;
+    use common::types::{
+        exchange::{Exchange, ExchangeConnectionState, PriceReporterState},
+        token::Token,
+        Price,
+    };
+    use external_api::bus_message::{price_report_topic_name, SystemBusMessage};
+    use futures_util::Stream;
+    use itertools::Itertools;
+    use statrs::statistics::{Data, Median};
+    use system_bus::SystemBus;
+    use tokio::time::Instant;
+    use util::get_current_time_seconds;
+
+    use crate::{
+        errors::ExchangeConnectionError,
+        exchange::connection::{ExchangeConnection, PongTracker},
+        feed_health::FeedMonitor,
+        smoothing::EmaSmoother,
+        worker::PriceReporterConfig,
+        MEDIAN_SOURCE_NAME,
+    };
+
+    use super::{
+        keepalive_timer, send_keepalives, weighted_median, AtomicPriceStreamState, ConnectionMap,
+        MedianScheduler, Reporter,
+    };
+
+    /// The prices reported by a set of mock exchanges
+    const PRICES: [f64; 4] = [100., 101., 102., 110.];
+    /// The keepalive interval used in tests
+    const KEEPALIVE_INTERVAL: Duration = Duration::from_millis(50);
+    /// The feed grace period used in tests
+    const FEED_GRACE_PERIOD_MS: u64 = 1_000;
+    /// The number of pongs a mock connection may miss
+    const MAX_MISSED_PONGS: usize = 2;
+    /// The error returned when a mock connection is dialed
+    const ERR_MOCK_CONNECT: &str = "mock connections cannot be dialed";
+
+    /// A mock exchange connection that streams no prices and counts the
+    /// keepalive pings sent on it
+    struct MockKeepaliveConnection {
+        /// The number of pings sent on the connection
+        pings: Arc<AtomicUsize>,
+        /// Tracks the pings left unanswered
+        pong_tracker: PongTracker,
+        /// Whether the mock exchange answers pings
+        answer_pings: bool,
+    }
+
+    impl Stream for MockKeepaliveConnection {
+        type Item = Result<Price, ExchangeConnectionError>;
+
+        fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            Poll::Pending
+        }
+    }
+
+    #[async_trait]
+    impl ExchangeConnection for MockKeepaliveConnection {
+        async fn connect(
+            _base_token: Token,
+            _quote_token: Token,
+            _config: &PriceReporterConfig,
+        ) -> Result<Self, ExchangeConnectionError> {
+            // Mock connections are constructed directly, never dialed
+            Err(ExchangeConnectionError::HandshakeFailure(ERR_MOCK_CONNECT.to_string()))
+        }
+
+        async fn send_keepalive(&mut self) -> Result<(), ExchangeConnectionError> {
+            self.pings.fetch_add(1, Ordering::Relaxed);
+            self.pong_tracker.record_ping()?;
+            if self.answer_pings {
+                self.pong_tracker.record_pong();
+            }
+
+            Ok(())
+        }
+    }
+
+    /// Build a connection map holding a single mock connection, returning the
+    /// map and the connection's ping counter
+    fn mock_connection_map(answer_pings: bool) -> (ConnectionMap, Arc<AtomicUsize>) {
+        let pings = Arc::new(AtomicUsize::new(0));
+        let conn = MockKeepaliveConnection {
+            pings: pings.clone(),
+            pong_tracker: PongTracker::new(MAX_MISSED_PONGS),
+            answer_pings,
+        };
+
+        let mut stream_map = ConnectionMap::new();
+        stream_map.insert(Exchange::Binance, Box::new(conn) as Box<dyn ExchangeConnection>);
+        (stream_map, pings)
+    }
+
+    /// Build a feed monitor for a mock reporter
+    fn mock_feed_monitor() -> Arc<Mutex<FeedMonitor>> {
+        Arc::new(Mutex::new(FeedMonitor::new(FEED_GRACE_PERIOD_MS)))
+    }
+
+    /// Tests that equal weights reproduce the unweighted median
+    #[test]
+    fn test_equal_weights() {
+        for n in 1..=PRICES.len() {
+            let prices = PRICES[..n].to_vec();
+            let expected = Data::new(prices.clone()).median();
+
+            for weight in [1., 2.5] {
+                let weighted = prices.iter().map(|price| (*price, weight)).collect_vec();
+                assert_eq!(weighted_median(weighted), expected);
+            }
+        }
+    }
+
+    /// Tests that a heavily weighted exchange dominates the median
+    #[test]
+    fn test_weighted_vs_unweighted() {
+        let unweighted = Data::new(PRICES.to_vec()).median();
+        assert_eq!(unweighted, 101.5);
+
+        // The exchange reporting the highest price outweighs the others
+        let weights = [1., 1., 1., 4.];
+        let weighted = PRICES.into_iter().zip(weights).collect_vec();
+        assert_eq!(weighted_median(weighted), 110.);
+
+        // Down-weighting the outlier pulls the median towards the lower prices
+        let weights = [1., 1., 1., 0.1];
+        let weighted = PRICES.into_iter().zip(weights).collect_vec();
+        assert_eq!(weighted_median(weighted), 101.);
+    }
+
+    /// Tests that a cumulative weight landing on the midpoint up to rounding
+    /// averages the straddling prices
+    #[test]
+    fn test_midpoint_rounding() {
+        // The first three weights sum to 0.7000000000000001, while half the total
+        // weight is 0.7
+        let weights = [0.1, 0.2, 0.4, 0.7];
+        let weighted = PRICES.into_iter().zip(weights).collect_vec();
+        assert_eq!(weighted_median(weighted), 106.);
+    }
+
+    /// Tests that an overridden price flows through the median alongside
+    /// exchange prices
+    #[test]
+    fn test_override_in_median() {
+        let exchange_info = AtomicPriceStreamState::new_from_exchanges(&[Exchange::Binance]);
+        let reporter = Reporter {
+            base_token: Token::from_ticker("WETH"),
+            quote_token: Token::from_ticker("USDC"),
+            exchange_info: exchange_info.clone(),
+            exchange_weights: HashMap::from([(Exchange::Binance, 1.), (Exchange::Override, 1.)]),
+            price_override: Some(101.),
+            price_bounds: None,
+            smoother: None,
+            feed_monitor: mock_feed_monitor(),
+        };
+
+        // With no exchange data, the override alone sets the price
+        let PriceReporterState::Nominal(report) = reporter.peek_median() else {
+            panic!("expected a nominal price report");
+        };
+        assert_eq!(report.midpoint_price, 101.);
+        assert!(!report.smoothed);
+
+        // The override is aggregated with the exchange prices
+        exchange_info.new_price(Exchange::Binance, 100., get_current_time_seconds());
+        let PriceReporterState::Nominal(report) = reporter.peek_median() else {
+            panic!("expected a nominal price report");
+        };
+        assert_eq!(report.midpoint_price, 100.5);
+
+        // The override is reported as its own source
+        let states = reporter.peek_all_exchanges();
+        let ExchangeConnectionState::Nominal(report) = &states[&Exchange::Override] else {
+            panic!("expected a nominal override report");
+        };
+        assert_eq!(report.exchange, Some(Exchange::Override));
+        assert_eq!(report.midpoint_price, 101.);
+    }
+
+    /// Tests that reports outside the configured price bounds are dropped
+    /// before aggregation, while reports within them are aggregated
+    #[test]
+    fn test_price_bounds() {
+        let exchanges = [Exchange::Binance, Exchange::Coinbase];
+        let exchange_info = AtomicPriceStreamState::new_from_exchanges(&exchanges);
+        let reporter = Reporter {
+            base_token: Token::from_ticker("WETH"),
+            quote_token: Token::from_ticker("USDC"),
+            exchange_info: exchange_info.clone(),
+            exchange_weights: exchanges.into_iter().map(|exchange| (exchange, 1.)).collect(),
+            price_override: None,
+            price_bounds: Some((90., 110.)),
+            smoother: None,
+            feed_monitor: mock_feed_monitor(),
+        };
+
+        // Reports within the bounds are aggregated
+        let now = get_current_time_seconds();
+        exchange_info.new_price(Exchange::Binance, 100., now);
+        exchange_info.new_price(Exchange::Coinbase, 102., now);
+        let PriceReporterState::Nominal(report) = reporter.peek_median() else {
+            panic!("expected a nominal price report");
+        };
+        assert_eq!(report.midpoint_price, 101.);
+
+        // A report outside the bounds is dropped
+        exchange_info.new_price(Exchange::Coinbase, 1_000., now);
+        let PriceReporterState::Nominal(report) = reporter.peek_median() else {
+            panic!("expected a nominal price report");
+        };
+        assert_eq!(report.midpoint_price, 100.);
+
+        // With every report out of bounds, no price is reported
+        exchange_info.new_price(Exchange::Binance, 1., now);
+        assert!(matches!(reporter.peek_median(), PriceReporterState::NotEnoughDataReported(0)));
+    }
+
+    /// Tests that the medians of fast pairs are published every reporting
+    /// interval while more pairs than the concurrency limit are slower than
+    /// the interval
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_slow_pairs_do_not_delay_medians() {
+        const N_PAIRS: usize = 100;
+        const CONCURRENCY: usize = 4;
+        const N_SLOW: usize = 3 * CONCURRENCY;
+        const N_TICKS: usize = 5;
+        let interval = Duration::from_millis(200);
+        let slot_deadline = Duration::from_millis(10);
+
+        let system_bus = SystemBus::new();
+        let quote_token = Token::from_ticker("USDC");
+        let reporters = (0..N_PAIRS)
+            .map(|i| Reporter {
+                base_token: Token::from_addr(&format!("{i:#042x}")),
+                quote_token: quote_token.clone(),
+                exchange_info: AtomicPriceStreamState::new_from_exchanges(&[]),
+                exchange_weights: HashMap::from([(Exchange::Override, 1.)]),
+                price_override: Some(i as Price + 1.),
+                price_bounds: None,
+                smoother: None,
+                feed_monitor: mock_feed_monitor(),
+            })
+            .collect_vec();
+
+        let mut readers = reporters
+            .iter()
+            .map(|reporter| {
+                let topic = price_report_topic_name(
+                    MEDIAN_SOURCE_NAME,
+                    &reporter.base_token,
+                    &reporter.quote_token,
+                );
+                system_bus.subscribe(topic)
+            })
+            .collect_vec();
+
+        // The first pairs, scheduled ahead of the others, take several reporting
+        // intervals to compute
+        let slow_tokens = reporters[..N_SLOW].iter().map(|r| r.base_token.clone()).collect_vec();
+        let bus = system_bus.clone();
+        let publish = Arc::new(move |reporter: &Reporter| {
+            if slow_tokens.contains(&reporter.base_token) {
+                std::thread::sleep(3 * interval);
+            }
+            reporter.publish_median(&bus)
+        });
+        let scheduler = MedianScheduler::new_with(publish, CONCURRENCY, slot_deadline);
+
+        // Every other pair's median is published within each interval
+        for _ in 0..N_TICKS {
+            let tick = Instant::now();
+            scheduler.schedule(reporters.clone());
+
+            for (i, reader) in readers.iter_mut().enumerate().skip(N_SLOW) {
+                let msg = tokio::time::timeout_at(tick + interval, reader.next_message())
+                    .await
+                    .expect("median not published within the reporting interval");
+                let SystemBusMessage::PriceReportMedian(report) = msg else {
+                    panic!("expected a median price report");
+                };
+                assert_eq!(report.midpoint_price, i as Price + 1.);
+            }
+
+            tokio::time::sleep_until(tick + interval).await;
+        }
+
+        // The slow pairs' medians are published once computed
+        for (i, reader) in readers.iter_mut().enumerate().take(N_SLOW) {
+            let SystemBusMessage::PriceReportMedian(report) = reader.next_message().await else {
+                panic!("expected a median price report");
+            };
+            assert_eq!(report.midpoint_price, i as Price + 1.);
+        }
+    }
+
+    /// Tests that a smoothed median is flagged as such
+    #[test]
+    fn test_smoothed_median_flagged() {
+        let reporter = Reporter {
+            base_token: Token::from_ticker("WETH"),
+            quote_token: Token::from_ticker("USDC"),
+            exchange_info: AtomicPriceStreamState::new_from_exchanges(&[]),
+            exchange_weights: HashMap::from([(Exchange::Override, 1.)]),
+            price_override: Some(101.),
+            price_bounds: None,
+            smoother: Some(Arc::new(Mutex::new(EmaSmoother::new(1_000)))),
+            feed_monitor: mock_feed_monitor(),
+        };
+
+        let PriceReporterState::Nominal(report) = reporter.peek_median() else {
+            panic!("expected a nominal price report");
+        };
+        assert!(report.smoothed);
+        assert_eq!(report.midpoint_price, 101.);
+    }
+
+    /// Tests that keepalive pings are sent at the configured interval
+    #[tokio::test]
+    async fn test_keepalive_interval() {
+        const N_PINGS: u32 = 3;
+
+        let (mut stream_map, pings) = mock_connection_map(true /* answer_pings */);
+        let mut timer = keepalive_timer(KEEPALIVE_INTERVAL);
+
+        let start = Instant::now();
+        for i in 1..=N_PINGS {
+            timer.tick().await;
+            assert!(start.elapsed() >= KEEPALIVE_INTERVAL * i);
+            assert!(send_keepalives(&mut stream_map).await.is_empty());
+        }
+
+        assert_eq!(pings.load(Ordering::Relaxed), N_PINGS as usize);
+    }
+
+    /// Tests that a connection which misses too many pongs is flagged for
+    /// reconnection
+    #[tokio::test]
+    async fn test_missed_pong_triggers_reconnect() {
+        let (mut stream_map, pings) = mock_connection_map(false /* answer_pings */);
+
+        for _ in 0..MAX_MISSED_PONGS {
+            assert!(send_keepalives(&mut stream_map).await.is_empty());
+        }
+
+        let dead = send_keepalives(&mut stream_map).await;
+        assert_eq!(dead, vec![Exchange::Binance]);
+        assert_eq!(pings.load(Ordering::Relaxed), MAX_MISSED_PONGS + 1);
+    }
+}
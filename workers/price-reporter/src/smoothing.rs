@@ -0,0 +1,80 @@
+//! Defines an exponential moving average smoother applied to published median
+//! prices
+
+use common::types::Price;
+
+/// Smooths a price series with an exponential moving average
+///
+/// The weight of a price halves every `half_life_ms` milliseconds, so the
+/// smoother adapts to irregularly spaced updates
+#[derive(Clone, Debug)]
+pub struct EmaSmoother {
+    /// The time in milliseconds over which the weight of a price halves
+    half_life_ms: u64,
+    /// The current average and the time in milliseconds it was last updated
+    state: Option<(Price, u64)>,
+}
+
+impl EmaSmoother {
+    /// Construct a smoother with the given half-life
+    pub fn new(half_life_ms: u64) -> Self {
+        assert!(half_life_ms > 0, "EMA half-life must be positive");
+        Self { half_life_ms, state: None }
+    }
+
+    /// Fold a new price observed at `now_ms` into the average, returning the
+    /// smoothed price
+    ///
+    /// The first price observed seeds the average
+    pub fn update(&mut self, price: Price, now_ms: u64) -> Price {
+        let smoothed = match self.state {
+            None => price,
+            Some((average, last_update)) => {
+                let elapsed = now_ms.saturating_sub(last_update) as f64;
+                let alpha = 1. - 0.5f64.powf(elapsed / self.half_life_ms as f64);
+                average + alpha * (price - average)
+            },
+        };
+
+        self.state = Some((smoothed, now_ms));
+        smoothed
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::EmaSmoother;
+
+    /// The half-life used in the tests
+    const HALF_LIFE_MS: u64 = 1_000;
+
+    /// Tests that the average converges to a step change in price, halving
+    /// the remaining gap with each half-life elapsed
+    #[test]
+    fn test_step_change_convergence() {
+        let mut smoother = EmaSmoother::new(HALF_LIFE_MS);
+        assert_eq!(smoother.update(100., 0), 100.);
+
+        // Step the price up and report once per half-life
+        let mut prev_gap = 100.;
+        for i in 1..=10 {
+            let smoothed = smoother.update(200., i * HALF_LIFE_MS);
+            let gap = 200. - smoothed;
+
+            assert!((gap - prev_gap / 2.).abs() < 1e-9);
+            prev_gap = gap;
+        }
+
+        // After ten half-lives the average is within 0.1% of the new price
+        assert!(prev_gap < 0.1);
+    }
+
+    /// Tests that a report at the same instant as the last does not move the
+    /// average
+    #[test]
+    fn test_no_elapsed_time() {
+        let mut smoother = EmaSmoother::new(HALF_LIFE_MS);
+        smoother.update(100., 0);
+        assert_eq!(smoother.update(200., 0), 100.);
+    }
+}
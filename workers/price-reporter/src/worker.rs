@@ -2,22 +2,30 @@
 //! dispatches jobs to the PriceReporterExecutor.
 use common::{
     default_wrapper::DefaultOption,
-    types::{exchange::Exchange, CancelChannel},
+    types::{exchange::Exchange, token::Token, CancelChannel, Price},
     worker::Worker,
 };
 use external_api::bus_message::SystemBusMessage;
 use job_types::price_reporter::PriceReporterReceiver;
-use std::thread::{self, JoinHandle};
+use std::{
+    collections::HashMap,
+    thread::{self, JoinHandle},
+    time::Duration,
+};
 use system_bus::SystemBus;
 use tokio::runtime::Builder as TokioBuilder;
+use tungstenite::protocol::WebSocketConfig;
 
 use super::{
     errors::PriceReporterError,
+    exchange::connection::TlsPolicy,
     manager::{PriceReporter, PriceReporterExecutor},
 };
 
 /// The number of threads backing the price reporter manager
 const PRICE_REPORTER_MANAGER_NUM_THREADS: usize = 2;
+/// The weight of an exchange in the median price when none is configured
+const DEFAULT_EXCHANGE_WEIGHT: f64 = 1.;
 
 /// The config passed from the coordinator to the PriceReporter
 #[derive(Clone, Debug)]
@@ -36,6 +44,46 @@ pub struct PriceReporterConfig {
     pub disabled: bool,
     /// Exchanges that are explicitly disabled for price reporting
     pub disabled_exchanges: Vec<Exchange>,
+    /// The weight of each exchange in the median price, exchanges without an
+    /// entry have a weight of one
+    pub exchange_weights: HashMap<Exchange, f64>,
+    /// The fixed prices of pairs without a reliable exchange feed, keyed by
+    /// `(base, quote)`
+    pub price_overrides: HashMap<(Token, Token), Price>,
+    /// The absolute `(min, max)` bounds on each pair's price, keyed by
+    /// `(base, quote)`. Reports outside the bounds are dropped before
+    /// aggregation
+    pub price_bounds: HashMap<(Token, Token), (Price, Price)>,
+    /// The maximum size in bytes of a websocket frame read from an exchange
+    pub ws_max_frame_size: usize,
+    /// The maximum size in bytes of the buffer a websocket message from an
+    /// exchange is read into
+    pub ws_max_buffer_size: usize,
+    /// The half-life in milliseconds of the moving average applied to
+    /// published median prices, smoothing is disabled if not set
+    pub ema_half_life_ms: Option<u64>,
+    /// The time in milliseconds a pair may go without a fresh price before
+    /// its feeds are reported down
+    pub feed_grace_period_ms: u64,
+    /// The number of pairs whose median prices are computed and published
+    /// concurrently
+    pub median_concurrency: usize,
+    /// The interval in milliseconds at which keepalive pings are sent on each
+    /// exchange connection
+    pub keepalive_interval_ms: u64,
+    /// The number of consecutive keepalive pings an exchange may leave
+    /// unanswered before its connection is re-established
+    pub max_missed_pongs: usize,
+    /// Whether plaintext `ws://` exchange connections are permitted, e.g. for
+    /// devnet feeds
+    pub allow_insecure_ws: bool,
+    /// The hosts each exchange's websocket URL must point at, exchanges
+    /// without an entry are not pinned
+    pub exchange_hosts: HashMap<Exchange, String>,
+    /// The hex encoded SHA-256 fingerprints of the certificates each
+    /// exchange's server may present, exchanges without an entry are not
+    /// pinned
+    pub exchange_cert_pins: HashMap<Exchange, Vec<String>>,
     /// The channel on which the coordinator may mandate that the price reporter
     /// manager cancel its execution
     pub cancel_channel: CancelChannel,
@@ -59,6 +107,46 @@ impl PriceReporterConfig {
 
         !disabled && configured
     }
+
+    /// Returns the price override configured for the given pair, if any
+    pub(crate) fn price_override(&self, base: &Token, quote: &Token) -> Option<Price> {
+        self.price_overrides.get(&(base.clone(), quote.clone())).copied()
+    }
+
+    /// Returns the price bounds configured for the given pair, if any
+    pub(crate) fn price_bounds(&self, base: &Token, quote: &Token) -> Option<(Price, Price)> {
+        self.price_bounds.get(&(base.clone(), quote.clone())).copied()
+    }
+
+    /// Returns the websocket config bounding the memory used by a connection
+    /// to an exchange
+    pub(crate) fn ws_config(&self) -> WebSocketConfig {
+        WebSocketConfig {
+            max_frame_size: Some(self.ws_max_frame_size),
+            max_message_size: Some(self.ws_max_buffer_size),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the interval at which keepalive pings are sent on each exchange
+    /// connection
+    pub(crate) fn keepalive_interval(&self) -> Duration {
+        Duration::from_millis(self.keepalive_interval_ms)
+    }
+
+    /// Returns the TLS policy for connections to the given exchange
+    pub(crate) fn tls_policy(&self, exchange: Exchange) -> TlsPolicy {
+        TlsPolicy {
+            allow_insecure: self.allow_insecure_ws,
+            pinned_host: self.exchange_hosts.get(&exchange).cloned(),
+            cert_pins: self.exchange_cert_pins.get(&exchange).cloned().unwrap_or_default(),
+        }
+    }
+
+    /// Returns the weight of the given exchange in the median price
+    pub(crate) fn exchange_weight(&self, exchange: Exchange) -> f64 {
+        self.exchange_weights.get(&exchange).copied().unwrap_or(DEFAULT_EXCHANGE_WEIGHT)
+    }
 }
 
 impl Worker for PriceReporter {
@@ -20,6 +20,16 @@ pub enum ProofManagerError {
     Setup(String),
 }
 
+impl ProofManagerError {
+    /// Whether a job that failed with this error may succeed if attempted
+    /// again
+    ///
+    /// A prover failure is deterministic in the job's witness and statement
+    pub fn is_retryable(&self) -> bool {
+        !matches!(self, Self::Prover(_))
+    }
+}
+
 impl Display for ProofManagerError {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         write!(f, "{:?}", self)
@@ -33,7 +33,7 @@ use common::types::proof_bundles::{
     mocks::{dummy_link_hint, dummy_proof},
     ProofBundle,
 };
-use job_types::proof_manager::{ProofJob, ProofManagerReceiver};
+use job_types::proof_manager::{ProofJob, ProofJobResult, ProofManagerReceiver};
 use tokio::{runtime::Handle, sync::oneshot::Sender as TokioSender};
 use tracing::error;
 
@@ -74,7 +74,7 @@ impl MockProofManager {
     /// Handle a job by immediately returning a dummy proof
     fn handle_job(
         job_type: ProofJob,
-        response_channel: TokioSender<ProofBundle>,
+        response_channel: TokioSender<ProofJobResult>,
     ) -> Result<(), ProofManagerError> {
         let bundle = match job_type {
             ProofJob::ValidWalletCreate { witness, statement } => {
@@ -103,7 +103,7 @@ impl MockProofManager {
             },
         }?;
 
-        response_channel.send(bundle).expect(ERR_RESPONSE_CHANNEL_CLOSED);
+        response_channel.send(Ok(bundle)).expect(ERR_RESPONSE_CHANNEL_CLOSED);
         Ok(())
     }
 
@@ -2,7 +2,7 @@
 //! happen to the state. It provides an abstracted messaging interface for other
 //! workers to submit proof requests to.
 
-use std::{sync::Arc, thread::JoinHandle};
+use std::{sync::Arc, thread::JoinHandle, time::Instant};
 
 use circuits::{
     singleprover_prove_with_hint,
@@ -35,8 +35,9 @@ use circuits::{
     },
 };
 use common::types::{proof_bundles::ProofBundle, CancelChannel};
-use job_types::proof_manager::{ProofJob, ProofManagerJob, ProofManagerReceiver};
+use job_types::proof_manager::{ProofJob, ProofJobError, ProofManagerJob, ProofManagerReceiver};
 use rayon::ThreadPool;
+use renegade_metrics::registry::RELAYER_METRICS;
 use tracing::{error, info, info_span, instrument};
 
 use super::error::ProofManagerError;
@@ -46,8 +47,6 @@ use super::error::ProofManagerError;
 // -------------
 /// Error message when sending a proof response fails
 const ERR_SENDING_RESPONSE: &str = "error sending proof response, channel closed";
-/// The number of threads to allocate towards the proof generation worker pool
-pub(crate) const PROOF_GENERATION_N_THREADS: usize = 10;
 
 // --------------------
 // | Proof Generation |
@@ -102,7 +101,8 @@ impl ProofManager {
 
     /// The main job handler, run by a thread in the pool
     fn handle_proof_job(job: ProofManagerJob) -> Result<(), ProofManagerError> {
-        let proof_bundle = match job.type_ {
+        let start = Instant::now();
+        let res = match job.type_ {
             ProofJob::ValidWalletCreate { witness, statement } => {
                 // Prove `VALID WALLET CREATE`
                 Self::prove_valid_wallet_create(witness, statement)
@@ -141,10 +141,22 @@ impl ProofManager {
                 // Prove `VALID FEE REDEMPTION`
                 Self::prove_valid_fee_redemption(witness, statement)
             },
-        }?;
+        };
+
+        // Report the failure to the requester, so that it may decide whether to retry
+        let res = match res {
+            Ok(bundle) => {
+                RELAYER_METRICS.proof_generation_latency.observe(start.elapsed());
+                Ok(bundle)
+            },
+            Err(e) => {
+                error!("Error generating proof: {e}");
+                Err(ProofJobError { message: e.to_string(), retryable: e.is_retryable() })
+            },
+        };
 
         job.response_channel
-            .send(proof_bundle)
+            .send(res)
             .map_err(|_| ProofManagerError::Response(ERR_SENDING_RESPONSE.to_string()))
     }
 
@@ -10,10 +10,7 @@ use common::{types::CancelChannel, worker::Worker};
 use job_types::proof_manager::ProofManagerReceiver;
 use rayon::ThreadPoolBuilder;
 
-use super::{
-    error::ProofManagerError,
-    proof_manager::{ProofManager, PROOF_GENERATION_N_THREADS},
-};
+use super::{error::ProofManagerError, proof_manager::ProofManager};
 
 /// The name of the main worker thread
 const MAIN_THREAD_NAME: &str = "proof-generation-main";
@@ -23,6 +20,8 @@ const MAIN_THREAD_NAME: &str = "proof-generation-main";
 pub struct ProofManagerConfig {
     /// The job queue on which the manager may receive proof generation jobs
     pub job_queue: ProofManagerReceiver,
+    /// The number of threads in the proof generation pool
+    pub num_threads: usize,
     /// The cancel channel that the coordinator uses to signal to the proof
     /// generation module that it should shut down
     pub cancel_channel: CancelChannel,
@@ -38,7 +37,7 @@ impl Worker for ProofManager {
     {
         // Build a thread pool for the worker
         let proof_generation_thread_pool = ThreadPoolBuilder::new()
-            .num_threads(PROOF_GENERATION_N_THREADS)
+            .num_threads(config.num_threads)
             .build()
             .map_err(|err| ProofManagerError::Setup(err.to_string()))?;
 
@@ -82,3 +81,24 @@ impl Worker for ProofManager {
         vec![self.join_handle.take().unwrap()]
     }
 }
+
+#[cfg(test)]
+mod test {
+    use common::{types::new_cancel_channel, worker::Worker};
+    use job_types::proof_manager::new_proof_manager_queue;
+
+    use crate::proof_manager::ProofManager;
+
+    use super::ProofManagerConfig;
+
+    /// Tests that the proof generation pool is sized to the configured value
+    #[test]
+    fn test_thread_pool_size() {
+        let (_job_sender, job_queue) = new_proof_manager_queue();
+        let (_cancel_sender, cancel_channel) = new_cancel_channel();
+
+        let config = ProofManagerConfig { job_queue, num_threads: 3, cancel_channel };
+        let manager = ProofManager::new(config).unwrap();
+        assert_eq!(manager.thread_pool.current_num_threads(), 3);
+    }
+}
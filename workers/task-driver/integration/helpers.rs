@@ -20,7 +20,10 @@ use eyre::Result;
 use job_types::{
     network_manager::NetworkManagerQueue,
     proof_manager::ProofManagerQueue,
-    task_driver::{new_task_notification, TaskDriverJob, TaskDriverReceiver},
+    task_driver::{
+        new_match_gas_ledger, new_task_notification, new_task_registry, TaskDriverJob,
+        TaskDriverReceiver,
+    },
 };
 use num_bigint::BigUint;
 use rand::thread_rng;
@@ -88,7 +91,7 @@ pub(crate) async fn await_task(
     let (rx, job) = new_task_notification(task_id);
     test_args.task_queue.send(job).unwrap();
 
-    rx.await.unwrap().map_err(|e| eyre::eyre!(e))
+    rx.await.unwrap().map(|_| ()).map_err(|e| eyre::eyre!(e))
 }
 
 /// Await the execution and completion of a task run immediately
@@ -104,7 +107,7 @@ pub(crate) async fn await_immediate_task(
     let (rx, job) = new_task_notification(task_id);
     test_args.task_queue.send(job).unwrap();
 
-    rx.await.unwrap().map_err(|e| eyre::eyre!(e))
+    rx.await.unwrap().map(|_| ()).map_err(|e| eyre::eyre!(e))
 }
 
 /// Wait for a task queue on a wallet to flush
@@ -159,7 +162,7 @@ pub async fn mock_wallet_update(wallet: &mut Wallet, client: &ArbitrumClient) ->
 
     let mut rng = thread_rng();
     let share_comm = wallet.get_private_share_commitment();
-    let nullifier = Scalar::random(&mut rng);
+    let nullifier = Scalar::random(&mut rng).into();
 
     // Mock a `VALID WALLET UPDATE` proof bundle
     let mut proof = dummy_valid_wallet_update_bundle();
@@ -220,6 +223,7 @@ pub fn new_mock_task_driver(
         backoff_ceiling_ms: 1_000, // 1 second
         initial_backoff_ms: 100,   // 100 milliseconds
         n_retries: 2,
+        max_restarts: 2,
         n_threads: 5,
     };
 
@@ -231,6 +235,13 @@ pub fn new_mock_task_driver(
         network_queue,
         proof_queue,
         state,
+        task_registry: new_task_registry(),
+        match_confirmation_depth: 1,
+        match_gas_ledger: new_match_gas_ledger(),
+        match_batch_window_ms: None,
+        max_concurrent_wallet_lookups: 4,
+        reblind_idle_interval_ms: None,
+        order_ttl_ms: None,
     };
 
     // Start the driver
@@ -14,7 +14,9 @@ use std::{str::FromStr, sync::Arc};
 
 use arbitrum_client::{
     client::{ArbitrumClient, ArbitrumClientConfig},
-    constants::Chain,
+    constants::{
+        Chain, DEFAULT_EVENT_BLOCK_WINDOW, DEFAULT_GAS_LIMIT_CEILING, DEFAULT_RPC_TIMEOUT_MS,
+    },
 };
 use clap::Parser;
 use common::types::token::TOKEN_REMAPS;
@@ -172,6 +174,10 @@ fn setup_arbitrum_client_mock(test_args: &CliArgs) -> ArbitrumClient {
         darkpool_addr,
         arb_priv_key,
         rpc_url: test_args.devnet_url.clone(),
+        fallback_rpc_urls: vec![],
+        rpc_timeout_ms: DEFAULT_RPC_TIMEOUT_MS,
+        event_block_window: DEFAULT_EVENT_BLOCK_WINDOW,
+        gas_limit_ceiling: DEFAULT_GAS_LIMIT_CEILING,
     }))
     .unwrap()
 }
@@ -267,8 +267,9 @@ async fn dummy_match_bundle(
     // Await a response
     let match_proof: ValidMatchSettleBundle = recv
         .await
+        .map_err(|_| eyre!("Failed to receive proof bundle"))?
         .map(|bundle| bundle.proof.into())
-        .map_err(|_| eyre!("Failed to receive proof bundle"))?;
+        .map_err(|e| eyre!("Failed to generate proof: {e}"))?;
 
     Ok(MatchBundle {
         match_proof,
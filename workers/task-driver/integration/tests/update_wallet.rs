@@ -69,7 +69,8 @@ pub(crate) async fn execute_wallet_update(
 
     let id = new_wallet.wallet_id;
     let task =
-        UpdateWalletTaskDescriptor::new(transfer_with_auth, old_wallet, new_wallet, sig).unwrap();
+        UpdateWalletTaskDescriptor::new(transfer_with_auth, old_wallet, new_wallet, sig, None)
+            .unwrap();
 
     await_task(task.into(), &test_args).await?;
 
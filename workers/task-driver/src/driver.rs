@@ -4,6 +4,7 @@
 use std::{
     collections::{HashMap, HashSet},
     fmt::{Debug, Display},
+    sync::Arc,
     time::Duration,
 };
 
@@ -17,15 +18,23 @@ use common::{
     Shared,
 };
 use futures::Future;
+use itertools::Itertools;
 use job_types::task_driver::{TaskDriverJob, TaskDriverReceiver, TaskNotificationSender};
+use renegade_metrics::registry::RELAYER_METRICS;
 use serde::Serialize;
 use state::State;
-use tokio::runtime::{Builder as TokioRuntimeBuilder, Runtime as TokioRuntime};
-use tracing::{error, info, info_span, instrument, warn, Instrument};
+use tokio::{
+    runtime::{Builder as TokioRuntimeBuilder, Runtime as TokioRuntime},
+    sync::Semaphore,
+};
+use tracing::{error, field, info, info_span, instrument, warn, Instrument, Span};
 
 use crate::{
     error::TaskDriverError,
-    running_task::RunnableTask,
+    idle_reblind::IdleReblinder,
+    order_expiry::OrderExpirer,
+    running_task::{DrivenTask, RunnableTask},
+    settlement_batcher::SettlementBatcher,
     tasks::{
         create_new_wallet::{NewWalletTask, NewWalletTaskState},
         lookup_wallet::{LookupWalletTask, LookupWalletTaskState},
@@ -37,7 +46,7 @@ use crate::{
         update_merkle_proof::{UpdateMerkleProofTask, UpdateMerkleProofTaskState},
         update_wallet::{UpdateWalletTask, UpdateWalletTaskState},
     },
-    traits::{Task, TaskContext, TaskState},
+    traits::{RetryPolicy, Task, TaskContext, TaskState},
     worker::TaskDriverConfig,
 };
 
@@ -53,9 +62,24 @@ const TASK_DRIVER_N_THREADS: usize = 5;
 const TASK_DRIVER_THREAD_NAME: &str = "renegade-task-driver";
 /// The number of times to retry a step in a task before propagating the error
 const TASK_DRIVER_N_RETRIES: usize = 5;
+/// The number of times a task failing before its commit point may be restarted
+const TASK_DRIVER_MAX_RESTARTS: usize = 2;
 /// The stack size to allocate for task driver threads
 const DRIVER_THREAD_STACK_SIZE: usize = 5_000_000; // 5MB
 
+/// Build the span that a task runs in
+///
+/// The task name is recorded once the task is constructed from its descriptor,
+/// spans created within the task inherit these fields as their parent's
+pub(crate) fn task_span(task_id: TaskIdentifier, wallet_ids: &[WalletIdentifier]) -> Span {
+    info_span!(
+        "task",
+        task_id = %task_id,
+        task_name = field::Empty,
+        wallet_id = %wallet_ids.iter().join(","),
+    )
+}
+
 /// Error message sent on a notification when a task is not found
 const TASK_NOT_FOUND_ERROR: &str = "task not found";
 
@@ -84,6 +108,11 @@ pub struct TaskExecutor {
     preemptive_tasks: Shared<HashSet<TaskIdentifier>>,
     /// The map of task notifications to send
     task_notifications: TaskNotificationMap,
+    /// Limits the number of wallet lookups running concurrently
+    ///
+    /// Lookups scan chain events, so we bound them to avoid overwhelming the
+    /// RPC provider when many are enqueued at once
+    lookup_limiter: Arc<Semaphore>,
 }
 
 /// The config of the runtime arguments
@@ -99,6 +128,9 @@ pub struct RuntimeArgs {
     pub initial_backoff_ms: u64,
     /// The number of retries to attempt before propagating an error
     pub n_retries: usize,
+    /// The number of times a task failing before its commit point may be
+    /// restarted from its descriptor, further failures retry the failed step
+    pub max_restarts: usize,
     /// The number of threads backing the tokio runtime
     pub n_threads: usize,
 }
@@ -110,6 +142,7 @@ impl Default for RuntimeArgs {
             backoff_ceiling_ms: BACKOFF_CEILING_MS,
             initial_backoff_ms: INITIAL_BACKOFF_MS,
             n_retries: TASK_DRIVER_N_RETRIES,
+            max_restarts: TASK_DRIVER_MAX_RESTARTS,
             n_threads: TASK_DRIVER_N_THREADS,
         }
     }
@@ -127,12 +160,31 @@ impl TaskExecutor {
             .build()
             .expect("error building task driver runtime");
 
+        let settlement_batcher = SettlementBatcher::maybe_new(
+            config.arbitrum_client.clone(),
+            config.match_batch_window_ms.map(Duration::from_millis),
+            runtime.handle(),
+        );
+
+        if let Some(interval_ms) = config.reblind_idle_interval_ms {
+            let interval = Duration::from_millis(interval_ms);
+            IdleReblinder::new(config.state.clone(), interval).spawn(runtime.handle());
+        }
+
+        if let Some(ttl_ms) = config.order_ttl_ms {
+            OrderExpirer::new(config.state.clone(), ttl_ms).spawn(runtime.handle());
+        }
+
         let task_context = TaskContext {
             arbitrum_client: config.arbitrum_client,
             network_queue: config.network_queue,
             proof_queue: config.proof_queue,
             state: config.state,
             bus: config.system_bus.clone(),
+            task_registry: config.task_registry,
+            match_confirmation_depth: config.match_confirmation_depth,
+            match_gas_ledger: config.match_gas_ledger,
+            settlement_batcher,
         };
 
         Self {
@@ -142,6 +194,7 @@ impl TaskExecutor {
             task_context,
             preemptive_tasks: new_shared(HashSet::new()),
             task_notifications: new_shared(HashMap::new()),
+            lookup_limiter: Arc::new(Semaphore::new(config.max_concurrent_wallet_lookups)),
         }
     }
 
@@ -189,9 +242,12 @@ impl TaskExecutor {
             let job = queue.recv().map_err(|_| TaskDriverError::JobQueueClosed)?;
             let res = match job {
                 TaskDriverJob::Run(task) => {
+                    let wallet_ids = vec![task.descriptor.queue_key()];
+                    let span = task_span(task.id, &wallet_ids);
                     let fut = self.create_task_future(
                         false, // immediate
                         task.id,
+                        wallet_ids,
                         task.descriptor,
                     );
                     self.runtime.spawn(
@@ -201,7 +257,7 @@ impl TaskExecutor {
                                 error!("error running task: {e:?}");
                             }
                         }
-                        .instrument(info_span!("task", task_id = %task.id)),
+                        .instrument(span),
                     );
 
                     Ok(())
@@ -269,7 +325,9 @@ impl TaskExecutor {
         self.add_preemptive_task(task_id);
         let preemptive_tasks = self.preemptive_tasks.clone();
 
-        let fut = self.create_task_future(true /* immediate */, task_id, task);
+        let span = task_span(task_id, &wallet_ids);
+        let fut =
+            self.create_task_future(true /* immediate */, task_id, wallet_ids.clone(), task);
         let state = self.state().clone();
         self.runtime.spawn(
             async move {
@@ -290,7 +348,7 @@ impl TaskExecutor {
                 // Remove from the preemptive tasks list
                 preemptive_tasks.write().unwrap().remove(&task_id);
             }
-            .instrument(info_span!("task", task_id = %task_id)),
+            .instrument(span),
         );
 
         Ok(())
@@ -305,68 +363,94 @@ impl TaskExecutor {
         &self,
         immediate: bool,
         task_id: TaskIdentifier,
+        wallet_ids: Vec<WalletIdentifier>,
         descriptor: TaskDescriptor,
     ) -> impl Future<Output = Result<(), TaskDriverError>> {
         // Collect the arguments then spawn
         let ctx = self.task_context();
         let args = self.runtime_config;
         let task_notifications = self.task_notifications.clone();
-
-        Self::start_task(immediate, task_id, descriptor, ctx, args, task_notifications)
+        let lookup_limiter = self.lookup_limiter.clone();
+
+        Self::start_task(
+            immediate,
+            task_id,
+            wallet_ids,
+            descriptor,
+            ctx,
+            args,
+            task_notifications,
+            lookup_limiter,
+        )
     }
 
     /// Spawn a new task in the driver
     ///
     /// Returns the success of the task
+    #[allow(clippy::too_many_arguments)]
     async fn start_task(
         immediate: bool,
         id: TaskIdentifier,
+        wallet_ids: Vec<WalletIdentifier>,
         task: TaskDescriptor,
         ctx: TaskContext,
         args: RuntimeArgs,
         notif: TaskNotificationMap,
+        lookup_limiter: Arc<Semaphore>,
     ) -> Result<(), TaskDriverError> {
         // Construct the task from the descriptor
         match task {
             TaskDescriptor::NewWallet(desc) => {
-                Self::start_task_helper::<NewWalletTask>(immediate, id, desc, ctx, args, notif)
-                    .await
+                Self::start_task_helper::<NewWalletTask>(
+                    immediate, id, wallet_ids, desc, ctx, args, notif,
+                )
+                .await
             },
             TaskDescriptor::LookupWallet(desc) => {
-                Self::start_task_helper::<LookupWalletTask>(immediate, id, desc, ctx, args, notif)
-                    .await
+                let fut = Self::start_task_helper::<LookupWalletTask>(
+                    immediate, id, wallet_ids, desc, ctx, args, notif,
+                );
+                run_with_limit(&lookup_limiter, fut).await
             },
             TaskDescriptor::OfflineFee(desc) => {
-                Self::start_task_helper::<PayOfflineFeeTask>(immediate, id, desc, ctx, args, notif)
-                    .await
+                Self::start_task_helper::<PayOfflineFeeTask>(
+                    immediate, id, wallet_ids, desc, ctx, args, notif,
+                )
+                .await
             },
             TaskDescriptor::RelayerFee(desc) => {
-                Self::start_task_helper::<PayRelayerFeeTask>(immediate, id, desc, ctx, args, notif)
-                    .await
+                Self::start_task_helper::<PayRelayerFeeTask>(
+                    immediate, id, wallet_ids, desc, ctx, args, notif,
+                )
+                .await
             },
             TaskDescriptor::RedeemRelayerFee(desc) => {
                 Self::start_task_helper::<RedeemRelayerFeeTask>(
-                    immediate, id, desc, ctx, args, notif,
+                    immediate, id, wallet_ids, desc, ctx, args, notif,
                 )
                 .await
             },
             TaskDescriptor::UpdateWallet(desc) => {
-                Self::start_task_helper::<UpdateWalletTask>(immediate, id, desc, ctx, args, notif)
-                    .await
+                Self::start_task_helper::<UpdateWalletTask>(
+                    immediate, id, wallet_ids, desc, ctx, args, notif,
+                )
+                .await
             },
             TaskDescriptor::SettleMatch(desc) => {
-                Self::start_task_helper::<SettleMatchTask>(immediate, id, desc, ctx, args, notif)
-                    .await
+                Self::start_task_helper::<SettleMatchTask>(
+                    immediate, id, wallet_ids, desc, ctx, args, notif,
+                )
+                .await
             },
             TaskDescriptor::SettleMatchInternal(desc) => {
                 Self::start_task_helper::<SettleMatchInternalTask>(
-                    immediate, id, desc, ctx, args, notif,
+                    immediate, id, wallet_ids, desc, ctx, args, notif,
                 )
                 .await
             },
             TaskDescriptor::UpdateMerkleProof(desc) => {
                 Self::start_task_helper::<UpdateMerkleProofTask>(
-                    immediate, id, desc, ctx, args, notif,
+                    immediate, id, wallet_ids, desc, ctx, args, notif,
                 )
                 .await
             },
@@ -378,23 +462,29 @@ impl TaskExecutor {
     async fn start_task_helper<T: Task>(
         immediate: bool,
         id: TaskIdentifier,
+        wallet_ids: Vec<WalletIdentifier>,
         descriptor: T::Descriptor,
         ctx: TaskContext,
         args: RuntimeArgs,
         notifications: TaskNotificationMap,
     ) -> Result<(), TaskDriverError> {
         // Create the task
-        let mut task = RunnableTask::<T>::from_descriptor(immediate, id, descriptor, ctx).await?;
+        let mut task =
+            RunnableTask::<T>::from_descriptor(immediate, id, wallet_ids, descriptor, ctx).await?;
+        Span::current().record("task_name", task.name());
 
-        // Run the task
+        // Run the task, the guard leaves the task in flight until cleanup completes
+        let in_flight = RELAYER_METRICS.in_flight_tasks.track();
         let res = Self::run_task_to_completion(&mut task, args).await;
 
         // Cleanup
         let cleanup_res = task.cleanup().await;
         let combined_res = res.and(cleanup_res);
+        drop(in_flight);
 
         // Notify any listeners that the task has completed
-        let str_res = combined_res.clone().map_err(|e| e.to_string());
+        let output = task.output();
+        let str_res = combined_res.clone().map(|_| output).map_err(|e| e.to_string());
         for sender in notifications.write().unwrap().remove(&id).unwrap_or_default() {
             let _ = sender.send(str_res.clone());
         }
@@ -403,20 +493,22 @@ impl TaskExecutor {
     }
 
     /// Run a task to completion
-    async fn run_task_to_completion<T: Task>(
-        task: &mut RunnableTask<T>,
+    async fn run_task_to_completion<T: DrivenTask>(
+        task: &mut T,
         args: RuntimeArgs,
     ) -> Result<(), TaskDriverError> {
         let id = task.id();
         let backoff_ceiling = Duration::from_millis(args.backoff_ceiling_ms);
 
         // Run each step individually and update the state after each step
+        let mut restarts = 0;
         'outer: while !task.completed() {
             // Take a step
             let mut retries = args.n_retries;
             let mut curr_backoff = Duration::from_millis(args.initial_backoff_ms);
 
-            while !task.step().await? {
+            let step_span = info_span!("task_step", state = %task.state());
+            while !task.step().instrument(step_span.clone()).await? {
                 retries -= 1;
                 if retries == 0 {
                     error!("retries exceeded... task failed");
@@ -425,10 +517,19 @@ impl TaskExecutor {
 
                 // Sleep the backoff time and retry
                 tokio::time::sleep(curr_backoff).await;
-                info!("retrying task {id:?} from state: {}", task.state());
-
                 curr_backoff *= args.backoff_amplification_factor;
                 curr_backoff = Duration::min(curr_backoff, backoff_ceiling);
+
+                // A task that has not committed may restart from scratch, bounded so that a
+                // persistently failing step cannot restart the task indefinitely
+                let state = task.state();
+                if state.retry_policy() == RetryPolicy::Restart && restarts < args.max_restarts {
+                    info!("restarting task {id:?} after failure in state: {state}");
+                    restarts += 1;
+                    task.restart().await?;
+                } else {
+                    info!("retrying task {id:?} from state: {state}");
+                }
             }
         }
 
@@ -440,6 +541,13 @@ impl TaskExecutor {
     }
 }
 
+/// Run a future once a permit is available from the given limiter, queued
+/// futures wait in FIFO order
+async fn run_with_limit<F: Future>(limiter: &Semaphore, fut: F) -> F::Output {
+    let _permit = limiter.acquire().await.expect("task limiter closed");
+    fut.await
+}
+
 // --------------------
 // | State Management |
 // --------------------
@@ -485,6 +593,21 @@ impl StateWrapper {
         }
     }
 
+    /// The retry policy applied to a failure of the step run from this state
+    pub fn retry_policy(&self) -> RetryPolicy {
+        match self {
+            StateWrapper::LookupWallet(state) => state.retry_policy(),
+            StateWrapper::NewWallet(state) => state.retry_policy(),
+            StateWrapper::PayOfflineFee(state) => state.retry_policy(),
+            StateWrapper::PayRelayerFee(state) => state.retry_policy(),
+            StateWrapper::RedeemRelayerFee(state) => state.retry_policy(),
+            StateWrapper::SettleMatch(state) => state.retry_policy(),
+            StateWrapper::SettleMatchInternal(state) => state.retry_policy(),
+            StateWrapper::UpdateWallet(state) => state.retry_policy(),
+            StateWrapper::UpdateMerkleProof(state) => state.retry_policy(),
+        }
+    }
+
     /// Whether or not this state commits the task, i.e. is the first state that
     /// for which `committed` is true
     pub fn is_committing(&self) -> bool {
@@ -533,3 +656,196 @@ impl From<StateWrapper> for QueuedTaskState {
         QueuedTaskState::Running { state, committed }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use async_trait::async_trait;
+    use common::types::tasks::TaskIdentifier;
+    use futures::future::join_all;
+    use tokio::sync::Semaphore;
+
+    use crate::{
+        error::TaskDriverError,
+        running_task::DrivenTask,
+        tasks::{
+            settle_match::SettleMatchTaskState, settle_match_internal::SettleMatchInternalTaskState,
+        },
+        traits::{RetryPolicy, TaskState},
+    };
+
+    use super::{run_with_limit, RuntimeArgs, StateWrapper, TaskExecutor};
+
+    /// A mock settlement task that fails its step once in each of the given
+    /// states
+    struct MockSettlementTask {
+        /// The current state of the task
+        state: SettleMatchInternalTaskState,
+        /// The states in which the next step fails
+        fail_in: Vec<SettleMatchInternalTaskState>,
+        /// The number of times the task was restarted
+        restarts: usize,
+        /// The number of times the match was submitted
+        submissions: usize,
+    }
+
+    impl MockSettlementTask {
+        /// Constructor
+        fn new(fail_in: Vec<SettleMatchInternalTaskState>) -> Self {
+            Self {
+                state: SettleMatchInternalTaskState::Pending,
+                fail_in,
+                restarts: 0,
+                submissions: 0,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl DrivenTask for MockSettlementTask {
+        fn id(&self) -> TaskIdentifier {
+            TaskIdentifier::default()
+        }
+
+        fn completed(&self) -> bool {
+            self.state.completed()
+        }
+
+        fn state(&self) -> StateWrapper {
+            self.state.clone().into()
+        }
+
+        async fn step(&mut self) -> Result<bool, TaskDriverError> {
+            use SettleMatchInternalTaskState::*;
+            if let Some(idx) = self.fail_in.iter().position(|s| s == &self.state) {
+                self.fail_in.remove(idx);
+                return Ok(false);
+            }
+
+            self.state = match self.state {
+                Pending => ProvingMatchSettle,
+                ProvingMatchSettle => SubmittingMatch,
+                SubmittingMatch => {
+                    self.submissions += 1;
+                    AwaitingConfirmation
+                },
+                AwaitingConfirmation => UpdatingState,
+                UpdatingState => UpdatingValidityProofs,
+                UpdatingValidityProofs | Completed => Completed,
+            };
+            Ok(true)
+        }
+
+        async fn restart(&mut self) -> Result<(), TaskDriverError> {
+            self.state = SettleMatchInternalTaskState::Pending;
+            self.restarts += 1;
+            Ok(())
+        }
+    }
+
+    /// Drive a mock settlement failing in the given states to completion
+    async fn run_mock_settlement(
+        fail_in: Vec<SettleMatchInternalTaskState>,
+        max_restarts: usize,
+    ) -> MockSettlementTask {
+        let args = RuntimeArgs {
+            backoff_amplification_factor: 2,
+            backoff_ceiling_ms: 1,
+            initial_backoff_ms: 1,
+            n_retries: 3,
+            max_restarts,
+            n_threads: 1,
+        };
+
+        let mut task = MockSettlementTask::new(fail_in);
+        TaskExecutor::run_task_to_completion(&mut task, args).await.unwrap();
+        assert!(task.completed());
+        task
+    }
+
+    /// Tests that queued lookups never exceed the concurrency limit
+    #[tokio::test]
+    async fn test_lookup_limit() {
+        const LIMIT: usize = 3;
+        const N_LOOKUPS: usize = 10;
+
+        let limiter = Arc::new(Semaphore::new(LIMIT));
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+
+        let mut handles = Vec::new();
+        for _ in 0..N_LOOKUPS {
+            let limiter = limiter.clone();
+            let running = running.clone();
+            let max_running = max_running.clone();
+            handles.push(tokio::spawn(async move {
+                run_with_limit(&limiter, async {
+                    let n = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_running.fetch_max(n, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                })
+                .await
+            }));
+        }
+
+        for res in join_all(handles).await {
+            res.unwrap();
+        }
+        assert_eq!(max_running.load(Ordering::SeqCst), LIMIT);
+        assert_eq!(limiter.available_permits(), LIMIT);
+    }
+
+    /// Tests that a task failing before its commit point restarts from
+    /// scratch under a restart policy
+    #[test]
+    fn test_pre_commit_failure_restarts() {
+        let proving =
+            StateWrapper::SettleMatchInternal(SettleMatchInternalTaskState::ProvingMatchSettle);
+        assert!(!proving.committed());
+        assert_eq!(proving.retry_policy(), RetryPolicy::Restart);
+
+        // A task without a restart policy resumes before its commit point
+        let pending = StateWrapper::SettleMatch(SettleMatchTaskState::Pending);
+        assert!(!pending.committed());
+        assert_eq!(pending.retry_policy(), RetryPolicy::Resume);
+    }
+
+    /// Tests that a task failing at or after its commit point resumes from the
+    /// committed step, regardless of its pre-commit policy
+    #[test]
+    fn test_post_commit_failure_resumes() {
+        let submitting =
+            StateWrapper::SettleMatchInternal(SettleMatchInternalTaskState::SubmittingMatch);
+        assert!(submitting.is_committing());
+        assert_eq!(submitting.retry_policy(), RetryPolicy::Resume);
+
+        let updating =
+            StateWrapper::SettleMatchInternal(SettleMatchInternalTaskState::UpdatingState);
+        assert!(updating.committed());
+        assert_eq!(updating.retry_policy(), RetryPolicy::Resume);
+    }
+
+    /// Tests that the driver restarts a task failing before its commit point
+    /// and resumes a task failing after it without resubmitting its match
+    #[tokio::test]
+    async fn test_driver_retry_around_commit_point() {
+        use SettleMatchInternalTaskState::*;
+
+        let task = run_mock_settlement(vec![ProvingMatchSettle, AwaitingConfirmation], 2).await;
+        assert_eq!(task.restarts, 1);
+        assert_eq!(task.submissions, 1);
+
+        // Without a restart budget the pre-commit failure retries the failed step
+        let task = run_mock_settlement(vec![ProvingMatchSettle, AwaitingConfirmation], 0).await;
+        assert_eq!(task.restarts, 0);
+        assert_eq!(task.submissions, 1);
+    }
+}
@@ -1,8 +1,13 @@
 //! Helpers for common functionality across tasks
 
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use arbitrum_client::{client::ArbitrumClient, errors::ArbitrumClientError};
+use arbitrum_client::{
+    client::ArbitrumClient, errors::ArbitrumClientError, traits::DarkpoolClient,
+};
 use circuit_types::{
     balance::Balance,
     native_helpers::{
@@ -30,18 +35,19 @@ use common::types::{
     proof_bundles::{OrderValidityProofBundle, OrderValidityWitnessBundle},
     wallet::OrderIdentifier,
 };
+use ethers::types::TxHash;
 use gossip_api::pubsub::{
     orderbook::{OrderBookManagementMessage, ORDER_BOOK_TOPIC},
     PubsubMessage,
 };
 use job_types::{
     network_manager::{NetworkManagerJob, NetworkManagerQueue},
-    proof_manager::{ProofJob, ProofManagerJob, ProofManagerQueue},
+    proof_manager::{ProofJob, ProofJobError, ProofJobResult, ProofManagerJob, ProofManagerQueue},
 };
 use num_bigint::BigUint;
 use state::State;
 use tokio::sync::oneshot::{self, Receiver as TokioReceiver};
-use tracing::debug;
+use tracing::{debug, warn};
 
 // -------------
 // | Constants |
@@ -49,6 +55,8 @@ use tracing::debug;
 
 /// Error message emitted when enqueuing a job with the proof manager fails
 const ERR_ENQUEUING_JOB: &str = "error enqueuing job with proof manager";
+/// Error message emitted when the proof manager drops a job without responding
+const ERR_PROOF_JOB_DROPPED: &str = "proof manager dropped the job";
 /// Error message emitted when a balance cannot be found for an order
 const ERR_BALANCE_NOT_FOUND: &str = "cannot find balance for order";
 /// Error message emitted when a wallet is given missing an authentication path
@@ -59,6 +67,19 @@ const ERR_ORDER_NOT_FOUND: &str = "cannot find order in wallet";
 const ERR_PROVE_COMMITMENTS_FAILED: &str = "failed to prove valid commitments";
 /// Error message emitted when proving VALID REBLIND fails
 const ERR_PROVE_REBLIND_FAILED: &str = "failed to prove valid reblind";
+/// Error message emitted when a transaction does not reach its confirmation
+/// depth in time
+pub(crate) const ERR_CONFIRMATION_TIMEOUT: &str = "timed out awaiting confirmations";
+/// Error message emitted when a transaction is no longer included in its
+/// block once confirmed, i.e. it was reorged out
+pub(crate) const ERR_TX_REORGED: &str = "transaction reorged out of its block";
+
+/// The maximum number of times a validity proof job is attempted before the
+/// proof is considered failed
+const MAX_PROOF_ATTEMPTS: usize = 3;
+/// The initial backoff between validity proof attempts, doubled after each
+/// failed attempt
+const PROOF_RETRY_BACKOFF_MS: u64 = 250;
 
 // -----------
 // | Helpers |
@@ -70,7 +91,7 @@ const ERR_PROVE_REBLIND_FAILED: &str = "failed to prove valid reblind";
 pub(crate) fn enqueue_proof_job(
     job: ProofJob,
     work_queue: &ProofManagerQueue,
-) -> Result<TokioReceiver<ProofBundle>, String> {
+) -> Result<TokioReceiver<ProofJobResult>, String> {
     let (response_sender, response_receiver) = oneshot::channel();
     work_queue
         .send(ProofManagerJob { type_: job, response_channel: response_sender })
@@ -79,6 +100,54 @@ pub(crate) fn enqueue_proof_job(
     Ok(response_receiver)
 }
 
+/// Await the result of a job enqueued with the proof manager
+///
+/// A job whose response channel is dropped without a result is treated as a
+/// retryable failure
+pub(crate) async fn await_proof(
+    receiver: TokioReceiver<ProofJobResult>,
+) -> Result<ProofBundle, ProofJobError> {
+    receiver.await.map_err(|_| ProofJobError::retryable(ERR_PROOF_JOB_DROPPED))?
+}
+
+/// Await a proof from the proof manager, re-enqueuing the job with backoff if
+/// it fails with a retryable error
+///
+/// The proof manager drops a job or fails it as retryable when the failure is
+/// transient, e.g. when it is momentarily saturated, so the job is attempted up
+/// to `MAX_PROOF_ATTEMPTS` times. A permanent failure, such as a witness that
+/// does not satisfy the circuit, is returned immediately. A failure to enqueue
+/// the job is not retried, as the proof manager has shut down
+async fn await_proof_with_retry(
+    job: ProofJob,
+    mut receiver: TokioReceiver<ProofJobResult>,
+    work_queue: &ProofManagerQueue,
+    err_msg: &str,
+) -> Result<ProofBundle, String> {
+    let mut attempt = 1;
+    let mut backoff_ms = PROOF_RETRY_BACKOFF_MS;
+    loop {
+        match await_proof(receiver).await {
+            Ok(bundle) => return Ok(bundle),
+            Err(e) if e.retryable && attempt < MAX_PROOF_ATTEMPTS => {
+                warn!(
+                    "{err_msg} (attempt {attempt}/{MAX_PROOF_ATTEMPTS}): {e}, retrying in \
+                     {backoff_ms}ms"
+                );
+                tokio::time::sleep(Duration::from_millis(backoff_ms)).await;
+
+                receiver = enqueue_proof_job(job.clone(), work_queue)?;
+                attempt += 1;
+                backoff_ms *= 2;
+            },
+            Err(e) => {
+                warn!("{err_msg} (attempt {attempt}/{MAX_PROOF_ATTEMPTS}): {e}");
+                return Err(err_msg.to_string());
+            },
+        }
+    }
+}
+
 /// Find the merkle authentication path of a wallet
 pub(crate) async fn find_merkle_path(
     wallet: &Wallet,
@@ -89,11 +158,42 @@ pub(crate) async fn find_merkle_path(
     arbitrum_client.find_merkle_authentication_path(wallet.get_wallet_share_commitment()).await
 }
 
-/// Re-blind the wallet and prove `VALID REBLIND` for the wallet
+/// Await the given number of block confirmations on a transaction included in
+/// `tx_block`, polling the current block number at the given interval
+///
+/// The block including the transaction counts as the first confirmation. Once
+/// the chain reaches the confirmation depth the transaction's receipt is
+/// re-fetched, and the wait fails if the transaction is no longer included in
+/// `tx_block`. The wait also fails if the depth is not reached within `timeout`
+pub(crate) async fn await_confirmations<C: DarkpoolClient>(
+    client: &C,
+    tx_hash: TxHash,
+    tx_block: u64,
+    depth: u64,
+    poll_interval: Duration,
+    timeout: Duration,
+) -> Result<(), String> {
+    let target_block = tx_block + depth.saturating_sub(1);
+    let deadline = Instant::now() + timeout;
+    while client.current_block().await.map_err(|e| e.to_string())? < target_block {
+        if Instant::now() >= deadline {
+            return Err(ERR_CONFIRMATION_TIMEOUT.to_string());
+        }
+
+        tokio::time::sleep(poll_interval).await;
+    }
+
+    let receipt = client.get_transaction_receipt(tx_hash).await.map_err(|e| e.to_string())?;
+    match receipt.and_then(|r| r.block_number) {
+        Some(block) if block.as_u64() == tx_block => Ok(()),
+        _ => Err(ERR_TX_REORGED.to_string()),
+    }
+}
+
+/// Re-blind the wallet and build a `VALID REBLIND` proof job for the wallet
 pub(crate) fn construct_wallet_reblind_proof(
     wallet: &Wallet,
-    prover_queue: &ProofManagerQueue,
-) -> Result<(SizedValidReblindWitness, TokioReceiver<ProofBundle>), String> {
+) -> Result<(SizedValidReblindWitness, ProofJob), String> {
     // If the wallet doesn't have an authentication path return an error
     let authentication_path =
         wallet.merkle_proof.clone().ok_or_else(|| ERR_MISSING_AUTHENTICATION_PATH.to_string())?;
@@ -123,21 +223,17 @@ pub(crate) fn construct_wallet_reblind_proof(
         sk_match: wallet.key_chain.secret_keys.sk_match,
     };
 
-    // Forward a job to the proof manager
     let job = ProofJob::ValidReblind { witness: witness.clone(), statement };
-    let recv = enqueue_proof_job(job, prover_queue)?;
-
-    Ok((witness, recv))
+    Ok((witness, job))
 }
 
-/// Prove `VALID COMMITMENTS` for an order within a wallet
+/// Build a `VALID COMMITMENTS` proof job for an order within a wallet
 ///
 /// Returns a copy of the witness for indexing
 pub(crate) fn construct_order_commitment_proof(
     order: Order,
     valid_reblind_witness: &SizedValidReblindWitness,
-    proof_manager_work_queue: &ProofManagerQueue,
-) -> Result<(SizedValidCommitmentsWitness, TokioReceiver<ProofBundle>), String> {
+) -> Result<(SizedValidCommitmentsWitness, ProofJob), String> {
     // Build an augmented wallet
     let mut augmented_wallet: SizedWallet = wallet_from_blinded_shares(
         &valid_reblind_witness.reblinded_wallet_private_shares,
@@ -171,11 +267,8 @@ pub(crate) fn construct_order_commitment_proof(
         balance_receive,
     };
 
-    // Dispatch a job to the proof manager to prove `VALID COMMITMENTS`
     let job = ProofJob::ValidCommitments { witness: witness.clone(), statement };
-    let recv = enqueue_proof_job(job, proof_manager_work_queue)?;
-
-    Ok((witness, recv))
+    Ok((witness, job))
 }
 
 /// Build the indices and fetch the balances for a given order
@@ -251,6 +344,9 @@ fn find_order(order: &Order, wallet: &SizedWallet) -> Option<usize> {
 /// Find a wallet on-chain, and update its validity proofs. That is, a proof of
 /// `VALID REBLIND` for the wallet, and one proof of `VALID COMMITMENTS` for
 /// each order in the wallet
+///
+/// Witness construction errors are permanent and returned immediately, while
+/// proof jobs dropped by the proof manager are retried with backoff
 pub(crate) async fn update_wallet_validity_proofs(
     wallet: &Wallet,
     proof_manager_work_queue: ProofManagerQueue,
@@ -268,30 +364,39 @@ pub(crate) async fn update_wallet_validity_proofs(
     }
 
     // Dispatch a proof of `VALID REBLIND` for the wallet
-    let (reblind_witness, reblind_response_channel) =
-        construct_wallet_reblind_proof(wallet, &proof_manager_work_queue)?;
+    let (reblind_witness, reblind_job) = construct_wallet_reblind_proof(wallet)?;
+    let reblind_response_channel =
+        enqueue_proof_job(reblind_job.clone(), &proof_manager_work_queue)?;
 
     // For each order, construct a proof of `VALID COMMITMENTS`
     let mut commitments_instances = Vec::new();
     for (id, order) in wallet.orders.iter().filter(|(_id, o)| !o.is_zero()) {
         // Start a proof of `VALID COMMITMENTS`
-        let (commitments_witness, response_channel) = construct_order_commitment_proof(
-            order.clone(),
-            &reblind_witness,
-            &proof_manager_work_queue,
-        )?;
-        commitments_instances.push((*id, commitments_witness, response_channel));
+        let (commitments_witness, job) =
+            construct_order_commitment_proof(order.clone(), &reblind_witness)?;
+        let response_channel = enqueue_proof_job(job.clone(), &proof_manager_work_queue)?;
+        commitments_instances.push((*id, commitments_witness, job, response_channel));
     }
 
     // Await the proof of `VALID REBLIND`
-    let reblind_proof: ProofBundle =
-        reblind_response_channel.await.map_err(|_| ERR_PROVE_REBLIND_FAILED.to_string())?;
+    let reblind_proof: ProofBundle = await_proof_with_retry(
+        reblind_job,
+        reblind_response_channel,
+        &proof_manager_work_queue,
+        ERR_PROVE_REBLIND_FAILED,
+    )
+    .await?;
 
     // Await proofs of `VALID COMMITMENTS` for each order, store them in the state
-    for (order_id, commitments_witness, receiver) in commitments_instances.into_iter() {
+    for (order_id, commitments_witness, job, receiver) in commitments_instances.into_iter() {
         // Await a proof
-        let commitment_proof: ProofBundle =
-            receiver.await.map_err(|_| ERR_PROVE_COMMITMENTS_FAILED.to_string())?;
+        let commitment_proof: ProofBundle = await_proof_with_retry(
+            job,
+            receiver,
+            &proof_manager_work_queue,
+            ERR_PROVE_COMMITMENTS_FAILED,
+        )
+        .await?;
 
         link_and_store_proofs(
             &order_id,
@@ -403,3 +508,202 @@ pub(crate) fn enqueue_relayer_redeem_job(note: Note, state: &State) -> Result<()
 
     state.append_task(descriptor.into()).map_err(|e| e.to_string()).map(|_| ())
 }
+
+#[cfg(test)]
+mod test {
+    use std::{
+        iter,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use arbitrum_client::{
+        errors::ArbitrumClientError, mock::MockDarkpoolClient, traits::DarkpoolClient,
+    };
+    use circuit_types::traits::BaseType;
+    use circuits::zk_circuits::valid_reblind::{SizedValidReblindWitness, ValidReblindStatement};
+    use common::types::proof_bundles::{
+        mocks::{
+            dummy_link_hint, dummy_link_proof, dummy_proof, dummy_valid_match_settle_bundle,
+            dummy_validity_proof_bundle,
+        },
+        MatchBundle, ProofBundle,
+    };
+    use constants::Scalar;
+    use ethers::types::TxHash;
+    use job_types::proof_manager::{
+        new_proof_manager_queue, ProofJob, ProofJobError, ProofManagerQueue,
+    };
+
+    use super::{
+        await_confirmations, await_proof_with_retry, enqueue_proof_job, ERR_CONFIRMATION_TIMEOUT,
+        ERR_TX_REORGED, MAX_PROOF_ATTEMPTS,
+    };
+
+    /// The error message used for proofs in the retry tests
+    const ERR_TEST_PROOF_FAILED: &str = "test proof failed";
+
+    /// Start a flaky mock proof manager that fails the first `n_failures` jobs
+    /// and returns a dummy proof for the rest
+    ///
+    /// A failed job's response channel is dropped, or answered with a
+    /// permanent error if `permanent` is set. Returns the queue and a counter
+    /// of jobs received
+    fn flaky_proof_queue(n_failures: u64, permanent: bool) -> (ProofManagerQueue, Arc<AtomicU64>) {
+        let (queue, receiver) = new_proof_manager_queue();
+        let n_jobs = Arc::new(AtomicU64::new(0));
+
+        let n_jobs_clone = n_jobs.clone();
+        std::thread::spawn(move || {
+            while let Ok(job) = receiver.recv() {
+                if n_jobs_clone.fetch_add(1, Ordering::Relaxed) < n_failures {
+                    if permanent {
+                        let err = ProofJobError::permanent(ERR_TEST_PROOF_FAILED);
+                        let _ = job.response_channel.send(Err(err));
+                    }
+                    continue;
+                }
+
+                let bundle = ProofBundle::new_valid_reblind(
+                    ValidReblindStatement::from_scalars(&mut iter::repeat(Scalar::one())),
+                    dummy_proof(),
+                    dummy_link_hint(),
+                );
+                let _ = job.response_channel.send(Ok(bundle));
+            }
+        });
+
+        (queue, n_jobs)
+    }
+
+    /// Enqueue a dummy `VALID REBLIND` job and await it with retries
+    async fn prove_dummy_job(queue: &ProofManagerQueue) -> Result<ProofBundle, String> {
+        let mut scalars = iter::repeat(Scalar::one());
+        let job = ProofJob::ValidReblind {
+            witness: SizedValidReblindWitness::from_scalars(&mut scalars),
+            statement: ValidReblindStatement::from_scalars(&mut scalars),
+        };
+
+        let receiver = enqueue_proof_job(job.clone(), queue)?;
+        await_proof_with_retry(job, receiver, queue, ERR_TEST_PROOF_FAILED).await
+    }
+
+    /// The timeout on confirmations used in tests
+    const CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Submit a match to a mock chain at `start_block` that includes it in
+    /// `tx_block`, returning the client and the settlement's hash
+    async fn submit_mock_match(start_block: u64, tx_block: u64) -> (MockDarkpoolClient, TxHash) {
+        let client = MockDarkpoolClient::new();
+        client.set_current_block(start_block);
+        client.set_match_block(tx_block);
+
+        let proofs = dummy_validity_proof_bundle();
+        let match_bundle = MatchBundle {
+            match_proof: dummy_valid_match_settle_bundle().into(),
+            commitments_link0: dummy_link_proof(),
+            commitments_link1: dummy_link_proof(),
+        };
+        let receipt = client.process_match_settle(&proofs, &proofs, &match_bundle).await.unwrap();
+        (client, receipt.transaction_hash)
+    }
+
+    /// Await confirmations against a mock chain that advances one block per
+    /// poll, returning the block number the chain advanced to
+    async fn await_with_advancing_chain(start_block: u64, tx_block: u64, depth: u64) -> u64 {
+        let (client, tx_hash) = submit_mock_match(start_block, tx_block).await;
+        let poll_interval = Duration::from_millis(1);
+        await_confirmations(&client, tx_hash, tx_block, depth, poll_interval, CONFIRMATION_TIMEOUT)
+            .await
+            .unwrap();
+
+        client.current_block().await.unwrap()
+    }
+
+    /// Tests that a single confirmation is satisfied by the including block
+    #[tokio::test]
+    async fn test_await_single_confirmation() {
+        assert_eq!(await_with_advancing_chain(10, 10, 1).await, 11);
+        assert_eq!(await_with_advancing_chain(10, 10, 0).await, 11);
+    }
+
+    /// Tests awaiting several confirmations as the chain advances
+    #[tokio::test]
+    async fn test_await_multiple_confirmations() {
+        // Blocks 10 through 14 are polled before the fifth confirmation lands
+        assert_eq!(await_with_advancing_chain(10, 10, 5).await, 15);
+
+        // The chain is already past the target block
+        assert_eq!(await_with_advancing_chain(20, 10, 5).await, 21);
+    }
+
+    /// Tests that a transaction reorged out of its block fails the wait once
+    /// the confirmation depth is reached
+    #[tokio::test]
+    async fn test_await_confirmations_reorg() {
+        let (client, tx_hash) = submit_mock_match(10, 10).await;
+        client.reorg();
+
+        let poll_interval = Duration::from_millis(1);
+        let res =
+            await_confirmations(&client, tx_hash, 10, 5, poll_interval, CONFIRMATION_TIMEOUT).await;
+        assert_eq!(res, Err(ERR_TX_REORGED.to_string()));
+    }
+
+    /// Tests that the wait fails if the chain does not reach the confirmation
+    /// depth in time
+    #[tokio::test]
+    async fn test_await_confirmations_timeout() {
+        let (client, tx_hash) = submit_mock_match(0, 1_000_000).await;
+
+        let poll_interval = Duration::from_millis(1);
+        let timeout = Duration::from_millis(20);
+        let res = await_confirmations(&client, tx_hash, 1_000_000, 5, poll_interval, timeout).await;
+        assert_eq!(res, Err(ERR_CONFIRMATION_TIMEOUT.to_string()));
+    }
+
+    /// Tests that an error fetching the block number is propagated
+    #[tokio::test]
+    async fn test_await_confirmations_error() {
+        let (client, tx_hash) = submit_mock_match(10, 10).await;
+        client.fail_next(ArbitrumClientError::Rpc("rpc error".to_string()));
+
+        let poll_interval = Duration::from_millis(1);
+        let res =
+            await_confirmations(&client, tx_hash, 10, 5, poll_interval, CONFIRMATION_TIMEOUT).await;
+        assert!(res.is_err());
+    }
+
+    /// Tests that a proof job dropped by the proof manager is retried
+    #[tokio::test]
+    async fn test_proof_retry_succeeds() {
+        let (queue, n_jobs) =
+            flaky_proof_queue(1 /* n_failures */, false /* permanent */);
+        prove_dummy_job(&queue).await.unwrap();
+        assert_eq!(n_jobs.load(Ordering::Relaxed), 2);
+    }
+
+    /// Tests that a proof job is abandoned after the maximum number of attempts
+    #[tokio::test]
+    async fn test_proof_retry_exhausted() {
+        let (queue, n_jobs) =
+            flaky_proof_queue(u64::MAX /* n_failures */, false /* permanent */);
+        let err = prove_dummy_job(&queue).await.unwrap_err();
+
+        assert_eq!(err, ERR_TEST_PROOF_FAILED);
+        assert_eq!(n_jobs.load(Ordering::Relaxed), MAX_PROOF_ATTEMPTS as u64);
+    }
+
+    /// Tests that a proof job failing with a permanent error is not retried
+    #[tokio::test]
+    async fn test_proof_permanent_failure() {
+        let (queue, n_jobs) = flaky_proof_queue(1 /* n_failures */, true /* permanent */);
+        let err = prove_dummy_job(&queue).await.unwrap_err();
+
+        assert_eq!(err, ERR_TEST_PROOF_FAILED);
+        assert_eq!(n_jobs.load(Ordering::Relaxed), 1);
+    }
+}
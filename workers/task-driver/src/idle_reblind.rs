@@ -0,0 +1,197 @@
+//! Periodically reblinds wallets that have rested unchanged, rotating their
+//! on-chain commitment
+//!
+//! A wallet that is never updated keeps the same commitment and public shares
+//! on-chain indefinitely. The reblinder tracks the blinder of each wallet and,
+//! once a wallet's blinder has not changed for the configured interval, submits
+//! an update that reblinds the wallet without changing its contents. The update
+//! re-proves `VALID REBLIND` for the wallet's orders against the new shares
+//!
+//! The update must be authorized by the wallet's root key, so only wallets for
+//! which the relayer holds `sk_root` are reblinded
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use common::types::{
+    tasks::UpdateWalletTaskDescriptor,
+    wallet::{Wallet, WalletIdentifier},
+};
+use constants::Scalar;
+use state::State;
+use tokio::runtime::Handle;
+use tracing::{error, info};
+
+/// The name of the sweep, used when checking leadership
+const IDLE_REBLIND_SWEEP: &str = "idle reblind";
+
+/// Reblinds wallets whose blinder has not changed for a configured interval
+pub struct IdleReblinder {
+    /// A handle on the global state
+    state: State,
+    /// The interval after which an unchanged wallet is reblinded
+    idle_interval: Duration,
+    /// The blinder of each wallet and the time it was first observed
+    last_seen: HashMap<WalletIdentifier, (Scalar, Instant)>,
+}
+
+impl IdleReblinder {
+    /// Constructor
+    pub fn new(state: State, idle_interval: Duration) -> Self {
+        Self { state, idle_interval, last_seen: HashMap::new() }
+    }
+
+    /// Spawn the reblinding loop onto the given runtime
+    ///
+    /// The loop checks for idle wallets once per interval, so a wallet is
+    /// reblinded between one and two intervals after it was last changed
+    pub fn spawn(mut self, runtime: &Handle) {
+        runtime.spawn(async move {
+            loop {
+                tokio::time::sleep(self.idle_interval).await;
+                if let Err(e) = self.reblind_idle_wallets(Instant::now()).await {
+                    error!("error reblinding idle wallets: {e}");
+                }
+            }
+        });
+    }
+
+    /// Enqueue a reblinding update for each wallet idle as of `now`
+    ///
+    /// Runs only on the raft leader, and skips wallets with queued tasks or a
+    /// paused task queue. Returns the IDs of the wallets being reblinded
+    pub async fn reblind_idle_wallets(
+        &mut self,
+        now: Instant,
+    ) -> Result<Vec<WalletIdentifier>, String> {
+        if !self.state.leader_guard().should_run(IDLE_REBLIND_SWEEP) {
+            return Ok(vec![]);
+        }
+
+        let wallets = self.state.get_all_wallets()?;
+        self.last_seen.retain(|id, _| wallets.iter().any(|w| w.wallet_id == *id));
+
+        let mut reblinded = Vec::new();
+        for wallet in wallets.into_iter() {
+            if !self.is_idle(&wallet, now) || self.is_locked(&wallet.wallet_id)? {
+                continue;
+            }
+
+            if wallet.key_chain.secret_keys.sk_root.is_none() {
+                continue;
+            }
+
+            let id = wallet.wallet_id;
+            let new_blinder = self.enqueue_reblind(wallet).await?;
+            self.last_seen.insert(id, (new_blinder, now));
+            reblinded.push(id);
+        }
+
+        Ok(reblinded)
+    }
+
+    // -----------
+    // | Helpers |
+    // -----------
+
+    /// Whether the wallet's blinder has been unchanged for the idle interval,
+    /// recording the blinder if it has changed
+    fn is_idle(&mut self, wallet: &Wallet, now: Instant) -> bool {
+        match self.last_seen.get(&wallet.wallet_id) {
+            Some((blinder, since)) if *blinder == wallet.blinder => {
+                now.duration_since(*since) >= self.idle_interval
+            },
+            _ => {
+                self.last_seen.insert(wallet.wallet_id, (wallet.blinder, now));
+                false
+            },
+        }
+    }
+
+    /// Whether the wallet has queued tasks or a paused task queue
+    fn is_locked(&self, wallet_id: &WalletIdentifier) -> Result<bool, String> {
+        let queued = self.state.get_task_queue_len(wallet_id)?;
+        let paused = self.state.is_task_queue_paused(wallet_id)?;
+        Ok(queued > 0 || paused)
+    }
+
+    /// Enqueue an update that reblinds the wallet, returning the new blinder
+    async fn enqueue_reblind(&self, wallet: Wallet) -> Result<Scalar, String> {
+        let mut new_wallet = wallet.clone();
+        new_wallet.reblind_wallet();
+        let new_blinder = new_wallet.blinder;
+
+        let commitment = new_wallet.get_wallet_share_commitment();
+        let sig = wallet.sign_commitment(commitment)?.to_vec();
+
+        info!("reblinding idle wallet {}", wallet.wallet_id);
+        let task = UpdateWalletTaskDescriptor::new(
+            None, // transfer
+            wallet, new_wallet, sig, None,
+        )?;
+        let (_task_id, waiter) = self.state.append_task(task.into())?;
+        waiter.await?;
+
+        Ok(new_blinder)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use circuit_types::balance::Balance;
+    use common::types::{
+        tasks::TaskDescriptor,
+        wallet::OrderIdentifier,
+        wallet_mocks::{mock_empty_wallet, mock_order},
+    };
+    use num_bigint::BigUint;
+    use state::test_helpers::mock_state;
+
+    use super::IdleReblinder;
+
+    /// The idle interval used in tests
+    const IDLE_INTERVAL: Duration = Duration::from_secs(60);
+
+    /// Tests that an idle wallet is reblinded after the interval, with its
+    /// orders and balances preserved
+    #[tokio::test]
+    async fn test_idle_wallet_reblinded() {
+        let state = mock_state();
+        let mut wallet = mock_empty_wallet();
+        wallet.add_order(OrderIdentifier::new_v4(), mock_order()).unwrap();
+        let balance = Balance::new_from_mint_and_amount(BigUint::from(1u8), 100);
+        wallet.add_balance(balance).unwrap();
+        wallet.reblind_wallet();
+        state.new_wallet(wallet.clone()).unwrap().await.unwrap();
+
+        // The first sweep observes the wallet, and it is not yet idle
+        let mut reblinder = IdleReblinder::new(state.clone(), IDLE_INTERVAL);
+        let start = Instant::now();
+        assert!(reblinder.reblind_idle_wallets(start).await.unwrap().is_empty());
+        let before_interval = start + IDLE_INTERVAL / 2;
+        assert!(reblinder.reblind_idle_wallets(before_interval).await.unwrap().is_empty());
+
+        // Once the interval elapses the wallet is reblinded
+        let after_interval = start + IDLE_INTERVAL;
+        let reblinded = reblinder.reblind_idle_wallets(after_interval).await.unwrap();
+        assert_eq!(reblinded, vec![wallet.wallet_id]);
+
+        let tasks = state.get_queued_tasks(&wallet.wallet_id).unwrap();
+        assert_eq!(tasks.len(), 1);
+        let desc = match &tasks[0].descriptor {
+            TaskDescriptor::UpdateWallet(desc) => desc,
+            _ => panic!("expected a wallet update"),
+        };
+        assert_ne!(desc.new_wallet.blinder, wallet.blinder);
+        assert_eq!(desc.new_wallet.orders, wallet.orders);
+        assert_eq!(desc.new_wallet.balances, wallet.balances);
+
+        // The wallet is not reblinded again while its update is queued
+        let later = after_interval + IDLE_INTERVAL * 2;
+        assert!(reblinder.reblind_idle_wallets(later).await.unwrap().is_empty());
+    }
+}
@@ -17,7 +17,10 @@
 pub mod driver;
 pub mod error;
 mod helpers;
+pub mod idle_reblind;
+pub mod order_expiry;
 mod running_task;
+pub mod settlement_batcher;
 pub mod tasks;
 pub mod traits;
 pub mod worker;
@@ -40,5 +43,5 @@ pub async fn await_task(
     let (rx, job) = new_task_notification(task_id);
     task_queue.send(job).unwrap();
 
-    rx.await.unwrap()
+    rx.await.unwrap().map(|_| ())
 }
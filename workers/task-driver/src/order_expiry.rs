@@ -0,0 +1,223 @@
+//! Periodically cancels locally managed orders that have outlived the
+//! configured order TTL
+//!
+//! An order's age is measured from the time it was received into the order
+//! book, which is unchanged by updates to the rest of its wallet. Expired
+//! orders are removed from their wallet in a single update, recorded with
+//! `CancellationReason::Expired`
+//!
+//! The update must be authorized by the wallet's root key, so only orders in
+//! wallets for which the relayer holds `sk_root` expire
+
+use std::time::Duration;
+
+use common::types::{
+    network_order::CancellationReason,
+    tasks::UpdateWalletTaskDescriptor,
+    wallet::{OrderIdentifier, Wallet, WalletIdentifier},
+};
+use state::State;
+use tokio::runtime::Handle;
+use tracing::{error, info};
+use util::get_current_time_millis;
+
+/// The name of the sweep, used when checking leadership
+const ORDER_EXPIRY_SWEEP: &str = "order expiry";
+/// The interval at which the expirer checks for expired orders
+const ORDER_EXPIRY_INTERVAL_MS: u64 = 5_000; // 5 seconds
+
+/// Cancels locally managed orders older than a configured TTL
+pub struct OrderExpirer {
+    /// A handle on the global state
+    state: State,
+    /// The time in milliseconds after it is received that an order expires
+    ttl_ms: u64,
+}
+
+impl OrderExpirer {
+    /// Constructor
+    pub fn new(state: State, ttl_ms: u64) -> Self {
+        Self { state, ttl_ms }
+    }
+
+    /// Spawn the expiry loop onto the given runtime
+    ///
+    /// The loop checks for expired orders every few seconds, or once per TTL if
+    /// shorter, so an order is cancelled at most one check interval after it
+    /// expires
+    pub fn spawn(self, runtime: &Handle) {
+        let interval = Duration::from_millis(self.ttl_ms.min(ORDER_EXPIRY_INTERVAL_MS));
+        runtime.spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let now_ms = get_current_time_millis() as u64;
+                if let Err(e) = self.cancel_expired_orders(now_ms).await {
+                    error!("error cancelling expired orders: {e}");
+                }
+            }
+        });
+    }
+
+    /// Enqueue an update cancelling the expired orders of each wallet, as of
+    /// `now_ms`
+    ///
+    /// Runs only on the raft leader, and skips wallets with queued tasks or a
+    /// paused task queue. Returns the IDs of the cancelled orders
+    pub async fn cancel_expired_orders(&self, now_ms: u64) -> Result<Vec<OrderIdentifier>, String> {
+        if !self.state.leader_guard().should_run(ORDER_EXPIRY_SWEEP) {
+            return Ok(vec![]);
+        }
+
+        let cutoff = now_ms.saturating_sub(self.ttl_ms);
+        let mut cancelled = Vec::new();
+        for wallet in self.state.get_all_wallets()?.into_iter() {
+            if wallet.key_chain.secret_keys.sk_root.is_none()
+                || self.is_locked(&wallet.wallet_id)?
+            {
+                continue;
+            }
+
+            let expired = self.expired_orders(&wallet, cutoff)?;
+            if expired.is_empty() {
+                continue;
+            }
+
+            self.enqueue_cancellation(wallet, &expired).await?;
+            cancelled.extend(expired);
+        }
+
+        Ok(cancelled)
+    }
+
+    // -----------
+    // | Helpers |
+    // -----------
+
+    /// The orders in the wallet received at or before `cutoff_ms`
+    fn expired_orders(
+        &self,
+        wallet: &Wallet,
+        cutoff_ms: u64,
+    ) -> Result<Vec<OrderIdentifier>, String> {
+        let ids = wallet.orders.keys().cloned().collect::<Vec<_>>();
+        let orders = self.state.get_orders_batch(&ids)?;
+        Ok(orders
+            .into_iter()
+            .flatten()
+            .filter(|o| o.received_by(cutoff_ms))
+            .map(|o| o.id)
+            .collect())
+    }
+
+    /// Whether the wallet has queued tasks or a paused task queue
+    fn is_locked(&self, wallet_id: &WalletIdentifier) -> Result<bool, String> {
+        let queued = self.state.get_task_queue_len(wallet_id)?;
+        let paused = self.state.is_task_queue_paused(wallet_id)?;
+        Ok(queued > 0 || paused)
+    }
+
+    /// Enqueue an update that removes the given orders from the wallet
+    async fn enqueue_cancellation(
+        &self,
+        wallet: Wallet,
+        expired: &[OrderIdentifier],
+    ) -> Result<(), String> {
+        let mut new_wallet = wallet.clone();
+        for id in expired.iter() {
+            new_wallet.orders.remove(id);
+        }
+        new_wallet.reblind_wallet();
+
+        let commitment = new_wallet.get_wallet_share_commitment();
+        let sig = wallet.sign_commitment(commitment)?.to_vec();
+
+        info!("cancelling {} expired orders in wallet {}", expired.len(), wallet.wallet_id);
+        let task = UpdateWalletTaskDescriptor::new(
+            None, // transfer
+            wallet, new_wallet, sig, None,
+        )?
+        .with_cancelled_orders(expired.iter().copied(), CancellationReason::Expired);
+        let (_task_id, waiter) = self.state.append_task(task.into())?;
+        waiter.await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use common::types::{
+        network_order::CancellationReason,
+        tasks::TaskDescriptor,
+        wallet::OrderIdentifier,
+        wallet_mocks::{mock_empty_wallet, mock_order},
+    };
+    use state::{test_helpers::mock_state, State};
+
+    use super::OrderExpirer;
+
+    /// The order TTL used in tests
+    const TTL_MS: u64 = 60_000;
+
+    /// Add a wallet holding a single order to the state, returning the order's
+    /// ID and the time in milliseconds by which it was received
+    async fn add_wallet_with_order(state: &State, hold_root_key: bool) -> (OrderIdentifier, u64) {
+        let mut wallet = mock_empty_wallet();
+        if !hold_root_key {
+            wallet.key_chain.secret_keys.sk_root = None;
+        }
+        state.new_wallet(wallet.clone()).unwrap().await.unwrap();
+
+        let id = OrderIdentifier::new_v4();
+        wallet.add_order(id, mock_order()).unwrap();
+        wallet.reblind_wallet();
+        state.update_wallet(wallet).unwrap().await.unwrap();
+
+        let order = state.get_order(&id).unwrap().unwrap();
+        (id, order.timestamp * 1000 + 999)
+    }
+
+    /// Tests that an order is cancelled once it outlives the TTL, and the
+    /// cancellation is recorded as an expiry
+    #[tokio::test]
+    async fn test_expired_order_cancelled() {
+        let state = mock_state();
+        let (id, received_ms) = add_wallet_with_order(&state, true /* hold_root_key */).await;
+        let wallet_id = state.get_wallet_for_order(&id).unwrap().unwrap();
+
+        // The order has not yet expired
+        let expirer = OrderExpirer::new(state.clone(), TTL_MS);
+        let cancelled = expirer.cancel_expired_orders(received_ms + TTL_MS - 1).await.unwrap();
+        assert!(cancelled.is_empty());
+        assert!(state.get_queued_tasks(&wallet_id).unwrap().is_empty());
+
+        // Once the TTL elapses the order is cancelled
+        let cancelled = expirer.cancel_expired_orders(received_ms + TTL_MS).await.unwrap();
+        assert_eq!(cancelled, vec![id]);
+
+        let tasks = state.get_queued_tasks(&wallet_id).unwrap();
+        assert_eq!(tasks.len(), 1);
+        let desc = match &tasks[0].descriptor {
+            TaskDescriptor::UpdateWallet(desc) => desc,
+            _ => panic!("expected a wallet update"),
+        };
+        assert!(!desc.new_wallet.orders.contains_key(&id));
+        assert_eq!(desc.cancelled_orders, vec![(id, CancellationReason::Expired)]);
+
+        // The order is not cancelled again while its update is queued
+        let later = received_ms + TTL_MS * 2;
+        assert!(expirer.cancel_expired_orders(later).await.unwrap().is_empty());
+    }
+
+    /// Tests that orders in a wallet whose root key the relayer does not hold
+    /// do not expire
+    #[tokio::test]
+    async fn test_unmanaged_key_not_expired() {
+        let state = mock_state();
+        let (_id, received_ms) = add_wallet_with_order(&state, false /* hold_root_key */).await;
+
+        let expirer = OrderExpirer::new(state, TTL_MS);
+        let cancelled = expirer.cancel_expired_orders(received_ms + TTL_MS).await.unwrap();
+        assert!(cancelled.is_empty());
+    }
+}
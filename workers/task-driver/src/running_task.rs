@@ -1,11 +1,17 @@
 //! Encapsulates the running task's bookkeeping structure to simplify the driver
 //! logic
 
-use common::types::tasks::TaskIdentifier;
+use async_trait::async_trait;
+use common::types::{
+    tasks::{RunningTaskInfo, TaskIdentifier, TaskOutput},
+    wallet::WalletIdentifier,
+};
 use external_api::bus_message::{task_topic_name, SystemBusMessage};
+use job_types::task_driver::TaskRegistry;
 use state::{error::StateError, State};
 use system_bus::SystemBus;
 use tracing::{error, info};
+use util::get_current_time_millis;
 
 use crate::{
     driver::StateWrapper,
@@ -17,6 +23,27 @@ use crate::{
 // | Running Task |
 // ----------------
 
+/// The interface through which the driver steps a task and retries its
+/// failures
+#[async_trait]
+pub trait DrivenTask: Send {
+    /// The ID of the underlying task
+    fn id(&self) -> TaskIdentifier;
+    /// Whether the underlying task completed
+    fn completed(&self) -> bool;
+    /// Returns the state of the underlying task
+    fn state(&self) -> StateWrapper;
+    /// Step the underlying task, returns whether the driver should continue or
+    /// abort. `Ok(true)` means successful step, `Ok(false)` means that the
+    /// task step failed and should be retried, an error should be aborted
+    async fn step(&mut self) -> Result<bool, TaskDriverError>;
+    /// Discard the progress of the underlying task and return it to its
+    /// initial state
+    ///
+    /// The driver must only restart a task that has not committed
+    async fn restart(&mut self) -> Result<(), TaskDriverError>;
+}
+
 /// The container type for a task running in the driver
 ///
 /// Used to simplify driver logic
@@ -27,69 +54,66 @@ pub struct RunnableTask<T: Task> {
     task_id: TaskIdentifier,
     /// The underlying task
     task: T,
+    /// The descriptor the task was constructed from, used to restart it
+    descriptor: T::Descriptor,
+    /// The context the task was constructed with, used to restart it
+    ctx: TaskContext,
     /// A handle to the relayer-global state
     state: State,
     /// A sender to the system bus for state updates
     bus: SystemBus<SystemBusMessage>,
+    /// The registry of running tasks, the task is listed here until cleanup
+    registry: TaskRegistry,
 }
 
 impl<T: Task> RunnableTask<T> {
-    /// Creates a new running task from the given task and state
+    /// Creates a new running task from the given task, along with the
+    /// descriptor and context it was constructed from
+    ///
+    /// The task is added to the registry of running tasks
     pub fn new(
         preemptive: bool,
         task_id: TaskIdentifier,
+        wallet_ids: Vec<WalletIdentifier>,
         task: T,
-        state: State,
-        bus: SystemBus<SystemBusMessage>,
+        descriptor: T::Descriptor,
+        ctx: TaskContext,
     ) -> Self {
-        Self { preemptive, task_id, task, state, bus }
+        let state = ctx.state.clone();
+        let bus = ctx.bus.clone();
+        let registry = ctx.task_registry.clone();
+        let info = RunningTaskInfo {
+            id: task_id,
+            name: task.name(),
+            state: task.state().to_string(),
+            wallet_ids,
+            start_time: get_current_time_millis() as u64,
+        };
+        registry.write().unwrap().insert(task_id, info);
+
+        Self { preemptive, task_id, task, descriptor, ctx, state, bus, registry }
     }
 
     /// Create a runnable from the given descriptor and context
     pub async fn from_descriptor(
         preemptive: bool,
         id: TaskIdentifier,
+        wallet_ids: Vec<WalletIdentifier>,
         descriptor: T::Descriptor,
         ctx: TaskContext,
     ) -> Result<Self, TaskDriverError> {
-        let state = ctx.state.clone();
-        let bus = ctx.bus.clone();
-        let task = T::new(descriptor, ctx).await?;
-
-        Ok(Self::new(preemptive, id, task, state, bus))
-    }
-
-    /// The ID of the underlying task
-    pub fn id(&self) -> TaskIdentifier {
-        self.task_id
-    }
-
-    /// Whether the underlying task completed
-    pub fn completed(&self) -> bool {
-        self.task.completed()
+        let task = T::new(descriptor.clone(), ctx.clone()).await?;
+        Ok(Self::new(preemptive, id, wallet_ids, task, descriptor, ctx))
     }
 
-    /// Returns the state of the underlying task
-    pub fn state(&self) -> StateWrapper {
-        self.task.state().into()
+    /// The name of the underlying task
+    pub fn name(&self) -> String {
+        self.task.name()
     }
 
-    /// Step the underlying task, returns whether the driver should continue or
-    /// abort. `Ok(true)` means successful step, `Ok(false)` means that the task
-    /// step failed and should be retried, an error should be aborted
-    ///
-    /// This includes a state transition in the consensus engine, if this method
-    /// returns an error the driver should abort the task
-    pub async fn step(&mut self) -> Result<bool, TaskDriverError> {
-        // Handle a failed step
-        if let Err(e) = self.task.step().await {
-            error!("error executing task step: {e}");
-            return if e.retryable() { Ok(false) } else { Err(e.into()) };
-        };
-
-        // Successful step, attempt to transition the state
-        self.transition_state().await?;
-        Ok(true)
+    /// The output of the underlying task
+    pub fn output(&self) -> Option<TaskOutput> {
+        self.task.output()
     }
 
     /// Attempts to transition the state of the underlying task in the consensus
@@ -99,6 +123,9 @@ impl<T: Task> RunnableTask<T> {
         let name = self.task.name();
         let new_state = self.state();
         info!("task {name}({task_id:?}) transitioning to state {new_state}");
+        if let Some(info) = self.registry.write().unwrap().get_mut(&task_id) {
+            info.state = new_state.to_string();
+        }
 
         // Preemptive tasks need not update state in the consensus engine
         if self.preemptive {
@@ -128,6 +155,9 @@ impl<T: Task> RunnableTask<T> {
             error!("error cleaning up task: {e:?}");
         }
 
+        // Remove the task from the registry of running tasks
+        self.registry.write().unwrap().remove(&self.task_id);
+
         // Pop the task from the state
         // Preemptive tasks are not indexed, so no work needs to be done
         if !self.preemptive {
@@ -137,3 +167,40 @@ impl<T: Task> RunnableTask<T> {
         Ok(())
     }
 }
+
+#[async_trait]
+impl<T: Task> DrivenTask for RunnableTask<T> {
+    fn id(&self) -> TaskIdentifier {
+        self.task_id
+    }
+
+    fn completed(&self) -> bool {
+        self.task.completed()
+    }
+
+    fn state(&self) -> StateWrapper {
+        self.task.state().into()
+    }
+
+    /// Steps the task, this includes a state transition in the consensus
+    /// engine, if this method returns an error the driver should abort the task
+    async fn step(&mut self) -> Result<bool, TaskDriverError> {
+        // Handle a failed step
+        if let Err(e) = self.task.step().await {
+            error!("error executing task step: {e}");
+            return if e.retryable() { Ok(false) } else { Err(e.into()) };
+        };
+
+        // Successful step, attempt to transition the state
+        self.transition_state().await?;
+        Ok(true)
+    }
+
+    /// Reconstructs the task from its descriptor, transitioning the task back
+    /// to its initial state
+    async fn restart(&mut self) -> Result<(), TaskDriverError> {
+        self.task = T::new(self.descriptor.clone(), self.ctx.clone()).await?;
+        self.transition_state().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,191 @@
+//! Batches match settlements that become ready within a short window of one
+//! another, so that they may be submitted together
+//!
+//! Each settlement task submits its match to the batcher and awaits the
+//! receipt of the transaction that settled it. The batcher collects the
+//! matches submitted within the window of the first and hands them to the
+//! client as a single batch
+//!
+//! Clients that cannot settle a batch in a single transaction gain nothing from
+//! the window, so no batcher is built for them
+
+use std::time::Duration;
+
+use arbitrum_client::{
+    errors::ArbitrumClientError,
+    traits::{DarkpoolClient, MatchSettlement},
+};
+use ethers::types::TransactionReceipt;
+use tokio::{
+    runtime::Handle,
+    sync::{
+        mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+        oneshot::{self, Sender as OneshotSender},
+    },
+    time::{timeout_at, Instant},
+};
+use tracing::{info, warn};
+
+/// The error message emitted when the batcher's loop has stopped
+const ERR_BATCHER_STOPPED: &str = "settlement batcher stopped";
+
+/// The result of settling a match
+type SettlementResult = Result<TransactionReceipt, ArbitrumClientError>;
+
+/// A match awaiting settlement in a batch
+struct PendingSettlement {
+    /// The match to settle
+    settlement: MatchSettlement,
+    /// The channel on which to send the result of the settlement
+    response: OneshotSender<SettlementResult>,
+}
+
+/// A handle on the settlement batcher
+#[derive(Clone)]
+pub struct SettlementBatcher {
+    /// The queue of matches awaiting settlement
+    queue: UnboundedSender<PendingSettlement>,
+}
+
+impl SettlementBatcher {
+    /// Constructor, spawns the batching loop onto the given runtime
+    pub fn new<C: DarkpoolClient + 'static>(client: C, window: Duration, runtime: &Handle) -> Self {
+        let (queue, receiver) = unbounded_channel();
+        runtime.spawn(run_batch_loop(client, window, receiver));
+
+        Self { queue }
+    }
+
+    /// Build a batcher for the client if a window is configured and the client
+    /// settles batches in a single transaction, `None` otherwise
+    pub fn maybe_new<C: DarkpoolClient + 'static>(
+        client: C,
+        window: Option<Duration>,
+        runtime: &Handle,
+    ) -> Option<Self> {
+        let window = window?;
+        if !client.supports_batch_settlement() {
+            warn!("client does not support batch settlement, ignoring the batch window");
+            return None;
+        }
+
+        Some(Self::new(client, window, runtime))
+    }
+
+    /// Submit a match for settlement, returning the receipt of the transaction
+    /// that settled it
+    pub async fn submit(&self, settlement: MatchSettlement) -> SettlementResult {
+        let (response, receiver) = oneshot::channel();
+        self.queue.send(PendingSettlement { settlement, response }).map_err(|_| {
+            ArbitrumClientError::ContractInteraction(ERR_BATCHER_STOPPED.to_string())
+        })?;
+
+        receiver.await.map_err(|_| {
+            ArbitrumClientError::ContractInteraction(ERR_BATCHER_STOPPED.to_string())
+        })?
+    }
+}
+
+/// The batching loop, collects the matches submitted within the window of the
+/// first pending match and settles them in a single batch
+async fn run_batch_loop<C: DarkpoolClient>(
+    client: C,
+    window: Duration,
+    mut receiver: UnboundedReceiver<PendingSettlement>,
+) {
+    while let Some(first) = receiver.recv().await {
+        let mut batch = vec![first];
+        let deadline = Instant::now() + window;
+        while let Ok(Some(pending)) = timeout_at(deadline, receiver.recv()).await {
+            batch.push(pending);
+        }
+
+        info!("settling batch of {} matches", batch.len());
+        let (settlements, responses): (Vec<_>, Vec<_>) =
+            batch.into_iter().map(|pending| (pending.settlement, pending.response)).unzip();
+        let results = client.batch_process_match_settle(&settlements).await;
+        for (res, response) in results.into_iter().zip(responses) {
+            let _ = response.send(res);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use arbitrum_client::{mock::MockDarkpoolClient, traits::MatchSettlement};
+    use common::types::proof_bundles::{
+        mocks::{dummy_link_proof, dummy_valid_match_settle_bundle, dummy_validity_proof_bundle},
+        MatchBundle,
+    };
+    use futures::future::join_all;
+    use tokio::runtime::Handle;
+
+    use super::SettlementBatcher;
+
+    /// The batching window used in tests
+    const WINDOW: Duration = Duration::from_millis(100);
+
+    /// Build a dummy match settlement
+    fn dummy_settlement() -> MatchSettlement {
+        MatchSettlement {
+            party0_validity_proofs: dummy_validity_proof_bundle(),
+            party1_validity_proofs: dummy_validity_proof_bundle(),
+            match_bundle: MatchBundle {
+                match_proof: dummy_valid_match_settle_bundle().into(),
+                commitments_link0: dummy_link_proof(),
+                commitments_link1: dummy_link_proof(),
+            },
+        }
+    }
+
+    /// Tests that matches submitted within the window are settled in a single
+    /// batch, and that each receives the batch's receipt
+    #[tokio::test]
+    async fn test_matches_batched_within_window() {
+        const N_MATCHES: usize = 3;
+        const BLOCK: u64 = 7;
+
+        let client = MockDarkpoolClient::new();
+        client.set_match_block(BLOCK);
+        let batcher = SettlementBatcher::new(client.clone(), WINDOW, &Handle::current());
+
+        let submissions = (0..N_MATCHES).map(|_| batcher.submit(dummy_settlement()));
+        for res in join_all(submissions).await {
+            let receipt = res.unwrap();
+            assert_eq!(receipt.block_number, Some(BLOCK.into()));
+        }
+
+        assert_eq!(client.n_batch_settlements(), 1);
+        assert_eq!(client.n_match_settlements(), N_MATCHES);
+    }
+
+    /// Tests that matches submitted in separate windows are settled in
+    /// separate batches
+    #[tokio::test]
+    async fn test_matches_outside_window_not_batched() {
+        let client = MockDarkpoolClient::new();
+        let batcher = SettlementBatcher::new(client.clone(), WINDOW, &Handle::current());
+
+        batcher.submit(dummy_settlement()).await.unwrap();
+        batcher.submit(dummy_settlement()).await.unwrap();
+
+        assert_eq!(client.n_batch_settlements(), 2);
+        assert_eq!(client.n_match_settlements(), 2);
+    }
+
+    /// Tests that no batcher is built for a client that cannot settle a batch
+    /// in a single transaction
+    #[tokio::test]
+    async fn test_batcher_bypassed_without_batch_support() {
+        let client = MockDarkpoolClient::new();
+        let batcher =
+            SettlementBatcher::maybe_new(client.clone(), Some(WINDOW), &Handle::current());
+        assert!(batcher.is_some());
+
+        client.disable_batch_settlement();
+        let batcher = SettlementBatcher::maybe_new(client, Some(WINDOW), &Handle::current());
+        assert!(batcher.is_none());
+    }
+}
@@ -27,8 +27,8 @@ use state::State;
 use tracing::instrument;
 
 use crate::driver::StateWrapper;
-use crate::helpers::enqueue_proof_job;
-use crate::traits::{Task, TaskContext, TaskError, TaskState};
+use crate::helpers::{await_proof, enqueue_proof_job};
+use crate::traits::{RetryPolicy, Task, TaskContext, TaskError, TaskState};
 
 use crate::helpers::find_merkle_path;
 
@@ -61,6 +61,10 @@ impl TaskState for NewWalletTaskState {
         NewWalletTaskState::SubmittingTx
     }
 
+    fn pre_commit_retry_policy() -> RetryPolicy {
+        RetryPolicy::Restart
+    }
+
     fn completed(&self) -> bool {
         matches!(self, NewWalletTaskState::Completed)
     }
@@ -243,8 +247,9 @@ impl NewWalletTask {
             .map_err(NewWalletTaskError::SendMessage)?;
 
         // Await the proof
-        let bundle =
-            proof_recv.await.map_err(|e| NewWalletTaskError::ProofGeneration(e.to_string()))?;
+        let bundle = await_proof(proof_recv)
+            .await
+            .map_err(|e| NewWalletTaskError::ProofGeneration(e.to_string()))?;
         self.proof_bundle = Some(bundle.proof.into());
         Ok(())
     }
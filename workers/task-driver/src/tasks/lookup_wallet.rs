@@ -6,10 +6,13 @@ use std::{
     fmt::{Display, Formatter, Result as FmtResult},
 };
 
-use arbitrum_client::client::ArbitrumClient;
+use arbitrum_client::{
+    client::ArbitrumClient, errors::ArbitrumClientError, traits::DarkpoolClient,
+};
 use async_trait::async_trait;
 use circuit_types::{traits::BaseType, SizedWalletShare};
 use common::types::{
+    merkle::MerkleAuthenticationPath,
     tasks::LookupWalletTaskDescriptor,
     wallet::{KeyChain, Wallet, WalletIdentifier},
 };
@@ -27,10 +30,14 @@ use crate::{
     traits::{Task, TaskContext, TaskError, TaskState},
 };
 
-use crate::helpers::{find_merkle_path, update_wallet_validity_proofs};
+use crate::helpers::update_wallet_validity_proofs;
 
 /// The error thrown when the wallet cannot be found in tx history
 pub const ERR_WALLET_NOT_FOUND: &str = "wallet not found in wallet_last_updated map";
+/// The error thrown when the recovered wallet's commitment is not in the
+/// contract's Merkle tree
+pub const ERR_COMMITMENT_NOT_FOUND: &str =
+    "recovered wallet commitment not found in the contract Merkle tree";
 /// The task name for the lookup wallet task
 const LOOKUP_WALLET_TASK_NAME: &str = "lookup-wallet";
 
@@ -45,6 +52,9 @@ pub enum LookupWalletTaskState {
     Pending,
     /// The task is finding the wallet in contract storage
     FindingWallet,
+    /// The task is verifying that the recovered wallet is committed to in the
+    /// contract's Merkle tree, then indexing it
+    VerifyingWallet,
     /// The task is creating validity proofs for the orders in the wallet
     CreatingValidityProofs,
     /// The task is completed
@@ -82,6 +92,8 @@ impl From<LookupWalletTaskState> for StateWrapper {
 pub enum LookupWalletTaskError {
     /// Wallet was not found in contract storage
     NotFound(String),
+    /// The recovered wallet's commitment is not in the contract's Merkle tree
+    CommitmentNotFound(String),
     /// Error generating a proof of `VALID COMMITMENTS`
     ProofGeneration(String),
     /// Error interacting with the arbitrum client
@@ -175,6 +187,11 @@ impl Task for LookupWalletTask {
 
             LookupWalletTaskState::FindingWallet => {
                 self.find_wallet().await?;
+                self.task_state = LookupWalletTaskState::VerifyingWallet;
+            },
+
+            LookupWalletTaskState::VerifyingWallet => {
+                self.verify_wallet().await?;
                 self.task_state = LookupWalletTaskState::CreatingValidityProofs;
             },
 
@@ -209,8 +226,8 @@ impl LookupWalletTask {
     // | Task Steps |
     // --------------
 
-    /// Find the wallet in the contract storage and create an opening for the
-    /// wallet
+    /// Find the wallet in the contract storage and reconstruct it from its
+    /// shares
     async fn find_wallet(&mut self) -> Result<(), LookupWalletTaskError> {
         // Lookup the public and private shares from contract calldata
         let (blinded_public_shares, private_shares) = self.find_wallet_shares().await?;
@@ -220,7 +237,7 @@ impl LookupWalletTask {
         let recovered_wallet = unblinded_public_shares + private_shares.clone();
 
         // Construct a wallet from the recovered shares
-        let mut wallet = Wallet {
+        let wallet = Wallet {
             wallet_id: self.wallet_id,
             orders: recovered_wallet.orders.iter().cloned().map(|o| (Uuid::new_v4(), o)).collect(),
             balances: recovered_wallet
@@ -232,25 +249,34 @@ impl LookupWalletTask {
             key_chain: KeyChain {
                 public_keys: recovered_wallet.keys,
                 secret_keys: self.key_chain.secret_keys.clone(),
+                scoped_keys: self.key_chain.scoped_keys.clone(),
             },
             match_fee: recovered_wallet.match_fee,
             managing_cluster: recovered_wallet.managing_cluster,
             blinder: recovered_wallet.blinder,
             private_shares,
             blinded_public_shares,
-            merkle_proof: None, // constructed below
+            merkle_proof: None, // constructed when verifying the wallet
+            update_nonce: 0,
             merkle_staleness: Default::default(),
         };
+        self.wallet = Some(wallet);
 
-        // Find the authentication path for the wallet
-        let authentication_path = find_merkle_path(&wallet, &self.arbitrum_client)
-            .await
-            .map_err(|e| LookupWalletTaskError::Arbitrum(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Verify that the recovered wallet is committed to on-chain, then index
+    /// the wallet in the global state with its Merkle opening
+    async fn verify_wallet(&mut self) -> Result<(), LookupWalletTaskError> {
+        let wallet = self
+            .wallet
+            .as_mut()
+            .expect("wallet should be present when VerifyingWallet state is reached");
+
+        let authentication_path = verify_wallet_commitment(wallet, &self.arbitrum_client).await?;
         wallet.merkle_proof = Some(authentication_path);
 
         self.global_state.update_wallet(wallet.clone())?.await?;
-        self.wallet = Some(wallet);
-
         Ok(())
     }
 
@@ -351,3 +377,65 @@ impl LookupWalletTask {
         Ok((blinder_index, curr_blinder, curr_blinder_private_share))
     }
 }
+
+/// Verify that the commitment to a recovered wallet's shares is present in the
+/// contract's Merkle tree, returning the wallet's authentication path
+///
+/// A wallet whose commitment is not in the tree was reconstructed from the
+/// wrong seeds or keychain, and must not be indexed as the user's wallet
+async fn verify_wallet_commitment<C: DarkpoolClient>(
+    wallet: &Wallet,
+    client: &C,
+) -> Result<MerkleAuthenticationPath, LookupWalletTaskError> {
+    let commitment = wallet.get_wallet_share_commitment();
+    client.find_merkle_authentication_path(commitment).await.map_err(|err| match err {
+        ArbitrumClientError::CommitmentNotFound => {
+            LookupWalletTaskError::CommitmentNotFound(ERR_COMMITMENT_NOT_FOUND.to_string())
+        },
+        err => LookupWalletTaskError::Arbitrum(err.to_string()),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use arbitrum_client::mock::MockDarkpoolClient;
+    use common::types::{merkle::MerkleAuthenticationPath, wallet_mocks::mock_empty_wallet};
+
+    use super::{verify_wallet_commitment, LookupWalletTaskError};
+
+    /// Tests verifying a recovered wallet whose commitment is in the tree
+    #[tokio::test]
+    async fn test_verify_matching_commitment() {
+        let wallet = mock_empty_wallet();
+        let client = MockDarkpoolClient::new();
+        let path = MerkleAuthenticationPath {
+            value: wallet.get_wallet_share_commitment(),
+            leaf_index: 7u8.into(),
+            ..Default::default()
+        };
+        client.set_merkle_path(path.clone());
+
+        let res = verify_wallet_commitment(&wallet, &client).await.unwrap();
+        assert_eq!(res, path);
+    }
+
+    /// Tests that a recovered wallet whose commitment is not in the tree is
+    /// rejected
+    #[tokio::test]
+    async fn test_verify_non_matching_commitment() {
+        let wallet = mock_empty_wallet();
+        let client = MockDarkpoolClient::new();
+
+        // Index a different wallet's commitment
+        let mut other_wallet = wallet.clone();
+        other_wallet.reblind_wallet();
+        let path = MerkleAuthenticationPath {
+            value: other_wallet.get_wallet_share_commitment(),
+            ..Default::default()
+        };
+        client.set_merkle_path(path);
+
+        let res = verify_wallet_commitment(&wallet, &client).await;
+        assert!(matches!(res, Err(LookupWalletTaskError::CommitmentNotFound(_))));
+    }
+}
@@ -7,6 +7,7 @@ pub mod pay_relayer_fee;
 pub mod redeem_relayer_fee;
 pub mod settle_match;
 pub mod settle_match_internal;
+mod settlement_helpers;
 pub mod update_merkle_proof;
 pub mod update_wallet;
 
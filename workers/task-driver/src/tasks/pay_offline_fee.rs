@@ -28,10 +28,10 @@ use util::{arbitrum::get_protocol_encryption_key, err_str};
 use crate::{
     driver::StateWrapper,
     helpers::{
-        enqueue_proof_job, enqueue_relayer_redeem_job, find_merkle_path,
+        await_proof, enqueue_proof_job, enqueue_relayer_redeem_job, find_merkle_path,
         update_wallet_validity_proofs,
     },
-    traits::{Task, TaskContext, TaskError, TaskState},
+    traits::{RetryPolicy, Task, TaskContext, TaskError, TaskState},
 };
 
 use super::{ERR_BALANCE_MISSING, ERR_NO_MERKLE_PROOF, ERR_WALLET_MISSING};
@@ -65,6 +65,10 @@ impl TaskState for PayOfflineFeeTaskState {
         PayOfflineFeeTaskState::SubmittingPayment
     }
 
+    fn pre_commit_retry_policy() -> RetryPolicy {
+        RetryPolicy::Restart
+    }
+
     fn completed(&self) -> bool {
         matches!(self, PayOfflineFeeTaskState::Completed)
     }
@@ -247,7 +251,9 @@ impl PayOfflineFeeTask {
             .map_err(PayOfflineFeeTaskError::ProofGeneration)?;
 
         // Await the proof
-        let bundle = proof_recv.await.map_err(err_str!(PayOfflineFeeTaskError::ProofGeneration))?;
+        let bundle = await_proof(proof_recv)
+            .await
+            .map_err(err_str!(PayOfflineFeeTaskError::ProofGeneration))?;
         self.proof = Some(bundle.proof.into());
         Ok(())
     }
@@ -24,8 +24,10 @@ use tracing::instrument;
 use util::err_str;
 
 use crate::driver::StateWrapper;
-use crate::helpers::{enqueue_proof_job, find_merkle_path, update_wallet_validity_proofs};
-use crate::traits::{Task, TaskContext, TaskError, TaskState};
+use crate::helpers::{
+    await_proof, enqueue_proof_job, find_merkle_path, update_wallet_validity_proofs,
+};
+use crate::traits::{RetryPolicy, Task, TaskContext, TaskError, TaskState};
 
 use super::{ERR_BALANCE_MISSING, ERR_NO_MERKLE_PROOF, ERR_WALLET_MISSING};
 
@@ -58,6 +60,10 @@ impl TaskState for PayRelayerFeeTaskState {
         PayRelayerFeeTaskState::SubmittingPayment
     }
 
+    fn pre_commit_retry_policy() -> RetryPolicy {
+        RetryPolicy::Restart
+    }
+
     fn completed(&self) -> bool {
         matches!(self, PayRelayerFeeTaskState::Completed)
     }
@@ -243,7 +249,9 @@ impl PayRelayerFeeTask {
             .map_err(PayRelayerFeeTaskError::ProofGeneration)?;
 
         // Await the proof
-        let bundle = proof_recv.await.map_err(err_str!(PayRelayerFeeTaskError::ProofGeneration))?;
+        let bundle = await_proof(proof_recv)
+            .await
+            .map_err(err_str!(PayRelayerFeeTaskError::ProofGeneration))?;
         self.proof = Some(bundle.proof.into());
         Ok(())
     }
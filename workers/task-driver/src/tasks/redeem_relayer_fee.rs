@@ -23,9 +23,9 @@ use util::err_str;
 
 use crate::{
     driver::StateWrapper,
-    helpers::{enqueue_proof_job, find_merkle_path},
+    helpers::{await_proof, enqueue_proof_job, find_merkle_path},
     tasks::ERR_NO_MERKLE_PROOF,
-    traits::{Task, TaskContext, TaskError, TaskState},
+    traits::{RetryPolicy, Task, TaskContext, TaskError, TaskState},
 };
 
 use super::lookup_wallet::ERR_WALLET_NOT_FOUND;
@@ -59,6 +59,10 @@ impl TaskState for RedeemRelayerFeeTaskState {
         RedeemRelayerFeeTaskState::SubmittingRedemption
     }
 
+    fn pre_commit_retry_policy() -> RetryPolicy {
+        RetryPolicy::Restart
+    }
+
     fn completed(&self) -> bool {
         matches!(self, RedeemRelayerFeeTaskState::Completed)
     }
@@ -247,7 +251,8 @@ impl RedeemRelayerFeeTask {
             .map_err(err_str!(RedeemRelayerFeeError::ProofGeneration))?;
 
         // Await the proof
-        let bundle = proof.await.map_err(err_str!(RedeemRelayerFeeError::ProofGeneration))?;
+        let bundle =
+            await_proof(proof).await.map_err(err_str!(RedeemRelayerFeeError::ProofGeneration))?;
         self.proof = Some(bundle.proof.into());
 
         Ok(())
@@ -11,28 +11,35 @@
 use std::error::Error;
 use std::fmt::{Display, Formatter, Result as FmtResult};
 
-use arbitrum_client::client::ArbitrumClient;
+use arbitrum_client::{client::ArbitrumClient, errors::ArbitrumClientError};
 use ark_mpc::PARTY0;
 use async_trait::async_trait;
-use circuit_types::SizedWalletShare;
+use circuit_types::{Amount, SizedWalletShare};
 use common::types::proof_bundles::MatchBundle;
-use common::types::tasks::SettleMatchTaskDescriptor;
-use common::types::wallet::Wallet;
+use common::types::tasks::{SettleMatchTaskDescriptor, TaskOutput};
+use common::types::wallet::{OrderFill, OrderIdentifier, Wallet};
 use common::types::{
-    handshake::HandshakeState, proof_bundles::OrderValidityProofBundle, wallet::WalletIdentifier,
+    handshake::{ConnectionRole, HandshakeState},
+    proof_bundles::OrderValidityProofBundle,
+    wallet::WalletIdentifier,
 };
+use external_api::bus_message::SystemBusMessage;
 use job_types::network_manager::NetworkManagerQueue;
 use job_types::proof_manager::ProofManagerQueue;
+use job_types::task_driver::MatchGasLedger;
 use serde::Serialize;
 use state::error::StateError;
 use state::State;
+use system_bus::SystemBus;
 use tracing::instrument;
 
 use crate::driver::StateWrapper;
+use crate::settlement_batcher::SettlementBatcher;
 use crate::traits::{Task, TaskContext, TaskError, TaskState};
 
-use crate::helpers::{
-    enqueue_fee_settlement_tasks, find_merkle_path, update_wallet_validity_proofs,
+use super::settlement_helpers::{
+    find_opening, index_settled_wallet, nullify_matched_orders, publish_order_fill,
+    submit_match_and_record_gas, update_settled_wallets_proofs,
 };
 
 /// The error message the contract emits when a nullifier has been used
@@ -156,6 +163,9 @@ pub struct SettleMatchTask {
     pub party0_validity_proof: OrderValidityProofBundle,
     /// The validity proofs submitted by the second party
     pub party1_validity_proof: OrderValidityProofBundle,
+    /// The block in which the local relayer settled the match, `None` until
+    /// the match is submitted or if the counterparty submitted it
+    pub settlement_block: Option<u64>,
     /// The arbitrum client to use for submitting transactions
     pub arbitrum_client: ArbitrumClient,
     /// A sender to the network manager's work queue
@@ -164,6 +174,12 @@ pub struct SettleMatchTask {
     pub global_state: State,
     /// The work queue to add proof management jobs to
     pub proof_queue: ProofManagerQueue,
+    /// A handle on the system bus
+    pub bus: SystemBus<SystemBusMessage>,
+    /// The ledger in which to record the gas used by the settlement
+    pub match_gas_ledger: MatchGasLedger,
+    /// The batcher through which to submit the match, if batching is enabled
+    pub settlement_batcher: Option<SettlementBatcher>,
     /// The state of the task
     pub task_state: SettleMatchTaskState,
 }
@@ -189,10 +205,14 @@ impl Task for SettleMatchTask {
             match_bundle,
             party0_validity_proof,
             party1_validity_proof,
+            settlement_block: None,
             arbitrum_client: context.arbitrum_client,
             network_sender: context.network_queue,
             global_state: context.state,
             proof_queue: context.proof_queue,
+            bus: context.bus,
+            match_gas_ledger: context.match_gas_ledger,
+            settlement_batcher: context.settlement_batcher,
             task_state: SettleMatchTaskState::Pending,
         })
     }
@@ -240,6 +260,10 @@ impl Task for SettleMatchTask {
     fn state(&self) -> SettleMatchTaskState {
         self.task_state.clone()
     }
+
+    fn output(&self) -> Option<TaskOutput> {
+        Some(TaskOutput::SettleMatch { settlement_block: self.settlement_block })
+    }
 }
 
 // -----------------------
@@ -252,26 +276,41 @@ impl SettleMatchTask {
     // --------------
 
     /// Submit the match transaction to the contract
-    async fn submit_match(&self) -> Result<(), SettleMatchTaskError> {
-        let tx_submit_res = self
-            .arbitrum_client
-            .process_match_settle(
-                &self.party0_validity_proof,
-                &self.party1_validity_proof,
-                &self.match_bundle,
-            )
-            .await;
+    async fn submit_match(&mut self) -> Result<(), SettleMatchTaskError> {
+        // The dialer plays the role of party 0 in the match
+        let (local, peer) =
+            (self.handshake_state.local_order_id, self.handshake_state.peer_order_id);
+        let (party0_order_id, party1_order_id) = match self.handshake_state.role {
+            ConnectionRole::Dialer => (local, peer),
+            ConnectionRole::Listener => (peer, local),
+        };
+
+        let tx_submit_res = submit_match_and_record_gas(
+            &self.arbitrum_client,
+            self.settlement_batcher.as_ref(),
+            party0_order_id,
+            party1_order_id,
+            &self.party0_validity_proof,
+            &self.party1_validity_proof,
+            &self.match_bundle,
+            &self.match_gas_ledger,
+        )
+        .await;
 
         // If the transaction failed because a nullifier was already used, assume that
         // the counterparty already submitted a `match` and move on to
         // settlement
         if let Err(ref tx_rejection) = tx_submit_res
-            && tx_rejection.to_string().contains(NULLIFIER_USED_ERROR_MSG)
+            && (matches!(tx_rejection, ArbitrumClientError::NullifierAlreadySpent)
+                || tx_rejection.to_string().contains(NULLIFIER_USED_ERROR_MSG))
         {
             return Ok(());
         }
 
-        tx_submit_res.map_err(|e| SettleMatchTaskError::Arbitrum(e.to_string()))
+        let (_, block) =
+            tx_submit_res.map_err(|e| SettleMatchTaskError::Arbitrum(e.to_string()))?;
+        self.settlement_block = Some(block);
+        Ok(())
     }
 
     /// Apply the match result to the local wallet, find the wallet's new
@@ -281,37 +320,45 @@ impl SettleMatchTask {
         // reblind proof
         let mut wallet = self.get_wallet()?;
         let (private_shares, blinded_public_shares) = self.get_new_shares()?;
+        let order_id = self.handshake_state.local_order_id;
+        let prev_amount = Self::order_amount(&wallet, &order_id);
         wallet.update_from_shares(&private_shares, &blinded_public_shares);
 
+        // The shares are the source of truth for the settled wallet, so the fill is
+        // read off the order's volume before and after the update
+        let remaining_amount = Self::order_amount(&wallet, &order_id);
+        let fill = OrderFill {
+            order_id,
+            filled_amount: prev_amount.saturating_sub(remaining_amount),
+            remaining_amount,
+        };
+
         // Cancel all orders on both nullifiers, await new validity proofs
-        let party0_reblind_statement = &self.party0_validity_proof.reblind_proof.statement;
-        let party1_reblind_statement = &self.party1_validity_proof.reblind_proof.statement;
-        self.global_state.nullify_orders(party0_reblind_statement.original_shares_nullifier)?;
-        self.global_state.nullify_orders(party1_reblind_statement.original_shares_nullifier)?;
+        nullify_matched_orders(
+            &self.party0_validity_proof,
+            &self.party1_validity_proof,
+            &self.global_state,
+        )?;
 
         // Find the wallet's new Merkle opening
-        let opening = find_merkle_path(&wallet, &self.arbitrum_client)
+        find_opening(&mut wallet, &self.arbitrum_client)
             .await
-            .map_err(|err| SettleMatchTaskError::Arbitrum(err.to_string()))?;
-        wallet.merkle_proof = Some(opening);
+            .map_err(SettleMatchTaskError::Arbitrum)?;
 
-        // Index the updated wallet in global state
-        let wallet_id = wallet.wallet_id;
-        self.global_state.update_wallet(wallet)?.await?;
-
-        // Enqueue a job to settle the wallet's fees
-        enqueue_fee_settlement_tasks(wallet_id, &self.global_state)
+        // Index the updated wallet in global state and settle its fees
+        index_settled_wallet(wallet, &self.global_state)
             .await
             .map_err(SettleMatchTaskError::State)?;
 
+        publish_order_fill(&fill, &self.bus);
         Ok(())
     }
 
     /// Update the validity proofs for all orders in the wallet after settlement
     async fn update_validity_proofs(&self) -> Result<(), SettleMatchTaskError> {
-        let wallet = self.global_state.get_wallet(&self.wallet_id)?.unwrap();
-        update_wallet_validity_proofs(
-            &wallet,
+        let wallet = self.get_wallet()?;
+        update_settled_wallets_proofs(
+            vec![wallet],
             self.proof_queue.clone(),
             self.global_state.clone(),
             self.network_sender.clone(),
@@ -331,6 +378,12 @@ impl SettleMatchTask {
             .ok_or_else(|| SettleMatchTaskError::State(ERR_WALLET_NOT_FOUND.to_string()))
     }
 
+    /// Get the amount of the given order in the wallet, zero if the order is
+    /// not present
+    fn order_amount(wallet: &Wallet, order_id: &OrderIdentifier) -> Amount {
+        wallet.get_order(order_id).map(|order| order.amount).unwrap_or_default()
+    }
+
     /// Get the new private and blinded public shares for the wallet after
     /// update
     fn get_new_shares(&self) -> Result<(SizedWalletShare, SizedWalletShare), SettleMatchTaskError> {
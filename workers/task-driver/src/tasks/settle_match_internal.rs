@@ -3,22 +3,21 @@
 
 use std::error::Error;
 use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::time::Duration;
 
-use crate::helpers::{
-    enqueue_fee_settlement_tasks, enqueue_proof_job, update_wallet_validity_proofs,
-};
-use crate::traits::{Task, TaskContext, TaskError, TaskState};
-use crate::{driver::StateWrapper, helpers::find_merkle_path};
-use arbitrum_client::client::ArbitrumClient;
-use ark_mpc::{PARTY0, PARTY1};
+use crate::driver::StateWrapper;
+use crate::helpers::{await_confirmations, await_proof, enqueue_proof_job};
+use crate::settlement_batcher::SettlementBatcher;
+use crate::traits::{RetryPolicy, Task, TaskContext, TaskError, TaskState};
+use arbitrum_client::{client::ArbitrumClient, traits::DarkpoolClient};
 use async_trait::async_trait;
+use circuit_types::errors::MatchValidationError;
 use circuit_types::fixed_point::PROTOCOL_FEE_FP;
 use circuit_types::{fixed_point::FixedPoint, r#match::MatchResult};
-use circuits::zk_circuits::proof_linking::link_sized_commitments_match_settle;
 use circuits::zk_circuits::valid_match_settle::{
     SizedValidMatchSettleStatement, SizedValidMatchSettleWitness,
 };
-use common::types::proof_bundles::{MatchBundle, ProofBundle, ValidMatchSettleBundle};
+use common::types::proof_bundles::MatchBundle;
 use common::types::tasks::SettleMatchInternalTaskDescriptor;
 use common::types::wallet::WalletIdentifier;
 use common::types::{
@@ -26,16 +25,23 @@ use common::types::{
     wallet::{OrderIdentifier, Wallet},
 };
 use constants::Scalar;
+use ethers::types::TxHash;
+use external_api::bus_message::SystemBusMessage;
 use job_types::network_manager::NetworkManagerQueue;
 use job_types::proof_manager::{ProofJob, ProofManagerQueue};
-use renegade_metrics::helpers::record_match_volume;
+use job_types::task_driver::MatchGasLedger;
 use serde::Serialize;
 use state::error::StateError;
 use state::State;
-use tokio::task::JoinHandle as TokioJoinHandle;
+use system_bus::SystemBus;
 use tracing::instrument;
 use util::matching_engine::{
-    compute_fee_obligation, compute_max_amount, settle_match_into_wallets,
+    compute_fee_obligation, compute_max_amount, settle_match_into_wallets, validate_execution_price,
+};
+
+use super::settlement_helpers::{
+    create_link_proofs, find_opening, index_settled_wallet, nullify_matched_orders,
+    publish_order_fill, submit_match_and_record_gas, update_settled_wallets_proofs,
 };
 
 // -------------
@@ -49,6 +55,15 @@ pub const SETTLE_MATCH_INTERNAL_TASK_NAME: &str = "settle-match-internal";
 const ERR_AWAITING_PROOF: &str = "error awaiting proof";
 /// Error message emitted when a wallet cannot be found
 const ERR_WALLET_NOT_FOUND: &str = "wallet not found in global state";
+/// Error message emitted when awaiting confirmation on a match that has not
+/// been submitted
+const ERR_MATCH_NOT_SUBMITTED: &str = "match transaction not submitted";
+
+/// The interval at which to poll the block number while awaiting match
+/// confirmations
+const CONFIRMATION_POLL_INTERVAL_MS: u64 = 500;
+/// The time allotted to each awaited confirmation before the wait fails
+const CONFIRMATION_TIMEOUT_PER_BLOCK_MS: u64 = 30_000; // 30 seconds
 
 // --------------
 // | Task State |
@@ -63,6 +78,8 @@ pub enum SettleMatchInternalTaskState {
     ProvingMatchSettle,
     /// The task is submitting the match transaction
     SubmittingMatch,
+    /// The task is awaiting block confirmations on the match transaction
+    AwaitingConfirmation,
     /// The task is updating the wallet state and Merkle openings
     UpdatingState,
     /// The task is updating validity proofs for the wallet
@@ -76,6 +93,12 @@ impl TaskState for SettleMatchInternalTaskState {
         Self::SubmittingMatch
     }
 
+    /// Restarting re-proves `VALID MATCH SETTLE` from the descriptor's
+    /// witnesses; the orders' validity proofs in the descriptor are reused
+    fn pre_commit_retry_policy() -> RetryPolicy {
+        RetryPolicy::Restart
+    }
+
     fn completed(&self) -> bool {
         matches!(self, Self::Completed)
     }
@@ -106,8 +129,14 @@ pub enum SettleMatchInternalTaskError {
     MissingState(String),
     /// Error re-proving wallet and order validity
     ProvingValidity(String),
+    /// The execution price is outside the acceptable range of an order
+    InvalidExecutionPrice(String),
+    /// The match result is inconsistent with the orders or balances it settles
+    InvalidMatchResult(MatchValidationError),
     /// Error interacting with Arbitrum
     Arbitrum(String),
+    /// The contract reverted the match in a way that will recur on resubmission
+    ContractRevert(String),
     /// A wallet is already locked
     WalletLocked(WalletIdentifier),
     /// An error interacting with the global state
@@ -138,7 +167,10 @@ impl From<StateError> for SettleMatchInternalTaskError {
 // -------------------
 
 /// Describe the settle match internal task
-pub struct SettleMatchInternalTask {
+///
+/// Generic over the darkpool client so that the task steps may be tested
+/// against a mock client
+pub struct SettleMatchInternalTask<C: DarkpoolClient = ArbitrumClient> {
     /// The price at which the match was executed
     execution_price: FixedPoint,
     /// The identifier of the first order
@@ -161,14 +193,24 @@ pub struct SettleMatchInternalTask {
     match_result: MatchResult,
     /// The proof of `VALID MATCH SETTLE` generated in the first task step
     match_bundle: Option<MatchBundle>,
+    /// The hash of the match transaction and the block it was included in
+    match_tx: Option<(TxHash, u64)>,
+    /// The number of block confirmations to await on the match transaction
+    confirmation_depth: u64,
     /// The arbitrum client to use for submitting transactions
-    arbitrum_client: ArbitrumClient,
+    arbitrum_client: C,
     /// A sender to the network manager's work queue
     network_sender: NetworkManagerQueue,
     /// A copy of the relayer-global state
     state: State,
     /// The work queue to add proof management jobs to
     proof_queue: ProofManagerQueue,
+    /// A handle on the system bus
+    bus: SystemBus<SystemBusMessage>,
+    /// The ledger in which to record the gas used by the settlement
+    match_gas_ledger: MatchGasLedger,
+    /// The batcher through which to submit the match, if batching is enabled
+    settlement_batcher: Option<SettlementBatcher>,
     /// The state of the task
     task_state: SettleMatchInternalTaskState,
 }
@@ -205,10 +247,15 @@ impl Task for SettleMatchInternalTask {
             order2_validity_witness,
             match_result,
             match_bundle: None, // Assuming default initialization
+            match_tx: None,
+            confirmation_depth: ctx.match_confirmation_depth,
             arbitrum_client: ctx.arbitrum_client,
             network_sender: ctx.network_queue,
             state: ctx.state,
             proof_queue: ctx.proof_queue,
+            bus: ctx.bus,
+            match_gas_ledger: ctx.match_gas_ledger,
+            settlement_batcher: ctx.settlement_batcher,
             task_state: SettleMatchInternalTaskState::Pending, // Assuming default initialization
         })
     }
@@ -219,6 +266,7 @@ impl Task for SettleMatchInternalTask {
         // Dispatch based on the current task state
         match self.state() {
             SettleMatchInternalTaskState::Pending => {
+                self.validate_match_result()?;
                 self.task_state = SettleMatchInternalTaskState::ProvingMatchSettle
             },
 
@@ -229,6 +277,11 @@ impl Task for SettleMatchInternalTask {
 
             SettleMatchInternalTaskState::SubmittingMatch => {
                 self.submit_match().await?;
+                self.task_state = SettleMatchInternalTaskState::AwaitingConfirmation
+            },
+
+            SettleMatchInternalTaskState::AwaitingConfirmation => {
+                self.await_confirmation().await?;
                 self.task_state = SettleMatchInternalTaskState::UpdatingState
             },
 
@@ -240,8 +293,6 @@ impl Task for SettleMatchInternalTask {
             SettleMatchInternalTaskState::UpdatingValidityProofs => {
                 self.update_proofs().await?;
                 self.task_state = SettleMatchInternalTaskState::Completed;
-
-                record_match_volume(&self.match_result);
             },
 
             SettleMatchInternalTaskState::Completed => {
@@ -269,13 +320,19 @@ impl Task for SettleMatchInternalTask {
 // | Task Implementation |
 // -----------------------
 
-impl SettleMatchInternalTask {
+impl<C: DarkpoolClient> SettleMatchInternalTask<C> {
     // --------------
     // | Task Steps |
     // --------------
 
     /// Prove `VALID MATCH SETTLE` on the order pair
     async fn prove_match_settle(&mut self) -> Result<(), SettleMatchInternalTaskError> {
+        // Check the price natively, the proof would otherwise fail to verify
+        let order1 = &self.order1_validity_witness.commitment_witness.order;
+        let order2 = &self.order2_validity_witness.commitment_witness.order;
+        validate_execution_price(order1, order2, self.execution_price)
+            .map_err(SettleMatchInternalTaskError::InvalidExecutionPrice)?;
+
         let (witness, statement) = self.get_witness_statement();
 
         // Enqueue a job with the proof generation module
@@ -284,13 +341,18 @@ impl SettleMatchInternalTask {
             .map_err(SettleMatchInternalTaskError::EnqueuingJob)?;
 
         // Await the proof from the proof manager
-        let bundle = proof_recv.await.map_err(|_| {
+        let bundle = await_proof(proof_recv).await.map_err(|_| {
             SettleMatchInternalTaskError::EnqueuingJob(ERR_AWAITING_PROOF.to_string())
         })?;
 
         // Create proof links between the parties' proofs of `VALID COMMITMENTS` and the
         // `VALID MATCH SETTLE` proof
-        let match_bundle = self.create_link_proofs(bundle)?;
+        let match_bundle = create_link_proofs(
+            &self.order1_validity_witness,
+            &self.order2_validity_witness,
+            bundle,
+        )
+        .map_err(SettleMatchInternalTaskError::ProvingValidity)?;
         self.match_bundle = Some(match_bundle);
         Ok(())
     }
@@ -298,33 +360,69 @@ impl SettleMatchInternalTask {
     /// Submit the match transaction
     async fn submit_match(&mut self) -> Result<(), SettleMatchInternalTaskError> {
         // Submit a `match` transaction
-        self.arbitrum_client
-            .process_match_settle(
-                &self.order1_proof,
-                &self.order2_proof,
-                self.match_bundle.as_ref().unwrap(),
-            )
-            .await
-            .map_err(|e| SettleMatchInternalTaskError::Arbitrum(e.to_string()))
+        let match_tx = submit_match_and_record_gas(
+            &self.arbitrum_client,
+            self.settlement_batcher.as_ref(),
+            self.order_id1,
+            self.order_id2,
+            &self.order1_proof,
+            &self.order2_proof,
+            self.match_bundle.as_ref().unwrap(),
+            &self.match_gas_ledger,
+        )
+        .await
+        .map_err(|e| {
+            if e.is_permanent_revert() {
+                SettleMatchInternalTaskError::ContractRevert(e.to_string())
+            } else {
+                SettleMatchInternalTaskError::Arbitrum(e.to_string())
+            }
+        })?;
+
+        self.match_tx = Some(match_tx);
+        Ok(())
+    }
+
+    /// Await the configured number of block confirmations on the match
+    /// transaction before updating local state
+    ///
+    /// Fails if the transaction is reorged out of its block or the
+    /// confirmations do not land in time, leaving local state untouched
+    async fn await_confirmation(&self) -> Result<(), SettleMatchInternalTaskError> {
+        let (tx_hash, tx_block) = self.match_tx.ok_or_else(|| {
+            SettleMatchInternalTaskError::MissingState(ERR_MATCH_NOT_SUBMITTED.to_string())
+        })?;
+
+        let poll_interval = Duration::from_millis(CONFIRMATION_POLL_INTERVAL_MS);
+        let timeout = Duration::from_millis(
+            CONFIRMATION_TIMEOUT_PER_BLOCK_MS * self.confirmation_depth.max(1),
+        );
+        await_confirmations(
+            &self.arbitrum_client,
+            tx_hash,
+            tx_block,
+            self.confirmation_depth,
+            poll_interval,
+            timeout,
+        )
+        .await
+        .map_err(SettleMatchInternalTaskError::Arbitrum)
     }
 
     /// Update the wallet state and Merkle openings
     async fn update_state(&self) -> Result<(), SettleMatchInternalTaskError> {
         // Nullify orders on the newly matched values
-        let nullifier1 = self.order1_proof.reblind_proof.statement.original_shares_nullifier;
-        let nullifier2 = self.order2_proof.reblind_proof.statement.original_shares_nullifier;
-        self.state.nullify_orders(nullifier1)?;
-        self.state.nullify_orders(nullifier2)?;
+        nullify_matched_orders(&self.order1_proof, &self.order2_proof, &self.state)?;
 
         // Lookup the wallets that manage each order
         let mut wallet1 = self.find_wallet(&self.wallet_id1)?;
         let mut wallet2 = self.find_wallet(&self.wallet_id2)?;
 
         // Apply the match to each of the wallets
-        wallet1
+        let fill1 = wallet1
             .apply_match(&self.match_result, &self.order_id1)
             .map_err(SettleMatchInternalTaskError::State)?;
-        wallet2
+        let fill2 = wallet2
             .apply_match(&self.match_result, &self.order_id2)
             .map_err(SettleMatchInternalTaskError::State)?;
 
@@ -332,24 +430,23 @@ impl SettleMatchInternalTask {
         wallet1.reblind_wallet();
         wallet2.reblind_wallet();
 
-        self.find_opening(&mut wallet1).await?;
-        self.find_opening(&mut wallet2).await?;
-
-        // Re-index the updated wallets in the global state
-        let id1 = wallet1.wallet_id;
-        let id2 = wallet2.wallet_id;
-        self.state.update_wallet(wallet1)?.await?;
-        self.state.update_wallet(wallet2)?.await?;
+        find_opening(&mut wallet1, &self.arbitrum_client)
+            .await
+            .map_err(SettleMatchInternalTaskError::Arbitrum)?;
+        find_opening(&mut wallet2, &self.arbitrum_client)
+            .await
+            .map_err(SettleMatchInternalTaskError::Arbitrum)?;
 
-        // Enqueue jobs to pay fees for the wallets
-        let state = &self.state;
-        enqueue_fee_settlement_tasks(id1, state)
+        // Re-index the updated wallets in the global state and settle their fees
+        index_settled_wallet(wallet1, &self.state)
             .await
             .map_err(SettleMatchInternalTaskError::State)?;
-        enqueue_fee_settlement_tasks(id2, state)
+        index_settled_wallet(wallet2, &self.state)
             .await
             .map_err(SettleMatchInternalTaskError::State)?;
 
+        publish_order_fill(&fill1, &self.bus);
+        publish_order_fill(&fill2, &self.bus);
         Ok(())
     }
 
@@ -359,37 +456,35 @@ impl SettleMatchInternalTask {
         let wallet1 = self.find_wallet(&self.wallet_id1)?;
         let wallet2 = self.find_wallet(&self.wallet_id2)?;
 
-        // We spawn the proof updates in tasks so that they may run concurrently, we do
-        // not want to wait for the first wallet's proofs to finish before
-        // starting the second wallet's proofs when the proof generation module
-        // is capable of handling many at once
-        let t1 = Self::spawn_update_proofs_task(
-            wallet1,
-            self.proof_queue.clone(),
-            self.state.clone(),
-            self.network_sender.clone(),
-        );
-        let t2 = Self::spawn_update_proofs_task(
-            wallet2,
+        update_settled_wallets_proofs(
+            vec![wallet1, wallet2],
             self.proof_queue.clone(),
             self.state.clone(),
             self.network_sender.clone(),
-        );
-
-        // Await both threads and handle errors
-        let (res1, res2) = tokio::join!(t1, t2);
-        let res1 =
-            res1.unwrap().map_err(|e| SettleMatchInternalTaskError::ProvingValidity(e.to_string()));
-        let res2 =
-            res2.unwrap().map_err(|e| SettleMatchInternalTaskError::ProvingValidity(e.to_string()));
-
-        res1.and(res2)
+        )
+        .await
+        .map_err(SettleMatchInternalTaskError::ProvingValidity)
     }
 
     // -----------
     // | Helpers |
     // -----------
 
+    /// Check the match result against the matched orders and the balances
+    /// they send from, before any shares are computed from it
+    fn validate_match_result(&self) -> Result<(), SettleMatchInternalTaskError> {
+        let witness1 = &self.order1_validity_witness.commitment_witness;
+        let witness2 = &self.order2_validity_witness.commitment_witness;
+        self.match_result
+            .validate(
+                &witness1.order,
+                &witness2.order,
+                &witness1.balance_send,
+                &witness2.balance_send,
+            )
+            .map_err(SettleMatchInternalTaskError::InvalidMatchResult)
+    }
+
     /// Find the wallet for an order in the global state
     fn find_wallet(
         &self,
@@ -480,49 +575,100 @@ impl SettleMatchInternalTask {
 
         (witness, statement)
     }
+}
 
-    /// Create link proofs of `VALID MATCH SETTLE` to the parties' proofs of
-    /// `VALID COMMITMENTS`
-    fn create_link_proofs(
-        &self,
-        match_settle_proof: ProofBundle,
-    ) -> Result<MatchBundle, SettleMatchInternalTaskError> {
-        let match_link_hint = &match_settle_proof.link_hint;
-        let match_proof: ValidMatchSettleBundle = match_settle_proof.proof.into();
-
-        let party0_comms_hint = &self.order1_validity_witness.commitment_linking_hint;
-        let commitments_link0 =
-            link_sized_commitments_match_settle(PARTY0, party0_comms_hint, match_link_hint)
-                .map_err(|e| SettleMatchInternalTaskError::ProvingValidity(e.to_string()))?;
-
-        let party1_comms_hint = &self.order2_validity_witness.commitment_linking_hint;
-        let commitments_link1 =
-            link_sized_commitments_match_settle(PARTY1, party1_comms_hint, match_link_hint)
-                .map_err(|e| SettleMatchInternalTaskError::ProvingValidity(e.to_string()))?;
-
-        Ok(MatchBundle { match_proof, commitments_link0, commitments_link1 })
+#[cfg(test)]
+mod test {
+    use arbitrum_client::{mock::MockDarkpoolClient, traits::DarkpoolClient};
+    use circuit_types::{fixed_point::FixedPoint, r#match::MatchResult};
+    use common::types::proof_bundles::{
+        mocks::{
+            dummy_link_proof, dummy_valid_match_settle_bundle, dummy_validity_proof_bundle,
+            dummy_validity_witness_bundle,
+        },
+        MatchBundle,
+    };
+    use job_types::{
+        network_manager::new_network_manager_queue, proof_manager::new_proof_manager_queue,
+        task_driver::new_match_gas_ledger,
+    };
+    use state::test_helpers::mock_state;
+    use system_bus::SystemBus;
+    use uuid::Uuid;
+
+    use super::{
+        SettleMatchInternalTask, SettleMatchInternalTaskError, SettleMatchInternalTaskState,
+    };
+
+    /// The block in which the mock chain includes the match
+    const MATCH_BLOCK: u64 = 100;
+    /// The confirmation depth used in tests
+    const CONFIRMATION_DEPTH: u64 = 3;
+
+    /// Build a settle match internal task proven up to submission, backed by
+    /// the given mock client
+    fn setup_task(client: MockDarkpoolClient) -> SettleMatchInternalTask<MockDarkpoolClient> {
+        let (proof_queue, _) = new_proof_manager_queue();
+        let (network_sender, _) = new_network_manager_queue();
+        let match_bundle = MatchBundle {
+            match_proof: dummy_valid_match_settle_bundle().into(),
+            commitments_link0: dummy_link_proof(),
+            commitments_link1: dummy_link_proof(),
+        };
+
+        SettleMatchInternalTask {
+            execution_price: FixedPoint::from_integer(1),
+            order_id1: Uuid::new_v4(),
+            wallet_id1: Uuid::new_v4(),
+            order_id2: Uuid::new_v4(),
+            wallet_id2: Uuid::new_v4(),
+            order1_proof: dummy_validity_proof_bundle(),
+            order1_validity_witness: dummy_validity_witness_bundle(),
+            order2_proof: dummy_validity_proof_bundle(),
+            order2_validity_witness: dummy_validity_witness_bundle(),
+            match_result: MatchResult::default(),
+            match_bundle: Some(match_bundle),
+            match_tx: None,
+            confirmation_depth: CONFIRMATION_DEPTH,
+            arbitrum_client: client,
+            network_sender,
+            state: mock_state(),
+            proof_queue,
+            bus: SystemBus::new(),
+            match_gas_ledger: new_match_gas_ledger(),
+            settlement_batcher: None,
+            task_state: SettleMatchInternalTaskState::SubmittingMatch,
+        }
     }
 
-    /// Find and update the merkle opening for the wallet
-    async fn find_opening(&self, wallet: &mut Wallet) -> Result<(), SettleMatchInternalTaskError> {
-        let opening = find_merkle_path(wallet, &self.arbitrum_client)
-            .await
-            .map_err(|err| SettleMatchInternalTaskError::Arbitrum(err.to_string()))?;
+    /// Tests that the task waits for the chain to reach the confirmation depth
+    /// before moving on to update local state
+    #[tokio::test]
+    async fn test_match_confirmed() {
+        let client = MockDarkpoolClient::new();
+        client.set_match_block(MATCH_BLOCK);
+        client.set_current_block(MATCH_BLOCK);
+        let mut task = setup_task(client.clone());
 
-        wallet.merkle_proof = Some(opening);
-        Ok(())
+        task.submit_match().await.unwrap();
+        task.await_confirmation().await.unwrap();
+
+        // The chain advanced one block per poll until the depth was reached
+        assert_eq!(client.current_block().await.unwrap(), MATCH_BLOCK + CONFIRMATION_DEPTH);
     }
 
-    /// Spawns a task to update the validity proofs for the given wallet
-    /// Returns a `JoinHandle` to the spawned task
-    fn spawn_update_proofs_task(
-        wallet: Wallet,
-        proof_queue: ProofManagerQueue,
-        state: State,
-        network_sender: NetworkManagerQueue,
-    ) -> TokioJoinHandle<Result<(), String>> {
-        tokio::spawn(async move {
-            update_wallet_validity_proofs(&wallet, proof_queue, state, network_sender).await
-        })
+    /// Tests that a match reorged out of its block fails the confirmation wait
+    #[tokio::test]
+    async fn test_match_reorged() {
+        let client = MockDarkpoolClient::new();
+        client.set_match_block(MATCH_BLOCK);
+        client.set_current_block(MATCH_BLOCK);
+        let mut task = setup_task(client.clone());
+
+        task.submit_match().await.unwrap();
+        client.reorg();
+
+        let res = task.await_confirmation().await;
+        assert!(matches!(res, Err(SettleMatchInternalTaskError::Arbitrum(_))));
     }
 }
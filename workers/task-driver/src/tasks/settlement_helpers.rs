@@ -0,0 +1,553 @@
+//! Helpers shared by the match settlement tasks
+//!
+//! Both the cross-cluster `settle_match` task and the `settle_match_internal`
+//! task nullify the matched orders, update wallet openings, index the settled
+//! wallets, and re-prove validity for them. Routing both tasks through these
+//! helpers keeps the two settlement paths from drifting apart
+
+use arbitrum_client::{
+    errors::ArbitrumClientError,
+    traits::{DarkpoolClient, MatchSettlement},
+};
+use ark_mpc::{PARTY0, PARTY1};
+use circuits::zk_circuits::proof_linking::link_sized_commitments_match_settle;
+use common::types::{
+    proof_bundles::{
+        MatchBundle, OrderValidityProofBundle, OrderValidityWitnessBundle, ProofBundle,
+        ValidMatchSettleBundle,
+    },
+    tasks::MatchGasRecord,
+    wallet::{OrderFill, OrderIdentifier, Wallet},
+};
+use constants::ORDER_STATE_CHANGE_TOPIC;
+use ethers::types::TxHash;
+use external_api::bus_message::SystemBusMessage;
+use futures::future::join_all;
+use job_types::{
+    network_manager::NetworkManagerQueue, proof_manager::ProofManagerQueue,
+    task_driver::MatchGasLedger,
+};
+use state::{error::StateError, State};
+use system_bus::SystemBus;
+use tracing::{info_span, warn, Instrument};
+use util::get_current_time_millis;
+
+use crate::{
+    helpers::{enqueue_fee_settlement_tasks, update_wallet_validity_proofs},
+    settlement_batcher::SettlementBatcher,
+};
+
+/// The maximum number of settlements kept in the gas ledger, the oldest
+/// records are evicted once the ledger is full
+const MAX_MATCH_GAS_RECORDS: usize = 10_000;
+
+/// Submit a match to the contract and record the gas used by the settlement
+/// in the ledger
+///
+/// The match is submitted through the batcher if one is given, and directly
+/// otherwise. Returns the hash of the settlement transaction and the number of
+/// the block in which it was included
+#[allow(clippy::too_many_arguments)]
+pub(super) async fn submit_match_and_record_gas<C: DarkpoolClient>(
+    client: &C,
+    batcher: Option<&SettlementBatcher>,
+    party0_order_id: OrderIdentifier,
+    party1_order_id: OrderIdentifier,
+    party0_validity_proofs: &OrderValidityProofBundle,
+    party1_validity_proofs: &OrderValidityProofBundle,
+    match_bundle: &MatchBundle,
+    ledger: &MatchGasLedger,
+) -> Result<(TxHash, u64), ArbitrumClientError> {
+    let receipt = match batcher {
+        Some(batcher) => {
+            let settlement = MatchSettlement {
+                party0_validity_proofs: party0_validity_proofs.clone(),
+                party1_validity_proofs: party1_validity_proofs.clone(),
+                match_bundle: match_bundle.clone(),
+            };
+            batcher.submit(settlement).await?
+        },
+        None => {
+            client
+                .process_match_settle(party0_validity_proofs, party1_validity_proofs, match_bundle)
+                .await?
+        },
+    };
+    let block = receipt.block_number.ok_or(ArbitrumClientError::TxDropped)?.as_u64();
+
+    let tx_hash = receipt.transaction_hash;
+    match receipt.gas_used {
+        Some(gas_used) => {
+            let record = MatchGasRecord {
+                party0_order_id,
+                party1_order_id,
+                tx_hash: format!("{tx_hash:#x}"),
+                gas_used: gas_used.as_u64(),
+                timestamp: get_current_time_millis() as u64,
+            };
+            record_match_gas(ledger, record);
+        },
+        None => {
+            warn!("settlement receipt for tx {tx_hash:#x} has no gas used, skipping gas record")
+        },
+    }
+
+    Ok((tx_hash, block))
+}
+
+/// Append a record to the gas ledger, evicting the oldest record if the
+/// ledger is full
+fn record_match_gas(ledger: &MatchGasLedger, record: MatchGasRecord) {
+    let mut ledger = ledger.write().unwrap();
+    if ledger.len() >= MAX_MATCH_GAS_RECORDS {
+        ledger.pop_front();
+    }
+
+    ledger.push_back(record);
+}
+
+/// Create link proofs of `VALID MATCH SETTLE` to the parties' proofs of
+/// `VALID COMMITMENTS`
+pub(super) fn create_link_proofs(
+    party0_validity_witness: &OrderValidityWitnessBundle,
+    party1_validity_witness: &OrderValidityWitnessBundle,
+    match_settle_proof: ProofBundle,
+) -> Result<MatchBundle, String> {
+    let match_link_hint = &match_settle_proof.link_hint;
+    let match_proof: ValidMatchSettleBundle = match_settle_proof.proof.into();
+
+    let party0_comms_hint = &party0_validity_witness.commitment_linking_hint;
+    let commitments_link0 =
+        link_sized_commitments_match_settle(PARTY0, party0_comms_hint, match_link_hint)
+            .map_err(|e| e.to_string())?;
+
+    let party1_comms_hint = &party1_validity_witness.commitment_linking_hint;
+    let commitments_link1 =
+        link_sized_commitments_match_settle(PARTY1, party1_comms_hint, match_link_hint)
+            .map_err(|e| e.to_string())?;
+
+    Ok(MatchBundle { match_proof, commitments_link0, commitments_link1 })
+}
+
+/// Cancel all orders on the nullifiers spent by the two parties' validity
+/// proofs, new orders will be indexed once new validity proofs are generated
+pub(super) fn nullify_matched_orders(
+    party0_validity_proof: &OrderValidityProofBundle,
+    party1_validity_proof: &OrderValidityProofBundle,
+    state: &State,
+) -> Result<(), StateError> {
+    let nullifier0 = party0_validity_proof.reblind_proof.statement.original_shares_nullifier;
+    let nullifier1 = party1_validity_proof.reblind_proof.statement.original_shares_nullifier;
+    state.nullify_orders(nullifier0)?;
+    state.nullify_orders(nullifier1)
+}
+
+/// Find and update the Merkle opening for a settled wallet
+pub(super) async fn find_opening<C: DarkpoolClient>(
+    wallet: &mut Wallet,
+    client: &C,
+) -> Result<(), String> {
+    let commitment = wallet.get_wallet_share_commitment();
+    let opening =
+        client.find_merkle_authentication_path(commitment).await.map_err(|e| e.to_string())?;
+    wallet.merkle_proof = Some(opening);
+
+    Ok(())
+}
+
+/// Index a settled wallet in the global state and enqueue tasks to settle the
+/// fees it owes
+pub(super) async fn index_settled_wallet(wallet: Wallet, state: &State) -> Result<(), String> {
+    let wallet_id = wallet.wallet_id;
+    state.update_wallet(wallet).map_err(|e| e.to_string())?.await.map_err(|e| e.to_string())?;
+
+    enqueue_fee_settlement_tasks(wallet_id, state).await
+}
+
+/// Publish the fill of a settled order on the order state change topic
+pub(super) fn publish_order_fill(fill: &OrderFill, bus: &SystemBus<SystemBusMessage>) {
+    bus.publish(
+        ORDER_STATE_CHANGE_TOPIC.to_string(),
+        SystemBusMessage::OrderFilled {
+            order_id: fill.order_id,
+            filled_amount: fill.filled_amount,
+            remaining_amount: fill.remaining_amount,
+        },
+    );
+}
+
+/// Update the validity proofs for each of the settled wallets
+///
+/// The updates run concurrently so that the proof generation module may
+/// prove for all wallets at once, rather than one wallet after another
+pub(super) async fn update_settled_wallets_proofs(
+    wallets: Vec<Wallet>,
+    proof_queue: ProofManagerQueue,
+    state: State,
+    network_sender: NetworkManagerQueue,
+) -> Result<(), String> {
+    let handles = wallets.into_iter().map(|wallet| {
+        let proof_queue = proof_queue.clone();
+        let state = state.clone();
+        let network_sender = network_sender.clone();
+
+        // Spawned tasks do not inherit the current span, so the task's span is
+        // attached as the parent explicitly
+        let span = info_span!("update_wallet_proofs", wallet_id = %wallet.wallet_id);
+        tokio::spawn(
+            async move {
+                update_wallet_validity_proofs(&wallet, proof_queue, state, network_sender).await
+            }
+            .instrument(span),
+        )
+    });
+
+    for res in join_all(handles).await {
+        res.map_err(|e| e.to_string())??;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use arbitrum_client::mock::MockDarkpoolClient;
+    use circuit_types::balance::Balance;
+    use common::types::{
+        network_order::{test_helpers::dummy_network_order, NetworkOrderState},
+        proof_bundles::{
+            mocks::{
+                dummy_link_hint, dummy_link_proof, dummy_proof, dummy_valid_match_settle_bundle,
+                dummy_validity_proof_bundle,
+            },
+            MatchBundle, ProofBundle,
+        },
+        tasks::MatchGasRecord,
+        wallet::{OrderFill, OrderIdentifier, Wallet},
+        wallet_mocks::{mock_empty_wallet, mock_order},
+    };
+    use constants::ORDER_STATE_CHANGE_TOPIC;
+    use external_api::bus_message::SystemBusMessage;
+    use job_types::{
+        network_manager::new_network_manager_queue,
+        proof_manager::{new_proof_manager_queue, ProofJob, ProofManagerReceiver},
+        task_driver::new_match_gas_ledger,
+    };
+    use state::{test_helpers::mock_state, State};
+    use std::sync::{Arc, Mutex};
+    use system_bus::SystemBus;
+    use tracing::{
+        span::{Attributes, Id},
+        Instrument, Subscriber,
+    };
+    use tracing_subscriber::{
+        layer::{Context, SubscriberExt},
+        registry::LookupSpan,
+        Layer, Registry,
+    };
+    use uuid::Uuid;
+
+    use crate::driver::task_span;
+
+    use super::{
+        nullify_matched_orders, publish_order_fill, record_match_gas, submit_match_and_record_gas,
+        update_settled_wallets_proofs, MAX_MATCH_GAS_RECORDS,
+    };
+
+    /// A span recorded by the `SpanCapture` layer
+    struct CapturedSpan {
+        /// The name of the span
+        name: &'static str,
+        /// The name of the span's parent, if any
+        parent: Option<&'static str>,
+        /// The names of the span's fields
+        fields: Vec<&'static str>,
+    }
+
+    /// A tracing layer that records each span created
+    #[derive(Clone, Default)]
+    struct SpanCapture(Arc<Mutex<Vec<CapturedSpan>>>);
+
+    impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for SpanCapture {
+        fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+            let parent = ctx.span(id).and_then(|span| span.parent()).map(|parent| parent.name());
+            let fields = attrs.fields().iter().map(|f| f.name()).collect();
+            let span = CapturedSpan { name: attrs.metadata().name(), parent, fields };
+            self.0.lock().unwrap().push(span);
+        }
+    }
+
+    /// Respond to validity proof jobs with dummy proofs, in place of the proof
+    /// manager
+    fn mock_validity_prover(proof_recv: ProofManagerReceiver) {
+        std::thread::spawn(move || {
+            while let Ok(job) = proof_recv.recv() {
+                let bundle = match job.type_ {
+                    ProofJob::ValidReblind { statement, .. } => {
+                        ProofBundle::new_valid_reblind(statement, dummy_proof(), dummy_link_hint())
+                    },
+                    ProofJob::ValidCommitments { statement, .. } => {
+                        ProofBundle::new_valid_commitments(
+                            statement,
+                            dummy_proof(),
+                            dummy_link_hint(),
+                        )
+                    },
+                    job => panic!("unexpected proof job: {job:?}"),
+                };
+                let _ = job.response_channel.send(Ok(bundle));
+            }
+        });
+    }
+
+    /// Add a wallet holding a funded order to the state, returning the wallet
+    /// and the order's ID
+    async fn add_wallet_with_order(state: &State) -> (Wallet, OrderIdentifier) {
+        let mut wallet = mock_empty_wallet();
+        state.new_wallet(wallet.clone()).unwrap().await.unwrap();
+
+        let order = mock_order();
+        let balance = Balance::new_from_mint_and_amount(order.quote_mint.clone(), 1000);
+        let id = OrderIdentifier::new_v4();
+        wallet.add_balance(balance).unwrap();
+        wallet.add_order(id, order).unwrap();
+        wallet.reblind_wallet();
+        state.update_wallet(wallet.clone()).unwrap().await.unwrap();
+
+        (wallet, id)
+    }
+
+    /// Tests that both parties' orders are cancelled when a match is settled
+    #[test]
+    fn test_nullify_matched_orders() {
+        let state = mock_state();
+
+        let order0 = dummy_network_order();
+        let order1 = dummy_network_order();
+        let unmatched = dummy_network_order();
+        state.add_order(order0.clone()).unwrap();
+        state.add_order(order1.clone()).unwrap();
+        state.add_order(unmatched.clone()).unwrap();
+
+        let mut proof0 = dummy_validity_proof_bundle();
+        let mut proof1 = dummy_validity_proof_bundle();
+        proof0.reblind_proof.statement.original_shares_nullifier = order0.public_share_nullifier;
+        proof1.reblind_proof.statement.original_shares_nullifier = order1.public_share_nullifier;
+        nullify_matched_orders(&proof0, &proof1, &state).unwrap();
+
+        let order_state = |id| state.get_order(id).unwrap().unwrap().state;
+        assert_eq!(order_state(&order0.id), NetworkOrderState::Cancelled);
+        assert_eq!(order_state(&order1.id), NetworkOrderState::Cancelled);
+        assert_eq!(order_state(&unmatched.id), NetworkOrderState::Received);
+    }
+
+    /// Tests updating validity proofs for the single local wallet of a
+    /// cross-cluster match and the two wallets of an internal match, and that
+    /// only the settled wallets are re-proven
+    #[tokio::test]
+    async fn test_update_settled_wallets_proofs() {
+        let state = mock_state();
+        let (proof_queue, proof_recv) = new_proof_manager_queue();
+        let (network_sender, _network_recv) = new_network_manager_queue();
+        mock_validity_prover(proof_recv);
+
+        let (wallet0, order0) = add_wallet_with_order(&state).await;
+        let (wallet1, order1) = add_wallet_with_order(&state).await;
+        let (wallet2, order2) = add_wallet_with_order(&state).await;
+        let (_unsettled, unsettled_order) = add_wallet_with_order(&state).await;
+        let has_proofs = |id| state.get_validity_proofs(id).unwrap().is_some();
+
+        // A cross-cluster match settles one local wallet
+        update_settled_wallets_proofs(
+            vec![wallet0],
+            proof_queue.clone(),
+            state.clone(),
+            network_sender.clone(),
+        )
+        .await
+        .unwrap();
+        assert!(has_proofs(&order0));
+        assert!(!has_proofs(&order1));
+        assert!(!has_proofs(&order2));
+
+        // An internal match settles both wallets
+        let wallets = vec![wallet1, wallet2];
+        update_settled_wallets_proofs(wallets, proof_queue, state.clone(), network_sender)
+            .await
+            .unwrap();
+        assert!(has_proofs(&order1));
+        assert!(has_proofs(&order2));
+        assert!(!has_proofs(&unsettled_order));
+    }
+
+    /// Tests that the spans of the proof update tasks spawned by a task are
+    /// children of the task's span
+    #[tokio::test]
+    async fn test_update_proofs_span_propagation() {
+        let capture = SpanCapture::default();
+        let _guard = tracing::subscriber::set_default(Registry::default().with(capture.clone()));
+
+        let state = mock_state();
+        let (proof_queue, _proof_recv) = new_proof_manager_queue();
+        let (network_sender, _network_recv) = new_network_manager_queue();
+
+        let wallet = mock_empty_wallet();
+        let span = task_span(Uuid::new_v4(), &[wallet.wallet_id]);
+        update_settled_wallets_proofs(vec![wallet], proof_queue, state, network_sender)
+            .instrument(span)
+            .await
+            .unwrap();
+
+        let spans = capture.0.lock().unwrap();
+        let task_span = spans.iter().find(|span| span.name == "task").unwrap();
+        assert_eq!(task_span.fields, vec!["task_id", "task_name", "wallet_id"]);
+
+        let child_span = spans.iter().find(|span| span.name == "update_wallet_proofs").unwrap();
+        assert_eq!(child_span.parent, Some("task"));
+        assert_eq!(child_span.fields, vec!["wallet_id"]);
+    }
+
+    /// Tests that partial and full fills are published on the order state
+    /// change topic with their residual amounts
+    #[tokio::test]
+    async fn test_publish_order_fill() {
+        let bus = SystemBus::new();
+        let mut reader = bus.subscribe(ORDER_STATE_CHANGE_TOPIC.to_string());
+
+        let order_id = Uuid::new_v4();
+        let partial = OrderFill { order_id, filled_amount: 4, remaining_amount: 6 };
+        let full = OrderFill { order_id, filled_amount: 6, remaining_amount: 0 };
+        assert!(!partial.is_full());
+        assert!(full.is_full());
+
+        for fill in [partial, full] {
+            publish_order_fill(&fill, &bus);
+            match reader.next_message().await {
+                SystemBusMessage::OrderFilled { order_id, filled_amount, remaining_amount } => {
+                    assert_eq!(order_id, fill.order_id);
+                    assert_eq!(filled_amount, fill.filled_amount);
+                    assert_eq!(remaining_amount, fill.remaining_amount);
+                },
+                msg => panic!("unexpected message: {msg:?}"),
+            }
+        }
+    }
+
+    /// Tests that the gas used by a settlement is recorded in the ledger with
+    /// the matched orders
+    #[tokio::test]
+    async fn test_match_gas_recorded() {
+        const BLOCK: u64 = 42;
+        const GAS_USED: u64 = 1_234_567;
+
+        let client = MockDarkpoolClient::new();
+        client.set_match_block(BLOCK);
+        client.set_match_gas_used(GAS_USED);
+
+        let ledger = new_match_gas_ledger();
+        let (order0, order1) = (Uuid::new_v4(), Uuid::new_v4());
+        let match_bundle = MatchBundle {
+            match_proof: dummy_valid_match_settle_bundle().into(),
+            commitments_link0: dummy_link_proof(),
+            commitments_link1: dummy_link_proof(),
+        };
+
+        let (_, block) = submit_match_and_record_gas(
+            &client,
+            None, // batcher
+            order0,
+            order1,
+            &dummy_validity_proof_bundle(),
+            &dummy_validity_proof_bundle(),
+            &match_bundle,
+            &ledger,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(block, BLOCK);
+        assert_eq!(client.n_match_settlements(), 1);
+
+        let ledger = ledger.read().unwrap();
+        assert_eq!(ledger.len(), 1);
+        let record = &ledger[0];
+        assert_eq!(record.gas_used, GAS_USED);
+        assert_eq!(record.party0_order_id, order0);
+        assert_eq!(record.party1_order_id, order1);
+    }
+
+    /// Tests that no gas record is written when the settlement receipt omits
+    /// the gas used
+    #[tokio::test]
+    async fn test_match_gas_missing() {
+        let client = MockDarkpoolClient::new();
+        client.set_match_block(1);
+        client.omit_gas_used();
+
+        let ledger = new_match_gas_ledger();
+        let match_bundle = MatchBundle {
+            match_proof: dummy_valid_match_settle_bundle().into(),
+            commitments_link0: dummy_link_proof(),
+            commitments_link1: dummy_link_proof(),
+        };
+
+        submit_match_and_record_gas(
+            &client,
+            None, // batcher
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            &dummy_validity_proof_bundle(),
+            &dummy_validity_proof_bundle(),
+            &match_bundle,
+            &ledger,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(client.n_match_settlements(), 1);
+        assert!(ledger.read().unwrap().is_empty());
+    }
+
+    /// Tests that the oldest record is evicted once the gas ledger is full
+    #[test]
+    fn test_match_gas_ledger_bounded() {
+        let ledger = new_match_gas_ledger();
+        for timestamp in 0..=MAX_MATCH_GAS_RECORDS as u64 {
+            let record = MatchGasRecord {
+                party0_order_id: Uuid::new_v4(),
+                party1_order_id: Uuid::new_v4(),
+                tx_hash: String::new(),
+                gas_used: 1,
+                timestamp,
+            };
+            record_match_gas(&ledger, record);
+        }
+
+        let ledger = ledger.read().unwrap();
+        assert_eq!(ledger.len(), MAX_MATCH_GAS_RECORDS);
+        assert_eq!(ledger.front().unwrap().timestamp, 1);
+        assert_eq!(ledger.back().unwrap().timestamp, MAX_MATCH_GAS_RECORDS as u64);
+    }
+
+    /// Tests that settling the same pair of orders twice keeps a record of
+    /// each settlement
+    #[test]
+    fn test_match_gas_repeated_pair() {
+        let ledger = new_match_gas_ledger();
+        let (party0_order_id, party1_order_id) = (Uuid::new_v4(), Uuid::new_v4());
+        for (timestamp, gas_used) in [(1, 100), (2, 200)] {
+            let record = MatchGasRecord {
+                party0_order_id,
+                party1_order_id,
+                tx_hash: String::new(),
+                gas_used,
+                timestamp,
+            };
+            record_match_gas(&ledger, record);
+        }
+
+        let ledger = ledger.read().unwrap();
+        let gas = ledger.iter().map(|r| r.gas_used).collect::<Vec<_>>();
+        assert_eq!(gas, vec![100, 200]);
+    }
+}
@@ -7,7 +7,7 @@ use std::{
     sync::atomic::Ordering,
 };
 
-use arbitrum_client::client::ArbitrumClient;
+use arbitrum_client::{client::ArbitrumClient, traits::DarkpoolClient};
 use async_trait::async_trait;
 use common::types::{tasks::UpdateMerkleProofTaskDescriptor, wallet::Wallet};
 use job_types::{network_manager::NetworkManagerQueue, proof_manager::ProofManagerQueue};
@@ -124,11 +124,14 @@ impl From<StateError> for UpdateMerkleProofTaskError {
 // -------------------
 
 /// Defines the long running flow for updating the Merkle opening for a wallet
-pub struct UpdateMerkleProofTask {
+///
+/// Generic over the darkpool client so that the task steps may be tested
+/// against a mock client
+pub struct UpdateMerkleProofTask<C: DarkpoolClient = ArbitrumClient> {
     /// The wallet to update
     pub wallet: Wallet,
     /// The arbitrum client to use for submitting transactions
-    pub arbitrum_client: ArbitrumClient,
+    pub arbitrum_client: C,
     /// A copy of the relayer-global state
     pub global_state: State,
     /// The work queue to add proof management jobs to
@@ -197,7 +200,7 @@ impl Task for UpdateMerkleProofTask {
 // | Task Implementation |
 // -----------------------
 
-impl UpdateMerkleProofTask {
+impl<C: DarkpoolClient> UpdateMerkleProofTask<C> {
     // --------------
     // | Task Steps |
     // --------------
@@ -230,3 +233,68 @@ impl UpdateMerkleProofTask {
         .map_err(|e| UpdateMerkleProofTaskError::UpdatingValidityProofs(e.to_string()))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use arbitrum_client::mock::MockDarkpoolClient;
+    use common::types::{merkle::MerkleAuthenticationPath, wallet_mocks::mock_empty_wallet};
+    use job_types::{
+        network_manager::new_network_manager_queue, proof_manager::new_proof_manager_queue,
+    };
+    use state::{test_helpers::mock_state, State};
+
+    use super::{UpdateMerkleProofTask, UpdateMerkleProofTaskError, UpdateMerkleProofTaskState};
+
+    /// Build an update Merkle proof task for a new wallet, backed by the given
+    /// mock client
+    async fn setup_task(
+        client: MockDarkpoolClient,
+    ) -> (UpdateMerkleProofTask<MockDarkpoolClient>, State) {
+        let state = mock_state();
+        let wallet = mock_empty_wallet();
+        state.new_wallet(wallet.clone()).unwrap().await.unwrap();
+
+        let (proof_queue, _) = new_proof_manager_queue();
+        let (network_sender, _) = new_network_manager_queue();
+        let task = UpdateMerkleProofTask {
+            wallet,
+            arbitrum_client: client,
+            global_state: state.clone(),
+            proof_queue,
+            network_sender,
+            task_state: UpdateMerkleProofTaskState::Pending,
+        };
+
+        (task, state)
+    }
+
+    /// Tests updating a wallet's opening to the path indexed by the client
+    #[tokio::test]
+    async fn test_find_opening() {
+        let client = MockDarkpoolClient::new();
+        let (mut task, state) = setup_task(client.clone()).await;
+
+        let path = MerkleAuthenticationPath {
+            value: task.wallet.get_wallet_share_commitment(),
+            leaf_index: 42u8.into(),
+            ..Default::default()
+        };
+        client.set_merkle_path(path.clone());
+
+        // The wallet has no orders, so no validity proofs are needed
+        task.find_opening().await.unwrap();
+        task.update_validity_proofs().await.unwrap();
+
+        let wallet = state.get_wallet(&task.wallet.wallet_id).unwrap().unwrap();
+        assert_eq!(wallet.merkle_proof, Some(path));
+    }
+
+    /// Tests that a wallet missing from the Merkle tree fails the task step
+    #[tokio::test]
+    async fn test_find_opening_not_found() {
+        let (mut task, _) = setup_task(MockDarkpoolClient::new()).await;
+
+        let res = task.find_opening().await;
+        assert!(matches!(res, Err(UpdateMerkleProofTaskError::Arbitrum(_))));
+    }
+}
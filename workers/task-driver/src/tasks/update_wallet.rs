@@ -15,8 +15,16 @@ use circuits::zk_circuits::valid_wallet_update::{
     SizedValidWalletUpdateStatement, SizedValidWalletUpdateWitness,
 };
 use common::types::{
-    proof_bundles::ValidWalletUpdateBundle, tasks::UpdateWalletTaskDescriptor,
-    transfer_auth::ExternalTransferWithAuth, wallet::Wallet,
+    network_order::CancellationReason,
+    proof_bundles::ValidWalletUpdateBundle,
+    tasks::{check_wallet_update_nonce, UpdateWalletTaskDescriptor},
+    transfer_auth::ExternalTransferWithAuth,
+    wallet::{OrderIdentifier, Wallet},
+};
+use constants::ORDER_STATE_CHANGE_TOPIC;
+use external_api::{
+    bus_message::{wallet_topic_name, SystemBusMessage},
+    http::wallet::WalletUpdateDelta,
 };
 use job_types::network_manager::NetworkManagerQueue;
 use job_types::proof_manager::{ProofJob, ProofManagerQueue};
@@ -24,11 +32,12 @@ use renegade_metrics::helpers::maybe_record_transfer_metrics;
 use serde::Serialize;
 use state::error::StateError;
 use state::State;
+use system_bus::SystemBus;
 use tracing::instrument;
 
 use crate::driver::StateWrapper;
-use crate::helpers::{enqueue_proof_job, find_merkle_path};
-use crate::traits::{Task, TaskContext, TaskError, TaskState};
+use crate::helpers::{await_proof, enqueue_proof_job, find_merkle_path};
+use crate::traits::{RetryPolicy, Task, TaskContext, TaskError, TaskState};
 
 use crate::helpers::update_wallet_validity_proofs;
 
@@ -38,6 +47,8 @@ const UPDATE_WALLET_TASK_NAME: &str = "update-wallet";
 const ERR_INVALID_BLINDING: &str = "invalid blinding for new wallet";
 /// The wallet does not have a known Merkle proof attached
 const ERR_NO_MERKLE_PROOF: &str = "merkle proof for wallet not found";
+/// The wallet being updated is not in the global state
+const ERR_WALLET_NOT_FOUND: &str = "wallet not found in global state";
 
 // --------------
 // | Task State |
@@ -68,6 +79,10 @@ impl TaskState for UpdateWalletTaskState {
         Self::SubmittingTx
     }
 
+    fn pre_commit_retry_policy() -> RetryPolicy {
+        RetryPolicy::Restart
+    }
+
     fn completed(&self) -> bool {
         matches!(self, Self::Completed)
     }
@@ -116,6 +131,9 @@ pub enum UpdateWalletTaskError {
     State(String),
     /// An error while updating validity proofs for a wallet
     UpdatingValidityProofs(String),
+    /// The update's nonce is not the next nonce of the wallet, i.e. the update
+    /// is stale or replayed
+    StaleNonce(String),
     /// Wallet is already locked, cannot update
     WalletLocked,
 }
@@ -161,6 +179,8 @@ pub struct UpdateWalletTask {
     /// A signature of the `VALID WALLET UPDATE` statement by the wallet's root
     /// key, the contract uses this to authorize the update
     pub wallet_update_signature: Vec<u8>,
+    /// The orders cancelled by the update, and the reason each was cancelled
+    pub cancelled_orders: Vec<(OrderIdentifier, CancellationReason)>,
     /// A proof of `VALID WALLET UPDATE` created in the first step
     pub proof_bundle: Option<ValidWalletUpdateBundle>,
     /// The arbitrum client to use for submitting transactions
@@ -171,6 +191,8 @@ pub struct UpdateWalletTask {
     pub global_state: State,
     /// The work queue to add proof management jobs to
     pub proof_manager_work_queue: ProofManagerQueue,
+    /// A handle on the system bus
+    pub bus: SystemBus<SystemBusMessage>,
     /// The state of the task
     pub task_state: UpdateWalletTaskState,
 }
@@ -185,16 +207,27 @@ impl Task for UpdateWalletTask {
         // Safety check, the new wallet's secret shares must recover the new wallet
         Self::check_wallet_shares(&descriptor.new_wallet)?;
 
+        // The update must carry the next nonce of the wallet as it is stored now
+        let wallet_id = descriptor.new_wallet.wallet_id;
+        let current_wallet = ctx
+            .state
+            .get_wallet(&wallet_id)?
+            .ok_or_else(|| UpdateWalletTaskError::Missing(ERR_WALLET_NOT_FOUND.to_string()))?;
+        check_wallet_update_nonce(&current_wallet, &descriptor.new_wallet)
+            .map_err(UpdateWalletTaskError::StaleNonce)?;
+
         Ok(Self {
             transfer: descriptor.transfer,
             old_wallet: descriptor.old_wallet,
             new_wallet: descriptor.new_wallet,
             wallet_update_signature: descriptor.wallet_update_signature,
+            cancelled_orders: descriptor.cancelled_orders,
             proof_bundle: None,
             arbitrum_client: ctx.arbitrum_client,
             network_sender: ctx.network_queue,
             global_state: ctx.state,
             proof_manager_work_queue: ctx.proof_queue,
+            bus: ctx.bus,
             task_state: UpdateWalletTaskState::Pending,
         })
     }
@@ -270,8 +303,9 @@ impl UpdateWalletTask {
             .map_err(UpdateWalletTaskError::ProofGeneration)?;
 
         // Await the proof
-        let bundle =
-            proof_recv.await.map_err(|e| UpdateWalletTaskError::ProofGeneration(e.to_string()))?;
+        let bundle = await_proof(proof_recv)
+            .await
+            .map_err(|e| UpdateWalletTaskError::ProofGeneration(e.to_string()))?;
 
         self.proof_bundle = Some(bundle.proof.into());
         Ok(())
@@ -302,6 +336,14 @@ impl UpdateWalletTask {
         // After the state is finalized on-chain, re-index the wallet in the global
         // state
         self.global_state.update_wallet(self.new_wallet.clone())?.await?;
+
+        // The update is now final, publish its changes
+        publish_wallet_update(
+            &self.old_wallet,
+            &self.new_wallet,
+            &self.cancelled_orders,
+            &self.bus,
+        );
         Ok(())
     }
 
@@ -396,3 +438,81 @@ impl UpdateWalletTask {
         }
     }
 }
+
+/// Publish the changes made by a finalized wallet update
+///
+/// The update's delta is published on the wallet's topic, and the orders it
+/// cancelled on the order state change topic
+fn publish_wallet_update(
+    old_wallet: &Wallet,
+    new_wallet: &Wallet,
+    cancelled_orders: &[(OrderIdentifier, CancellationReason)],
+    bus: &SystemBus<SystemBusMessage>,
+) {
+    let delta = WalletUpdateDelta::new(old_wallet, new_wallet);
+    bus.publish(
+        wallet_topic_name(&new_wallet.wallet_id),
+        SystemBusMessage::WalletUpdateDelta {
+            wallet_id: new_wallet.wallet_id,
+            delta: Box::new(delta),
+        },
+    );
+
+    for &(order_id, reason) in cancelled_orders.iter() {
+        bus.publish(
+            ORDER_STATE_CHANGE_TOPIC.to_string(),
+            SystemBusMessage::OrderCancelled { order_id, reason },
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use common::types::{
+        network_order::CancellationReason,
+        wallet::OrderIdentifier,
+        wallet_mocks::{mock_empty_wallet, mock_order},
+    };
+    use constants::ORDER_STATE_CHANGE_TOPIC;
+    use external_api::bus_message::{wallet_topic_name, SystemBusMessage};
+    use system_bus::SystemBus;
+
+    use super::publish_wallet_update;
+
+    /// Tests that a cancellation publishes the wallet's delta and the
+    /// cancelled order with its reason
+    #[tokio::test]
+    async fn test_publish_wallet_update() {
+        let mut old_wallet = mock_empty_wallet();
+        let (cancelled_id, kept_id) = (OrderIdentifier::new_v4(), OrderIdentifier::new_v4());
+        old_wallet.add_order(cancelled_id, mock_order()).unwrap();
+        old_wallet.add_order(kept_id, mock_order()).unwrap();
+
+        let mut new_wallet = old_wallet.clone();
+        new_wallet.orders.remove(&cancelled_id);
+
+        let bus = SystemBus::new();
+        let mut wallet_reader = bus.subscribe(wallet_topic_name(&new_wallet.wallet_id));
+        let mut order_reader = bus.subscribe(ORDER_STATE_CHANGE_TOPIC.to_string());
+
+        let cancelled = [(cancelled_id, CancellationReason::UserCancelled)];
+        publish_wallet_update(&old_wallet, &new_wallet, &cancelled, &bus);
+
+        match wallet_reader.next_message().await {
+            SystemBusMessage::WalletUpdateDelta { wallet_id, delta } => {
+                assert_eq!(wallet_id, new_wallet.wallet_id);
+                assert_eq!(delta.removed_orders, vec![cancelled_id]);
+                assert!(delta.updated_orders.is_empty());
+            },
+            msg => panic!("unexpected message: {msg:?}"),
+        }
+
+        match order_reader.next_message().await {
+            SystemBusMessage::OrderCancelled { order_id, reason } => {
+                assert_eq!(order_id, cancelled_id);
+                assert_eq!(reason, CancellationReason::UserCancelled);
+            },
+            msg => panic!("unexpected message: {msg:?}"),
+        }
+    }
+}
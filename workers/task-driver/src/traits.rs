@@ -4,13 +4,18 @@ use std::fmt::{Debug, Display};
 
 use arbitrum_client::client::ArbitrumClient;
 use async_trait::async_trait;
+use common::types::tasks::TaskOutput;
 use external_api::bus_message::SystemBusMessage;
-use job_types::{network_manager::NetworkManagerQueue, proof_manager::ProofManagerQueue};
+use job_types::{
+    network_manager::NetworkManagerQueue,
+    proof_manager::ProofManagerQueue,
+    task_driver::{MatchGasLedger, TaskRegistry},
+};
 use serde::{Deserialize, Serialize};
 use state::State;
 use system_bus::SystemBus;
 
-use crate::driver::StateWrapper;
+use crate::{driver::StateWrapper, settlement_batcher::SettlementBatcher};
 
 // ------------------
 // | Task and State |
@@ -29,8 +34,9 @@ pub trait Task: Send + Sized {
     /// The descriptor of a task, this may be used to construct the task
     ///
     /// The descriptor must be serializable so that it can be placed into the
-    /// task queue and managed by the consensus engine
-    type Descriptor: Debug + Serialize + for<'de> Deserialize<'de>;
+    /// task queue and managed by the consensus engine. The driver retains a
+    /// copy so that the task may be restarted
+    type Descriptor: Clone + Debug + Send + Serialize + for<'de> Deserialize<'de>;
     /// The state type of the task, used for task introspection
     ///
     /// The state must be orderable so that a commit point can be defined and
@@ -56,6 +62,10 @@ pub trait Task: Send + Sized {
     async fn cleanup(&mut self) -> Result<(), Self::Error> {
         Ok(())
     }
+    /// The output of the task, reported to workers awaiting its completion
+    fn output(&self) -> Option<TaskOutput> {
+        None
+    }
 }
 
 /// The state of a task
@@ -73,6 +83,35 @@ pub trait TaskState: Debug + Display + Ord + Send + Serialize + Into<StateWrappe
     fn committed(&self) -> bool {
         *self >= Self::commit_point()
     }
+    /// The retry policy applied to a step failing before the commit point
+    ///
+    /// Tasks that prove before committing may restart, discarding their
+    /// progress and re-running every step from the descriptor. A restart does
+    /// not refresh the descriptor itself; proofs and wallets it carries, such
+    /// as a match's validity proofs, are reused as is
+    fn pre_commit_retry_policy() -> RetryPolicy {
+        RetryPolicy::Resume
+    }
+    /// The retry policy applied to a failure of the step run from this state
+    ///
+    /// A step failing at or past the commit point always resumes from the
+    /// committed step, restarting the task may resubmit its transaction
+    fn retry_policy(&self) -> RetryPolicy {
+        if self.committed() {
+            RetryPolicy::Resume
+        } else {
+            Self::pre_commit_retry_policy()
+        }
+    }
+}
+
+/// The way in which the driver retries a task after a step fails
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RetryPolicy {
+    /// Retry the failed step from the task's current state
+    Resume,
+    /// Discard the task's progress and reconstruct it from its descriptor
+    Restart,
 }
 
 /// The error type of a task
@@ -103,4 +142,13 @@ pub struct TaskContext {
     pub proof_queue: ProofManagerQueue,
     /// A handle on the system bus
     pub bus: SystemBus<SystemBusMessage>,
+    /// The registry of running tasks
+    pub task_registry: TaskRegistry,
+    /// The number of block confirmations to await on a match settlement
+    pub match_confirmation_depth: u64,
+    /// The ledger in which to record the gas used by match settlements
+    pub match_gas_ledger: MatchGasLedger,
+    /// The batcher through which to submit match settlements, matches are
+    /// submitted individually if batching is disabled
+    pub settlement_batcher: Option<SettlementBatcher>,
 }
@@ -6,8 +6,9 @@ use arbitrum_client::client::ArbitrumClient;
 use common::{default_wrapper::DefaultOption, worker::Worker};
 use external_api::bus_message::SystemBusMessage;
 use job_types::{
-    network_manager::NetworkManagerQueue, proof_manager::ProofManagerQueue,
-    task_driver::TaskDriverReceiver,
+    network_manager::NetworkManagerQueue,
+    proof_manager::ProofManagerQueue,
+    task_driver::{MatchGasLedger, TaskDriverReceiver, TaskRegistry},
 };
 use state::State;
 use system_bus::SystemBus;
@@ -37,10 +38,31 @@ pub struct TaskDriverConfig {
     pub system_bus: SystemBus<SystemBusMessage>,
     /// A handle on the global state
     pub state: State,
+    /// The registry of running tasks, shared with workers that inspect the
+    /// driver
+    pub task_registry: TaskRegistry,
+    /// The number of block confirmations to await on a match settlement
+    /// before updating local state
+    pub match_confirmation_depth: u64,
+    /// The ledger in which to record the gas used by match settlements
+    pub match_gas_ledger: MatchGasLedger,
+    /// The window in milliseconds within which ready match settlements are
+    /// batched together, settlements are submitted individually if unset
+    pub match_batch_window_ms: Option<u64>,
+    /// The maximum number of wallet lookup tasks that may run concurrently,
+    /// further lookups wait for a running lookup to finish
+    pub max_concurrent_wallet_lookups: usize,
+    /// The interval in milliseconds after which an unchanged wallet is
+    /// reblinded, idle wallets are not reblinded if unset
+    pub reblind_idle_interval_ms: Option<u64>,
+    /// The time in milliseconds after it is received that a locally managed
+    /// order is cancelled, orders do not expire if unset
+    pub order_ttl_ms: Option<u64>,
 }
 
 impl TaskDriverConfig {
     /// Create a new config with default values
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         task_queue: TaskDriverReceiver,
         arbitrum_client: ArbitrumClient,
@@ -48,15 +70,30 @@ impl TaskDriverConfig {
         proof_queue: ProofManagerQueue,
         system_bus: SystemBus<SystemBusMessage>,
         state: State,
+        task_registry: TaskRegistry,
+        match_confirmation_depth: u64,
+        match_gas_ledger: MatchGasLedger,
+        match_batch_window_ms: Option<u64>,
+        max_concurrent_wallet_lookups: usize,
+        reblind_idle_interval_ms: Option<u64>,
+        order_ttl_ms: Option<u64>,
+        max_task_restarts: usize,
     ) -> Self {
         Self {
-            runtime_config: Default::default(),
+            runtime_config: RuntimeArgs { max_restarts: max_task_restarts, ..Default::default() },
             task_queue,
             arbitrum_client,
             network_queue,
             proof_queue,
             system_bus,
             state,
+            task_registry,
+            match_confirmation_depth,
+            match_gas_ledger,
+            match_batch_window_ms,
+            max_concurrent_wallet_lookups,
+            reblind_idle_interval_ms,
+            order_ttl_ms,
         }
     }
 }